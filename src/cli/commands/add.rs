@@ -4,44 +4,78 @@
 
 use std::path::Path;
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 
-use crate::cli::output::{print_detail, print_success};
-use crate::core::add::{add_package, AddOptions};
+use crate::cli::output::{print_detail, print_success, print_warning};
+use crate::core::add::AddOptions;
+use crate::core::project::ZigrootProject;
 
 /// Execute the add command
 pub async fn execute(
     path: &Path,
     package: &str,
     git: Option<String>,
+    git_ref: Option<String>,
     registry: Option<String>,
+    no_deps: bool,
+    offline: bool,
 ) -> Result<()> {
-    // Check if manifest exists
-    let manifest_path = path.join("zigroot.toml");
-    if !manifest_path.exists() {
-        anyhow::bail!(
-            "No zigroot.toml found in {}. Run 'zigroot init' first.",
-            path.display()
-        );
-    }
+    let mut project = ZigrootProject::open(path)?;
+    let options = AddOptions {
+        git,
+        git_ref,
+        registry,
+        no_deps,
+        offline,
+    };
 
-    let options = AddOptions { git, registry };
+    let result = project.add_package(package, &options).await?;
 
-    let result = add_package(path, package, &options)
-        .await
-        .with_context(|| format!("Failed to add package '{package}'"))?;
+    for warning in &result.warnings {
+        print_warning(warning);
+    }
 
     // Print success message
     print_success(&format!(
         "Added {} v{}",
         result.package_name, result.version
     ));
+    if let Some(requirement) = result
+        .requirement
+        .as_ref()
+        .filter(|requirement| **requirement != result.version)
+    {
+        print_detail(&format!(
+            "Recorded version = \"{requirement}\" in zigroot.toml"
+        ));
+    }
 
-    if !result.dependencies.is_empty() {
-        print_detail("Dependencies:");
+    if let Some(ref sha) = result.git_sha {
+        print_detail(&format!("Pinned to commit {sha}"));
+    }
+
+    if result.dependencies_added {
+        if !result.dependencies.is_empty() {
+            print_detail("Dependencies:");
+            for dep in &result.dependencies {
+                print_detail(&format!(
+                    "  + {} v{} (required by {})",
+                    dep.name, dep.version, dep.required_by
+                ));
+            }
+        }
+    } else if !result.dependencies.is_empty() {
+        print_warning(&format!(
+            "{} depends on packages missing from zigroot.toml; the build will fail until they are added:",
+            result.package_name
+        ));
         for dep in &result.dependencies {
-            print_detail(&format!("  + {dep}"));
+            print_detail(&format!(
+                "  {} {} (required by {})",
+                dep.name, dep.requirement, dep.required_by
+            ));
         }
+        print_detail("Add them with 'zigroot add <package>', or rerun without --no-deps.");
     }
 
     if result.lock_updated {
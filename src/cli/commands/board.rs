@@ -7,8 +7,12 @@
 use anyhow::Result;
 use std::path::Path;
 
+use crate::cli::output::{is_json, report_warning};
 use crate::core::board::BoardDefinition;
-use crate::core::manifest::Manifest;
+use crate::core::build_env::compiler_flags;
+use crate::core::manifest::{BoardConfig, Manifest};
+use crate::infra::filesystem::write_file_atomic;
+use crate::registry::cache::{CachePolicy, CacheStatus};
 use crate::registry::client::RegistryClient;
 
 /// Execute the board list command
@@ -47,6 +51,10 @@ pub async fn execute_list() -> Result<()> {
             println!("    Keywords: {}", board.keywords.join(", "));
         }
 
+        if !board.variants.is_empty() {
+            println!("    Variants: {}", board.variants.join(", "));
+        }
+
         println!();
     }
 
@@ -102,7 +110,7 @@ pub async fn execute_set(project_dir: &Path, board_name: &str) -> Result<()> {
         .to_toml()
         .map_err(|e| anyhow::anyhow!("Failed to serialize manifest: {}", e))?;
 
-    std::fs::write(&manifest_path, updated_content)?;
+    write_file_atomic(&manifest_path, updated_content.as_bytes())?;
 
     println!("✓ Board set to '{}'", board_name);
     println!("  Target: {}", board_def.board.target);
@@ -122,30 +130,58 @@ pub async fn execute_set(project_dir: &Path, board_name: &str) -> Result<()> {
 
 /// Execute the board info command
 ///
-/// Displays detailed information about a specific board.
+/// Displays detailed information about a specific board. The definition is
+/// shown from the cache when available, see [`CachePolicy`].
 /// **Validates: Requirement 9.4**
-pub async fn execute_info(board_name: &str) -> Result<()> {
+pub async fn execute_info(board_name: &str, policy: CachePolicy) -> Result<()> {
     let client = RegistryClient::new();
 
-    tracing::info!("Fetching board '{}' from registry...", board_name);
+    tracing::info!("Looking up board '{}'...", board_name);
 
-    // Fetch board definition from registry
-    let board_toml = client
-        .fetch_board(board_name)
+    let mut lookup = client
+        .lookup_board(board_name, policy)
         .await
         .map_err(|e| anyhow::anyhow!("Board '{}' not found: {}", board_name, e))?;
 
     // Parse the board definition
-    let board_def: BoardDefinition = board_toml
+    let board_def: BoardDefinition = lookup
+        .data
+        .clone()
         .try_into()
         .map_err(|e| anyhow::anyhow!("Failed to parse board definition: {}", e))?;
 
+    print_board_info(&board_def, &lookup.status)?;
+
+    if let Some(e) = lookup.finish_refresh().await {
+        report_warning(&format!(
+            "Could not refresh board '{board_name}' from the registry, showing cached data: {e}"
+        ));
+    }
+    Ok(())
+}
+
+/// Print a board definition and the age of the data
+fn print_board_info(board_def: &BoardDefinition, status: &CacheStatus) -> Result<()> {
+    let flags = board_compiler_flags(board_def);
+
+    if is_json() {
+        let mut info = board_info_json(board_def, &flags);
+        info["cache"] = serde_json::json!({
+            "age_secs": status.age_secs,
+            "stale": status.stale,
+        });
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+
     // Display board information
     println!("Board: {}", board_def.board.name);
     println!();
     println!("  Description: {}", board_def.board.description);
     println!("  Target: {}", board_def.board.target);
+    println!("  Libc: {}", board_def.libc(&BoardConfig::default()));
     println!("  CPU: {}", board_def.board.cpu);
+    println!("  Compiler: zig cc {}", flags.join(" "));
 
     // Features
     if !board_def.board.features.is_empty() {
@@ -199,7 +235,9 @@ pub async fn execute_info(board_name: &str) -> Result<()> {
     if !board_def.options.is_empty() {
         println!();
         println!("Options:");
-        for (name, opt) in &board_def.options {
+        let mut options: Vec<_> = board_def.options.iter().collect();
+        options.sort_by_key(|(name, _)| *name);
+        for (name, opt) in options {
             println!("  {} ({}) - {}", name, opt.option_type, opt.description);
             println!("    Default: {}", opt.default);
             if !opt.choices.is_empty() {
@@ -208,9 +246,67 @@ pub async fn execute_info(board_name: &str) -> Result<()> {
         }
     }
 
+    // Variants
+    if !board_def.variants.is_empty() {
+        println!();
+        println!("Variants:");
+        for variant in &board_def.variants {
+            if variant.description.is_empty() {
+                println!("  {}:{}", board_def.board.name, variant.name);
+            } else {
+                println!(
+                    "  {}:{} - {}",
+                    board_def.board.name, variant.name, variant.description
+                );
+            }
+            let mut options: Vec<_> = variant.options.iter().collect();
+            options.sort_by_key(|(name, _)| *name);
+            for (name, value) in options {
+                println!("    {name} = {value}");
+            }
+        }
+    }
+
+    println!();
+    println!("({})", status.footer());
     Ok(())
 }
 
+/// Compiler flags zigroot uses for the board
+fn board_compiler_flags(board_def: &BoardDefinition) -> Vec<String> {
+    compiler_flags(
+        &board_def.board.target,
+        &board_def.board.cpu,
+        &board_def.board.features,
+    )
+}
+
+/// JSON representation of `board info`
+///
+/// The whole board definition as parsed, with the `[board]` fields and the
+/// compiler flags repeated at the top level.
+fn board_info_json(board_def: &BoardDefinition, flags: &[String]) -> serde_json::Value {
+    let board = &board_def.board;
+    let mut json = serde_json::to_value(board_def).unwrap_or_default();
+    for (key, value) in [
+        ("name", serde_json::json!(board.name)),
+        ("description", serde_json::json!(board.description)),
+        ("target", serde_json::json!(board.target)),
+        (
+            "libc",
+            serde_json::json!(board_def.libc(&BoardConfig::default())),
+        ),
+        ("cpu", serde_json::json!(board.cpu)),
+        ("features", serde_json::json!(board.features)),
+        ("kernel", serde_json::json!(board.kernel)),
+        ("zigroot_version", serde_json::json!(board.zigroot_version)),
+        ("compiler_flags", serde_json::json!(flags)),
+    ] {
+        json[key] = value;
+    }
+    json
+}
+
 /// Validate that the board is compatible with existing packages
 fn validate_board_compatibility(manifest: &Manifest, board_def: &BoardDefinition) -> Result<()> {
     // Check if any packages have architecture restrictions
@@ -244,24 +340,34 @@ fn validate_board_compatibility(manifest: &Manifest, board_def: &BoardDefinition
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::manifest::{BoardConfig, BuildConfig, ProjectConfig};
-    use std::collections::HashMap;
+    use crate::core::manifest::{BoardConfig, BuildConfig, ProjectConfig, MANIFEST_VERSION};
+    use std::collections::{BTreeMap, HashMap};
 
     #[test]
     fn test_validate_board_compatibility_empty_manifest() {
         let manifest = Manifest {
+            manifest_version: MANIFEST_VERSION,
             project: ProjectConfig {
                 name: "test-project".to_string(),
                 version: "1.0.0".to_string(),
                 description: None,
+                strict_env: false,
             },
             board: BoardConfig {
                 name: None,
                 options: HashMap::new(),
+                libc: None,
             },
             build: BuildConfig::default(),
             packages: HashMap::new(),
             external: HashMap::new(),
+            fit: None,
+            partitions: Vec::new(),
+            disk_image: None,
+            flash: None,
+            permissions: BTreeMap::new(),
+            license: None,
+            hooks: None,
         };
         let board_def = BoardDefinition {
             board: crate::core::board::BoardMetadata {
@@ -272,20 +378,63 @@ mod tests {
                 features: vec![],
                 kernel: None,
                 zigroot_version: None,
+                libc: None,
             },
             defaults: crate::core::board::BoardDefaults {
                 image_format: "ext4".to_string(),
                 rootfs_size: "256M".to_string(),
                 hostname: "test".to_string(),
+                partitions: Vec::new(),
+                disk_image: None,
             },
             requires: vec![],
             flash: vec![],
             options: std::collections::HashMap::new(),
+            package_options: HashMap::new(),
+            fit: None,
+            qemu: None,
+            variants: Vec::new(),
         };
 
         let result = validate_board_compatibility(&manifest, &board_def);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_board_info_json_includes_compiler_flags() {
+        let board_def = BoardDefinition::from_toml(
+            r#"
+[board]
+name = "test-board"
+description = "Test board"
+target = "arm-linux-musleabihf"
+cpu = "cortex-a7"
+features = ["neon"]
+
+[defaults]
+image_format = "ext4"
+rootfs_size = "256M"
+hostname = "test"
+"#,
+        )
+        .unwrap();
+
+        let flags = board_compiler_flags(&board_def);
+        let json = board_info_json(&board_def, &flags);
+        assert_eq!(
+            json["compiler_flags"],
+            serde_json::json!([
+                "-target",
+                "arm-linux-musleabihf",
+                "-mcpu=cortex_a7+neon",
+                "-mfloat-abi=hard"
+            ])
+        );
+        assert_eq!(json["target"], "arm-linux-musleabihf");
+        assert_eq!(json["cpu"], "cortex-a7");
+        assert_eq!(json["defaults"]["rootfs_size"], "256M");
+        assert_eq!(json["board"]["features"], serde_json::json!(["neon"]));
+    }
 }
 
 /// Execute the board new command
@@ -1,346 +1,548 @@
 //! Build command implementation
 //!
 //! Implements `zigroot build` to compile packages and create rootfs images.
+//! The build itself runs in [`ZigrootProject::build`]; this module shows its
+//! progress and summary, and adds `--watch` and `--analyze-size`.
 //!
 //! **Validates: Requirements 4.1-4.13, 5.1-5.7, 6.1-6.10, 27.1-27.9**
 
 use anyhow::{bail, Context, Result};
 use std::fs;
-use std::path::Path;
-
-use crate::core::compress::{self, CompressionConfig};
-use crate::core::lock::{LockFile, LockedPackageBuilder};
-use crate::core::manifest::Manifest;
-use crate::infra::sandbox::{resolve_sandbox_config, Sandbox, SandboxError};
-
-/// Build options
-pub struct BuildOptions {
-    /// Build only specified package
-    pub package: Option<String>,
-    /// Number of parallel jobs
-    pub jobs: Option<usize>,
-    /// Fail if packages differ from lock file
-    pub locked: bool,
-    /// Enable binary compression
-    pub compress: bool,
-    /// Disable binary compression
-    pub no_compress: bool,
-    /// Build only kernel and modules
-    pub kernel_only: bool,
-    /// Enable container isolation (--sandbox)
-    pub sandbox: bool,
-    /// Disable container isolation (--no-sandbox)
-    pub no_sandbox: bool,
-}
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::cli::output::{
+    defer_warning, format_duration, is_json, is_verbose, print_error, print_plain, report_warning,
+    status, take_warnings, OverallProgress,
+};
+use crate::core::builder;
+use crate::core::check;
+use crate::core::hooks::{self, HookContext, HookError, HookEvent, HooksConfig};
+use crate::core::output::OutputLayout;
+use crate::core::project::{
+    BuildOptions, BuildResult, ProgressEvent, ProgressSink, ZigrootProject,
+};
+use crate::core::size::{self, SizeReport};
+use crate::core::validate::{Finding, ValidationReport};
+use crate::core::watch::{self, WatchChanges};
 
 /// Execute the build command
-#[allow(clippy::too_many_lines)]
-pub async fn execute(project_dir: &Path, options: BuildOptions) -> Result<()> {
-    let manifest_path = project_dir.join("zigroot.toml");
-
-    // Check manifest exists
-    if !manifest_path.exists() {
-        bail!("No zigroot.toml found. Run 'zigroot init' to create a project.");
+///
+/// With `analyze_size`, the size report of the last build is printed
+/// instead; with `watch`, the project is rebuilt whenever its sources change.
+/// The project's `pre_build` hooks run first, then `post_build` or
+/// `on_failure` depending on the outcome.
+pub async fn execute(
+    project_dir: &Path,
+    options: BuildOptions,
+    analyze_size: bool,
+    watch: bool,
+) -> Result<()> {
+    if analyze_size {
+        return execute_analyze_size(project_dir, &options);
     }
-
-    // Load and validate manifest
-    let manifest_content = fs::read_to_string(&manifest_path)
-        .with_context(|| format!("Failed to read manifest at {}", manifest_path.display()))?;
-
-    let manifest =
-        Manifest::from_toml(&manifest_content).with_context(|| "Failed to parse zigroot.toml")?;
-
-    tracing::info!("Building project: {}", manifest.project.name);
-
-    // Resolve sandbox configuration
-    // Priority: CLI flags > manifest settings > default (disabled)
-    let cli_sandbox = if options.sandbox { Some(true) } else { None };
-    let sandbox_config = resolve_sandbox_config(
-        cli_sandbox,
-        options.no_sandbox,
-        manifest.build.sandbox,
-        false, // Package network will be set per-package
-    );
-
-    // Initialize sandbox if enabled
-    let mut sandbox = Sandbox::new(sandbox_config);
-    if let Err(e) = sandbox.init() {
-        match e {
-            SandboxError::RuntimeNotAvailable => {
-                bail!("Container runtime not available. Install Docker or Podman to use --sandbox");
+    if watch {
+        return execute_watch(project_dir, &options).await;
+    }
+    let project = ZigrootProject::open(project_dir)?;
+
+    // Ctrl+C stops the build at the current step, so that the next build
+    // resumes there; a second Ctrl+C exits at once
+    tokio::spawn(async {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            builder::request_interrupt();
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit(130);
             }
-            _ => bail!("Sandbox initialization failed: {}", e),
+        }
+    });
+    let hooks = project.manifest().hooks.clone().unwrap_or_default();
+    let mut context = HookContext::new(project.manifest());
+    let started = Instant::now();
+    let result = match run_project_hooks(project_dir, &hooks, HookEvent::PreBuild, &context) {
+        Ok(()) => {
+            let mut progress = TerminalProgress::default();
+            let result = project.build(&options, &mut progress).await;
+            progress.finish();
+            result
+        }
+        Err(e) => Err(e.into()),
+    };
+    context.duration = Some(started.elapsed());
+
+    match result {
+        Ok(result) => {
+            context.success = true;
+            context.image_path = result.image.clone().or_else(|| result.disk_image.clone());
+            print_build_summary(&result);
+            // The build stands even if a post hook fails
+            if let Err(e) = run_project_hooks(project_dir, &hooks, HookEvent::PostBuild, &context) {
+                print_error(&e.to_string());
+            }
+            Ok(())
+        }
+        Err(e) => {
+            if let Err(hook_error) =
+                run_project_hooks(project_dir, &hooks, HookEvent::OnFailure, &context)
+            {
+                print_error(&hook_error.to_string());
+            }
+            Err(e)
         }
     }
+}
 
-    if sandbox.is_enabled() {
-        let runtime = sandbox.runtime().map(|r| r.command()).unwrap_or("unknown");
-        tracing::info!("Build isolation enabled using {}", runtime);
-    }
-
-    // Create build directories
-    let build_dir = project_dir.join("build");
-    let output_dir = project_dir.join("output");
-    let stamps_dir = build_dir.join("stamps");
-    let logs_dir = build_dir.join("logs");
-
-    fs::create_dir_all(&build_dir).with_context(|| "Failed to create build directory")?;
-    fs::create_dir_all(&output_dir).with_context(|| "Failed to create output directory")?;
-    fs::create_dir_all(&stamps_dir).with_context(|| "Failed to create stamps directory")?;
-    fs::create_dir_all(&logs_dir).with_context(|| "Failed to create logs directory")?;
+/// Run the project hooks of an event, printing their output with a
+/// `[hook]` prefix
+pub(crate) fn run_project_hooks(
+    project_dir: &Path,
+    hooks: &HooksConfig,
+    event: HookEvent,
+    context: &HookContext,
+) -> Result<(), HookError> {
+    hooks::run_hooks(project_dir, hooks, event, context, &mut |line| {
+        print_plain(&format!("[hook] {line}"));
+    })
+}
 
-    // Load or create lock file
-    let lock_path = project_dir.join("zigroot.lock");
-    let mut lock_file = if lock_path.exists() {
-        LockFile::load(&lock_path).with_context(|| "Failed to load lock file")?
-    } else {
-        LockFile::new(env!("CARGO_PKG_VERSION"), "0.13.0")
-    };
+/// Build progress on the terminal
+#[derive(Default)]
+struct TerminalProgress {
+    overall: Option<OverallProgress>,
+}
 
-    // Handle --locked mode
-    if options.locked {
-        verify_locked_packages(project_dir, &manifest, &lock_file)?;
+impl TerminalProgress {
+    fn finish(&self) {
+        if let Some(ref overall) = self.overall {
+            overall.finish();
+        }
     }
 
-    // Determine which packages to build
-    let packages_to_build: Vec<String> = if options.kernel_only {
-        // Build only kernel packages
-        tracing::info!("Building kernel only (--kernel-only)");
-        manifest
-            .packages
-            .keys()
-            .filter(|name| is_kernel_package(project_dir, name))
-            .cloned()
-            .collect()
-    } else if let Some(ref pkg_name) = options.package {
-        // Build only specified package
-        if !manifest.packages.contains_key(pkg_name) {
-            bail!("Package '{pkg_name}' not found in manifest");
+    /// Print a line above the progress bar
+    fn println(&self, line: &str) {
+        match self.overall {
+            Some(ref overall) => overall.println(line),
+            None => print_plain(line),
         }
-        vec![pkg_name.clone()]
-    } else {
-        // Build all packages
-        manifest.packages.keys().cloned().collect()
-    };
-
-    // Build each package
-    let jobs = options.jobs.unwrap_or_else(num_cpus::get);
-    tracing::info!(
-        "Building {} packages with {} jobs",
-        packages_to_build.len(),
-        jobs
-    );
-
-    for pkg_name in &packages_to_build {
-        build_package(
-            project_dir,
-            pkg_name,
-            &manifest,
-            &mut lock_file,
-            &stamps_dir,
-            options.package.is_some(),
-        )?;
     }
-
-    // Determine target architecture from board (default to x86_64 if not set)
-    let target_arch = manifest
-        .board
-        .name
-        .as_ref()
-        .map(|_| "x86_64-linux-musl") // Would load from board definition
-        .unwrap_or("x86_64-linux-musl");
-
-    // Handle compression
-    handle_compression(project_dir, &options, &manifest, target_arch);
-
-    // Create rootfs image
-    let image_path = create_rootfs_image(&output_dir, &manifest)?;
-
-    // Save lock file
-    lock_file
-        .save(&lock_path)
-        .with_context(|| "Failed to save lock file")?;
-
-    // Display build summary
-    let image_size = fs::metadata(&image_path).map(|m| m.len()).unwrap_or(0);
-
-    println!("✓ Build complete!");
-    println!("  Packages built: {}", packages_to_build.len());
-    println!("  Image: {} ({image_size} bytes)", image_path.display());
-
-    Ok(())
 }
 
-/// Verify all packages match lock file in --locked mode
-fn verify_locked_packages(
-    project_dir: &Path,
-    manifest: &Manifest,
-    lock_file: &LockFile,
-) -> Result<()> {
-    for (name, pkg_ref) in &manifest.packages {
-        let version = pkg_ref.version.as_deref().unwrap_or("latest");
-
-        // For local packages, use "local" as checksum
-        let local_pkg_path = project_dir.join("packages").join(name);
-        let checksum = if local_pkg_path.exists() {
-            "local"
-        } else {
-            // Would need to fetch from registry to get actual checksum
-            "unknown"
-        };
+impl ProgressSink for TerminalProgress {
+    fn event(&mut self, event: ProgressEvent) {
+        match event {
+            ProgressEvent::Planned {
+                estimated_secs,
+                jobs,
+                ..
+            } => {
+                if is_verbose() {
+                    print_plain(&format!("Using {jobs} parallel jobs"));
+                }
+                self.overall = Some(OverallProgress::new(estimated_secs));
+            }
+            ProgressEvent::PackageStarted { name } => {
+                if let Some(ref overall) = self.overall {
+                    overall.start_package(&name);
+                }
+            }
+            ProgressEvent::PackageFinished { estimated_secs, .. } => {
+                if let Some(ref mut overall) = self.overall {
+                    overall.finish_package(estimated_secs);
+                }
+            }
+            ProgressEvent::PackageFailed {
+                name,
+                error,
+                estimated_secs,
+            } => {
+                self.println(&format!("{} {name} failed: {error}", status::ERROR));
+                if let Some(ref mut overall) = self.overall {
+                    overall.finish_package(estimated_secs);
+                }
+            }
+            ProgressEvent::PackageSkipped {
+                name,
+                dependency,
+                estimated_secs,
+            } => {
+                self.println(&format!(
+                    "{} {name} skipped due to failed dependency '{dependency}'",
+                    status::WARNING
+                ));
+                if let Some(ref mut overall) = self.overall {
+                    overall.finish_package(estimated_secs);
+                }
+            }
+            ProgressEvent::Assembling => self.finish(),
+            ProgressEvent::Warning(message) => report_warning(&message),
+            // Full output in verbose mode, otherwise only the latest line
+            // next to the progress bar; everything is in the package's log
+            ProgressEvent::BuildOutput { package, line } => {
+                if is_verbose() {
+                    self.println(&format!("[{package}] {line}"));
+                } else if let Some(ref overall) = self.overall {
+                    overall.show_output(&package, &line);
+                }
+            }
+            ProgressEvent::HookOutput { hook, line } => {
+                self.println(&format!("[hook] {hook}: {line}"));
+            }
+        }
+    }
+}
 
-        lock_file
-            .verify_package(name, version, checksum)
-            .with_context(|| format!("Package '{name}' differs from lock file"))?;
+/// Print the size report of the last build (--analyze-size)
+fn execute_analyze_size(project_dir: &Path, options: &BuildOptions) -> Result<()> {
+    let project = ZigrootProject::open(project_dir)?;
+    let mut manifest = project.manifest().clone();
+    if let Some(ref dir) = options.output_dir {
+        manifest.build.output_dir = Some(dir.clone());
     }
+    let layout = OutputLayout::new(project_dir, &manifest);
+    let report = SizeReport::load(&layout.image_dir())
+        .or_else(|_| SizeReport::load(layout.root()))
+        .with_context(|| "No size report found. Run 'zigroot build' first.")?;
+    print_size_report(&report);
     Ok(())
 }
 
-/// Build a single package
-fn build_package(
-    project_dir: &Path,
-    pkg_name: &str,
-    manifest: &Manifest,
-    lock_file: &mut LockFile,
-    stamps_dir: &Path,
-    force_rebuild: bool,
-) -> Result<()> {
-    let stamp_file = stamps_dir.join(format!("{pkg_name}.stamp"));
-
-    // Check if package needs rebuilding (incremental build)
-    let needs_rebuild = !stamp_file.exists() || force_rebuild;
+/// Event received while watching the project
+enum WatchEvent {
+    /// A file changed
+    Changed(PathBuf),
+    /// Ctrl+C was pressed
+    Interrupted,
+}
 
-    if !needs_rebuild {
-        tracing::info!("Package {pkg_name} is up to date, skipping");
-        return Ok(());
+/// Build, then rebuild affected packages whenever sources change (--watch)
+///
+/// Build failures are reported and watching continues; only Ctrl+C stops.
+async fn execute_watch(project_dir: &Path, options: &BuildOptions) -> Result<()> {
+    use notify::{RecursiveMode, Watcher};
+
+    // Event paths are absolute and canonical
+    let project_dir = project_dir
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", project_dir.display()))?;
+    if !project_dir.join("zigroot.toml").exists() {
+        bail!("No zigroot.toml found. Run 'zigroot init' to create a project.");
     }
 
-    tracing::info!("Building package: {pkg_name}");
-
-    // Check for local package
-    let local_pkg_path = project_dir.join("packages").join(pkg_name);
-    let pkg_ref = manifest.packages.get(pkg_name).unwrap();
-    let version = pkg_ref.version.as_deref().unwrap_or("1.0.0");
-
-    if local_pkg_path.exists() {
-        tracing::info!("Using local package: {}", local_pkg_path.display());
-
-        // Add to lock file with local source
-        lock_file.add_package(
-            LockedPackageBuilder::new(pkg_name, version, "local")
-                .source(&format!("path:packages/{pkg_name}"))
-                .build(),
-        );
-    } else {
-        // Registry package - would download and build
-        // For now, just add to lock file
-        lock_file.add_package(LockedPackageBuilder::new(pkg_name, version, "registry").build());
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let interrupt = tx.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            builder::request_interrupt();
+            let _ = interrupt.send(WatchEvent::Interrupted);
+        }
+    });
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        for path in event.map(|e| e.paths).unwrap_or_default() {
+            let _ = tx.send(WatchEvent::Changed(path));
+        }
+    })
+    .with_context(|| "Failed to start file watcher")?;
+    for watched in watch::watch_paths(&project_dir) {
+        let mode = if watched.recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher
+            .watch(&watched.path, mode)
+            .with_context(|| format!("Failed to watch {}", watched.path.display()))?;
     }
 
-    // Create stamp file to mark as built
-    fs::write(&stamp_file, chrono_lite_now())
-        .with_context(|| format!("Failed to create stamp file for {pkg_name}"))?;
+    let mut iteration = 0;
+    print_watch_summary(
+        iteration,
+        &[],
+        &watch_build(&project_dir, options, None).await,
+    );
+    if !is_json() {
+        println!("Watching for changes (Ctrl+C to stop)...");
+    }
 
-    tracing::info!("Built package: {pkg_name}");
+    while let Some(WatchEvent::Changed(first)) = rx.recv().await {
+        // Collect the burst of events of a save or checkout
+        let mut paths = vec![first];
+        loop {
+            match tokio::time::timeout(watch::DEBOUNCE, rx.recv()).await {
+                Ok(Some(WatchEvent::Changed(path))) => paths.push(path),
+                Ok(Some(WatchEvent::Interrupted) | None) => return Ok(()),
+                Err(_) => break,
+            }
+        }
+        let changes = WatchChanges::from_paths(&project_dir, &paths);
+        if changes.is_empty() {
+            continue;
+        }
+        iteration += 1;
+        paths.sort();
+        paths.dedup();
+        let files: Vec<String> = paths
+            .iter()
+            .filter_map(|path| path.strip_prefix(&project_dir).ok())
+            .map(|path| path.display().to_string())
+            .collect();
+        let outcome = watch_build(&project_dir, options, Some(&changes)).await;
+        print_watch_summary(iteration, &files, &outcome);
+    }
     Ok(())
 }
 
-/// Handle compression settings and compress binaries
-fn handle_compression(
+/// Result of one watch iteration
+struct WatchOutcome {
+    /// Build result, or the error that stopped the build
+    result: Result<BuildResult>,
+    /// Time the iteration took
+    duration: Duration,
+}
+
+/// Rebuild the packages affected by `changes`, or everything out of date
+///
+/// A changed manifest is checked first, and the build plan is recalculated
+/// from it.
+async fn watch_build(
     project_dir: &Path,
     options: &BuildOptions,
-    manifest: &Manifest,
-    target_arch: &str,
-) {
-    let config = CompressionConfig {
-        global_enabled: manifest.build.compress,
-        cli_compress: options.compress,
-        cli_no_compress: options.no_compress,
-        target_arch: target_arch.to_string(),
-    };
+    changes: Option<&WatchChanges>,
+) -> WatchOutcome {
+    let start = Instant::now();
+    let result = async {
+        let project = ZigrootProject::open(project_dir)?;
+
+        if changes.is_some_and(|c| c.manifest) {
+            let check = check::check(project_dir, project.manifest())?;
+            if !check.is_valid() {
+                let problems: Vec<&String> = check
+                    .config_errors
+                    .iter()
+                    .chain(&check.missing_dependencies)
+                    .chain(&check.conflicts)
+                    .collect();
+                bail!(
+                    "zigroot.toml failed the check:\n  {}",
+                    problems
+                        .iter()
+                        .map(|p| p.as_str())
+                        .collect::<Vec<_>>()
+                        .join("\n  ")
+                );
+            }
+        }
 
-    if !config.is_enabled() {
-        tracing::info!("Compression disabled");
-        return;
+        // Affected packages lose their stamps so that they are rebuilt
+        if let Some(changes) = changes {
+            let plan = project.resolve()?;
+            let stamps_dir = project_dir.join("build").join("stamps");
+            for name in changes.affected_packages(&plan.graph) {
+                let _ = fs::remove_file(stamps_dir.join(format!("{name}.stamp")));
+            }
+        }
+        let mut progress = TerminalProgress::default();
+        let result = project.build(options, &mut progress).await;
+        progress.finish();
+        result
     }
+    .await;
+    WatchOutcome {
+        result,
+        duration: start.elapsed(),
+    }
+}
 
-    let rootfs_dir = project_dir.join("build").join("rootfs");
-    if !rootfs_dir.exists() {
-        tracing::debug!("No rootfs directory found, skipping compression");
+/// Print the compact summary of a watch iteration
+fn print_watch_summary(iteration: usize, files: &[String], outcome: &WatchOutcome) {
+    let rebuilt: Vec<&str> = outcome
+        .result
+        .as_ref()
+        .map(|result| {
+            result
+                .report
+                .packages
+                .iter()
+                .filter(|p| p.rebuilt)
+                .map(|p| p.name.as_str())
+                .collect()
+        })
+        .unwrap_or_default();
+    let error = outcome.result.as_ref().err().map(|e| format!("{e:#}"));
+
+    if is_json() {
+        let line = serde_json::json!({
+            "iteration": iteration,
+            "status": if error.is_some() { "failed" } else { "success" },
+            "changed": files,
+            "rebuilt": rebuilt,
+            "image": outcome.result.as_ref().ok().and_then(|r| r.report.image.clone()),
+            "duration_secs": outcome.duration.as_secs_f64(),
+            "error": error,
+        });
+        println!("{line}");
         return;
     }
 
-    match compress::compress_rootfs(&rootfs_dir, &config) {
-        Ok(stats) => {
-            if stats.files_compressed > 0 || stats.files_failed > 0 {
-                compress::display_stats(&stats);
-            }
-        }
-        Err(e) => {
-            tracing::warn!("Compression failed: {}", e);
+    let trigger = match files {
+        [] => "initial build".to_string(),
+        [file] => file.clone(),
+        [file, rest @ ..] => format!("{file} and {} more", rest.len()),
+    };
+    let duration = format_duration(outcome.duration);
+    match error {
+        Some(error) => println!("✗ [{iteration}] {trigger}: build failed in {duration}: {error}"),
+        None if rebuilt.is_empty() => {
+            println!("✓ [{iteration}] {trigger}: image regenerated in {duration}");
         }
+        None => println!(
+            "✓ [{iteration}] {trigger}: rebuilt {} in {duration}",
+            rebuilt.join(", ")
+        ),
     }
 }
 
-/// Create the rootfs image
-fn create_rootfs_image(output_dir: &Path, manifest: &Manifest) -> Result<std::path::PathBuf> {
-    let image_format = &manifest.build.image_format;
-    let image_name = match image_format.as_str() {
-        "squashfs" => "rootfs.squashfs",
-        "initramfs" => "rootfs.cpio",
-        _ => "rootfs.img",
-    };
-    let image_path = output_dir.join(image_name);
-
-    tracing::info!("Creating {image_format} image: {}", image_path.display());
+/// Print the summary of a finished build
+fn print_build_summary(result: &BuildResult) {
+    let image_size = result.report.image_size;
+    let compression = &result.compression;
+    if is_json() {
+        let json_result = serde_json::json!({
+            "status": "success",
+            "packages_built": result.packages_built,
+            "build_time": {
+                "actual_secs": result.build_time.as_secs_f64(),
+                "estimated_secs": result.estimated_secs,
+            },
+            "image": result.image.as_ref().map(|path| path.display().to_string()),
+            "image_size": image_size,
+            "initramfs_size": result.initramfs_size,
+            "embedded_in_kernel": result.embedded_in_kernel,
+            "fit_image": result.fit_image.as_ref().map(|path| path.display().to_string()),
+            "disk_image": result.disk_image.as_ref().map(|path| path.display().to_string()),
+            "sizes": result.sizes,
+            "attestation": result.attestation.as_ref().map(|path| path.display().to_string()),
+            "checksum": result.report.checksum,
+            "strip": {
+                "files_stripped": result.strip.files_stripped,
+                "bytes_saved": result.strip.bytes_saved,
+            },
+            "compression": {
+                "files_compressed": compression.files_compressed,
+                "files_skipped": compression.files_skipped,
+                "files_failed": compression.files_failed,
+                "bytes_saved": compression.bytes_saved(),
+            },
+            "validation": result.validation.as_ref().map(|report| serde_json::json!({
+                "errors": report.errors().count(),
+                "warnings": report.warnings().count(),
+                "findings": report.findings,
+            })),
+            "warnings": take_warnings(),
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json_result).unwrap_or_default()
+        );
+        return;
+    }
 
-    // Create a placeholder image file
-    fs::write(
-        &image_path,
-        format!(
-            "# Zigroot {} image\n# Format: {}\n# Hostname: {}\n",
-            manifest.project.name, image_format, manifest.build.hostname
+    println!("✓ Build complete!");
+    println!("  Packages built: {}", result.packages_built);
+    if result.estimated_secs > 0.0 {
+        println!(
+            "  Build time: {} (estimated {})",
+            format_duration(result.build_time),
+            format_duration(Duration::from_secs_f64(result.estimated_secs))
+        );
+    }
+    match (&result.image, result.initramfs_size) {
+        (Some(path), Some(archive)) if archive != image_size => println!(
+            "  Image: {} ({image_size} bytes, {archive} bytes uncompressed)",
+            path.display()
         ),
-    )
-    .with_context(|| "Failed to create rootfs image")?;
-
-    Ok(image_path)
-}
-
-/// Simple timestamp generation
-fn chrono_lite_now() -> String {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let duration = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
-    format!("{}", duration.as_secs())
+        (Some(path), _) => println!("  Image: {} ({image_size} bytes)", path.display()),
+        (None, _) => {}
+    }
+    if result.embedded_in_kernel {
+        println!("  Initramfs: embedded into the kernel by 'zigroot kernel build'");
+    }
+    if let Some(ref path) = result.fit_image {
+        println!("  FIT image: {}", path.display());
+    }
+    if let Some(ref path) = result.disk_image {
+        println!("  Disk image: {}", path.display());
+    }
+    if let Some(ref checksum) = result.report.checksum {
+        println!("  SHA256: {}", checksum.sha256);
+        if let Some(ref signature) = checksum.signature {
+            println!("  Signature: {signature}");
+        }
+    }
+    if let Some(ref path) = result.attestation {
+        println!("  Attestation: {}", path.display());
+    }
+    if result.strip.files_stripped > 0 {
+        println!(
+            "  Stripped: {} binaries, {} saved",
+            result.strip.files_stripped,
+            size::format_bytes(result.strip.bytes_saved)
+        );
+    }
+    if compression.files_compressed > 0 || compression.files_failed > 0 {
+        println!(
+            "  Compression: {} binaries, {} saved",
+            compression.files_compressed,
+            size::format_bytes(compression.bytes_saved())
+        );
+    }
+    if let Some(ref report) = result.validation {
+        print_validation_warnings(report);
+    }
+    if let Some(ref report) = result.sizes {
+        println!();
+        print_size_table(report);
+    }
 }
 
-/// Check if a package is a kernel package
+/// Print the outcome of rootfs validation in the build summary
 ///
-/// A package is considered a kernel package if:
-/// - Its name contains "kernel" or "linux"
-/// - It has a GCC toolchain specified in its package.toml
-fn is_kernel_package(project_dir: &Path, pkg_name: &str) -> bool {
-    // Check by name
-    let name_lower = pkg_name.to_lowercase();
-    if name_lower.contains("kernel") || name_lower.contains("linux") {
-        return true;
+/// The findings themselves are listed in the warning summary.
+fn print_validation_warnings(report: &ValidationReport) {
+    let warnings: Vec<&Finding> = report.warnings().collect();
+    if warnings.is_empty() {
+        println!("  Validation: passed");
+        return;
     }
+    println!("  Validation: {} warning(s)", warnings.len());
+    for finding in warnings {
+        defer_warning(&format!("validation: {finding}"));
+    }
+}
 
-    // Check local package for GCC toolchain
-    let local_pkg_path = project_dir
-        .join("packages")
-        .join(pkg_name)
-        .join("package.toml");
-    if local_pkg_path.exists() {
-        if let Ok(content) = fs::read_to_string(&local_pkg_path) {
-            // Simple check for GCC toolchain in package.toml
-            if content.contains("[build.toolchain]") && content.contains("type = \"gcc\"") {
-                return true;
-            }
-        }
+/// Print a size report for `--analyze-size`
+fn print_size_report(report: &SizeReport) {
+    if is_json() {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(report).unwrap_or_default()
+        );
+        return;
     }
+    print_size_table(report);
+}
 
-    false
+/// Print the per-package size table, largest first
+fn print_size_table(report: &SizeReport) {
+    println!("Installed size by package:");
+    for pkg in &report.packages {
+        println!("  {:>10}  {}", size::format_bytes(pkg.bytes), pkg.name);
+    }
+    println!("  {:>10}  total", size::format_bytes(report.total));
+    if let Some(budget) = report.budget {
+        println!(
+            "  Budget: {} of {} used",
+            size::format_bytes(report.effective_size()),
+            size::format_bytes(budget)
+        );
+    }
 }
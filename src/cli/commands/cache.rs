@@ -7,7 +7,11 @@
 use anyhow::Result;
 use std::path::Path;
 
-use crate::core::cache::{clean_cache, export_cache, get_cache_info, import_cache};
+use crate::core::cache::{clean_cache, export_cache, get_cache_info, import_cache, ExportOptions};
+use crate::core::global_config::GlobalConfig;
+use crate::core::shared_storage::SharedStorage;
+use crate::core::variants::VariantStore;
+use crate::infra::dirs::ZigrootDirs;
 
 /// Execute cache info subcommand
 pub async fn execute_info(project_dir: &Path) -> Result<()> {
@@ -23,6 +27,31 @@ pub async fn execute_info(project_dir: &Path) -> Result<()> {
         println!("\n⚠️  Cache directory does not exist (empty cache)");
     }
 
+    print_build_variants(project_dir)
+}
+
+/// List the configurations whose build trees are kept per package
+fn print_build_variants(project_dir: &Path) -> Result<()> {
+    let keep = GlobalConfig::load(&ZigrootDirs::new())?.build_variants();
+    let variants = VariantStore::new(&project_dir.join("build"), keep).list();
+    if variants.is_empty() {
+        return Ok(());
+    }
+
+    println!("\nPackage configurations (keeping {keep} per package):");
+    for stored in &variants {
+        let state = if stored.active {
+            "active".to_string()
+        } else {
+            format_size(stored.size_bytes)
+        };
+        println!(
+            "  {} [{}] {} ({state})",
+            stored.variant.package,
+            stored.variant.hash,
+            stored.variant.describe()
+        );
+    }
     Ok(())
 }
 
@@ -48,17 +77,23 @@ pub async fn execute_clean(project_dir: &Path) -> Result<()> {
 }
 
 /// Execute cache export subcommand
-pub async fn execute_export(project_dir: &Path, output: &str) -> Result<()> {
+pub async fn execute_export(
+    project_dir: &Path,
+    output: &str,
+    options: &ExportOptions,
+) -> Result<()> {
     println!("📤 Exporting cache...\n");
 
-    let output_path = Path::new(output);
-
-    match export_cache(project_dir, output_path) {
-        Ok(size) => {
-            if size > 0 {
-                let size_str = format_size(size);
+    let storage = SharedStorage::new(&ZigrootDirs::new());
+    match export_cache(project_dir, &storage, Path::new(output), options) {
+        Ok(summary) => {
+            if summary.entries > 0 {
                 println!("✅ Cache exported to: {output}");
-                println!("   Size: {size_str}");
+                println!(
+                    "   {} files, {}",
+                    summary.entries,
+                    format_size(summary.bytes)
+                );
             } else {
                 println!("✅ Empty cache exported to: {output}");
             }
@@ -72,14 +107,19 @@ pub async fn execute_export(project_dir: &Path, output: &str) -> Result<()> {
 }
 
 /// Execute cache import subcommand
-pub async fn execute_import(project_dir: &Path, input: &str) -> Result<()> {
+pub async fn execute_import(input: &str) -> Result<()> {
     println!("📥 Importing cache...\n");
 
-    let input_path = Path::new(input);
-
-    match import_cache(project_dir, input_path) {
-        Ok(_) => {
+    let storage = SharedStorage::new(&ZigrootDirs::new());
+    match import_cache(&storage, Path::new(input)) {
+        Ok(summary) => {
             println!("✅ Cache imported from: {input}");
+            println!(
+                "   {} files imported ({}), {} already present",
+                summary.imported,
+                format_size(summary.bytes),
+                summary.skipped
+            );
             Ok(())
         }
         Err(e) => {
@@ -9,13 +9,16 @@ use std::fs;
 use std::path::Path;
 
 use crate::cli::output::{
-    is_json, is_quiet, print_detail, print_info, print_success, print_warning, status,
+    is_json, is_quiet, print_detail, print_info, print_success, report_warning, status,
 };
 use crate::core::check;
 use crate::core::manifest::Manifest;
 
 /// Execute the check command
-pub async fn execute(project_dir: &Path) -> Result<()> {
+///
+/// With `network`, external artifact URLs are probed as well. Unless
+/// `offline`, package names are checked against the registry index.
+pub async fn execute(project_dir: &Path, network: bool, offline: bool) -> Result<()> {
     let manifest_path = project_dir.join("zigroot.toml");
 
     // Check manifest exists
@@ -33,17 +36,25 @@ pub async fn execute(project_dir: &Path) -> Result<()> {
     tracing::info!("Checking project: {}", manifest.project.name);
 
     // Perform check
-    let result =
+    let mut result =
         check::check(project_dir, &manifest).map_err(|e| anyhow::anyhow!("Check failed: {}", e))?;
+    if !offline {
+        check::check_registry_packages(project_dir, &manifest, &mut result).await;
+    }
+    if network {
+        check::check_artifact_urls(&manifest, &mut result).await;
+    }
 
     // JSON output mode
     if is_json() {
         let json_result = serde_json::json!({
             "status": if result.is_valid() { "success" } else { "error" },
             "config_valid": result.config_valid,
+            "config_errors": result.config_errors,
             "dependencies_valid": result.dependencies_valid,
             "toolchains_available": result.toolchains_available,
             "missing_dependencies": result.missing_dependencies,
+            "conflicts": result.conflicts,
             "warnings": result.warnings,
             "packages_to_build": result.packages_to_build,
             "build_order": result.build_order,
@@ -72,11 +83,17 @@ pub async fn execute(project_dir: &Path) -> Result<()> {
         if !result.is_valid() {
             if !result.config_valid {
                 eprintln!("{} Configuration has errors", status::ERROR);
+                for error in &result.config_errors {
+                    eprintln!("{} {error}", status::ERROR);
+                }
             }
             if !result.dependencies_valid {
                 for dep in &result.missing_dependencies {
                     eprintln!("{} Missing dependency: {dep}", status::ERROR);
                 }
+                for conflict in &result.conflicts {
+                    eprintln!("{} {conflict}", status::ERROR);
+                }
             }
             bail!("Check failed");
         }
@@ -92,6 +109,9 @@ pub async fn execute(project_dir: &Path) -> Result<()> {
         println!("{} Configuration is valid", status::SUCCESS);
     } else {
         println!("{} Configuration has errors", status::ERROR);
+        for error in &result.config_errors {
+            print_detail(error);
+        }
     }
 
     // Dependencies status
@@ -102,6 +122,9 @@ pub async fn execute(project_dir: &Path) -> Result<()> {
         for dep in &result.missing_dependencies {
             print_detail(&format!("Missing dependency: {dep}"));
         }
+        for conflict in &result.conflicts {
+            print_detail(conflict);
+        }
     }
 
     // Toolchain status
@@ -111,11 +134,11 @@ pub async fn execute(project_dir: &Path) -> Result<()> {
         println!("{} Zig toolchain not found in PATH", status::WARNING);
     }
 
-    // Display warnings
+    // Display warnings, repeated in the summary at the end
     if !result.warnings.is_empty() {
         println!("\nWarnings:");
         for warning in &result.warnings {
-            print_warning(warning);
+            report_warning(warning);
         }
     }
 
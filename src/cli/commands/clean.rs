@@ -8,11 +8,16 @@ use std::path::Path;
 
 use anyhow::{Context, Result};
 
-use crate::core::clean::{clean_project, has_build_artifacts};
+use crate::core::clean::{clean_with_options, CleanOptions, CleanedCategory};
 use crate::core::manifest::Manifest;
+use crate::core::size::format_bytes;
+use crate::infra::dirs::ZigrootDirs;
+
+/// Categories listing more paths than this are summarized as a count
+const MAX_LISTED_PATHS: usize = 3;
 
 /// Execute the clean command
-pub async fn execute(path: &Path) -> Result<()> {
+pub async fn execute(path: &Path, options: CleanOptions) -> Result<()> {
     // Verify we're in a zigroot project
     let manifest_path = path.join("zigroot.toml");
     if !manifest_path.exists() {
@@ -25,27 +30,63 @@ pub async fn execute(path: &Path) -> Result<()> {
     // Validate manifest is readable (basic check)
     let manifest_content = std::fs::read_to_string(&manifest_path)
         .with_context(|| format!("Failed to read manifest from {}", manifest_path.display()))?;
-    let _manifest = Manifest::from_toml(&manifest_content)
+    let manifest = Manifest::from_toml(&manifest_content)
         .with_context(|| format!("Failed to parse manifest from {}", manifest_path.display()))?;
 
-    // Check if there's anything to clean
-    if !has_build_artifacts(path) {
-        println!("✓ Nothing to clean");
-        return Ok(());
-    }
-
     // Perform the clean
-    let result = clean_project(path).with_context(|| "Failed to clean build artifacts")?;
+    let result =
+        clean_with_options(path, &options).with_context(|| "Failed to clean build artifacts")?;
+
+    if let Some(package) = &options.package {
+        if result.is_empty() && !manifest.packages.contains_key(package) {
+            anyhow::bail!("Package '{package}' not found in manifest");
+        }
+    }
 
     // Report what was cleaned
-    if result.removed.is_empty() {
+    if result.is_empty() {
         println!("✓ Nothing to clean");
     } else {
-        println!("✓ Cleaned build artifacts:");
-        for dir in &result.removed {
-            println!("  Removed {dir}/");
+        let what = options.package.as_ref().map_or_else(
+            || "build artifacts".to_string(),
+            |p| format!("package '{p}'"),
+        );
+        if options.dry_run {
+            println!("Would clean {what} (dry run):");
+        } else {
+            println!("✓ Cleaned {what}:");
         }
+        for category in &result.categories {
+            println!(
+                "  {}: {} ({})",
+                category.name,
+                describe_paths(category),
+                format_bytes(category.bytes)
+            );
+        }
+        let verb = if options.dry_run {
+            "Would reclaim"
+        } else {
+            "Reclaimed"
+        };
+        println!("{verb} {}", format_bytes(result.total_bytes()));
+    }
+
+    if options.package.is_none() {
+        println!(
+            "Kept the shared download store at {}",
+            ZigrootDirs::new().downloads_dir().display()
+        );
     }
 
     Ok(())
 }
+
+/// Removed paths of a category, or their count if there are many
+fn describe_paths(category: &CleanedCategory) -> String {
+    if category.paths.len() > MAX_LISTED_PATHS {
+        format!("{} files", category.paths.len())
+    } else {
+        category.paths.join(", ")
+    }
+}
@@ -0,0 +1,112 @@
+//! CLI command implementation for `zigroot completions`
+//!
+//! Prints a shell completion script generated from the CLI definition. The
+//! bash, zsh and fish scripts also complete package and board names through
+//! the hidden `zigroot __complete <kind> <prefix>` command.
+
+use anyhow::Result;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use std::io::Write;
+
+use crate::cli::Cli;
+use crate::core::completion::{candidates, CompletionKind};
+use crate::registry::client::default_cache_dir;
+
+/// Bash wrapper completing names before falling back to the generated script
+const BASH_HOOKS: &str = r#"
+# Dynamic completion of package and board names
+_zigroot_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}"
+    local kind="" i
+    local -a args=()
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        [[ "${COMP_WORDS[i]}" != -* ]] && args+=("${COMP_WORDS[i]}")
+    done
+    case "${args[0]}:${#args[@]}" in
+        add:1) kind=packages ;;
+        remove:1 | tree:1 | env:1) kind=project-packages ;;
+        board:2) [[ "${args[1]}" == set ]] && kind=boards ;;
+        init:*) [[ "$prev" == --board || "$prev" == -b ]] && kind=boards ;;
+    esac
+    if [[ -n "$kind" && "$cur" != -* ]]; then
+        COMPREPLY=($(compgen -W "$(zigroot __complete "$kind" "$cur" 2>/dev/null)" -- "$cur"))
+        return 0
+    fi
+    _zigroot "$@"
+}
+complete -F _zigroot_dynamic -o bashdefault -o default zigroot
+"#;
+
+/// Zsh wrapper completing names before falling back to the generated script
+const ZSH_HOOKS: &str = r#"
+# Dynamic completion of package and board names
+_zigroot_dynamic() {
+    local kind
+    local -a args candidates
+    args=(${${words[2,CURRENT-1]}:#-*})
+    case "${args[1]}:${#args}" in
+        add:1) kind=packages ;;
+        remove:1|tree:1|env:1) kind=project-packages ;;
+        board:2) [[ "${args[2]}" == set ]] && kind=boards ;;
+        init:*) [[ "${words[CURRENT-1]}" == (--board|-b) ]] && kind=boards ;;
+    esac
+    if [[ -n "$kind" && "$PREFIX" != -* ]]; then
+        candidates=(${(f)"$(zigroot __complete "$kind" "$PREFIX" 2>/dev/null)"})
+        compadd -a candidates
+        return
+    fi
+    _zigroot "$@"
+}
+compdef _zigroot_dynamic zigroot
+"#;
+
+/// Fish completions of names, added to the generated ones
+const FISH_HOOKS: &str = r#"
+# Dynamic completion of package and board names
+complete -c zigroot -n "__fish_seen_subcommand_from add" -f -a "(zigroot __complete packages (commandline -ct) 2>/dev/null)"
+complete -c zigroot -n "__fish_seen_subcommand_from remove tree env" -f -a "(zigroot __complete project-packages (commandline -ct) 2>/dev/null)"
+complete -c zigroot -n "__fish_seen_subcommand_from board; and __fish_seen_subcommand_from set" -f -a "(zigroot __complete boards (commandline -ct) 2>/dev/null)"
+complete -c zigroot -n "__fish_seen_subcommand_from init" -s b -l board -x -a "(zigroot __complete boards (commandline -ct) 2>/dev/null)"
+"#;
+
+/// Execute the completions command
+pub fn execute(shell: Shell) -> Result<()> {
+    // The generators offer hidden subcommands too, so rebuild the command
+    // without `__complete`
+    let cli = Cli::command();
+    let mut command = clap::Command::new("zigroot")
+        .version(env!("CARGO_PKG_VERSION"))
+        .propagate_version(true)
+        .args(cli.get_arguments().cloned())
+        .subcommands(cli.get_subcommands().filter(|c| !c.is_hide_set()).cloned());
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, "zigroot", &mut script);
+
+    let hooks = match shell {
+        Shell::Bash => BASH_HOOKS,
+        Shell::Zsh => ZSH_HOOKS,
+        Shell::Fish => FISH_HOOKS,
+        _ => "",
+    };
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(&script)?;
+    stdout.write_all(hooks.as_bytes())?;
+    Ok(())
+}
+
+/// Execute the hidden `__complete` command used by the completion scripts
+///
+/// Prints one candidate per line. Unknown kinds print nothing, since the
+/// shell has no way to show an error.
+pub fn execute_complete(kind: &str, prefix: &str) -> Result<()> {
+    let Ok(kind) = kind.parse::<CompletionKind>() else {
+        return Ok(());
+    };
+    let project_dir = std::env::current_dir().ok();
+    let mut stdout = std::io::stdout().lock();
+    for name in candidates(kind, project_dir.as_deref(), &default_cache_dir(), prefix) {
+        writeln!(stdout, "{name}")?;
+    }
+    Ok(())
+}
@@ -1,16 +1,21 @@
 //! CLI command for `zigroot config`
 //!
-//! Launches interactive TUI configuration interface.
+//! Launches interactive TUI configuration interface, and implements
+//! `zigroot config get/set/list` for global settings.
 //!
-//! **Validates: Requirements 25.1-25.17**
+//! **Validates: Requirements 25.1-25.17, 32.5**
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::path::Path;
 
+use crate::cli::output::is_json;
 use crate::cli::tui::ConfigTui;
 use crate::core::config::{
     get_available_packages, is_terminal_interactive, load_manifest_for_config, ConfigState,
 };
+use crate::core::global_config::GlobalConfig;
+use crate::infra::dirs::ZigrootDirs;
+use crate::registry::client::RegistryClient;
 
 /// Execute config command
 pub async fn execute(project_dir: &Path, board_only: bool, packages_only: bool) -> Result<()> {
@@ -38,8 +43,12 @@ pub async fn execute(project_dir: &Path, board_only: bool, packages_only: bool)
         return Ok(());
     }
 
+    // Fetch the board index up front; the TUI itself stays synchronous
+    let board_index = RegistryClient::new().fetch_board_index().await;
+
     // Launch the TUI
     let mut tui = ConfigTui::new(project_dir, board_only, packages_only)?;
+    tui.set_board_index(board_index.map(|index| index.boards));
     tui.run()?;
 
     Ok(())
@@ -87,3 +96,71 @@ fn print_non_interactive_info(state: &ConfigState, project_dir: &Path) {
     println!("To use the interactive TUI, run this command in a terminal that supports");
     println!("interactive input (not in a dumb terminal or piped context).");
 }
+
+/// Execute `zigroot config get <key>`
+pub async fn execute_get(key: &str) -> Result<()> {
+    let dirs = ZigrootDirs::new();
+    let config = GlobalConfig::load(&dirs)?;
+    let value = config.get(key)?;
+
+    if is_json() {
+        let json = serde_json::json!({ "key": key, "value": value });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json).unwrap_or_default()
+        );
+    } else {
+        println!("{}", value.as_deref().unwrap_or("(not set)"));
+    }
+
+    Ok(())
+}
+
+/// Execute `zigroot config set <key> <value>`
+///
+/// Validates the value against the key's type before writing the global
+/// config file.
+pub async fn execute_set(key: &str, value: &str) -> Result<()> {
+    let dirs = ZigrootDirs::new();
+    let mut config = GlobalConfig::load(&dirs)?;
+    config.set(key, value)?;
+    config
+        .save(&dirs)
+        .with_context(|| "Failed to save global config")?;
+
+    println!("✓ Set {key} = {value}");
+    println!("  Saved to: {}", dirs.global_config_path().display());
+
+    Ok(())
+}
+
+/// Execute `zigroot config list`
+pub async fn execute_list() -> Result<()> {
+    let dirs = ZigrootDirs::new();
+    let config = GlobalConfig::load(&dirs)?;
+    let entries = config.entries();
+
+    if is_json() {
+        let settings: serde_json::Map<String, serde_json::Value> = entries
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), serde_json::json!(value)))
+            .collect();
+        let json = serde_json::json!({
+            "path": dirs.global_config_path().display().to_string(),
+            "settings": settings,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json).unwrap_or_default()
+        );
+        return Ok(());
+    }
+
+    println!("Global config: {}", dirs.global_config_path().display());
+    println!();
+    for (key, value) in entries {
+        println!("  {key} = {}", value.as_deref().unwrap_or("(not set)"));
+    }
+
+    Ok(())
+}
@@ -4,95 +4,101 @@
 //!
 //! **Validates: Requirements 14.5, 14.6**
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use std::path::Path;
 
 use crate::cli::output::{
     is_json, is_quiet, print_detail, print_info, print_success, print_warning, status,
 };
-use crate::core::doctor::run_doctor;
+use crate::core::doctor::{
+    check_registry, run_doctor, CheckCategory, CheckResult, CheckStatus, DoctorReport,
+};
+use crate::core::global_config::GlobalConfig;
+use crate::infra::dirs::ZigrootDirs;
 
 /// Execute the doctor command
-pub async fn execute(project_dir: Option<&Path>) -> Result<()> {
-    let report = run_doctor(project_dir);
+///
+/// Exits with an error when any check fails, or when any check warns and
+/// `warnings_as_errors` is set. With `offline`, the registry is not
+/// contacted.
+pub async fn execute(
+    project_dir: Option<&Path>,
+    warnings_as_errors: bool,
+    offline: bool,
+) -> Result<()> {
+    let mut report = run_doctor(project_dir);
+    if !offline {
+        let config = GlobalConfig::load(&ZigrootDirs::new()).unwrap_or_default();
+        report.add_check(check_registry(&config.network_settings(), config.packages_url()).await);
+    }
 
     // JSON output mode
     if is_json() {
         let json_result = serde_json::json!({
-            "status": if report.all_passed() { "success" } else if report.failed_required().is_empty() { "warning" } else { "error" },
+            "status": match report.status() {
+                CheckStatus::Pass => "success",
+                CheckStatus::Warn => "warning",
+                CheckStatus::Fail => "error",
+            },
             "checks": report.checks.iter().map(|c| serde_json::json!({
+                "id": c.id,
+                "category": c.category,
+                "status": c.status(),
                 "name": c.name,
                 "passed": c.passed,
                 "required": c.required,
+                "detected": c.detected(),
                 "version": c.version,
                 "error": c.error,
+                "remediation": c.remediation(),
                 "suggestion": c.suggestion
             })).collect::<Vec<_>>(),
-            "config_issues": report.config_issues,
             "passed_count": report.passed_count(),
+            "warning_count": report.count(CheckStatus::Warn),
+            "failed_count": report.count(CheckStatus::Fail),
             "total_count": report.checks.len()
         });
         println!(
             "{}",
             serde_json::to_string_pretty(&json_result).unwrap_or_default()
         );
-
-        if !report.failed_required().is_empty() {
-            return Err(anyhow::anyhow!("Missing required dependencies"));
-        }
-        return Ok(());
+        return exit_status(&report, warnings_as_errors);
     }
 
     // Quiet mode - only show errors
     if is_quiet() {
-        let failed_required = report.failed_required();
-        if !failed_required.is_empty() {
-            for check in failed_required {
-                eprintln!("{} Missing required: {}", status::ERROR, check.name);
+        for check in &report.checks {
+            match check.status() {
+                CheckStatus::Fail => {
+                    eprintln!("{} Failed: {}", status::ERROR, check.name);
+                }
+                CheckStatus::Warn if warnings_as_errors => {
+                    eprintln!("{} Warning: {}", status::WARNING, check.name);
+                }
+                _ => {}
             }
-            return Err(anyhow::anyhow!("Missing required dependencies"));
         }
-        return Ok(());
+        return exit_status(&report, warnings_as_errors);
     }
+    print_report(&report);
+    exit_status(&report, warnings_as_errors)
+}
+
+/// Print the checks and a summary in normal output mode
+fn print_report(report: &DoctorReport) {
+    let (project, system): (Vec<&CheckResult>, Vec<&CheckResult>) = report
+        .checks
+        .iter()
+        .partition(|c| c.category == CheckCategory::Project);
 
-    // Normal output mode
     print_info("Checking system dependencies...");
     println!();
-
-    // Print check results
-    for check in &report.checks {
-        let version_str = check
-            .version
-            .as_ref()
-            .map(|v| format!(" (v{v})"))
-            .unwrap_or_default();
-
-        let required_str = if check.required { "" } else { " [optional]" };
-
-        if check.passed {
-            println!(
-                "  {} {}{version_str}{required_str}",
-                status::SUCCESS,
-                check.name
-            );
-        } else {
-            println!("  {} {}{required_str}", status::ERROR, check.name);
-            if let Some(error) = &check.error {
-                print_detail(&format!("Error: {error}"));
-            }
-            if let Some(suggestion) = &check.suggestion {
-                print_detail(&format!("Suggestion: {suggestion}"));
-            }
-        }
-    }
-
-    // Print configuration issues
-    if !report.config_issues.is_empty() {
+    print_checks(&system);
+    if !project.is_empty() {
         println!();
-        print_warning("Configuration issues:");
-        for issue in &report.config_issues {
-            print_detail(&format!("• {issue}"));
-        }
+        print_info("Checking project...");
+        println!();
+        print_checks(&project);
     }
 
     // Print summary
@@ -117,10 +123,55 @@ pub async fn execute(project_dir: Option<&Path>) -> Result<()> {
                 print_detail(&format!("• {}: {suggestion}", check.name));
             }
         }
-        return Err(anyhow::anyhow!(
-            "Missing required dependencies. Run 'zigroot doctor' for details."
-        ));
     }
+}
+
+/// Print the result lines of a group of checks
+fn print_checks(checks: &[&CheckResult]) {
+    for check in checks {
+        let version_str = detected_label(check);
 
-    Ok(())
+        let required_str = if check.required { "" } else { " [optional]" };
+
+        if check.passed {
+            println!(
+                "  {} {}{version_str}{required_str}",
+                status::SUCCESS,
+                check.name
+            );
+        } else {
+            let symbol = if check.required {
+                status::ERROR
+            } else {
+                status::WARNING
+            };
+            println!("  {symbol} {}{required_str}", check.name);
+            if let Some(error) = &check.error {
+                print_detail(&format!("Error: {error}"));
+            }
+            if let Some(suggestion) = &check.suggestion {
+                print_detail(&format!("Suggestion: {suggestion}"));
+            }
+        }
+    }
+}
+
+/// Format the detected value of a check for display, e.g. " (v0.11.0)"
+fn detected_label(check: &CheckResult) -> String {
+    match check.detected() {
+        Some(value) if value.starts_with(|c: char| c.is_ascii_digit()) => format!(" (v{value})"),
+        Some(value) => format!(" ({value})"),
+        None => String::new(),
+    }
+}
+
+/// Turn the report into the command's exit status
+fn exit_status(report: &DoctorReport, warnings_as_errors: bool) -> Result<()> {
+    if !report.is_failure(warnings_as_errors) {
+        return Ok(());
+    }
+    if report.status() == CheckStatus::Fail {
+        bail!("Required checks failed. Run 'zigroot doctor' for details.");
+    }
+    bail!("Doctor found warnings, which --warnings-as-errors treats as errors");
 }
@@ -0,0 +1,177 @@
+//! CLI command implementation for `zigroot env`
+//!
+//! Prints the cross-compilation environment zigroot builds a package with,
+//! so its compiler invocations can be reproduced in a shell, or runs a
+//! command with that environment in the package's build directory.
+
+use anyhow::{anyhow, bail, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::cli::output::is_json;
+use crate::core::build_env::BuildEnvironment;
+use crate::core::project::ZigrootProject;
+
+/// Syntax of the printed environment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum EnvShell {
+    /// POSIX shells: `export KEY='value'`
+    #[default]
+    Sh,
+    /// fish: `set -gx KEY 'value'`
+    Fish,
+}
+
+/// Execute the env command
+///
+/// Without a package, the environment of the board is printed. With `run`,
+/// `command` is executed with the environment instead.
+pub async fn execute(
+    project_dir: &Path,
+    package: Option<String>,
+    shell: EnvShell,
+    run: bool,
+    command: Vec<String>,
+) -> Result<()> {
+    let project = ZigrootProject::open(project_dir)?;
+    let env = project.environment(package.as_deref()).await?;
+
+    if run {
+        return run_command(&env, &command).await;
+    }
+    if is_json() {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&env_json(package.as_deref(), &env))?
+        );
+    } else {
+        print!("{}", format_env(&env, shell));
+    }
+    Ok(())
+}
+
+/// Run `command` with the build environment in the build directory
+async fn run_command(env: &BuildEnvironment, command: &[String]) -> Result<()> {
+    let Some((program, args)) = command.split_first() else {
+        bail!("No command given. Pass it after --, e.g. 'zigroot env --run -- ./configure'");
+    };
+    if !env.srcdir.is_dir() {
+        bail!(
+            "Build directory {} does not exist. Run 'zigroot fetch' or 'zigroot build' first.",
+            env.srcdir.display()
+        );
+    }
+
+    let mut process = tokio::process::Command::new(program);
+    process
+        .args(args)
+        .envs(env.to_env_map())
+        .current_dir(&env.srcdir);
+    if let Some(path) = env.search_path() {
+        process.env("PATH", path);
+    }
+    let status = process.status().await.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            anyhow!("{program} not found")
+        } else {
+            anyhow!("Failed to run {program}: {e}")
+        }
+    })?;
+    if !status.success() {
+        bail!("{program} exited with {status}");
+    }
+    Ok(())
+}
+
+/// Environment as shell commands, sorted by variable
+fn format_env(env: &BuildEnvironment, shell: EnvShell) -> String {
+    let vars: BTreeMap<String, String> = env.to_env_map().into_iter().collect();
+    let mut out = String::new();
+    for (key, value) in &vars {
+        let line = match shell {
+            EnvShell::Sh => format!("export {key}={}\n", sh_quote(value)),
+            EnvShell::Fish => format!("set -gx {key} {}\n", fish_quote(value)),
+        };
+        out.push_str(&line);
+    }
+    if !env.tool_paths.is_empty() {
+        let dirs: Vec<String> = env
+            .tool_paths
+            .iter()
+            .map(|dir| dir.display().to_string())
+            .collect();
+        let line = match shell {
+            EnvShell::Sh => format!("export PATH={}:\"$PATH\"\n", sh_quote(&dirs.join(":"))),
+            EnvShell::Fish => {
+                let quoted: Vec<String> = dirs.iter().map(|dir| fish_quote(dir)).collect();
+                format!("set -gx PATH {} $PATH\n", quoted.join(" "))
+            }
+        };
+        out.push_str(&line);
+    }
+    out
+}
+
+/// JSON representation of `env`
+fn env_json(package: Option<&str>, env: &BuildEnvironment) -> serde_json::Value {
+    let vars: BTreeMap<String, String> = env.to_env_map().into_iter().collect();
+    serde_json::json!({
+        "package": package,
+        "target": env.target,
+        "directory": env.srcdir,
+        "sysroot": env.sysroots,
+        "path": env.tool_paths,
+        "env": vars,
+    })
+}
+
+/// Quote a value for POSIX shells
+fn sh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Quote a value for fish
+fn fish_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn environment() -> BuildEnvironment {
+        BuildEnvironment::for_zig(
+            "aarch64-linux-musl",
+            "generic",
+            PathBuf::from("/p/build/src/app"),
+            PathBuf::from("/p/build/destdir/app"),
+        )
+        .with_env("GREETING", "it's")
+        .with_tool_path(PathBuf::from("/tools/bin"))
+    }
+
+    #[test]
+    fn test_format_env_sh() {
+        let out = format_env(&environment(), EnvShell::Sh);
+        assert!(out.contains("export CC='zig cc -target aarch64-linux-musl'\n"));
+        assert!(out.contains("export GREETING='it'\\''s'\n"));
+        assert!(out.ends_with("export PATH='/tools/bin':\"$PATH\"\n"));
+        let keys: Vec<&str> = out
+            .lines()
+            .filter_map(|line| line.strip_prefix("export "))
+            .filter_map(|line| line.split('=').next())
+            .collect();
+        let mut sorted = keys[..keys.len() - 1].to_vec();
+        sorted.sort_unstable();
+        assert_eq!(keys[..keys.len() - 1], sorted);
+    }
+
+    #[test]
+    fn test_format_env_fish() {
+        let out = format_env(&environment(), EnvShell::Fish);
+        assert!(out.contains("set -gx CC 'zig cc -target aarch64-linux-musl'\n"));
+        assert!(out.contains("set -gx GREETING 'it\\'s'\n"));
+        assert!(out.ends_with("set -gx PATH '/tools/bin' $PATH\n"));
+    }
+}
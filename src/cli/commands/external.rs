@@ -5,18 +5,31 @@
 //!
 //! **Validates: Requirements 8.1, 8.2, 8.9-8.13**
 
-use crate::core::external::{self, ArtifactStatus};
+use crate::cli::output::{create_spinner, is_json};
+use crate::core::external;
+use crate::core::project::ZigrootProject;
+use crate::core::shared_storage::SharedStorage;
+use crate::core::size::format_bytes;
+use crate::infra::dirs::ZigrootDirs;
+use crate::infra::download::ChecksumAlgorithm;
 use anyhow::Result;
 use std::path::Path;
 
 /// Execute the `zigroot external list` command
 ///
-/// Lists all configured external artifacts and their status.
+/// Lists all configured external artifacts with whether their file is
+/// present, its size and whether it matches the checksum.
 ///
 /// **Validates: Requirement 8.9**
 pub async fn execute_list(project_dir: &Path) -> Result<()> {
     let artifacts = external::list_artifacts(project_dir)?;
 
+    if is_json() {
+        let json = serde_json::json!({ "artifacts": artifacts });
+        println!("{}", serde_json::to_string_pretty(&json)?);
+        return Ok(());
+    }
+
     if artifacts.is_empty() {
         println!("No external artifacts configured.");
         return Ok(());
@@ -26,21 +39,15 @@ pub async fn execute_list(project_dir: &Path) -> Result<()> {
     println!();
 
     for artifact in &artifacts {
-        let status_icon = match artifact.status {
-            ArtifactStatus::Local => "✓",
-            ArtifactStatus::Downloaded => "✓",
-            ArtifactStatus::Missing => "✗",
-        };
-
-        let status_text = match artifact.status {
-            ArtifactStatus::Local => "local",
-            ArtifactStatus::Downloaded => "downloaded",
-            ArtifactStatus::Missing => "missing",
-        };
+        let status_icon = if artifact.needs_fetch() { "✗" } else { "✓" };
+        let size = artifact
+            .size_bytes
+            .map(|bytes| format!(", {}", format_bytes(bytes)))
+            .unwrap_or_default();
 
         println!(
-            "  {} {} [{}] - {}",
-            status_icon, artifact.name, artifact.artifact_type, status_text
+            "  {} {} [{}] - {}{size}, {}",
+            status_icon, artifact.name, artifact.artifact_type, artifact.status, artifact.checksum
         );
 
         if let Some(ref url) = artifact.url {
@@ -54,6 +61,20 @@ pub async fn execute_list(project_dir: &Path) -> Result<()> {
         }
     }
 
+    let pending: Vec<&str> = artifacts
+        .iter()
+        .filter(|artifact| artifact.needs_fetch())
+        .map(|artifact| artifact.name.as_str())
+        .collect();
+    if !pending.is_empty() {
+        println!();
+        println!(
+            "{} artifact(s) missing or failing verification: {}. Run 'zigroot fetch' before building.",
+            pending.len(),
+            pending.join(", ")
+        );
+    }
+
     Ok(())
 }
 
@@ -68,20 +89,103 @@ pub async fn execute_add(
     artifact_type: &str,
     url: Option<&str>,
     path: Option<&str>,
+    checksum: Option<(ChecksumAlgorithm, &str)>,
+    format: Option<&str>,
 ) -> Result<()> {
-    external::add_artifact(project_dir, name, artifact_type, url, path)?;
+    external::add_artifact(
+        project_dir,
+        name,
+        artifact_type,
+        url,
+        path,
+        checksum,
+        format,
+    )?;
 
     println!("✓ Added external artifact '{name}' ({artifact_type})");
 
     if let Some(url) = url {
         println!("  URL: {url}");
-        if path.is_none() {
-            println!("  Note: Consider adding a sha256 checksum for verification");
-        }
+    }
+    if let Some((algorithm, digest)) = checksum {
+        println!("  {}: {digest}", algorithm.name().to_uppercase());
     }
     if let Some(path) = path {
         println!("  Path: {path}");
     }
+    if let Some(format) = format {
+        println!("  Format: {format}");
+    }
+
+    Ok(())
+}
+
+/// Execute the `zigroot external remove` command
+///
+/// Removes an external artifact from the manifest and the lock file.
+pub fn execute_remove(project_dir: &Path, name: &str) -> Result<()> {
+    external::remove_artifact(project_dir, name)?;
+    println!("✓ Removed external artifact '{name}'");
+    Ok(())
+}
+
+/// Execute the `zigroot external update` command
+///
+/// Changes the URL, path or checksum of an external artifact.
+pub fn execute_update(
+    project_dir: &Path,
+    name: &str,
+    url: Option<&str>,
+    path: Option<&str>,
+    checksum: Option<(ChecksumAlgorithm, &str)>,
+) -> Result<()> {
+    let artifact = external::update_artifact(project_dir, name, url, path, checksum)?;
+
+    println!("✓ Updated external artifact '{name}'");
+    if let Some(ref url) = artifact.url {
+        println!("  URL: {url}");
+    }
+    if let Some(ref path) = artifact.path {
+        println!("  Path: {path}");
+    }
+    if let Ok(Some(checksum)) = artifact.checksum() {
+        println!("  Checksum: {checksum}");
+    }
+
+    Ok(())
+}
+
+/// Execute the `zigroot external fetch` command
+///
+/// Downloads URL artifacts into shared storage, so the build finds them.
+pub async fn execute_fetch(project_dir: &Path, names: &[String], force: bool) -> Result<()> {
+    let project = ZigrootProject::open(project_dir)?;
+    let storage = SharedStorage::new(&ZigrootDirs::new());
+
+    let spinner = create_spinner("Fetching external artifacts...");
+    let fetched =
+        external::fetch_artifacts(project_dir, project.manifest(), &storage, names, force).await;
+    spinner.finish_and_clear();
+    let fetched = fetched?;
+
+    if is_json() {
+        let json = serde_json::json!({ "artifacts": fetched });
+        println!("{}", serde_json::to_string_pretty(&json)?);
+        return Ok(());
+    }
+
+    if fetched.is_empty() {
+        println!("No URL artifacts to fetch.");
+        return Ok(());
+    }
+    for artifact in &fetched {
+        let how = if artifact.downloaded {
+            "downloaded"
+        } else {
+            "from shared storage"
+        };
+        println!("✓ {} ({how}) -> {}", artifact.name, artifact.file.display());
+    }
 
     Ok(())
 }
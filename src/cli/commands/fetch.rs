@@ -4,41 +4,88 @@
 
 use std::path::Path;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 
-use crate::core::fetch::{fetch_packages, FetchOptions};
+use crate::cli::commands::build::run_project_hooks;
+use crate::cli::output::{print_error, DownloadProgress};
+use crate::core::fetch::FetchOptions;
+use crate::core::global_config::GlobalConfig;
+use crate::core::hooks::{HookContext, HookEvent};
+use crate::core::project::ZigrootProject;
+use crate::core::size::parse_size;
+use crate::infra::dirs::ZigrootDirs;
 
 /// Execute the fetch command
-pub async fn execute(path: &Path, parallel: usize, force: bool) -> Result<()> {
-    // Check if manifest exists
-    let manifest_path = path.join("zigroot.toml");
-    if !manifest_path.exists() {
-        anyhow::bail!(
-            "No zigroot.toml found in {}. Run 'zigroot init' first.",
-            path.display()
-        );
-    }
-
+///
+/// With `verify_only`, nothing is downloaded and any missing or mismatching
+/// file fails the command. Sources are also tried at the mirror prefix of
+/// the global config; `prefer_mirror` tries mirrors first. `limit_rate`
+/// (or `fetch.limit_rate`) caps the combined rate of all downloads, and
+/// `parallel` defaults to `download.concurrency`. With `locked`, git
+/// packages must be fetched at their locked commits. The project's
+/// `post_fetch` hooks run after fetching.
+#[allow(clippy::fn_params_excessive_bools)]
+pub async fn execute(
+    path: &Path,
+    parallel: Option<usize>,
+    force: bool,
+    verify_only: bool,
+    prefer_mirror: bool,
+    limit_rate: Option<&str>,
+    locked: bool,
+) -> Result<()> {
+    let project = ZigrootProject::open(path)?;
+    let config = GlobalConfig::load(&ZigrootDirs::new())?;
+    let limit_rate = match limit_rate {
+        Some(rate) => Some(
+            parse_size(rate)
+                .ok()
+                .filter(|rate| *rate > 0)
+                .with_context(|| {
+                    format!("Invalid --limit-rate '{rate}': expected e.g. 500K or 2M")
+                })?,
+        ),
+        None => config.fetch.limit_rate,
+    };
     let options = FetchOptions {
-        parallel: if parallel == 0 { 4 } else { parallel },
+        parallel: parallel
+            .filter(|parallel| *parallel > 0)
+            .unwrap_or_else(|| config.download_concurrency()),
         force,
+        verify_only,
+        prefer_mirror,
+        mirror_prefix: config.download.source_mirror_prefix,
+        limit_rate,
+        locked,
     };
+    let display = DownloadProgress::new();
+    let result = project.fetch(&options, Some(&display.sink())).await;
+    display.finish();
+    let result = result?;
 
-    let result = fetch_packages(path, &options)
-        .await
-        .with_context(|| "Failed to fetch packages")?;
+    if verify_only {
+        if !result.failed.is_empty() {
+            for (name, error) in &result.failed {
+                println!("✗ {name}: {error}");
+            }
+            bail!("{} item(s) failed verification", result.failed.len());
+        }
+        println!("✓ All fetched files match their checksums");
+        return Ok(());
+    }
 
     // Print summary
     if result.downloaded.is_empty()
         && result.skipped.is_empty()
         && result.external_downloaded.is_empty()
+        && result.failed.is_empty()
     {
         println!("✓ Nothing to fetch");
     } else {
         if !result.downloaded.is_empty() {
             println!("✓ Downloaded {} package(s):", result.downloaded.len());
             for pkg in &result.downloaded {
-                println!("    {} v{}", pkg.name, pkg.version);
+                println!("    {} v{} from {}", pkg.name, pkg.version, pkg.url);
             }
         }
 
@@ -54,8 +101,8 @@ pub async fn execute(path: &Path, parallel: usize, force: bool) -> Result<()> {
                 "✓ Downloaded {} external artifact(s):",
                 result.external_downloaded.len()
             );
-            for name in &result.external_downloaded {
-                println!("    {name}");
+            for (name, url) in &result.external_downloaded {
+                println!("    {name} from {url}");
             }
         }
 
@@ -71,6 +118,18 @@ pub async fn execute(path: &Path, parallel: usize, force: bool) -> Result<()> {
             for (name, error) in &result.failed {
                 println!("    {name}: {error}");
             }
+            if locked {
+                bail!("{} item(s) failed to fetch", result.failed.len());
+            }
+        }
+    }
+
+    // A failing post_fetch hook is reported; the fetch itself succeeded
+    if let Some(hooks) = &project.manifest().hooks {
+        let mut context = HookContext::new(project.manifest());
+        context.success = result.failed.is_empty();
+        if let Err(e) = run_project_hooks(path, hooks, HookEvent::PostFetch, &context) {
+            print_error(&e.to_string());
         }
     }
 
@@ -5,7 +5,8 @@
 use anyhow::{bail, Context, Result};
 use std::path::Path;
 
-use crate::core::flash::{load_board_definition, FlashExecutor, FlashOptions};
+use crate::cli::output::create_download_bar;
+use crate::core::flash::{check_image_file, load_board_definition, FlashExecutor, FlashOptions};
 use crate::core::manifest::Manifest;
 
 /// Execute the flash command
@@ -15,18 +16,29 @@ pub async fn execute(
     device: Option<String>,
     yes: bool,
     list: bool,
+    image: Option<&Path>,
 ) -> Result<()> {
-    // Load manifest
-    let manifest_path = project_root.join("zigroot.toml");
-    if !manifest_path.exists() {
-        bail!("No zigroot.toml found. Run 'zigroot init' to create a project.");
+    // An explicit image must look like one, and needs no project
+    let image = image.map(|path| project_root.join(path));
+    if let Some(ref path) = image {
+        let format = check_image_file(path)?;
+        tracing::info!("Flashing {} ({format})", path.display());
     }
 
-    let manifest_content = std::fs::read_to_string(&manifest_path)
-        .with_context(|| format!("Failed to read manifest: {}", manifest_path.display()))?;
-
-    let manifest =
-        Manifest::from_toml(&manifest_content).with_context(|| "Failed to parse zigroot.toml")?;
+    // Load manifest
+    let manifest_path = project_root.join("zigroot.toml");
+    let manifest = if manifest_path.exists() {
+        let manifest_content = std::fs::read_to_string(&manifest_path)
+            .with_context(|| format!("Failed to read manifest: {}", manifest_path.display()))?;
+        Manifest::from_toml(&manifest_content).with_context(|| "Failed to parse zigroot.toml")?
+    } else if image.is_some() {
+        Manifest::default()
+    } else {
+        bail!(
+            "No zigroot.toml found. Run 'zigroot init' to create a project, \
+             or flash an image file with --image <path>."
+        );
+    };
 
     // Load board definition if configured
     let board = if let Some(board_name) = &manifest.board.name {
@@ -49,9 +61,21 @@ pub async fn execute(
         list,
     };
 
-    // Execute flash
-    let executor = FlashExecutor::new(project_root, manifest, board);
-    let result = executor.execute(&options)?;
+    // Execute flash, showing the transfer of SSH flashing
+    let bar = create_download_bar(0);
+    let progress = bar.clone();
+    let mut executor = FlashExecutor::new(project_root, manifest, board).with_progress(Box::new(
+        move |sent, total| {
+            progress.set_length(total);
+            progress.set_position(sent);
+        },
+    ));
+    if let Some(ref path) = image {
+        executor = executor.with_image(path);
+    }
+    let result = executor.execute(&options);
+    bar.finish_and_clear();
+    let result = result?;
 
     // Print result
     if result.success {
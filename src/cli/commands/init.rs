@@ -6,18 +6,18 @@ use std::path::Path;
 
 use anyhow::{Context, Result};
 
-use crate::cli::output::{print_detail, print_success};
+use crate::cli::output::{is_json, print_detail, print_success};
 use crate::core::init::{
-    append_gitignore_entries, create_project_structure, derive_project_name,
-    generate_gitignore_content, generate_manifest_content, validate_init, InitOptions,
+    append_gitignore_entries, create_project_structure, find_template, generate_gitignore_content,
+    generate_manifest_content, manifest_settings, templates, validate_init, write_template_files,
+    InitOptions,
 };
 
 /// Execute the init command
-pub async fn execute(path: &Path, board: Option<String>, force: bool) -> Result<()> {
-    let options = InitOptions {
-        board: board.clone(),
-        force,
-    };
+pub async fn execute(path: &Path, options: InitOptions, template: Option<&str>) -> Result<()> {
+    // Resolve the template and settings before touching the directory
+    let template = template.map(find_template).transpose()?;
+    let settings = manifest_settings(path, &options, template.as_ref())?;
 
     // Validate we can proceed
     validate_init(path, &options).with_context(|| "Failed to validate initialization")?;
@@ -26,13 +26,18 @@ pub async fn execute(path: &Path, board: Option<String>, force: bool) -> Result<
     create_project_structure(path).with_context(|| "Failed to create project structure")?;
 
     // Generate and write manifest
-    let project_name = derive_project_name(path);
-    let manifest_content = generate_manifest_content(&project_name, board.as_deref());
+    let manifest_content = generate_manifest_content(&settings, template.as_ref());
     let manifest_path = path.join("zigroot.toml");
 
     std::fs::write(&manifest_path, &manifest_content)
         .with_context(|| format!("Failed to write manifest to {}", manifest_path.display()))?;
 
+    let template_files = match &template {
+        Some(template) => write_template_files(path, template)
+            .with_context(|| "Failed to write template files")?,
+        None => Vec::new(),
+    };
+
     // Handle .gitignore
     let gitignore_path = path.join(".gitignore");
     let gitignore_existed = gitignore_path.exists();
@@ -60,9 +65,63 @@ pub async fn execute(path: &Path, board: Option<String>, force: bool) -> Result<
         print_detail("Created .gitignore");
     }
 
-    if let Some(board_name) = &board {
-        print_detail(&format!("Configured board: {board_name}"));
+    if let Some(board_name) = &settings.board {
+        match &settings.variant {
+            Some(variant) => {
+                print_detail(&format!(
+                    "Configured board: {board_name} (variant {variant})"
+                ));
+            }
+            None => print_detail(&format!("Configured board: {board_name}")),
+        }
+    }
+    if let Some(template) = &template {
+        print_detail(&format!(
+            "Applied template '{}' with packages: {}",
+            template.template.name,
+            template
+                .packages
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        for file in &template_files {
+            print_detail(&format!("Created user/files/{file}"));
+        }
     }
 
     Ok(())
 }
+
+/// Execute `zigroot init --list-templates`
+pub fn execute_list_templates() -> Result<()> {
+    let templates = templates();
+
+    if is_json() {
+        let json = serde_json::json!({
+            "templates": templates.iter().map(|t| serde_json::json!({
+                "name": t.template.name,
+                "description": t.template.description,
+                "packages": t.packages.keys().collect::<Vec<_>>(),
+            })).collect::<Vec<_>>()
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json).unwrap_or_default()
+        );
+        return Ok(());
+    }
+
+    println!("Available templates:");
+    println!();
+    for template in &templates {
+        println!(
+            "  {:<12} {}",
+            template.template.name, template.template.description
+        );
+    }
+    println!();
+    println!("Use 'zigroot init --template <name>' to start from a template.");
+    Ok(())
+}
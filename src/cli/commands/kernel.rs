@@ -1,14 +1,25 @@
 //! Kernel command implementation
 //!
-//! Handles kernel-related commands like menuconfig.
+//! Handles kernel-related commands like menuconfig, savedefconfig and build.
 //!
 //! # Requirements
 //!
 //! - **26.11**: Launch kernel menuconfig
 //! - **26.12**: Save config to kernel/ directory
 
-use anyhow::{Context, Result};
-use std::path::Path;
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::core::build_env::BuildEnvironment;
+use crate::core::flash::load_board_definition;
+use crate::core::kernel::{
+    self, KernelBuildCommands, KernelBuildEnv, KernelConfig, DEFAULT_KERNEL_PACKAGE,
+    SAVED_DEFCONFIG_FILE,
+};
+use crate::core::manifest::Manifest;
+use crate::infra::filesystem::write_file_atomic;
 
 /// Execute kernel menuconfig command
 ///
@@ -64,3 +75,243 @@ pub async fn execute_menuconfig(project_dir: &Path) -> Result<()> {
 
     Ok(())
 }
+
+/// Execute kernel build command
+///
+/// Compiles the kernel declared by the board (or the default `linux-kernel`
+/// package) using its defconfig and config fragments, plus the minimal config
+/// saved by `kernel savedefconfig`, then collects the kernel image and DTBs
+/// into `output/kernel/` and registers them as external artifacts in
+/// zigroot.toml.
+///
+/// # Arguments
+///
+/// * `project_dir` - Path to the project directory
+///
+/// # Returns
+///
+/// Result indicating success or failure
+pub async fn execute_build(project_dir: &Path) -> Result<()> {
+    let mut ctx = load_kernel_context(project_dir, "build")?;
+
+    let destdir = &ctx.kernel_env.destdir;
+    std::fs::create_dir_all(destdir).context("Failed to create kernel staging directory")?;
+
+    let saved_defconfig = ctx.kernel_env.saved_defconfig_path();
+    let has_saved = ctx.config.apply_saved_defconfig(&saved_defconfig);
+
+    println!(
+        "🔧 Building kernel '{}' for {}...",
+        ctx.kernel_pkg, ctx.target
+    );
+    if let Some(defconfig) = ctx.config.defconfig.as_deref() {
+        println!("   defconfig: {defconfig}");
+    }
+    if has_saved {
+        println!("   minimal config: kernel/{SAVED_DEFCONFIG_FILE}");
+    }
+
+    // Embed the assembled rootfs as the kernel's built-in initramfs
+    if ctx.manifest.build.embed_in_kernel {
+        let rootfs_dir = project_dir.join("build/rootfs");
+        if !rootfs_dir.is_dir() {
+            bail!(
+                "build.embed_in_kernel is set but build/rootfs does not exist. Run 'zigroot build' first."
+            );
+        }
+        let compression = &ctx.manifest.build.initramfs_compression;
+        let fragment = kernel::write_initramfs_fragment(&ctx.kernel_env, &rootfs_dir, compression)
+            .context("Failed to write initramfs config fragment")?;
+        ctx.config
+            .config_fragments
+            .push(fragment.display().to_string());
+        println!("   initramfs: build/rootfs ({compression}) embedded");
+    }
+
+    let commands = KernelBuildCommands::generate_for_arch(
+        &ctx.config,
+        &ctx.kernel_env.arch,
+        &ctx.cross_compile,
+    );
+    run_kernel_commands(&commands, &ctx)?;
+
+    let artifacts = kernel::collect_artifacts(project_dir, &ctx.kernel_env)
+        .context("Failed to collect kernel artifacts")?;
+    if artifacts.is_empty() {
+        bail!(
+            "Kernel build produced no image at {}",
+            ctx.kernel_env.image_path().display()
+        );
+    }
+
+    kernel::register_artifacts(&mut ctx.manifest, &artifacts);
+    let updated = ctx
+        .manifest
+        .to_toml()
+        .with_context(|| "Failed to serialize manifest")?;
+    write_file_atomic(&ctx.manifest_path, updated.as_bytes()).with_context(|| {
+        format!(
+            "Failed to write manifest at {}",
+            ctx.manifest_path.display()
+        )
+    })?;
+
+    println!("✓ Kernel build complete");
+    for artifact in &artifacts {
+        println!(
+            "  {} [{}] -> {}",
+            artifact.name, artifact.artifact_type, artifact.path
+        );
+    }
+
+    Ok(())
+}
+
+/// Execute kernel savedefconfig command
+///
+/// Runs the kernel's `savedefconfig` target and stores the resulting minimal
+/// config in the project's kernel/ directory so that only the differences
+/// from the defaults are kept under version control.
+///
+/// # Arguments
+///
+/// * `project_dir` - Path to the project directory
+///
+/// # Returns
+///
+/// Result indicating success or failure
+pub async fn execute_savedefconfig(project_dir: &Path) -> Result<()> {
+    let ctx = load_kernel_context(project_dir, "savedefconfig")?;
+
+    let has_config =
+        kernel::stage_project_config(&ctx.kernel_env).context("Failed to stage kernel config")?;
+
+    println!(
+        "🔧 Saving minimal kernel config for '{}'...",
+        ctx.kernel_pkg
+    );
+
+    let commands = KernelBuildCommands::generate_savedefconfig(
+        &ctx.config,
+        &ctx.kernel_env.arch,
+        &ctx.cross_compile,
+        has_config,
+    );
+    run_kernel_commands(&commands, &ctx)?;
+
+    let stored = kernel::store_saved_defconfig(&ctx.kernel_env)
+        .context("savedefconfig did not produce a defconfig")?;
+
+    println!("✓ Minimal config saved to {}", stored.display());
+
+    Ok(())
+}
+
+/// Everything needed to run make in a kernel source tree
+struct KernelContext {
+    manifest_path: PathBuf,
+    manifest: Manifest,
+    kernel_pkg: String,
+    config: KernelConfig,
+    target: String,
+    cross_compile: String,
+    kernel_env: KernelBuildEnv,
+    env_vars: HashMap<String, String>,
+}
+
+/// Resolve the kernel package, its config, and the build environment
+///
+/// Fails with a hint to fetch if the kernel source is missing.
+/// `subcommand` is used in that message.
+fn load_kernel_context(project_dir: &Path, subcommand: &str) -> Result<KernelContext> {
+    let manifest_path = project_dir.join("zigroot.toml");
+    if !manifest_path.exists() {
+        bail!("No zigroot.toml found. Run 'zigroot init' to create a project.");
+    }
+
+    let content = std::fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read manifest at {}", manifest_path.display()))?;
+    let manifest = Manifest::from_toml(&content).with_context(|| "Failed to parse zigroot.toml")?;
+
+    // The board may declare which package provides its kernel
+    let board = manifest
+        .board
+        .name
+        .as_deref()
+        .and_then(|name| load_board_definition(project_dir, name).ok());
+    let kernel_pkg = board
+        .as_ref()
+        .and_then(|b| b.board.kernel.clone())
+        .unwrap_or_else(|| DEFAULT_KERNEL_PACKAGE.to_string());
+
+    let pkg_toml_path = project_dir
+        .join("packages")
+        .join(&kernel_pkg)
+        .join("package.toml");
+    if !pkg_toml_path.exists() {
+        bail!("No kernel package found. Create a kernel package in packages/{kernel_pkg}/");
+    }
+    let pkg_content = std::fs::read_to_string(&pkg_toml_path)
+        .with_context(|| format!("Failed to read {}", pkg_toml_path.display()))?;
+    let config = KernelConfig::from_package_toml(&pkg_content)
+        .with_context(|| format!("Failed to parse {}", pkg_toml_path.display()))?;
+
+    // Toolchain target: package [build.toolchain] wins over the board target
+    let Some(target) = kernel::toolchain_target_from_package_toml(&pkg_content)
+        .or_else(|| board.as_ref().map(|b| b.board.target.clone()))
+    else {
+        bail!(
+            "Cannot determine kernel target. Set [build.toolchain] target in packages/{kernel_pkg}/package.toml"
+        );
+    };
+    let cpu = board.as_ref().map_or("generic", |b| b.board.cpu.as_str());
+
+    let kernel_src_dir = project_dir.join("build/src").join(&kernel_pkg);
+    if !kernel_src_dir.exists() {
+        bail!(
+            "Kernel source not found. Run 'zigroot fetch' first to download kernel source, \
+             then run 'zigroot kernel {subcommand}' again."
+        );
+    }
+
+    let destdir = project_dir.join("build/kernel").join(&kernel_pkg);
+    let cross_compile = kernel::target_to_cross_compile(&target);
+    let build_env = BuildEnvironment::for_gcc(
+        &format!("{cross_compile}-"),
+        &target,
+        cpu,
+        kernel_src_dir,
+        destdir,
+    );
+    let kernel_env = KernelBuildEnv::from_build_env(&build_env, project_dir.join("kernel"));
+    let env_vars = kernel::kernel_env_vars(&build_env, &kernel_env);
+
+    Ok(KernelContext {
+        manifest_path,
+        manifest,
+        kernel_pkg,
+        config,
+        target,
+        cross_compile,
+        kernel_env,
+        env_vars,
+    })
+}
+
+/// Run make commands in the kernel source tree, failing on the first error
+fn run_kernel_commands(commands: &[String], ctx: &KernelContext) -> Result<()> {
+    for command in commands {
+        tracing::info!("Running: {command}");
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(&ctx.kernel_env.srcdir)
+            .envs(&ctx.env_vars)
+            .status()
+            .with_context(|| format!("Failed to run '{command}'"))?;
+        if !status.success() {
+            bail!("Kernel build step failed: {command}");
+        }
+    }
+    Ok(())
+}
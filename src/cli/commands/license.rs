@@ -1,18 +1,27 @@
 //! CLI command for `zigroot license`
 //!
-//! Displays license information and generates reports.
+//! Displays license information and generates reports, copies license
+//! files out of the package sources, and enforces the manifest's license
+//! deny list.
 //!
 //! **Validates: Requirements 22.1-22.6**
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use std::path::Path;
 
-use crate::core::license::collect_licenses;
+use crate::cli::output::print_warning;
+use crate::core::license::{collect_license_files, collect_licenses, LicenseReport};
 use crate::core::manifest::Manifest;
+use crate::core::output::OutputLayout;
 use crate::error::ZigrootError;
 
 /// Execute the license command
-pub async fn execute(project_dir: &Path, export: Option<String>, sbom: bool) -> Result<()> {
+pub async fn execute(
+    project_dir: &Path,
+    export: Option<String>,
+    sbom: bool,
+    collect: bool,
+) -> Result<()> {
     // Load manifest
     let manifest_path = project_dir.join("zigroot.toml");
     if !manifest_path.exists() {
@@ -43,8 +52,15 @@ pub async fn execute(project_dir: &Path, export: Option<String>, sbom: bool) ->
         };
 
         let sbom_content = report.generate_sbom(project_name);
-        let sbom_path = project_dir.join(format!("{project_name}-sbom.spdx"));
+        let output_dir = OutputLayout::new(project_dir, &manifest).board_dir();
+        let sbom_path = output_dir.join(format!("{project_name}-sbom.spdx"));
 
+        std::fs::create_dir_all(&output_dir).map_err(|e| {
+            ZigrootError::Filesystem(crate::error::FilesystemError::CreateDir {
+                path: output_dir.clone(),
+                error: e.to_string(),
+            })
+        })?;
         std::fs::write(&sbom_path, &sbom_content).map_err(|e| {
             ZigrootError::Filesystem(crate::error::FilesystemError::WriteFile {
                 path: sbom_path.clone(),
@@ -57,7 +73,33 @@ pub async fn execute(project_dir: &Path, export: Option<String>, sbom: bool) ->
             "\nSoftware Bill of Materials contains {} packages.",
             report.packages.len()
         );
-        return Ok(());
+        return check_deny_list(&manifest, &report);
+    }
+
+    // Handle license file collection
+    if collect {
+        let licenses_dir = OutputLayout::new(project_dir, &manifest)
+            .board_dir()
+            .join("licenses");
+        let collected =
+            collect_license_files(project_dir, &manifest, &licenses_dir).map_err(|e| {
+                ZigrootError::Filesystem(crate::error::FilesystemError::WriteFile {
+                    path: licenses_dir.clone(),
+                    error: e.to_string(),
+                })
+            })?;
+
+        for (name, files) in &collected.copied {
+            println!("  • {name}: {} file(s)", files.len());
+        }
+        println!("✅ License files collected in: {}", licenses_dir.display());
+        if !collected.missing.is_empty() {
+            print_warning(&format!(
+                "No license files found for: {} (set license_files in the package definition)",
+                collected.missing.join(", ")
+            ));
+        }
+        return check_deny_list(&manifest, &report);
     }
 
     // Handle export
@@ -89,13 +131,45 @@ pub async fn execute(project_dir: &Path, export: Option<String>, sbom: bool) ->
                     report.missing_licenses.join(", ")
                 );
             }
+            for warning in report.invalid_license_warnings() {
+                println!("⚠️  {warning}");
+            }
         }
 
-        return Ok(());
+        return check_deny_list(&manifest, &report);
     }
 
     // Display summary
     println!("{}", report.summary());
 
-    Ok(())
+    check_deny_list(&manifest, &report)
+}
+
+/// Fail when a package can only be used under a denied license
+fn check_deny_list(manifest: &Manifest, report: &LicenseReport) -> Result<()> {
+    let Some(config) = &manifest.license else {
+        return Ok(());
+    };
+
+    let denied = report
+        .denied_packages(&config.deny)
+        .map_err(|e| anyhow::anyhow!("Invalid [license] deny list: {e}"))?;
+    if denied.is_empty() {
+        return Ok(());
+    }
+
+    println!();
+    for pkg in &denied {
+        println!(
+            "❌ {} v{}: {}",
+            pkg.name,
+            pkg.version,
+            pkg.license.as_deref().unwrap_or_default()
+        );
+    }
+    bail!(
+        "{} package(s) use licenses denied in [license]: {}",
+        denied.len(),
+        config.deny.join(", ")
+    )
 }
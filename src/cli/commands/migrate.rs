@@ -0,0 +1,64 @@
+//! CLI implementation for `zigroot migrate` command
+//!
+//! Rewrites zigroot.toml in the current manifest schema. Comments and key
+//! order are preserved.
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+use crate::cli::output::is_json;
+use crate::core::manifest::{migrate_manifest, Manifest, MANIFEST_VERSION};
+
+/// Execute the migrate command
+pub async fn execute(project_dir: &Path) -> Result<()> {
+    let manifest_path = project_dir.join("zigroot.toml");
+    if !manifest_path.exists() {
+        bail!("No zigroot.toml found. Run 'zigroot init' to create a project.");
+    }
+
+    let content = tokio::fs::read_to_string(&manifest_path)
+        .await
+        .with_context(|| format!("Failed to read manifest at {}", manifest_path.display()))?;
+    let migration = migrate_manifest(&content).context("Failed to migrate zigroot.toml")?;
+
+    if !migration.is_current() {
+        // Make sure the result still loads before replacing the original
+        Manifest::from_toml(&migration.content)
+            .context("Migrated zigroot.toml is not a valid manifest")?;
+        tokio::fs::write(&manifest_path, &migration.content)
+            .await
+            .with_context(|| format!("Failed to write {}", manifest_path.display()))?;
+    }
+
+    if is_json() {
+        let renamed: Vec<_> = migration
+            .renamed
+            .iter()
+            .map(|(old, new)| serde_json::json!({ "from": old, "to": new }))
+            .collect();
+        let json = serde_json::json!({
+            "status": "success",
+            "from_version": migration.from_version,
+            "to_version": MANIFEST_VERSION,
+            "migrated": !migration.is_current(),
+            "renamed": renamed,
+        });
+        println!("{}", serde_json::to_string_pretty(&json)?);
+        return Ok(());
+    }
+
+    if migration.is_current() {
+        println!("✓ zigroot.toml already uses manifest_version {MANIFEST_VERSION}");
+        return Ok(());
+    }
+
+    println!(
+        "✓ Migrated zigroot.toml from manifest_version {} to {MANIFEST_VERSION}",
+        migration.from_version
+    );
+    for (old, new) in &migration.renamed {
+        println!("  Renamed {old} -> {new}");
+    }
+    Ok(())
+}
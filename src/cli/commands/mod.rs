@@ -8,19 +8,24 @@ pub mod build;
 pub mod cache;
 pub mod check;
 pub mod clean;
+pub mod completions;
 pub mod config;
 pub mod doctor;
+pub mod env;
 pub mod external;
 pub mod fetch;
 pub mod flash;
 pub mod init;
 pub mod kernel;
 pub mod license;
+pub mod migrate;
 pub mod package;
 pub mod publish;
 pub mod remove;
+pub mod run;
 pub mod sdk;
 pub mod search;
+pub mod stats;
 pub mod tree;
 pub mod update;
 pub mod verify;
@@ -28,6 +33,8 @@ pub mod verify;
 use anyhow::Result;
 use clap::Subcommand;
 
+use crate::registry::cache::CachePolicy;
+
 /// Available CLI commands
 #[derive(Subcommand, Debug)]
 pub enum Commands {
@@ -40,26 +47,72 @@ pub enum Commands {
         /// Force initialization in non-empty directory
         #[arg(short, long)]
         force: bool,
+
+        /// Start from a project template (see --list-templates)
+        #[arg(short, long)]
+        template: Option<String>,
+
+        /// List the available templates and exit
+        #[arg(long, conflicts_with = "template")]
+        list_templates: bool,
+
+        /// Project name (defaults to the directory name)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Target hostname
+        #[arg(long)]
+        hostname: Option<String>,
+
+        /// Image format: ext4, squashfs, or initramfs
+        #[arg(long)]
+        image_format: Option<String>,
+
+        /// Root filesystem size (e.g., 256M)
+        #[arg(long)]
+        rootfs_size: Option<String>,
     },
 
     /// Add a package to the project
     Add {
-        /// Package name (optionally with @version)
+        /// Package name, optionally with @version (exact) or @constraint (e.g. @^1.36)
         package: String,
 
-        /// Add from git repository
+        /// Add from git repository (optionally with #ref); the ref is
+        /// resolved to a commit recorded in zigroot.lock
         #[arg(long)]
         git: Option<String>,
 
+        /// Git branch, tag or commit to use with --git
+        #[arg(long = "ref", alias = "ref_", value_name = "REF", requires = "git")]
+        git_ref: Option<String>,
+
+        /// Only list missing dependencies instead of adding them
+        #[arg(long)]
+        no_deps: bool,
+
         /// Use custom registry
         #[arg(long)]
         registry: Option<String>,
+
+        /// Contact neither the registry nor the git repository; a --git ref
+        /// is then not pinned to a commit
+        #[arg(long)]
+        offline: bool,
     },
 
     /// Remove a package from the project
     Remove {
         /// Package name to remove
         package: String,
+
+        /// Remove the package even if other packages depend on it
+        #[arg(long)]
+        force: bool,
+
+        /// Also remove dependencies that no other package needs
+        #[arg(long)]
+        cascade: bool,
     },
 
     /// Update packages to newer versions
@@ -68,28 +121,53 @@ pub enum Commands {
         package: Option<String>,
 
         /// Check for zigroot updates
-        #[arg(long)]
+        #[arg(long, alias = "self")]
         self_update: bool,
+
+        /// Do not contact the network (with --self-update)
+        #[arg(long)]
+        offline: bool,
     },
 
     /// Download package sources
     Fetch {
-        /// Number of parallel downloads
-        #[arg(short, long, default_value = "4")]
-        parallel: usize,
+        /// Number of parallel downloads (default: download.concurrency of
+        /// the global config, or 4)
+        #[arg(short, long)]
+        parallel: Option<usize>,
 
         /// Force re-download even if files exist
         #[arg(short, long)]
         force: bool,
+
+        /// Only verify the checksums of already fetched files
+        #[arg(long, conflicts_with = "force")]
+        verify_only: bool,
+
+        /// Try mirrors before the primary URL of each source
+        #[arg(long)]
+        prefer_mirror: bool,
+
+        /// Limit the combined download rate, in bytes per second (e.g. 500K, 2M)
+        #[arg(long, value_name = "RATE")]
+        limit_rate: Option<String>,
+
+        /// Fail if a git package cannot be fetched at its locked commit
+        #[arg(long)]
+        locked: bool,
     },
 
     /// Build the rootfs
     Build {
-        /// Build only specified package
+        /// Build only specified package and its dependencies
         #[arg(short, long)]
         package: Option<String>,
 
-        /// Number of parallel jobs
+        /// With --package, build only that package (dependencies must be built)
+        #[arg(long, requires = "package")]
+        no_deps: bool,
+
+        /// Number of parallel jobs (default and 0: all CPUs)
         #[arg(short, long)]
         jobs: Option<usize>,
 
@@ -97,32 +175,105 @@ pub enum Commands {
         #[arg(long)]
         locked: bool,
 
-        /// Enable binary compression
+        /// Enable binary compression (default: `build.compress`)
         #[arg(long)]
         compress: bool,
 
-        /// Disable binary compression
-        #[arg(long)]
+        /// Disable binary compression (default: `build.compress`)
+        #[arg(long, conflicts_with = "compress")]
         no_compress: bool,
 
         /// Build only kernel and modules
         #[arg(long)]
         kernel_only: bool,
 
-        /// Enable container isolation (Docker/Podman)
+        /// Build packages in a namespace sandbox (bubblewrap) without network
         #[arg(long)]
         sandbox: bool,
 
-        /// Disable container isolation (overrides manifest setting)
+        /// Disable build isolation (overrides manifest setting)
         #[arg(long)]
         no_sandbox: bool,
+
+        /// Show per-package sizes from the last build without rebuilding
+        #[arg(long)]
+        analyze_size: bool,
+
+        /// Also write the standalone initramfs when it is embedded in the kernel
+        #[arg(long)]
+        also_standalone: bool,
+
+        /// Write a JSON build report (default: output/build-report.json)
+        #[arg(
+            long,
+            value_name = "PATH",
+            num_args = 0..=1,
+            default_missing_value = crate::core::report::DEFAULT_REPORT_PATH,
+            conflicts_with = "analyze_size"
+        )]
+        report: Option<String>,
+
+        /// Skip the check for free disk space before building
+        #[arg(long)]
+        skip_space_check: bool,
+
+        /// Rebuild affected packages and the image whenever sources change
+        #[arg(long, conflicts_with = "analyze_size")]
+        watch: bool,
+
+        /// Write outputs below this directory instead of `build.output_dir`
+        #[arg(long, value_name = "DIR")]
+        output_dir: Option<String>,
+
+        /// Skip checking the assembled rootfs for missing libraries, init and shells
+        #[arg(long)]
+        no_validate: bool,
+
+        /// Pin file times and image metadata so identical inputs give identical images
+        #[arg(long)]
+        reproducible: bool,
+
+        /// Keep building independent packages after one fails, reporting all failures
+        #[arg(long)]
+        keep_going: bool,
+
+        /// Write the per-package build logs to this directory instead of build/logs
+        #[arg(long, value_name = "DIR")]
+        log_dir: Option<String>,
     },
 
     /// Remove build artifacts
-    Clean,
+    Clean {
+        /// Only remove this package's build, staging and rootfs files, forcing a rebuild
+        #[arg(long, conflicts_with_all = ["downloads", "all"])]
+        package: Option<String>,
+
+        /// Also remove project-local downloads
+        #[arg(long)]
+        downloads: bool,
+
+        /// Remove all project-local artifacts, including downloads
+        #[arg(long)]
+        all: bool,
+
+        /// Show what would be removed without removing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
 
     /// Validate configuration without building
-    Check,
+    Check {
+        /// Also check that external artifact URLs are reachable
+        #[arg(long, conflicts_with = "offline")]
+        network: bool,
+
+        /// Don't check package names against the registry index
+        #[arg(long)]
+        offline: bool,
+    },
+
+    /// Rewrite zigroot.toml in the current manifest schema
+    Migrate,
 
     /// Search for packages and boards
     Search {
@@ -140,6 +291,47 @@ pub enum Commands {
         /// Force refresh of index
         #[arg(long)]
         refresh: bool,
+
+        /// Show only packages and boards in the current project
+        #[arg(long)]
+        installed: bool,
+    },
+
+    /// Print the cross-compilation environment of a package build
+    Env {
+        /// Package whose build environment to print (default: the board's)
+        package: Option<String>,
+
+        /// Shell syntax of the printed environment
+        #[arg(long, value_enum, default_value_t = env::EnvShell::Sh)]
+        shell: env::EnvShell,
+
+        /// Run a command with the environment in the package's build directory
+        #[arg(long, requires = "command")]
+        run: bool,
+
+        /// Command to run with --run (after --)
+        #[arg(last = true, value_name = "COMMAND", requires = "run")]
+        command: Vec<String>,
+    },
+
+    /// Boot the built image in QEMU
+    Run {
+        /// Open a display window; the serial console stays on the terminal
+        #[arg(long, overrides_with = "no_graphic")]
+        graphic: bool,
+
+        /// Use only the serial console (default)
+        #[arg(long, overrides_with = "graphic")]
+        no_graphic: bool,
+
+        /// Forward this host port to SSH in the guest
+        #[arg(long, value_name = "PORT")]
+        ssh_port: Option<u16>,
+
+        /// Extra arguments passed to QEMU (after --)
+        #[arg(last = true, value_name = "QEMU_ARGS")]
+        qemu_args: Vec<String>,
     },
 
     /// Flash image to device
@@ -147,7 +339,11 @@ pub enum Commands {
         /// Flash method to use
         method: Option<String>,
 
-        /// Device path
+        /// Flash method to use (instead of the positional argument)
+        #[arg(long = "method", value_name = "METHOD", conflicts_with = "method")]
+        method_option: Option<String>,
+
+        /// Device path (for SSH flashing, the remote target path)
         #[arg(short, long)]
         device: Option<String>,
 
@@ -158,6 +354,10 @@ pub enum Commands {
         /// List available flash methods
         #[arg(short, long)]
         list: bool,
+
+        /// Flash this image file instead of the project's build output
+        #[arg(long, value_name = "PATH")]
+        image: Option<std::path::PathBuf>,
     },
 
     /// Package management subcommands
@@ -180,6 +380,14 @@ pub enum Commands {
         /// Output in DOT graph format
         #[arg(long)]
         graph: bool,
+
+        /// Also show build-only dependencies (`build_depends`)
+        #[arg(long)]
+        build_deps: bool,
+
+        /// Show every dependency path that pulls this package into the build
+        #[arg(long, value_name = "PACKAGE", conflicts_with_all = ["package", "build_deps"])]
+        why: Option<String>,
     },
 
     /// Manage external artifacts
@@ -189,7 +397,15 @@ pub enum Commands {
     },
 
     /// Check system dependencies
-    Doctor,
+    Doctor {
+        /// Exit with an error when any check has warnings
+        #[arg(long)]
+        warnings_as_errors: bool,
+
+        /// Skip checks that contact the registry
+        #[arg(long)]
+        offline: bool,
+    },
 
     /// Generate standalone SDK
     Sdk {
@@ -207,6 +423,10 @@ pub enum Commands {
         /// Generate SPDX SBOM
         #[arg(long)]
         sbom: bool,
+
+        /// Copy package license files into the output directory
+        #[arg(long)]
+        collect: bool,
     },
 
     /// Manage build cache
@@ -215,7 +435,18 @@ pub enum Commands {
         command: CacheCommands,
     },
 
-    /// Interactive configuration (TUI)
+    /// Show local build statistics
+    Stats {
+        /// Number of recent builds in the trends
+        #[arg(long, default_value = "10")]
+        last: usize,
+
+        /// Delete the recorded statistics
+        #[arg(long)]
+        clear: bool,
+    },
+
+    /// Interactive configuration (TUI), or global settings via subcommands
     Config {
         /// Show only board selection
         #[arg(long)]
@@ -224,6 +455,9 @@ pub enum Commands {
         /// Show only package selection
         #[arg(long)]
         packages: bool,
+
+        #[command(subcommand)]
+        command: Option<ConfigCommands>,
     },
 
     /// Verify package or board definition
@@ -234,12 +468,33 @@ pub enum Commands {
         /// Also fetch and verify checksums
         #[arg(long)]
         fetch: bool,
+
+        /// Ed25519 public key to check the definition's signature with
+        /// (default: `registry.public_key`)
+        #[arg(long, value_name = "PATH")]
+        public_key: Option<std::path::PathBuf>,
+
+        /// Fail on lint warnings as well as errors
+        #[arg(long)]
+        deny_warnings: bool,
+
+        /// Check that dependencies exist in the registry index
+        #[arg(long)]
+        network: bool,
     },
 
     /// Publish package or board to registry
     Publish {
         /// Path to package or board directory
         path: String,
+
+        /// Run the pre-flight checks without publishing
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Ed25519 private key to sign the definition with
+        #[arg(long, value_name = "PATH", env = "ZIGROOT_SIGNING_KEY")]
+        signing_key: Option<std::path::PathBuf>,
     },
 
     /// Kernel management subcommands
@@ -247,6 +502,23 @@ pub enum Commands {
         #[command(subcommand)]
         command: KernelCommands,
     },
+
+    /// Print a shell completion script
+    Completions {
+        /// Shell to generate the script for
+        shell: clap_complete::Shell,
+    },
+
+    /// List completion candidates for the shell completion scripts
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        /// What to complete (packages, project-packages, boards)
+        kind: String,
+
+        /// Prefix typed so far
+        #[arg(default_value = "", allow_hyphen_values = true)]
+        prefix: String,
+    },
 }
 
 /// Package subcommands
@@ -259,6 +531,14 @@ pub enum PackageCommands {
     Info {
         /// Package name
         package: String,
+
+        /// Fetch the registry metadata again instead of using the cache
+        #[arg(long, conflicts_with = "offline")]
+        refresh: bool,
+
+        /// Only use cached registry data, never the network
+        #[arg(long)]
+        offline: bool,
     },
 
     /// Create a new package template
@@ -267,10 +547,22 @@ pub enum PackageCommands {
         name: String,
     },
 
-    /// Test build a package
+    /// Test build a package in a throwaway project
     Test {
         /// Path to package directory
         path: String,
+
+        /// Board to build for (repeatable)
+        #[arg(long = "board", value_name = "NAME")]
+        boards: Vec<String>,
+
+        /// Target triples to build for without a board (comma-separated)
+        #[arg(long, value_name = "TRIPLES", value_delimiter = ',')]
+        targets: Vec<String>,
+
+        /// Keep the scratch projects and print where they are
+        #[arg(long)]
+        keep: bool,
     },
 
     /// Bump package version
@@ -300,6 +592,14 @@ pub enum BoardCommands {
     Info {
         /// Board name
         board: String,
+
+        /// Fetch the board definition again instead of using the cache
+        #[arg(long, conflicts_with = "offline")]
+        refresh: bool,
+
+        /// Only use cached registry data, never the network
+        #[arg(long)]
+        offline: bool,
     },
 
     /// Create a new board template
@@ -320,20 +620,94 @@ pub enum ExternalCommands {
         /// Artifact name
         name: String,
 
-        /// Artifact type
+        /// Artifact type (bootloader, kernel, `partition_table`, dtb, firmware, other)
         #[arg(long, value_name = "TYPE")]
         artifact_type: String,
 
-        /// Remote URL
+        /// Remote URL (requires --sha256, --sha512 or --blake3)
         #[arg(long)]
         url: Option<String>,
 
         /// Local path
         #[arg(long)]
         path: Option<String>,
+
+        /// SHA256 checksum of the file at --url
+        #[arg(long, requires = "url", conflicts_with_all = ["sha512", "blake3"])]
+        sha256: Option<String>,
+
+        /// SHA512 checksum of the file at --url
+        #[arg(long, requires = "url", conflicts_with = "blake3")]
+        sha512: Option<String>,
+
+        /// BLAKE3 checksum of the file at --url
+        #[arg(long, requires = "url")]
+        blake3: Option<String>,
+
+        /// Partition table format (gpt, mbr, rockchip)
+        #[arg(long)]
+        format: Option<String>,
+    },
+
+    /// Remove external artifact
+    Remove {
+        /// Artifact name
+        name: String,
+    },
+
+    /// Change the source or checksum of an external artifact
+    Update {
+        /// Artifact name
+        name: String,
+
+        /// New remote URL, replacing the local path and the mirrors
+        #[arg(long, conflicts_with = "path")]
+        url: Option<String>,
+
+        /// New local path, replacing the URL
+        #[arg(long)]
+        path: Option<String>,
+
+        /// New SHA256 checksum
+        #[arg(long, conflicts_with_all = ["sha512", "blake3"])]
+        sha256: Option<String>,
+
+        /// New SHA512 checksum
+        #[arg(long, conflicts_with = "blake3")]
+        sha512: Option<String>,
+
+        /// New BLAKE3 checksum
+        #[arg(long)]
+        blake3: Option<String>,
+    },
+
+    /// Download URL artifacts into shared storage ahead of a build
+    Fetch {
+        /// Artifacts to fetch (default: all URL artifacts)
+        names: Vec<String>,
+
+        /// Download again even if shared storage has a verified copy
+        #[arg(long)]
+        force: bool,
     },
 }
 
+/// The checksum given with `--sha256`, `--sha512` or `--blake3`
+fn checksum_flag(
+    sha256: Option<String>,
+    sha512: Option<String>,
+    blake3: Option<String>,
+) -> Option<(crate::infra::download::ChecksumAlgorithm, String)> {
+    use crate::infra::download::ChecksumAlgorithm;
+    [
+        (ChecksumAlgorithm::Sha256, sha256),
+        (ChecksumAlgorithm::Sha512, sha512),
+        (ChecksumAlgorithm::Blake3, blake3),
+    ]
+    .into_iter()
+    .find_map(|(algorithm, digest)| Some((algorithm, digest?)))
+}
+
 /// Cache subcommands
 #[derive(Subcommand, Debug)]
 pub enum CacheCommands {
@@ -343,63 +717,178 @@ pub enum CacheCommands {
     /// Clear cache
     Clean,
 
-    /// Export cache to tarball
+    /// Export the shared downloads and build cache to a .tar.zst archive
     Export {
         /// Output path
         output: String,
+
+        /// Only export entries referenced by the project's lock file
+        #[arg(long)]
+        project: bool,
+
+        /// Only export entries of these packages (comma-separated)
+        #[arg(long, value_name = "NAMES", value_delimiter = ',')]
+        packages: Vec<String>,
+
+        /// Only export source downloads
+        #[arg(long, conflicts_with = "builds_only")]
+        downloads_only: bool,
+
+        /// Only export build artifacts
+        #[arg(long)]
+        builds_only: bool,
     },
 
-    /// Import cache from tarball
+    /// Import a cache export, skipping entries the cache already has
     Import {
         /// Input path
         input: String,
     },
 }
 
+/// Global config subcommands
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// Show the value of a global setting
+    Get {
+        /// Setting key (e.g. download.concurrency)
+        key: String,
+    },
+
+    /// Set a global setting
+    Set {
+        /// Setting key (e.g. download.concurrency)
+        key: String,
+
+        /// New value
+        value: String,
+    },
+
+    /// List all global settings
+    List,
+}
+
 /// Kernel subcommands
 #[derive(Subcommand, Debug)]
 pub enum KernelCommands {
     /// Launch kernel menuconfig
     Menuconfig,
+
+    /// Save a minimal defconfig (diff from defaults) to kernel/defconfig
+    Savedefconfig,
+
+    /// Build the kernel image and DTBs from the declared config
+    Build,
 }
 
 impl Commands {
+    /// Whether the command changes the project, and so holds its lock
+    pub fn changes_project(&self) -> bool {
+        match self {
+            Self::Add { .. }
+            | Self::Remove { .. }
+            | Self::Fetch { .. }
+            | Self::Build { .. }
+            | Self::Clean { .. }
+            | Self::Kernel { .. } => true,
+            Self::Board { command } => matches!(command, BoardCommands::Set { .. }),
+            Self::Config { command, .. } => command.is_none(),
+            Self::Update { self_update, .. } => !self_update,
+            Self::External { command } => !matches!(command, ExternalCommands::List),
+            _ => false,
+        }
+    }
+
     /// Execute the command
     pub async fn run(self) -> Result<()> {
         match self {
-            Self::Init { board, force } => {
+            Self::Init {
+                board,
+                force,
+                template,
+                list_templates,
+                name,
+                hostname,
+                image_format,
+                rootfs_size,
+            } => {
+                if list_templates {
+                    return init::execute_list_templates();
+                }
                 let current_dir = std::env::current_dir()?;
-                init::execute(&current_dir, board, force).await
+                let options = crate::core::init::InitOptions {
+                    board,
+                    force,
+                    name,
+                    hostname,
+                    image_format,
+                    rootfs_size,
+                };
+                init::execute(&current_dir, options, template.as_deref()).await
             }
             Self::Add {
                 package,
                 git,
+                git_ref,
+                no_deps,
                 registry,
+                offline,
             } => {
                 let current_dir = std::env::current_dir()?;
-                add::execute(&current_dir, &package, git, registry).await
+                add::execute(
+                    &current_dir,
+                    &package,
+                    git,
+                    git_ref,
+                    registry,
+                    no_deps,
+                    offline,
+                )
+                .await
             }
-            Self::Remove { package } => {
+            Self::Remove {
+                package,
+                force,
+                cascade,
+            } => {
                 let current_dir = std::env::current_dir()?;
-                remove::execute(&current_dir, &package).await
+                remove::execute(&current_dir, &package, force, cascade).await
             }
             Self::Update {
                 package,
                 self_update,
+                offline,
             } => {
                 if self_update {
-                    update::execute_self_update().await
+                    update::execute_self_update(offline).await
                 } else {
                     let current_dir = std::env::current_dir()?;
                     update::execute(&current_dir, package).await
                 }
             }
-            Self::Fetch { parallel, force } => {
+            Self::Fetch {
+                parallel,
+                force,
+                verify_only,
+                prefer_mirror,
+                limit_rate,
+                locked,
+            } => {
                 let current_dir = std::env::current_dir()?;
-                fetch::execute(&current_dir, parallel, force).await
+                fetch::execute(
+                    &current_dir,
+                    parallel,
+                    force,
+                    verify_only,
+                    prefer_mirror,
+                    limit_rate.as_deref(),
+                    locked,
+                )
+                .await
             }
             Self::Build {
                 package,
+                no_deps,
                 jobs,
                 locked,
                 compress,
@@ -407,10 +896,21 @@ impl Commands {
                 kernel_only,
                 sandbox,
                 no_sandbox,
+                analyze_size,
+                also_standalone,
+                report,
+                skip_space_check,
+                watch,
+                output_dir,
+                no_validate,
+                reproducible,
+                keep_going,
+                log_dir,
             } => {
                 let current_dir = std::env::current_dir()?;
-                let options = build::BuildOptions {
+                let options = crate::core::project::BuildOptions {
                     package,
+                    no_deps,
                     jobs,
                     locked,
                     compress,
@@ -418,36 +918,76 @@ impl Commands {
                     kernel_only,
                     sandbox,
                     no_sandbox,
+                    also_standalone,
+                    report,
+                    skip_space_check,
+                    output_dir,
+                    no_validate,
+                    reproducible,
+                    keep_going,
+                    log_dir,
                 };
-                build::execute(&current_dir, options).await
+                build::execute(&current_dir, options, analyze_size, watch).await
             }
-            Self::Clean => {
+            Self::Clean {
+                package,
+                downloads,
+                all,
+                dry_run,
+            } => {
                 let current_dir = std::env::current_dir()?;
-                clean::execute(&current_dir).await
+                let options = crate::core::clean::CleanOptions {
+                    package,
+                    downloads,
+                    all,
+                    dry_run,
+                };
+                clean::execute(&current_dir, options).await
             }
-            Self::Check => {
+            Self::Check { network, offline } => {
                 let current_dir = std::env::current_dir()?;
-                check::execute(&current_dir).await
+                check::execute(&current_dir, network, offline).await
+            }
+            Self::Migrate => {
+                let current_dir = std::env::current_dir()?;
+                migrate::execute(&current_dir).await
             }
             Self::Search {
                 query,
                 packages,
                 boards,
                 refresh,
-            } => search::execute(&query, packages, boards, refresh).await,
+                installed,
+            } => {
+                let current_dir = std::env::current_dir()?;
+                let options = crate::core::search::SearchOptions {
+                    packages_only: packages,
+                    boards_only: boards,
+                    refresh,
+                };
+                search::execute(&current_dir, &query, options, installed).await
+            }
             Self::Package { command } => {
                 let current_dir = std::env::current_dir()?;
                 match command {
                     PackageCommands::List => package::execute_list(&current_dir).await,
-                    PackageCommands::Info { package: pkg_name } => {
-                        package::execute_info(&current_dir, &pkg_name).await
+                    PackageCommands::Info {
+                        package: pkg_name,
+                        refresh,
+                        offline,
+                    } => {
+                        let policy = CachePolicy::from_flags(refresh, offline);
+                        package::execute_info(&current_dir, &pkg_name, policy).await
                     }
                     PackageCommands::New { name } => {
                         package::execute_new(&current_dir, &name).await
                     }
-                    PackageCommands::Test { path } => {
-                        package::execute_test(&current_dir, &path).await
-                    }
+                    PackageCommands::Test {
+                        path,
+                        boards,
+                        targets,
+                        keep,
+                    } => package::execute_test(&current_dir, &path, &boards, &targets, keep).await,
                     PackageCommands::Bump { path, new_version } => {
                         package::execute_bump(&current_dir, &path, &new_version).await
                     }
@@ -460,24 +1000,62 @@ impl Commands {
                     BoardCommands::Set { board: board_name } => {
                         board::execute_set(&current_dir, &board_name).await
                     }
-                    BoardCommands::Info { board: board_name } => {
-                        board::execute_info(&board_name).await
+                    BoardCommands::Info {
+                        board: board_name,
+                        refresh,
+                        offline,
+                    } => {
+                        board::execute_info(&board_name, CachePolicy::from_flags(refresh, offline))
+                            .await
                     }
                     BoardCommands::New { name } => board::execute_new(&current_dir, &name).await,
                 }
             }
-            Self::Tree { package, graph } => {
+            Self::Tree {
+                package,
+                graph,
+                build_deps,
+                why,
+            } => {
+                let current_dir = std::env::current_dir()?;
+                tree::execute(&current_dir, package, graph, build_deps, why).await
+            }
+            Self::Env {
+                package,
+                shell,
+                run,
+                command,
+            } => {
                 let current_dir = std::env::current_dir()?;
-                tree::execute(&current_dir, package, graph).await
+                env::execute(&current_dir, package, shell, run, command).await
+            }
+            Self::Run {
+                graphic,
+                no_graphic,
+                ssh_port,
+                qemu_args,
+            } => {
+                let current_dir = std::env::current_dir()?;
+                run::execute(&current_dir, graphic && !no_graphic, ssh_port, qemu_args).await
             }
             Self::Flash {
                 method,
+                method_option,
                 device,
                 yes,
                 list,
+                image,
             } => {
                 let current_dir = std::env::current_dir()?;
-                flash::execute(&current_dir, method, device, yes, list).await
+                flash::execute(
+                    &current_dir,
+                    method.or(method_option),
+                    device,
+                    yes,
+                    list,
+                    image.as_deref(),
+                )
+                .await
             }
             Self::External { command } => {
                 let current_dir = std::env::current_dir()?;
@@ -488,61 +1066,145 @@ impl Commands {
                         artifact_type,
                         url,
                         path,
+                        sha256,
+                        sha512,
+                        blake3,
+                        format,
                     } => {
+                        let checksum = checksum_flag(sha256, sha512, blake3);
                         external::execute_add(
                             &current_dir,
                             &name,
                             &artifact_type,
                             url.as_deref(),
                             path.as_deref(),
+                            checksum
+                                .as_ref()
+                                .map(|(algorithm, digest)| (*algorithm, digest.as_str())),
+                            format.as_deref(),
                         )
                         .await
                     }
+                    ExternalCommands::Remove { name } => {
+                        external::execute_remove(&current_dir, &name)
+                    }
+                    ExternalCommands::Update {
+                        name,
+                        url,
+                        path,
+                        sha256,
+                        sha512,
+                        blake3,
+                    } => {
+                        let checksum = checksum_flag(sha256, sha512, blake3);
+                        external::execute_update(
+                            &current_dir,
+                            &name,
+                            url.as_deref(),
+                            path.as_deref(),
+                            checksum
+                                .as_ref()
+                                .map(|(algorithm, digest)| (*algorithm, digest.as_str())),
+                        )
+                    }
+                    ExternalCommands::Fetch { names, force } => {
+                        external::execute_fetch(&current_dir, &names, force).await
+                    }
                 }
             }
-            Self::Doctor => {
+            Self::Doctor {
+                warnings_as_errors,
+                offline,
+            } => {
                 let current_dir = std::env::current_dir().ok();
-                doctor::execute(current_dir.as_deref()).await
+                doctor::execute(current_dir.as_deref(), warnings_as_errors, offline).await
             }
             Self::Sdk { output } => {
                 let current_dir = std::env::current_dir()?;
                 sdk::execute(&current_dir, output).await
             }
-            Self::License { export, sbom } => {
+            Self::License {
+                export,
+                sbom,
+                collect,
+            } => {
+                let current_dir = std::env::current_dir()?;
+                license::execute(&current_dir, export, sbom, collect).await
+            }
+            Self::Stats { last, clear } => {
                 let current_dir = std::env::current_dir()?;
-                license::execute(&current_dir, export, sbom).await
+                stats::execute(&current_dir, last, clear).await
             }
             Self::Cache { command } => {
                 let current_dir = std::env::current_dir()?;
                 match command {
                     CacheCommands::Info => cache::execute_info(&current_dir).await,
                     CacheCommands::Clean => cache::execute_clean(&current_dir).await,
-                    CacheCommands::Export { output } => {
-                        cache::execute_export(&current_dir, &output).await
-                    }
-                    CacheCommands::Import { input } => {
-                        cache::execute_import(&current_dir, &input).await
+                    CacheCommands::Export {
+                        output,
+                        project,
+                        packages,
+                        downloads_only,
+                        builds_only,
+                    } => {
+                        let options = crate::core::cache::ExportOptions {
+                            project,
+                            packages,
+                            downloads_only,
+                            builds_only,
+                        };
+                        cache::execute_export(&current_dir, &output, &options).await
                     }
+                    CacheCommands::Import { input } => cache::execute_import(&input).await,
                 }
             }
-            Self::Config { board, packages } => {
-                let current_dir = std::env::current_dir()?;
-                config::execute(&current_dir, board, packages).await
-            }
-            Self::Verify { path, fetch } => {
+            Self::Config {
+                board,
+                packages,
+                command,
+            } => match command {
+                Some(ConfigCommands::Get { key }) => config::execute_get(&key).await,
+                Some(ConfigCommands::Set { key, value }) => config::execute_set(&key, &value).await,
+                Some(ConfigCommands::List) => config::execute_list().await,
+                None => {
+                    let current_dir = std::env::current_dir()?;
+                    config::execute(&current_dir, board, packages).await
+                }
+            },
+            Self::Verify {
+                path,
+                fetch,
+                public_key,
+                deny_warnings,
+                network,
+            } => {
                 let current_dir = std::env::current_dir()?;
-                verify::execute(&current_dir, &path, fetch).await
+                let lints = verify::LintOptions {
+                    deny_warnings,
+                    network,
+                };
+                verify::execute(&current_dir, &path, fetch, public_key.as_deref(), lints).await
             }
-            Self::Publish { path } => {
+            Self::Publish {
+                path,
+                dry_run,
+                signing_key,
+            } => {
                 let current_dir = std::env::current_dir()?;
-                publish::execute(&current_dir, &path).await
+                publish::execute(&current_dir, &path, dry_run, signing_key.as_deref()).await
             }
             Self::Kernel { command } => {
                 let current_dir = std::env::current_dir()?;
                 match command {
                     KernelCommands::Menuconfig => kernel::execute_menuconfig(&current_dir).await,
+                    KernelCommands::Savedefconfig => {
+                        kernel::execute_savedefconfig(&current_dir).await
+                    }
+                    KernelCommands::Build => kernel::execute_build(&current_dir).await,
                 }
             }
+            Self::Completions { shell } => completions::execute(shell),
+            Self::Complete { kind, prefix } => completions::execute_complete(&kind, &prefix),
         }
     }
 }
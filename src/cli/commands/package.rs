@@ -8,7 +8,16 @@
 use anyhow::Result;
 use std::path::Path;
 
+use crate::cli::output::report_warning;
+use crate::core::config::get_package_options;
+use crate::core::fetch::FetchOptions;
 use crate::core::manifest::Manifest;
+use crate::core::package::PackageDefinition;
+use crate::core::package_test::{self, TestTarget};
+use crate::core::project::{BuildOptions, ProgressEvent, ZigrootProject};
+use crate::infra::dirs::ZigrootDirs;
+use crate::registry::cache::CachePolicy;
+use crate::registry::client::{CachedLookup, RegistryClient};
 
 /// Execute the package list command
 ///
@@ -57,7 +66,14 @@ pub async fn execute_list(project_dir: &Path) -> Result<()> {
 ///
 /// Displays detailed information about a specific package.
 /// **Validates: Requirement 2.11**
-pub async fn execute_info(project_dir: &Path, package_name: &str) -> Result<()> {
+///
+/// Registry metadata is shown from the cache when available, see
+/// [`CachePolicy`].
+pub async fn execute_info(
+    project_dir: &Path,
+    package_name: &str,
+    policy: CachePolicy,
+) -> Result<()> {
     let manifest_path = project_dir.join("zigroot.toml");
 
     if !manifest_path.exists() {
@@ -90,30 +106,15 @@ pub async fn execute_info(project_dir: &Path, package_name: &str) -> Result<()>
         println!("  Source: {}", source);
     }
 
-    // Description (from registry or local)
-    let description = get_package_description(package_name);
-    if !description.is_empty() {
-        println!("  Description: {}", description);
-    }
-
-    // License
-    let license = get_package_license(package_name);
-    if !license.is_empty() {
-        println!("  License: {}", license);
-    }
-
-    // Homepage
-    let homepage = get_package_homepage(package_name);
-    if !homepage.is_empty() {
-        println!("  Homepage: {}", homepage);
-    }
-
-    // Dependencies
-    let dependencies = get_package_dependencies(package_name);
-    if !dependencies.is_empty() {
-        println!("  Dependencies: {}", dependencies.join(", "));
+    // Description, license and dependencies from the registry
+    let local = project_dir.join("packages").join(package_name).exists();
+    let mut lookup = if pkg_ref.git.is_none() && !local {
+        registry_metadata(package_name, policy).await
     } else {
-        println!("  Dependencies: none");
+        None
+    };
+    if let Some(metadata) = lookup.as_ref().and_then(|l| l.data.get("package")) {
+        print_registry_metadata(metadata);
     }
 
     // Git info if applicable
@@ -129,17 +130,68 @@ pub async fn execute_info(project_dir: &Path, package_name: &str) -> Result<()>
         println!("  Registry: {}", registry);
     }
 
-    // Options if any
-    if !pkg_ref.options.is_empty() {
+    // Effective options with where each value came from
+    let options = get_package_options(project_dir, &manifest, package_name);
+    if !options.is_empty() {
         println!("  Options:");
-        for (key, value) in &pkg_ref.options {
-            println!("    {}: {}", key, value);
+        for (key, option) in &options {
+            println!("    {}: {} ({})", key, option.value, option.source.label());
+        }
+    }
+
+    if let Some(lookup) = &mut lookup {
+        println!();
+        println!("({})", lookup.status.footer());
+        if let Some(e) = lookup.finish_refresh().await {
+            report_warning(&format!(
+                "Could not refresh '{package_name}' from the registry, showing cached data: {e}"
+            ));
         }
     }
 
     Ok(())
 }
 
+/// Look up a package's registry metadata, warning when it is unavailable
+async fn registry_metadata(package_name: &str, policy: CachePolicy) -> Option<CachedLookup> {
+    RegistryClient::new()
+        .lookup_package_metadata(package_name, policy)
+        .await
+        .map_err(|e| {
+            report_warning(&format!(
+                "Registry metadata of '{package_name}' is unavailable: {e}"
+            ));
+        })
+        .ok()
+}
+
+/// Print the description, license, homepage and dependencies of a
+/// registry package's `[package]` table
+fn print_registry_metadata(metadata: &toml::Value) {
+    let field = |name: &str| metadata.get(name).and_then(|v| v.as_str());
+    for (label, name) in [
+        ("Description", "description"),
+        ("License", "license"),
+        ("Homepage", "homepage"),
+    ] {
+        if let Some(value) = field(name) {
+            println!("  {label}: {value}");
+        }
+    }
+
+    let dependencies: Vec<&str> = ["depends", "dependencies"]
+        .into_iter()
+        .filter_map(|key| metadata.get(key).and_then(|v| v.as_array()))
+        .flatten()
+        .filter_map(|v| v.as_str())
+        .collect();
+    if dependencies.is_empty() {
+        println!("  Dependencies: none");
+    } else {
+        println!("  Dependencies: {}", dependencies.join(", "));
+    }
+}
+
 /// Get source information for a package reference
 fn get_source_info(pkg_ref: &crate::core::manifest::PackageRef) -> String {
     if let Some(git) = &pkg_ref.git {
@@ -169,44 +221,6 @@ fn get_package_description(package_name: &str) -> String {
     }
 }
 
-/// Get package license from registry or local cache
-/// This is a placeholder - in a real implementation, this would query the registry
-fn get_package_license(package_name: &str) -> String {
-    match package_name {
-        "busybox" => "GPL-2.0".to_string(),
-        "dropbear" => "MIT".to_string(),
-        "nginx" => "BSD-2-Clause".to_string(),
-        "zlib" => "Zlib".to_string(),
-        "openssl" => "Apache-2.0".to_string(),
-        "curl" => "MIT".to_string(),
-        _ => String::new(),
-    }
-}
-
-/// Get package homepage from registry or local cache
-/// This is a placeholder - in a real implementation, this would query the registry
-fn get_package_homepage(package_name: &str) -> String {
-    match package_name {
-        "busybox" => "https://busybox.net".to_string(),
-        "dropbear" => "https://matt.ucc.asn.au/dropbear/dropbear.html".to_string(),
-        "nginx" => "https://nginx.org".to_string(),
-        "zlib" => "https://zlib.net".to_string(),
-        "openssl" => "https://www.openssl.org".to_string(),
-        "curl" => "https://curl.se".to_string(),
-        _ => String::new(),
-    }
-}
-
-/// Get package dependencies from registry or local cache
-/// This is a placeholder - in a real implementation, this would query the registry
-fn get_package_dependencies(package_name: &str) -> Vec<String> {
-    match package_name {
-        "nginx" => vec!["zlib".to_string(), "openssl".to_string()],
-        "curl" => vec!["zlib".to_string(), "openssl".to_string()],
-        _ => vec![],
-    }
-}
-
 /// Execute the package new command
 ///
 /// Creates a new package template in packages/<name>/ with metadata.toml and version file.
@@ -298,6 +312,7 @@ sha256 = "0000000000000000000000000000000000000000000000000000000000000000"
 # Version-specific dependencies (optional)
 # [dependencies]
 # depends = ["zlib"]
+# build_depends = []
 # requires = []
 "#
     )
@@ -305,40 +320,157 @@ sha256 = "0000000000000000000000000000000000000000000000000000000000000000"
 
 /// Execute the package test command
 ///
-/// Attempts to build a package and reports success or failure.
+/// Builds the package in a throwaway project per target: each `boards`
+/// entry, and each target triple in `targets`. Without either, the current
+/// project's board is used, or `x86_64-linux-musl` outside a project.
+/// Prints a summary table and fails if any target failed; with `keep`, the
+/// scratch projects are kept for debugging.
 /// **Validates: Requirement 28.6**
-pub async fn execute_test(project_dir: &Path, path: &str) -> Result<()> {
+pub async fn execute_test(
+    project_dir: &Path,
+    path: &str,
+    boards: &[String],
+    targets: &[String],
+    keep: bool,
+) -> Result<()> {
     let pkg_path = project_dir.join(path);
 
     if !pkg_path.exists() {
         anyhow::bail!("Package path '{}' does not exist", path);
     }
+    let definition = package_test::package_definition(&pkg_path)?;
+    let name = &definition.package.name;
+
+    let mut matrix: Vec<TestTarget> = boards
+        .iter()
+        .map(|board| TestTarget::Board(board.clone()))
+        .chain(
+            targets
+                .iter()
+                .map(|triple| TestTarget::Triple(triple.clone())),
+        )
+        .collect();
+    if matrix.is_empty() {
+        matrix.push(default_test_target(project_dir));
+    }
 
-    // Check for required files
-    let metadata_path = pkg_path.join("metadata.toml");
-    if !metadata_path.exists() {
-        anyhow::bail!(
-            "No metadata.toml found in '{}'. Is this a valid package directory?",
-            path
-        );
+    let scratch = std::env::temp_dir().join(format!(
+        "zigroot-package-test-{name}-{}",
+        std::process::id()
+    ));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
     }
 
-    println!("Testing package at '{}'...", path);
-    println!();
+    println!("Testing package '{name}' {}...", definition.package.version);
+    let mut results = Vec::new();
+    for target in &matrix {
+        println!();
+        println!("▶ Building for {target}");
+        let dir = scratch.join(target.dir_name());
+        let outcome = test_target(project_dir, &pkg_path, &definition, target, &dir).await;
+        match &outcome {
+            Ok(()) => println!("  ✓ {target} passed"),
+            Err(e) => println!("  ✗ {target} failed: {e:#}"),
+        }
+        results.push((target, outcome));
+    }
 
-    // For now, just validate the package structure
-    // A full implementation would attempt to build the package
-    println!("✓ Package structure is valid");
     println!();
-    println!("Note: Full build testing requires a configured project.");
-    println!(
-        "Run 'zigroot build --package {}' in a project to test the build.",
-        path
-    );
+    println!("  {:<36} Result", "Target");
+    for (target, outcome) in &results {
+        let result = match outcome {
+            Ok(()) => "✓ pass".to_string(),
+            Err(e) => format!(
+                "✗ fail: {}",
+                e.to_string().lines().next().unwrap_or_default()
+            ),
+        };
+        println!("  {:<36} {result}", target.to_string());
+    }
+
+    if keep {
+        println!();
+        println!("Scratch projects kept in {}", scratch.display());
+    } else if let Err(e) = std::fs::remove_dir_all(&scratch) {
+        report_warning(&format!(
+            "Failed to remove scratch projects in {}: {e}",
+            scratch.display()
+        ));
+    }
 
+    let failed = results
+        .iter()
+        .filter(|(_, outcome)| outcome.is_err())
+        .count();
+    if failed > 0 {
+        anyhow::bail!(
+            "Package '{name}' failed on {failed} of {} target(s)",
+            results.len()
+        );
+    }
+    let digest = package_test::package_digest(&pkg_path)?;
+    package_test::record_tested(&ZigrootDirs::new().data_dir(), &digest, &matrix)?;
     Ok(())
 }
 
+/// Target of a package test without `--board` or `--targets`
+fn default_test_target(project_dir: &Path) -> TestTarget {
+    std::fs::read_to_string(project_dir.join("zigroot.toml"))
+        .ok()
+        .and_then(|content| Manifest::from_toml(&content).ok())
+        .and_then(|manifest| manifest.board.name)
+        .map_or_else(
+            || TestTarget::Triple("x86_64-linux-musl".to_string()),
+            TestTarget::Board,
+        )
+}
+
+/// Fetch and build the package for one target in a scratch project at `dir`
+async fn test_target(
+    project_dir: &Path,
+    pkg_path: &Path,
+    definition: &PackageDefinition,
+    target: &TestTarget,
+    dir: &Path,
+) -> Result<()> {
+    let (board_name, board_toml) = match target {
+        TestTarget::Board(board) => (board.as_str(), board_definition(project_dir, board).await?),
+        TestTarget::Triple(triple) => (
+            package_test::TRIPLE_BOARD,
+            package_test::placeholder_board(triple),
+        ),
+    };
+    package_test::create_scratch_project(dir, pkg_path, definition, board_name, &board_toml)?;
+
+    let project = ZigrootProject::open(dir)?;
+    let fetched = project.fetch(&FetchOptions::default(), None).await?;
+    if let Some((item, error)) = fetched.failed.first() {
+        anyhow::bail!("Failed to fetch {item}: {error}");
+    }
+    project
+        .build(&BuildOptions::default(), &mut |event| {
+            if let ProgressEvent::Warning(message) = event {
+                report_warning(&message);
+            }
+        })
+        .await?;
+    Ok(())
+}
+
+/// Board definition from the project's `boards/` or the board registry
+async fn board_definition(project_dir: &Path, board: &str) -> Result<String> {
+    let local = project_dir.join("boards").join(board).join("board.toml");
+    if local.is_file() {
+        return Ok(std::fs::read_to_string(local)?);
+    }
+    let definition = RegistryClient::new()
+        .fetch_board(board)
+        .await
+        .map_err(|e| anyhow::anyhow!("Board '{board}' not found: {e}"))?;
+    Ok(toml::to_string(&definition)?)
+}
+
 /// Execute the package bump command
 ///
 /// Creates a new version file from the latest version.
@@ -469,10 +601,4 @@ mod tests {
         assert!(!get_package_description("busybox").is_empty());
         assert!(get_package_description("unknown-pkg").is_empty());
     }
-
-    #[test]
-    fn test_get_package_license() {
-        assert_eq!(get_package_license("busybox"), "GPL-2.0");
-        assert!(get_package_license("unknown-pkg").is_empty());
-    }
 }
@@ -1,17 +1,41 @@
 //! Publish command implementation
 //!
 //! Implements `zigroot publish` for publishing packages and boards to registries.
+//! Pre-flight checks run before anything is uploaded; `--dry-run` runs only
+//! those. Packages that have not passed `zigroot package test` since they
+//! changed are offered a test run first. With a signing key, the definition is signed before it is
+//! uploaded.
 //!
 //! **Validates: Requirements 28.7-28.11, 29.5-29.8**
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::io::Write;
 use std::path::Path;
 
+use crate::cli::commands::{package, verify};
+use crate::cli::output::{is_json, print_warning, report_warning, take_warnings};
+use crate::core::config::is_terminal_interactive;
+use crate::core::global_config::GlobalConfig;
+use crate::core::package_test;
+use crate::core::signing::{SigningKey, SIGNATURE_FILE};
+use crate::infra::dirs::ZigrootDirs;
+use crate::registry::client::{default_cache_dir, RegistryClient};
+
+/// License values that do not name a license
+const UNKNOWN_LICENSES: &[&str] = &["UNKNOWN", "NOASSERTION", "NONE"];
+
 /// Execute the publish command
 ///
-/// Creates a PR to the appropriate registry (zigroot-packages or zigroot-boards).
+/// Creates a PR to the appropriate registry (zigroot-packages or zigroot-boards)
+/// after pre-flight checks pass. With `dry_run`, only the checks are run.
+/// The definition is signed with `signing_key` when one is given.
 /// **Validates: Requirements 28.7-28.11, 29.5-29.8**
-pub async fn execute(project_dir: &Path, path: &str) -> Result<()> {
+pub async fn execute(
+    project_dir: &Path,
+    path: &str,
+    dry_run: bool,
+    signing_key: Option<&Path>,
+) -> Result<()> {
     let full_path = project_dir.join(path);
 
     // Check if path exists
@@ -23,10 +47,15 @@ pub async fn execute(project_dir: &Path, path: &str) -> Result<()> {
     let is_package = full_path.join("metadata.toml").exists();
     let is_board = full_path.join("board.toml").exists();
 
+    let key = signing_key
+        .map(|path| SigningKey::load(&project_dir.join(path)))
+        .transpose()?;
+
     if is_package {
-        publish_package(&full_path, path).await
+        ensure_tested(project_dir, path, dry_run).await?;
+        publish_package(&full_path, dry_run, key.as_ref()).await
     } else if is_board {
-        publish_board(&full_path, path).await
+        publish_board(&full_path, dry_run, key.as_ref()).await
     } else {
         anyhow::bail!(
             "Cannot determine type of '{}'. Expected metadata.toml (package) or board.toml (board)",
@@ -35,28 +64,55 @@ pub async fn execute(project_dir: &Path, path: &str) -> Result<()> {
     }
 }
 
+/// What a publish would add to the registry
+struct PublishPlan {
+    /// "package" or "board"
+    kind: &'static str,
+    /// Package or board name
+    name: String,
+    /// Versions not yet in the registry (packages only)
+    versions: Vec<String>,
+    /// Declared license (packages only)
+    license: Option<String>,
+    /// Whether the registry already has this package or board
+    exists: bool,
+    /// Registry repository receiving the PR
+    repository: &'static str,
+}
+
 /// Publish a package to the registry
-async fn publish_package(pkg_path: &Path, _path: &str) -> Result<()> {
+async fn publish_package(pkg_path: &Path, dry_run: bool, key: Option<&SigningKey>) -> Result<()> {
     let pkg_name = pkg_path
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("unknown");
 
-    println!("Publishing package '{}'...", pkg_name);
+    if !is_json() {
+        let action = if dry_run { "Checking" } else { "Publishing" };
+        println!("{action} package '{pkg_name}'...");
+    }
 
     // Validate package first
     validate_package(pkg_path, pkg_name)?;
 
-    // Check for GitHub authentication
-    let token = get_github_token()?;
+    // Authentication is needed to upload, not to check
+    let token = authenticate(dry_run)?;
+
+    let plan = preflight_package(pkg_path, pkg_name).await?;
+    if dry_run {
+        print_dry_run(&plan, token.is_some(), key);
+        return Ok(());
+    }
+    let token = token.unwrap_or_default();
 
     println!("  ✓ Package validation passed");
     println!("  ✓ GitHub authentication found");
+    sign_definition(pkg_path, key)?;
 
     // In a real implementation, this would:
     // 1. Fork the zigroot-packages repo (if needed)
     // 2. Create a branch
-    // 3. Copy package files
+    // 3. Copy package files, including the signature
     // 4. Create a PR
 
     println!();
@@ -69,28 +125,66 @@ async fn publish_package(pkg_path: &Path, _path: &str) -> Result<()> {
     Ok(())
 }
 
+/// Offer to run `zigroot package test` on a package that has not passed
+/// it since it last changed
+///
+/// Only interactive publishes ask; otherwise a warning is printed. A
+/// failing test stops the publish.
+async fn ensure_tested(project_dir: &Path, path: &str, dry_run: bool) -> Result<()> {
+    let digest = package_test::package_digest(&project_dir.join(path))?;
+    if package_test::is_tested(&ZigrootDirs::new().data_dir(), &digest) {
+        return Ok(());
+    }
+    let untested = format!("Package has not passed 'zigroot package test {path}' since it changed");
+    if dry_run || is_json() || !is_terminal_interactive() {
+        report_warning(&untested);
+        return Ok(());
+    }
+
+    eprint!("{untested}. Run it now? [Y/n] ");
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if matches!(answer.trim().to_lowercase().as_str(), "" | "y" | "yes") {
+        package::execute_test(project_dir, path, &[], &[], false).await?;
+        println!();
+    }
+    Ok(())
+}
+
 /// Publish a board to the registry
-async fn publish_board(board_path: &Path, _path: &str) -> Result<()> {
+async fn publish_board(board_path: &Path, dry_run: bool, key: Option<&SigningKey>) -> Result<()> {
     let board_name = board_path
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("unknown");
 
-    println!("Publishing board '{}'...", board_name);
+    if !is_json() {
+        let action = if dry_run { "Checking" } else { "Publishing" };
+        println!("{action} board '{board_name}'...");
+    }
 
     // Validate board first
     validate_board(board_path, board_name)?;
 
-    // Check for GitHub authentication
-    let token = get_github_token()?;
+    // Authentication is needed to upload, not to check
+    let token = authenticate(dry_run)?;
+
+    let plan = preflight_board(board_path, board_name).await?;
+    if dry_run {
+        print_dry_run(&plan, token.is_some(), key);
+        return Ok(());
+    }
+    let token = token.unwrap_or_default();
 
     println!("  ✓ Board validation passed");
     println!("  ✓ GitHub authentication found");
+    sign_definition(board_path, key)?;
 
     // In a real implementation, this would:
     // 1. Fork the zigroot-boards repo (if needed)
     // 2. Create a branch
-    // 3. Copy board files
+    // 3. Copy board files, including the signature
     // 4. Create a PR
 
     println!();
@@ -103,6 +197,200 @@ async fn publish_board(board_path: &Path, _path: &str) -> Result<()> {
     Ok(())
 }
 
+/// Sign a definition, or warn that it is published unsigned
+fn sign_definition(path: &Path, key: Option<&SigningKey>) -> Result<()> {
+    let Some(key) = key else {
+        print_warning(
+            "Publishing an unsigned definition. Pass --signing-key or set ZIGROOT_SIGNING_KEY to sign it.",
+        );
+        return Ok(());
+    };
+    key.sign_definition(path)?;
+    println!(
+        "  ✓ Signed definition ({SIGNATURE_FILE}, public key {})",
+        key.public_key()
+    );
+    Ok(())
+}
+
+/// GitHub token, required unless this is a dry run
+fn authenticate(dry_run: bool) -> Result<Option<String>> {
+    if dry_run {
+        Ok(get_github_token().ok())
+    } else {
+        get_github_token().map(Some)
+    }
+}
+
+/// Run the pre-flight checks of a package
+///
+/// The same checks as `zigroot verify --fetch`, plus the license and a
+/// registry query for versions that are already published.
+async fn preflight_package(pkg_path: &Path, pkg_name: &str) -> Result<PublishPlan> {
+    let sources = verify::check_package(pkg_path, true).await?;
+    verify::check_mirrors(pkg_name, &sources)?;
+    let license = check_license(pkg_path, pkg_name)?;
+
+    let versions = package_versions(pkg_path)?;
+    let index = registry_client()?
+        .fetch_package_index()
+        .await
+        .with_context(|| "Failed to query the package registry")?;
+    let published = index.packages.iter().find(|p| p.name == pkg_name).map(|p| {
+        p.versions
+            .iter()
+            .map(|v| v.version.clone())
+            .collect::<Vec<_>>()
+    });
+    let new_versions: Vec<String> = versions
+        .iter()
+        .filter(|v| !published.iter().flatten().any(|p| p == *v))
+        .cloned()
+        .collect();
+    if new_versions.is_empty() {
+        anyhow::bail!(
+            "Package '{}' version {} is already published. Add a new version file before publishing.",
+            pkg_name,
+            versions.join(", ")
+        );
+    }
+
+    Ok(PublishPlan {
+        kind: "package",
+        name: pkg_name.to_string(),
+        versions: new_versions,
+        license: Some(license),
+        exists: published.is_some(),
+        repository: "zigroot-project/zigroot-packages",
+    })
+}
+
+/// Run the pre-flight checks of a board
+async fn preflight_board(board_path: &Path, board_name: &str) -> Result<PublishPlan> {
+    verify::check_board(board_path)?;
+    let index = registry_client()?
+        .fetch_board_index()
+        .await
+        .with_context(|| "Failed to query the board registry")?;
+
+    Ok(PublishPlan {
+        kind: "board",
+        name: board_name.to_string(),
+        versions: Vec::new(),
+        license: None,
+        exists: index.boards.iter().any(|b| b.name == board_name),
+        repository: "zigroot-project/zigroot-boards",
+    })
+}
+
+/// Registry client for pre-flight queries
+///
+/// Uses the registries of the global configuration and ignores the cache
+/// TTL, since the checks need the registry's current state.
+fn registry_client() -> Result<RegistryClient> {
+    let config = GlobalConfig::load(&ZigrootDirs::new())?;
+    Ok(RegistryClient::with_config(
+        config.packages_url().to_string(),
+        config.boards_url().to_string(),
+        default_cache_dir(),
+        0,
+    ))
+}
+
+/// Print the summary of a dry run
+fn print_dry_run(plan: &PublishPlan, authenticated: bool, key: Option<&SigningKey>) {
+    let public_key = key.map(SigningKey::public_key);
+    if is_json() {
+        let output = serde_json::json!({
+            "dry_run": true,
+            "type": plan.kind,
+            "name": plan.name,
+            "versions": plan.versions,
+            "license": plan.license,
+            "exists": plan.exists,
+            "repository": plan.repository,
+            "authenticated": authenticated,
+            "signed": public_key.is_some(),
+            "public_key": public_key,
+            "warnings": take_warnings(),
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output).unwrap_or_default()
+        );
+        return;
+    }
+
+    println!();
+    println!("✓ Pre-flight checks passed");
+    let what = if plan.versions.is_empty() {
+        format!("{} '{}'", plan.kind, plan.name)
+    } else {
+        format!(
+            "{} '{}' version {}",
+            plan.kind,
+            plan.name,
+            plan.versions.join(", ")
+        )
+    };
+    let change = if plan.exists { "update" } else { "new entry" };
+    println!("Would publish {what} to {} ({change})", plan.repository);
+    if let Some(ref license) = plan.license {
+        println!("  License: {license}");
+    }
+    match public_key {
+        Some(public_key) => println!("  Signed with public key {public_key}"),
+        None => println!("  ⚠ Not signed; pass --signing-key or set ZIGROOT_SIGNING_KEY"),
+    }
+    if !authenticated {
+        println!("  ⚠ GitHub authentication not found; set GITHUB_TOKEN or run 'gh auth login' before publishing");
+    }
+}
+
+/// Versions declared by a package's version files, sorted
+fn package_versions(pkg_path: &Path) -> Result<Vec<String>> {
+    let mut versions = Vec::new();
+    for entry in std::fs::read_dir(pkg_path)? {
+        let path = entry?.path();
+        let is_version_file = path.extension().is_some_and(|e| e == "toml")
+            && path.file_name().is_some_and(|n| n != "metadata.toml");
+        if !is_version_file {
+            continue;
+        }
+        let version: toml::Value = toml::from_str(&std::fs::read_to_string(&path)?)?;
+        if let Some(v) = version
+            .get("release")
+            .and_then(|r| r.get("version"))
+            .or_else(|| version.get("version"))
+            .and_then(|v| v.as_str())
+        {
+            versions.push(v.to_string());
+        }
+    }
+    versions.sort();
+    Ok(versions)
+}
+
+/// Check that a package declares a license, returning it
+fn check_license(pkg_path: &Path, pkg_name: &str) -> Result<String> {
+    let metadata: toml::Value =
+        toml::from_str(&std::fs::read_to_string(pkg_path.join("metadata.toml"))?)?;
+    let license = metadata
+        .get("package")
+        .and_then(|p| p.get("license"))
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .trim();
+    if license.is_empty() || UNKNOWN_LICENSES.contains(&license.to_uppercase().as_str()) {
+        anyhow::bail!(
+            "Package '{}' must declare its license as an SPDX identifier, not '{}'",
+            pkg_name,
+            license
+        );
+    }
+    Ok(license.to_string())
+}
+
 /// Validate a package before publishing
 fn validate_package(pkg_path: &Path, pkg_name: &str) -> Result<()> {
     // Check for metadata.toml
@@ -279,6 +567,33 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("metadata.toml"));
     }
 
+    #[test]
+    fn test_check_license_rejects_missing_license() {
+        let dir = TempDir::new().unwrap();
+        let metadata = dir.path().join("metadata.toml");
+        std::fs::write(&metadata, "[package]\nlicense = \"NOASSERTION\"\n").unwrap();
+        assert!(check_license(dir.path(), "test-pkg").is_err());
+
+        std::fs::write(&metadata, "[package]\nlicense = \"MIT\"\n").unwrap();
+        assert_eq!(check_license(dir.path(), "test-pkg").unwrap(), "MIT");
+    }
+
+    #[test]
+    fn test_package_versions_reads_version_files() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("metadata.toml"), "[package]\n").unwrap();
+        std::fs::write(
+            dir.path().join("1.1.0.toml"),
+            "[release]\nversion = \"1.1.0\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("1.0.0.toml"), "version = \"1.0.0\"\n").unwrap();
+        assert_eq!(
+            package_versions(dir.path()).unwrap(),
+            vec!["1.0.0", "1.1.0"]
+        );
+    }
+
     #[test]
     fn test_validate_board_missing_board_toml() {
         let dir = TempDir::new().unwrap();
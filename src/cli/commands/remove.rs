@@ -6,10 +6,11 @@ use std::path::Path;
 
 use anyhow::{Context, Result};
 
-use crate::core::remove::remove_package;
+use crate::cli::output::print_warning;
+use crate::core::remove::{remove_package, RemoveOptions};
 
 /// Execute the remove command
-pub async fn execute(path: &Path, package: &str) -> Result<()> {
+pub async fn execute(path: &Path, package: &str, force: bool, cascade: bool) -> Result<()> {
     // Check if manifest exists
     let manifest_path = path.join("zigroot.toml");
     if !manifest_path.exists() {
@@ -19,7 +20,8 @@ pub async fn execute(path: &Path, package: &str) -> Result<()> {
         );
     }
 
-    let result = remove_package(path, package)
+    let options = RemoveOptions { force, cascade };
+    let result = remove_package(path, package, &options)
         .with_context(|| format!("Failed to remove package '{package}'"))?;
 
     // Print success message
@@ -28,6 +30,20 @@ pub async fn execute(path: &Path, package: &str) -> Result<()> {
     } else {
         println!("✓ Removed {}", result.package_name);
     }
+    for dep in &result.dependencies_removed {
+        match &dep.version {
+            Some(version) => println!("✓ Removed {} v{version} (no longer needed)", dep.name),
+            None => println!("✓ Removed {} (no longer needed)", dep.name),
+        }
+    }
+
+    if !result.broken_dependents.is_empty() {
+        print_warning(&format!(
+            "{} is still required by: {}",
+            result.package_name,
+            result.broken_dependents.join(", ")
+        ));
+    }
 
     if result.lock_updated {
         println!("  Updated zigroot.lock");
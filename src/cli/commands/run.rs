@@ -0,0 +1,94 @@
+//! CLI command implementation for `zigroot run`
+//!
+//! Boots the built image in QEMU using the board's `[qemu]` section, with
+//! the serial console on the current terminal.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::Path;
+
+use crate::cli::output::{print_detail, print_info, print_warning};
+use crate::core::flash::load_board_definition;
+use crate::core::manifest::Manifest;
+use crate::core::qemu::{self, RunOptions, SSH_PACKAGES};
+
+/// Execute the run command
+pub async fn execute(
+    project_dir: &Path,
+    graphic: bool,
+    ssh_port: Option<u16>,
+    qemu_args: Vec<String>,
+) -> Result<()> {
+    let manifest_path = project_dir.join("zigroot.toml");
+    if !manifest_path.exists() {
+        bail!("No zigroot.toml found. Run 'zigroot init' to create a project.");
+    }
+    let manifest_content = std::fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read manifest: {}", manifest_path.display()))?;
+    let manifest =
+        Manifest::from_toml(&manifest_content).with_context(|| "Failed to parse zigroot.toml")?;
+
+    let Some(board_name) = manifest.board.name.as_deref() else {
+        bail!("No board configured. Set one with 'zigroot board set <name>'.");
+    };
+    let board = load_board_definition(project_dir, board_name)?;
+    let target = &board.board.target;
+    let Some(config) = &board.qemu else {
+        bail!(
+            "Board '{board_name}' has no [qemu] section. Add one to boards/{board_name}/board.toml, for example:\n\n{}",
+            qemu::example_section(target)
+        );
+    };
+    let binary = qemu::qemu_binary(config, target).with_context(|| {
+        format!("No QEMU emulator is known for target '{target}'. Set qemu.binary in the board definition.")
+    })?;
+
+    let ssh_port = match ssh_port {
+        Some(_) if !qemu::has_ssh_package(&manifest) => {
+            print_warning(&format!(
+                "Ignoring --ssh-port: the project has no SSH server package ({})",
+                SSH_PACKAGES.join(", ")
+            ));
+            None
+        }
+        port => port,
+    };
+
+    let inputs = qemu::resolve_inputs(project_dir, &manifest, config)?;
+    let options = RunOptions {
+        graphic,
+        ssh_port,
+        extra_args: qemu_args,
+    };
+    let args = qemu::build_args(
+        config,
+        qemu::attach_mode(config, &manifest),
+        &inputs,
+        &options,
+    )?;
+
+    print_info(&format!("Running {binary} {}", args.join(" ")));
+    if let Some(port) = ssh_port {
+        print_detail(&format!("SSH: ssh -p {port} root@localhost"));
+    }
+    if !graphic {
+        print_detail("Press Ctrl-A X to quit QEMU");
+    }
+
+    let status = tokio::process::Command::new(&binary)
+        .args(&args)
+        .status()
+        .await
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                anyhow!(
+                    "{binary} not found. Install QEMU or set qemu.binary in the board definition. Run 'zigroot doctor' for details."
+                )
+            } else {
+                anyhow!("Failed to run {binary}: {e}")
+            }
+        })?;
+    if !status.success() {
+        bail!("{binary} exited with {status}");
+    }
+    Ok(())
+}
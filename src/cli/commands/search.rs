@@ -4,31 +4,45 @@
 //!
 //! **Validates: Requirements 10.1-10.9**
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use std::path::Path;
 
-use crate::core::search::{self, SearchOptions, SearchResultType};
+use crate::cli::output::use_color;
+use crate::core::lock::LockFile;
+use crate::core::manifest::Manifest;
+use crate::core::search::{self, InstalledItems, SearchOptions, SearchResultType};
 use crate::registry::client::RegistryClient;
 
 /// Execute the search command
+///
+/// Inside a project, results in its `zigroot.toml` are marked as installed;
+/// with `installed`, only those are shown.
 pub async fn execute(
+    project_dir: &Path,
     query: &str,
-    packages_only: bool,
-    boards_only: bool,
-    refresh: bool,
+    options: SearchOptions,
+    installed: bool,
 ) -> Result<()> {
-    let client = RegistryClient::new();
+    let project = load_installed(project_dir)?;
+    if installed && project.is_none() {
+        bail!("No zigroot.toml found. --installed only works inside a project.");
+    }
 
-    let options = SearchOptions {
-        packages_only,
-        boards_only,
-        refresh,
-    };
+    let client = RegistryClient::new();
 
     tracing::info!("Searching for '{}'...", query);
 
-    let results = search::search(&client, query, &options)
+    let mut results = search::search(&client, query, &options)
         .await
         .map_err(|e| anyhow::anyhow!("{}", e))?;
+    if let Some(project) = &project {
+        results.mark_installed(project, installed);
+    }
+
+    if results.is_empty() && installed {
+        println!("No packages or boards in this project match '{query}'");
+        return Ok(());
+    }
 
     if results.is_empty() {
         println!("No results found for '{}'", query);
@@ -81,6 +95,27 @@ pub async fn execute(
     Ok(())
 }
 
+/// Packages and board of the project in `project_dir`, if there is one
+fn load_installed(project_dir: &Path) -> Result<Option<InstalledItems>> {
+    let manifest_path = project_dir.join("zigroot.toml");
+    if !manifest_path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read manifest: {}", manifest_path.display()))?;
+    let manifest = Manifest::from_toml(&content).with_context(|| "Failed to parse zigroot.toml")?;
+    let lock_path = project_dir.join("zigroot.lock");
+    let lock_file = if lock_path.exists() {
+        Some(LockFile::load(&lock_path).with_context(|| "Failed to read zigroot.lock")?)
+    } else {
+        None
+    };
+    Ok(Some(InstalledItems::from_project(
+        &manifest,
+        lock_file.as_ref(),
+    )))
+}
+
 /// Display a single search result with highlighting
 fn display_result(result: &search::SearchResult, query: &str) {
     let type_label = match result.result_type {
@@ -88,8 +123,12 @@ fn display_result(result: &search::SearchResult, query: &str) {
         SearchResultType::Board => "[board]",
     };
 
-    // Highlight the query in the name if present
-    let highlighted_name = highlight_match(&result.name, query);
+    // Highlight the query in the name if present and colors are enabled
+    let highlighted_name = if use_color() {
+        highlight_match(&result.name, query)
+    } else {
+        result.name.clone()
+    };
 
     // Format version/arch info
     let version_info = match result.result_type {
@@ -97,9 +136,15 @@ fn display_result(result: &search::SearchResult, query: &str) {
         SearchResultType::Board => result.version_or_arch.clone(),
     };
 
+    let installed = match (&result.installed, &result.pinned_version) {
+        (false, _) => String::new(),
+        (true, Some(version)) => format!(" (installed, pinned {version})"),
+        (true, None) => " (installed)".to_string(),
+    };
+
     // Print the result
     println!(
-        "  {} {} {} - {}",
+        "  {} {} {} - {}{installed}",
         type_label, highlighted_name, version_info, result.description
     );
 
@@ -0,0 +1,120 @@
+//! CLI command for `zigroot stats`
+//!
+//! Summarizes the local build statistics. Inside a project only its builds
+//! are shown; elsewhere all recorded builds are.
+
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+
+use crate::cli::output::{format_duration, is_json, print_success, print_warning};
+use crate::core::manifest::Manifest;
+use crate::core::size::format_bytes;
+use crate::core::stats::{self, BuildRecord, Summary, STATS_FILE};
+use crate::infra::dirs::ZigrootDirs;
+
+/// Execute the stats command
+pub async fn execute(project_dir: &Path, last: usize, clear: bool) -> Result<()> {
+    let path = ZigrootDirs::new().data_dir().join(STATS_FILE);
+    if clear {
+        let existed =
+            stats::clear(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        if existed {
+            print_success("Cleared build statistics");
+        } else {
+            println!("No build statistics recorded");
+        }
+        return Ok(());
+    }
+
+    let (mut records, warnings) =
+        stats::load(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let project = project_name(project_dir);
+    if let Some(ref name) = project {
+        let hash = stats::project_hash(name);
+        records.retain(|record| record.project == hash);
+    }
+    let summary = Summary::new(&records, last);
+
+    if is_json() {
+        let output = serde_json::json!({
+            "project": project,
+            "records": records,
+            "summary": summary,
+            "warnings": warnings,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    for warning in &warnings {
+        print_warning(warning);
+    }
+    print_summary(project.as_deref(), &records, &summary);
+    Ok(())
+}
+
+/// Name of the project in `project_dir`, if there is one
+fn project_name(project_dir: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(project_dir.join("zigroot.toml")).ok()?;
+    Manifest::from_toml(&content)
+        .ok()
+        .map(|manifest| manifest.project.name)
+}
+
+fn print_summary(project: Option<&str>, records: &[BuildRecord], summary: &Summary) {
+    let scope = project.map_or_else(|| "all projects".to_string(), |p| format!("'{p}'"));
+    if records.is_empty() {
+        println!("No builds recorded for {scope}");
+        return;
+    }
+
+    println!("📊 Build statistics for {scope}\n");
+    println!("Builds: {} ({} failed)", summary.builds, summary.failures);
+    if let Some(secs) = summary.average_secs {
+        println!(
+            "Average build time: {}",
+            format_duration(Duration::from_secs_f64(secs))
+        );
+    }
+
+    println!(
+        "\nCache hits (last {} builds):",
+        summary.cache_hit_trend.len()
+    );
+    for ratio in &summary.cache_hit_trend {
+        println!("  {:>5.1}%", ratio * 100.0);
+    }
+
+    if !summary.slowest_packages.is_empty() {
+        println!("\nSlowest packages:");
+        for package in &summary.slowest_packages {
+            println!(
+                "  {:<20} {} ({} rebuilds)",
+                package.name,
+                format_duration(Duration::from_secs_f64(package.average_secs)),
+                package.rebuilds
+            );
+        }
+    }
+
+    if !summary.image_sizes.is_empty() {
+        println!("\nImage size:");
+        for size in &summary.image_sizes {
+            println!(
+                "  {:>10}  {}",
+                format_age(size.timestamp),
+                format_bytes(size.bytes)
+            );
+        }
+    }
+}
+
+/// How long ago a timestamp was, e.g. "3h 05m ago"
+fn format_age(timestamp: u64) -> String {
+    let age = SystemTime::now()
+        .duration_since(UNIX_EPOCH + Duration::from_secs(timestamp))
+        .unwrap_or_default();
+    format!("{} ago", format_duration(age))
+}
@@ -9,8 +9,17 @@ use anyhow::Result;
 use crate::core::tree;
 
 /// Execute the tree command
-pub async fn execute(project_dir: &Path, package: Option<String>, graph: bool) -> Result<()> {
-    let output = tree::display_tree(project_dir, package.as_deref(), graph)?;
+pub async fn execute(
+    project_dir: &Path,
+    package: Option<String>,
+    graph: bool,
+    build_deps: bool,
+    why: Option<String>,
+) -> Result<()> {
+    let output = match why {
+        Some(why) => tree::display_why(project_dir, &why, graph)?,
+        None => tree::display_tree(project_dir, package.as_deref(), graph, build_deps)?,
+    };
     println!("{output}");
     Ok(())
 }
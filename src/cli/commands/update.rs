@@ -9,7 +9,7 @@ use anyhow::{Context, Result};
 
 use crate::core::update::update_packages;
 use crate::core::version::{
-    check_for_updates, detect_install_method, format_update_result, UpdateCheckResult,
+    check_for_updates_cached, detect_install_method, format_update_result, UpdateCheckResult,
 };
 
 /// Execute the update command for packages
@@ -62,10 +62,12 @@ pub async fn execute(path: &Path, package: Option<String>) -> Result<()> {
 }
 
 /// Execute the self-update command (zigroot update --self)
-pub async fn execute_self_update() -> Result<()> {
+///
+/// With `offline`, GitHub is not contacted and the check is reported as failed.
+pub async fn execute_self_update(offline: bool) -> Result<()> {
     println!("Checking for zigroot updates...\n");
 
-    let result = check_for_updates().await;
+    let result = check_for_updates_cached(offline).await;
     let install_method = detect_install_method();
 
     let output = format_update_result(&result, &install_method);
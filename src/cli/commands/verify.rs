@@ -1,12 +1,24 @@
 //! Verify command implementation
 //!
 //! Implements `zigroot verify` for validating package and board definitions.
+//! With `--fetch`, each package source is downloaded from every declared
+//! mirror so that a mirror serving drifted bytes is caught. When a public key
+//! is given or configured, the definition's signature is checked as well.
+//! Packages are also linted for common mistakes, see [`crate::core::verify`].
 //!
 //! **Validates: Requirements 28.2-28.5, 29.2-29.4**
 
 use anyhow::Result;
 use std::path::Path;
 
+use crate::cli::output::is_json;
+use crate::core::global_config::GlobalConfig;
+use crate::core::signing::PublicKey;
+use crate::core::verify::{lint_dependencies, lint_package, LintFinding, LintSeverity};
+use crate::infra::dirs::ZigrootDirs;
+use crate::infra::download::{Checksum, DownloadManager};
+use crate::registry::client::{default_cache_dir, RegistryClient};
+
 /// Known valid Zig target triples
 const VALID_ZIG_TARGETS: &[&str] = &[
     "arm-linux-musleabihf",
@@ -25,11 +37,26 @@ const VALID_ZIG_TARGETS: &[&str] = &[
     "powerpc64-linux-musl",
 ];
 
+/// How package lints are run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LintOptions {
+    /// Fail on warnings as well as errors
+    pub deny_warnings: bool,
+    /// Check dependencies against the registry index
+    pub network: bool,
+}
+
 /// Execute the verify command
 ///
 /// Validates package or board structure, required fields, and TOML syntax.
 /// **Validates: Requirements 28.2-28.5, 29.2-29.4**
-pub async fn execute(project_dir: &Path, path: &str, fetch: bool) -> Result<()> {
+pub async fn execute(
+    project_dir: &Path,
+    path: &str,
+    fetch: bool,
+    public_key: Option<&Path>,
+    lints: LintOptions,
+) -> Result<()> {
     let full_path = project_dir.join(path);
 
     // Check if path exists
@@ -37,6 +64,8 @@ pub async fn execute(project_dir: &Path, path: &str, fetch: bool) -> Result<()>
         anyhow::bail!("Path '{}' does not exist", path);
     }
 
+    verify_signature(project_dir, &full_path, public_key)?;
+
     // Detect if this is a package or board
     let is_package = full_path.join("metadata.toml").exists()
         || path.contains("packages")
@@ -44,7 +73,7 @@ pub async fn execute(project_dir: &Path, path: &str, fetch: bool) -> Result<()>
     let is_board = full_path.join("board.toml").exists() || path.contains("boards");
 
     if is_package {
-        verify_package(&full_path, fetch).await?;
+        verify_package(&full_path, fetch, lints).await?;
     } else if is_board {
         verify_board(&full_path).await?;
     } else {
@@ -54,7 +83,7 @@ pub async fn execute(project_dir: &Path, path: &str, fetch: bool) -> Result<()>
             let has_board = full_path.join("board.toml").exists();
 
             if has_metadata {
-                verify_package(&full_path, fetch).await?;
+                verify_package(&full_path, fetch, lints).await?;
             } else if has_board {
                 verify_board(&full_path).await?;
             } else {
@@ -71,14 +100,224 @@ pub async fn execute(project_dir: &Path, path: &str, fetch: bool) -> Result<()>
     Ok(())
 }
 
+/// Check the signature of a definition when a public key is known
+///
+/// The key from the command line takes precedence over `registry.public_key`
+/// of the global configuration; without either, nothing is checked.
+fn verify_signature(
+    project_dir: &Path,
+    definition: &Path,
+    public_key: Option<&Path>,
+) -> Result<()> {
+    let configured = GlobalConfig::load(&ZigrootDirs::new())?.registry.public_key;
+    let Some(key_path) = public_key
+        .map(Path::to_path_buf)
+        .or_else(|| configured.map(Into::into))
+    else {
+        return Ok(());
+    };
+    let key = PublicKey::load(&project_dir.join(key_path))?;
+    key.verify_definition(definition)?;
+    if !is_json() {
+        println!("  ✓ Signature matches public key {}", key.encoded());
+    }
+    Ok(())
+}
+
+/// Outcome of fetching a source from one of its mirrors
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MirrorStatus {
+    /// Served bytes match the declared checksum
+    Match,
+    /// Served bytes have a different checksum
+    Mismatch { actual: String },
+    /// The mirror could not be fetched
+    Error(String),
+}
+
+/// Result of verifying one mirror of a source
+#[derive(Debug, Clone)]
+struct MirrorResult {
+    url: String,
+    status: MirrorStatus,
+}
+
+impl MirrorResult {
+    fn to_json(&self) -> serde_json::Value {
+        match &self.status {
+            MirrorStatus::Match => serde_json::json!({"url": self.url, "status": "match"}),
+            MirrorStatus::Mismatch { actual } => serde_json::json!({
+                "url": self.url,
+                "status": "mismatch",
+                "actual": actual,
+            }),
+            MirrorStatus::Error(error) => serde_json::json!({
+                "url": self.url,
+                "status": "error",
+                "error": error,
+            }),
+        }
+    }
+}
+
+/// Verification results of a version file's source
+#[derive(Debug, Clone)]
+pub struct SourceResult {
+    file: String,
+    checksum: Option<Checksum>,
+    mirrors: Vec<MirrorResult>,
+}
+
 /// Verify a package definition
-async fn verify_package(pkg_path: &Path, fetch: bool) -> Result<()> {
-    let pkg_name = pkg_path
-        .file_name()
+async fn verify_package(pkg_path: &Path, fetch: bool, lints: LintOptions) -> Result<()> {
+    let pkg_name = package_name(pkg_path);
+    let sources = check_package(pkg_path, fetch).await?;
+    let failed = failed_mirrors(&sources);
+    let findings = lint_package_dir(pkg_path, lints.network).await?;
+    let lint_errors = findings
+        .iter()
+        .filter(|f| f.severity == LintSeverity::Error)
+        .count();
+    let lint_warnings = findings.len() - lint_errors;
+    let lints_failed = lint_errors > 0 || (lints.deny_warnings && lint_warnings > 0);
+
+    if is_json() {
+        let output = serde_json::json!({
+            "package": pkg_name,
+            "valid": failed == 0 && !lints_failed,
+            "lints": findings,
+            "sources": sources.iter().map(|source| serde_json::json!({
+                "file": source.file,
+                "algorithm": source.checksum.as_ref().map(|c| c.algorithm.name()),
+                "checksum": source.checksum.as_ref().map(|c| &c.digest),
+                "mirrors": source.mirrors.iter().map(MirrorResult::to_json).collect::<Vec<_>>(),
+            })).collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        print_findings(&findings);
+    }
+    check_mirrors(pkg_name, &sources)?;
+    if lints_failed {
+        anyhow::bail!(
+            "Package '{}' failed linting: {} error(s), {} warning(s){}",
+            pkg_name,
+            lint_errors,
+            lint_warnings,
+            if lints.deny_warnings {
+                " (warnings denied)"
+            } else {
+                ""
+            }
+        );
+    }
+
+    if !is_json() {
+        println!();
+        println!("✓ Package '{pkg_name}' is valid");
+    }
+    Ok(())
+}
+
+/// Lint a package's definition files
+///
+/// With `network`, dependencies are also checked against the registry index.
+async fn lint_package_dir(pkg_path: &Path, network: bool) -> Result<Vec<LintFinding>> {
+    let read = |file: &str| -> Result<toml::Value> {
+        let content = std::fs::read_to_string(pkg_path.join(file))?;
+        Ok(toml::from_str(&content)?)
+    };
+    let metadata = read("metadata.toml")?;
+    let mut versions = Vec::new();
+    for file in find_version_files(pkg_path)? {
+        let version = read(&file)?;
+        versions.push((file, version));
+    }
+    versions.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut findings = lint_package(&metadata, &versions);
+    if network {
+        let config = GlobalConfig::load(&ZigrootDirs::new())?;
+        let index = RegistryClient::with_config(
+            config.packages_url().to_string(),
+            config.boards_url().to_string(),
+            default_cache_dir(),
+            0,
+        )
+        .fetch_package_index()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch the registry index: {e}"))?;
+        findings.extend(lint_dependencies(&metadata, &index));
+        findings.sort_by_key(|f| f.severity);
+    }
+    Ok(findings)
+}
+
+/// Print lint findings grouped by severity
+fn print_findings(findings: &[LintFinding]) {
+    for (severity, heading, symbol) in [
+        (LintSeverity::Error, "Errors", "✗"),
+        (LintSeverity::Warning, "Warnings", "⚠"),
+    ] {
+        let group: Vec<_> = findings.iter().filter(|f| f.severity == severity).collect();
+        if group.is_empty() {
+            continue;
+        }
+        println!("  {heading}:");
+        for finding in group {
+            println!(
+                "    {symbol} [{}] {}: {}",
+                finding.rule, finding.file, finding.message
+            );
+        }
+    }
+}
+
+/// Name of a package or board from its directory
+fn package_name(path: &Path) -> &str {
+    path.file_name()
         .and_then(|n| n.to_str())
-        .unwrap_or("unknown");
+        .unwrap_or("unknown")
+}
+
+/// Number of mirrors that did not serve the declared checksum
+fn failed_mirrors(sources: &[SourceResult]) -> usize {
+    sources
+        .iter()
+        .flat_map(|s| &s.mirrors)
+        .filter(|m| m.status != MirrorStatus::Match)
+        .count()
+}
+
+/// Fail if any fetched mirror did not serve the declared checksum
+pub fn check_mirrors(pkg_name: &str, sources: &[SourceResult]) -> Result<()> {
+    let failed = failed_mirrors(sources);
+    if failed > 0 {
+        let total = sources.iter().map(|s| s.mirrors.len()).sum::<usize>();
+        anyhow::bail!(
+            "Package '{}': {} of {} fetched mirror(s) failed checksum verification",
+            pkg_name,
+            failed,
+            total
+        );
+    }
+    Ok(())
+}
+
+/// Check a package's metadata and version files
+///
+/// With `fetch`, every source is also downloaded from each of its mirrors;
+/// mismatches are returned rather than failing, see [`check_mirrors`].
+pub async fn check_package(pkg_path: &Path, fetch: bool) -> Result<Vec<SourceResult>> {
+    let pkg_name = package_name(pkg_path);
+    let json = is_json();
+    let say = |message: String| {
+        if !json {
+            println!("{message}");
+        }
+    };
 
-    println!("Verifying package '{}'...", pkg_name);
+    say(format!("Verifying package '{pkg_name}'..."));
 
     // Check for metadata.toml
     let metadata_path = pkg_path.join("metadata.toml");
@@ -99,17 +338,19 @@ async fn verify_package(pkg_path: &Path, fetch: bool) -> Result<()> {
     // Validate required fields in metadata.toml
     validate_package_metadata(&metadata, pkg_name)?;
 
-    println!("  ✓ metadata.toml is valid");
+    say("  ✓ metadata.toml is valid".to_string());
 
     // Find and validate version files
-    let version_files = find_version_files(pkg_path)?;
+    let mut version_files = find_version_files(pkg_path)?;
     if version_files.is_empty() {
         anyhow::bail!(
             "Package '{}' has no version files (e.g., 1.0.0.toml)",
             pkg_name
         );
     }
+    version_files.sort();
 
+    let mut sources = Vec::new();
     for version_file in &version_files {
         let version_path = pkg_path.join(version_file);
         let version_content = std::fs::read_to_string(&version_path)
@@ -124,28 +365,114 @@ async fn verify_package(pkg_path: &Path, fetch: bool) -> Result<()> {
         })?;
 
         validate_version_file(&version, version_file)?;
-        println!("  ✓ {} is valid", version_file);
+        say(format!("  ✓ {version_file} is valid"));
 
-        // If --fetch, download and verify checksum
+        // If --fetch, download from every mirror and verify the checksum
         if fetch {
             if let Some(source) = version.get("source") {
-                if let (Some(url), Some(_sha256)) = (
-                    source.get("url").and_then(|v| v.as_str()),
-                    source.get("sha256").and_then(|v| v.as_str()),
-                ) {
-                    println!("  → Fetching source from {}...", url);
-                    // Note: Actual download would happen here
-                    // For now, we just acknowledge the flag
-                    println!("  ⚠ Fetch verification not yet implemented");
+                sources.push(verify_source(version_file, source, json).await);
+            }
+        }
+    }
+
+    Ok(sources)
+}
+
+/// Fetch a version file's source from all of its mirrors
+async fn verify_source(version_file: &str, source: &toml::Value, json: bool) -> SourceResult {
+    let checksum = source_checksum(source).ok().flatten();
+    let mirrors = match checksum {
+        Some(ref checksum) => verify_mirrors(&source_mirrors(source), checksum).await,
+        None => Vec::new(),
+    };
+    if !json {
+        for mirror in &mirrors {
+            match &mirror.status {
+                MirrorStatus::Match => println!("    ✓ {} matches", mirror.url),
+                MirrorStatus::Mismatch { actual } => {
+                    println!("    ✗ {} checksum mismatch (got {actual})", mirror.url);
+                }
+                MirrorStatus::Error(error) => {
+                    println!("    ✗ {} could not be fetched: {error}", mirror.url);
                 }
             }
         }
     }
+    SourceResult {
+        file: version_file.to_string(),
+        checksum,
+        mirrors,
+    }
+}
 
-    println!();
-    println!("✓ Package '{}' is valid", pkg_name);
+/// URLs a source can be fetched from: `url`, then `urls` and `mirrors`
+fn source_mirrors(source: &toml::Value) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    let listed = ["urls", "mirrors"].into_iter().flat_map(|key| {
+        source
+            .get(key)
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+    });
+    for url in source
+        .get("url")
+        .and_then(|v| v.as_str())
+        .into_iter()
+        .chain(listed)
+    {
+        if !urls.iter().any(|u| u == url) {
+            urls.push(url.to_string());
+        }
+    }
+    urls
+}
 
-    Ok(())
+/// Checksum declared by a source's `sha256`, `sha512` or `blake3` field
+fn source_checksum(source: &toml::Value) -> Result<Option<Checksum>> {
+    let field = |name: &str| source.get(name).and_then(|v| v.as_str());
+    Ok(Checksum::from_fields(
+        field("sha256"),
+        field("sha512"),
+        field("blake3"),
+    )?)
+}
+
+/// Fetch a source from each mirror and compare it with `checksum`
+async fn verify_mirrors(urls: &[String], checksum: &Checksum) -> Vec<MirrorResult> {
+    let downloads = DownloadManager::new();
+    let dir = std::env::temp_dir().join(format!("zigroot-verify-{}", std::process::id()));
+    let mut results = Vec::new();
+    for (index, url) in urls.iter().enumerate() {
+        let dest = dir.join(index.to_string());
+        let status = match std::fs::create_dir_all(&dir) {
+            Err(e) => MirrorStatus::Error(e.to_string()),
+            Ok(()) => match downloads.download(url, &dest, None).await {
+                Ok(_) => match std::fs::read(&dest) {
+                    Ok(data) => {
+                        let actual = Checksum::compute(checksum.algorithm, &data);
+                        if actual == *checksum {
+                            MirrorStatus::Match
+                        } else {
+                            MirrorStatus::Mismatch {
+                                actual: actual.to_string(),
+                            }
+                        }
+                    }
+                    Err(e) => MirrorStatus::Error(e.to_string()),
+                },
+                Err(e) => MirrorStatus::Error(e.to_string()),
+            },
+        };
+        let _ = std::fs::remove_file(&dest);
+        results.push(MirrorResult {
+            url: url.clone(),
+            status,
+        });
+    }
+    let _ = std::fs::remove_dir(&dir);
+    results
 }
 
 /// Validate package metadata.toml required fields
@@ -235,12 +562,14 @@ fn validate_version_file(version: &toml::Value, filename: &str) -> Result<()> {
         );
     }
 
-    // Check for sha256
-    if source.get("sha256").and_then(|v| v.as_str()).is_none() {
-        anyhow::bail!(
-            "Version file '{}' is missing required field: source.sha256",
+    // Check for exactly one well-formed checksum
+    match source_checksum(source) {
+        Ok(Some(_)) => {}
+        Ok(None) => anyhow::bail!(
+            "Version file '{}' is missing required field: source.sha256, source.sha512 or source.blake3",
             filename
-        );
+        ),
+        Err(e) => anyhow::bail!("Version file '{filename}' has an invalid checksum: {e}"),
     }
 
     Ok(())
@@ -248,12 +577,21 @@ fn validate_version_file(version: &toml::Value, filename: &str) -> Result<()> {
 
 /// Verify a board definition
 async fn verify_board(board_path: &Path) -> Result<()> {
-    let board_name = board_path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown");
+    let board_name = package_name(board_path);
 
     println!("Verifying board '{}'...", board_name);
+    check_board(board_path)?;
+
+    println!("  ✓ board.toml is valid");
+    println!();
+    println!("✓ Board '{}' is valid", board_name);
+
+    Ok(())
+}
+
+/// Check a board's `board.toml`
+pub fn check_board(board_path: &Path) -> Result<()> {
+    let board_name = package_name(board_path);
 
     // Check for board.toml
     let board_toml_path = board_path.join("board.toml");
@@ -269,13 +607,7 @@ async fn verify_board(board_path: &Path) -> Result<()> {
         .map_err(|e| anyhow::anyhow!("Failed to parse board.toml - TOML syntax error: {}", e))?;
 
     // Validate required fields
-    validate_board_definition(&board, board_name)?;
-
-    println!("  ✓ board.toml is valid");
-    println!();
-    println!("✓ Board '{}' is valid", board_name);
-
-    Ok(())
+    validate_board_definition(&board, board_name)
 }
 
 /// Validate board.toml required fields
@@ -418,4 +750,24 @@ mod tests {
         assert!(!is_valid_zig_target("not-a-valid-target"));
         assert!(!is_valid_zig_target("invalid"));
     }
+
+    #[test]
+    fn test_source_mirrors_lists_each_url_once() {
+        let source: toml::Value = toml::from_str(
+            r#"
+url = "https://a.example/x.tar.gz"
+urls = ["https://b.example/x.tar.gz", "https://a.example/x.tar.gz"]
+mirrors = ["https://c.example/x.tar.gz"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            source_mirrors(&source),
+            vec![
+                "https://a.example/x.tar.gz",
+                "https://b.example/x.tar.gz",
+                "https://c.example/x.tar.gz",
+            ]
+        );
+    }
 }
@@ -7,9 +7,13 @@ pub mod commands;
 pub mod output;
 pub mod tui;
 
+use std::io::IsTerminal;
+
 use anyhow::Result;
 use clap::Parser;
 
+use crate::core::version;
+use crate::infra::flock;
 use commands::Commands;
 
 /// Build version string with git info
@@ -44,21 +48,81 @@ pub struct Cli {
     #[arg(long, global = true)]
     pub json: bool,
 
+    /// When to use colors: auto (terminal without `NO_COLOR`), always or never
+    #[arg(long, global = true, value_enum, default_value_t = output::ColorChoice::Auto)]
+    pub color: output::ColorChoice,
+
+    /// Wait for another zigroot process in the project to finish instead
+    /// of failing
+    #[arg(long, global = true)]
+    pub wait: bool,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
 impl Cli {
     /// Execute the CLI command
+    ///
+    /// Warnings reported during the command are summarized when it ends.
+    /// A rate-limited update check runs alongside the command. Its
+    /// notification is printed after a successful command, but only if the
+    /// check has already finished, so it never delays the command.
     pub async fn run(self) -> Result<()> {
-        if let Some(cmd) = self.command {
-            cmd.run().await
-        } else {
+        let Some(cmd) = self.command else {
             // No subcommand provided, show help
             use clap::CommandFactory;
             let mut cmd = Self::command();
             cmd.print_help()?;
-            Ok(())
+            return Ok(());
+        };
+
+        // `update --self` performs its own check, and completion must stay
+        // fast and offline
+        let checks_itself = matches!(
+            cmd,
+            Commands::Update {
+                self_update: true,
+                ..
+            } | Commands::Completions { .. }
+                | Commands::Complete { .. }
+        );
+        let update_check = (!checks_itself
+            && std::io::stderr().is_terminal()
+            && version::update_check_enabled(self.quiet, self.json))
+        .then(|| tokio::spawn(version::background_update_check()));
+
+        // Held until the command ends; commands that change the project
+        // must not run concurrently in it
+        let _project_lock = if cmd.changes_project() {
+            flock::lock_project(&std::env::current_dir()?, self.wait, |holder| {
+                output::print_info(&format!(
+                    "Waiting for another zigroot process ({holder}) to finish..."
+                ));
+            })?
+        } else {
+            None
+        };
+
+        let result = cmd.run().await;
+        output::print_warning_summary();
+
+        if let Some(mut handle) = update_check {
+            // A check still waiting for GitHub gets a moment to finish and
+            // cache its result; it has recorded the attempt already
+            let finished = if result.is_ok() {
+                tokio::time::timeout(version::BACKGROUND_CHECK_GRACE, &mut handle)
+                    .await
+                    .ok()
+            } else {
+                None
+            };
+            match finished {
+                Some(Ok(Some(notification))) => eprint!("{notification}"),
+                Some(_) => {}
+                None => handle.abort(),
+            }
         }
+        result
     }
 }
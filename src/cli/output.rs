@@ -11,20 +11,37 @@
 //!
 //! ## Color Coding
 //!
+//! Colors are used with `--color always`, or with `--color auto` (the
+//! default) when stdout is a terminal and `NO_COLOR` is not set.
+//!
 //! - Green (✓): Success messages
 //! - Red (✗): Error messages
 //! - Yellow (⚠): Warning messages
 //! - Blue (ℹ): Informational messages
+//!
+//! ## Warnings
+//!
+//! Warnings reported with [`report_warning`] are collected during a command
+//! and summarized when it ends, so they do not get lost in long output.
 
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use serde::Serialize;
+use std::collections::HashMap;
 use std::io::{self, IsTerminal, Write};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::{Duration, Instant};
 
+use crate::core::fetch::{FetchEvent, FetchProgress};
+
 /// Global output configuration
 static QUIET_MODE: AtomicBool = AtomicBool::new(false);
 static JSON_MODE: AtomicBool = AtomicBool::new(false);
+static VERBOSE_MODE: AtomicBool = AtomicBool::new(false);
+static COLOR_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Warnings reported during the current command
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
 
 /// Output configuration for CLI commands
 #[derive(Debug, Clone, Default)]
@@ -35,6 +52,33 @@ pub struct OutputConfig {
     pub json: bool,
     /// Verbose level (0 = normal, 1 = info, 2 = debug)
     pub verbose: u8,
+    /// When to use colors
+    pub color: ColorChoice,
+}
+
+/// When to use colors in output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    /// Always color, even when piped
+    Always,
+    /// Never color
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color, given the `NO_COLOR` variable and whether stdout
+    /// is a terminal
+    pub fn resolve(self, no_color: Option<&str>, terminal: bool) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            // Per no-color.org, an empty NO_COLOR does not disable colors
+            Self::Auto => terminal && no_color.map_or(true, str::is_empty),
+        }
+    }
 }
 
 impl OutputConfig {
@@ -44,13 +88,27 @@ impl OutputConfig {
             quiet,
             json,
             verbose,
+            color: ColorChoice::Auto,
         }
     }
 
+    /// Set when to use colors
+    #[must_use]
+    pub fn with_color(mut self, color: ColorChoice) -> Self {
+        self.color = color;
+        self
+    }
+
     /// Apply this configuration globally
     pub fn apply_global(&self) {
         QUIET_MODE.store(self.quiet, Ordering::SeqCst);
         JSON_MODE.store(self.json, Ordering::SeqCst);
+        VERBOSE_MODE.store(self.verbose > 0, Ordering::SeqCst);
+        let no_color = std::env::var("NO_COLOR").ok();
+        let color = self
+            .color
+            .resolve(no_color.as_deref(), io::stdout().is_terminal());
+        COLOR_MODE.store(color, Ordering::SeqCst);
     }
 }
 
@@ -64,9 +122,70 @@ pub fn is_json() -> bool {
     JSON_MODE.load(Ordering::SeqCst)
 }
 
+/// Check if verbose output (-v) is enabled
+pub fn is_verbose() -> bool {
+    VERBOSE_MODE.load(Ordering::SeqCst)
+}
+
+/// Check if colored output is enabled
+pub fn use_color() -> bool {
+    COLOR_MODE.load(Ordering::SeqCst)
+}
+
+/// Wrap text in an ANSI color code when colors are enabled
+pub fn paint(text: &str, color: Color) -> String {
+    if use_color() {
+        format!("\x1b[{}m{text}\x1b[0m", color.code())
+    } else {
+        text.to_string()
+    }
+}
+
+/// Colors used in output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// Success
+    Green,
+    /// Errors
+    Red,
+    /// Warnings
+    Yellow,
+    /// Information
+    Blue,
+    /// Emphasis
+    Bold,
+}
+
+impl Color {
+    /// SGR parameter of the color
+    fn code(self) -> &'static str {
+        match self {
+            Self::Green => "32",
+            Self::Red => "31",
+            Self::Yellow => "33",
+            Self::Blue => "34",
+            Self::Bold => "1",
+        }
+    }
+}
+
 /// Check if output is interactive (terminal)
+///
+/// Progress bars are only drawn when this holds; otherwise the progress
+/// helpers return hidden bars, so spinner frames and carriage returns never
+/// end up in JSON or piped output.
 pub fn is_interactive() -> bool {
-    io::stdout().is_terminal() && !is_quiet() && !is_json()
+    progress_visible(
+        is_quiet(),
+        is_json(),
+        io::stdout().is_terminal() && io::stderr().is_terminal(),
+    )
+}
+
+/// Whether to draw progress bars, given the output mode and whether stdout
+/// and stderr (where the bars are drawn) are terminals
+pub fn progress_visible(quiet: bool, json: bool, terminal: bool) -> bool {
+    terminal && !quiet && !json
 }
 
 /// Create a spinner for operations with unknown duration
@@ -119,6 +238,310 @@ pub fn create_build_bar(total: u64) -> ProgressBar {
     pb
 }
 
+/// Characters of a build output line shown next to the progress bar
+const OUTPUT_LINE_WIDTH: usize = 60;
+
+/// Overall progress of a build, weighted by estimated package durations
+///
+/// Each package counts with its estimated build time, so the ETA reflects
+/// slow packages still to come. In JSON mode an `overall_progress` event is
+/// printed as one JSON line whenever a package finishes.
+pub struct OverallProgress {
+    /// Progress bar, with the position in milliseconds of estimated time
+    bar: ProgressBar,
+    /// When the build started
+    started: Instant,
+    /// Estimated seconds of the finished packages
+    completed: f64,
+    /// Estimated seconds of all packages
+    total: f64,
+}
+
+impl OverallProgress {
+    /// Create the progress display for a build estimated at `total` seconds
+    pub fn new(total: f64) -> Self {
+        let bar = if is_interactive() {
+            let bar = ProgressBar::new(weight_millis(total));
+            bar.set_style(
+                ProgressStyle::default_bar()
+                    .template("{spinner:.green} [{bar:40.cyan/blue}] {percent:>3}% {msg}")
+                    .expect("Invalid progress bar template")
+                    .progress_chars("█▓▒░"),
+            );
+            bar
+        } else {
+            ProgressBar::hidden()
+        };
+        Self {
+            bar,
+            started: Instant::now(),
+            completed: 0.0,
+            total,
+        }
+    }
+
+    /// Show the package being built
+    pub fn start_package(&self, name: &str) {
+        let eta = self
+            .eta()
+            .map(|eta| format!(", ETA {}", format_duration(eta)))
+            .unwrap_or_default();
+        self.bar.set_message(format!("building {name}{eta}"));
+    }
+
+    /// Show the latest output line of the package being built
+    pub fn show_output(&self, name: &str, line: &str) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        let line: String = match line.char_indices().nth(OUTPUT_LINE_WIDTH) {
+            Some((end, _)) => format!("{}…", &line[..end]),
+            None => line.to_string(),
+        };
+        self.bar.set_message(format!("building {name}: {line}"));
+    }
+
+    /// Mark a package with estimated duration `weight` as finished
+    pub fn finish_package(&mut self, weight: f64) {
+        self.completed = (self.completed + weight).min(self.total);
+        self.bar.set_position(weight_millis(self.completed));
+        if is_json() {
+            let event = serde_json::json!({
+                "event": "overall_progress",
+                "completed": self.completed,
+                "total": self.total,
+                "eta_secs": self.eta().map(|eta| eta.as_secs_f64()),
+            });
+            println!("{event}");
+        }
+    }
+
+    /// Estimated remaining time
+    ///
+    /// The remaining estimate is scaled by how fast the finished packages
+    /// built compared to their estimate. `None` once everything is done.
+    pub fn eta(&self) -> Option<Duration> {
+        let remaining = self.total - self.completed;
+        if remaining <= 0.0 {
+            return None;
+        }
+        let speed = if self.completed > 0.0 {
+            self.started.elapsed().as_secs_f64() / self.completed
+        } else {
+            1.0
+        };
+        Some(Duration::from_secs_f64(remaining * speed))
+    }
+
+    /// Print a line above the progress bar
+    pub fn println(&self, line: &str) {
+        self.bar.suspend(|| print_plain(line));
+    }
+
+    /// Remove the progress bar
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+/// Minimum time between two progress events of a download without bars
+const DOWNLOAD_EVENT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Progress of concurrent downloads
+///
+/// Every download gets a bar on one `MultiProgress`, below a total bar with
+/// the combined bytes and throughput. Without a terminal the bars are
+/// replaced by `fetch_progress` events, at most one per download every
+/// [`DOWNLOAD_EVENT_INTERVAL`] plus one when it finishes: printed as JSON
+/// lines in JSON mode, logged otherwise.
+pub struct DownloadProgress {
+    /// Bars of all downloads
+    multi: MultiProgress,
+    /// Combined bytes of all downloads
+    total: ProgressBar,
+    /// When the first download started
+    started: Instant,
+    /// Per-download state
+    downloads: Mutex<HashMap<String, DownloadState>>,
+}
+
+/// State of one download
+struct DownloadState {
+    /// Its bar, hidden without a terminal
+    bar: ProgressBar,
+    /// Bytes downloaded so far
+    downloaded: u64,
+    /// File size, 0 while unknown
+    size: u64,
+    /// When its last event was emitted
+    reported: Option<Instant>,
+}
+
+impl DownloadProgress {
+    /// Create the display, with no downloads yet
+    pub fn new() -> Arc<Self> {
+        let multi = MultiProgress::new();
+        let total = if is_interactive() {
+            let bar = multi.add(ProgressBar::new(0));
+            bar.set_style(
+                ProgressStyle::default_bar()
+                    .template(
+                        "{spinner:.green} total [{bar:40.cyan/blue}] {bytes}/{total_bytes} \
+                         ({binary_bytes_per_sec})",
+                    )
+                    .expect("Invalid progress bar template")
+                    .progress_chars("█▓▒░"),
+            );
+            bar
+        } else {
+            ProgressBar::hidden()
+        };
+        Arc::new(Self {
+            multi,
+            total,
+            started: Instant::now(),
+            downloads: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Receiver of the fetch events, updating this display
+    pub fn sink(self: &Arc<Self>) -> FetchProgress {
+        let display = Arc::clone(self);
+        Arc::new(move |event| display.event(event))
+    }
+
+    /// Handle one fetch event
+    fn event(&self, event: FetchEvent) {
+        let mut downloads = self
+            .downloads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        match event {
+            FetchEvent::Started { name } => {
+                let bar = if is_interactive() {
+                    let bar = self.multi.add(ProgressBar::new(0));
+                    bar.set_style(
+                        ProgressStyle::default_bar()
+                            .template(
+                                "{spinner:.green} {msg:20} [{bar:40.cyan/blue}] \
+                                 {bytes}/{total_bytes} ({eta})",
+                            )
+                            .expect("Invalid progress bar template")
+                            .progress_chars("█▓▒░"),
+                    );
+                    bar.set_message(name.clone());
+                    bar
+                } else {
+                    ProgressBar::hidden()
+                };
+                downloads.insert(
+                    name,
+                    DownloadState {
+                        bar,
+                        downloaded: 0,
+                        size: 0,
+                        reported: None,
+                    },
+                );
+            }
+            FetchEvent::Progress {
+                name,
+                downloaded,
+                total,
+            } => {
+                let Some(state) = downloads.get_mut(&name) else {
+                    return;
+                };
+                state.downloaded = downloaded;
+                state.size = total;
+                state.bar.set_length(total);
+                state.bar.set_position(downloaded);
+                let due = state
+                    .reported
+                    .map_or(true, |at| at.elapsed() >= DOWNLOAD_EVENT_INTERVAL);
+                if due {
+                    state.reported = Some(Instant::now());
+                }
+                self.update_total(&downloads);
+                if due {
+                    self.report(&name, &downloads);
+                }
+            }
+            FetchEvent::Finished { name } => {
+                if let Some(state) = downloads.get(&name) {
+                    state.bar.finish_and_clear();
+                    self.multi.remove(&state.bar);
+                }
+                self.update_total(&downloads);
+                self.report(&name, &downloads);
+            }
+        }
+    }
+
+    /// Set the total bar from all downloads
+    fn update_total(&self, downloads: &HashMap<String, DownloadState>) {
+        self.total
+            .set_length(downloads.values().map(|state| state.size).sum());
+        self.total
+            .set_position(downloads.values().map(|state| state.downloaded).sum());
+    }
+
+    /// Emit a progress event of a download when there are no bars
+    #[allow(clippy::cast_precision_loss)]
+    fn report(&self, name: &str, downloads: &HashMap<String, DownloadState>) {
+        if is_interactive() {
+            return;
+        }
+        let Some(state) = downloads.get(name) else {
+            return;
+        };
+        let combined: u64 = downloads.values().map(|state| state.downloaded).sum();
+        let elapsed_ms = u64::try_from(self.started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        let rate = combined.saturating_mul(1000) / elapsed_ms.max(1);
+        if is_json() {
+            let event = serde_json::json!({
+                "event": "fetch_progress",
+                "name": name,
+                "downloaded": state.downloaded,
+                "total": state.size,
+                "combined_downloaded": combined,
+                "bytes_per_sec": rate,
+            });
+            println!("{event}");
+        } else {
+            tracing::info!(
+                "{name}: {}/{} ({} total at {}/s)",
+                format_size(state.downloaded),
+                format_size(state.size),
+                format_size(combined),
+                format_size(rate)
+            );
+        }
+    }
+
+    /// Remove the bars
+    pub fn finish(&self) {
+        self.total.finish_and_clear();
+        let _ = self.multi.clear();
+    }
+}
+
+/// Estimated seconds as a progress bar position
+fn weight_millis(secs: f64) -> u64 {
+    u64::try_from(Duration::from_secs_f64(secs.max(0.0)).as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Format a duration as e.g. "45s", "3m 05s" or "1h 02m"
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs() + u64::from(duration.subsec_millis() >= 500);
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
 /// Status message prefixes
 pub mod status {
     /// Success prefix (green checkmark)
@@ -140,7 +563,7 @@ pub fn print_success(message: &str) {
         let output = JsonOutput::success(message);
         println!("{}", serde_json::to_string(&output).unwrap_or_default());
     } else if !is_quiet() {
-        println!("{} {message}", status::SUCCESS);
+        println!("{} {message}", paint(status::SUCCESS, Color::Green));
     }
 }
 
@@ -150,7 +573,7 @@ pub fn print_error(message: &str) {
         let output = JsonOutput::error(message);
         eprintln!("{}", serde_json::to_string(&output).unwrap_or_default());
     } else {
-        eprintln!("{} {message}", status::ERROR);
+        eprintln!("{} {message}", paint(status::ERROR, Color::Red));
     }
 }
 
@@ -160,7 +583,62 @@ pub fn print_warning(message: &str) {
         let output = JsonOutput::warning(message);
         println!("{}", serde_json::to_string(&output).unwrap_or_default());
     } else if !is_quiet() {
-        println!("{} {message}", status::WARNING);
+        println!("{} {message}", paint(status::WARNING, Color::Yellow));
+    }
+}
+
+/// Report a warning: print it now and repeat it in the summary
+///
+/// In JSON mode the warning is only collected, for the command to include
+/// in its JSON output or for [`print_warning_summary`].
+pub fn report_warning(message: &str) {
+    if !is_json() {
+        print_warning(message);
+    }
+    defer_warning(message);
+}
+
+/// Collect a warning for the summary without printing it now
+pub fn defer_warning(message: &str) {
+    WARNINGS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(message.to_string());
+}
+
+/// Remove and return the collected warnings
+///
+/// Commands with their own JSON output include these as an array.
+pub fn take_warnings() -> Vec<String> {
+    std::mem::take(&mut *WARNINGS.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// Print the collected warnings, e.g. "3 warnings:" and one per line
+///
+/// Called once when a command ends. Nothing is printed in quiet mode; in
+/// JSON mode the warnings are printed as one JSON line.
+pub fn print_warning_summary() {
+    let warnings = take_warnings();
+    if warnings.is_empty() || is_quiet() {
+        return;
+    }
+    let header = match warnings.len() {
+        1 => "1 warning".to_string(),
+        n => format!("{n} warnings"),
+    };
+    if is_json() {
+        let output =
+            JsonOutput::warning(&header).with_data(serde_json::json!({ "warnings": warnings }));
+        println!("{}", serde_json::to_string(&output).unwrap_or_default());
+        return;
+    }
+    println!();
+    println!(
+        "{}",
+        paint(&format!("{} {header}:", status::WARNING), Color::Yellow)
+    );
+    for warning in &warnings {
+        println!("  • {}", warning.replace('\n', "\n    "));
     }
 }
 
@@ -170,7 +648,7 @@ pub fn print_info(message: &str) {
         let output = JsonOutput::info(message);
         println!("{}", serde_json::to_string(&output).unwrap_or_default());
     } else if !is_quiet() {
-        println!("{} {message}", status::INFO);
+        println!("{} {message}", paint(status::INFO, Color::Blue));
     }
 }
 
@@ -255,6 +733,10 @@ pub struct BuildSummary {
     pub total_packages: usize,
     /// Image size in bytes (if applicable)
     pub image_size: Option<u64>,
+    /// SHA256 of the final image (if applicable)
+    pub image_sha256: Option<String>,
+    /// Estimated build time from earlier builds (if known)
+    pub estimated_time: Option<Duration>,
     /// Whether build was successful
     pub success: bool,
 }
@@ -267,6 +749,8 @@ impl BuildSummary {
             packages_built,
             total_packages,
             image_size: None,
+            image_sha256: None,
+            estimated_time: None,
             success: true,
         }
     }
@@ -277,6 +761,20 @@ impl BuildSummary {
         self
     }
 
+    /// Set the SHA256 of the final image
+    #[must_use]
+    pub fn with_image_sha256(mut self, sha256: &str) -> Self {
+        self.image_sha256 = Some(sha256.to_string());
+        self
+    }
+
+    /// Set the estimated build time
+    #[must_use]
+    pub fn with_estimated_time(mut self, estimate: Duration) -> Self {
+        self.estimated_time = Some(estimate);
+        self
+    }
+
     /// Mark as failed
     pub fn failed(mut self) -> Self {
         self.success = false;
@@ -306,9 +804,12 @@ impl BuildSummary {
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
 
         if self.success {
-            println!("{} Build completed successfully", status::SUCCESS);
+            println!(
+                "{} Build completed successfully",
+                paint(status::SUCCESS, Color::Green)
+            );
         } else {
-            println!("{} Build failed", status::ERROR);
+            println!("{} Build failed", paint(status::ERROR, Color::Red));
         }
 
         println!();
@@ -316,11 +817,21 @@ impl BuildSummary {
             "  Packages: {}/{} built",
             self.packages_built, self.total_packages
         );
-        println!("  Time:     {:.2}s", self.total_time.as_secs_f64());
+        match self.estimated_time {
+            Some(estimate) => println!(
+                "  Time:     {:.2}s (estimated {})",
+                self.total_time.as_secs_f64(),
+                format_duration(estimate)
+            ),
+            None => println!("  Time:     {:.2}s", self.total_time.as_secs_f64()),
+        }
 
         if let Some(size) = self.image_size {
             println!("  Image:    {}", format_size(size));
         }
+        if let Some(ref sha256) = self.image_sha256 {
+            println!("  SHA256:   {sha256}");
+        }
 
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     }
@@ -348,6 +859,7 @@ pub mod suggestions {
     use crate::error::{
         BoardError, BuildError, DownloadError, InitError, PackageError, ResolverError, ZigrootError,
     };
+    use crate::infra::flock::FlockError;
 
     /// Get a suggestion for a given error
     pub fn get_suggestion(error: &anyhow::Error) -> Option<String> {
@@ -381,6 +893,10 @@ pub mod suggestions {
             return get_resolver_suggestion(e);
         }
 
+        if let Some(FlockError::Busy { .. }) = error.downcast_ref::<FlockError>() {
+            return Some("Wait for it to finish, or pass --wait to start once it has".to_string());
+        }
+
         // Check for IO errors
         if let Some(e) = error.downcast_ref::<std::io::Error>() {
             return get_io_suggestion(e);
@@ -422,13 +938,13 @@ pub mod suggestions {
                 Some("Specify only one source type: url, git, or sources".to_string())
             }
             PackageError::NoSourceType { .. } => {
-                Some("Add a source type: url+sha256, git+ref, or sources".to_string())
+                Some("Add a source type: url+checksum, git+ref, or sources".to_string())
             }
             PackageError::GitWithoutRef { .. } => {
                 Some("Add a tag, branch, or rev to your git source".to_string())
             }
             PackageError::UrlWithoutChecksum { .. } => {
-                Some("Add a sha256 checksum for the URL source".to_string())
+                Some("Add a sha256, sha512 or blake3 checksum for the URL source".to_string())
             }
             _ => None,
         }
@@ -545,7 +1061,10 @@ pub fn display_error(error: &anyhow::Error) {
     // Print suggestion if available
     if let Some(suggestion) = suggestions::get_suggestion(error) {
         eprintln!();
-        eprintln!("{} Suggestion: {suggestion}", status::INFO);
+        eprintln!(
+            "{} Suggestion: {suggestion}",
+            paint(status::INFO, Color::Blue)
+        );
     }
 }
 
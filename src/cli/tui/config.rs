@@ -23,10 +23,13 @@ use ratatui::{
 };
 
 use crate::core::config::{
-    get_available_packages, get_package_dependencies, get_package_dependents,
-    load_manifest_for_config, ConfigCategory,
+    filter_boards, find_selection_conflicts, get_available_packages, get_package_dependencies,
+    get_package_dependents, get_package_options, load_manifest_for_config, ConfigCategory,
 };
-use crate::core::manifest::{Manifest, PackageRef};
+use crate::core::manifest::{is_valid_size_format, Manifest, PackageRef};
+use crate::core::options::OptionSource;
+use crate::infra::filesystem::write_file_atomic;
+use crate::registry::client::{BoardIndexEntry, RegistryError};
 
 /// TUI Application state
 pub struct ConfigTui {
@@ -43,16 +46,32 @@ pub struct ConfigTui {
     selected_category: ConfigCategory,
     /// Category list state
     category_state: ListState,
+    /// Board name from the manifest when the TUI was opened
+    original_board: Option<String>,
+    /// Boards from the registry index
+    available_boards: Vec<BoardIndexEntry>,
+    /// Board list state (index into the filtered boards)
+    board_state: ListState,
+    /// Board filter text
+    board_filter: String,
+    /// Whether the filter of the current view is being edited
+    editing_filter: bool,
+    /// Message shown when the board index is unavailable or empty
+    board_message: Option<String>,
     /// Available packages
     available_packages: Vec<PackageInfo>,
-    /// Package list state
+    /// Package list state (index into the filtered packages)
     package_state: ListState,
+    /// Package filter text
+    package_filter: String,
     /// Selected packages (names)
     selected_packages: HashSet<String>,
     /// Build options
     build_options: Vec<BuildOption>,
     /// Build option list state
     build_option_state: ListState,
+    /// External artifact list state (index into the sorted artifact names)
+    external_state: ListState,
     /// Currently editing option index
     editing_option: Option<usize>,
     /// Edit buffer for text input
@@ -134,6 +153,39 @@ pub enum OptionType {
     Choice(Vec<String>),
 }
 
+impl BuildOption {
+    /// Check a new value against the option's expected format
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        match &self.option_type {
+            OptionType::Bool => {
+                if value == "true" || value == "false" {
+                    Ok(())
+                } else {
+                    Err(format!("{} must be true or false", self.name))
+                }
+            }
+            OptionType::Choice(choices) => {
+                if choices.iter().any(|c| c == value) {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "{} must be one of: {}",
+                        self.name,
+                        choices.join(", ")
+                    ))
+                }
+            }
+            OptionType::String => match self.name.as_str() {
+                "rootfs_size" if !is_valid_size_format(value) => Err(format!(
+                    "Invalid rootfs_size '{value}': expected format like '256M', '1G' or 'auto'"
+                )),
+                "hostname" if value.is_empty() => Err("hostname cannot be empty".to_string()),
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
 impl ConfigTui {
     /// Create a new TUI instance
     pub fn new(project_dir: &Path, board_only: bool, packages_only: bool) -> anyhow::Result<Self> {
@@ -213,8 +265,21 @@ impl ConfigTui {
             build_option_state.select(Some(0));
         }
 
+        let mut external_state = ListState::default();
+        if !manifest.external.is_empty() {
+            external_state.select(Some(0));
+        }
+
+        let original_board = manifest.board.name.clone();
+
         Ok(Self {
             manifest,
+            original_board,
+            available_boards: Vec::new(),
+            board_state: ListState::default(),
+            board_filter: String::new(),
+            editing_filter: false,
+            board_message: Some("Board index not loaded.".to_string()),
             project_dir: project_dir.to_path_buf(),
             has_changes: false,
             view_mode,
@@ -222,9 +287,11 @@ impl ConfigTui {
             category_state,
             available_packages,
             package_state,
+            package_filter: String::new(),
             selected_packages,
             build_options,
             build_option_state,
+            external_state,
             editing_option: None,
             edit_buffer: String::new(),
             show_diff: false,
@@ -234,6 +301,31 @@ impl ConfigTui {
         })
     }
 
+    /// Provide the registry board index for board selection
+    pub fn set_board_index(&mut self, boards: Result<Vec<BoardIndexEntry>, RegistryError>) {
+        match boards {
+            Ok(boards) if boards.is_empty() => {
+                self.available_boards = Vec::new();
+                self.board_message = Some("The registry board index is empty.".to_string());
+            }
+            Ok(boards) => {
+                // Start on the current board if it is in the index
+                let current = self.manifest.board.name.as_deref();
+                let idx = boards
+                    .iter()
+                    .position(|b| Some(b.name.as_str()) == current)
+                    .unwrap_or(0);
+                self.available_boards = boards;
+                self.board_state.select(Some(idx));
+                self.board_message = None;
+            }
+            Err(e) => {
+                self.available_boards = Vec::new();
+                self.board_message = Some(format!("Could not fetch board index: {e}"));
+            }
+        }
+    }
+
     /// Run the TUI
     pub fn run(&mut self) -> anyhow::Result<()> {
         // Setup terminal
@@ -282,12 +374,16 @@ impl ConfigTui {
                     if self.is_editing() {
                         self.editing_option = None;
                         self.edit_buffer.clear();
+                        self.warning_message = None;
+                        if self.editing_filter && self.view_mode == ViewMode::PackageSelection {
+                            self.set_package_filter(String::new());
+                        }
+                        self.editing_filter = false;
                     } else if self.show_diff {
                         self.show_diff = false;
                         self.view_mode = ViewMode::MainMenu;
                     } else if self.view_mode != ViewMode::MainMenu {
-                        self.view_mode = ViewMode::MainMenu;
-                        self.focus = FocusArea::Categories;
+                        self.return_to_main_menu();
                     } else {
                         if self.has_changes {
                             self.show_diff = true;
@@ -335,7 +431,47 @@ impl ConfigTui {
 
     /// Check if currently editing a text field
     fn is_editing(&self) -> bool {
-        self.editing_option.is_some()
+        self.editing_option.is_some() || self.editing_filter
+    }
+
+    /// Indices of available packages matching the package filter
+    fn filtered_packages(&self) -> Vec<usize> {
+        let query = self.package_filter.to_lowercase();
+        self.available_packages
+            .iter()
+            .enumerate()
+            .filter(|(_, pkg)| {
+                query.is_empty()
+                    || pkg.name.to_lowercase().contains(&query)
+                    || pkg
+                        .description
+                        .as_deref()
+                        .is_some_and(|d| d.to_lowercase().contains(&query))
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Package under the cursor in the filtered list
+    fn highlighted_package(&self) -> Option<&PackageInfo> {
+        let filtered = self.filtered_packages();
+        let idx = *filtered.get(self.package_state.selected()?)?;
+        self.available_packages.get(idx)
+    }
+
+    /// Change the package filter, keeping the cursor on the same package if still visible
+    fn set_package_filter(&mut self, filter: String) {
+        let current = self.highlighted_package().map(|pkg| pkg.name.clone());
+        self.package_filter = filter;
+        let filtered = self.filtered_packages();
+        let position = current
+            .and_then(|name| {
+                filtered
+                    .iter()
+                    .position(|&idx| self.available_packages[idx].name == name)
+            })
+            .or(if filtered.is_empty() { None } else { Some(0) });
+        self.package_state.select(position);
     }
 
     /// Draw the TUI
@@ -482,33 +618,143 @@ impl ConfigTui {
     /// Draw board selection view
     fn draw_board_selection(&mut self, f: &mut Frame, area: Rect) {
         let current_board = self.manifest.board.name.as_deref().unwrap_or("Not set");
-        let text = format!(
-            "Current board: {current_board}\n\n\
-             Board selection from registry is not yet implemented.\n\
-             You can set the board manually in zigroot.toml.\n\n\
-             Press Esc to go back."
+
+        if let Some(ref message) = self.board_message {
+            let text = format!(
+                "Current board: {current_board}\n\n\
+                 {message}\n\
+                 You can set the board manually in zigroot.toml.\n\n\
+                 Press Esc to go back."
+            );
+
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title("Board Selection");
+
+            let paragraph = Paragraph::new(text).wrap(Wrap { trim: true }).block(block);
+
+            f.render_widget(paragraph, area);
+            return;
+        }
+
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(5)])
+            .split(area);
+
+        // Filter box
+        let filter_display = if self.editing_filter {
+            format!("{}_", self.board_filter)
+        } else if self.board_filter.is_empty() {
+            "(press / to filter)".to_string()
+        } else {
+            self.board_filter.clone()
+        };
+        let filter_style = if self.editing_filter {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        let filter = Paragraph::new(filter_display)
+            .style(filter_style)
+            .block(Block::default().borders(Borders::ALL).title("Filter"));
+        f.render_widget(filter, rows[0]);
+
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(rows[1]);
+
+        // Board list
+        let filtered = filter_boards(&self.available_boards, &self.board_filter);
+        let items: Vec<ListItem> = filtered
+            .iter()
+            .map(|board| {
+                let is_current = Some(board.name.as_str()) == self.manifest.board.name.as_deref();
+                let marker = if is_current { "[✓]" } else { "[ ]" };
+                ListItem::new(format!("{marker} {} ({})", board.name, board.arch))
+            })
+            .collect();
+
+        let title = format!(
+            "Boards ({}/{})",
+            filtered.len(),
+            self.available_boards.len()
         );
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().bg(Color::Blue).fg(Color::White))
+            .highlight_symbol("▶ ");
 
-        let block = Block::default()
+        f.render_stateful_widget(list, chunks[0], &mut self.board_state);
+
+        // Board details
+        let details = match self.board_state.selected().and_then(|i| filtered.get(i)) {
+            Some(board) => {
+                let keywords = if board.keywords.is_empty() {
+                    "none".to_string()
+                } else {
+                    board.keywords.join(", ")
+                };
+                format!(
+                    "Board: {}\n\
+                     Architecture: {}\n\
+                     Target: {}\n\
+                     Keywords: {keywords}\n\n\
+                     {}\n\n\
+                     Current board: {current_board}",
+                    board.name, board.arch, board.target, board.description
+                )
+            }
+            None => format!("No boards match the filter.\n\nCurrent board: {current_board}"),
+        };
+
+        let details_block = Block::default()
             .borders(Borders::ALL)
-            .title("Board Selection");
+            .title("Board Details");
 
-        let paragraph = Paragraph::new(text).wrap(Wrap { trim: true }).block(block);
+        let details_text = Paragraph::new(details)
+            .wrap(Wrap { trim: true })
+            .block(details_block);
 
-        f.render_widget(paragraph, area);
+        f.render_widget(details_text, chunks[1]);
     }
 
     /// Draw package selection view
     fn draw_package_selection(&mut self, f: &mut Frame, area: Rect) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(5)])
+            .split(area);
+
+        // Filter box
+        let filter_display = if self.editing_filter {
+            format!("{}_", self.package_filter)
+        } else if self.package_filter.is_empty() {
+            "(press / to filter)".to_string()
+        } else {
+            self.package_filter.clone()
+        };
+        let filter_style = if self.editing_filter {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        let filter = Paragraph::new(filter_display)
+            .style(filter_style)
+            .block(Block::default().borders(Borders::ALL).title("Filter"));
+        f.render_widget(filter, rows[0]);
+
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(area);
+            .split(rows[1]);
 
         // Package list
-        let items: Vec<ListItem> = self
-            .available_packages
+        let filtered = self.filtered_packages();
+        let items: Vec<ListItem> = filtered
             .iter()
+            .map(|&idx| &self.available_packages[idx])
             .map(|pkg| {
                 let selected = self.selected_packages.contains(&pkg.name);
                 let marker = if selected { "[✓]" } else { "[ ]" };
@@ -521,43 +767,48 @@ impl ConfigTui {
             })
             .collect();
 
+        let title = format!(
+            "Packages ({}/{})",
+            filtered.len(),
+            self.available_packages.len()
+        );
         let list = List::new(items)
-            .block(Block::default().borders(Borders::ALL).title("Packages"))
+            .block(Block::default().borders(Borders::ALL).title(title))
             .highlight_style(Style::default().bg(Color::Blue).fg(Color::White))
             .highlight_symbol("▶ ");
 
         f.render_stateful_widget(list, chunks[0], &mut self.package_state);
 
         // Package details
-        let details = if let Some(idx) = self.package_state.selected() {
-            if let Some(pkg) = self.available_packages.get(idx) {
-                let deps = if pkg.dependencies.is_empty() {
-                    "None".to_string()
-                } else {
-                    pkg.dependencies.join(", ")
-                };
-                let dependents = get_package_dependents(&self.project_dir, &pkg.name);
-                let dependents_str = if dependents.is_empty() {
-                    "None".to_string()
-                } else {
-                    dependents.join(", ")
-                };
-                format!(
-                    "Package: {}\n\
+        let details = if let Some(pkg) = self.highlighted_package() {
+            let deps = if pkg.dependencies.is_empty() {
+                "None".to_string()
+            } else {
+                pkg.dependencies.join(", ")
+            };
+            let dependents = get_package_dependents(&self.project_dir, &pkg.name);
+            let dependents_str = if dependents.is_empty() {
+                "None".to_string()
+            } else {
+                dependents.join(", ")
+            };
+            let options = package_options_summary(&self.project_dir, &self.manifest, &pkg.name);
+            format!(
+                "Package: {}\n\
                      Version: {}\n\
                      Description: {}\n\n\
                      Dependencies: {deps}\n\
                      Depended by: {dependents_str}\n\n\
+                     {options}\
                      Press Space to toggle selection.",
-                    pkg.name,
-                    pkg.version.as_deref().unwrap_or("unknown"),
-                    pkg.description.as_deref().unwrap_or("No description")
-                )
-            } else {
-                "No package selected".to_string()
-            }
-        } else {
+                pkg.name,
+                pkg.version.as_deref().unwrap_or("unknown"),
+                pkg.description.as_deref().unwrap_or("No description")
+            )
+        } else if self.available_packages.is_empty() {
             "No packages available".to_string()
+        } else {
+            "No packages match the filter".to_string()
         };
 
         let details_block = Block::default()
@@ -664,25 +915,94 @@ impl ConfigTui {
             .block(details_block);
 
         f.render_widget(details_text, chunks[1]);
+
+        // Show validation warning if any
+        if let Some(ref warning) = self.warning_message {
+            let warning_area = Rect {
+                x: area.x + 2,
+                y: area.y + area.height - 3,
+                width: area.width - 4,
+                height: 2,
+            };
+            let warning_text =
+                Paragraph::new(warning.as_str()).style(Style::default().fg(Color::Yellow));
+            f.render_widget(warning_text, warning_area);
+        }
     }
 
     /// Draw external artifacts view
     fn draw_external_artifacts(&mut self, f: &mut Frame, area: Rect) {
-        let text = format!(
-            "External Artifacts: {} configured\n\n\
-             External artifact management is not yet fully implemented.\n\
-             You can configure artifacts manually in zigroot.toml.\n\n\
-             Press Esc to go back.",
-            self.manifest.external.len()
-        );
+        if self.manifest.external.is_empty() {
+            let text = "No external artifacts configured.\n\n\
+                        Add them with 'zigroot external add' or in zigroot.toml.\n\n\
+                        Press Esc to go back.";
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title("External Artifacts");
+            let paragraph = Paragraph::new(text).wrap(Wrap { trim: true }).block(block);
+            f.render_widget(paragraph, area);
+            return;
+        }
 
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .title("External Artifacts");
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(area);
 
-        let paragraph = Paragraph::new(text).wrap(Wrap { trim: true }).block(block);
+        let names = self.external_names();
+        let items: Vec<ListItem> = names
+            .iter()
+            .map(|name| {
+                let artifact_type = &self.manifest.external[name].artifact_type;
+                ListItem::new(format!("{name} ({artifact_type})"))
+            })
+            .collect();
 
-        f.render_widget(paragraph, area);
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("External Artifacts ({})", names.len())),
+            )
+            .highlight_style(Style::default().bg(Color::Blue).fg(Color::White))
+            .highlight_symbol("▶ ");
+
+        f.render_stateful_widget(list, chunks[0], &mut self.external_state);
+
+        let details = self
+            .external_state
+            .selected()
+            .and_then(|i| names.get(i))
+            .map_or_else(String::new, |name| {
+                let artifact = &self.manifest.external[name];
+                let source = artifact
+                    .path
+                    .as_deref()
+                    .map(|path| format!("Path: {path}"))
+                    .or_else(|| artifact.url.as_deref().map(|url| format!("URL: {url}")))
+                    .unwrap_or_else(|| "Source: (none)".to_string());
+                format!(
+                    "Name: {name}\n\
+                     Type: {}\n\
+                     {source}\n\
+                     Checksum: {}",
+                    artifact.artifact_type,
+                    artifact
+                        .checksum()
+                        .ok()
+                        .flatten()
+                        .map_or_else(|| "(none)".to_string(), |c| c.to_string())
+                )
+            });
+
+        let details_block = Block::default()
+            .borders(Borders::ALL)
+            .title("Artifact Details");
+        let details_text = Paragraph::new(details)
+            .wrap(Wrap { trim: true })
+            .block(details_block);
+
+        f.render_widget(details_text, chunks[1]);
     }
 
     /// Draw diff view before saving
@@ -731,8 +1051,13 @@ impl ConfigTui {
 
         let help = match self.view_mode {
             ViewMode::MainMenu => "↑↓/jk: Navigate • Enter: Select • s: Save • q: Quit",
-            ViewMode::PackageSelection => {
-                "↑↓/jk: Navigate • Space: Toggle • Enter: Details • Esc: Back"
+            ViewMode::PackageSelection if self.editing_filter => {
+                "Type to filter • Enter: Done • Esc: Clear"
+            }
+            ViewMode::PackageSelection => "↑↓/jk: Navigate • Space: Toggle • /: Filter • Esc: Back",
+            ViewMode::BoardSelection if self.editing_filter => "Type to filter • Enter/Esc: Done",
+            ViewMode::BoardSelection if self.board_message.is_none() => {
+                "↑↓/jk: Navigate • Enter: Select • /: Filter • Esc: Back"
             }
             ViewMode::BuildOptions => "↑↓/jk: Navigate • Space/Enter: Edit • Esc: Back",
             ViewMode::DiffView => "y/Enter: Save • n/Esc: Discard",
@@ -762,109 +1087,170 @@ impl ConfigTui {
                 let new_i = if i >= 3 { 0 } else { i + 1 };
                 self.category_state.select(Some(new_i));
             }
-            KeyCode::Enter => {
-                match self.category_state.selected() {
-                    Some(0) => self.view_mode = ViewMode::BoardSelection,
-                    Some(1) => self.view_mode = ViewMode::PackageSelection,
-                    Some(2) => self.view_mode = ViewMode::BuildOptions,
-                    Some(3) => self.view_mode = ViewMode::ExternalArtifacts,
-                    _ => {}
-                }
-                self.focus = FocusArea::Content;
-            }
+            KeyCode::Enter => match self.category_state.selected() {
+                Some(0) => self.enter_view(ViewMode::BoardSelection),
+                Some(1) => self.enter_view(ViewMode::PackageSelection),
+                Some(2) => self.enter_view(ViewMode::BuildOptions),
+                Some(3) => self.enter_view(ViewMode::ExternalArtifacts),
+                _ => {}
+            },
             _ => {}
         }
     }
 
     /// Handle board selection input
     fn handle_board_input(&mut self, key: KeyCode) {
+        if self.editing_filter {
+            match key {
+                KeyCode::Enter => self.editing_filter = false,
+                KeyCode::Backspace => {
+                    self.board_filter.pop();
+                    self.reset_board_selection();
+                }
+                KeyCode::Char(c) => {
+                    self.board_filter.push(c);
+                    self.reset_board_selection();
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        let count = filter_boards(&self.available_boards, &self.board_filter).len();
+
         match key {
-            KeyCode::Esc => {
-                self.view_mode = ViewMode::MainMenu;
-                self.focus = FocusArea::Categories;
+            KeyCode::Up | KeyCode::Char('k') if count > 0 => {
+                let i = self.board_state.selected().unwrap_or(0);
+                let new_i = if i == 0 { count - 1 } else { i - 1 };
+                self.board_state.select(Some(new_i));
+            }
+            KeyCode::Down | KeyCode::Char('j') if count > 0 => {
+                let i = self.board_state.selected().unwrap_or(0);
+                let new_i = if i >= count - 1 { 0 } else { i + 1 };
+                self.board_state.select(Some(new_i));
+            }
+            KeyCode::Char('/') if self.board_message.is_none() => {
+                self.editing_filter = true;
+            }
+            KeyCode::Enter => {
+                let filtered = filter_boards(&self.available_boards, &self.board_filter);
+                if let Some(board) = self.board_state.selected().and_then(|i| filtered.get(i)) {
+                    if self.manifest.board.name.as_deref() != Some(board.name.as_str()) {
+                        self.manifest.board.name = Some(board.name.clone());
+                        self.has_changes = true;
+                    }
+                }
             }
+            KeyCode::Esc => self.return_to_main_menu(),
             _ => {}
         }
     }
 
+    /// Select the first board matching the current filter
+    fn reset_board_selection(&mut self) {
+        let count = filter_boards(&self.available_boards, &self.board_filter).len();
+        self.board_state
+            .select(if count == 0 { None } else { Some(0) });
+    }
+
     /// Handle package selection input
     fn handle_package_input(&mut self, key: KeyCode) {
         self.warning_message = None;
 
-        match key {
-            KeyCode::Up | KeyCode::Char('k') => {
-                if !self.available_packages.is_empty() {
-                    let i = self.package_state.selected().unwrap_or(0);
-                    let new_i = if i == 0 {
-                        self.available_packages.len() - 1
-                    } else {
-                        i - 1
-                    };
-                    self.package_state.select(Some(new_i));
+        if self.editing_filter {
+            match key {
+                KeyCode::Enter => self.editing_filter = false,
+                KeyCode::Backspace => {
+                    let mut filter = self.package_filter.clone();
+                    filter.pop();
+                    self.set_package_filter(filter);
                 }
-            }
-            KeyCode::Down | KeyCode::Char('j') => {
-                if !self.available_packages.is_empty() {
-                    let i = self.package_state.selected().unwrap_or(0);
-                    let new_i = if i >= self.available_packages.len() - 1 {
-                        0
-                    } else {
-                        i + 1
-                    };
-                    self.package_state.select(Some(new_i));
+                KeyCode::Char(c) => {
+                    let filter = format!("{}{c}", self.package_filter);
+                    self.set_package_filter(filter);
                 }
+                _ => {}
+            }
+            return;
+        }
+
+        let count = self.filtered_packages().len();
+
+        match key {
+            KeyCode::Up | KeyCode::Char('k') if count > 0 => {
+                let i = self.package_state.selected().unwrap_or(0);
+                let new_i = if i == 0 { count - 1 } else { i - 1 };
+                self.package_state.select(Some(new_i));
+            }
+            KeyCode::Down | KeyCode::Char('j') if count > 0 => {
+                let i = self.package_state.selected().unwrap_or(0);
+                let new_i = if i >= count - 1 { 0 } else { i + 1 };
+                self.package_state.select(Some(new_i));
+            }
+            KeyCode::Char('/') => {
+                self.editing_filter = true;
             }
             KeyCode::Char(' ') => {
-                if let Some(idx) = self.package_state.selected() {
-                    if let Some(pkg) = self.available_packages.get(idx) {
-                        let pkg_name = pkg.name.clone();
-                        if self.selected_packages.contains(&pkg_name) {
-                            // Deselecting - check for dependents
-                            let dependents = get_package_dependents(&self.project_dir, &pkg_name);
-                            let selected_dependents: Vec<_> = dependents
-                                .iter()
-                                .filter(|d| self.selected_packages.contains(*d))
-                                .cloned()
-                                .collect();
-
-                            if !selected_dependents.is_empty() {
-                                self.warning_message = Some(format!(
-                                    "⚠️  Warning: {} depends on this package",
-                                    selected_dependents.join(", ")
-                                ));
-                            }
-                            self.selected_packages.remove(&pkg_name);
-                            self.has_changes = true;
-                        } else {
-                            // Selecting - auto-select dependencies
-                            self.selected_packages.insert(pkg_name.clone());
-                            let deps = get_package_dependencies(&self.project_dir, &pkg_name);
-                            for dep in deps {
-                                if !self.selected_packages.contains(&dep) {
-                                    self.selected_packages.insert(dep);
-                                }
+                if let Some(pkg_name) = self.highlighted_package().map(|pkg| pkg.name.clone()) {
+                    if self.selected_packages.contains(&pkg_name) {
+                        // Deselecting - check for dependents
+                        let dependents = get_package_dependents(&self.project_dir, &pkg_name);
+                        let selected_dependents: Vec<_> = dependents
+                            .iter()
+                            .filter(|d| self.selected_packages.contains(*d))
+                            .cloned()
+                            .collect();
+
+                        if !selected_dependents.is_empty() {
+                            self.warning_message = Some(format!(
+                                "⚠️  Warning: {} depends on this package",
+                                selected_dependents.join(", ")
+                            ));
+                        }
+                        self.selected_packages.remove(&pkg_name);
+                        self.has_changes = true;
+                    } else {
+                        // Selecting - warn about conflicts, auto-select dependencies
+                        let conflicts = find_selection_conflicts(
+                            &self.project_dir,
+                            &pkg_name,
+                            &self.selected_packages,
+                        );
+                        if !conflicts.is_empty() {
+                            self.warning_message =
+                                Some(format!("⚠️  Warning: {}", conflicts.join("; ")));
+                        }
+                        self.selected_packages.insert(pkg_name.clone());
+                        let deps = get_package_dependencies(&self.project_dir, &pkg_name);
+                        for dep in deps {
+                            if !self.selected_packages.contains(&dep) {
+                                self.selected_packages.insert(dep);
                             }
-                            self.has_changes = true;
                         }
+                        self.has_changes = true;
                     }
                 }
             }
-            KeyCode::Esc => {
-                self.view_mode = ViewMode::MainMenu;
-                self.focus = FocusArea::Categories;
-            }
+            KeyCode::Esc => self.return_to_main_menu(),
             _ => {}
         }
     }
 
     /// Handle build options input
     fn handle_build_options_input(&mut self, key: KeyCode) {
+        self.warning_message = None;
+
         if self.is_editing() {
             // Handle text editing
             match key {
                 KeyCode::Enter => {
                     if let Some(idx) = self.editing_option {
                         if let Some(opt) = self.build_options.get_mut(idx) {
+                            // Keep editing until the value is valid
+                            if let Err(e) = opt.validate(&self.edit_buffer) {
+                                self.warning_message = Some(format!("⚠️  {e}"));
+                                return;
+                            }
                             opt.value = self.edit_buffer.clone();
                             self.has_changes = true;
                         }
@@ -933,32 +1319,83 @@ impl ConfigTui {
                     }
                 }
             }
-            KeyCode::Esc => {
-                self.view_mode = ViewMode::MainMenu;
-                self.focus = FocusArea::Categories;
-            }
+            KeyCode::Esc => self.return_to_main_menu(),
             _ => {}
         }
     }
 
     /// Handle external artifacts input
     fn handle_external_input(&mut self, key: KeyCode) {
+        let count = self.manifest.external.len();
+
         match key {
-            KeyCode::Esc => {
-                self.view_mode = ViewMode::MainMenu;
-                self.focus = FocusArea::Categories;
+            KeyCode::Up | KeyCode::Char('k') if count > 0 => {
+                let i = self.external_state.selected().unwrap_or(0);
+                let new_i = if i == 0 { count - 1 } else { i - 1 };
+                self.external_state.select(Some(new_i));
+            }
+            KeyCode::Down | KeyCode::Char('j') if count > 0 => {
+                let i = self.external_state.selected().unwrap_or(0);
+                let new_i = if i >= count - 1 { 0 } else { i + 1 };
+                self.external_state.select(Some(new_i));
             }
+            KeyCode::Esc => self.return_to_main_menu(),
             _ => {}
         }
     }
 
+    /// Switch from the main menu to a view
+    ///
+    /// List states are kept per view, so the cursor is where it was left;
+    /// it is only clamped in case the list shrank in the meantime.
+    fn enter_view(&mut self, view: ViewMode) {
+        match view {
+            ViewMode::BoardSelection => {
+                let count = filter_boards(&self.available_boards, &self.board_filter).len();
+                clamp_selection(&mut self.board_state, count);
+            }
+            ViewMode::PackageSelection => {
+                let count = self.filtered_packages().len();
+                clamp_selection(&mut self.package_state, count);
+            }
+            ViewMode::BuildOptions => {
+                clamp_selection(&mut self.build_option_state, self.build_options.len());
+            }
+            ViewMode::ExternalArtifacts => {
+                clamp_selection(&mut self.external_state, self.manifest.external.len());
+            }
+            ViewMode::MainMenu | ViewMode::DiffView => {}
+        }
+        self.view_mode = view;
+        self.focus = FocusArea::Content;
+    }
+
+    /// Go back to the main menu, leaving the view's list state untouched
+    fn return_to_main_menu(&mut self) {
+        self.view_mode = ViewMode::MainMenu;
+        self.focus = FocusArea::Categories;
+    }
+
+    /// External artifact names in display order
+    fn external_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.manifest.external.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
     /// Generate diff of changes
     fn generate_diff(&mut self) {
         self.pending_diff.clear();
 
         // Board changes
-        let old_board = self.manifest.board.name.as_deref().unwrap_or("(none)");
-        self.pending_diff.push(format!("Board: {old_board}"));
+        let old_board = self.original_board.as_deref().unwrap_or("(none)");
+        let new_board = self.manifest.board.name.as_deref().unwrap_or("(none)");
+        if old_board == new_board {
+            self.pending_diff.push(format!("Board: {old_board}"));
+        } else {
+            self.pending_diff
+                .push(format!("Board: {old_board} → {new_board}"));
+        }
 
         // Package changes
         let old_packages: HashSet<_> = self.manifest.packages.keys().cloned().collect();
@@ -1057,7 +1494,7 @@ impl ConfigTui {
         // Write manifest to file
         let manifest_path = self.project_dir.join("zigroot.toml");
         let toml_content = self.manifest.to_toml()?;
-        std::fs::write(&manifest_path, toml_content)?;
+        write_file_atomic(&manifest_path, toml_content.as_bytes())?;
 
         println!("✓ Configuration saved to zigroot.toml");
         self.has_changes = false;
@@ -1065,3 +1502,221 @@ impl ConfigTui {
         Ok(())
     }
 }
+
+/// Describe a package's effective options for the details panel
+///
+/// Options not set in the manifest show the inherited value and whether it
+/// comes from the board or the package default.
+fn package_options_summary(project_dir: &Path, manifest: &Manifest, package_name: &str) -> String {
+    let options = get_package_options(project_dir, manifest, package_name);
+    if options.is_empty() {
+        return String::new();
+    }
+
+    let mut summary = "Options:\n".to_string();
+    for (name, option) in options {
+        let line = match option.source {
+            OptionSource::Cli | OptionSource::Package => format!("  {name} = {}\n", option.value),
+            source => format!(
+                "  {name} = {} (inherited from {})\n",
+                option.value,
+                source.label()
+            ),
+        };
+        summary.push_str(&line);
+    }
+    summary.push('\n');
+    summary
+}
+
+/// Keep a list selection within `len` items, selecting nothing for an empty list
+fn clamp_selection(state: &mut ListState, len: usize) {
+    let selected = match state.selected() {
+        _ if len == 0 => None,
+        Some(i) => Some(i.min(len - 1)),
+        None => Some(0),
+    };
+    state.select(selected);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn option(name: &str, option_type: OptionType) -> BuildOption {
+        BuildOption {
+            name: name.to_string(),
+            option_type,
+            value: String::new(),
+            description: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_package_filter_keeps_selection_valid() {
+        let temp = tempfile::TempDir::new().unwrap();
+        for name in ["busybox", "dropbear", "zlib"] {
+            let dir = temp.path().join("packages").join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(
+                dir.join("package.toml"),
+                format!("[package]\nname = \"{name}\"\n"),
+            )
+            .unwrap();
+        }
+        let mut tui = ConfigTui::new(temp.path(), false, true).unwrap();
+        tui.package_state.select(Some(2));
+        assert_eq!(tui.highlighted_package().unwrap().name, "zlib");
+
+        // Cursor follows the highlighted package while it stays visible
+        tui.set_package_filter("local".to_string());
+        assert_eq!(tui.filtered_packages().len(), 3);
+        assert_eq!(tui.highlighted_package().unwrap().name, "zlib");
+
+        tui.set_package_filter("Drop".to_string());
+        assert_eq!(tui.highlighted_package().unwrap().name, "dropbear");
+
+        tui.set_package_filter("zl".to_string());
+        assert_eq!(tui.highlighted_package().unwrap().name, "zlib");
+
+        tui.set_package_filter("nothing".to_string());
+        assert!(tui.package_state.selected().is_none());
+        assert!(tui.highlighted_package().is_none());
+    }
+
+    #[test]
+    fn test_package_options_summary_shows_inherited_values() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let pkg_dir = temp.path().join("packages").join("busybox");
+        std::fs::create_dir_all(&pkg_dir).unwrap();
+        std::fs::write(
+            pkg_dir.join("package.toml"),
+            r#"[package]
+name = "busybox"
+version = "1.36.1"
+description = "Swiss army knife of embedded Linux"
+
+[source]
+url = "https://busybox.net/downloads/busybox-1.36.1.tar.bz2"
+sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+
+[options.static]
+type = "bool"
+default = false
+description = "Link statically"
+"#,
+        )
+        .unwrap();
+        let mut manifest = Manifest::default();
+        manifest.packages.insert(
+            "busybox".to_string(),
+            PackageRef {
+                version: None,
+                git: None,
+                ref_: None,
+                registry: None,
+                options: std::collections::HashMap::new(),
+            },
+        );
+
+        let summary = package_options_summary(temp.path(), &manifest, "busybox");
+        assert_eq!(
+            summary,
+            "Options:\n  static = false (inherited from default)\n\n"
+        );
+
+        manifest
+            .packages
+            .get_mut("busybox")
+            .unwrap()
+            .options
+            .insert("static".to_string(), toml::Value::Boolean(true));
+        let summary = package_options_summary(temp.path(), &manifest, "busybox");
+        assert_eq!(summary, "Options:\n  static = true\n\n");
+
+        assert!(package_options_summary(temp.path(), &manifest, "zlib").is_empty());
+    }
+
+    #[test]
+    fn test_view_switches_keep_list_positions() {
+        let temp = tempfile::TempDir::new().unwrap();
+        for name in ["busybox", "dropbear", "zlib"] {
+            let dir = temp.path().join("packages").join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(
+                dir.join("package.toml"),
+                format!("[package]\nname = \"{name}\"\n"),
+            )
+            .unwrap();
+        }
+        std::fs::write(
+            temp.path().join("zigroot.toml"),
+            "[project]\nname = \"t\"\n\n\
+             [external.a]\ntype = \"other\"\npath = \"a\"\n\n\
+             [external.b]\ntype = \"other\"\npath = \"b\"\n",
+        )
+        .unwrap();
+        let mut tui = ConfigTui::new(temp.path(), false, false).unwrap();
+
+        let visit = |tui: &mut ConfigTui, category: usize, keys: &[KeyCode]| {
+            tui.category_state.select(Some(category));
+            tui.handle_main_menu_input(KeyCode::Enter);
+            for &key in keys {
+                match tui.view_mode {
+                    ViewMode::PackageSelection => tui.handle_package_input(key),
+                    ViewMode::BuildOptions => tui.handle_build_options_input(key),
+                    ViewMode::ExternalArtifacts => tui.handle_external_input(key),
+                    _ => unreachable!(),
+                }
+            }
+            assert_eq!(tui.view_mode, ViewMode::MainMenu);
+        };
+
+        visit(&mut tui, 1, &[KeyCode::Down, KeyCode::Down, KeyCode::Esc]);
+        visit(&mut tui, 2, &[KeyCode::Down, KeyCode::Esc]);
+        visit(&mut tui, 3, &[KeyCode::Down, KeyCode::Esc]);
+
+        // Re-entering each view restores its cursor
+        visit(&mut tui, 1, &[KeyCode::Esc]);
+        visit(&mut tui, 2, &[KeyCode::Esc]);
+        visit(&mut tui, 3, &[KeyCode::Esc]);
+        assert_eq!(tui.highlighted_package().unwrap().name, "zlib");
+        assert_eq!(tui.build_option_state.selected(), Some(1));
+        assert_eq!(tui.external_state.selected(), Some(1));
+    }
+
+    #[test]
+    fn test_clamp_selection() {
+        let mut state = ListState::default();
+        state.select(Some(5));
+        clamp_selection(&mut state, 3);
+        assert_eq!(state.selected(), Some(2));
+        clamp_selection(&mut state, 0);
+        assert_eq!(state.selected(), None);
+        clamp_selection(&mut state, 2);
+        assert_eq!(state.selected(), Some(0));
+    }
+
+    #[test]
+    fn test_validate_rootfs_size() {
+        let opt = option("rootfs_size", OptionType::String);
+        assert!(opt.validate("256M").is_ok());
+        assert!(opt.validate("1G").is_ok());
+        assert!(opt.validate("banana").is_err());
+        assert!(opt.validate("256").is_err());
+    }
+
+    #[test]
+    fn test_validate_choice_and_hostname() {
+        let opt = option(
+            "image_format",
+            OptionType::Choice(vec!["ext4".to_string(), "squashfs".to_string()]),
+        );
+        assert!(opt.validate("ext4").is_ok());
+        assert!(opt.validate("btrfs").is_err());
+
+        let opt = option("hostname", OptionType::String);
+        assert!(opt.validate("device").is_ok());
+        assert!(opt.validate("").is_err());
+    }
+}
@@ -6,8 +6,8 @@ pub const MAX_DOWNLOAD_RETRIES: u32 = 3;
 /// Default number of parallel downloads
 pub const DEFAULT_PARALLEL_DOWNLOADS: usize = 4;
 
-/// Default number of parallel build jobs
-pub const DEFAULT_BUILD_JOBS: usize = 4;
+/// Configurations of each package kept in the build directory
+pub const DEFAULT_BUILD_VARIANTS: usize = 2;
 
 /// Default image format
 pub const DEFAULT_IMAGE_FORMAT: &str = "ext4";
@@ -15,6 +15,9 @@ pub const DEFAULT_IMAGE_FORMAT: &str = "ext4";
 /// Default rootfs size
 pub const DEFAULT_ROOTFS_SIZE: &str = "256M";
 
+/// Free space added to an `auto` rootfs size, in percent of the content
+pub const DEFAULT_ROOTFS_SLACK: u32 = 20;
+
 /// Default hostname
 pub const DEFAULT_HOSTNAME: &str = "zigroot";
 
@@ -4,21 +4,30 @@
 //! It handles fetching from registry, git sources, and custom registries,
 //! as well as resolving transitive dependencies and updating the lock file.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::path::Path;
 
 use crate::core::lock::{LockFile, LockedPackage, LockedPackageBuilder};
 use crate::core::manifest::{Manifest, PackageRef};
-use crate::core::resolver::{detect_version_conflict, DependencyGraph};
-use crate::registry::client::{PackageIndexEntry, RegistryClient};
+use crate::core::resolver::{resolve_constraints, Constraint, ConstraintOrigin, DependencyGraph};
+use crate::core::search::{closest_matches, did_you_mean, MAX_SUGGESTIONS};
+use crate::core::version::{parse_constraint, split_requirement};
+use crate::error::ResolverError;
+use crate::infra::filesystem::write_file_atomic;
+use crate::infra::git::resolve_remote_ref;
+use crate::registry::client::{PackageIndex, PackageIndexEntry, RegistryClient};
+use semver::Version;
 use thiserror::Error;
 
 /// Errors that can occur during package addition
 #[derive(Error, Debug)]
 pub enum AddError {
-    /// Package not found in registry
-    #[error("Package '{name}' not found in registry")]
-    PackageNotFound { name: String },
+    /// Package not found in registry, with similarly named packages
+    #[error("Package '{name}' not found in registry{}", did_you_mean(.suggestions))]
+    PackageNotFound {
+        name: String,
+        suggestions: Vec<String>,
+    },
 
     /// Version not found
     #[error("Version '{version}' not found for package '{package}'")]
@@ -47,6 +56,10 @@ pub enum AddError {
     /// Invalid package specification
     #[error("Invalid package specification: {0}")]
     InvalidSpec(String),
+
+    /// Git repository unreachable or ref not found
+    #[error("Git error: {0}")]
+    GitError(String),
 }
 
 /// Options for adding a package
@@ -54,8 +67,14 @@ pub enum AddError {
 pub struct AddOptions {
     /// Git repository URL (for --git flag)
     pub git: Option<String>,
+    /// Git branch, tag or commit (for --ref flag, overrides `url#ref`)
+    pub git_ref: Option<String>,
     /// Custom registry URL (for --registry flag)
     pub registry: Option<String>,
+    /// Only list missing dependencies instead of adding them (--no-deps)
+    pub no_deps: bool,
+    /// Contact neither the git repository nor the registry (--offline)
+    pub offline: bool,
 }
 
 /// Result of adding a package
@@ -65,10 +84,31 @@ pub struct AddResult {
     pub package_name: String,
     /// Version that was added
     pub version: String,
-    /// Transitive dependencies that were added
-    pub dependencies: Vec<String>,
+    /// Version requirement written to zigroot.toml, if any
+    pub requirement: Option<String>,
+    /// Transitive dependencies missing from the manifest
+    pub dependencies: Vec<ResolvedDependency>,
+    /// Whether the dependencies were added to the manifest (not --no-deps)
+    pub dependencies_added: bool,
+    /// Commit a git ref was resolved to and locked
+    pub git_sha: Option<String>,
     /// Whether the lock file was updated
     pub lock_updated: bool,
+    /// Non-fatal problems, e.g. the version could not be resolved offline
+    pub warnings: Vec<String>,
+}
+
+/// A dependency resolved from the registry while adding a package
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedDependency {
+    /// Package name
+    pub name: String,
+    /// Version requirement to record in the manifest
+    pub requirement: String,
+    /// Version selected from the registry index
+    pub version: String,
+    /// Package that declared the dependency
+    pub required_by: String,
 }
 
 /// Parse a package specification (name or name@version)
@@ -114,17 +154,12 @@ pub async fn add_package(
     let (package_name, requested_version) = parse_package_spec(package_spec);
 
     // Determine source and create package reference
+    let mut warnings = Vec::new();
+    let mut git_sha = None;
     let (package_ref, version, dependencies) = if let Some(git_url) = &options.git {
-        // Git source
-        let (url, git_ref) = parse_git_url(git_url);
-        let pkg_ref = PackageRef {
-            version: None,
-            git: Some(url),
-            ref_: git_ref.clone(),
-            registry: None,
-            options: HashMap::new(),
-        };
-        let ver = git_ref.unwrap_or_else(|| "HEAD".to_string());
+        // Git source, pinned to the commit its ref points to now
+        let (pkg_ref, ver, sha) = add_from_git(git_url, options, &mut warnings).await?;
+        git_sha = sha;
         (pkg_ref, ver, vec![])
     } else if let Some(registry_url) = &options.registry {
         // Custom registry source
@@ -139,45 +174,40 @@ pub async fn add_package(
         (pkg_ref, ver, vec![])
     } else {
         // Default registry source - try to fetch, but fall back to offline mode
-        let client = RegistryClient::new();
-        if let Ok((version, deps)) = resolve_from_registry(
-            &client,
+        add_from_registry(
             &package_name,
-            requested_version.as_deref(),
+            requested_version,
             &manifest,
+            options.offline,
+            &mut warnings,
         )
-        .await
-        {
-            let pkg_ref = PackageRef {
-                version: Some(version.clone()),
-                git: None,
-                ref_: None,
-                registry: None,
-                options: HashMap::new(),
-            };
-            (pkg_ref, version, deps)
-        } else {
-            // Offline mode: add package with requested version or "latest"
-            let version = requested_version.unwrap_or_else(|| "latest".to_string());
-            let pkg_ref = PackageRef {
-                version: Some(version.clone()),
-                git: None,
-                ref_: None,
-                registry: None,
-                options: HashMap::new(),
-            };
-            (pkg_ref, version, vec![])
-        }
+        .await?
     };
+    let requirement = package_ref.version.clone();
 
-    // Add package to manifest
+    // Add package, and unless --no-deps its missing dependencies, to manifest
     manifest.packages.insert(package_name.clone(), package_ref);
+    let dependencies_added = !options.no_deps;
+    if dependencies_added {
+        for dep in &dependencies {
+            manifest.packages.insert(
+                dep.name.clone(),
+                PackageRef {
+                    version: Some(dep.requirement.clone()),
+                    git: None,
+                    ref_: None,
+                    registry: None,
+                    options: HashMap::new(),
+                },
+            );
+        }
+    }
 
     // Save manifest
     let new_manifest_content = manifest
         .to_toml()
         .map_err(|e| AddError::ManifestError(e.to_string()))?;
-    std::fs::write(&manifest_path, new_manifest_content)
+    write_file_atomic(&manifest_path, new_manifest_content.as_bytes())
         .map_err(|e| AddError::IoError(e.to_string()))?;
 
     // Update lock file
@@ -188,19 +218,25 @@ pub async fn add_package(
     };
 
     // Add the main package to lock file
-    let locked_pkg = create_locked_package(&package_name, &version, options);
+    let locked_pkg = create_locked_package(
+        &package_name,
+        &version,
+        package_ref_source(options).as_deref(),
+        git_sha.as_deref(),
+    );
     lock_file.add_package(locked_pkg);
 
     // Add dependencies to lock file
-    for dep in &dependencies {
-        let (dep_name, dep_version) = parse_package_spec(dep);
-        let dep_locked = LockedPackageBuilder::new(
-            &dep_name,
-            &dep_version.unwrap_or_else(|| "latest".to_string()),
-            "pending", // Checksum will be filled during fetch
-        )
-        .build();
-        lock_file.add_package(dep_locked);
+    if dependencies_added {
+        for dep in &dependencies {
+            let dep_locked = LockedPackageBuilder::new(
+                &dep.name,
+                &dep.version,
+                "pending", // Checksum will be filled during fetch
+            )
+            .build();
+            lock_file.add_package(dep_locked);
+        }
     }
 
     // Save lock file
@@ -211,18 +247,122 @@ pub async fn add_package(
     Ok(AddResult {
         package_name,
         version,
+        requirement,
         dependencies,
+        dependencies_added,
+        git_sha,
         lock_updated: true,
+        warnings,
     })
 }
 
+/// Package reference, version and resolved commit of a git package
+///
+/// With `offline` the repository is not contacted and nothing is pinned.
+async fn add_from_git(
+    git_url: &str,
+    options: &AddOptions,
+    warnings: &mut Vec<String>,
+) -> Result<(PackageRef, String, Option<String>), AddError> {
+    let (url, url_ref) = parse_git_url(git_url);
+    let git_ref = options.git_ref.clone().or(url_ref);
+    let git_sha = if options.offline {
+        warnings.push(format!(
+            "Offline: '{}' of {url} was not resolved to a commit, so the lock file does \
+             not pin it. Add the package again when online to pin it.",
+            git_ref.as_deref().unwrap_or("HEAD")
+        ));
+        None
+    } else {
+        Some(resolve_git_commit(&url, git_ref.as_deref()).await?)
+    };
+    let pkg_ref = PackageRef {
+        version: None,
+        git: Some(url),
+        ref_: git_ref.clone(),
+        registry: None,
+        options: HashMap::new(),
+    };
+    let version = git_ref.unwrap_or_else(|| "HEAD".to_string());
+    Ok((pkg_ref, version, git_sha))
+}
+
+/// Resolve a git ref to the commit it points to, checking the repository
+/// is reachable
+async fn resolve_git_commit(url: &str, git_ref: Option<&str>) -> Result<String, AddError> {
+    let url = url.to_string();
+    let git_ref = git_ref.map(String::from);
+    // gix uses blocking network I/O
+    tokio::task::spawn_blocking(move || resolve_remote_ref(&url, git_ref.as_deref()))
+        .await
+        .map_err(|e| AddError::GitError(e.to_string()))?
+        .map_err(|e| AddError::GitError(e.to_string()))
+}
+
+/// Package reference, version and missing dependencies of a default-registry
+/// package
+///
+/// Falls back to the requested version (or "latest") with a warning when the
+/// registry can't resolve it, or is not contacted with `offline`. Dependency
+/// conflicts and names missing from the index are errors.
+async fn add_from_registry(
+    package_name: &str,
+    requested_version: Option<String>,
+    manifest: &Manifest,
+    offline: bool,
+    warnings: &mut Vec<String>,
+) -> Result<(PackageRef, String, Vec<ResolvedDependency>), AddError> {
+    let client = RegistryClient::new();
+    let resolved = if offline {
+        Err(AddError::RegistryError("offline".to_string()))
+    } else {
+        resolve_from_registry(
+            &client,
+            package_name,
+            requested_version.as_deref(),
+            manifest,
+        )
+        .await
+    };
+    match resolved {
+        Ok((version, deps)) => {
+            let pkg_ref = PackageRef {
+                version: Some(manifest_requirement(requested_version.as_deref(), &version)),
+                git: None,
+                ref_: None,
+                registry: None,
+                options: HashMap::new(),
+            };
+            Ok((pkg_ref, version, deps))
+        }
+        Err(e @ (AddError::DependencyConflict(_) | AddError::PackageNotFound { .. })) => Err(e),
+        Err(e) => {
+            // Offline mode: add package with requested version or "latest"
+            let version = requested_version.unwrap_or_else(|| "latest".to_string());
+            warnings.push(format!(
+                "Could not resolve a version of '{package_name}' from the registry ({e}); \
+                 recorded '{version}' in zigroot.toml. Run 'zigroot update {package_name}' \
+                 when online to pin it."
+            ));
+            let pkg_ref = PackageRef {
+                version: Some(version.clone()),
+                git: None,
+                ref_: None,
+                registry: None,
+                options: HashMap::new(),
+            };
+            Ok((pkg_ref, version, vec![]))
+        }
+    }
+}
+
 /// Resolve package from registry, including transitive dependencies
 async fn resolve_from_registry(
     client: &RegistryClient,
     package_name: &str,
     requested_version: Option<&str>,
     manifest: &Manifest,
-) -> Result<(String, Vec<String>), AddError> {
+) -> Result<(String, Vec<ResolvedDependency>), AddError> {
     // Fetch package index
     let index = client
         .fetch_package_index()
@@ -236,111 +376,249 @@ async fn resolve_from_registry(
         .find(|p| p.name == package_name)
         .ok_or_else(|| AddError::PackageNotFound {
             name: package_name.to_string(),
+            suggestions: closest_matches(
+                package_name,
+                index.packages.iter().map(|p| p.name.as_str()),
+                MAX_SUGGESTIONS,
+            ),
         })?;
 
     // Determine version to use
-    let version = if let Some(req_ver) = requested_version {
-        // Check if requested version exists
-        if !package_entry.versions.iter().any(|v| v.version == req_ver) {
-            return Err(AddError::VersionNotFound {
-                package: package_name.to_string(),
-                version: req_ver.to_string(),
-            });
-        }
-        req_ver.to_string()
-    } else {
-        // Use latest version
-        package_entry.latest.clone()
-    };
+    let version = select_version(package_entry, requested_version)?;
 
     // Resolve transitive dependencies
-    let dependencies = resolve_dependencies(client, package_entry, &version, manifest).await?;
+    let dependencies =
+        resolve_dependencies(client, &index, package_name, &version, manifest).await?;
 
     Ok((version, dependencies))
 }
 
-/// Resolve transitive dependencies for a package
+/// Pick the version to add from a package's index entry
+///
+/// Without a request the latest release is used. A bare version is an exact
+/// pin; any other request is a semver constraint resolved to the newest
+/// matching release.
+fn select_version(
+    package_entry: &PackageIndexEntry,
+    requested_version: Option<&str>,
+) -> Result<String, AddError> {
+    let Some(requested) = requested_version else {
+        return Ok(package_entry.latest.clone());
+    };
+    let not_found = || AddError::VersionNotFound {
+        package: package_entry.name.clone(),
+        version: requested.to_string(),
+    };
+
+    if Version::parse(requested).is_ok() {
+        return package_entry
+            .versions
+            .iter()
+            .find(|v| v.version == requested)
+            .map(|v| v.version.clone())
+            .ok_or_else(not_found);
+    }
+
+    let constraint =
+        parse_constraint(requested).map_err(|e| AddError::InvalidSpec(e.to_string()))?;
+    package_entry
+        .versions
+        .iter()
+        .filter_map(|v| Version::parse(&v.version).ok())
+        .filter(|v| constraint.matches(v))
+        .max()
+        .map(|v| v.to_string())
+        .ok_or_else(not_found)
+}
+
+/// Version requirement to record in the manifest for a resolved version
+///
+/// Exact pins and user constraints are kept as given; otherwise the resolved
+/// version becomes a caret requirement so later builds stay compatible.
+fn manifest_requirement(requested_version: Option<&str>, resolved: &str) -> String {
+    requested_version.map_or_else(|| format!("^{resolved}"), ToString::to_string)
+}
+
+/// Resolve the transitive dependencies of a package being added
+///
+/// Walks the registry metadata of every dependency not yet in the manifest
+/// and collects each constraint placed on a package. Packages already in the
+/// manifest must satisfy all constraints on them; missing ones are resolved
+/// to the newest version that does. Any unsatisfiable constraint set is a
+/// `DependencyConflict` naming the chain of packages behind each
+/// constraint. Returns the missing dependencies, sorted by name.
 async fn resolve_dependencies(
     client: &RegistryClient,
-    package_entry: &PackageIndexEntry,
-    _version: &str,
+    index: &PackageIndex,
+    package_name: &str,
+    version: &str,
     manifest: &Manifest,
-) -> Result<Vec<String>, AddError> {
-    let mut dependencies = Vec::new();
-    let mut graph = DependencyGraph::new();
+) -> Result<Vec<ResolvedDependency>, AddError> {
+    let (constraints, graph) = collect_constraints(client, package_name, version, manifest).await?;
 
-    // Fetch package metadata to get dependencies
-    let metadata = client
-        .fetch_package_metadata(&package_entry.name)
-        .await
-        .map_err(|e| AddError::RegistryError(e.to_string()))?;
+    let mut dependencies = Vec::new();
+    for (dep_name, mut required) in constraints {
+        let required_by = required[0].origin.name().to_string();
+        let mut available: Vec<String> = index
+            .packages
+            .iter()
+            .find(|p| p.name == dep_name)
+            .map(|entry| entry.versions.iter().map(|v| v.version.clone()).collect())
+            .unwrap_or_default();
+
+        let existing = if dep_name == package_name {
+            Some(version.to_string())
+        } else {
+            manifest.packages.get(&dep_name).map(|pkg_ref| {
+                pkg_ref
+                    .version
+                    .clone()
+                    .unwrap_or_else(|| "latest".to_string())
+            })
+        };
 
-    // Extract dependencies from metadata
-    let deps = extract_dependencies(&metadata);
-
-    // Add to graph
-    graph.add_package(&package_entry.name, deps.clone());
-
-    // Check for conflicts with existing packages
-    for dep in &deps {
-        let (dep_name, dep_constraint) = parse_dependency_constraint(dep);
-
-        // Check if this dependency conflicts with existing packages
-        if let Some(existing) = manifest.packages.get(&dep_name) {
-            if let Some(existing_version) = &existing.version {
-                // Check version compatibility
-                let constraints = vec![
-                    dep_constraint.unwrap_or_else(|| "*".to_string()),
-                    format!("={existing_version}"),
-                ];
-                let available = vec![existing_version.clone()];
-
-                if detect_version_conflict(&dep_name, &constraints, &available).is_err() {
-                    return Err(AddError::DependencyConflict(format!(
-                        "Package '{}' requires '{}' but version '{}' is already installed",
-                        package_entry.name, dep, existing_version
-                    )));
+        if let Some(existing) = existing {
+            // Check the pinned version against every constraint on it
+            if existing != "latest" {
+                let (_, base) = split_requirement(&existing);
+                if !available.iter().any(|v| v == base) {
+                    available.push(base.to_string());
                 }
+                required.push(Constraint::new(
+                    &semver_requirement(&existing),
+                    ConstraintOrigin::manifest(),
+                ));
             }
+            resolve_constraints(&dep_name, &required, &available)
+                .map_err(|e| AddError::DependencyConflict(conflict_message(e)))?;
+            continue;
+        }
+
+        if available.is_empty() {
+            // Not in the index: record it unpinned, like an offline add
+            dependencies.push(ResolvedDependency {
+                name: dep_name,
+                requirement: "latest".to_string(),
+                version: "latest".to_string(),
+                required_by,
+            });
+            continue;
         }
 
-        dependencies.push(dep.clone());
+        let resolved = resolve_constraints(&dep_name, &required, &available)
+            .map_err(|e| AddError::DependencyConflict(conflict_message(e)))?;
+        let explicit: Vec<&str> = required
+            .iter()
+            .map(|c| c.requirement.as_str())
+            .filter(|req| *req != "*")
+            .collect();
+        let requirement = if explicit.is_empty() {
+            format!("^{resolved}")
+        } else {
+            explicit.join(", ")
+        };
+        dependencies.push(ResolvedDependency {
+            name: dep_name,
+            requirement,
+            version: resolved,
+            required_by,
+        });
     }
 
-    // Recursively resolve transitive dependencies
-    for dep in deps {
-        let (dep_name, _) = parse_dependency_constraint(&dep);
+    if let Err(e) = graph.topological_sort() {
+        return Err(AddError::DependencyConflict(e.to_string()));
+    }
 
-        // Skip if already in manifest
-        if manifest.packages.contains_key(&dep_name) {
-            continue;
-        }
+    Ok(dependencies)
+}
 
-        // Fetch dependency's dependencies
-        if let Ok(dep_metadata) = client.fetch_package_metadata(&dep_name).await {
-            let transitive_deps = extract_dependencies(&dep_metadata);
-            for trans_dep in transitive_deps {
-                if !dependencies.contains(&trans_dep) {
-                    dependencies.push(trans_dep);
-                }
+/// Constraints on each dependency of a package being added, with where
+/// they come from, and the dependency graph walked to find them
+async fn collect_constraints(
+    client: &RegistryClient,
+    package_name: &str,
+    version: &str,
+    manifest: &Manifest,
+) -> Result<(BTreeMap<String, Vec<Constraint>>, DependencyGraph), AddError> {
+    let mut constraints: BTreeMap<String, Vec<Constraint>> = BTreeMap::new();
+    let mut graph = DependencyGraph::new();
+    // Chain of packages from the manifest entry to each visited package
+    let mut paths = HashMap::from([(package_name.to_string(), vec![package_name.to_string()])]);
+    let mut queue = VecDeque::from([package_name.to_string()]);
+
+    while let Some(name) = queue.pop_front() {
+        let metadata = match client.fetch_package_metadata(&name).await {
+            Ok(metadata) => metadata,
+            Err(e) if name == package_name => return Err(AddError::RegistryError(e.to_string())),
+            // Dependencies without metadata are added without their own deps
+            Err(_) => continue,
+        };
+        let declared_version = if name == package_name {
+            Some(version)
+        } else {
+            metadata
+                .get("package")
+                .and_then(|p| p.get("version"))
+                .and_then(|v| v.as_str())
+        };
+        let path = paths[&name].clone();
+
+        let deps = extract_dependencies(&metadata);
+        let mut dep_names = Vec::new();
+        for dep in deps {
+            let (dep_name, dep_constraint) = parse_dependency_constraint(&dep);
+            constraints
+                .entry(dep_name.clone())
+                .or_default()
+                .push(Constraint::new(
+                    dep_constraint.as_deref().unwrap_or("*"),
+                    ConstraintOrigin::dependency(&name, declared_version, path.clone()),
+                ));
+            // Packages already in the manifest brought their own dependencies
+            if !manifest.packages.contains_key(&dep_name) && !paths.contains_key(&dep_name) {
+                let mut dep_path = path.clone();
+                dep_path.push(dep_name.clone());
+                paths.insert(dep_name.clone(), dep_path);
+                queue.push_back(dep_name.clone());
             }
+            dep_names.push(dep_name);
         }
+        graph.add_package(&name, dep_names);
     }
+    Ok((constraints, graph))
+}
 
-    Ok(dependencies)
+/// Semver form of a manifest requirement, where a bare version is exact
+fn semver_requirement(requirement: &str) -> String {
+    if Version::parse(requirement).is_ok() {
+        format!("={requirement}")
+    } else {
+        requirement.to_string()
+    }
+}
+
+/// Message of a resolver error without its "Dependency conflict" prefix,
+/// which `AddError::DependencyConflict` adds itself
+fn conflict_message(error: ResolverError) -> String {
+    match error {
+        ResolverError::Conflict { message } => message,
+        other => other.to_string(),
+    }
 }
 
 /// Extract dependencies from package metadata
 fn extract_dependencies(metadata: &toml::Value) -> Vec<String> {
     let mut deps = Vec::new();
 
-    // Check for depends array in package section
+    // Check for runtime and build-only dependency arrays in package section
     if let Some(package) = metadata.get("package") {
-        if let Some(depends) = package.get("depends") {
-            if let Some(arr) = depends.as_array() {
+        for key in ["depends", "dependencies", "build_depends"] {
+            if let Some(arr) = package.get(key).and_then(|v| v.as_array()) {
                 for dep in arr {
                     if let Some(s) = dep.as_str() {
-                        deps.push(s.to_string());
+                        if !deps.contains(&s.to_string()) {
+                            deps.push(s.to_string());
+                        }
                     }
                 }
             }
@@ -366,7 +644,7 @@ fn extract_dependencies(metadata: &toml::Value) -> Vec<String> {
 }
 
 /// Parse a dependency constraint (e.g., "zlib>=1.2.0" -> ("zlib", Some(">=1.2.0")))
-fn parse_dependency_constraint(dep: &str) -> (String, Option<String>) {
+pub(crate) fn parse_dependency_constraint(dep: &str) -> (String, Option<String>) {
     // Find first version constraint character
     let constraint_chars = ['>', '<', '=', '^', '~'];
     if let Some(pos) = dep.find(|c| constraint_chars.contains(&c)) {
@@ -378,20 +656,39 @@ fn parse_dependency_constraint(dep: &str) -> (String, Option<String>) {
     }
 }
 
-/// Create a locked package entry
-fn create_locked_package(name: &str, version: &str, options: &AddOptions) -> LockedPackage {
-    let mut builder = LockedPackageBuilder::new(name, version, "pending");
-
+/// Lock file source of a package added with the given options, if not the
+/// default registry
+fn package_ref_source(options: &AddOptions) -> Option<String> {
     if let Some(git_url) = &options.git {
-        let (url, git_ref) = parse_git_url(git_url);
-        let source = format!(
+        let (url, url_ref) = parse_git_url(git_url);
+        let git_ref = options.git_ref.clone().or(url_ref);
+        Some(format!(
             "git:{}#{}",
             url,
             git_ref.unwrap_or_else(|| "HEAD".to_string())
-        );
-        builder = builder.source(&source);
-    } else if let Some(registry_url) = &options.registry {
-        builder = builder.source(&format!("registry:{registry_url}"));
+        ))
+    } else {
+        options
+            .registry
+            .as_ref()
+            .map(|registry_url| format!("registry:{registry_url}"))
+    }
+}
+
+/// Create a locked package entry
+fn create_locked_package(
+    name: &str,
+    version: &str,
+    source: Option<&str>,
+    git_sha: Option<&str>,
+) -> LockedPackage {
+    let mut builder = LockedPackageBuilder::new(name, version, "pending");
+
+    if let Some(source) = source {
+        builder = builder.source(source);
+    }
+    if let Some(sha) = git_sha {
+        builder = builder.git_sha(sha);
     }
 
     builder.build()
@@ -436,6 +733,237 @@ mod tests {
         assert_eq!(git_ref, None);
     }
 
+    fn index_entry(versions: &[&str], latest: &str) -> PackageIndexEntry {
+        PackageIndexEntry {
+            name: "busybox".to_string(),
+            description: String::new(),
+            license: None,
+            keywords: vec![],
+            versions: versions
+                .iter()
+                .map(|v| crate::registry::client::PackageVersionEntry {
+                    version: (*v).to_string(),
+                    released: None,
+                    sha256: None,
+                })
+                .collect(),
+            latest: latest.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_select_version_resolves_requests() {
+        let entry = index_entry(&["1.35.0", "1.36.0", "1.36.1", "2.0.0"], "2.0.0");
+
+        assert_eq!(select_version(&entry, None).unwrap(), "2.0.0");
+        assert_eq!(select_version(&entry, Some("1.36.0")).unwrap(), "1.36.0");
+        assert_eq!(select_version(&entry, Some("^1.35")).unwrap(), "1.36.1");
+        assert_eq!(select_version(&entry, Some("~1.35.0")).unwrap(), "1.35.0");
+        assert!(matches!(
+            select_version(&entry, Some("1.37.0")),
+            Err(AddError::VersionNotFound { .. })
+        ));
+        assert!(matches!(
+            select_version(&entry, Some(">=3")),
+            Err(AddError::VersionNotFound { .. })
+        ));
+        assert!(matches!(
+            select_version(&entry, Some("not-a-version")),
+            Err(AddError::InvalidSpec(_))
+        ));
+    }
+
+    #[test]
+    fn test_manifest_requirement() {
+        assert_eq!(manifest_requirement(None, "1.36.1"), "^1.36.1");
+        assert_eq!(manifest_requirement(Some("1.36.0"), "1.36.0"), "1.36.0");
+        assert_eq!(manifest_requirement(Some("~1.35"), "1.35.2"), "~1.35");
+    }
+
+    #[test]
+    fn test_package_ref_source_prefers_ref_flag() {
+        let options = AddOptions {
+            git: Some("https://github.com/example/repo#v1.0.0".to_string()),
+            git_ref: Some("main".to_string()),
+            registry: None,
+            no_deps: false,
+            offline: false,
+        };
+        assert_eq!(
+            package_ref_source(&options).as_deref(),
+            Some("git:https://github.com/example/repo#main")
+        );
+        assert_eq!(package_ref_source(&AddOptions::default()), None);
+    }
+
+    /// Registry serving package metadata, with an index listing `versions`
+    async fn mock_registry(
+        versions: &[(&str, &[&str])],
+        metadata: &[(&str, &str)],
+    ) -> (
+        wiremock::MockServer,
+        tempfile::TempDir,
+        RegistryClient,
+        PackageIndex,
+    ) {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, ResponseTemplate};
+
+        let server = wiremock::MockServer::start().await;
+        for (name, content) in metadata {
+            Mock::given(method("GET"))
+                .and(path(format!("/packages/{name}/metadata.toml")))
+                .respond_with(ResponseTemplate::new(200).set_body_string(*content))
+                .mount(&server)
+                .await;
+        }
+        let temp = tempfile::TempDir::new().unwrap();
+        let client =
+            RegistryClient::with_config(server.uri(), server.uri(), temp.path().to_path_buf(), 0);
+        let index = PackageIndex {
+            version: 1,
+            updated: String::new(),
+            packages: versions
+                .iter()
+                .map(|(name, versions)| {
+                    let mut entry = index_entry(versions, versions.last().unwrap());
+                    entry.name = (*name).to_string();
+                    entry
+                })
+                .collect(),
+        };
+        (server, temp, client, index)
+    }
+
+    #[tokio::test]
+    async fn test_resolve_dependencies_walks_transitive_constraints() {
+        let (_server, _temp, client, index) = mock_registry(
+            &[
+                ("nginx", &["1.25.0"]),
+                ("openssl", &["3.0.0", "3.1.0"]),
+                ("zlib", &["1.2.11", "1.2.13", "1.3.1"]),
+            ],
+            &[
+                (
+                    "nginx",
+                    "[package]\nname = \"nginx\"\ndepends = [\"zlib>=1.2.0\", \"openssl\"]\n",
+                ),
+                (
+                    "openssl",
+                    "[package]\nname = \"openssl\"\ndepends = [\"zlib<1.3\"]\n",
+                ),
+            ],
+        )
+        .await;
+
+        let deps = resolve_dependencies(&client, &index, "nginx", "1.25.0", &Manifest::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            deps,
+            vec![
+                ResolvedDependency {
+                    name: "openssl".to_string(),
+                    requirement: "^3.1.0".to_string(),
+                    version: "3.1.0".to_string(),
+                    required_by: "nginx".to_string(),
+                },
+                ResolvedDependency {
+                    name: "zlib".to_string(),
+                    requirement: ">=1.2.0, <1.3".to_string(),
+                    version: "1.2.13".to_string(),
+                    required_by: "nginx".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_dependencies_rejects_conflicting_pins() {
+        let (_server, _temp, client, index) = mock_registry(
+            &[("nginx", &["1.25.0"]), ("zlib", &["1.2.13", "1.3.1"])],
+            &[(
+                "nginx",
+                "[package]\nname = \"nginx\"\ndepends = [\"zlib<1.3\"]\n",
+            )],
+        )
+        .await;
+
+        let mut manifest = Manifest::default();
+        manifest.packages.insert(
+            "zlib".to_string(),
+            PackageRef {
+                version: Some("1.3.1".to_string()),
+                git: None,
+                ref_: None,
+                registry: None,
+                options: HashMap::new(),
+            },
+        );
+        let err = resolve_dependencies(&client, &index, "nginx", "1.25.0", &manifest)
+            .await
+            .unwrap_err();
+        let AddError::DependencyConflict(message) = err else {
+            panic!("expected a dependency conflict, got {err:?}");
+        };
+        assert!(
+            message.contains(
+                "zlib: nginx 1.25.0 requires <1.3 (via manifest › nginx), \
+                 zigroot.toml requires =1.3.1"
+            ),
+            "{message}"
+        );
+        assert!(message.contains("Hint: relax"), "{message}");
+
+        // A compatible pin is kept and not added again
+        manifest.packages.get_mut("zlib").unwrap().version = Some("^1.2.13".to_string());
+        let deps = resolve_dependencies(&client, &index, "nginx", "1.25.0", &manifest)
+            .await
+            .unwrap();
+        assert!(deps.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_dependencies_reports_diamond_conflict_chains() {
+        let (_server, _temp, client, index) = mock_registry(
+            &[
+                ("app", &["1.0.0"]),
+                ("nginx", &["1.25.0"]),
+                ("dropbear", &["2022.83.0"]),
+                ("zlib", &["1.2.13", "1.3.1"]),
+            ],
+            &[
+                (
+                    "app",
+                    "[package]\nname = \"app\"\ndepends = [\"nginx\", \"dropbear\"]\n",
+                ),
+                (
+                    "nginx",
+                    "[package]\nname = \"nginx\"\nversion = \"1.25.0\"\ndepends = [\"zlib^1.3\"]\n",
+                ),
+                (
+                    "dropbear",
+                    "[package]\nname = \"dropbear\"\nversion = \"2022.83.0\"\n\
+                     depends = [\"zlib<1.3\"]\n",
+                ),
+            ],
+        )
+        .await;
+
+        let err = resolve_dependencies(&client, &index, "app", "1.0.0", &Manifest::default())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Dependency conflict: zlib: \
+             nginx 1.25.0 requires ^1.3 (via manifest › app › nginx), \
+             dropbear 2022.83.0 requires <1.3 (via manifest › app › dropbear). \
+             Available versions: [1.2.13, 1.3.1]. \
+             Hint: relax ^1.3 of nginx (zlib 1.2.13 satisfies the rest) \
+             or <1.3 of dropbear (zlib 1.3.1 satisfies the rest)"
+        );
+    }
+
     #[test]
     fn test_parse_dependency_constraint_with_version() {
         let (name, constraint) = parse_dependency_constraint("zlib>=1.2.0");
@@ -2,10 +2,16 @@
 //!
 //! Handles parsing of board.toml files that define hardware targets.
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 
+use super::fit::FitConfig;
+use super::flash::SshFlashConfig;
+use super::libc::Libc;
+use super::manifest::BoardConfig;
 use super::package::OptionDefinition;
+use super::partition::{DiskImageConfig, PartitionSpec};
+use super::qemu::QemuConfig;
 
 /// Complete board definition
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -20,13 +26,53 @@ pub struct BoardDefinition {
     #[serde(default)]
     pub requires: Vec<String>,
 
-    /// Flash profiles
-    #[serde(default)]
+    /// Flash profiles, as `[[flash]]` or `[[flash.methods]]` entries
+    #[serde(default, deserialize_with = "deserialize_flash_profiles")]
     pub flash: Vec<FlashProfile>,
 
     /// Board options
     #[serde(default)]
     pub options: HashMap<String, OptionDefinition>,
+
+    /// Package option defaults for this board, keyed by package name
+    ///
+    /// These take priority over global options but not over options set in
+    /// the project manifest.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub package_options: HashMap<String, HashMap<String, toml::Value>>,
+
+    /// FIT image generation for U-Boot boards
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fit: Option<FitConfig>,
+
+    /// Emulation settings for `zigroot run`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qemu: Option<QemuConfig>,
+
+    /// Sub-variants of the board, as `[[variants]]` entries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<BoardVariant>,
+}
+
+/// Board option holding the selected variant in `[board.options]`
+pub const VARIANT_OPTION: &str = "variant";
+
+/// A sub-variant of a board, e.g. a memory size
+///
+/// Selecting a variant applies its option values on top of the board's
+/// option defaults.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BoardVariant {
+    /// Variant name, selected with `<board>:<variant>`
+    pub name: String,
+
+    /// Variant description
+    #[serde(default)]
+    pub description: String,
+
+    /// Board option values of the variant
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub options: HashMap<String, toml::Value>,
 }
 
 /// Board metadata
@@ -55,6 +101,10 @@ pub struct BoardMetadata {
     /// Minimum zigroot version required
     #[serde(default)]
     pub zigroot_version: Option<String>,
+
+    /// C library (musl, glibc, uclibc); defaults to the target's ABI
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub libc: Option<Libc>,
 }
 
 /// Default settings for the board
@@ -68,6 +118,14 @@ pub struct BoardDefaults {
 
     /// Default hostname
     pub hostname: String,
+
+    /// Default disk partition layout
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub partitions: Vec<PartitionSpec>,
+
+    /// Default disk image settings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_image: Option<DiskImageConfig>,
 }
 
 /// Flash profile for programming the device
@@ -90,6 +148,41 @@ pub struct FlashProfile {
     /// Required external artifacts
     #[serde(default)]
     pub requires: Vec<String>,
+
+    /// Copy the image to a running device over SSH
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh: Option<SshFlashConfig>,
+
+    /// Command template, e.g. `rkdeveloptool wl 0 {image}`
+    ///
+    /// See [`crate::core::flash`] for the placeholders.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+
+    /// Host tools the command needs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub host_tools: Vec<String>,
+}
+
+/// Accept flash profiles as a `[[flash]]` array or a `[flash]` table with
+/// `[[flash.methods]]`
+fn deserialize_flash_profiles<'de, D>(deserializer: D) -> Result<Vec<FlashProfile>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum FlashProfiles {
+        List(Vec<FlashProfile>),
+        Table {
+            #[serde(default)]
+            methods: Vec<FlashProfile>,
+        },
+    }
+
+    Ok(match FlashProfiles::deserialize(deserializer)? {
+        FlashProfiles::List(profiles) | FlashProfiles::Table { methods: profiles } => profiles,
+    })
 }
 
 impl BoardDefinition {
@@ -102,6 +195,38 @@ impl BoardDefinition {
     pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
         toml::to_string_pretty(self)
     }
+
+    /// Find a variant by name
+    pub fn variant(&self, name: &str) -> Option<&BoardVariant> {
+        self.variants.iter().find(|v| v.name == name)
+    }
+
+    /// C library selected for the board, the project's override winning
+    pub fn libc(&self, config: &BoardConfig) -> Libc {
+        config
+            .libc
+            .or(self.board.libc)
+            .unwrap_or_else(|| Libc::of_target(&self.board.target))
+    }
+
+    /// Target triple with the selected C library applied
+    pub fn target(&self, config: &BoardConfig) -> String {
+        let libc = self.libc(config);
+        if libc == Libc::of_target(&self.board.target) {
+            self.board.target.clone()
+        } else {
+            libc.apply_to(&self.board.target)
+        }
+    }
+}
+
+/// Split a board selection such as `rpi4:2gb` into board and variant
+pub fn parse_board_spec(spec: &str) -> (&str, Option<&str>) {
+    match spec.split_once(':') {
+        Some((board, variant)) if !variant.is_empty() => (board, Some(variant)),
+        Some((board, _)) => (board, None),
+        None => (spec, None),
+    }
 }
 
 impl TryFrom<toml::Value> for BoardDefinition {
@@ -217,6 +342,81 @@ hostname = "minimal"
         assert!(board.flash.is_empty());
         assert!(board.requires.is_empty());
         assert!(board.options.is_empty());
+        assert!(board.package_options.is_empty());
+    }
+
+    #[test]
+    fn test_board_libc_selection() {
+        let mut board = BoardDefinition::from_toml(
+            r#"
+[board]
+name = "armhf"
+description = "ARM board"
+target = "arm-linux-musleabihf"
+cpu = "cortex-a7"
+
+[defaults]
+image_format = "ext4"
+rootfs_size = "128M"
+hostname = "armhf"
+"#,
+        )
+        .unwrap();
+        let mut config = BoardConfig::default();
+        assert_eq!(board.libc(&config), Libc::Musl);
+        assert_eq!(board.target(&config), "arm-linux-musleabihf");
+
+        board.board.libc = Some(Libc::Glibc);
+        assert_eq!(board.libc(&config), Libc::Glibc);
+        assert_eq!(board.target(&config), "arm-linux-gnueabihf");
+
+        // The project's [board] section wins
+        config.libc = Some(Libc::Uclibc);
+        assert_eq!(board.target(&config), "arm-linux-uclibcgnueabihf");
+        config.libc = Some(Libc::Musl);
+        assert_eq!(board.target(&config), "arm-linux-musleabihf");
+
+        assert!(
+            BoardDefinition::from_toml(&board.to_toml().unwrap().replace("glibc", "newlib"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_board_variants() {
+        let board = BoardDefinition::from_toml(
+            r#"
+[board]
+name = "rpi4"
+description = "Raspberry Pi 4"
+target = "aarch64-linux-musl"
+cpu = "cortex_a72"
+
+[defaults]
+image_format = "ext4"
+rootfs_size = "512M"
+hostname = "rpi4"
+
+[[variants]]
+name = "2gb"
+description = "2 GB RAM"
+options = { memory = "2G" }
+
+[[variants]]
+name = "8gb"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(board.variants.len(), 2);
+        let variant = board.variant("2gb").expect("2gb variant");
+        assert_eq!(variant.options["memory"].as_str(), Some("2G"));
+        assert!(board.variant("8gb").unwrap().options.is_empty());
+        assert!(board.variant("4gb").is_none());
+
+        assert_eq!(parse_board_spec("rpi4:2gb"), ("rpi4", Some("2gb")));
+        assert_eq!(parse_board_spec("rpi4"), ("rpi4", None));
+        assert_eq!(parse_board_spec("rpi4:"), ("rpi4", None));
     }
 
     // ============================================
@@ -234,11 +434,14 @@ hostname = "minimal"
                 features: vec!["neon".to_string()],
                 kernel: None,
                 zigroot_version: None,
+                libc: None,
             },
             defaults: BoardDefaults {
                 image_format: "ext4".to_string(),
                 rootfs_size: "256M".to_string(),
                 hostname: "test".to_string(),
+                partitions: Vec::new(),
+                disk_image: None,
             },
             requires: vec!["busybox".to_string()],
             flash: vec![FlashProfile {
@@ -247,8 +450,15 @@ hostname = "minimal"
                 script: None,
                 tool: Some("dd".to_string()),
                 requires: vec![],
+                ssh: None,
+                command: None,
+                host_tools: vec![],
             }],
             options: HashMap::new(),
+            package_options: HashMap::new(),
+            fit: None,
+            qemu: None,
+            variants: Vec::new(),
         };
 
         let toml_str = board.to_toml().expect("Failed to serialize");
@@ -433,6 +643,38 @@ script = "flash.sh"
         assert_eq!(board.flash[0].tool, None);
     }
 
+    #[test]
+    fn test_flash_methods_table() {
+        let toml_content = r#"
+[board]
+name = "test-board"
+description = "Test board"
+target = "aarch64-linux-musl"
+cpu = "cortex-a53"
+
+[defaults]
+image_format = "ext4"
+rootfs_size = "256M"
+hostname = "test"
+
+[[flash.methods]]
+name = "rockusb"
+description = "Write over USB with rkdeveloptool"
+host_tools = ["rkdeveloptool"]
+command = "rkdeveloptool wl 0 {image}"
+"#;
+
+        let board = BoardDefinition::from_toml(toml_content).expect("Failed to parse");
+
+        assert_eq!(board.flash.len(), 1);
+        assert_eq!(board.flash[0].name, "rockusb");
+        assert_eq!(board.flash[0].host_tools, vec!["rkdeveloptool"]);
+        assert_eq!(
+            board.flash[0].command.as_deref(),
+            Some("rkdeveloptool wl 0 {image}")
+        );
+    }
+
     // ============================================
     // Board options tests
     // ============================================
@@ -554,15 +796,22 @@ description = "Enable debug mode"
                             features: vec![],
                             kernel: None,
                             zigroot_version: None,
+                            libc: None,
                         },
                         defaults: BoardDefaults {
                             image_format,
                             rootfs_size,
                             hostname,
+                            partitions: Vec::new(),
+                            disk_image: None,
                         },
                         requires: vec![],
                         flash: vec![],
                         options: HashMap::new(),
+                        package_options: HashMap::new(),
+                        fit: None,
+                        qemu: None,
+                        variants: Vec::new(),
                     }
                 },
             )
@@ -605,15 +854,22 @@ description = "Enable debug mode"
                     features: vec![],
                     kernel: None,
                     zigroot_version: None,
+                    libc: None,
                 },
                 defaults: BoardDefaults {
                     image_format: "ext4".to_string(),
                     rootfs_size: "256M".to_string(),
                     hostname: "test".to_string(),
+                    partitions: Vec::new(),
+                    disk_image: None,
                 },
                 requires: vec![],
                 flash: vec![],
                 options: HashMap::new(),
+                package_options: HashMap::new(),
+                fit: None,
+                qemu: None,
+                variants: Vec::new(),
             };
 
             let toml_str = board.to_toml().expect("Should serialize");
@@ -636,15 +892,22 @@ description = "Enable debug mode"
                     features: vec![],
                     kernel: None,
                     zigroot_version: None,
+                    libc: None,
                 },
                 defaults: BoardDefaults {
                     image_format: "ext4".to_string(),
                     rootfs_size: "256M".to_string(),
                     hostname: "test".to_string(),
+                    partitions: Vec::new(),
+                    disk_image: None,
                 },
                 requires: vec![],
                 flash: vec![],
                 options: HashMap::new(),
+                package_options: HashMap::new(),
+                fit: None,
+                qemu: None,
+                variants: Vec::new(),
             };
 
             let toml_str = board.to_toml().expect("Should serialize");
@@ -1,18 +1,83 @@
 //! Build environment setup
 //!
 //! Provides build environment configuration for package compilation.
-//! Sets up environment variables like CC, TARGET, JOBS, SRCDIR, DESTDIR, PREFIX.
+//! Sets up environment variables like CC, TARGET, LIBC, JOBS, SRCDIR, DESTDIR, PREFIX.
+//! `MAKEFLAGS` carries the job count to `make` invocations of build steps.
+//! The staging trees of a package's dependencies form its build sysroot:
+//! `CFLAGS`, `LDFLAGS` and `PKG_CONFIG_LIBDIR` point into them.
 //!
 //! **Validates: Requirements 18.17-18.27**
 
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::core::libc::Libc;
+use crate::core::package::PackageBuildConfig;
+
+/// Number of parallel jobs to use
+///
+/// `None` and 0 mean every logical CPU.
+pub fn resolve_jobs(jobs: Option<usize>) -> usize {
+    match jobs {
+        Some(jobs) if jobs > 0 => jobs,
+        _ => std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get),
+    }
+}
+
+/// GCC cross-compiler prefix for a target triple (e.g., "arm-linux-gnueabihf-")
+pub fn gcc_prefix(target: &str) -> String {
+    format!("{target}-")
+}
+
+/// Whether a package is built with the GCC cross-toolchain
+///
+/// Besides packages asking for `toolchain = "gcc"`, this covers boards
+/// whose C library Zig cannot provide (uClibc, glibc on exotic
+/// architectures).
+pub fn needs_gcc(build: &PackageBuildConfig, board_target: &str) -> bool {
+    build.uses_gcc() || !Libc::of_target(board_target).zig_provides(board_target)
+}
+
+/// Compiler flags for a target triple, CPU and CPU features
+///
+/// These are the flags zigroot derives for `zig cc`, so a compile can be
+/// reproduced by hand with `zig cc <flags> ...`. Zig spells CPU models and
+/// features with underscores (e.g., `-mcpu=cortex_a7+neon`).
+pub fn compiler_flags(target: &str, cpu: &str, features: &[String]) -> Vec<String> {
+    let mut flags = vec!["-target".to_string(), target.to_string()];
+
+    if cpu != "generic" || !features.is_empty() {
+        let mut mcpu = format!("-mcpu={}", cpu.replace('-', "_"));
+        for feature in features {
+            let (sign, name) = match feature.strip_prefix('-') {
+                Some(name) => ('-', name),
+                None => ('+', feature.trim_start_matches('+')),
+            };
+            mcpu.push(sign);
+            mcpu.push_str(&name.replace('-', "_"));
+        }
+        flags.push(mcpu);
+    }
+
+    // 32-bit ARM float ABI follows the triple's ABI suffix
+    let arch = target.split('-').next().unwrap_or(target);
+    if arch.starts_with("arm") || arch.starts_with("thumb") {
+        let float_abi = if target.ends_with("hf") {
+            "hard"
+        } else {
+            "soft"
+        };
+        flags.push(format!("-mfloat-abi={float_abi}"));
+    }
+
+    flags
+}
+
 /// Build environment for a package.
 ///
 /// For Zig-based builds (the default), cross-compilation is handled internally
 /// by Zig - we just set CC="zig cc -target <target>" and Zig handles everything.
-/// No sysroot, no separate AR/LD/RANLIB needed.
+/// No toolchain sysroot is needed; AR and RANLIB are Zig's own.
 ///
 /// For GCC-based builds (kernel, bootloader), we set up a traditional
 /// cross-compilation environment with the downloaded toolchain.
@@ -38,6 +103,11 @@ pub struct BuildEnvironment {
     pub jobs: usize,
     /// Additional environment variables
     pub extra_env: HashMap<String, String>,
+    /// Directories put in front of `PATH` (provisioned host tools)
+    pub tool_paths: Vec<PathBuf>,
+    /// Staging trees of the dependencies, searched for headers, libraries
+    /// and pkg-config files
+    pub sysroots: Vec<PathBuf>,
 }
 
 impl BuildEnvironment {
@@ -52,8 +122,10 @@ impl BuildEnvironment {
             srcdir,
             destdir,
             prefix: "/usr".to_string(),
-            jobs: num_cpus::get(),
+            jobs: resolve_jobs(None),
             extra_env: HashMap::new(),
+            tool_paths: Vec::new(),
+            sysroots: Vec::new(),
         }
     }
 
@@ -74,8 +146,35 @@ impl BuildEnvironment {
             srcdir,
             destdir,
             prefix: "/usr".to_string(),
-            jobs: num_cpus::get(),
+            jobs: resolve_jobs(None),
             extra_env: HashMap::new(),
+            tool_paths: Vec::new(),
+            sysroots: Vec::new(),
+        }
+    }
+
+    /// Create environment for a package build
+    ///
+    /// Packages with `toolchain = "gcc"`, and every package of a board
+    /// whose libc Zig cannot provide, are compiled with the GCC
+    /// cross-toolchain for the board's triple (or the package's own
+    /// `[build.toolchain]` target); all others use Zig.
+    pub fn for_package(
+        build: &PackageBuildConfig,
+        board_target: &str,
+        cpu: &str,
+        srcdir: PathBuf,
+        destdir: PathBuf,
+    ) -> Self {
+        if needs_gcc(build, board_target) {
+            let target = build
+                .toolchain
+                .as_ref()
+                .and_then(|t| t.target())
+                .unwrap_or(board_target);
+            Self::for_gcc(&gcc_prefix(target), target, cpu, srcdir, destdir)
+        } else {
+            Self::for_zig(board_target, cpu, srcdir, destdir)
         }
     }
 
@@ -100,6 +199,40 @@ impl BuildEnvironment {
         self
     }
 
+    /// Put a directory of host tools in front of `PATH`
+    #[must_use]
+    pub fn with_tool_path(mut self, dir: PathBuf) -> Self {
+        self.tool_paths.push(dir);
+        self
+    }
+
+    /// Add the staging tree of a dependency to the build sysroot
+    #[must_use]
+    pub fn with_sysroot(mut self, dir: PathBuf) -> Self {
+        self.sysroots.push(dir);
+        self
+    }
+
+    /// Whether the compilers are Zig's
+    pub fn uses_zig(&self) -> bool {
+        self.cc.starts_with("zig ")
+    }
+
+    /// `PATH` with the host tool directories in front of the inherited one,
+    /// `None` without host tools
+    pub fn search_path(&self) -> Option<std::ffi::OsString> {
+        if self.tool_paths.is_empty() {
+            return None;
+        }
+        let inherited = std::env::var_os("PATH").unwrap_or_default();
+        let dirs = self
+            .tool_paths
+            .iter()
+            .cloned()
+            .chain(std::env::split_paths(&inherited));
+        std::env::join_paths(dirs).ok()
+    }
+
     /// Convert to environment variable map for process execution
     pub fn to_env_map(&self) -> HashMap<String, String> {
         let mut env = HashMap::new();
@@ -108,15 +241,62 @@ impl BuildEnvironment {
         env.insert("CC".to_string(), self.cc.clone());
         env.insert("CXX".to_string(), self.cxx.clone());
         env.insert("TARGET".to_string(), self.target.clone());
+        env.insert(
+            "LIBC".to_string(),
+            Libc::of_target(&self.target).to_string(),
+        );
         env.insert("CPU".to_string(), self.cpu.clone());
         env.insert("SRCDIR".to_string(), self.srcdir.display().to_string());
         env.insert("DESTDIR".to_string(), self.destdir.display().to_string());
         env.insert("PREFIX".to_string(), self.prefix.clone());
         env.insert("JOBS".to_string(), self.jobs.to_string());
+        env.insert("MAKEFLAGS".to_string(), format!("-j{}", self.jobs));
 
-        // Optional archiver
+        // Archiver and index tool of the toolchain
         if let Some(ref ar) = self.ar {
             env.insert("AR".to_string(), ar.clone());
+            if let Some(prefix) = ar.strip_suffix("ar") {
+                env.insert("RANLIB".to_string(), format!("{prefix}ranlib"));
+            }
+        } else if self.uses_zig() {
+            env.insert("AR".to_string(), "zig ar".to_string());
+            env.insert("RANLIB".to_string(), "zig ranlib".to_string());
+        }
+
+        // Target CPU flags and the build sysroot
+        let mut cflags = Vec::new();
+        if self.uses_zig() {
+            cflags.extend(
+                compiler_flags(&self.target, &self.cpu, &[])
+                    .into_iter()
+                    .skip(2),
+            );
+        }
+        let mut ldflags = Vec::new();
+        let mut pkg_config = Vec::new();
+        for sysroot in &self.sysroots {
+            let usr = sysroot.join(self.prefix.trim_start_matches('/'));
+            cflags.push(format!("-I{}", usr.join("include").display()));
+            ldflags.push(format!("-L{}", usr.join("lib").display()));
+            pkg_config.push(usr.join("lib/pkgconfig"));
+            pkg_config.push(usr.join("share/pkgconfig"));
+        }
+        if !cflags.is_empty() {
+            env.insert("CFLAGS".to_string(), cflags.join(" "));
+        }
+        if !ldflags.is_empty() {
+            env.insert("LDFLAGS".to_string(), ldflags.join(" "));
+        }
+        if !self.sysroots.is_empty() {
+            let join = |dirs: &[PathBuf]| {
+                std::env::join_paths(dirs)
+                    .map(|paths| paths.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            };
+            env.insert("SYSROOT_DIRS".to_string(), join(&self.sysroots));
+            // Only the dependencies' .pc files, never the host's
+            env.insert("PKG_CONFIG_LIBDIR".to_string(), join(&pkg_config));
+            env.insert("PKG_CONFIG_PATH".to_string(), String::new());
         }
 
         // Extra environment variables
@@ -209,6 +389,99 @@ mod tests {
         assert_eq!(env.target, "arm-linux-gnueabihf");
     }
 
+    #[test]
+    fn test_compiler_flags() {
+        assert_eq!(
+            compiler_flags("x86_64-linux-musl", "generic", &[]),
+            vec!["-target", "x86_64-linux-musl"]
+        );
+        assert_eq!(
+            compiler_flags(
+                "arm-linux-musleabihf",
+                "cortex-a7",
+                &["neon".to_string(), "-thumb-mode".to_string()]
+            ),
+            vec![
+                "-target",
+                "arm-linux-musleabihf",
+                "-mcpu=cortex_a7+neon-thumb_mode",
+                "-mfloat-abi=hard"
+            ]
+        );
+        assert_eq!(
+            compiler_flags("arm-linux-musleabi", "generic", &[]),
+            vec!["-target", "arm-linux-musleabi", "-mfloat-abi=soft"]
+        );
+        assert_eq!(
+            compiler_flags("aarch64-linux-musl", "cortex-a53", &[]),
+            vec!["-target", "aarch64-linux-musl", "-mcpu=cortex_a53"]
+        );
+    }
+
+    #[test]
+    fn test_package_environment_selects_toolchain() {
+        use crate::core::package::ToolchainConfig;
+
+        let mut build = PackageBuildConfig::default();
+        let env = BuildEnvironment::for_package(
+            &build,
+            "arm-linux-musleabihf",
+            "cortex-a7",
+            PathBuf::from("/src"),
+            PathBuf::from("/dest"),
+        );
+        assert_eq!(env.cc, "zig cc -target arm-linux-musleabihf");
+
+        build.toolchain = Some(ToolchainConfig::Name("gcc".to_string()));
+        let env = BuildEnvironment::for_package(
+            &build,
+            "arm-linux-musleabihf",
+            "cortex-a7",
+            PathBuf::from("/src"),
+            PathBuf::from("/dest"),
+        );
+        assert_eq!(env.cc, "arm-linux-musleabihf-gcc");
+        assert_eq!(env.ar, Some("arm-linux-musleabihf-ar".to_string()));
+
+        build.toolchain = Some(ToolchainConfig::Table {
+            kind: "gcc".to_string(),
+            target: Some("arm-linux-gnueabihf".to_string()),
+        });
+        let env = BuildEnvironment::for_package(
+            &build,
+            "arm-linux-musleabihf",
+            "cortex-a7",
+            PathBuf::from("/src"),
+            PathBuf::from("/dest"),
+        );
+        assert_eq!(env.cc, "arm-linux-gnueabihf-gcc");
+        assert_eq!(env.target, "arm-linux-gnueabihf");
+    }
+
+    #[test]
+    fn test_libc_without_zig_support_uses_gcc() {
+        let build = PackageBuildConfig::default();
+        let env = BuildEnvironment::for_package(
+            &build,
+            "arm-linux-uclibcgnueabihf",
+            "cortex-a7",
+            PathBuf::from("/src"),
+            PathBuf::from("/dest"),
+        );
+        assert_eq!(env.cc, "arm-linux-uclibcgnueabihf-gcc");
+        assert_eq!(env.to_env_map()["LIBC"], "uclibc");
+
+        let env = BuildEnvironment::for_package(
+            &build,
+            "arm-linux-gnueabihf",
+            "cortex-a7",
+            PathBuf::from("/src"),
+            PathBuf::from("/dest"),
+        );
+        assert_eq!(env.cc, "zig cc -target arm-linux-gnueabihf");
+        assert_eq!(env.to_env_map()["LIBC"], "glibc");
+    }
+
     #[test]
     fn test_env_map_contains_required_variables() {
         let env = BuildEnvironment::for_zig(
@@ -279,6 +552,14 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_resolve_jobs() {
+        let cpus = std::thread::available_parallelism().unwrap().get();
+        assert_eq!(resolve_jobs(Some(3)), 3);
+        assert_eq!(resolve_jobs(Some(0)), cpus);
+        assert_eq!(resolve_jobs(None), cpus);
+    }
+
     #[test]
     fn test_validation_fails_for_zero_jobs() {
         let mut env = BuildEnvironment::for_zig(
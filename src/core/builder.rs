@@ -2,6 +2,26 @@
 //!
 //! Coordinates the build process across multiple packages.
 
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::core::build_env::BuildEnvironment;
+use crate::core::flash::load_board_definition;
+use crate::core::libc::Libc;
+use crate::core::manifest::Manifest;
+use crate::core::package::{BuildStep, PackageBuildConfig, PackageMetadata};
+use crate::core::partition::{DiskLayout, Partition};
+use crate::core::permissions::PermissionTable;
+use crate::core::reproducible;
+use crate::error::{BuildError, FilesystemError};
+use crate::infra::download::compute_checksum;
+use crate::infra::filesystem::{write_file_atomic, FilesystemSpace};
+use crate::infra::hook;
+use crate::infra::namespace::{is_system_path, NamespaceSandbox, NamespaceTool};
+use crate::infra::sandbox::MountConfig;
+
 /// Build orchestrator state
 #[derive(Debug, Default)]
 pub struct BuildOrchestrator {
@@ -35,4 +55,1699 @@ impl BuildOrchestrator {
     pub fn build_order(&self) -> &[String] {
         &self.build_order
     }
+
+    /// Packages whose artifacts are installed into the rootfs
+    ///
+    /// Selected packages are installed together with their runtime
+    /// dependencies. A selected package that other selected packages only
+    /// list in `build_depends` stays in the build sysroot. Packages without
+    /// a known definition are treated as having no dependencies. The result
+    /// follows the build order.
+    pub fn rootfs_packages(&self, definitions: &HashMap<String, PackageMetadata>) -> Vec<String> {
+        let build_only: HashSet<&str> = self
+            .packages
+            .iter()
+            .filter_map(|name| definitions.get(name))
+            .flat_map(|def| def.build_depends.iter().map(String::as_str))
+            .collect();
+        let runtime_needed: HashSet<String> = self
+            .packages
+            .iter()
+            .filter_map(|name| definitions.get(name))
+            .flat_map(PackageMetadata::runtime_dependencies)
+            .collect();
+
+        let mut installed = HashSet::new();
+        let mut stack: Vec<String> = self
+            .packages
+            .iter()
+            .filter(|name| {
+                !build_only.contains(name.as_str()) || runtime_needed.contains(name.as_str())
+            })
+            .cloned()
+            .collect();
+
+        while let Some(name) = stack.pop() {
+            if !installed.insert(name.clone()) {
+                continue;
+            }
+            if let Some(def) = definitions.get(&name) {
+                stack.extend(def.runtime_dependencies());
+            }
+        }
+
+        let mut ordered: Vec<String> = self
+            .build_order
+            .iter()
+            .filter(|name| installed.contains(*name))
+            .cloned()
+            .collect();
+        let mut rest: Vec<String> = installed
+            .into_iter()
+            .filter(|name| !self.build_order.contains(name))
+            .collect();
+        rest.sort();
+        ordered.extend(rest);
+        ordered
+    }
+}
+
+/// Packages that failed to build, and their dependents that were skipped
+///
+/// With `--keep-going` the build continues past a failed package; its
+/// dependents are skipped while independent packages still build.
+#[derive(Debug, Default)]
+pub struct BuildFailures {
+    /// Failed packages with their errors, in build order
+    failed: Vec<(String, String)>,
+    /// Skipped packages with the failed dependency they waited for
+    skipped: Vec<(String, String)>,
+}
+
+impl BuildFailures {
+    /// Record a package that failed to build
+    pub fn record_failure(&mut self, package: &str, error: String) {
+        self.failed.push((package.to_string(), error));
+    }
+
+    /// Record a package skipped because `dependency` failed
+    pub fn record_skipped(&mut self, package: &str, dependency: &str) {
+        self.skipped
+            .push((package.to_string(), dependency.to_string()));
+    }
+
+    /// First failed package among `dependencies`, if any
+    ///
+    /// `dependencies` are transitive, so a dependent of a skipped package
+    /// is blocked by the same failure.
+    pub fn failed_dependency(&self, dependencies: &HashSet<String>) -> Option<&str> {
+        self.failed
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .find(|name| dependencies.contains(*name))
+    }
+
+    /// Whether every package built
+    pub fn is_empty(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Error reporting all failures together
+    pub fn into_error(self) -> BuildError {
+        BuildError::PackagesFailed {
+            failed: self.failed,
+            skipped: self.skipped,
+        }
+    }
+}
+
+/// Directory (relative to the build directory) holding per-package install trees
+pub const STAGING_DIR: &str = "destdir";
+
+/// Directory (relative to the build directory) listing the files each
+/// package installed into the rootfs
+pub const INSTALLED_DIR: &str = "installed";
+
+/// Install the staged files of the given packages into the rootfs directory
+///
+/// Each package's files are expected under `<staging_root>/<package>/`.
+/// Packages without a staging directory are skipped. Symbolic links are
+/// recreated, not followed. The files each package installed are recorded in
+/// `<installed_dir>/<package>.files`, one path relative to the rootfs per
+/// line. Files recorded for packages not in `packages` are removed from the
+/// rootfs first. Returns the packages that were installed.
+pub fn install_into_rootfs(
+    staging_root: &Path,
+    rootfs_dir: &Path,
+    installed_dir: &Path,
+    packages: &[String],
+) -> std::io::Result<Vec<String>> {
+    let mut installed = Vec::new();
+    std::fs::create_dir_all(installed_dir)?;
+    remove_stale_packages(rootfs_dir, installed_dir, packages)?;
+
+    for package in packages {
+        let staged = staging_root.join(package);
+        if !staged.is_dir() {
+            continue;
+        }
+
+        let mut files = String::new();
+        for entry in walkdir::WalkDir::new(&staged).sort_by_file_name() {
+            let entry = entry?;
+            let Ok(relative) = entry.path().strip_prefix(&staged) else {
+                continue;
+            };
+            let dest = rootfs_dir.join(relative);
+            if entry.file_type().is_dir() {
+                std::fs::create_dir_all(&dest)?;
+            } else {
+                if let Some(parent) = dest.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                // Never write through a link a previous install left here
+                remove_path(&dest)?;
+                if entry.file_type().is_symlink() {
+                    // Links are recreated as they are: their targets are
+                    // paths in the image, not on the build host
+                    let target = std::fs::read_link(entry.path())?;
+                    std::os::unix::fs::symlink(target, &dest)?;
+                } else {
+                    std::fs::copy(entry.path(), &dest)?;
+                }
+                files.push_str(&relative.to_string_lossy());
+                files.push('\n');
+            }
+        }
+
+        std::fs::write(installed_dir.join(format!("{package}.files")), files)?;
+        installed.push(package.clone());
+    }
+
+    Ok(installed)
+}
+
+/// Remove the rootfs files of packages that were installed by an earlier
+/// build but are not in `packages` anymore, along with their file lists
+fn remove_stale_packages(
+    rootfs_dir: &Path,
+    installed_dir: &Path,
+    packages: &[String],
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(installed_dir)? {
+        let list = entry?.path();
+        let Some(package) = list
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".files"))
+        else {
+            continue;
+        };
+        if packages.iter().any(|name| name == package) {
+            continue;
+        }
+        for file in installed_files(installed_dir, package)? {
+            let path = rootfs_dir.join(&file);
+            remove_path(&path)?;
+            // Drop the directories the package left empty
+            for dir in path.ancestors().skip(1) {
+                if dir == rootfs_dir || std::fs::remove_dir(dir).is_err() {
+                    break;
+                }
+            }
+        }
+        remove_path(&list)?;
+    }
+    Ok(())
+}
+
+/// Files a package installed into the rootfs, relative to the rootfs
+///
+/// Returns an empty list if the package was never installed.
+pub fn installed_files(installed_dir: &Path, package: &str) -> std::io::Result<Vec<PathBuf>> {
+    match std::fs::read_to_string(installed_dir.join(format!("{package}.files"))) {
+        Ok(content) => Ok(content.lines().map(PathBuf::from).collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Directory (relative to the build directory) holding the completion
+/// stamps of built packages
+pub const STAMPS_DIR: &str = "stamps";
+
+/// Suffix of a staging tree that is still being written
+pub const PARTIAL_SUFFIX: &str = ".partial";
+
+/// File (relative to the build directory) naming the package an interrupted
+/// build stopped at
+pub const INTERRUPTED_FILE: &str = "interrupted";
+
+/// Mark a package as built with the given cache key
+///
+/// The stamp is written atomically, so an interrupted build never leaves a
+/// stamp that looks complete.
+pub fn write_stamp(stamps_dir: &Path, package: &str, key: &str) -> Result<(), FilesystemError> {
+    write_file_atomic(
+        &stamps_dir.join(format!("{package}.stamp")),
+        format!("{key}\n").as_bytes(),
+    )
+}
+
+/// Whether a package has a stamp for exactly this cache key
+///
+/// A stamp from another version or configuration is stale.
+pub fn stamp_is_current(stamps_dir: &Path, package: &str, key: &str) -> bool {
+    std::fs::read_to_string(stamps_dir.join(format!("{package}.stamp")))
+        .is_ok_and(|content| content.lines().next() == Some(key))
+}
+
+/// Move the staging tree of a package aside for a build
+///
+/// The package installs into the returned `<package>.partial` directory,
+/// which holds the tree of the previous build, if any. [`commit_staging`]
+/// renames it back into place once the build succeeded; until then the
+/// package has no staging tree.
+pub fn begin_staging(staging_root: &Path, package: &str) -> std::io::Result<PathBuf> {
+    let staged = staging_root.join(package);
+    let partial = staging_root.join(format!("{package}{PARTIAL_SUFFIX}"));
+    remove_path(&partial)?;
+    if staged.is_dir() {
+        std::fs::rename(&staged, &partial)?;
+    } else {
+        std::fs::create_dir_all(&partial)?;
+    }
+    Ok(partial)
+}
+
+/// Replace the staging tree of a package with its finished partial tree
+pub fn commit_staging(staging_root: &Path, package: &str) -> std::io::Result<()> {
+    let staged = staging_root.join(package);
+    remove_path(&staged)?;
+    std::fs::rename(
+        staging_root.join(format!("{package}{PARTIAL_SUFFIX}")),
+        staged,
+    )
+}
+
+/// Record the package an interrupted build stopped at
+pub fn record_interrupted(build_dir: &Path, package: &str) -> Result<(), FilesystemError> {
+    write_file_atomic(
+        &build_dir.join(INTERRUPTED_FILE),
+        format!("{package}\n").as_bytes(),
+    )
+}
+
+/// Leftovers of an interrupted build, cleaned up by [`recover_build_dir`]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Recovery {
+    /// Package the interrupted build stopped at, if it was recorded
+    pub interrupted: Option<String>,
+    /// Partial trees and files that were removed
+    pub removed: Vec<PathBuf>,
+}
+
+/// Clean up after an interrupted build
+///
+/// Partial staging trees and half-written stamps are removed, and so are
+/// the stamps of the packages they belong to and of the package the build
+/// stopped at. Completed packages keep their stamps, so the next build
+/// resumes after them.
+pub fn recover_build_dir(build_dir: &Path) -> std::io::Result<Recovery> {
+    let stamps_dir = build_dir.join(STAMPS_DIR);
+    let mut recovery = Recovery::default();
+    let mut stale_stamps = Vec::new();
+
+    let record = build_dir.join(INTERRUPTED_FILE);
+    if let Ok(content) = std::fs::read_to_string(&record) {
+        let package = content.trim().to_string();
+        if !package.is_empty() {
+            stale_stamps.push(stamps_dir.join(format!("{package}.stamp")));
+            recovery.interrupted = Some(package);
+        }
+        remove_path(&record)?;
+    }
+
+    let leftovers = [
+        (build_dir.join(STAGING_DIR), PARTIAL_SUFFIX),
+        (stamps_dir.clone(), ".tmp"),
+    ];
+    for (dir, suffix) in leftovers {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(stem) = name.strip_suffix(suffix) else {
+                continue;
+            };
+            if suffix == PARTIAL_SUFFIX {
+                stale_stamps.push(stamps_dir.join(format!("{stem}.stamp")));
+            }
+            recovery.removed.push(entry.path());
+        }
+    }
+    recovery
+        .removed
+        .extend(stale_stamps.into_iter().filter(|stamp| stamp.exists()));
+    recovery.removed.sort();
+    recovery.removed.dedup();
+    for path in &recovery.removed {
+        remove_path(path)?;
+    }
+    Ok(recovery)
+}
+
+/// Remove a file or directory tree, if it exists
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    let result = match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => std::fs::remove_dir_all(path),
+        Ok(_) => std::fs::remove_file(path),
+        Err(e) => Err(e),
+    };
+    match result {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Set once the user asked to stop the build (Ctrl+C)
+static INTERRUPT: AtomicBool = AtomicBool::new(false);
+
+/// Ask the running build to stop
+///
+/// The current build step is stopped, files being written are finished, and
+/// the build fails with [`BuildError::Interrupted`].
+pub fn request_interrupt() {
+    INTERRUPT.store(true, Ordering::SeqCst);
+}
+
+/// Whether the build was asked to stop
+pub fn interrupt_requested() -> bool {
+    INTERRUPT.load(Ordering::SeqCst)
+}
+
+/// Directory (relative to the build directory) holding the empty mount
+/// points of package sandboxes
+pub const SANDBOX_DIR: &str = "sandbox";
+
+/// Sandbox for the build of a package
+///
+/// Only the package definition and the staging trees of `dependencies`
+/// (read-only), the package's source and staging directories (read-write)
+/// and the toolchain (read-only) are mounted. The environment is exactly the
+/// build environment.
+pub fn package_sandbox(
+    tool: NamespaceTool,
+    project_dir: &Path,
+    package: &str,
+    env: &BuildEnvironment,
+    dependencies: &[String],
+) -> NamespaceSandbox {
+    let build_dir = project_dir.join("build");
+    let mut sandbox = NamespaceSandbox::new(tool, build_dir.join(SANDBOX_DIR).join(package))
+        .with_mount(MountConfig::read_only(
+            project_dir.join("packages").join(package),
+            project_dir.join("packages").join(package),
+        ))
+        .with_mount(MountConfig::read_write(
+            env.srcdir.clone(),
+            env.srcdir.clone(),
+        ))
+        .with_mount(MountConfig::read_write(
+            env.destdir.clone(),
+            env.destdir.clone(),
+        ));
+    for dependency in dependencies {
+        let sysroot = build_dir.join(STAGING_DIR).join(dependency);
+        if sysroot.is_dir() {
+            sandbox = sandbox.with_mount(MountConfig::read_only(sysroot.clone(), sysroot));
+        }
+    }
+
+    let mut path = Vec::new();
+    for dir in &env.tool_paths {
+        sandbox = sandbox.with_mount(MountConfig::read_only(dir.clone(), dir.clone()));
+        path.push(dir.clone());
+    }
+    if let Some((root, bin)) = toolchain_dirs(&env.cc) {
+        sandbox = sandbox.with_mount(MountConfig::read_only(root.clone(), root));
+        path.push(bin);
+    }
+    sandbox = sandbox.with_path(&path).with_env("HOME", "/tmp");
+    let mut vars: Vec<_> = env.to_env_map().into_iter().collect();
+    vars.sort();
+    for (key, value) in vars {
+        sandbox = sandbox.with_env(key, value);
+    }
+    sandbox.with_workdir(env.srcdir.clone())
+}
+
+/// Installation and binary directories of the compiler in `cc`
+///
+/// Returns `None` if the compiler is not found or is part of the host
+/// system, which every sandbox mounts already.
+fn toolchain_dirs(cc: &str) -> Option<(PathBuf, PathBuf)> {
+    let program = cc.split_whitespace().next()?;
+    let binary = which::which(program).ok()?.canonicalize().ok()?;
+    let bin = binary.parent()?.to_path_buf();
+    if is_system_path(&bin) {
+        return None;
+    }
+    // GCC toolchains keep binaries in <root>/bin, Zig next to its lib/
+    let root = if bin.file_name().is_some_and(|name| name == "bin") {
+        bin.parent()?.to_path_buf()
+    } else {
+        bin.clone()
+    };
+    Some((root, bin))
+}
+
+/// Lines of a failed step's output quoted in the build error
+pub const FAILURE_LOG_LINES: usize = 20;
+
+/// Run the custom build steps of a package
+///
+/// Each step runs through `sh -c` in the source directory, with its
+/// arguments as positional parameters. Inside a sandbox the environment is
+/// replaced; otherwise the build variables are added to the inherited one.
+/// Output lines of all steps are passed to `on_line` and appended to
+/// `log_path` as they are read. A failing step is reported with the last
+/// [`FAILURE_LOG_LINES`] lines of its output and the path of the log.
+pub fn run_build_steps(
+    package: &str,
+    steps: &[BuildStep],
+    env: &BuildEnvironment,
+    sandbox: Option<&NamespaceSandbox>,
+    log_path: &Path,
+    on_line: &mut dyn FnMut(&str),
+) -> Result<(), BuildError> {
+    use std::io::Write;
+
+    let failed = |error: String| BuildError::BuildFailed {
+        package: package.to_string(),
+        error,
+    };
+    let log_failed = |e: std::io::Error| {
+        failed(format!(
+            "Failed to write build log {}: {e}",
+            log_path.display()
+        ))
+    };
+
+    let mut log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .map_err(log_failed)?;
+    for step in steps {
+        let mut args = vec![
+            "-c".to_string(),
+            format!("{} \"$@\"", step.run),
+            "sh".to_string(),
+        ];
+        args.extend(step.args.iter().cloned());
+        let mut command = if let Some(sandbox) = sandbox {
+            sandbox.command("/bin/sh", &args)
+        } else {
+            let mut command = std::process::Command::new("sh");
+            command
+                .args(&args)
+                .envs(env.to_env_map())
+                .current_dir(&env.srcdir);
+            if let Some(path) = env.search_path() {
+                command.env("PATH", path);
+            }
+            command
+        };
+
+        writeln!(log, "$ {}", step.run).map_err(log_failed)?;
+        let mut write_error = None;
+        let output = hook::run_streaming(&mut command, &|_| interrupt_requested(), &mut |line| {
+            if write_error.is_none() {
+                write_error = writeln!(log, "{line}").err();
+            }
+            on_line(line);
+        })
+        .map_err(|e| failed(format!("Failed to run '{}': {e}", step.run)))?;
+        if let Some(e) = write_error {
+            return Err(log_failed(e));
+        }
+
+        let Some(status) = output.status.filter(|_| !interrupt_requested()) else {
+            writeln!(log, "Interrupted").map_err(log_failed)?;
+            return Err(BuildError::Interrupted {
+                package: package.to_string(),
+            });
+        };
+        if !status.success() {
+            let lines: Vec<&str> = output.output.lines().collect();
+            let tail = lines[lines.len().saturating_sub(FAILURE_LOG_LINES)..].join("\n  ");
+            return Err(failed(format!(
+                "step '{}' exited with {status}:\n  {tail}\nFull log: {}",
+                step.run,
+                log_path.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Run the `stage` hook of a package (`pre_build`, `post_build` or
+/// `post_install`)
+///
+/// The hook runs through `sh -c` with the build environment, like the build
+/// steps, in the source directory, or in `$DESTDIR` for `post_install`.
+/// Each output line is passed to `on_line` and appended to `log_path`.
+pub fn run_package_hook(
+    package: &str,
+    stage: &str,
+    command: &str,
+    env: &BuildEnvironment,
+    sandbox: Option<&NamespaceSandbox>,
+    log_path: &Path,
+    on_line: &mut dyn FnMut(&str),
+) -> Result<(), BuildError> {
+    let failed = |error: String| BuildError::BuildFailed {
+        package: package.to_string(),
+        error,
+    };
+
+    let dir = if stage == "post_install" {
+        &env.destdir
+    } else {
+        &env.srcdir
+    };
+    let mut process = if let Some(sandbox) = sandbox {
+        let script = format!("cd '{}' && {command}", dir.display());
+        sandbox.command("/bin/sh", &["-c".to_string(), script])
+    } else {
+        hook::shell_command(command, dir, env.to_env_map(), &env.tool_paths)
+    };
+    let output = hook::run_hook(&mut process, None, on_line)
+        .map_err(|e| failed(format!("Failed to run {stage} hook '{command}': {e}")))?;
+
+    let log = format!("$ [{stage}] {command}\n{}", output.output);
+    append_log(log_path, log.as_bytes()).map_err(|e| {
+        failed(format!(
+            "Failed to write build log {}: {e}",
+            log_path.display()
+        ))
+    })?;
+    match output.failure() {
+        Some(reason) => Err(failed(format!(
+            "{stage} hook '{command}' {reason}\nFull log: {}",
+            log_path.display()
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn append_log(log_path: &Path, log: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)?
+        .write_all(log)
+}
+
+/// Hash of the hook commands of a package, `None` without hooks
+pub fn hooks_hash(build: &PackageBuildConfig) -> Option<String> {
+    let hooks = build.hooks();
+    if hooks.is_empty() {
+        return None;
+    }
+    let text: Vec<String> = hooks
+        .iter()
+        .map(|(stage, command)| format!("{stage}={command}"))
+        .collect();
+    Some(compute_checksum(text.join("\n").as_bytes())[..16].to_string())
+}
+
+/// Build history file in the cache directory
+pub const BUILD_HISTORY_FILE: &str = "build-history.json";
+
+/// Durations of past package builds, used to estimate build times
+///
+/// Entries are keyed by package, version and options hash (see
+/// [`history_key`]), so a changed configuration doesn't reuse the timing of
+/// another one.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BuildHistory {
+    /// Duration of the last build in seconds, by key
+    pub durations: BTreeMap<String, f64>,
+}
+
+impl BuildHistory {
+    /// Load the history, starting empty if the file is missing or corrupt
+    pub fn load(path: &Path) -> Self {
+        let Ok(content) = std::fs::read(path) else {
+            return Self::default();
+        };
+        serde_json::from_slice(&content)
+            .map_err(|e| tracing::debug!("Ignoring corrupt build history {}: {e}", path.display()))
+            .unwrap_or_default()
+    }
+
+    /// Save the history
+    pub fn save(&self, path: &Path) -> Result<(), FilesystemError> {
+        let content = serde_json::to_vec_pretty(self).map_err(|e| FilesystemError::WriteFile {
+            path: path.to_path_buf(),
+            error: e.to_string(),
+        })?;
+        write_file_atomic(path, &content)
+    }
+
+    /// Record the duration of a build
+    pub fn record(&mut self, key: String, secs: f64) {
+        self.durations.insert(key, secs);
+    }
+
+    /// Median of all recorded durations
+    pub fn median(&self) -> Option<f64> {
+        let mut durations: Vec<f64> = self.durations.values().copied().collect();
+        if durations.is_empty() {
+            return None;
+        }
+        durations.sort_by(f64::total_cmp);
+        let middle = durations.len() / 2;
+        Some(if durations.len() % 2 == 0 {
+            (durations[middle - 1] + durations[middle]) / 2.0
+        } else {
+            durations[middle]
+        })
+    }
+
+    /// Estimated duration in seconds of each key
+    ///
+    /// Keys without history count at the median duration, or one second
+    /// when there is no history at all.
+    pub fn estimate(&self, keys: &[String]) -> Vec<f64> {
+        let fallback = self.median().unwrap_or(1.0);
+        keys.iter()
+            .map(|key| self.durations.get(key).copied().unwrap_or(fallback))
+            .collect()
+    }
+}
+
+/// Key of a package build in the [`BuildHistory`]
+pub fn history_key(package: &str, version: &str, options_hash: &str) -> String {
+    format!("{package}@{version}#{options_hash}")
+}
+
+/// Hash of the settings a package build depends on: the board, its C
+/// library and the effective package options
+///
+/// The libc only enters the hash when it is not musl, so builds from before
+/// libc selection stay current.
+pub fn package_options_hash(project_dir: &Path, manifest: &Manifest, package: &str) -> String {
+    let options: BTreeMap<String, toml::Value> =
+        crate::core::config::get_package_options(project_dir, manifest, package)
+            .into_iter()
+            .map(|(key, option)| (key, option.value))
+            .collect();
+    let mut settings = serde_json::json!({
+        "board": manifest.board.name,
+        "options": options,
+    });
+    let libc = manifest
+        .board
+        .name
+        .as_deref()
+        .and_then(|name| load_board_definition(project_dir, name).ok())
+        .map_or(manifest.board.libc, |board| {
+            Some(board.libc(&manifest.board))
+        });
+    if let Some(libc) = libc.filter(|libc| *libc != Libc::Musl) {
+        settings["libc"] = serde_json::json!(libc);
+    }
+    compute_checksum(settings.to_string().as_bytes())[..16].to_string()
+}
+
+/// File extension added to an initramfs by a compression method
+pub fn initramfs_extension(compression: &str) -> &'static str {
+    match compression {
+        "gzip" => ".gz",
+        "xz" => ".xz",
+        "zstd" => ".zst",
+        "lz4" => ".lz4",
+        _ => "",
+    }
+}
+
+/// Compress a cpio archive in place with a kernel-compatible compressor
+///
+/// The uncompressed archive is replaced by `<archive><extension>`, which is
+/// returned. Uncompressed archives are returned unchanged.
+pub fn compress_initramfs(archive: &Path, compression: &str) -> std::io::Result<PathBuf> {
+    let extension = initramfs_extension(compression);
+    if extension.is_empty() {
+        return Ok(archive.to_path_buf());
+    }
+
+    let mut compressed = archive.as_os_str().to_os_string();
+    compressed.push(extension);
+    let compressed = PathBuf::from(compressed);
+
+    // xz needs CRC32 and lz4 the legacy frame format for the kernel unpacker
+    let (program, args): (&str, &[&str]) = match compression {
+        "gzip" => ("gzip", &["-9", "-n", "-c"]),
+        "xz" => ("xz", &["--check=crc32", "-9", "-c"]),
+        "zstd" => ("zstd", &["-19", "-q", "-c"]),
+        _ => ("lz4", &["-l", "-9", "-c"]),
+    };
+
+    let output = std::fs::File::create(&compressed)?;
+    let status = std::process::Command::new(program)
+        .args(args)
+        .arg(archive)
+        .stdout(output)
+        .status()
+        .map_err(|e| std::io::Error::new(e.kind(), format!("Failed to run {program}: {e}")))?;
+    if !status.success() {
+        let _ = std::fs::remove_file(&compressed);
+        return Err(std::io::Error::other(format!(
+            "{program} failed to compress {}",
+            archive.display()
+        )));
+    }
+
+    std::fs::remove_file(archive)?;
+    Ok(compressed)
+}
+
+/// Device nodes added to an initramfs whose rootfs lacks them
+///
+/// Unprivileged builds cannot create device nodes, but the kernel opens
+/// `/dev/console` for init before any devtmpfs is mounted.
+const INITRAMFS_DEVICES: &[(&str, u32, u32, u32)] =
+    &[("dev/console", 0o600, 5, 1), ("dev/null", 0o666, 1, 3)];
+
+/// Write a rootfs tree as a `newc` cpio archive, returning its size
+///
+/// Entries are owned by root unless `permissions` overlays their owner,
+/// group or mode; device nodes in the tree keep their numbers and
+/// [`INITRAMFS_DEVICES`] are added when missing. With an `epoch`, every
+/// entry has that modification time.
+pub fn write_initramfs(
+    rootfs_dir: &Path,
+    archive: &Path,
+    epoch: Option<u64>,
+    permissions: &PermissionTable,
+) -> std::io::Result<u64> {
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::MetadataExt;
+
+    let file = std::fs::File::create(archive)?;
+    let mut cpio = CpioWriter::new(std::io::BufWriter::new(file));
+    let walker = walkdir::WalkDir::new(rootfs_dir)
+        .min_depth(1)
+        .sort_by_file_name();
+    for entry in walker {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let name = entry
+            .path()
+            .strip_prefix(rootfs_dir)
+            .unwrap_or(entry.path());
+        let data = if entry.file_type().is_symlink() {
+            std::fs::read_link(entry.path())?
+                .as_os_str()
+                .as_bytes()
+                .to_vec()
+        } else if entry.file_type().is_file() {
+            std::fs::read(entry.path())?
+        } else {
+            Vec::new()
+        };
+        let rdev = metadata.rdev();
+        let major = ((rdev >> 8) & 0xfff) | ((rdev >> 32) & !0xfff);
+        let minor = (rdev & 0xff) | ((rdev >> 12) & !0xff);
+        let overlay = permissions.get(name);
+        cpio.entry(&CpioEntry {
+            name: name.as_os_str().as_bytes(),
+            mode: overlay.map_or(metadata.mode(), |a| a.apply_mode(metadata.mode())),
+            owner: overlay.map_or((0, 0), |a| (a.uid, a.gid)),
+            mtime: epoch.unwrap_or_else(|| u64::try_from(metadata.mtime()).unwrap_or(0)),
+            rdev: (
+                u32::try_from(major).unwrap_or(0),
+                u32::try_from(minor).unwrap_or(0),
+            ),
+            data: &data,
+        })?;
+    }
+
+    let mtime = epoch.unwrap_or(0);
+    if !rootfs_dir.join("dev").is_dir() {
+        cpio.entry(&CpioEntry {
+            name: b"dev",
+            mode: 0o040_755,
+            owner: (0, 0),
+            mtime,
+            rdev: (0, 0),
+            data: &[],
+        })?;
+    }
+    for (path, mode, major, minor) in INITRAMFS_DEVICES {
+        if rootfs_dir.join(path).symlink_metadata().is_err() {
+            cpio.entry(&CpioEntry {
+                name: path.as_bytes(),
+                mode: 0o020_000 | mode,
+                owner: (0, 0),
+                mtime,
+                rdev: (*major, *minor),
+                data: &[],
+            })?;
+        }
+    }
+    cpio.finish()
+}
+
+/// One member of a cpio archive
+struct CpioEntry<'a> {
+    name: &'a [u8],
+    mode: u32,
+    /// uid and gid
+    owner: (u32, u32),
+    mtime: u64,
+    rdev: (u32, u32),
+    data: &'a [u8],
+}
+
+/// Writer of `newc` (SVR4 without CRC) cpio archives
+struct CpioWriter<W: std::io::Write> {
+    out: W,
+    inode: u32,
+    written: u64,
+}
+
+impl<W: std::io::Write> CpioWriter<W> {
+    fn new(out: W) -> Self {
+        Self {
+            out,
+            inode: 0,
+            written: 0,
+        }
+    }
+
+    fn entry(&mut self, entry: &CpioEntry) -> std::io::Result<()> {
+        let too_large = || std::io::Error::other("file too large for a cpio archive");
+        let size = u32::try_from(entry.data.len()).map_err(|_| too_large())?;
+        let name_size = u32::try_from(entry.name.len() + 1).map_err(|_| too_large())?;
+        let is_dir = entry.mode & 0o170_000 == 0o040_000;
+        self.inode += 1;
+        let fields = [
+            self.inode,
+            entry.mode,
+            entry.owner.0,
+            entry.owner.1,
+            if is_dir { 2 } else { 1 },
+            u32::try_from(entry.mtime).unwrap_or(u32::MAX),
+            size,
+            0,
+            0,
+            entry.rdev.0,
+            entry.rdev.1,
+            name_size,
+            0,
+        ];
+        self.write(b"070701")?;
+        for field in fields {
+            self.write(format!("{field:08X}").as_bytes())?;
+        }
+        self.write(entry.name)?;
+        self.write(&[0])?;
+        self.pad()?;
+        self.write(entry.data)?;
+        self.pad()
+    }
+
+    fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.out.write_all(data)?;
+        self.written += data.len() as u64;
+        Ok(())
+    }
+
+    /// Pad to the next 4-byte boundary
+    fn pad(&mut self) -> std::io::Result<()> {
+        let padding = (4 - self.written % 4) % 4;
+        self.write(&[0; 3][..usize::try_from(padding).unwrap_or(0)])
+    }
+
+    /// Write the trailer and flush, returning the archive size
+    fn finish(mut self) -> std::io::Result<u64> {
+        self.entry(&CpioEntry {
+            name: b"TRAILER!!!",
+            mode: 0,
+            owner: (0, 0),
+            mtime: 0,
+            rdev: (0, 0),
+            data: &[],
+        })?;
+        self.out.flush()?;
+        Ok(self.written)
+    }
+}
+
+/// Disk space needed in the project per byte of rootfs
+///
+/// Covers downloaded sources, build trees, the staged rootfs and the images.
+pub const BUILD_SPACE_FACTOR: u64 = 4;
+
+/// Estimate the disk space a build needs at each location
+///
+/// The project's build tree holds sources, build output and images; the
+/// temporary directory holds intermediate files of up to one rootfs.
+pub fn estimate_build_space(
+    project_dir: &Path,
+    temp_dir: &Path,
+    rootfs_size: u64,
+) -> Vec<(PathBuf, u64)> {
+    vec![
+        (
+            project_dir.join("build"),
+            rootfs_size.saturating_mul(BUILD_SPACE_FACTOR),
+        ),
+        (temp_dir.to_path_buf(), rootfs_size),
+    ]
+}
+
+/// Check estimated space requirements against the available space
+///
+/// Requirements on the same filesystem are added up. Locations whose free
+/// space cannot be queried are skipped with a warning.
+pub fn check_space<F>(requirements: &[(PathBuf, u64)], query: F) -> Result<(), BuildError>
+where
+    F: Fn(&Path) -> Result<FilesystemSpace, FilesystemError>,
+{
+    let mut filesystems: Vec<(FilesystemSpace, Vec<&Path>, u64)> = Vec::new();
+    for (path, required) in requirements {
+        let space = match query(path) {
+            Ok(space) => space,
+            Err(e) => {
+                tracing::warn!("Skipping disk space check: {e}");
+                continue;
+            }
+        };
+        match filesystems
+            .iter_mut()
+            .find(|(known, _, _)| known.mount_point == space.mount_point)
+        {
+            Some((_, paths, total)) => {
+                paths.push(path);
+                *total = total.saturating_add(*required);
+            }
+            None => filesystems.push((space, vec![path], *required)),
+        }
+    }
+
+    for (space, paths, required) in filesystems {
+        if required > space.available {
+            return Err(BuildError::InsufficientSpace {
+                mount_point: space.mount_point.display().to_string(),
+                paths: paths
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                required,
+                available: space.available,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Size of partition content: a file's length or the total of a directory's files
+pub fn content_size(path: &Path) -> std::io::Result<u64> {
+    if !path.is_dir() {
+        return Ok(std::fs::metadata(path)?.len());
+    }
+    let mut total = 0;
+    for entry in walkdir::WalkDir::new(path) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            total += entry.metadata()?.len();
+        }
+    }
+    Ok(total)
+}
+
+/// Assemble a partitioned disk image
+///
+/// `contents` holds the resolved content of each partition in layout order.
+/// Files are written as-is; directories are first turned into a filesystem
+/// of the partition's type. `seed` makes the table identifiers reproducible;
+/// with an `epoch`, the filesystems are deterministic as well.
+pub fn assemble_disk_image(
+    disk_path: &Path,
+    layout: &DiskLayout,
+    contents: &[Option<PathBuf>],
+    seed: &str,
+    epoch: Option<u64>,
+) -> std::io::Result<()> {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let mut disk = std::fs::File::create(disk_path)?;
+    disk.set_len(layout.size)?;
+    for (offset, data) in layout.table_chunks(seed) {
+        disk.seek(SeekFrom::Start(offset))?;
+        disk.write_all(&data)?;
+    }
+
+    for (partition, content) in layout.partitions.iter().zip(contents) {
+        let Some(content) = content else {
+            continue;
+        };
+        partition
+            .check_content_size(content_size(content)?)
+            .map_err(std::io::Error::other)?;
+
+        let mut filesystem = disk_path.as_os_str().to_os_string();
+        filesystem.push(format!(".{}", partition.name));
+        let filesystem = PathBuf::from(filesystem);
+        let source = if content.is_dir() {
+            let seed = format!("{seed}:{}", partition.name);
+            make_filesystem(content, &filesystem, partition, &seed, epoch)?;
+            filesystem.as_path()
+        } else {
+            content.as_path()
+        };
+
+        let mut input = std::fs::File::open(source)?;
+        let written = input.metadata()?.len();
+        partition
+            .check_content_size(written)
+            .map_err(std::io::Error::other)?;
+        disk.seek(SeekFrom::Start(partition.offset))?;
+        std::io::copy(&mut input, &mut disk)?;
+        if source == filesystem {
+            std::fs::remove_file(&filesystem)?;
+        }
+    }
+
+    disk.flush()
+}
+
+/// Create a filesystem image of a partition's size from a directory
+///
+/// ext4 is populated by `mkfs.ext4 -d`; FAT is formatted with `mkfs.vfat`
+/// and filled with `mcopy`. With an `epoch`, identifiers come from `seed`
+/// and all timestamps are set to the epoch.
+fn make_filesystem(
+    dir: &Path,
+    image: &Path,
+    partition: &Partition,
+    seed: &str,
+    epoch: Option<u64>,
+) -> std::io::Result<()> {
+    use std::ffi::OsString;
+
+    let label = OsString::from(&partition.name);
+    let commands: Vec<(&str, Vec<OsString>)> = match partition.partition_type.as_str() {
+        "ext4" => vec![(
+            "mkfs.ext4",
+            vec![
+                "-q".into(),
+                "-F".into(),
+                "-L".into(),
+                label,
+                "-d".into(),
+                dir.into(),
+                image.into(),
+            ],
+        )],
+        "fat32" => {
+            let mut copy: Vec<OsString> = vec!["-i".into(), image.into(), "-s".into()];
+            let mut entries = std::fs::read_dir(dir)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<std::io::Result<Vec<_>>>()?;
+            entries.sort();
+            copy.extend(entries.into_iter().map(OsString::from));
+            copy.push("::/".into());
+            vec![
+                (
+                    "mkfs.vfat",
+                    vec!["-F".into(), "32".into(), "-n".into(), label, image.into()],
+                ),
+                ("mcopy", copy),
+            ]
+        }
+        other => {
+            return Err(std::io::Error::other(format!(
+                "Partition '{}' has type '{other}', which cannot hold a directory",
+                partition.name
+            )))
+        }
+    };
+
+    std::fs::File::create(image)?.set_len(partition.size)?;
+    for (program, args) in commands {
+        let mut command = std::process::Command::new(program);
+        if let Some(epoch) = epoch {
+            command
+                .args(reproducible::tool_args(program, epoch, seed))
+                .envs(reproducible::tool_env(epoch));
+        }
+        run_image_tool(command.args(&args), program, image, partition, None)?;
+    }
+
+    // mkfs.ext4 -d copies change times, which only debugfs can rewrite
+    if let (Some(epoch), "ext4") = (epoch, partition.partition_type.as_str()) {
+        let mut paths = vec!["/".to_string(), "/lost+found".to_string()];
+        for entry in walkdir::WalkDir::new(dir).min_depth(1).sort_by_file_name() {
+            let entry = entry.map_err(std::io::Error::other)?;
+            if let Ok(relative) = entry.path().strip_prefix(dir) {
+                paths.push(format!("/{}", relative.display()));
+            }
+        }
+        let script = reproducible::debugfs_time_script(&paths, epoch);
+        let mut command = std::process::Command::new("debugfs");
+        command
+            .args(["-w", "-f", "-"])
+            .arg(image)
+            .envs(reproducible::tool_env(epoch));
+        run_image_tool(&mut command, "debugfs", image, partition, Some(&script))?;
+    }
+    Ok(())
+}
+
+/// Run a filesystem tool, removing the image if it fails
+fn run_image_tool(
+    command: &mut std::process::Command,
+    program: &str,
+    image: &Path,
+    partition: &Partition,
+    input: Option<&str>,
+) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| std::io::Error::new(e.kind(), format!("Failed to run {program}: {e}")))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(image);
+        return Err(std::io::Error::other(format!(
+            "{program} failed for partition '{}': {}",
+            partition.name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(name: &str, depends: &[&str], build_depends: &[&str]) -> PackageMetadata {
+        PackageMetadata {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            description: String::new(),
+            license: None,
+            homepage: None,
+            keywords: vec![],
+            depends: depends.iter().map(ToString::to_string).collect(),
+            build_depends: build_depends.iter().map(ToString::to_string).collect(),
+            host_depends: Vec::new(),
+            requires: vec![],
+            arch: vec![],
+            supported_libc: vec![],
+            provides: vec![],
+            conflicts: vec![],
+            zigroot_version: None,
+            license_files: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_build_failures_block_dependents() {
+        let deps = |names: &[&str]| names.iter().map(|n| (*n).to_string()).collect();
+        let mut failures = BuildFailures::default();
+        assert!(failures.is_empty());
+        assert_eq!(failures.failed_dependency(&deps(&["zlib"])), None);
+
+        failures.record_failure("zlib", "step 'make' failed".to_string());
+        assert_eq!(
+            failures.failed_dependency(&deps(&["musl", "zlib"])),
+            Some("zlib")
+        );
+        assert_eq!(failures.failed_dependency(&deps(&["musl"])), None);
+        failures.record_skipped("openssl", "zlib");
+
+        let message = failures.into_error().to_string();
+        assert_eq!(
+            message,
+            "1 package(s) failed to build:\n  zlib: step 'make' failed\n  \
+             openssl: skipped due to failed dependency 'zlib'"
+        );
+    }
+
+    #[test]
+    fn test_rootfs_excludes_build_only_dependencies() {
+        let definitions: HashMap<String, PackageMetadata> = [
+            metadata("app", &["libfoo"], &["zlib-static"]),
+            metadata("libfoo", &[], &[]),
+            metadata("zlib-static", &[], &[]),
+        ]
+        .into_iter()
+        .map(|m| (m.name.clone(), m))
+        .collect();
+
+        let orchestrator = BuildOrchestrator::new()
+            .with_packages(vec![
+                "app".to_string(),
+                "libfoo".to_string(),
+                "zlib-static".to_string(),
+            ])
+            .with_build_order(vec![
+                "zlib-static".to_string(),
+                "libfoo".to_string(),
+                "app".to_string(),
+            ]);
+
+        assert_eq!(
+            orchestrator.rootfs_packages(&definitions),
+            vec!["libfoo", "app"]
+        );
+    }
+
+    #[test]
+    fn test_install_into_rootfs_only_copies_listed_packages() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let staging = temp.path().join(STAGING_DIR);
+        let rootfs = temp.path().join("rootfs");
+        std::fs::create_dir_all(staging.join("app/usr/bin")).unwrap();
+        std::fs::write(staging.join("app/usr/bin/app"), "bin").unwrap();
+        std::fs::create_dir_all(staging.join("zlib-static/usr/lib")).unwrap();
+        std::fs::write(staging.join("zlib-static/usr/lib/libz.a"), "lib").unwrap();
+
+        let installed_dir = temp.path().join(INSTALLED_DIR);
+
+        let installed = install_into_rootfs(
+            &staging,
+            &rootfs,
+            &installed_dir,
+            &["app".to_string(), "missing".to_string()],
+        )
+        .unwrap();
+
+        assert_eq!(installed, vec!["app"]);
+        assert!(rootfs.join("usr/bin/app").exists());
+        assert!(!rootfs.join("usr/lib/libz.a").exists());
+        assert_eq!(
+            installed_files(&installed_dir, "app").unwrap(),
+            vec![PathBuf::from("usr/bin/app")]
+        );
+        assert!(installed_files(&installed_dir, "missing")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_install_into_rootfs_keeps_symlinks() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let staging = temp.path().join(STAGING_DIR);
+        let rootfs = temp.path().join("rootfs");
+        let bin = staging.join("busybox/bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::write(bin.join("busybox"), "busybox").unwrap();
+        std::os::unix::fs::symlink("busybox", bin.join("sh")).unwrap();
+        std::os::unix::fs::symlink("/bin/busybox", bin.join("ls")).unwrap();
+        std::os::unix::fs::symlink("/nonexistent/applet", bin.join("dangling")).unwrap();
+        let installed_dir = temp.path().join(INSTALLED_DIR);
+
+        // Installing twice replaces the links instead of following them
+        for _ in 0..2 {
+            install_into_rootfs(&staging, &rootfs, &installed_dir, &["busybox".to_string()])
+                .unwrap();
+        }
+
+        for (link, target) in [
+            ("sh", "busybox"),
+            ("ls", "/bin/busybox"),
+            ("dangling", "/nonexistent/applet"),
+        ] {
+            let path = rootfs.join("bin").join(link);
+            assert!(path.symlink_metadata().unwrap().file_type().is_symlink());
+            assert_eq!(std::fs::read_link(&path).unwrap(), PathBuf::from(target));
+        }
+        assert_eq!(installed_files(&installed_dir, "busybox").unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_stamps_record_the_cache_key() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let stamps = temp.path().join(STAMPS_DIR);
+
+        assert!(!stamp_is_current(&stamps, "app", "app-1.0.0-abc"));
+        write_stamp(&stamps, "app", "app-1.0.0-abc").unwrap();
+        assert!(stamp_is_current(&stamps, "app", "app-1.0.0-abc"));
+        assert!(!stamp_is_current(&stamps, "app", "app-1.1.0-abc"));
+    }
+
+    #[test]
+    fn test_staging_is_replaced_only_on_commit() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let staging = temp.path().join(STAGING_DIR);
+        std::fs::create_dir_all(staging.join("app")).unwrap();
+        std::fs::write(staging.join("app/old"), "old").unwrap();
+
+        let partial = begin_staging(&staging, "app").unwrap();
+        assert_eq!(partial, staging.join("app.partial"));
+        assert!(partial.join("old").exists());
+        assert!(!staging.join("app").exists());
+
+        std::fs::write(partial.join("new"), "new").unwrap();
+        commit_staging(&staging, "app").unwrap();
+        assert!(staging.join("app/old").exists());
+        assert!(staging.join("app/new").exists());
+        assert!(!partial.exists());
+    }
+
+    #[test]
+    fn test_recover_build_dir_cleans_interrupted_build() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let build = temp.path();
+        let stamps = build.join(STAMPS_DIR);
+        let staging = build.join(STAGING_DIR);
+        for package in ["done", "killed", "stopped"] {
+            write_stamp(&stamps, package, "key").unwrap();
+        }
+        std::fs::write(stamps.join(".done.stamp.1.0.tmp"), "key").unwrap();
+        std::fs::create_dir_all(staging.join("done")).unwrap();
+        std::fs::create_dir_all(staging.join("killed.partial")).unwrap();
+        record_interrupted(build, "stopped").unwrap();
+
+        let recovery = recover_build_dir(build).unwrap();
+        assert_eq!(recovery.interrupted.as_deref(), Some("stopped"));
+        assert_eq!(recovery.removed.len(), 4);
+        assert!(stamp_is_current(&stamps, "done", "key"));
+        assert!(staging.join("done").is_dir());
+        assert!(!stamps.join("killed.stamp").exists());
+        assert!(!stamps.join("stopped.stamp").exists());
+        assert!(!stamps.join(".done.stamp.1.0.tmp").exists());
+        assert!(!staging.join("killed.partial").exists());
+        assert!(!build.join(INTERRUPTED_FILE).exists());
+
+        assert_eq!(recover_build_dir(build).unwrap(), Recovery::default());
+    }
+
+    #[test]
+    fn test_run_build_steps_uses_build_environment_and_logs() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let srcdir = temp.path().join("src");
+        let destdir = temp.path().join("dest");
+        std::fs::create_dir_all(&srcdir).unwrap();
+        let env =
+            BuildEnvironment::for_zig("x86_64-linux-musl", "generic", srcdir, destdir.clone());
+        let steps = vec![
+            BuildStep {
+                run: "mkdir -p \"$DESTDIR/etc\" && echo".to_string(),
+                args: vec!["$TARGET".to_string()],
+            },
+            BuildStep {
+                run: "echo \"$TARGET\" > \"$DESTDIR/etc/target\"".to_string(),
+                args: Vec::new(),
+            },
+        ];
+        let log = temp.path().join("app.log");
+
+        run_build_steps("app", &steps, &env, None, &log, &mut |_| {}).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(destdir.join("etc/target")).unwrap(),
+            "x86_64-linux-musl\n"
+        );
+        // Arguments are passed as-is, not expanded by the shell
+        assert!(std::fs::read_to_string(&log).unwrap().contains("$TARGET\n"));
+    }
+
+    #[test]
+    fn test_run_build_steps_reports_failing_step() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let env = BuildEnvironment::for_zig(
+            "x86_64-linux-musl",
+            "generic",
+            temp.path().to_path_buf(),
+            temp.path().join("dest"),
+        );
+        let steps = vec![
+            BuildStep {
+                run: "echo broken >&2; exit 3".to_string(),
+                args: Vec::new(),
+            },
+            BuildStep {
+                run: "touch never".to_string(),
+                args: Vec::new(),
+            },
+        ];
+
+        let mut lines = Vec::new();
+        let err = run_build_steps(
+            "app",
+            &steps,
+            &env,
+            None,
+            &temp.path().join("app.log"),
+            &mut |line| lines.push(line.to_string()),
+        )
+        .unwrap_err()
+        .to_string();
+
+        assert!(err.contains("exited with"), "{err}");
+        assert!(err.contains("broken"), "{err}");
+        assert!(err.contains("Full log:"), "{err}");
+        assert_eq!(lines, vec!["broken"]);
+        assert!(!temp.path().join("never").exists());
+    }
+
+    #[test]
+    fn test_build_history_estimates_unknown_packages_at_median() {
+        let mut history = BuildHistory::default();
+        assert_eq!(history.estimate(&["a".to_string()]), vec![1.0]);
+
+        history.record(history_key("busybox", "1.36.1", "abc"), 30.0);
+        history.record(history_key("zlib", "1.3.1", "abc"), 10.0);
+        history.record(history_key("openssl", "3.1.0", "abc"), 120.0);
+        assert_eq!(history.median(), Some(30.0));
+
+        let keys = [
+            history_key("zlib", "1.3.1", "abc"),
+            history_key("zlib", "1.3.1", "changed"),
+        ];
+        assert_eq!(history.estimate(&keys), vec![10.0, 30.0]);
+    }
+
+    #[test]
+    fn test_build_history_ignores_corrupt_file() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join(BUILD_HISTORY_FILE);
+        std::fs::write(&path, "{ not json").unwrap();
+        assert_eq!(BuildHistory::load(&path), BuildHistory::default());
+
+        let mut history = BuildHistory::default();
+        history.record("app@1.0.0#abc".to_string(), 2.5);
+        history.save(&path).unwrap();
+        assert_eq!(BuildHistory::load(&path), history);
+    }
+
+    #[test]
+    fn test_rootfs_keeps_package_needed_at_runtime_elsewhere() {
+        let definitions: HashMap<String, PackageMetadata> = [
+            metadata("app", &[], &["zlib"]),
+            metadata("tool", &["zlib"], &[]),
+            metadata("zlib", &[], &[]),
+        ]
+        .into_iter()
+        .map(|m| (m.name.clone(), m))
+        .collect();
+
+        let orchestrator = BuildOrchestrator::new().with_packages(vec![
+            "app".to_string(),
+            "tool".to_string(),
+            "zlib".to_string(),
+        ]);
+
+        assert_eq!(
+            orchestrator.rootfs_packages(&definitions),
+            vec!["app", "tool", "zlib"]
+        );
+    }
+
+    #[test]
+    fn test_compress_initramfs_gzip_replaces_archive() {
+        if which::which("gzip").is_err() {
+            return;
+        }
+        let temp = tempfile::TempDir::new().unwrap();
+        let archive = temp.path().join("rootfs.cpio");
+        std::fs::write(&archive, b"070701").unwrap();
+
+        let compressed = compress_initramfs(&archive, "gzip").unwrap();
+        assert_eq!(compressed, temp.path().join("rootfs.cpio.gz"));
+        assert!(!archive.exists());
+        let data = std::fs::read(&compressed).unwrap();
+        assert_eq!(&data[..2], &[0x1f, 0x8b]);
+    }
+
+    /// Name, mode, uid and gid, and data of a cpio member
+    type CpioMember = (String, u32, (u32, u32), Vec<u8>);
+
+    /// Members of a newc archive
+    fn read_cpio(data: &[u8]) -> Vec<CpioMember> {
+        let field = |at: usize| {
+            u32::from_str_radix(std::str::from_utf8(&data[at..at + 8]).unwrap(), 16).unwrap()
+        };
+        let mut members = Vec::new();
+        let mut pos = 0;
+        loop {
+            assert_eq!(&data[pos..pos + 6], b"070701");
+            let mode = field(pos + 14);
+            let owner = (field(pos + 22), field(pos + 30));
+            let size = field(pos + 54) as usize;
+            let name_size = field(pos + 94) as usize;
+            let name =
+                String::from_utf8(data[pos + 110..pos + 110 + name_size - 1].to_vec()).unwrap();
+            pos = (pos + 110 + name_size).next_multiple_of(4);
+            if name == "TRAILER!!!" {
+                return members;
+            }
+            members.push((name, mode, owner, data[pos..pos + size].to_vec()));
+            pos = (pos + size).next_multiple_of(4);
+        }
+    }
+
+    #[test]
+    fn test_write_initramfs_newc_archive() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let rootfs = temp.path().join("rootfs");
+        std::fs::create_dir_all(rootfs.join("bin")).unwrap();
+        std::fs::write(rootfs.join("bin/busybox"), b"binary").unwrap();
+        std::os::unix::fs::symlink("bin/busybox", rootfs.join("init")).unwrap();
+
+        let archive = temp.path().join("rootfs.cpio");
+        let none = PermissionTable::default();
+        let size = write_initramfs(&rootfs, &archive, Some(1_700_000_000), &none).unwrap();
+        let data = std::fs::read(&archive).unwrap();
+        assert_eq!(size, data.len() as u64);
+
+        let members = read_cpio(&data);
+        let names: Vec<&str> = members.iter().map(|m| m.0.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "bin",
+                "bin/busybox",
+                "init",
+                "dev",
+                "dev/console",
+                "dev/null"
+            ]
+        );
+        assert_eq!(members[0].1 & 0o170_000, 0o040_000);
+        assert_eq!(members[1].3, b"binary");
+        assert_eq!(members[2].1 & 0o170_000, 0o120_000);
+        assert_eq!(members[2].3, b"bin/busybox");
+        assert_eq!(members[4].1, 0o020_600);
+
+        // Ownership is root and the archive does not depend on the host
+        assert_eq!(&data[22..38], b"0000000000000000");
+        let again = temp.path().join("again.cpio");
+        write_initramfs(&rootfs, &again, Some(1_700_000_000), &none).unwrap();
+        assert_eq!(std::fs::read(&again).unwrap(), data);
+    }
+
+    #[test]
+    fn test_write_initramfs_with_epoch_ignores_creation_order_and_times() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let none = PermissionTable::default();
+        let mut archives = Vec::new();
+        for (round, files) in [["etc/motd", "bin/sh"], ["bin/sh", "etc/motd"]]
+            .iter()
+            .enumerate()
+        {
+            let rootfs = temp.path().join(format!("rootfs{round}"));
+            for (index, file) in files.iter().enumerate() {
+                let path = rootfs.join(file);
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(&path, file.as_bytes()).unwrap();
+                let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(index as u64);
+                std::fs::File::open(&path)
+                    .unwrap()
+                    .set_modified(time)
+                    .unwrap();
+            }
+            let archive = temp.path().join(format!("rootfs{round}.cpio"));
+            write_initramfs(&rootfs, &archive, Some(1_700_000_000), &none).unwrap();
+            archives.push(std::fs::read(&archive).unwrap());
+        }
+        assert_eq!(archives[0], archives[1]);
+    }
+
+    #[test]
+    fn test_write_initramfs_records_permission_overlay() {
+        use crate::core::permissions::PermissionEntry;
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let rootfs = temp.path().join("rootfs");
+        std::fs::create_dir_all(rootfs.join("bin")).unwrap();
+        std::fs::write(rootfs.join("bin/busybox"), b"binary").unwrap();
+        let entries = BTreeMap::from([(
+            "/bin/busybox".to_string(),
+            PermissionEntry {
+                owner: Some("1000".to_string()),
+                group: Some("50".to_string()),
+                mode: Some("4755".to_string()),
+            },
+        )]);
+        let permissions = PermissionTable::resolve(&rootfs, &entries).unwrap();
+
+        let archive = temp.path().join("rootfs.cpio");
+        write_initramfs(&rootfs, &archive, None, &permissions).unwrap();
+        let members = read_cpio(&std::fs::read(&archive).unwrap());
+        assert_eq!(members[0].2, (0, 0));
+        assert_eq!(members[1].0, "bin/busybox");
+        assert_eq!(members[1].1, 0o104_755);
+        assert_eq!(members[1].2, (1000, 50));
+        assert_eq!(members[1].3, b"binary");
+    }
+
+    #[test]
+    fn test_compress_initramfs_none_is_unchanged() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let archive = temp.path().join("rootfs.cpio");
+        std::fs::write(&archive, b"070701").unwrap();
+        assert_eq!(compress_initramfs(&archive, "none").unwrap(), archive);
+        assert!(archive.exists());
+    }
+
+    #[test]
+    fn test_check_space_sums_requirements_per_filesystem() {
+        let query = |path: &Path| {
+            Ok(FilesystemSpace {
+                mount_point: PathBuf::from(if path.starts_with("/tmp") {
+                    "/tmp"
+                } else {
+                    "/"
+                }),
+                available: 1000,
+            })
+        };
+        let requirements = estimate_build_space(Path::new("/project"), Path::new("/tmp"), 200);
+        assert_eq!(requirements[0].1, 800);
+        assert!(check_space(&requirements, query).is_ok());
+
+        // Both locations on the same filesystem need 1000 + 200 bytes
+        let requirements = estimate_build_space(Path::new("/project"), Path::new("/var"), 250);
+        let err = check_space(&requirements, query).unwrap_err();
+        assert!(matches!(
+            err,
+            BuildError::InsufficientSpace {
+                required: 1250,
+                available: 1000,
+                ..
+            }
+        ));
+        assert!(err.to_string().contains("/project/build, /var"));
+
+        let failing = |path: &Path| {
+            Err(FilesystemError::SpaceQuery {
+                path: path.to_path_buf(),
+                error: "no df".to_string(),
+            })
+        };
+        assert!(check_space(&requirements, failing).is_ok());
+    }
 }
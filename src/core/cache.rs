@@ -4,9 +4,21 @@
 //!
 //! **Validates: Requirements 24.1-24.8**
 
-use std::path::{Path, PathBuf};
-
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::core::flash::load_board_definition;
+use crate::core::lock::LockFile;
+use crate::core::manifest::Manifest;
+use crate::core::shared_storage::SharedStorage;
 use crate::error::ZigrootError;
+use crate::infra::download::Checksum;
 
 /// Cache information
 #[derive(Debug)]
@@ -116,79 +128,548 @@ pub fn clean_cache(project_dir: &Path) -> Result<u64, ZigrootError> {
     Ok(size_before)
 }
 
-/// Export cache to tarball
-pub fn export_cache(project_dir: &Path, output_path: &Path) -> Result<u64, ZigrootError> {
-    let cache_path = get_cache_dir(project_dir);
+/// Version of the shared cache layout recorded in exports
+///
+/// Bumped whenever the paths of [`SharedStorage`] change; archives written
+/// with a newer layout are refused on import.
+pub const CACHE_LAYOUT_VERSION: u32 = 1;
+
+/// First entry of an export, describing the archive
+const EXPORT_MANIFEST: &str = "zigroot-cache.toml";
+
+/// PAX extension holding the SHA256 of an entry's content
+const CHECKSUM_PAX_KEY: &str = "ZIGROOT.sha256";
+
+/// zstd compression level of exports
+const EXPORT_COMPRESSION_LEVEL: i32 = 3;
+
+/// Cache export and import errors
+#[derive(Error, Debug)]
+pub enum CacheError {
+    /// Reading or writing a file failed
+    #[error("IO error for '{path}': {error}")]
+    Io { path: PathBuf, error: String },
+
+    /// `--project` was given without a lock file
+    #[error("No zigroot.lock in '{path}'; run 'zigroot fetch' first")]
+    NoLockFile { path: PathBuf },
+
+    /// The lock file could not be read
+    #[error("Failed to load zigroot.lock: {0}")]
+    Lock(String),
+
+    /// The file is not a cache export
+    #[error("'{path}' is not a zigroot cache export: {reason}")]
+    InvalidArchive { path: PathBuf, reason: String },
+
+    /// The archive was written with a newer cache layout
+    #[error(
+        "'{path}' uses cache layout version {found}, but this zigroot supports up to version {supported}; upgrade zigroot to import it"
+    )]
+    IncompatibleLayout {
+        path: PathBuf,
+        found: u32,
+        supported: u32,
+    },
+
+    /// An entry does not match its recorded checksum
+    #[error("Checksum mismatch for '{entry}': expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        entry: String,
+        expected: String,
+        actual: String,
+    },
+}
 
-    if !cache_path.exists() {
-        // Create empty tarball marker
-        std::fs::write(output_path, "# Empty cache export\n").map_err(|e| {
-            ZigrootError::Filesystem(crate::error::FilesystemError::WriteFile {
-                path: output_path.to_path_buf(),
-                error: e.to_string(),
-            })
-        })?;
-        return Ok(0);
+/// Part of the shared cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheSection {
+    /// Source archives, git checkouts and external artifacts
+    Downloads,
+    /// Build artifacts
+    Builds,
+}
+
+impl CacheSection {
+    /// All sections, in export order
+    pub const ALL: [CacheSection; 2] = [CacheSection::Downloads, CacheSection::Builds];
+
+    /// Top-level directory of the section in an export
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CacheSection::Downloads => "downloads",
+            CacheSection::Builds => "builds",
+        }
     }
 
-    // For now, create a simple marker file
-    // In production, this would use tar crate to create actual tarball
-    let cache_info = get_cache_info(project_dir);
-    let content = format!(
-        "# Zigroot Cache Export\n# Items: {}\n# Size: {}\n# Path: {}\n",
-        cache_info.item_count,
-        cache_info.format_size(),
-        cache_info.path.display()
-    );
-
-    std::fs::write(output_path, content).map_err(|e| {
-        ZigrootError::Filesystem(crate::error::FilesystemError::WriteFile {
-            path: output_path.to_path_buf(),
-            error: e.to_string(),
-        })
-    })?;
+    /// Directory of the section in shared storage
+    fn dir(self, storage: &SharedStorage) -> &Path {
+        match self {
+            CacheSection::Downloads => storage.downloads_dir(),
+            CacheSection::Builds => storage.build_cache_dir(),
+        }
+    }
 
-    Ok(cache_info.size_bytes)
+    /// Number of path components naming one cached item
+    ///
+    /// Downloads are `<package>/<version>/<digest>`, `git/<url>/<commit>`
+    /// or `external/<algorithm>/<digest>`; builds are `<prefix>/<key>`.
+    fn item_depth(self) -> usize {
+        match self {
+            CacheSection::Downloads => 3,
+            CacheSection::Builds => 2,
+        }
+    }
 }
 
-/// Import cache from tarball
-pub fn import_cache(project_dir: &Path, input_path: &Path) -> Result<u64, ZigrootError> {
-    if !input_path.exists() {
-        return Err(ZigrootError::Filesystem(
-            crate::error::FilesystemError::ReadFile {
-                path: input_path.to_path_buf(),
-                error: "File not found".to_string(),
-            },
-        ));
+/// What to put into a cache export
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Only items the project's lock file refers to
+    pub project: bool,
+    /// Only items of these packages (all when empty)
+    pub packages: Vec<String>,
+    /// Only the downloads section
+    pub downloads_only: bool,
+    /// Only the builds section
+    pub builds_only: bool,
+}
+
+impl ExportOptions {
+    /// Whether a section is exported
+    fn includes(&self, section: CacheSection) -> bool {
+        match section {
+            CacheSection::Downloads => !self.builds_only,
+            CacheSection::Builds => !self.downloads_only,
+        }
     }
+}
 
-    // Read and validate tarball
-    let content = std::fs::read_to_string(input_path).map_err(|e| {
-        ZigrootError::Filesystem(crate::error::FilesystemError::ReadFile {
-            path: input_path.to_path_buf(),
-            error: e.to_string(),
-        })
+/// Result of a cache export
+#[derive(Debug, Default)]
+pub struct ExportSummary {
+    /// Files written to the archive
+    pub entries: usize,
+    /// Uncompressed size of the files
+    pub bytes: u64,
+}
+
+/// Result of a cache import
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    /// Files extracted into the cache
+    pub imported: usize,
+    /// Files skipped because the cache already had them
+    pub skipped: usize,
+    /// Size of the extracted files
+    pub bytes: u64,
+}
+
+/// Description of an export, stored as its first entry
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportManifest {
+    /// Cache layout the archive was written with
+    layout_version: u32,
+    /// zigroot version that wrote the archive
+    #[serde(default)]
+    zigroot_version: String,
+}
+
+/// Export the shared cache to a zstd-compressed tarball
+///
+/// Files are streamed into the archive one at a time, each preceded by its
+/// SHA256 in a PAX header. `project_dir`'s lock file selects the items of
+/// `--project` and names the owners of git checkouts, external artifacts
+/// and build artifacts for `--packages`.
+pub fn export_cache(
+    project_dir: &Path,
+    storage: &SharedStorage,
+    output_path: &Path,
+    options: &ExportOptions,
+) -> Result<ExportSummary, CacheError> {
+    let references = match lock_references(project_dir, storage) {
+        Ok(references) => references,
+        Err(e) if options.project => return Err(e),
+        Err(_) => HashMap::new(),
+    };
+
+    let file = File::create(output_path).map_err(io_error(output_path))?;
+    let encoder =
+        zstd::Encoder::new(file, EXPORT_COMPRESSION_LEVEL).map_err(io_error(output_path))?;
+    let mut archive = tar::Builder::new(encoder);
+    archive.follow_symlinks(false);
+
+    let manifest = toml::to_string(&ExportManifest {
+        layout_version: CACHE_LAYOUT_VERSION,
+        zigroot_version: env!("CARGO_PKG_VERSION").to_string(),
+    })
+    .map_err(|e| CacheError::Io {
+        path: output_path.to_path_buf(),
+        error: e.to_string(),
     })?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    archive
+        .append_data(&mut header, EXPORT_MANIFEST, manifest.as_bytes())
+        .map_err(io_error(output_path))?;
+
+    let mut summary = ExportSummary::default();
+    for section in CacheSection::ALL {
+        let dir = section.dir(storage);
+        if !options.includes(section) || !dir.exists() {
+            continue;
+        }
+        for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+            let entry = entry.map_err(|e| CacheError::Io {
+                path: dir.to_path_buf(),
+                error: e.to_string(),
+            })?;
+            let file_type = entry.file_type();
+            if !file_type.is_file() && !file_type.is_symlink() {
+                continue;
+            }
+            let Ok(relative) = entry.path().strip_prefix(dir) else {
+                continue;
+            };
+            // Lock files and other bookkeeping next to the items
+            if relative.components().count() <= section.item_depth() {
+                continue;
+            }
+            let item: PathBuf = Path::new(section.as_str()).join(
+                relative
+                    .components()
+                    .take(section.item_depth())
+                    .collect::<PathBuf>(),
+            );
+            if options.project && !references.contains_key(&item) {
+                continue;
+            }
+            if !options.packages.is_empty() {
+                let owner = references
+                    .get(&item)
+                    .map(String::as_str)
+                    .or_else(|| download_owner(section, relative));
+                if !owner.is_some_and(|owner| options.packages.iter().any(|p| p == owner)) {
+                    continue;
+                }
+            }
+
+            let name = Path::new(section.as_str()).join(relative);
+            summary.bytes +=
+                append_entry(&mut archive, entry.path(), &name, file_type.is_symlink())
+                    .map_err(io_error(entry.path()))?;
+            summary.entries += 1;
+        }
+    }
 
-    // Check if it's a valid cache export
-    if !content.contains("Zigroot Cache Export") && !content.contains("Empty cache export") {
-        return Err(ZigrootError::Generic(
-            "Invalid cache tarball format".to_string(),
-        ));
+    archive
+        .into_inner()
+        .and_then(zstd::Encoder::finish)
+        .map_err(io_error(output_path))?;
+    Ok(summary)
+}
+
+/// Import a cache export into shared storage
+///
+/// Each file is extracted next to its destination, checked against its
+/// recorded SHA256 and only then moved in place. Files the cache already
+/// has are skipped.
+pub fn import_cache(
+    storage: &SharedStorage,
+    input_path: &Path,
+) -> Result<ImportSummary, CacheError> {
+    let invalid = |reason: String| CacheError::InvalidArchive {
+        path: input_path.to_path_buf(),
+        reason,
+    };
+
+    let file = File::open(input_path).map_err(io_error(input_path))?;
+    let decoder = zstd::Decoder::new(file).map_err(io_error(input_path))?;
+    let mut archive = tar::Archive::new(decoder);
+    let mut entries = archive.entries().map_err(|e| invalid(e.to_string()))?;
+
+    let mut first = entries
+        .next()
+        .ok_or_else(|| invalid("the archive is empty".to_string()))?
+        .map_err(|e| invalid(e.to_string()))?;
+    if first.path().ok().as_deref() != Some(Path::new(EXPORT_MANIFEST)) {
+        return Err(invalid(format!("it does not start with {EXPORT_MANIFEST}")));
+    }
+    let mut content = String::new();
+    first
+        .read_to_string(&mut content)
+        .map_err(|e| invalid(e.to_string()))?;
+    let manifest: ExportManifest =
+        toml::from_str(&content).map_err(|e| invalid(format!("invalid {EXPORT_MANIFEST}: {e}")))?;
+    if manifest.layout_version > CACHE_LAYOUT_VERSION {
+        return Err(CacheError::IncompatibleLayout {
+            path: input_path.to_path_buf(),
+            found: manifest.layout_version,
+            supported: CACHE_LAYOUT_VERSION,
+        });
     }
 
-    // Create cache directory
-    let cache_path = get_cache_dir(project_dir);
-    std::fs::create_dir_all(&cache_path).map_err(|e| {
-        ZigrootError::Filesystem(crate::error::FilesystemError::CreateDir {
-            path: cache_path.clone(),
-            error: e.to_string(),
+    let mut summary = ImportSummary::default();
+    for entry in entries {
+        let mut entry = entry.map_err(|e| invalid(e.to_string()))?;
+        let name = entry
+            .path()
+            .map_err(|e| invalid(e.to_string()))?
+            .into_owned();
+        let target = entry_target(storage, &name)
+            .ok_or_else(|| invalid(format!("unexpected entry '{}'", name.display())))?;
+        let expected = entry_checksum(&mut entry)
+            .ok_or_else(|| invalid(format!("entry '{}' has no checksum", name.display())))?;
+        // A symlink imported earlier must not redirect later entries
+        // out of the cache
+        if let Some(link) = symlinked_parent(storage, &target) {
+            return Err(invalid(format!(
+                "entry '{}' is below the symlink {}",
+                name.display(),
+                link.display()
+            )));
+        }
+
+        if target.symlink_metadata().is_ok() {
+            summary.skipped += 1;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error(parent))?;
+        }
+
+        let mismatch = |actual: String| CacheError::ChecksumMismatch {
+            entry: name.display().to_string(),
+            expected: expected.clone(),
+            actual,
+        };
+        if entry.header().entry_type().is_symlink() {
+            let link =
+                entry.link_name().ok().flatten().ok_or_else(|| {
+                    invalid(format!("symlink '{}' has no target", name.display()))
+                })?;
+            let actual = hex::encode(Sha256::digest(link.as_os_str().as_encoded_bytes()));
+            if actual != expected {
+                return Err(mismatch(actual));
+            }
+            entry.unpack(&target).map_err(io_error(&target))?;
+        } else {
+            let (actual, size) = extract_verified(&mut entry, &target, &expected)?;
+            if actual != expected {
+                return Err(mismatch(actual));
+            }
+            summary.bytes += size;
+        }
+        summary.imported += 1;
+    }
+    Ok(summary)
+}
+
+/// Write an entry's content next to `target`, moving it in place if its
+/// SHA256 is `expected`
+///
+/// Returns the SHA256 and size of the content.
+fn extract_verified(
+    entry: &mut tar::Entry<'_, impl Read>,
+    target: &Path,
+    expected: &str,
+) -> Result<(String, u64), CacheError> {
+    let mut partial_name = target.file_name().unwrap_or_default().to_os_string();
+    partial_name.push(".part");
+    let partial = target.with_file_name(partial_name);
+
+    let mut hasher = Sha256::new();
+    let mut size = 0;
+    let mut out = File::create(&partial).map_err(io_error(&partial))?;
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = entry.read(&mut buffer).map_err(io_error(target))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        out.write_all(&buffer[..read]).map_err(io_error(&partial))?;
+        size += read as u64;
+    }
+    drop(out);
+
+    let actual = hex::encode(hasher.finalize());
+    if actual != expected {
+        let _ = std::fs::remove_file(&partial);
+        return Ok((actual, size));
+    }
+    #[cfg(unix)]
+    if let Ok(mode) = entry.header().mode() {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&partial, std::fs::Permissions::from_mode(mode & 0o7777))
+            .map_err(io_error(&partial))?;
+    }
+    std::fs::rename(&partial, target).map_err(io_error(target))?;
+    Ok((actual, size))
+}
+
+/// Add a file or symlink to an export, preceded by its SHA256
+///
+/// A symlink's SHA256 is that of its target path. Returns the size of the
+/// file.
+fn append_entry(
+    archive: &mut tar::Builder<impl Write>,
+    path: &Path,
+    name: &Path,
+    symlink: bool,
+) -> std::io::Result<u64> {
+    let (checksum, size) = if symlink {
+        let link = std::fs::read_link(path)?;
+        (
+            hex::encode(Sha256::digest(link.as_os_str().as_encoded_bytes())),
+            0,
+        )
+    } else {
+        let mut hasher = Sha256::new();
+        let size = std::io::copy(&mut File::open(path)?, &mut hasher)?;
+        (hex::encode(hasher.finalize()), size)
+    };
+    archive.append_pax_extensions([(CHECKSUM_PAX_KEY, checksum.as_bytes())])?;
+    archive.append_path_with_name(path, name)?;
+    Ok(size)
+}
+
+/// SHA256 recorded for an entry of an export
+fn entry_checksum(entry: &mut tar::Entry<'_, impl Read>) -> Option<String> {
+    entry
+        .pax_extensions()
+        .ok()
+        .flatten()?
+        .filter_map(Result::ok)
+        .find(|extension| extension.key() == Ok(CHECKSUM_PAX_KEY))
+        .and_then(|extension| extension.value().ok().map(str::to_string))
+}
+
+/// Where an entry of an export goes in shared storage
+///
+/// `None` for entries outside the sections or above the cached items, and
+/// for paths that could escape the cache directories.
+fn entry_target(storage: &SharedStorage, name: &Path) -> Option<PathBuf> {
+    let mut components = name.components();
+    let Some(Component::Normal(first)) = components.next() else {
+        return None;
+    };
+    let section = CacheSection::ALL
+        .into_iter()
+        .find(|section| first == section.as_str())?;
+    let relative = components.as_path();
+    let safe = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    (safe && relative.components().count() > section.item_depth())
+        .then(|| section.dir(storage).join(relative))
+}
+
+/// First directory between a cache section and `target` that is a symlink
+fn symlinked_parent<'a>(storage: &SharedStorage, target: &'a Path) -> Option<&'a Path> {
+    let roots = CacheSection::ALL.map(|section| section.dir(storage));
+    target
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| !roots.contains(dir))
+        .find(|dir| {
+            dir.symlink_metadata()
+                .is_ok_and(|metadata| metadata.file_type().is_symlink())
         })
-    })?;
+}
+
+/// Package a download belongs to, from its `<package>/...` path
+fn download_owner(section: CacheSection, relative: &Path) -> Option<&str> {
+    if section != CacheSection::Downloads {
+        return None;
+    }
+    let first = relative.components().next()?.as_os_str().to_str()?;
+    (first != "git" && first != "external").then_some(first)
+}
+
+/// Cached items the project's lock file refers to, as
+/// `<section>/<item>` paths, with the package or artifact they belong to
+///
+/// Build artifacts are keyed with the board's target, so they are only
+/// found when the board definition loads.
+fn lock_references(
+    project_dir: &Path,
+    storage: &SharedStorage,
+) -> Result<HashMap<PathBuf, String>, CacheError> {
+    let lock_path = project_dir.join("zigroot.lock");
+    if !lock_path.exists() {
+        return Err(CacheError::NoLockFile {
+            path: project_dir.to_path_buf(),
+        });
+    }
+    let lock = LockFile::load(&lock_path).map_err(|e| CacheError::Lock(e.to_string()))?;
+    let target = Manifest::load(&project_dir.join("zigroot.toml"))
+        .ok()
+        .and_then(|manifest| {
+            let board = load_board_definition(project_dir, manifest.board.name.as_deref()?).ok()?;
+            Some(board.target(&manifest.board))
+        });
+
+    let mut references = HashMap::new();
+    let mut add = |section: CacheSection, path: &Path, owner: &str| {
+        if let Ok(relative) = path.strip_prefix(section.dir(storage)) {
+            references.insert(
+                Path::new(section.as_str()).join(relative),
+                owner.to_string(),
+            );
+        }
+    };
+    for package in &lock.packages {
+        if let Some(checksum) = package.parsed_checksum() {
+            let download = storage.download_path(&package.name, &package.version, &checksum.digest);
+            if let Some(item) = download.parent() {
+                add(CacheSection::Downloads, item, &package.name);
+            }
+            if let Some(target) = &target {
+                let key = SharedStorage::compute_cache_key(
+                    &package.name,
+                    &package.version,
+                    &checksum.digest,
+                    target,
+                    &lock.metadata.zig_version,
+                );
+                add(
+                    CacheSection::Builds,
+                    &storage.cache_path(&key),
+                    &package.name,
+                );
+            }
+        }
+        let git_url = package
+            .source
+            .as_deref()
+            .and_then(|source| source.strip_prefix("git:"))
+            .map(|source| source.split_once('#').map_or(source, |(url, _)| url));
+        if let (Some(url), Some(commit)) = (git_url, &package.git_sha) {
+            add(
+                CacheSection::Downloads,
+                &storage.git_path(url, commit),
+                &package.name,
+            );
+        }
+    }
+    for external in &lock.externals {
+        if let Ok(checksum) = external.checksum.parse::<Checksum>() {
+            if let Some(item) = storage.external_path(&checksum, &external.name).parent() {
+                add(CacheSection::Downloads, item, &external.name);
+            }
+        }
+    }
+    Ok(references)
+}
 
-    // In production, this would extract the tarball
-    // For now, just return success
-    Ok(0)
+/// Error mapper for IO errors on a path
+fn io_error(path: &Path) -> impl Fn(std::io::Error) -> CacheError + '_ {
+    move |e| CacheError::Io {
+        path: path.to_path_buf(),
+        error: e.to_string(),
+    }
 }
 
 /// Generate cache key for a package
@@ -199,8 +680,6 @@ pub fn generate_cache_key(
     target: &str,
     compiler_version: &str,
 ) -> String {
-    use sha2::{Digest, Sha256};
-
     let mut hasher = Sha256::new();
     hasher.update(package_name.as_bytes());
     hasher.update(version.as_bytes());
@@ -251,6 +730,34 @@ mod tests {
         assert!(info.format_size().contains("MB"));
     }
 
+    #[test]
+    fn test_entry_target_stays_inside_cache() {
+        let storage = SharedStorage::new(&crate::infra::dirs::ZigrootDirs::new());
+        assert_eq!(
+            entry_target(
+                &storage,
+                Path::new("downloads/zlib/1.3/abcd1234/zlib.tar.gz")
+            ),
+            Some(
+                storage
+                    .downloads_dir()
+                    .join("zlib/1.3/abcd1234/zlib.tar.gz")
+            )
+        );
+        assert_eq!(
+            entry_target(&storage, Path::new("builds/ab/abcdef/lib/libz.a")),
+            Some(storage.build_cache_dir().join("ab/abcdef/lib/libz.a"))
+        );
+        for name in [
+            "downloads/zlib/../../../etc/passwd",
+            "/downloads/zlib/1.3/abcd1234/zlib.tar.gz",
+            "builds/ab/abcdef",
+            "sources/zlib/1.3/abcd1234/zlib.tar.gz",
+        ] {
+            assert_eq!(entry_target(&storage, Path::new(name)), None, "{name}");
+        }
+    }
+
     #[test]
     fn test_generate_cache_key() {
         let key1 = generate_cache_key("pkg", "1.0.0", "abc123", "arm-linux", "0.11.0");
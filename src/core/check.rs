@@ -5,13 +5,28 @@
 //!
 //! **Validates: Requirements 4.13**
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::time::Duration;
 
+use crate::core::board::BoardDefinition;
+use crate::core::external;
+use crate::core::fit;
+use crate::core::flash::load_board_definition;
+use crate::core::hooks;
 use crate::core::manifest::Manifest;
-use crate::core::package::PackageDefinition;
-use crate::core::resolver::DependencyGraph;
+use crate::core::options::{resolve_all_options, validate_all_options, OptionSource};
+use crate::core::package::{OptionDefinition, PackageDefinition};
+use crate::core::partition;
+use crate::core::permissions;
+use crate::core::resolver::{detect_package_conflicts, DependencyGraph};
+use crate::core::search::{closest_matches, did_you_mean, MAX_SUGGESTIONS};
 use crate::error::ZigrootError;
+use crate::infra::download::{DownloadManager, UrlStatus};
+use crate::registry::client::{cached_package_index, PackageIndex, RegistryClient};
+
+/// Timeout for each URL reachability check
+const URL_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Result of the check operation
 #[derive(Debug)]
@@ -30,6 +45,10 @@ pub struct CheckResult {
     pub warnings: Vec<String>,
     /// Missing dependencies (if any)
     pub missing_dependencies: Vec<String>,
+    /// Conflicts between selected packages (if any)
+    pub conflicts: Vec<String>,
+    /// Configuration errors (if any)
+    pub config_errors: Vec<String>,
 }
 
 impl CheckResult {
@@ -43,6 +62,8 @@ impl CheckResult {
             build_order: Vec::new(),
             warnings: Vec::new(),
             missing_dependencies: Vec::new(),
+            conflicts: Vec::new(),
+            config_errors: Vec::new(),
         }
     }
 
@@ -72,6 +93,8 @@ pub fn check(project_dir: &Path, manifest: &Manifest) -> Result<CheckResult, Zig
     let packages_dir = project_dir.join("packages");
     let mut dependency_graph = DependencyGraph::new();
     let mut all_dependencies: HashSet<String> = HashSet::new();
+    let mut declared_conflicts = HashMap::new();
+    let mut package_options = Vec::new();
 
     for pkg_name in &result.packages_to_build {
         let local_pkg_path = packages_dir.join(pkg_name).join("package.toml");
@@ -83,13 +106,18 @@ pub fn check(project_dir: &Path, manifest: &Manifest) -> Result<CheckResult, Zig
                     match PackageDefinition::from_toml(&content) {
                         Ok(pkg_def) => {
                             // Add package to dependency graph
-                            let deps: Vec<String> = pkg_def.package.depends.clone();
+                            let deps = pkg_def.package.build_order_dependencies();
 
                             for dep in &deps {
                                 all_dependencies.insert(dep.clone());
                             }
 
                             dependency_graph.add_package(pkg_name, deps);
+                            declared_conflicts
+                                .insert(pkg_name.clone(), pkg_def.package.conflicts.clone());
+                            if !pkg_def.options.is_empty() {
+                                package_options.push((pkg_name.clone(), pkg_def.options));
+                            }
                         }
                         Err(e) => {
                             result.warnings.push(format!(
@@ -107,9 +135,16 @@ pub fn check(project_dir: &Path, manifest: &Manifest) -> Result<CheckResult, Zig
         } else {
             // Registry package - add with no dependencies for now
             dependency_graph.add_package(pkg_name, vec![]);
+            declared_conflicts.insert(pkg_name.clone(), Vec::new());
         }
     }
 
+    // Check for conflicting packages
+    if let Err(e) = detect_package_conflicts(&declared_conflicts) {
+        result.dependencies_valid = false;
+        result.conflicts.push(e.to_string());
+    }
+
     // Check for missing dependencies
     let known_packages: HashSet<String> = result.packages_to_build.iter().cloned().collect();
     for dep in &all_dependencies {
@@ -151,16 +186,225 @@ pub fn check(project_dir: &Path, manifest: &Manifest) -> Result<CheckResult, Zig
             .push("Zig toolchain not found in PATH".to_string());
     }
 
+    result.config_errors = config_errors(project_dir, manifest, package_options);
+    if !result.config_errors.is_empty() {
+        result.config_valid = false;
+    }
+
+    Ok(result)
+}
+
+/// Collect configuration errors of external artifacts, FIT inputs, the
+/// disk layout, the permissions overlay, hooks and package options
+fn config_errors(
+    project_dir: &Path,
+    manifest: &Manifest,
+    mut package_options: Vec<(String, HashMap<String, OptionDefinition>)>,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+
     // Validate external artifacts
-    for (name, artifact) in &manifest.external {
-        if artifact.url.is_some() && artifact.sha256.is_none() {
-            result.warnings.push(format!(
-                "External artifact '{name}' has URL but no sha256 checksum"
-            ));
+    let mut artifacts: Vec<_> = manifest.external.iter().collect();
+    artifacts.sort_by_key(|(name, _)| *name);
+    for (name, artifact) in artifacts {
+        errors.extend(external::validate_artifact(project_dir, name, artifact));
+    }
+
+    // Validate FIT inputs and the disk layout before building
+    if let Some(fit_config) = fit::project_fit(project_dir, manifest) {
+        errors.extend(fit::check_inputs(project_dir, manifest, &fit_config));
+    }
+    errors.extend(partition::check_layout(project_dir, manifest));
+    errors.extend(permissions::check_entries(&manifest.permissions));
+    if let Some(hooks_config) = &manifest.hooks {
+        errors.extend(hooks::check_hooks(hooks_config));
+    }
+
+    // Validate each package's resolved options, including their relations
+    package_options.sort_by(|a, b| a.0.cmp(&b.0));
+    let manifest_content = std::fs::read_to_string(project_dir.join("zigroot.toml")).ok();
+    let board = manifest
+        .board
+        .name
+        .as_deref()
+        .and_then(|name| load_board_definition(project_dir, name).ok());
+    for (pkg_name, definitions) in &package_options {
+        errors.extend(check_package_options(
+            pkg_name,
+            definitions,
+            manifest,
+            board.as_ref(),
+            manifest_content.as_deref(),
+        ));
+    }
+
+    errors
+}
+
+/// Resolve and validate a package's options from the manifest and board
+///
+/// Each error names the manifest line to change, or where to add the
+/// option when a board or package default is in effect.
+fn check_package_options(
+    pkg_name: &str,
+    definitions: &HashMap<String, OptionDefinition>,
+    manifest: &Manifest,
+    board: Option<&BoardDefinition>,
+    manifest_content: Option<&str>,
+) -> Vec<String> {
+    let package_values = manifest
+        .packages
+        .get(pkg_name)
+        .map(|pkg_ref| pkg_ref.options.clone())
+        .unwrap_or_default();
+    let board_values = board
+        .and_then(|board| board.package_options.get(pkg_name).cloned())
+        .unwrap_or_default();
+    let mut resolved = resolve_all_options(
+        definitions,
+        &HashMap::new(),
+        &package_values,
+        &board_values,
+        &HashMap::new(),
+    );
+
+    for (name, option) in &mut resolved {
+        option.location = Some(match option.source {
+            OptionSource::Package => manifest_content
+                .and_then(|content| {
+                    manifest_line(content, &["packages", pkg_name, "options", name])
+                })
+                .map_or_else(
+                    || format!("[packages.{pkg_name}.options] in zigroot.toml"),
+                    |line| format!("zigroot.toml line {line}"),
+                ),
+            OptionSource::Board => format!(
+                "[package_options.{pkg_name}] of board '{}', override it under [packages.{pkg_name}.options] in zigroot.toml",
+                board.map_or("", |board| board.board.name.as_str())
+            ),
+            _ => format!("set it under [packages.{pkg_name}.options] in zigroot.toml"),
+        });
+    }
+
+    match validate_all_options(definitions, &resolved) {
+        Ok(()) => Vec::new(),
+        Err(crate::error::OptionError::Relationships { violations }) => violations
+            .into_iter()
+            .map(|v| format!("Package '{pkg_name}': {v}"))
+            .collect(),
+        Err(e) => vec![format!("Package '{pkg_name}': {e}")],
+    }
+}
+
+/// 1-based line of a manifest value, if present
+fn manifest_line(content: &str, path: &[&str]) -> Option<usize> {
+    let doc = toml_edit::ImDocument::parse(content).ok()?;
+    let mut item = doc.as_item();
+    for key in path {
+        item = item.get(key)?;
+    }
+    let span = item.span()?;
+    Some(content[..span.start].matches('\n').count() + 1)
+}
+
+/// Check that external artifact URLs are reachable (`--network`)
+///
+/// Unreachable URLs are configuration errors. Servers that reject HEAD
+/// requests only produce a warning, since the URL may still be fine.
+pub async fn check_artifact_urls(manifest: &Manifest, result: &mut CheckResult) {
+    let mut urls: Vec<(&String, &str)> = manifest
+        .external
+        .iter()
+        .filter_map(|(name, artifact)| Some((name, artifact.url.as_deref()?)))
+        .collect();
+    urls.sort_unstable();
+
+    let downloads = DownloadManager::new();
+    let probes = urls
+        .iter()
+        .map(|(_, url)| downloads.probe(url, URL_CHECK_TIMEOUT));
+    let statuses = futures::future::join_all(probes).await;
+
+    for ((name, url), status) in urls.into_iter().zip(statuses) {
+        match status {
+            UrlStatus::Reachable => {}
+            UrlStatus::HeadUnsupported(code) => result.warnings.push(format!(
+                "External artifact '{name}': {url} does not support HEAD requests (HTTP {code}), reachability not verified"
+            )),
+            UrlStatus::Unreachable(reason) => {
+                result.config_errors.push(format!(
+                    "External artifact '{name}': {url} is unreachable: {reason}"
+                ));
+                result.config_valid = false;
+            }
         }
     }
+}
 
-    Ok(result)
+/// Check that the manifest's registry packages exist in the package index
+///
+/// The cached index is used while fresh, otherwise it is fetched; a stale
+/// cache still serves when the registry is unreachable. Without any index
+/// the check is skipped with a warning.
+pub async fn check_registry_packages(
+    project_dir: &Path,
+    manifest: &Manifest,
+    result: &mut CheckResult,
+) {
+    let client = RegistryClient::new();
+    let index = match client.fetch_package_index().await {
+        Ok(index) => index,
+        Err(e) => {
+            let Some(index) = cached_package_index(client.cache_dir()) else {
+                result.warnings.push(format!(
+                    "Package names not checked against the registry: {e}"
+                ));
+                return;
+            };
+            index
+        }
+    };
+
+    let errors = unknown_packages(project_dir, manifest, &index);
+    if !errors.is_empty() {
+        result.config_errors.extend(errors);
+        result.config_valid = false;
+    }
+}
+
+/// Errors for manifest packages missing from the package index
+///
+/// Packages with a definition in `packages/`, from git or from a custom
+/// registry are not looked up.
+fn unknown_packages(project_dir: &Path, manifest: &Manifest, index: &PackageIndex) -> Vec<String> {
+    let known: HashSet<&str> = index.packages.iter().map(|p| p.name.as_str()).collect();
+    let mut names: Vec<&String> = manifest
+        .packages
+        .iter()
+        .filter(|(name, pkg_ref)| {
+            pkg_ref.git.is_none()
+                && pkg_ref.registry.is_none()
+                && !project_dir
+                    .join("packages")
+                    .join(name)
+                    .join("package.toml")
+                    .exists()
+                && !known.contains(name.as_str())
+        })
+        .map(|(name, _)| name)
+        .collect();
+    names.sort_unstable();
+
+    names
+        .into_iter()
+        .map(|name| {
+            let suggestions = closest_matches(name, known.iter().copied(), MAX_SUGGESTIONS);
+            format!(
+                "Package '{name}' not found in the registry{}",
+                did_you_mean(&suggestions)
+            )
+        })
+        .collect()
 }
 
 /// Check if the Zig toolchain is available
@@ -171,21 +415,30 @@ fn check_toolchain_availability() -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::manifest::{BoardConfig, BuildConfig, ProjectConfig};
-    use std::collections::HashMap;
+    use crate::core::manifest::{BoardConfig, BuildConfig, ProjectConfig, MANIFEST_VERSION};
+    use std::collections::{BTreeMap, HashMap};
     use tempfile::TempDir;
 
     fn create_test_manifest() -> Manifest {
         Manifest {
+            manifest_version: MANIFEST_VERSION,
             project: ProjectConfig {
                 name: "test-project".to_string(),
                 version: "1.0.0".to_string(),
                 description: None,
+                strict_env: false,
             },
             board: BoardConfig::default(),
             build: BuildConfig::default(),
             packages: HashMap::new(),
             external: HashMap::new(),
+            fit: None,
+            partitions: Vec::new(),
+            disk_image: None,
+            flash: None,
+            permissions: BTreeMap::new(),
+            license: None,
+            hooks: None,
         }
     }
 
@@ -201,6 +454,96 @@ mod tests {
         assert!(result.packages_to_build.is_empty());
     }
 
+    #[test]
+    fn test_check_reports_conflicting_packages() {
+        let temp_dir = TempDir::new().unwrap();
+        let pkg_dir = temp_dir.path().join("packages").join("dropbear");
+        std::fs::create_dir_all(&pkg_dir).unwrap();
+        std::fs::write(
+            pkg_dir.join("package.toml"),
+            r#"
+[package]
+name = "dropbear"
+version = "2024.85"
+description = "Small SSH server"
+conflicts = [{ name = "openssh", reason = "both provide sshd" }]
+
+[source]
+url = "https://example.com/dropbear.tar.bz2"
+sha256 = "abc123"
+"#,
+        )
+        .unwrap();
+
+        let mut manifest = create_test_manifest();
+        for name in ["dropbear", "openssh"] {
+            manifest.packages.insert(
+                name.to_string(),
+                crate::core::manifest::PackageRef {
+                    version: Some("1.0.0".to_string()),
+                    git: None,
+                    ref_: None,
+                    registry: None,
+                    options: HashMap::new(),
+                },
+            );
+        }
+
+        let result = check(temp_dir.path(), &manifest).unwrap();
+
+        assert!(!result.dependencies_valid);
+        assert_eq!(result.conflicts.len(), 1);
+        assert!(result.conflicts[0].contains("dropbear"));
+        assert!(result.conflicts[0].contains("openssh"));
+        assert!(result.conflicts[0].contains("both provide sshd"));
+    }
+
+    #[test]
+    fn test_unknown_packages_suggest_index_names() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("packages/myapp")).unwrap();
+        std::fs::write(temp_dir.path().join("packages/myapp/package.toml"), "").unwrap();
+
+        let mut manifest = create_test_manifest();
+        for (name, git) in [
+            ("busyboxx", None),
+            ("zlib", None),
+            ("myapp", None),
+            ("fork", Some("https://example.com/fork.git")),
+        ] {
+            manifest.packages.insert(
+                name.to_string(),
+                crate::core::manifest::PackageRef {
+                    version: Some("1.0.0".to_string()),
+                    git: git.map(str::to_string),
+                    ref_: None,
+                    registry: None,
+                    options: HashMap::new(),
+                },
+            );
+        }
+        let index = PackageIndex {
+            version: 1,
+            updated: String::new(),
+            packages: ["busybox", "zlib"]
+                .into_iter()
+                .map(|name| crate::registry::client::PackageIndexEntry {
+                    name: name.to_string(),
+                    description: String::new(),
+                    license: None,
+                    keywords: vec![],
+                    versions: vec![],
+                    latest: "1.0.0".to_string(),
+                })
+                .collect(),
+        };
+
+        assert_eq!(
+            unknown_packages(temp_dir.path(), &manifest, &index),
+            vec!["Package 'busyboxx' not found in the registry; did you mean 'busybox'?"]
+        );
+    }
+
     #[test]
     fn test_check_result_is_valid() {
         let result = CheckResult::new();
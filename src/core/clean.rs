@@ -1,17 +1,52 @@
 //! Clean logic
 //!
 //! This module contains the business logic for cleaning build artifacts.
-//! It removes the build/ and output/ directories.
+//! By default it removes the build/ and output/ directories, including the
+//! flat output layout of older builds, and the output directory configured
+//! in the manifest; project-local downloads and single packages can be
+//! cleaned on request. The shared download store is never touched.
 //!
 //! **Validates: Requirement 4.5**
 
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
 
+use crate::core::builder::{installed_files, INSTALLED_DIR, STAGING_DIR};
+use crate::core::manifest::Manifest;
+use crate::core::output::{OutputLayout, DEFAULT_OUTPUT_DIR};
+use crate::core::variants::VARIANTS_DIR;
 use crate::error::FilesystemError;
 
 /// Directories to remove during clean
 pub const CLEAN_DIRECTORIES: &[&str] = &["build", "output"];
 
+/// Project-local download directory, removed with `--downloads` or `--all`
+pub const DOWNLOADS_DIRECTORY: &str = "downloads";
+
+/// What to clean
+#[derive(Debug, Clone, Default)]
+pub struct CleanOptions {
+    /// Only remove the artifacts of this package
+    pub package: Option<String>,
+    /// Also remove project-local downloads
+    pub downloads: bool,
+    /// Remove every project-local artifact, including downloads
+    pub all: bool,
+    /// Report what would be removed without removing anything
+    pub dry_run: bool,
+}
+
+/// Paths removed in one category of artifacts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CleanedCategory {
+    /// Category name, e.g. `build` or `staging`
+    pub name: &'static str,
+    /// Removed paths, relative to the project root
+    pub paths: Vec<String>,
+    /// Space reclaimed in bytes
+    pub bytes: u64,
+}
+
 /// Result of clean operation
 #[derive(Debug, Default)]
 pub struct CleanResult {
@@ -19,6 +54,33 @@ pub struct CleanResult {
     pub removed: Vec<String>,
     /// Directories that didn't exist (skipped)
     pub skipped: Vec<String>,
+    /// Removed paths and reclaimed space per category
+    pub categories: Vec<CleanedCategory>,
+}
+
+impl CleanResult {
+    /// Total space reclaimed in bytes
+    pub fn total_bytes(&self) -> u64 {
+        self.categories.iter().map(|c| c.bytes).sum()
+    }
+
+    /// Whether nothing was (or would be) removed
+    pub fn is_empty(&self) -> bool {
+        self.categories.is_empty()
+    }
+
+    fn add(&mut self, name: &'static str, path: String, bytes: u64) {
+        if let Some(category) = self.categories.iter_mut().find(|c| c.name == name) {
+            category.paths.push(path);
+            category.bytes += bytes;
+        } else {
+            self.categories.push(CleanedCategory {
+                name,
+                paths: vec![path],
+                bytes,
+            });
+        }
+    }
 }
 
 /// Clean build artifacts from a project
@@ -34,26 +96,215 @@ pub struct CleanResult {
 /// * `Ok(CleanResult)` - Information about what was cleaned
 /// * `Err(FilesystemError)` - If removal fails
 pub fn clean_project(project_path: &Path) -> Result<CleanResult, FilesystemError> {
-    let mut result = CleanResult::default();
+    clean_with_options(project_path, &CleanOptions::default())
+}
 
-    for dir_name in CLEAN_DIRECTORIES {
+/// Clean build artifacts from a project with the given options
+///
+/// With `dry_run`, the result lists what would be removed and nothing is
+/// deleted.
+pub fn clean_with_options(
+    project_path: &Path,
+    options: &CleanOptions,
+) -> Result<CleanResult, FilesystemError> {
+    if let Some(package) = &options.package {
+        return clean_package(project_path, package, options.dry_run);
+    }
+
+    let mut directories = CLEAN_DIRECTORIES.to_vec();
+    if options.downloads || options.all {
+        directories.push(DOWNLOADS_DIRECTORY);
+    }
+
+    let mut result = CleanResult::default();
+    for dir_name in directories {
         let dir_path = project_path.join(dir_name);
 
         if dir_path.exists() {
-            // Remove the directory recursively
-            std::fs::remove_dir_all(&dir_path).map_err(|e| FilesystemError::RemoveDir {
-                path: dir_path.clone(),
-                error: e.to_string(),
-            })?;
-            result.removed.push((*dir_name).to_string());
+            let bytes = disk_usage(&dir_path);
+            if !options.dry_run {
+                remove_path(&dir_path)?;
+            }
+            result.removed.push(dir_name.to_string());
+            result.add(dir_name, format!("{dir_name}/"), bytes);
         } else {
-            result.skipped.push((*dir_name).to_string());
+            result.skipped.push(dir_name.to_string());
+        }
+    }
+
+    if let Some(path) = configured_output(project_path).filter(|path| path.exists()) {
+        let name = relative(project_path, &path);
+        let bytes = disk_usage(&path);
+        if !options.dry_run {
+            remove_path(&path)?;
+        }
+        result.removed.push(name.trim_end_matches('/').to_string());
+        result.add("output", name, bytes);
+    }
+
+    Ok(result)
+}
+
+/// Outputs in the `build.output_dir` of the manifest, if one is set
+///
+/// A directory outside the project may be shared, so only the outputs of
+/// the project's board are removed from it.
+fn configured_output(project_path: &Path) -> Option<PathBuf> {
+    let content = std::fs::read_to_string(project_path.join("zigroot.toml")).ok()?;
+    let manifest = Manifest::from_toml(&content).ok()?;
+    let dir = Path::new(manifest.build.output_dir.as_deref()?);
+    let layout = OutputLayout::new(project_path, &manifest);
+    let inside = dir
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    let path = if inside {
+        layout.root().to_path_buf()
+    } else {
+        layout.board_dir()
+    };
+    (path != project_path.join(DEFAULT_OUTPUT_DIR) && path != project_path).then_some(path)
+}
+
+/// Remove the build artifacts of a single package
+///
+/// Removes the package's build directory, its staging tree, its parked
+/// configurations, the files it installed into the assembled rootfs and its
+/// build stamp, so the next build rebuilds it. Rootfs files also installed by another package are
+/// kept.
+fn clean_package(
+    project_path: &Path,
+    package: &str,
+    dry_run: bool,
+) -> Result<CleanResult, FilesystemError> {
+    let build_dir = project_path.join("build");
+    let installed_dir = build_dir.join(INSTALLED_DIR);
+    let mut result = CleanResult::default();
+
+    // Rootfs contributions first, while the install manifest still exists
+    let rootfs_dir = build_dir.join("rootfs");
+    let shared = files_installed_by_others(&installed_dir, package)?;
+    let files =
+        installed_files(&installed_dir, package).map_err(|e| FilesystemError::ReadFile {
+            path: installed_dir.join(format!("{package}.files")),
+            error: e.to_string(),
+        })?;
+    for file in files.iter().filter(|file| !shared.contains(*file)) {
+        let path = rootfs_dir.join(file);
+        let Ok(metadata) = path.symlink_metadata() else {
+            continue;
+        };
+        result.add("rootfs", relative(project_path, &path), metadata.len());
+        if !dry_run {
+            remove_path(&path)?;
+            prune_empty_parents(&path, &rootfs_dir);
+        }
+    }
+
+    let candidates = [
+        ("build", build_dir.join("src").join(package)),
+        ("staging", build_dir.join(STAGING_DIR).join(package)),
+        ("build", build_dir.join(VARIANTS_DIR).join(package)),
+        (
+            "stamps",
+            build_dir.join("stamps").join(format!("{package}.stamp")),
+        ),
+        (
+            "logs",
+            build_dir.join("logs").join(format!("{package}.log")),
+        ),
+        ("stamps", installed_dir.join(format!("{package}.files"))),
+    ];
+    for (category, path) in candidates {
+        if path.symlink_metadata().is_err() {
+            continue;
+        }
+        result.add(category, relative(project_path, &path), disk_usage(&path));
+        if !dry_run {
+            remove_path(&path)?;
         }
     }
 
     Ok(result)
 }
 
+/// Rootfs files recorded for any package other than `package`
+fn files_installed_by_others(
+    installed_dir: &Path,
+    package: &str,
+) -> Result<HashSet<PathBuf>, FilesystemError> {
+    let mut shared = HashSet::new();
+    let Ok(entries) = std::fs::read_dir(installed_dir) else {
+        return Ok(shared);
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(other) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".files"))
+        else {
+            continue;
+        };
+        if other == package {
+            continue;
+        }
+        let files =
+            installed_files(installed_dir, other).map_err(|e| FilesystemError::ReadFile {
+                path: path.clone(),
+                error: e.to_string(),
+            })?;
+        shared.extend(files);
+    }
+    Ok(shared)
+}
+
+/// Remove a file, symlink or directory tree
+fn remove_path(path: &Path) -> Result<(), FilesystemError> {
+    let removed = if path.is_dir() && !path.is_symlink() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+    removed.map_err(|e| FilesystemError::RemoveDir {
+        path: path.to_path_buf(),
+        error: e.to_string(),
+    })
+}
+
+/// Remove directories left empty by removing `path`, up to `root`
+fn prune_empty_parents(path: &Path, root: &Path) {
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        if current == root || !current.starts_with(root) {
+            break;
+        }
+        if std::fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+/// Total size of the files under a path
+fn disk_usage(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| !entry.file_type().is_dir())
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+fn relative(project_path: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(project_path).unwrap_or(path);
+    if path.is_dir() {
+        format!("{}/", relative.display())
+    } else {
+        relative.display().to_string()
+    }
+}
+
 /// Check if a project has any build artifacts
 ///
 /// # Arguments
@@ -68,6 +319,7 @@ pub fn has_build_artifacts(project_path: &Path) -> bool {
     CLEAN_DIRECTORIES
         .iter()
         .any(|dir| project_path.join(dir).exists())
+        || configured_output(project_path).is_some_and(|path| path.exists())
 }
 
 #[cfg(test)]
@@ -105,6 +357,34 @@ mod tests {
         assert!(result.removed.contains(&"output".to_string()));
     }
 
+    #[test]
+    fn test_clean_removes_configured_output_directory() {
+        let project = create_test_project();
+        let shared = create_test_project();
+        let manifest = |dir: &str| {
+            format!("[project]\nname = \"p\"\n\n[board]\nname = \"rpi4\"\n\n[build]\noutput_dir = \"{dir}\"\n")
+        };
+        std::fs::write(project.path().join("zigroot.toml"), manifest("images")).unwrap();
+        let images = project.path().join("images");
+        std::fs::create_dir_all(images.join("rpi4/ext4")).unwrap();
+
+        let result = clean_project(project.path()).unwrap();
+        assert!(!images.exists());
+        assert!(result.removed.contains(&"images".to_string()));
+
+        // Only the board's outputs are removed from a directory outside
+        std::fs::write(
+            project.path().join("zigroot.toml"),
+            manifest(&shared.path().display().to_string()),
+        )
+        .unwrap();
+        std::fs::create_dir_all(shared.path().join("rpi4/ext4")).unwrap();
+        std::fs::create_dir_all(shared.path().join("other")).unwrap();
+        clean_project(project.path()).unwrap();
+        assert!(!shared.path().join("rpi4").exists());
+        assert!(shared.path().join("other").exists());
+    }
+
     #[test]
     fn test_clean_removes_both_directories() {
         let project = create_test_project();
@@ -146,4 +426,93 @@ mod tests {
 
         assert!(!has_build_artifacts(project.path()));
     }
+
+    #[test]
+    fn test_clean_dry_run_keeps_directories() {
+        let project = create_test_project();
+        std::fs::create_dir_all(project.path().join("build")).unwrap();
+        std::fs::write(project.path().join("build/test.txt"), "12345").unwrap();
+
+        let options = CleanOptions {
+            dry_run: true,
+            ..CleanOptions::default()
+        };
+        let result = clean_with_options(project.path(), &options).unwrap();
+
+        assert!(project.path().join("build").exists());
+        assert_eq!(result.categories[0].name, "build");
+        assert_eq!(result.total_bytes(), 5);
+    }
+
+    #[test]
+    fn test_clean_downloads_only_on_request() {
+        let project = create_test_project();
+        std::fs::create_dir_all(project.path().join(DOWNLOADS_DIRECTORY)).unwrap();
+
+        clean_project(project.path()).unwrap();
+        assert!(project.path().join(DOWNLOADS_DIRECTORY).exists());
+
+        let options = CleanOptions {
+            downloads: true,
+            ..CleanOptions::default()
+        };
+        let result = clean_with_options(project.path(), &options).unwrap();
+        assert!(!project.path().join(DOWNLOADS_DIRECTORY).exists());
+        assert!(result.removed.contains(&DOWNLOADS_DIRECTORY.to_string()));
+    }
+
+    #[test]
+    fn test_clean_package_removes_only_its_artifacts() {
+        let project = create_test_project();
+        let build = project.path().join("build");
+        for (path, content) in [
+            ("src/app/main.o", "obj"),
+            ("src/other/main.o", "obj"),
+            ("destdir/app/usr/bin/app", "bin"),
+            ("variants/app/0123abcd/destdir/usr/bin/app", "bin"),
+            ("stamps/app.stamp", "1"),
+            ("stamps/other.stamp", "1"),
+            ("rootfs/usr/bin/app", "bin"),
+            ("rootfs/etc/shared.conf", "conf"),
+            ("rootfs/usr/bin/other", "bin"),
+            ("installed/app.files", "usr/bin/app\netc/shared.conf\n"),
+            ("installed/other.files", "usr/bin/other\netc/shared.conf\n"),
+        ] {
+            let path = build.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        let options = CleanOptions {
+            package: Some("app".to_string()),
+            ..CleanOptions::default()
+        };
+        let result = clean_with_options(project.path(), &options).unwrap();
+
+        for removed in [
+            "src/app",
+            "destdir/app",
+            "variants/app",
+            "stamps/app.stamp",
+            "rootfs/usr/bin/app",
+            "installed/app.files",
+        ] {
+            assert!(!build.join(removed).exists(), "{removed}");
+        }
+        for kept in [
+            "src/other",
+            "stamps/other.stamp",
+            "rootfs/etc/shared.conf",
+            "rootfs/usr/bin/other",
+        ] {
+            assert!(build.join(kept).exists(), "{kept}");
+        }
+        let rootfs = result
+            .categories
+            .iter()
+            .find(|c| c.name == "rootfs")
+            .unwrap();
+        assert_eq!(rootfs.paths, vec!["build/rootfs/usr/bin/app"]);
+        assert_eq!(rootfs.bytes, 3);
+    }
 }
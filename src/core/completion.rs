@@ -0,0 +1,164 @@
+//! Dynamic shell completion
+//!
+//! Completes package and board names for the shell scripts of
+//! `zigroot completions`. Candidates come from the project and the cached
+//! registry indexes only: completion never touches the network, so a cold
+//! cache just yields fewer candidates.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::core::manifest::Manifest;
+use crate::registry::client::{cached_board_index, cached_package_index};
+
+/// What a dynamic completion completes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    /// Packages of the project and the registry (`add`)
+    Packages,
+    /// Packages in the project's manifest (`remove`, `tree`)
+    ProjectPackages,
+    /// Boards of the project and the registry (`board set`, `init --board`)
+    Boards,
+}
+
+impl std::str::FromStr for CompletionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "packages" => Ok(Self::Packages),
+            "project-packages" => Ok(Self::ProjectPackages),
+            "boards" => Ok(Self::Boards),
+            other => Err(format!("Unknown completion kind '{other}'")),
+        }
+    }
+}
+
+/// Names of `kind` starting with `prefix`, sorted and without duplicates
+///
+/// `project_dir` is searched for the manifest and local `packages/` and
+/// `boards/`; `cache_dir` is the registry cache directory.
+pub fn candidates(
+    kind: CompletionKind,
+    project_dir: Option<&Path>,
+    cache_dir: &Path,
+    prefix: &str,
+) -> Vec<String> {
+    let mut names = BTreeSet::new();
+    match kind {
+        CompletionKind::ProjectPackages => {
+            names.extend(manifest_packages(project_dir));
+        }
+        CompletionKind::Packages => {
+            names.extend(manifest_packages(project_dir));
+            names.extend(local_definitions(project_dir, "packages", "package.toml"));
+            if let Some(index) = cached_package_index(cache_dir) {
+                names.extend(index.packages.into_iter().map(|p| p.name));
+            }
+        }
+        CompletionKind::Boards => {
+            names.extend(local_definitions(project_dir, "boards", "board.toml"));
+            if let Some(index) = cached_board_index(cache_dir) {
+                names.extend(index.boards.into_iter().map(|b| b.name));
+            }
+        }
+    }
+    names
+        .into_iter()
+        .filter(|name| name.starts_with(prefix))
+        .collect()
+}
+
+/// Packages listed in the project's manifest
+fn manifest_packages(project_dir: Option<&Path>) -> Vec<String> {
+    project_dir
+        .and_then(|dir| std::fs::read_to_string(dir.join("zigroot.toml")).ok())
+        .and_then(|content| Manifest::from_toml(&content).ok())
+        .map(|manifest| manifest.packages.into_keys().collect())
+        .unwrap_or_default()
+}
+
+/// Directories under `project_dir/subdir` holding a `file` definition
+fn local_definitions(project_dir: Option<&Path>, subdir: &str, file: &str) -> Vec<String> {
+    let Some(entries) = project_dir.and_then(|dir| std::fs::read_dir(dir.join(subdir)).ok()) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().join(file).is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    fn project() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        write(
+            &temp.path().join("project/zigroot.toml"),
+            "[project]\nname = \"demo\"\n\n[packages.busybox]\nversion = \"1.36.1\"\n",
+        );
+        write(
+            &temp.path().join("project/packages/my-app/package.toml"),
+            "[package]\nname = \"my-app\"\n",
+        );
+        write(
+            &temp.path().join("project/boards/my-board/board.toml"),
+            "[board]\nname = \"my-board\"\n",
+        );
+        write(
+            &temp.path().join("cache/packages-index.json"),
+            r#"{"data": {"version": 1, "updated": "2024-01-01", "packages": [
+                {"name": "busybox", "description": "", "versions": [], "latest": "1.36.1"},
+                {"name": "bash", "description": "", "versions": [], "latest": "5.2"}
+            ]}, "cached_at": 0}"#,
+        );
+        write(
+            &temp.path().join("cache/boards-index.json"),
+            r#"{"data": {"version": 1, "updated": "2024-01-01", "boards": [
+                {"name": "rpi4", "description": "", "arch": "aarch64", "target": "aarch64-linux-musl"}
+            ]}, "cached_at": 0}"#,
+        );
+        temp
+    }
+
+    #[test]
+    fn test_package_candidates_merge_project_and_stale_cache() {
+        let temp = project();
+        let dir = temp.path().join("project");
+        let cache = temp.path().join("cache");
+
+        assert_eq!(
+            candidates(CompletionKind::Packages, Some(&dir), &cache, ""),
+            vec!["bash", "busybox", "my-app"]
+        );
+        assert_eq!(
+            candidates(CompletionKind::Packages, Some(&dir), &cache, "b"),
+            vec!["bash", "busybox"]
+        );
+        assert_eq!(
+            candidates(CompletionKind::ProjectPackages, Some(&dir), &cache, ""),
+            vec!["busybox"]
+        );
+        assert_eq!(
+            candidates(CompletionKind::Boards, Some(&dir), &cache, ""),
+            vec!["my-board", "rpi4"]
+        );
+    }
+
+    #[test]
+    fn test_candidates_without_project_or_cache_are_empty() {
+        let temp = TempDir::new().unwrap();
+        assert!(candidates(CompletionKind::Packages, None, temp.path(), "").is_empty());
+        assert!(candidates(CompletionKind::Boards, None, temp.path(), "").is_empty());
+    }
+}
@@ -44,6 +44,15 @@ impl CompressionStats {
     pub fn bytes_saved(&self) -> u64 {
         self.original_size.saturating_sub(self.compressed_size)
     }
+
+    /// Add another set of statistics to this one
+    pub fn merge(&mut self, other: &Self) {
+        self.files_compressed += other.files_compressed;
+        self.files_skipped += other.files_skipped;
+        self.files_failed += other.files_failed;
+        self.original_size += other.original_size;
+        self.compressed_size += other.compressed_size;
+    }
 }
 
 /// Compression configuration
@@ -93,6 +102,25 @@ impl CompressionConfig {
     }
 }
 
+/// Compression forced on the command line by `--compress` or `--no-compress`
+///
+/// `None` means neither flag was given, so the package's `compress` setting
+/// and then `build.compress` of the manifest decide.
+///
+/// # Errors
+///
+/// Returns an error if both flags are given.
+pub fn cli_override(compress: bool, no_compress: bool) -> Result<Option<bool>> {
+    match (compress, no_compress) {
+        (true, true) => anyhow::bail!(
+            "--compress and --no-compress cannot be used together; pass one of them, or neither to use build.compress from zigroot.toml"
+        ),
+        (true, false) => Ok(Some(true)),
+        (false, true) => Ok(Some(false)),
+        (false, false) => Ok(None),
+    }
+}
+
 /// Check if UPX is installed on the system
 pub fn is_upx_available() -> bool {
     which::which("upx").is_ok()
@@ -124,8 +152,9 @@ pub fn find_elf_binaries(dir: &Path) -> Result<Vec<PathBuf>> {
         .into_iter()
         .filter_map(Result::ok)
     {
+        // Links are left alone: their targets may be host paths
         let path = entry.path();
-        if is_elf_binary(path) {
+        if entry.file_type().is_file() && is_elf_binary(path) {
             binaries.push(path.to_path_buf());
         }
     }
@@ -133,12 +162,64 @@ pub fn find_elf_binaries(dir: &Path) -> Result<Vec<PathBuf>> {
     Ok(binaries)
 }
 
+/// Check if a file name matches a glob pattern (`*` and `?` wildcards)
+pub fn matches_glob(pattern: &str, name: &str) -> bool {
+    fn matches(pattern: &[char], name: &[char]) -> bool {
+        match pattern.split_first() {
+            None => name.is_empty(),
+            Some(('*', rest)) => (0..=name.len()).any(|i| matches(rest, &name[i..])),
+            Some(('?', rest)) => !name.is_empty() && matches(rest, &name[1..]),
+            Some((c, rest)) => name.first() == Some(c) && matches(rest, &name[1..]),
+        }
+    }
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    matches(&pattern, &name)
+}
+
+/// Check if a binary is excluded from compression by file name
+pub fn is_excluded(path: &Path, exclude: &[String]) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    exclude.iter().any(|pattern| matches_glob(pattern, name))
+}
+
 /// Compress a single binary using UPX
+///
+/// The packed binary is checked with `upx -t`; if packing or the check
+/// fails, the original binary is restored and an error is returned.
 pub fn compress_binary(path: &Path) -> Result<(u64, u64)> {
     let original_size = std::fs::metadata(path)
         .with_context(|| format!("Failed to get size of {}", path.display()))?
         .len();
 
+    // Keep the original so a broken result can be rolled back
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".upx-orig");
+    let backup = PathBuf::from(backup);
+    std::fs::copy(path, &backup)
+        .with_context(|| format!("Failed to back up {}", path.display()))?;
+
+    let result = pack_and_verify(path);
+    if result.is_err() {
+        std::fs::rename(&backup, path)
+            .with_context(|| format!("Failed to restore {}", path.display()))?;
+    } else {
+        let _ = std::fs::remove_file(&backup);
+    }
+    result?;
+
+    let compressed_size = std::fs::metadata(path)
+        .with_context(|| format!("Failed to get compressed size of {}", path.display()))?
+        .len();
+
+    Ok((original_size, compressed_size))
+}
+
+/// Run UPX on a binary and test the packed result
+fn pack_and_verify(path: &Path) -> Result<()> {
     // Run UPX with best compression
     let output = Command::new("upx")
         .args(["--best", "--quiet"])
@@ -151,30 +232,32 @@ pub fn compress_binary(path: &Path) -> Result<(u64, u64)> {
         anyhow::bail!("UPX failed for {}: {}", path.display(), stderr);
     }
 
-    let compressed_size = std::fs::metadata(path)
-        .with_context(|| format!("Failed to get compressed size of {}", path.display()))?
-        .len();
-
-    Ok((original_size, compressed_size))
-}
-
-/// Compress all binaries in a rootfs directory
-pub fn compress_rootfs(rootfs_dir: &Path, config: &CompressionConfig) -> Result<CompressionStats> {
-    let mut stats = CompressionStats::default();
+    let output = Command::new("upx")
+        .args(["-t", "--quiet"])
+        .arg(path)
+        .output()
+        .with_context(|| format!("Failed to verify {}", path.display()))?;
 
-    // Check if compression is enabled
-    if !config.is_enabled() {
-        tracing::info!("Compression disabled");
-        return Ok(stats);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("UPX verification failed for {}: {}", path.display(), stderr);
     }
 
+    Ok(())
+}
+
+/// Check that UPX can be used for the configured target
+///
+/// Logs a warning and returns false if the architecture is unsupported
+/// or UPX is not installed.
+pub fn is_upx_usable(config: &CompressionConfig) -> bool {
     // Check if architecture is supported
     if !config.is_arch_supported() {
         tracing::warn!(
             "Architecture '{}' is not supported by UPX, skipping compression",
             config.target_arch
         );
-        return Ok(stats);
+        return false;
     }
 
     // Check if UPX is available
@@ -182,13 +265,23 @@ pub fn compress_rootfs(rootfs_dir: &Path, config: &CompressionConfig) -> Result<
         tracing::warn!(
             "UPX not found, skipping compression. Install UPX to enable binary compression."
         );
-        return Ok(stats);
+        return false;
     }
 
-    tracing::info!("Compressing binaries in {}", rootfs_dir.display());
+    true
+}
+
+/// Compress all binaries in a directory
+///
+/// Binaries whose file name matches an `exclude` pattern are skipped.
+/// Binaries that fail to compress or verify are left uncompressed.
+pub fn compress_dir(dir: &Path, exclude: &[String]) -> Result<CompressionStats> {
+    let mut stats = CompressionStats::default();
+
+    tracing::info!("Compressing binaries in {}", dir.display());
 
     // Find all ELF binaries
-    let binaries = find_elf_binaries(rootfs_dir)?;
+    let binaries = find_elf_binaries(dir)?;
 
     if binaries.is_empty() {
         tracing::info!("No ELF binaries found to compress");
@@ -199,6 +292,12 @@ pub fn compress_rootfs(rootfs_dir: &Path, config: &CompressionConfig) -> Result<
 
     // Compress each binary
     for binary in &binaries {
+        if is_excluded(binary, exclude) {
+            tracing::debug!("Skipping excluded binary {}", binary.display());
+            stats.files_skipped += 1;
+            continue;
+        }
+
         match compress_binary(binary) {
             Ok((original, compressed)) => {
                 stats.files_compressed += 1;
@@ -317,6 +416,28 @@ mod tests {
         assert!(!config.is_enabled_for_package(None));
     }
 
+    #[test]
+    fn test_cli_override_precedence() {
+        assert!(cli_override(true, true).is_err());
+        assert_eq!(cli_override(true, false).unwrap(), Some(true));
+        assert_eq!(cli_override(false, true).unwrap(), Some(false));
+        assert_eq!(cli_override(false, false).unwrap(), None);
+
+        // Without a flag, the package setting and then the manifest decide
+        for global_enabled in [false, true] {
+            let config = CompressionConfig {
+                global_enabled,
+                cli_compress: false,
+                cli_no_compress: false,
+                target_arch: "x86_64".to_string(),
+            };
+            assert_eq!(config.is_enabled(), global_enabled);
+            assert_eq!(config.is_enabled_for_package(None), global_enabled);
+            assert!(config.is_enabled_for_package(Some(true)));
+            assert!(!config.is_enabled_for_package(Some(false)));
+        }
+    }
+
     #[test]
     fn test_arch_supported() {
         let supported = [
@@ -366,6 +487,36 @@ mod tests {
         assert!(!is_elf_binary(Path::new("/nonexistent/file")));
     }
 
+    #[test]
+    fn test_find_elf_binaries_skips_symlinks() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("app"), b"\x7fELF\x02\x01\x01").unwrap();
+        std::os::unix::fs::symlink("app", dir.path().join("alias")).unwrap();
+
+        assert_eq!(
+            find_elf_binaries(dir.path()).unwrap(),
+            vec![dir.path().join("app")]
+        );
+    }
+
+    #[test]
+    fn test_matches_glob() {
+        assert!(matches_glob("busybox", "busybox"));
+        assert!(matches_glob("lib*", "libc.so.6"));
+        assert!(matches_glob("*.so", "libfoo.so"));
+        assert!(matches_glob("sh?", "shd"));
+        assert!(!matches_glob("lib*", "busybox"));
+        assert!(!matches_glob("sh?", "sh"));
+    }
+
+    #[test]
+    fn test_is_excluded_uses_file_name() {
+        let exclude = vec!["busybox".to_string(), "lib*".to_string()];
+        assert!(is_excluded(Path::new("/rootfs/bin/busybox"), &exclude));
+        assert!(is_excluded(Path::new("/rootfs/lib/libc.so"), &exclude));
+        assert!(!is_excluded(Path::new("/rootfs/usr/bin/app"), &exclude));
+    }
+
     #[test]
     fn test_compression_stats_ratio() {
         let stats = CompressionStats {
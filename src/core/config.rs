@@ -4,10 +4,16 @@
 //!
 //! **Validates: Requirements 25.1-25.17**
 
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
+use crate::core::flash::load_board_definition;
 use crate::core::manifest::Manifest;
+use crate::core::options::{resolve_all_options, OptionSource, ResolvedOption};
+use crate::core::package::{ConflictSpec, PackageDefinition};
+use crate::core::resolver::package_conflict_error;
 use crate::error::ZigrootError;
+use crate::registry::client::BoardIndexEntry;
 
 /// Configuration categories available in the TUI
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -75,7 +81,7 @@ pub fn is_terminal_interactive() -> bool {
 
 /// Load manifest for configuration
 pub fn load_manifest_for_config(project_dir: &Path) -> Result<Manifest, ZigrootError> {
-    Manifest::load(project_dir)
+    Manifest::load(&project_dir.join("zigroot.toml"))
 }
 
 /// Get available packages for selection
@@ -110,22 +116,24 @@ pub fn get_package_dependencies(project_dir: &Path, package_name: &str) -> Vec<S
         return Vec::new();
     }
 
+    let mut deps = Vec::new();
     if let Ok(content) = std::fs::read_to_string(&package_toml) {
         if let Ok(value) = content.parse::<toml::Value>() {
             if let Some(package) = value.get("package") {
-                if let Some(depends) = package.get("depends") {
-                    if let Some(deps) = depends.as_array() {
-                        return deps
-                            .iter()
-                            .filter_map(|v| v.as_str().map(String::from))
-                            .collect();
+                for key in ["depends", "dependencies", "build_depends"] {
+                    if let Some(list) = package.get(key).and_then(|v| v.as_array()) {
+                        for dep in list.iter().filter_map(|v| v.as_str()) {
+                            if !deps.iter().any(|d| d == dep) {
+                                deps.push(dep.to_string());
+                            }
+                        }
                     }
                 }
             }
         }
     }
 
-    Vec::new()
+    deps
 }
 
 /// Get packages that depend on a given package
@@ -146,6 +154,138 @@ pub fn get_package_dependents(project_dir: &Path, package_name: &str) -> Vec<Str
     dependents
 }
 
+/// Get conflicts declared by a package
+pub fn get_package_conflicts(project_dir: &Path, package_name: &str) -> Vec<ConflictSpec> {
+    let package_toml = project_dir
+        .join("packages")
+        .join(package_name)
+        .join("package.toml");
+
+    let Ok(content) = std::fs::read_to_string(&package_toml) else {
+        return Vec::new();
+    };
+
+    content
+        .parse::<toml::Value>()
+        .ok()
+        .and_then(|value| value.get("package")?.get("conflicts").cloned())
+        .and_then(|conflicts| conflicts.try_into().ok())
+        .unwrap_or_default()
+}
+
+/// Get the effective options of a package, sorted by name
+///
+/// Options defined by a local package resolve through the full chain
+/// (manifest > board > default). For other packages only the values set in
+/// the manifest and by the board are known.
+pub fn get_package_options(
+    project_dir: &Path,
+    manifest: &Manifest,
+    package_name: &str,
+) -> Vec<(String, ResolvedOption)> {
+    let definitions = std::fs::read_to_string(
+        project_dir
+            .join("packages")
+            .join(package_name)
+            .join("package.toml"),
+    )
+    .ok()
+    .and_then(|content| PackageDefinition::from_toml(&content).ok())
+    .map(|pkg_def| pkg_def.options)
+    .unwrap_or_default();
+    let package_values = manifest
+        .packages
+        .get(package_name)
+        .map(|pkg_ref| pkg_ref.options.clone())
+        .unwrap_or_default();
+    let board_values = manifest
+        .board
+        .name
+        .as_deref()
+        .and_then(|name| load_board_definition(project_dir, name).ok())
+        .and_then(|board| board.package_options.get(package_name).cloned())
+        .unwrap_or_default();
+
+    let mut resolved = resolve_all_options(
+        &definitions,
+        &HashMap::new(),
+        &package_values,
+        &board_values,
+        &HashMap::new(),
+    );
+    for (values, source) in [
+        (&package_values, OptionSource::Package),
+        (&board_values, OptionSource::Board),
+    ] {
+        for (name, value) in values {
+            resolved
+                .entry(name.clone())
+                .or_insert_with(|| ResolvedOption {
+                    value: value.clone(),
+                    source: source.clone(),
+                    location: None,
+                });
+        }
+    }
+
+    let mut options: Vec<_> = resolved.into_iter().collect();
+    options.sort_by(|a, b| a.0.cmp(&b.0));
+    options
+}
+
+/// Describe conflicts between a package and an existing selection
+///
+/// Conflicts declared on either side are reported.
+pub fn find_selection_conflicts(
+    project_dir: &Path,
+    package_name: &str,
+    selected: &HashSet<String>,
+) -> Vec<String> {
+    let mut messages = Vec::new();
+
+    for spec in get_package_conflicts(project_dir, package_name) {
+        if spec.name() != package_name && selected.contains(spec.name()) {
+            messages.push(package_conflict_error(package_name, &spec).to_string());
+        }
+    }
+
+    let mut others: Vec<&String> = selected.iter().collect();
+    others.sort();
+    for other in others {
+        if other == package_name {
+            continue;
+        }
+        for spec in get_package_conflicts(project_dir, other) {
+            if spec.name() == package_name {
+                messages.push(package_conflict_error(other, &spec).to_string());
+            }
+        }
+    }
+
+    messages
+}
+
+/// Filter boards by a case-insensitive query, preserving index order
+///
+/// Matches against the board name, description, architecture and keywords.
+/// An empty query returns every board.
+pub fn filter_boards<'a>(boards: &'a [BoardIndexEntry], query: &str) -> Vec<&'a BoardIndexEntry> {
+    let query = query.trim().to_lowercase();
+    boards
+        .iter()
+        .filter(|board| {
+            query.is_empty()
+                || board.name.to_lowercase().contains(&query)
+                || board.description.to_lowercase().contains(&query)
+                || board.arch.to_lowercase().contains(&query)
+                || board
+                    .keywords
+                    .iter()
+                    .any(|k| k.to_lowercase().contains(&query))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -169,4 +309,149 @@ mod tests {
         assert_eq!(state.selected_category, ConfigCategory::Board);
         assert!(!state.has_changes);
     }
+
+    #[test]
+    fn test_find_selection_conflicts_either_direction() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let pkg_dir = temp.path().join("packages").join("dropbear");
+        std::fs::create_dir_all(&pkg_dir).unwrap();
+        std::fs::write(
+            pkg_dir.join("package.toml"),
+            "[package]\nname = \"dropbear\"\nconflicts = [{ name = \"openssh\", reason = \"both provide sshd\" }]\n",
+        )
+        .unwrap();
+
+        let selected: HashSet<String> = ["openssh".to_string()].into_iter().collect();
+        let messages = find_selection_conflicts(temp.path(), "dropbear", &selected);
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("both provide sshd"));
+
+        let selected: HashSet<String> = ["dropbear".to_string()].into_iter().collect();
+        let messages = find_selection_conflicts(temp.path(), "openssh", &selected);
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("dropbear"));
+    }
+
+    #[test]
+    fn test_get_package_options_reports_sources() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let pkg_dir = temp.path().join("packages").join("busybox");
+        std::fs::create_dir_all(&pkg_dir).unwrap();
+        std::fs::write(
+            pkg_dir.join("package.toml"),
+            r#"[package]
+name = "busybox"
+version = "1.36.1"
+description = "Swiss army knife of embedded Linux"
+
+[source]
+url = "https://busybox.net/downloads/busybox-1.36.1.tar.bz2"
+sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+
+[options.static]
+type = "bool"
+default = false
+description = "Link statically"
+
+[options.shell]
+type = "choice"
+default = "ash"
+choices = ["ash", "hush"]
+description = "Default shell"
+
+[options.applets]
+type = "string"
+default = "all"
+description = "Applets to enable"
+"#,
+        )
+        .unwrap();
+        let board_dir = temp.path().join("boards").join("tiny");
+        std::fs::create_dir_all(&board_dir).unwrap();
+        std::fs::write(
+            board_dir.join("board.toml"),
+            r#"[board]
+name = "tiny"
+description = "A tiny board"
+target = "arm-linux-musleabihf"
+cpu = "cortex-m7"
+
+[defaults]
+image_format = "initramfs"
+rootfs_size = "8M"
+hostname = "tiny"
+
+[package_options.busybox]
+shell = "hush"
+static = true
+"#,
+        )
+        .unwrap();
+
+        let manifest = Manifest::from_toml(
+            r#"[project]
+name = "test"
+
+[board]
+name = "tiny"
+
+[packages.busybox]
+version = "1.36.1"
+
+[packages.busybox.options]
+static = false
+"#,
+        )
+        .unwrap();
+
+        let options = get_package_options(temp.path(), &manifest, "busybox");
+        let summary: Vec<(&str, String, OptionSource)> = options
+            .iter()
+            .map(|(name, option)| {
+                (
+                    name.as_str(),
+                    option.value.to_string(),
+                    option.source.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("applets", "\"all\"".to_string(), OptionSource::Default),
+                ("shell", "\"hush\"".to_string(), OptionSource::Board),
+                ("static", "false".to_string(), OptionSource::Package),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_boards_matches_name_arch_and_keywords() {
+        let board = |name: &str, arch: &str, keywords: &[&str]| BoardIndexEntry {
+            name: name.to_string(),
+            description: format!("{name} board"),
+            arch: arch.to_string(),
+            target: format!("{arch}-linux-musl"),
+            keywords: keywords.iter().map(|k| (*k).to_string()).collect(),
+            variants: Vec::new(),
+        };
+        let boards = vec![
+            board("luckfox-pico", "arm", &["rockchip"]),
+            board("rpi4", "aarch64", &["raspberry"]),
+            board("qemu-x86", "x86_64", &[]),
+        ];
+
+        assert_eq!(filter_boards(&boards, "").len(), 3);
+        let names = |q: &str| -> Vec<String> {
+            filter_boards(&boards, q)
+                .into_iter()
+                .map(|b| b.name.clone())
+                .collect()
+        };
+        assert_eq!(names("RPI"), vec!["rpi4"]);
+        assert_eq!(names("aarch64"), vec!["rpi4"]);
+        assert_eq!(names("rockchip"), vec!["luckfox-pico"]);
+        assert_eq!(names("board"), vec!["luckfox-pico", "rpi4", "qemu-x86"]);
+        assert!(names("riscv").is_empty());
+    }
 }
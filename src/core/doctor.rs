@@ -4,11 +4,68 @@
 //!
 //! **Validates: Requirements 14.5, 14.6**
 
+use serde::Serialize;
 use std::path::Path;
 
+use crate::core::board::BoardDefinition;
+use crate::core::build_env::gcc_prefix;
+use crate::core::flash::{find_in_path, load_board_definition};
+use crate::core::global_config::GlobalConfig;
+use crate::core::host_tools::{self, HostToolRequirement, HostToolStatus};
+use crate::core::lock::LockFile;
+use crate::core::manifest::Manifest;
+use crate::core::package::PackageDefinition;
+use crate::core::partition;
+use crate::core::qemu::qemu_binary;
+use crate::core::version::satisfies_requirement;
+use crate::infra::dirs::ZigrootDirs;
+use crate::infra::http::{self, NetworkFailure, NetworkSettings};
+use crate::infra::namespace::{namespaces_supported, NamespaceTool, INSTALL_HINT};
+
+/// Area of the system a check covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CheckCategory {
+    /// Compilers and core tools
+    Toolchain,
+    /// Host tools needed by builds, images, flashing and emulation
+    HostTools,
+    /// The project in the current directory
+    Project,
+    /// Network access to registries and downloads
+    Network,
+}
+
+impl std::fmt::Display for CheckCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Toolchain => write!(f, "toolchain"),
+            Self::HostTools => write!(f, "host-tools"),
+            Self::Project => write!(f, "project"),
+            Self::Network => write!(f, "network"),
+        }
+    }
+}
+
+/// Outcome of a check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    /// The check passed
+    Pass,
+    /// An optional check failed
+    Warn,
+    /// A required check failed
+    Fail,
+}
+
 /// Result of a single dependency check
 #[derive(Debug, Clone)]
 pub struct CheckResult {
+    /// Stable identifier of the check (e.g., "zig" or "flash-tool:fastboot")
+    pub id: String,
+    /// Area the check belongs to
+    pub category: CheckCategory,
     /// Name of the dependency being checked
     pub name: String,
     /// Whether the check passed
@@ -25,8 +82,13 @@ pub struct CheckResult {
 
 impl CheckResult {
     /// Create a passing check result
+    ///
+    /// The check is in the toolchain category with an id derived from
+    /// `name` until [`CheckResult::with_id`] sets them.
     pub fn pass(name: &str, version: Option<String>, required: bool) -> Self {
         Self {
+            id: check_id(name),
+            category: CheckCategory::Toolchain,
             name: name.to_string(),
             passed: true,
             version,
@@ -39,6 +101,8 @@ impl CheckResult {
     /// Create a failing check result
     pub fn fail(name: &str, error: &str, suggestion: Option<&str>, required: bool) -> Self {
         Self {
+            id: check_id(name),
+            category: CheckCategory::Toolchain,
             name: name.to_string(),
             passed: false,
             version: None,
@@ -47,15 +111,49 @@ impl CheckResult {
             required,
         }
     }
+
+    /// Set the id and category of the check
+    #[must_use]
+    pub fn with_id(mut self, category: CheckCategory, id: &str) -> Self {
+        self.category = category;
+        self.id = id.to_string();
+        self
+    }
+
+    /// Outcome of the check: failed optional checks are warnings
+    pub fn status(&self) -> CheckStatus {
+        match (self.passed, self.required) {
+            (true, _) => CheckStatus::Pass,
+            (false, true) => CheckStatus::Fail,
+            (false, false) => CheckStatus::Warn,
+        }
+    }
+
+    /// Value the check detected, such as a tool version
+    pub fn detected(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// How to fix a failed check
+    pub fn remediation(&self) -> Option<&str> {
+        self.suggestion.as_deref()
+    }
+}
+
+/// Derive a check id from its display name ("Zig compiler" -> "zig-compiler")
+fn check_id(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
 }
 
 /// Overall doctor report
 #[derive(Debug, Default)]
 pub struct DoctorReport {
-    /// Individual check results
+    /// Individual check results, system and project checks alike
     pub checks: Vec<CheckResult>,
-    /// Configuration issues found
-    pub config_issues: Vec<String>,
 }
 
 impl DoctorReport {
@@ -69,11 +167,6 @@ impl DoctorReport {
         self.checks.push(result);
     }
 
-    /// Add a configuration issue
-    pub fn add_config_issue(&mut self, issue: String) {
-        self.config_issues.push(issue);
-    }
-
     /// Check if all required checks passed
     pub fn all_required_passed(&self) -> bool {
         self.checks.iter().filter(|c| c.required).all(|c| c.passed)
@@ -81,7 +174,7 @@ impl DoctorReport {
 
     /// Check if all checks passed (including optional)
     pub fn all_passed(&self) -> bool {
-        self.checks.iter().all(|c| c.passed) && self.config_issues.is_empty()
+        self.checks.iter().all(|c| c.passed)
     }
 
     /// Count passed checks
@@ -101,6 +194,33 @@ impl DoctorReport {
             .filter(|c| c.required && !c.passed)
             .collect()
     }
+
+    /// Count checks with a status
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks.iter().filter(|c| c.status() == status).count()
+    }
+
+    /// Overall outcome: the worst status of any check
+    pub fn status(&self) -> CheckStatus {
+        if self.count(CheckStatus::Fail) > 0 {
+            CheckStatus::Fail
+        } else if self.count(CheckStatus::Warn) > 0 {
+            CheckStatus::Warn
+        } else {
+            CheckStatus::Pass
+        }
+    }
+
+    /// Whether `zigroot doctor` should exit with an error
+    ///
+    /// Failed checks always do; warnings only with `warnings_as_errors`.
+    pub fn is_failure(&self, warnings_as_errors: bool) -> bool {
+        match self.status() {
+            CheckStatus::Pass => false,
+            CheckStatus::Warn => warnings_as_errors,
+            CheckStatus::Fail => true,
+        }
+    }
 }
 
 /// Check if a command is available in PATH
@@ -134,7 +254,7 @@ fn extract_version(output: &str) -> Option<String> {
 
 /// Check Zig compiler availability
 pub fn check_zig() -> CheckResult {
-    match check_command_available("zig") {
+    let result = match check_command_available("zig") {
         Some(version) => CheckResult::pass("Zig compiler", Some(version), true),
         None => CheckResult::fail(
             "Zig compiler",
@@ -142,12 +262,13 @@ pub fn check_zig() -> CheckResult {
             Some("Install Zig from https://ziglang.org/download/ or use your package manager"),
             true,
         ),
-    }
+    };
+    result.with_id(CheckCategory::Toolchain, "zig")
 }
 
 /// Check Git availability
 pub fn check_git() -> CheckResult {
-    match check_command_available("git") {
+    let result = match check_command_available("git") {
         Some(version) => CheckResult::pass("Git", Some(version), true),
         None => CheckResult::fail(
             "Git",
@@ -155,12 +276,35 @@ pub fn check_git() -> CheckResult {
             Some("Install Git from https://git-scm.com/ or use your package manager"),
             true,
         ),
-    }
+    };
+    result.with_id(CheckCategory::Toolchain, "git")
+}
+
+/// Minimum UPX version known to pack and test all supported architectures
+pub const MIN_UPX_VERSION: &str = "3.96";
+
+/// Compare dotted version strings numerically
+pub fn version_at_least(version: &str, minimum: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> {
+        v.split(['.', '-'])
+            .map_while(|part| part.parse().ok())
+            .collect()
+    };
+    parse(version) >= parse(minimum)
 }
 
 /// Check UPX availability (optional, for compression)
 pub fn check_upx() -> CheckResult {
-    match check_command_available("upx") {
+    let result = match check_command_available("upx") {
+        Some(version) if !version_at_least(&version, MIN_UPX_VERSION) => CheckResult {
+            version: Some(version.clone()),
+            ..CheckResult::fail(
+                "UPX (compression)",
+                &format!("UPX {version} is older than the minimum {MIN_UPX_VERSION}"),
+                Some("Upgrade UPX from https://upx.github.io/ (optional)"),
+                false,
+            )
+        },
         Some(version) => CheckResult::pass("UPX (compression)", Some(version), false),
         None => CheckResult::fail(
             "UPX (compression)",
@@ -168,18 +312,35 @@ pub fn check_upx() -> CheckResult {
             Some("Install UPX for binary compression: https://upx.github.io/ (optional)"),
             false,
         ),
-    }
+    };
+    result.with_id(CheckCategory::HostTools, "upx")
+}
+
+/// Check mkimage availability (optional, for FIT images)
+pub fn check_mkimage() -> CheckResult {
+    let result = match check_command_available("mkimage") {
+        Some(version) => CheckResult::pass("mkimage (FIT images)", Some(version), false),
+        None => CheckResult::fail(
+            "mkimage (FIT images)",
+            "mkimage not found in PATH",
+            Some("Install u-boot-tools to build FIT images (optional)"),
+            false,
+        ),
+    };
+    result.with_id(CheckCategory::HostTools, "mkimage")
 }
 
 /// Check Docker/Podman availability (optional, for sandboxed builds)
 pub fn check_container_runtime() -> CheckResult {
     // Try Docker first
     if let Some(version) = check_command_available("docker") {
-        return CheckResult::pass("Container runtime (Docker)", Some(version), false);
+        return CheckResult::pass("Container runtime (Docker)", Some(version), false)
+            .with_id(CheckCategory::HostTools, "container-runtime");
     }
     // Try Podman as alternative
     if let Some(version) = check_command_available("podman") {
-        return CheckResult::pass("Container runtime (Podman)", Some(version), false);
+        return CheckResult::pass("Container runtime (Podman)", Some(version), false)
+            .with_id(CheckCategory::HostTools, "container-runtime");
     }
     CheckResult::fail(
         "Container runtime",
@@ -187,6 +348,261 @@ pub fn check_container_runtime() -> CheckResult {
         Some("Install Docker or Podman for sandboxed builds (optional)"),
         false,
     )
+    .with_id(CheckCategory::HostTools, "container-runtime")
+}
+
+/// Check bubblewrap availability for sandboxed builds
+///
+/// Without bubblewrap, sandboxed builds fall back to `unshare`. The check
+/// is required when the project or the global config enables
+/// `build.sandbox` and neither works.
+pub fn check_build_sandbox(project_dir: Option<&Path>) -> CheckResult {
+    build_sandbox_result(project_dir).with_id(CheckCategory::HostTools, "build-sandbox")
+}
+
+fn build_sandbox_result(project_dir: Option<&Path>) -> CheckResult {
+    let name = "Build sandbox (bubblewrap)";
+    let required = project_dir
+        .and_then(|dir| std::fs::read_to_string(dir.join("zigroot.toml")).ok())
+        .and_then(|content| Manifest::from_toml(&content).ok())
+        .and_then(|manifest| manifest.build.sandbox)
+        .or_else(|| {
+            GlobalConfig::load(&ZigrootDirs::new())
+                .ok()
+                .and_then(|config| config.build.sandbox)
+        })
+        == Some(true);
+
+    if !namespaces_supported() {
+        return CheckResult::fail(
+            name,
+            "Linux namespaces are not available on this platform",
+            Some("Sandboxed builds only run on Linux; use --no-sandbox or build in a Linux VM"),
+            required,
+        );
+    }
+    if NamespaceTool::Bubblewrap.is_available() {
+        return CheckResult::pass(name, check_command_available("bwrap"), required);
+    }
+    let hint = format!("{INSTALL_HINT} for sandboxed builds");
+    if NamespaceTool::Unshare.is_available() {
+        CheckResult::fail(
+            name,
+            "bwrap not found or not working; sandboxed builds fall back to unshare",
+            Some(&hint),
+            false,
+        )
+    } else {
+        CheckResult::fail(
+            name,
+            "Neither bwrap nor unshare can create user namespaces",
+            Some(&hint),
+            required,
+        )
+    }
+}
+
+/// Check for a GCC cross-toolchain for the project's board
+///
+/// Returns `None` outside a project or without a configured board. The check
+/// is required when a local package sets `toolchain = "gcc"` or Zig cannot
+/// provide the board's C library.
+pub fn check_gcc_toolchain(project_dir: &Path) -> Option<CheckResult> {
+    let content = std::fs::read_to_string(project_dir.join("zigroot.toml")).ok()?;
+    let manifest = Manifest::from_toml(&content).ok()?;
+    let board = load_board_definition(project_dir, manifest.board.name.as_deref()?).ok()?;
+    let target = &board.target(&manifest.board);
+
+    let gcc_packages: Vec<&String> = manifest
+        .packages
+        .keys()
+        .filter(|name| {
+            let path = project_dir.join("packages").join(name).join("package.toml");
+            std::fs::read_to_string(path)
+                .ok()
+                .and_then(|c| PackageDefinition::from_toml(&c).ok())
+                .is_some_and(|def| def.build.uses_gcc())
+        })
+        .collect();
+    let required = !gcc_packages.is_empty() || !board.libc(&manifest.board).zig_provides(target);
+
+    let name = format!("GCC cross-toolchain ({target})");
+    let compiler = format!("{}gcc", gcc_prefix(target));
+    let result = match check_command_available(&compiler) {
+        Some(version) => CheckResult::pass(&name, Some(version), required),
+        None => CheckResult::fail(
+            &name,
+            &format!("{compiler} not found in PATH"),
+            Some(&format!(
+                "Install a {target} cross-toolchain (e.g., from https://toolchains.bootlin.com) and add it to PATH{}",
+                if required {
+                    ""
+                } else {
+                    " (optional, needed for packages with toolchain = \"gcc\")"
+                }
+            )),
+            required,
+        ),
+    };
+    Some(result.with_id(CheckCategory::Toolchain, "gcc-toolchain"))
+}
+
+/// Check for the QEMU emulator of the project's board
+///
+/// Returns `None` unless the board has a `[qemu]` section. QEMU is only
+/// needed for `zigroot run`, so the check is optional.
+pub fn check_qemu(project_dir: &Path) -> Option<CheckResult> {
+    let board = project_board(project_dir)?;
+    let config = board.qemu.as_ref()?;
+    let target = &board.board.target;
+
+    let Some(binary) = qemu_binary(config, target) else {
+        return Some(
+            CheckResult::fail(
+                "QEMU",
+                &format!("No QEMU emulator is known for target '{target}'"),
+                Some("Set qemu.binary in the board definition"),
+                false,
+            )
+            .with_id(CheckCategory::HostTools, "qemu"),
+        );
+    };
+    let name = format!("QEMU ({binary})");
+    let result = match check_command_available(&binary) {
+        Some(version) => CheckResult::pass(&name, Some(version), false),
+        None => CheckResult::fail(
+            &name,
+            &format!("{binary} not found in PATH"),
+            Some(&format!(
+                "Install QEMU with {binary} to use 'zigroot run' (optional)"
+            )),
+            false,
+        ),
+    };
+    Some(result.with_id(CheckCategory::HostTools, "qemu"))
+}
+
+/// Check the host tools of the board's flash methods
+///
+/// Flash tools are only needed for `zigroot flash`, so the checks are
+/// optional.
+pub fn check_flash_tools(project_dir: &Path) -> Vec<CheckResult> {
+    let Some(board) = project_board(project_dir) else {
+        return Vec::new();
+    };
+    let mut seen = std::collections::HashSet::new();
+    let mut checks = Vec::new();
+    for profile in &board.flash {
+        for tool in profile.host_tools.iter().filter(|t| seen.insert(*t)) {
+            let name = format!("Flash tool {tool}");
+            let result = if find_in_path(tool).is_some() {
+                CheckResult::pass(&name, None, false)
+            } else {
+                CheckResult::fail(
+                    &name,
+                    &format!("{tool} not found in PATH"),
+                    Some(&format!(
+                        "Install {tool} to use 'zigroot flash {}' (optional)",
+                        profile.name
+                    )),
+                    false,
+                )
+            };
+            checks.push(result.with_id(CheckCategory::HostTools, &format!("flash-tool:{tool}")));
+        }
+    }
+    checks
+}
+
+/// Check the host tools that local packages list in `host_depends`
+///
+/// Missing or outdated tools are downloaded by `zigroot build`, so the
+/// checks are optional. Tools downloaded before pass.
+pub fn check_host_tools(project_dir: &Path) -> Vec<CheckResult> {
+    let Some(manifest) = std::fs::read_to_string(project_dir.join("zigroot.toml"))
+        .ok()
+        .and_then(|content| Manifest::from_toml(&content).ok())
+    else {
+        return Vec::new();
+    };
+
+    // Packages needing each requirement, in a stable order
+    let mut requirements: std::collections::BTreeMap<String, Vec<&String>> =
+        std::collections::BTreeMap::new();
+    let mut packages: Vec<&String> = manifest.packages.keys().collect();
+    packages.sort();
+    for package in packages {
+        let path = project_dir
+            .join("packages")
+            .join(package)
+            .join("package.toml");
+        let Some(definition) = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| PackageDefinition::from_toml(&content).ok())
+        else {
+            continue;
+        };
+        for spec in definition.package.host_depends {
+            requirements.entry(spec).or_default().push(package);
+        }
+    }
+
+    let store = crate::infra::dirs::ZigrootDirs::new().host_tools_dir();
+    requirements
+        .into_iter()
+        .map(|(spec, packages)| {
+            host_tool_result(&spec, &packages, &store)
+                .with_id(CheckCategory::HostTools, &format!("host-tool:{spec}"))
+        })
+        .collect()
+}
+
+/// Check one `host_depends` requirement of the given packages
+fn host_tool_result(spec: &str, packages: &[&String], store: &Path) -> CheckResult {
+    let packages = packages
+        .iter()
+        .map(|p| p.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let name = format!("Host tool {spec} ({packages})");
+    let requirement = match HostToolRequirement::parse(spec) {
+        Ok(requirement) => requirement,
+        Err(e) => {
+            return CheckResult::fail(
+                &name,
+                &e.to_string(),
+                Some("Fix host_depends in the package definition"),
+                true,
+            )
+        }
+    };
+    let problem = match host_tools::check_host(&requirement) {
+        HostToolStatus::Found { version } => return CheckResult::pass(&name, version, false),
+        HostToolStatus::Outdated { version } => format!(
+            "{} {} found, which does not satisfy {spec}",
+            requirement.name,
+            version.as_deref().unwrap_or("of unknown version")
+        ),
+        HostToolStatus::Missing => format!("{} not found in PATH", requirement.name),
+    };
+    if let Some(tool) = host_tools::stored_tool(&requirement, store) {
+        return CheckResult::pass(&name, Some(format!("{} (downloaded)", tool.version)), false);
+    }
+    CheckResult::fail(
+        &name,
+        &problem,
+        Some(&format!(
+            "'zigroot build' downloads it from the registry, or install {requirement}"
+        )),
+        false,
+    )
+}
+
+/// Load the board definition of the project in `project_dir`
+fn project_board(project_dir: &Path) -> Option<BoardDefinition> {
+    let content = std::fs::read_to_string(project_dir.join("zigroot.toml")).ok()?;
+    let manifest = Manifest::from_toml(&content).ok()?;
+    load_board_definition(project_dir, manifest.board.name.as_deref()?).ok()
 }
 
 /// Check if project configuration is valid
@@ -197,10 +613,8 @@ pub fn check_project_config(project_dir: &Path) -> Vec<String> {
     if manifest_path.exists() {
         match std::fs::read_to_string(&manifest_path) {
             Ok(content) => {
-                // Try to parse as TOML
-                if let Err(e) = content.parse::<toml::Table>() {
-                    issues.push(format!("Invalid manifest TOML: {e}"));
-                } else {
+                // Parse errors are reported by the manifest check
+                {
                     // Check for common issues
                     if let Ok(table) = content.parse::<toml::Table>() {
                         // Check project section
@@ -223,6 +637,365 @@ pub fn check_project_config(project_dir: &Path) -> Vec<String> {
     issues
 }
 
+/// Check that the project's manifest, board and lock file are usable
+///
+/// Returns no checks outside a project. A missing lock file is only a
+/// warning since `zigroot build` creates it.
+pub fn check_project(project_dir: &Path) -> Vec<CheckResult> {
+    let manifest_path = project_dir.join("zigroot.toml");
+    if !manifest_path.exists() {
+        return Vec::new();
+    }
+    let manifest = match std::fs::read_to_string(&manifest_path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| Manifest::from_toml(&content).map_err(|e| e.to_string()))
+    {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            return vec![CheckResult::fail(
+                "Manifest (zigroot.toml)",
+                &e,
+                Some("Fix zigroot.toml; 'zigroot check' shows all validation errors"),
+                true,
+            )
+            .with_id(CheckCategory::Project, "manifest")];
+        }
+    };
+
+    let mut checks = vec![CheckResult::pass(
+        "Manifest (zigroot.toml)",
+        Some(manifest.project.version.clone()),
+        true,
+    )
+    .with_id(CheckCategory::Project, "manifest")];
+
+    checks.push(
+        match manifest.board.name.as_deref() {
+            Some(name) => match load_board_definition(project_dir, name) {
+                Ok(board) => CheckResult::pass(
+                    &format!("Board {name}"),
+                    Some(board.target(&manifest.board)),
+                    true,
+                ),
+                Err(e) => CheckResult::fail(
+                    &format!("Board {name}"),
+                    &e.to_string(),
+                    Some("Run 'zigroot board list' and set an available board with 'zigroot board set <name>'"),
+                    true,
+                ),
+            },
+            None => CheckResult::fail(
+                "Board",
+                "No board configured",
+                Some("Set one with 'zigroot board set <name>'"),
+                false,
+            ),
+        }
+        .with_id(CheckCategory::Project, "board"),
+    );
+
+    let lock_path = project_dir.join("zigroot.lock");
+    let mut lock_file = None;
+    let lock = if lock_path.exists() {
+        match LockFile::load(&lock_path) {
+            Ok(lock) => {
+                lock_file = Some(lock);
+                CheckResult::pass("Lock file (zigroot.lock)", None, true)
+            }
+            Err(e) => CheckResult::fail(
+                "Lock file (zigroot.lock)",
+                &e.to_string(),
+                Some("Delete zigroot.lock and run 'zigroot update' to recreate it"),
+                true,
+            ),
+        }
+    } else {
+        CheckResult::fail(
+            "Lock file (zigroot.lock)",
+            "zigroot.lock not found, so package versions are not pinned",
+            Some("Run 'zigroot build' or 'zigroot update' to create it"),
+            false,
+        )
+    };
+    checks.push(lock.with_id(CheckCategory::Project, "lock-file"));
+    if let Some(lock) = &lock_file {
+        checks.push(check_lock_consistency(&manifest, lock));
+    }
+    if let Some(check) = check_local_packages(project_dir, &manifest, lock_file.as_ref()) {
+        checks.push(check);
+    }
+
+    checks.extend(
+        image_tools(project_dir, &manifest)
+            .into_iter()
+            .map(|(tool, purpose)| {
+                let name = format!("Image tool {tool} ({purpose})");
+                if find_in_path(tool).is_some() {
+                    CheckResult::pass(&name, None, true)
+                } else {
+                    CheckResult::fail(
+                        &name,
+                        &format!("{tool} not found in PATH"),
+                        Some(&format!(
+                            "Install {tool}; 'zigroot build' needs it for the {purpose}"
+                        )),
+                        true,
+                    )
+                }
+                .with_id(CheckCategory::HostTools, &format!("image-tool:{tool}"))
+            }),
+    );
+
+    checks
+}
+
+/// Check that the lock file pins what the manifest asks for
+///
+/// Drift is a warning: `zigroot build` and `zigroot update` rewrite the lock
+/// file, but `--locked` builds fail until then.
+fn check_lock_consistency(manifest: &Manifest, lock: &LockFile) -> CheckResult {
+    let name = "Lock file matches manifest";
+    let problems = lock_problems(manifest, lock);
+    if problems.is_empty() {
+        return CheckResult::pass(name, None, false)
+            .with_id(CheckCategory::Project, "lock-consistency");
+    }
+    CheckResult::fail(
+        name,
+        &problems.join("; "),
+        Some("Run 'zigroot update' to refresh zigroot.lock"),
+        false,
+    )
+    .with_id(CheckCategory::Project, "lock-consistency")
+}
+
+/// Differences between the manifest and the lock file, in a stable order
+fn lock_problems(manifest: &Manifest, lock: &LockFile) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut packages: Vec<(&String, &crate::core::manifest::PackageRef)> =
+        manifest.packages.iter().collect();
+    packages.sort_by_key(|(name, _)| *name);
+    for (package, package_ref) in packages {
+        match lock.get_package(package) {
+            None => problems.push(format!("{package} is not locked")),
+            Some(locked) => {
+                if let Some(requirement) = &package_ref.version {
+                    if !satisfies_requirement(&locked.version, requirement) {
+                        problems.push(format!(
+                            "{package} is locked at {}, which does not satisfy {requirement}",
+                            locked.version
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    // Dependencies of locked packages are locked without a manifest entry
+    let dependencies: std::collections::HashSet<&str> = lock
+        .packages
+        .iter()
+        .flat_map(|p| &p.depends)
+        .map(|dep| dep.split('@').next().unwrap_or(dep))
+        .collect();
+    for locked in &lock.packages {
+        if !manifest.packages.contains_key(&locked.name)
+            && !dependencies.contains(locked.name.as_str())
+        {
+            problems.push(format!("{} is locked but not in zigroot.toml", locked.name));
+        }
+    }
+    for external in &lock.externals {
+        if !manifest.external.contains_key(&external.name) {
+            problems.push(format!(
+                "external {} is locked but not in zigroot.toml",
+                external.name
+            ));
+        }
+    }
+    problems
+}
+
+/// Check that the directories of the project's local packages exist
+///
+/// A package is local when the lock file records a `path:` source for it or
+/// `packages/<name>` exists. Returns `None` when the project has none.
+fn check_local_packages(
+    project_dir: &Path,
+    manifest: &Manifest,
+    lock: Option<&LockFile>,
+) -> Option<CheckResult> {
+    let mut packages: Vec<&String> = manifest.packages.keys().collect();
+    packages.sort();
+    let mut found = false;
+    let mut problems = Vec::new();
+    for package in packages {
+        let locked_path = lock
+            .and_then(|lock| lock.get_package(package))
+            .and_then(|locked| locked.source.as_deref())
+            .and_then(|source| source.strip_prefix("path:"));
+        let dir = match locked_path {
+            Some(path) => project_dir.join(path),
+            None => project_dir.join("packages").join(package),
+        };
+        if locked_path.is_none() && !dir.exists() {
+            continue;
+        }
+        found = true;
+        if !dir.is_dir() {
+            problems.push(format!("{package}: {} is missing", dir.display()));
+        } else if !dir.join("package.toml").is_file() {
+            problems.push(format!("{package}: {} has no package.toml", dir.display()));
+        }
+    }
+    if !found {
+        return None;
+    }
+    let name = "Local packages";
+    let result = if problems.is_empty() {
+        CheckResult::pass(name, None, true)
+    } else {
+        CheckResult::fail(
+            name,
+            &problems.join("; "),
+            Some("Restore the package directories or remove the packages with 'zigroot remove'"),
+            true,
+        )
+    };
+    Some(result.with_id(CheckCategory::Project, "local-packages"))
+}
+
+/// External tools that building the project's images runs, with their purpose
+fn image_tools(project_dir: &Path, manifest: &Manifest) -> Vec<(&'static str, String)> {
+    let mut tools = Vec::new();
+    if manifest.build.image_format == "initramfs" {
+        let compressor = match manifest.build.initramfs_compression.as_str() {
+            "gzip" => Some("gzip"),
+            "xz" => Some("xz"),
+            "zstd" => Some("zstd"),
+            "lz4" => Some("lz4"),
+            _ => None,
+        };
+        if let Some(compressor) = compressor {
+            tools.push((compressor, "initramfs compression".to_string()));
+        }
+    }
+
+    if let Some((_, partitions)) = partition::project_disk_config(project_dir, manifest) {
+        // Only directory contents are formatted; images are copied as-is
+        for spec in partitions
+            .iter()
+            .filter(|p| p.content.as_deref().is_some_and(|c| c != "rootfs"))
+        {
+            let purpose = format!("partition {}", spec.name);
+            match spec.partition_type.as_str() {
+                "ext4" => {
+                    tools.push(("mkfs.ext4", purpose.clone()));
+                    if manifest.build.reproducible {
+                        tools.push(("debugfs", purpose));
+                    }
+                }
+                "fat32" => {
+                    tools.push(("mkfs.vfat", purpose.clone()));
+                    tools.push(("mcopy", purpose));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    tools.retain(|(tool, _)| seen.insert(*tool));
+    tools
+}
+
+/// Environment variables holding a proxy URL, in the order they apply
+const PROXY_VARS: &[&str] = &[
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+
+/// Check the proxy configuration used for registry and source downloads
+///
+/// Proxy URLs that cannot be parsed are ignored by the HTTP client, so
+/// downloads silently bypass the proxy. The check is optional.
+pub fn check_proxy() -> CheckResult {
+    let name = "Network proxy";
+    let configured = PROXY_VARS.iter().find_map(|var| {
+        std::env::var(var)
+            .ok()
+            .filter(|value| !value.is_empty())
+            .map(|value| (*var, value))
+    });
+    let result = match configured {
+        None => CheckResult::pass(name, Some("direct".to_string()), false),
+        Some((var, value)) => match reqwest::Url::parse(&value) {
+            Ok(url) if url.host_str().is_some() => {
+                let host = url.host_str().unwrap_or_default();
+                let detected = match url.port_or_known_default() {
+                    Some(port) => format!("{host}:{port} (from {var})"),
+                    None => format!("{host} (from {var})"),
+                };
+                CheckResult::pass(name, Some(detected), false)
+            }
+            _ => CheckResult::fail(
+                name,
+                &format!("{var} is not a valid proxy URL"),
+                Some(&format!(
+                    "Set {var} to a URL such as http://proxy.example.com:3128"
+                )),
+                false,
+            ),
+        },
+    };
+    result.with_id(CheckCategory::Network, "proxy")
+}
+
+/// Check that the package registry is reachable
+///
+/// The request uses the proxy, CA bundle and timeout of `settings`, and a
+/// failure names its class (DNS, TLS, proxy authentication, timeout). The
+/// check is optional since cached indexes keep most commands working.
+pub async fn check_registry(settings: &NetworkSettings, registry_url: &str) -> CheckResult {
+    registry_result(settings, registry_url)
+        .await
+        .with_id(CheckCategory::Network, "registry")
+}
+
+async fn registry_result(settings: &NetworkSettings, registry_url: &str) -> CheckResult {
+    let name = "Package registry";
+    let client = match http::client_with(settings) {
+        Ok(client) => client,
+        Err(e) => {
+            return CheckResult::fail(
+                name,
+                &e.to_string(),
+                Some("Fix network.proxy or network.ca_bundle with 'zigroot config set'"),
+                false,
+            )
+        }
+    };
+    let url = format!("{}/index.json", registry_url.trim_end_matches('/'));
+    let failure = match client.get(&url).send().await {
+        Ok(response) if response.status().is_success() => {
+            let host = response.url().host_str().map(String::from);
+            return CheckResult::pass(name, host, false);
+        }
+        Ok(response) => NetworkFailure::from_status(response.status().as_u16()),
+        Err(e) => NetworkFailure::classify(&e),
+    };
+    CheckResult::fail(
+        name,
+        &format!("{failure} for {url}"),
+        Some(failure.suggestion()),
+        false,
+    )
+}
+
 /// Run all doctor checks
 pub fn run_doctor(project_dir: Option<&Path>) -> DoctorReport {
     let mut report = DoctorReport::new();
@@ -233,13 +1006,40 @@ pub fn run_doctor(project_dir: Option<&Path>) -> DoctorReport {
 
     // Check optional dependencies
     report.add_check(check_upx());
+    report.add_check(check_mkimage());
     report.add_check(check_container_runtime());
+    report.add_check(check_build_sandbox(project_dir));
+    report.add_check(check_proxy());
 
     // Check project configuration if in a project directory
     if let Some(dir) = project_dir {
-        let config_issues = check_project_config(dir);
-        for issue in config_issues {
-            report.add_config_issue(issue);
+        for check in check_project(dir) {
+            report.add_check(check);
+        }
+        if let Some(check) = check_gcc_toolchain(dir) {
+            report.add_check(check);
+        }
+        if let Some(check) = check_qemu(dir) {
+            report.add_check(check);
+        }
+        for check in check_flash_tools(dir) {
+            report.add_check(check);
+        }
+        for check in check_host_tools(dir) {
+            report.add_check(check);
+        }
+
+        let issues = check_project_config(dir);
+        if !issues.is_empty() {
+            report.add_check(
+                CheckResult::fail(
+                    "Project configuration",
+                    &issues.join("; "),
+                    Some("Fix the [project] section of zigroot.toml"),
+                    false,
+                )
+                .with_id(CheckCategory::Project, "config"),
+            );
         }
     }
 
@@ -282,6 +1082,128 @@ mod tests {
         assert!(!report.all_required_passed());
     }
 
+    #[test]
+    fn test_check_status() {
+        assert_eq!(
+            CheckResult::pass("a", None, true).status(),
+            CheckStatus::Pass
+        );
+        assert_eq!(
+            CheckResult::fail("b", "err", None, true).status(),
+            CheckStatus::Fail
+        );
+        assert_eq!(
+            CheckResult::fail("c", "err", None, false).status(),
+            CheckStatus::Warn
+        );
+    }
+
+    #[test]
+    fn test_check_id_defaults_to_name() {
+        let result = CheckResult::pass("UPX (compression)", None, false);
+        assert_eq!(result.id, "upx-compression");
+        assert_eq!(result.category, CheckCategory::Toolchain);
+
+        let result = result.with_id(CheckCategory::HostTools, "upx");
+        assert_eq!(result.id, "upx");
+        assert_eq!(result.category, CheckCategory::HostTools);
+    }
+
+    #[test]
+    fn test_report_exit_matrix() {
+        let report = |checks: Vec<CheckResult>| DoctorReport { checks };
+
+        let passed = report(vec![CheckResult::pass("a", None, true)]);
+        assert_eq!(passed.status(), CheckStatus::Pass);
+        assert!(!passed.is_failure(false));
+        assert!(!passed.is_failure(true));
+
+        let warned = report(vec![
+            CheckResult::pass("a", None, true),
+            CheckResult::fail("b", "err", None, false),
+        ]);
+        assert_eq!(warned.status(), CheckStatus::Warn);
+        assert!(!warned.is_failure(false));
+        assert!(warned.is_failure(true));
+
+        let failed = report(vec![
+            CheckResult::fail("a", "err", None, true),
+            CheckResult::fail("b", "err", None, false),
+        ]);
+        assert_eq!(failed.status(), CheckStatus::Fail);
+        assert!(failed.is_failure(false));
+        assert!(failed.is_failure(true));
+    }
+
+    #[test]
+    fn test_image_tools_for_format_and_partitions() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut manifest = Manifest::from_toml(
+            r#"
+[project]
+name = "images"
+
+[build]
+image_format = "initramfs"
+initramfs_compression = "xz"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            image_tools(temp.path(), &manifest),
+            vec![("xz", "initramfs compression".to_string())]
+        );
+
+        manifest.build.image_format = "ext4".to_string();
+        assert!(image_tools(temp.path(), &manifest).is_empty());
+    }
+
+    #[test]
+    fn test_lock_problems() {
+        use crate::core::lock::LockedPackageBuilder;
+
+        let manifest = Manifest::from_toml(
+            r#"
+[project]
+name = "locked"
+
+[packages]
+busybox = { version = "^1.36.0" }
+dropbear = { version = "2024.85" }
+"#,
+        )
+        .unwrap();
+        let mut lock = LockFile::default();
+        lock.add_package(
+            LockedPackageBuilder::new("busybox", "1.36.1", "sha256:abc")
+                .depends("zlib@1.3.1")
+                .build(),
+        );
+        lock.add_package(LockedPackageBuilder::new("zlib", "1.3.1", "sha256:def").build());
+        assert_eq!(
+            lock_problems(&manifest, &lock),
+            vec!["dropbear is not locked"]
+        );
+
+        lock.add_package(LockedPackageBuilder::new("dropbear", "2022.83", "sha256:0").build());
+        lock.add_package(LockedPackageBuilder::new("nginx", "1.25.0", "sha256:1").build());
+        assert_eq!(
+            lock_problems(&manifest, &lock),
+            vec![
+                "dropbear is locked at 2022.83, which does not satisfy 2024.85",
+                "nginx is locked but not in zigroot.toml",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_version_at_least() {
+        assert!(version_at_least("4.2.2", MIN_UPX_VERSION));
+        assert!(version_at_least("3.96", MIN_UPX_VERSION));
+        assert!(!version_at_least("3.95", MIN_UPX_VERSION));
+        assert!(!version_at_least("3.9", MIN_UPX_VERSION));
+    }
+
     #[test]
     fn test_extract_version() {
         assert_eq!(extract_version("zig 0.11.0"), Some("0.11.0".to_string()));
@@ -5,12 +5,19 @@
 //!
 //! **Validates: Requirements 8.1, 8.2, 8.9-8.13**
 
+use crate::core::lock::{LockFile, LockedExternal};
 use crate::core::manifest::{ExternalArtifact, Manifest};
+use crate::core::shared_storage::SharedStorage;
+use crate::error::DownloadError;
+use crate::infra::download::{source_urls, Checksum, ChecksumAlgorithm, DownloadManager};
+use crate::infra::filesystem::write_file_atomic;
 use anyhow::{Context, Result};
-use std::path::Path;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 
 /// Status of an external artifact
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ArtifactStatus {
     /// Artifact is present locally
     Local,
@@ -30,8 +37,33 @@ impl std::fmt::Display for ArtifactStatus {
     }
 }
 
+/// Checksum verification of an artifact's local file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumStatus {
+    /// The file matches the configured checksum
+    Verified,
+    /// The file does not match the configured checksum
+    Mismatch,
+    /// No valid checksum is configured
+    Unchecked,
+    /// There is no file to check yet
+    Pending,
+}
+
+impl std::fmt::Display for ChecksumStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Verified => write!(f, "verified"),
+            Self::Mismatch => write!(f, "mismatch"),
+            Self::Unchecked => write!(f, "no checksum"),
+            Self::Pending => write!(f, "not fetched"),
+        }
+    }
+}
+
 /// Information about an external artifact for display
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ArtifactInfo {
     /// Artifact name
     pub name: String,
@@ -41,12 +73,25 @@ pub struct ArtifactInfo {
     pub url: Option<String>,
     /// Local path
     pub path: Option<String>,
-    /// SHA256 checksum
-    pub sha256: Option<String>,
+    /// Declared checksum as `<algorithm>:<digest>`
+    pub digest: Option<String>,
     /// Partition table format (if applicable)
     pub format: Option<String>,
     /// Current status
     pub status: ArtifactStatus,
+    /// Local file, present or not
+    pub file: PathBuf,
+    /// Size of the local file, if present
+    pub size_bytes: Option<u64>,
+    /// Whether the local file matches the checksum
+    pub checksum: ChecksumStatus,
+}
+
+impl ArtifactInfo {
+    /// Whether `zigroot fetch` still has to download or replace the file
+    pub fn needs_fetch(&self) -> bool {
+        self.status == ArtifactStatus::Missing || self.checksum == ChecksumStatus::Mismatch
+    }
 }
 
 /// List all external artifacts from the manifest
@@ -55,26 +100,40 @@ pub struct ArtifactInfo {
 ///
 /// **Validates: Requirement 8.9**
 pub fn list_artifacts(project_dir: &Path) -> Result<Vec<ArtifactInfo>> {
-    let manifest_path = project_dir.join("zigroot.toml");
-    let manifest_content = std::fs::read_to_string(&manifest_path)
-        .with_context(|| format!("Failed to read manifest at {}", manifest_path.display()))?;
-
-    let manifest =
-        Manifest::from_toml(&manifest_content).with_context(|| "Failed to parse manifest")?;
+    let manifest = load_manifest(project_dir)?;
 
     let mut artifacts = Vec::new();
 
     for (name, artifact) in &manifest.external {
-        let status = determine_artifact_status(project_dir, artifact);
+        let file = artifact_file(project_dir, name, artifact);
+        let size_bytes = std::fs::metadata(&file)
+            .ok()
+            .filter(std::fs::Metadata::is_file)
+            .map(|m| m.len());
+        let declared = artifact.checksum().ok().flatten();
+        let checksum = match (&declared, size_bytes) {
+            (_, None) => ChecksumStatus::Pending,
+            (None, Some(_)) => ChecksumStatus::Unchecked,
+            (Some(expected), Some(_)) => {
+                if expected.matches_file(&file).unwrap_or(false) {
+                    ChecksumStatus::Verified
+                } else {
+                    ChecksumStatus::Mismatch
+                }
+            }
+        };
 
         artifacts.push(ArtifactInfo {
             name: name.clone(),
             artifact_type: artifact.artifact_type.clone(),
             url: artifact.url.clone(),
             path: artifact.path.clone(),
-            sha256: artifact.sha256.clone(),
+            digest: declared.as_ref().map(Checksum::to_string),
             format: artifact.format.clone(),
-            status,
+            status: determine_artifact_status(artifact, size_bytes.is_some()),
+            file,
+            size_bytes,
+            checksum,
         });
     }
 
@@ -84,28 +143,40 @@ pub fn list_artifacts(project_dir: &Path) -> Result<Vec<ArtifactInfo>> {
     Ok(artifacts)
 }
 
-/// Determine the status of an artifact
-fn determine_artifact_status(project_dir: &Path, artifact: &ExternalArtifact) -> ArtifactStatus {
-    // If there's a local path, check if the file exists
-    if let Some(ref path) = artifact.path {
-        let full_path = project_dir.join(path);
-        if full_path.exists() {
-            // If it has a URL, it was downloaded; otherwise it's local
-            if artifact.url.is_some() {
-                return ArtifactStatus::Downloaded;
-            }
-            return ArtifactStatus::Local;
-        }
+/// Determine the status of an artifact from whether its local file exists
+///
+/// Files of URL artifacts were downloaded by `zigroot fetch`, either to
+/// their `path` or to the download location under `external/`.
+fn determine_artifact_status(artifact: &ExternalArtifact, present: bool) -> ArtifactStatus {
+    match (present, &artifact.url) {
+        (false, _) => ArtifactStatus::Missing,
+        (true, Some(_)) => ArtifactStatus::Downloaded,
+        (true, None) => ArtifactStatus::Local,
     }
+}
 
-    // If there's only a URL and no path, check the default download location
-    if artifact.url.is_some() && artifact.path.is_none() {
-        // Default download location would be in external/ directory
-        // For now, assume it's missing if no path is specified
-        return ArtifactStatus::Missing;
+/// Local file of an external artifact
+///
+/// This is the artifact's `path`, or its download location under `external/`
+/// for URL-only artifacts.
+pub fn artifact_file(project_dir: &Path, name: &str, artifact: &ExternalArtifact) -> PathBuf {
+    if let Some(path) = &artifact.path {
+        return project_dir.join(path);
     }
+    let filename = artifact
+        .url
+        .as_deref()
+        .and_then(|url| url.rsplit('/').next())
+        .unwrap_or(name);
+    project_dir.join("external").join(filename)
+}
 
-    ArtifactStatus::Missing
+/// Resolve a reference to an external artifact name or a project-relative path
+pub fn resolve_reference(project_dir: &Path, manifest: &Manifest, reference: &str) -> PathBuf {
+    manifest.external.get(reference).map_or_else(
+        || project_dir.join(reference),
+        |artifact| artifact_file(project_dir, reference, artifact),
+    )
 }
 
 /// Valid artifact types
@@ -118,8 +189,91 @@ pub const VALID_ARTIFACT_TYPES: &[&str] = &[
     "other",
 ];
 
+/// Valid partition table formats
+pub const VALID_PARTITION_TABLE_FORMATS: &[&str] = &["gpt", "mbr", "rockchip"];
+
+/// Check an external artifact definition
+///
+/// Returns all problems found: an unknown type, not exactly one of url and
+/// path, a missing, malformed or ambiguous checksum for URLs, a missing local file, or a
+/// `format` on anything but a partition table or not a known format.
+pub fn validate_artifact(
+    project_dir: &Path,
+    name: &str,
+    artifact: &ExternalArtifact,
+) -> Vec<String> {
+    check_definition(Some(project_dir), name, artifact)
+}
+
+/// Check an artifact definition, and that its local file exists if a
+/// project directory is given
+fn check_definition(
+    project_dir: Option<&Path>,
+    name: &str,
+    artifact: &ExternalArtifact,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    if !VALID_ARTIFACT_TYPES.contains(&artifact.artifact_type.as_str()) {
+        errors.push(format!(
+            "External artifact '{name}' has invalid type '{}': must be one of {}",
+            artifact.artifact_type,
+            VALID_ARTIFACT_TYPES.join(", ")
+        ));
+    }
+
+    match (&artifact.url, &artifact.path) {
+        (Some(_), Some(_)) => errors.push(format!(
+            "External artifact '{name}' sets both url and path; use exactly one"
+        )),
+        (None, None) => errors.push(format!(
+            "External artifact '{name}' needs either url or path"
+        )),
+        (Some(_), None) => match artifact.checksum() {
+            Ok(None) => errors.push(format!(
+                "External artifact '{name}' has a url but no sha256, sha512 or blake3 checksum"
+            )),
+            Err(DownloadError::MalformedChecksum {
+                algorithm,
+                digest,
+                expected,
+            }) => errors.push(format!(
+                "External artifact '{name}' has malformed {algorithm} '{digest}': expected {expected} hex characters"
+            )),
+            Err(e) => errors.push(format!("External artifact '{name}': {e}")),
+            Ok(Some(_)) => {}
+        },
+        (None, Some(path)) => {
+            if project_dir.is_some_and(|dir| !dir.join(path).exists()) {
+                errors.push(format!(
+                    "External artifact '{name}' path '{path}' does not exist"
+                ));
+            }
+        }
+    }
+
+    match &artifact.format {
+        Some(_) if artifact.artifact_type != "partition_table" => errors.push(format!(
+            "External artifact '{name}' sets format, which is only allowed for type partition_table"
+        )),
+        Some(format) if !VALID_PARTITION_TABLE_FORMATS.contains(&format.as_str()) => {
+            errors.push(format!(
+                "External artifact '{name}' has invalid format '{format}': must be one of {}",
+                VALID_PARTITION_TABLE_FORMATS.join(", ")
+            ));
+        }
+        _ => {}
+    }
+
+    errors
+}
+
 /// Add an external artifact to the manifest
 ///
+/// The definition is checked before the manifest is touched, so a typo in
+/// the type or format never leaves a useless entry behind. An existing lock
+/// file pins a URL artifact to its checksum.
+///
 /// **Validates: Requirements 8.10, 8.11**
 pub fn add_artifact(
     project_dir: &Path,
@@ -127,49 +281,341 @@ pub fn add_artifact(
     artifact_type: &str,
     url: Option<&str>,
     path: Option<&str>,
+    checksum: Option<(ChecksumAlgorithm, &str)>,
+    format: Option<&str>,
 ) -> Result<()> {
-    // Validate artifact type
-    if !VALID_ARTIFACT_TYPES.contains(&artifact_type) {
+    let mut artifact = ExternalArtifact {
+        artifact_type: artifact_type.to_string(),
+        url: url.map(String::from),
+        mirrors: Vec::new(),
+        path: path.map(String::from),
+        sha256: None,
+        sha512: None,
+        blake3: None,
+        format: format.map(String::from),
+    };
+    if let Some(checksum) = checksum {
+        set_checksum(&mut artifact, checksum);
+    }
+    // The local file may be provided after adding it
+    let errors = check_definition(None, name, &artifact);
+    if !errors.is_empty() {
         anyhow::bail!(
-            "Invalid artifact type '{}'. Valid types are: {}",
-            artifact_type,
-            VALID_ARTIFACT_TYPES.join(", ")
+            "Cannot add external artifact '{name}':\n  {}",
+            errors.join("\n  ")
         );
     }
 
-    // At least one of url or path must be specified
-    if url.is_none() && path.is_none() {
-        anyhow::bail!("Either --url or --path must be specified for external artifact");
+    let mut manifest = load_manifest(project_dir)?;
+    manifest.external.insert(name.to_string(), artifact.clone());
+    save_manifest(project_dir, &manifest)?;
+    sync_lock(project_dir, name, Some(&artifact))
+}
+
+/// Change the source or checksum of an external artifact
+///
+/// A new URL replaces the path and the mirrors, a new path replaces the
+/// URL, and a new checksum replaces the declared one. The result is checked
+/// like [`add_artifact`] before the manifest is written, and the lock file
+/// entry follows it.
+pub fn update_artifact(
+    project_dir: &Path,
+    name: &str,
+    url: Option<&str>,
+    path: Option<&str>,
+    checksum: Option<(ChecksumAlgorithm, &str)>,
+) -> Result<ExternalArtifact> {
+    if url.is_none() && path.is_none() && checksum.is_none() {
+        anyhow::bail!(
+            "Nothing to update for external artifact '{name}': pass --url, --path or a checksum"
+        );
     }
+    let mut manifest = load_manifest(project_dir)?;
+    let Some(current) = manifest.external.get(name) else {
+        anyhow::bail!("External artifact '{name}' not found in zigroot.toml");
+    };
 
-    let manifest_path = project_dir.join("zigroot.toml");
-    let manifest_content = std::fs::read_to_string(&manifest_path)
-        .with_context(|| format!("Failed to read manifest at {}", manifest_path.display()))?;
+    let mut artifact = current.clone();
+    if url.is_some() || path.is_some() {
+        artifact.url = url.map(String::from);
+        artifact.path = path.map(String::from);
+        if url.is_some() {
+            artifact.mirrors.clear();
+        }
+    }
+    if let Some(checksum) = checksum {
+        set_checksum(&mut artifact, checksum);
+    }
+    let errors = check_definition(None, name, &artifact);
+    if !errors.is_empty() {
+        anyhow::bail!(
+            "Cannot update external artifact '{name}':\n  {}",
+            errors.join("\n  ")
+        );
+    }
 
-    let mut manifest =
-        Manifest::from_toml(&manifest_content).with_context(|| "Failed to parse manifest")?;
+    manifest.external.insert(name.to_string(), artifact.clone());
+    save_manifest(project_dir, &manifest)?;
+    sync_lock(project_dir, name, Some(&artifact))?;
+    Ok(artifact)
+}
 
-    // Create the new artifact
-    let artifact = ExternalArtifact {
-        artifact_type: artifact_type.to_string(),
-        url: url.map(String::from),
-        path: path.map(String::from),
-        sha256: None, // User should add this manually for URL sources
-        format: None,
-    };
+/// Remove an external artifact from the manifest and the lock file
+///
+/// Fails if the artifact is not declared, or is still used by the
+/// partition layout or the FIT image.
+pub fn remove_artifact(project_dir: &Path, name: &str) -> Result<()> {
+    let mut manifest = load_manifest(project_dir)?;
+    if !manifest.external.contains_key(name) {
+        anyhow::bail!("External artifact '{name}' not found in zigroot.toml");
+    }
+    let users = references(&manifest, name);
+    if !users.is_empty() {
+        anyhow::bail!(
+            "Cannot remove external artifact '{name}': it is used by {}",
+            users.join(", ")
+        );
+    }
+
+    manifest.external.remove(name);
+    save_manifest(project_dir, &manifest)?;
+    sync_lock(project_dir, name, None)
+}
+
+/// Where the manifest refers to an external artifact by name
+fn references(manifest: &Manifest, name: &str) -> Vec<String> {
+    let mut users: Vec<String> = manifest
+        .partitions
+        .iter()
+        .filter(|partition| partition.content.as_deref() == Some(name))
+        .map(|partition| format!("partition '{}'", partition.name))
+        .collect();
+    if manifest
+        .disk_image
+        .as_ref()
+        .and_then(|disk| disk.partition_table.as_deref())
+        == Some(name)
+    {
+        users.push("[disk_image] partition_table".to_string());
+    }
+    if let Some(fit) = &manifest.fit {
+        if fit.kernel == name
+            || fit.ramdisk.as_deref() == Some(name)
+            || fit.configs.iter().any(|c| c.dtb.as_deref() == Some(name))
+        {
+            users.push("[fit]".to_string());
+        }
+    }
+    users
+}
+
+/// Replace the declared checksum of an artifact
+fn set_checksum(artifact: &mut ExternalArtifact, (algorithm, digest): (ChecksumAlgorithm, &str)) {
+    let digest = Some(digest.to_lowercase());
+    artifact.sha256 = None;
+    artifact.sha512 = None;
+    artifact.blake3 = None;
+    match algorithm {
+        ChecksumAlgorithm::Sha256 => artifact.sha256 = digest,
+        ChecksumAlgorithm::Sha512 => artifact.sha512 = digest,
+        ChecksumAlgorithm::Blake3 => artifact.blake3 = digest,
+    }
+}
+
+/// Lock file entry pinning a URL artifact to its declared checksum
+///
+/// `None` for local artifacts and invalid checksums.
+pub fn locked_entry(name: &str, artifact: &ExternalArtifact) -> Option<LockedExternal> {
+    let url = artifact.url.clone()?;
+    let checksum = artifact.checksum().ok().flatten()?;
+    Some(LockedExternal {
+        name: name.to_string(),
+        artifact_type: artifact.artifact_type.clone(),
+        checksum: checksum.to_string(),
+        url,
+    })
+}
+
+/// Make the lock file entry of an artifact follow its definition
+///
+/// Only an existing lock file is changed; the build creates it otherwise.
+fn sync_lock(project_dir: &Path, name: &str, artifact: Option<&ExternalArtifact>) -> Result<()> {
+    let lock_path = project_dir.join("zigroot.lock");
+    if !lock_path.exists() {
+        return Ok(());
+    }
+    let mut lock = LockFile::load(&lock_path).with_context(|| "Failed to load lock file")?;
+    lock.remove_external(name);
+    if let Some(locked) = artifact.and_then(|artifact| locked_entry(name, artifact)) {
+        lock.add_external(locked);
+    }
+    lock.save(&lock_path)
+        .with_context(|| "Failed to save lock file")
+}
+
+/// An external artifact fetched into shared storage
+#[derive(Debug, Clone, Serialize)]
+pub struct FetchedArtifact {
+    /// Artifact name
+    pub name: String,
+    /// Declared checksum as `<algorithm>:<digest>`
+    pub checksum: String,
+    /// Copy in the project, under its `path` or `external/`
+    pub file: PathBuf,
+    /// Whether it was downloaded rather than found in shared storage
+    pub downloaded: bool,
+}
 
-    // Add to manifest
-    manifest.external.insert(name.to_string(), artifact);
+/// Fetch URL artifacts into shared storage and the project
+///
+/// Each file is downloaded once into [`SharedStorage`], addressed by its
+/// checksum and verified, and the project's copy is refreshed from there.
+/// `names` selects the artifacts, all URL artifacts if empty; local
+/// artifacts are skipped. With `force` the shared copy is downloaded again.
+pub async fn fetch_artifacts(
+    project_dir: &Path,
+    manifest: &Manifest,
+    storage: &SharedStorage,
+    names: &[String],
+    force: bool,
+) -> Result<Vec<FetchedArtifact>> {
+    if let Some(unknown) = names.iter().find(|n| !manifest.external.contains_key(*n)) {
+        anyhow::bail!("External artifact '{unknown}' not found in zigroot.toml");
+    }
+    let mut selected: Vec<(&String, &ExternalArtifact)> = manifest
+        .external
+        .iter()
+        .filter(|(name, artifact)| {
+            artifact.url.is_some() && (names.is_empty() || names.contains(name))
+        })
+        .collect();
+    selected.sort_by(|a, b| a.0.cmp(b.0));
+
+    let downloads = DownloadManager::new();
+    let mut fetched = Vec::new();
+    for (name, artifact) in selected {
+        let Some(url) = artifact.url.as_deref() else {
+            continue;
+        };
+        let checksum = artifact
+            .checksum()
+            .with_context(|| format!("External artifact '{name}' has an invalid checksum"))?
+            .with_context(|| format!("External artifact '{name}' has a url but no checksum"))?;
+        let file = artifact_file(project_dir, name, artifact);
+        let filename = file
+            .file_name()
+            .map_or_else(|| name.clone(), |f| f.to_string_lossy().into_owned());
+        let shared = storage.external_path(&checksum, &filename);
+
+        // Another project may be downloading the same file
+        let _entry_lock = storage
+            .lock_entry(&shared)
+            .await
+            .with_context(|| format!("Failed to lock shared copy of '{name}'"))?;
+        let downloaded = force || !checksum.matches_file(&shared).unwrap_or(false);
+        if downloaded {
+            if let Some(parent) = shared.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            let urls = source_urls(url, &artifact.mirrors, None, false);
+            downloads
+                .download_from(&urls, &shared, Some(&checksum), None)
+                .await
+                .with_context(|| format!("Failed to download external artifact '{name}'"))?;
+        }
+
+        if !checksum.matches_file(&file).unwrap_or(false) {
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            std::fs::copy(&shared, &file)
+                .with_context(|| format!("Failed to copy '{name}' to {}", file.display()))?;
+        }
+        fetched.push(FetchedArtifact {
+            name: name.clone(),
+            checksum: checksum.to_string(),
+            file,
+            downloaded,
+        });
+    }
+    Ok(fetched)
+}
+
+/// Output subdirectory of artifacts of a type
+///
+/// Partition tables are consumed by the disk image and not copied.
+pub fn output_subdir(artifact_type: &str) -> Option<&'static str> {
+    match artifact_type {
+        "bootloader" => Some("bootloader"),
+        "kernel" => Some("kernel"),
+        "dtb" => Some("dtbs"),
+        "firmware" => Some("firmware"),
+        "partition_table" => None,
+        _ => Some("external"),
+    }
+}
+
+/// External artifacts copied into the build output
+#[derive(Debug, Clone, Default)]
+pub struct PlacedArtifacts {
+    /// Copied files
+    pub files: Vec<PathBuf>,
+    /// Artifacts without a local file, which were skipped
+    pub missing: Vec<String>,
+}
+
+/// Copy the external artifacts into `output_dir`, by type
+///
+/// Each artifact goes to `<output_dir>/<subdir>/<file name>`, see
+/// [`output_subdir`].
+pub fn place_artifacts(
+    project_dir: &Path,
+    manifest: &Manifest,
+    output_dir: &Path,
+) -> Result<PlacedArtifacts> {
+    let mut names: Vec<&String> = manifest.external.keys().collect();
+    names.sort();
+
+    let mut placed = PlacedArtifacts::default();
+    for name in names {
+        let artifact = &manifest.external[name];
+        let Some(subdir) = output_subdir(&artifact.artifact_type) else {
+            continue;
+        };
+        let file = artifact_file(project_dir, name, artifact);
+        let (true, Some(filename)) = (file.is_file(), file.file_name()) else {
+            placed.missing.push(name.clone());
+            continue;
+        };
+        let dest_dir = output_dir.join(subdir);
+        std::fs::create_dir_all(&dest_dir)
+            .with_context(|| format!("Failed to create {}", dest_dir.display()))?;
+        let dest = dest_dir.join(filename);
+        std::fs::copy(&file, &dest)
+            .with_context(|| format!("Failed to copy '{name}' to {}", dest.display()))?;
+        placed.files.push(dest);
+    }
+    Ok(placed)
+}
 
-    // Write back to file
+/// Read the project's manifest
+fn load_manifest(project_dir: &Path) -> Result<Manifest> {
+    let manifest_path = project_dir.join("zigroot.toml");
+    let manifest_content = std::fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read manifest at {}", manifest_path.display()))?;
+    Manifest::from_toml(&manifest_content).with_context(|| "Failed to parse manifest")
+}
+
+/// Write the project's manifest
+fn save_manifest(project_dir: &Path, manifest: &Manifest) -> Result<()> {
+    let manifest_path = project_dir.join("zigroot.toml");
     let new_content = manifest
         .to_toml()
         .with_context(|| "Failed to serialize manifest")?;
-
-    std::fs::write(&manifest_path, new_content)
-        .with_context(|| format!("Failed to write manifest at {}", manifest_path.display()))?;
-
-    Ok(())
+    write_file_atomic(&manifest_path, new_content.as_bytes())
+        .with_context(|| format!("Failed to write manifest at {}", manifest_path.display()))
 }
 
 #[cfg(test)]
@@ -203,6 +649,73 @@ hostname = "test"
         assert!(artifacts.is_empty());
     }
 
+    #[test]
+    fn test_list_artifacts_reports_files_and_checksums() {
+        let dir = create_test_project();
+        let good = crate::infra::download::compute_checksum(b"u-boot");
+        let manifest = format!(
+            "{}\n[external.boot]\ntype = \"bootloader\"\nurl = \"https://example.com/u-boot.bin\"\nsha256 = \"{good}\"\n\
+             \n[external.dtb]\ntype = \"dtb\"\nurl = \"https://example.com/board.dtb\"\nsha256 = \"{}\"\n\
+             \n[external.kernel]\ntype = \"kernel\"\npath = \"images/Image\"\n\
+             \n[external.table]\ntype = \"partition_table\"\npath = \"images/gpt.bin\"\n",
+            std::fs::read_to_string(dir.path().join("zigroot.toml")).unwrap(),
+            "0".repeat(64)
+        );
+        std::fs::write(dir.path().join("zigroot.toml"), manifest).unwrap();
+        std::fs::create_dir_all(dir.path().join("external")).unwrap();
+        std::fs::create_dir_all(dir.path().join("images")).unwrap();
+        std::fs::write(dir.path().join("external/u-boot.bin"), "u-boot").unwrap();
+        std::fs::write(dir.path().join("external/board.dtb"), "stale").unwrap();
+        std::fs::write(dir.path().join("images/Image"), "kernel!").unwrap();
+
+        let artifacts = list_artifacts(dir.path()).unwrap();
+        let summary: Vec<(&str, ArtifactStatus, Option<u64>, ChecksumStatus, bool)> = artifacts
+            .iter()
+            .map(|a| {
+                (
+                    a.name.as_str(),
+                    a.status.clone(),
+                    a.size_bytes,
+                    a.checksum,
+                    a.needs_fetch(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "boot",
+                    ArtifactStatus::Downloaded,
+                    Some(6),
+                    ChecksumStatus::Verified,
+                    false
+                ),
+                (
+                    "dtb",
+                    ArtifactStatus::Downloaded,
+                    Some(5),
+                    ChecksumStatus::Mismatch,
+                    true
+                ),
+                (
+                    "kernel",
+                    ArtifactStatus::Local,
+                    Some(7),
+                    ChecksumStatus::Unchecked,
+                    false
+                ),
+                (
+                    "table",
+                    ArtifactStatus::Missing,
+                    None,
+                    ChecksumStatus::Pending,
+                    true
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn test_add_artifact_url() {
         let dir = create_test_project();
@@ -212,6 +725,8 @@ hostname = "test"
             "bootloader",
             Some("https://example.com/boot.bin"),
             None,
+            Some((ChecksumAlgorithm::Blake3, &"A".repeat(64))),
+            None,
         )
         .unwrap();
 
@@ -220,6 +735,10 @@ hostname = "test"
         assert_eq!(artifacts[0].name, "bootloader");
         assert_eq!(artifacts[0].artifact_type, "bootloader");
         assert!(artifacts[0].url.is_some());
+        assert_eq!(
+            artifacts[0].digest,
+            Some(format!("blake3:{}", "a".repeat(64)))
+        );
     }
 
     #[test]
@@ -231,6 +750,8 @@ hostname = "test"
             "kernel",
             None,
             Some("external/kernel.img"),
+            None,
+            None,
         )
         .unwrap();
 
@@ -247,17 +768,223 @@ hostname = "test"
         let result = add_artifact(
             dir.path(),
             "test",
-            "invalid_type",
-            Some("https://example.com/test.bin"),
+            "bootlader",
+            None,
+            Some("boot.bin"),
+            None,
             None,
         );
-        assert!(result.is_err());
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("invalid type 'bootlader'"), "{message}");
+        assert!(message.contains("bootloader, kernel, partition_table"));
+        assert!(list_artifacts(dir.path()).unwrap().is_empty());
     }
 
     #[test]
     fn test_add_artifact_requires_url_or_path() {
         let dir = create_test_project();
-        let result = add_artifact(dir.path(), "test", "bootloader", None, None);
+        let result = add_artifact(dir.path(), "test", "bootloader", None, None, None, None);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_add_artifact_checks_source_checksum_and_format() {
+        let dir = create_test_project();
+        let url = Some("https://example.com/boot.bin");
+        let sha256 = "a".repeat(64);
+        let add = |artifact_type: &str,
+                   url: Option<&str>,
+                   path: Option<&str>,
+                   sha256: Option<&str>,
+                   format: Option<&str>| {
+            let checksum = sha256.map(|digest| (ChecksumAlgorithm::Sha256, digest));
+            add_artifact(
+                dir.path(),
+                "test",
+                artifact_type,
+                url,
+                path,
+                checksum,
+                format,
+            )
+            .map_err(|e| e.to_string())
+        };
+
+        assert!(
+            add("bootloader", url, Some("boot.bin"), Some(&sha256), None)
+                .unwrap_err()
+                .contains("exactly one")
+        );
+        assert!(add("bootloader", url, None, None, None)
+            .unwrap_err()
+            .contains("no sha256"));
+        let error = add("partition_table", None, Some("gpt.bin"), None, Some("gtp")).unwrap_err();
+        assert!(error.contains("gpt, mbr, rockchip"), "{error}");
+        assert!(add("kernel", None, Some("k"), None, Some("gpt"))
+            .unwrap_err()
+            .contains("only allowed for type partition_table"));
+        assert!(list_artifacts(dir.path()).unwrap().is_empty());
+
+        add(
+            "partition_table",
+            url,
+            None,
+            Some(&sha256),
+            Some("rockchip"),
+        )
+        .unwrap();
+        let manifest = std::fs::read_to_string(dir.path().join("zigroot.toml")).unwrap();
+        let manifest = Manifest::from_toml(&manifest).unwrap();
+        assert_eq!(
+            manifest.external["test"].format.as_deref(),
+            Some("rockchip")
+        );
+        assert_eq!(
+            manifest.external["test"].sha256.as_deref(),
+            Some(sha256.as_str())
+        );
+    }
+
+    #[test]
+    fn test_update_and_remove_artifact_follow_lock() {
+        let dir = create_test_project();
+        LockFile::new("0.1.0", "0.13.0")
+            .save(&dir.path().join("zigroot.lock"))
+            .unwrap();
+        let sha256 = "a".repeat(64);
+        add_artifact(
+            dir.path(),
+            "boot",
+            "bootloader",
+            Some("https://example.com/boot.bin"),
+            None,
+            Some((ChecksumAlgorithm::Sha256, &sha256)),
+            None,
+        )
+        .unwrap();
+        let lock = || LockFile::load(&dir.path().join("zigroot.lock")).unwrap();
+        assert_eq!(
+            lock().get_external("boot").unwrap().checksum,
+            format!("sha256:{sha256}")
+        );
+
+        let blake3 = "b".repeat(64);
+        let updated = update_artifact(
+            dir.path(),
+            "boot",
+            Some("https://example.com/v2/boot.bin"),
+            None,
+            Some((ChecksumAlgorithm::Blake3, &blake3)),
+        )
+        .unwrap();
+        assert_eq!(updated.sha256, None);
+        assert_eq!(updated.blake3.as_deref(), Some(blake3.as_str()));
+        let locked = lock();
+        let locked = locked.get_external("boot").unwrap();
+        assert_eq!(locked.url, "https://example.com/v2/boot.bin");
+        assert_eq!(locked.checksum, format!("blake3:{blake3}"));
+
+        // Invalid updates change nothing; switching to a local file drops the pin
+        let error = update_artifact(
+            dir.path(),
+            "boot",
+            None,
+            None,
+            Some((ChecksumAlgorithm::Sha256, "abc")),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("malformed sha256"), "{error}");
+        update_artifact(dir.path(), "boot", None, Some("boot.bin"), None).unwrap();
+        assert!(lock().get_external("boot").is_none());
+        let error = update_artifact(dir.path(), "boot", None, None, None).unwrap_err();
+        assert!(error.to_string().contains("Nothing to update"), "{error}");
+        assert!(update_artifact(dir.path(), "dtb", None, Some("a.dtb"), None).is_err());
+
+        // Artifacts used by the disk layout stay
+        let manifest = std::fs::read_to_string(dir.path().join("zigroot.toml")).unwrap();
+        std::fs::write(
+            dir.path().join("zigroot.toml"),
+            format!(
+                "{manifest}\n[[partitions]]\nname = \"uboot\"\ntype = \"raw\"\nsize = \"1M\"\ncontent = \"boot\"\n"
+            ),
+        )
+        .unwrap();
+        let error = remove_artifact(dir.path(), "boot").unwrap_err();
+        assert!(error.to_string().contains("partition 'uboot'"), "{error}");
+        std::fs::write(dir.path().join("zigroot.toml"), manifest).unwrap();
+        remove_artifact(dir.path(), "boot").unwrap();
+        assert!(list_artifacts(dir.path()).unwrap().is_empty());
+        assert!(remove_artifact(dir.path(), "boot").is_err());
+    }
+
+    #[test]
+    fn test_place_artifacts_by_type() {
+        let dir = create_test_project();
+        let manifest = format!(
+            "{}\n[external.boot]\ntype = \"bootloader\"\npath = \"images/u-boot.bin\"\n\
+             \n[external.board]\ntype = \"dtb\"\npath = \"images/board.dtb\"\n\
+             \n[external.table]\ntype = \"partition_table\"\npath = \"images/gpt.bin\"\n\
+             \n[external.blob]\ntype = \"firmware\"\npath = \"images/missing.bin\"\n",
+            std::fs::read_to_string(dir.path().join("zigroot.toml")).unwrap()
+        );
+        let manifest = Manifest::from_toml(&manifest).unwrap();
+        std::fs::create_dir_all(dir.path().join("images")).unwrap();
+        for file in ["u-boot.bin", "board.dtb", "gpt.bin"] {
+            std::fs::write(dir.path().join("images").join(file), file).unwrap();
+        }
+
+        let output = dir.path().join("output");
+        let placed = place_artifacts(dir.path(), &manifest, &output).unwrap();
+        assert_eq!(
+            placed.files,
+            vec![
+                output.join("dtbs/board.dtb"),
+                output.join("bootloader/u-boot.bin")
+            ]
+        );
+        assert_eq!(placed.missing, vec!["blob".to_string()]);
+        assert_eq!(
+            std::fs::read_to_string(output.join("dtbs/board.dtb")).unwrap(),
+            "board.dtb"
+        );
+    }
+
+    #[test]
+    fn test_validate_artifact_reports_all_problems() {
+        let dir = create_test_project();
+        let artifact =
+            |artifact_type: &str, url: Option<&str>, path: Option<&str>| ExternalArtifact {
+                artifact_type: artifact_type.to_string(),
+                url: url.map(String::from),
+                mirrors: Vec::new(),
+                path: path.map(String::from),
+                sha256: None,
+                sha512: None,
+                blake3: None,
+                format: None,
+            };
+
+        let mut remote = artifact("bootloader", Some("https://example.com/boot.bin"), None);
+        assert_eq!(validate_artifact(dir.path(), "boot", &remote).len(), 1);
+        remote.sha256 = Some("abc".to_string());
+        assert!(validate_artifact(dir.path(), "boot", &remote)[0].contains("malformed sha256"));
+        remote.sha256 = Some("a".repeat(64));
+        assert!(validate_artifact(dir.path(), "boot", &remote).is_empty());
+        remote.blake3 = Some("b".repeat(64));
+        assert!(validate_artifact(dir.path(), "boot", &remote)[0].contains("Conflicting"));
+        remote.sha256 = None;
+        assert!(validate_artifact(dir.path(), "boot", &remote).is_empty());
+        remote.blake3 = None;
+        remote.sha512 = Some("c".repeat(64));
+        assert!(validate_artifact(dir.path(), "boot", &remote)[0].contains("malformed sha512 'ccc"));
+
+        let mut local = artifact("blob", None, Some("missing.bin"));
+        local.format = Some("gpt".to_string());
+        let errors = validate_artifact(dir.path(), "blob", &local);
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(errors[1].contains("does not exist"));
+
+        let both = artifact("kernel", Some("https://example.com/k"), Some("k"));
+        assert!(validate_artifact(dir.path(), "k", &both)[0].contains("exactly one"));
+    }
 }
@@ -3,14 +3,36 @@
 //! This module contains the business logic for downloading package sources
 //! and external artifacts. It handles checksum verification, parallel downloads,
 //! and caching of already downloaded files.
+//!
+//! Each source is tried at its primary URL, then its mirrors and the
+//! configured mirror prefix; the URL that served each download is appended
+//! to [`FETCH_LOG`] in the downloads directory.
+//!
+//! Up to [`FetchOptions::parallel`] downloads run at once, reporting their
+//! progress as [`FetchEvent`]s; a rate limit applies to their combined
+//! throughput.
 
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use futures::stream::{self, StreamExt};
 use thiserror::Error;
 
 use crate::core::lock::LockFile;
-use crate::core::manifest::Manifest;
-use crate::infra::download::{verify_checksum, DownloadManager};
+use crate::core::manifest::{Manifest, PackageRef};
+use crate::core::package::{self, PackageDefinition, SourceConfig};
+use crate::core::shared_storage::SharedStorage;
+use crate::core::version::satisfies_requirement;
+use crate::error::DownloadError;
+use crate::infra::dirs::ZigrootDirs;
+use crate::infra::download::{
+    source_urls, Checksum, DownloadManager, DownloadResult, ProgressCallback,
+};
+use crate::infra::git::{head_commit, resolve_remote_ref, GitOperations, GitRef};
+
+/// Log of the URL each download was served from, in the downloads directory
+pub const FETCH_LOG: &str = "fetch.log";
 
 /// Errors that can occur during fetch
 #[derive(Error, Debug)]
@@ -28,21 +50,52 @@ pub enum FetchError {
     DownloadError { name: String, error: String },
 
     /// Checksum error
-    #[error("Checksum verification failed for '{name}'")]
-    ChecksumError { name: String },
+    #[error("{algorithm} checksum verification failed for '{name}'")]
+    ChecksumError { name: String, algorithm: String },
+
+    /// Invalid checksum declaration
+    #[error("Invalid checksum for '{name}': {error}")]
+    InvalidChecksum { name: String, error: String },
+
+    /// File missing when only verifying
+    #[error("'{name}' has not been fetched: {path} is missing")]
+    NotFetched { name: String, path: String },
 
     /// IO error
     #[error("IO error: {0}")]
     IoError(String),
+
+    /// The commit the lock file pins a git package to cannot be fetched
+    #[error("Cannot fetch '{name}' at its locked commit {commit}: {error}")]
+    LockedCommitUnreachable {
+        name: String,
+        commit: String,
+        error: String,
+    },
 }
 
 /// Options for fetching packages
 #[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct FetchOptions {
     /// Number of parallel downloads
     pub parallel: usize,
     /// Force re-download even if files exist
     pub force: bool,
+    /// Only check the checksums of files already fetched, downloading
+    /// nothing; missing and mismatching files fail
+    pub verify_only: bool,
+    /// Try mirrors before the primary URL
+    pub prefer_mirror: bool,
+    /// Mirror serving every source file by name, tried after a source's
+    /// own mirrors (`download.source_mirror_prefix`)
+    pub mirror_prefix: Option<String>,
+    /// Limit of the combined download rate in bytes per second
+    /// (`--limit-rate`, `fetch.limit_rate`)
+    pub limit_rate: Option<u64>,
+    /// Fail if a git package cannot be fetched at its locked commit,
+    /// instead of resolving its ref again (`--locked`)
+    pub locked: bool,
 }
 
 impl Default for FetchOptions {
@@ -50,10 +103,42 @@ impl Default for FetchOptions {
         Self {
             parallel: 4,
             force: false,
+            verify_only: false,
+            prefer_mirror: false,
+            mirror_prefix: None,
+            limit_rate: None,
+            locked: false,
         }
     }
 }
 
+/// Progress of one download of a fetch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FetchEvent {
+    /// A download started
+    Started {
+        /// Package or artifact name
+        name: String,
+    },
+    /// Bytes of a download arrived
+    Progress {
+        /// Package or artifact name
+        name: String,
+        /// Bytes downloaded so far
+        downloaded: u64,
+        /// Size of the file, 0 if the server did not tell
+        total: u64,
+    },
+    /// A download finished, successfully or not
+    Finished {
+        /// Package or artifact name
+        name: String,
+    },
+}
+
+/// Receiver of [`FetchEvent`]s, shared by concurrent downloads
+pub type FetchProgress = Arc<dyn Fn(FetchEvent) + Send + Sync>;
+
 /// Information about a downloaded package
 #[derive(Debug, Clone)]
 pub struct DownloadedPackage {
@@ -63,6 +148,8 @@ pub struct DownloadedPackage {
     pub version: String,
     /// Path to downloaded file
     pub path: PathBuf,
+    /// URL the file was served from
+    pub url: String,
 }
 
 /// Result of fetching packages
@@ -72,8 +159,8 @@ pub struct FetchResult {
     pub downloaded: Vec<DownloadedPackage>,
     /// Packages that were skipped (already downloaded)
     pub skipped: Vec<String>,
-    /// External artifacts that were downloaded
-    pub external_downloaded: Vec<String>,
+    /// External artifacts that were downloaded, with the URL that served them
+    pub external_downloaded: Vec<(String, String)>,
     /// External artifacts that were skipped
     pub external_skipped: Vec<String>,
     /// Failed downloads with error messages
@@ -81,9 +168,12 @@ pub struct FetchResult {
 }
 
 /// Fetch all packages and external artifacts for a project
+///
+/// Download progress is reported to `progress`, if given.
 pub async fn fetch_packages(
     project_path: &Path,
     options: &FetchOptions,
+    progress: Option<&FetchProgress>,
 ) -> Result<FetchResult, FetchError> {
     let manifest_path = project_path.join("zigroot.toml");
     let lock_path = project_path.join("zigroot.lock");
@@ -107,23 +197,34 @@ pub async fn fetch_packages(
     std::fs::create_dir_all(&downloads_dir).map_err(|e| FetchError::IoError(e.to_string()))?;
 
     let mut result = FetchResult::default();
-    let download_manager = DownloadManager::new();
+    let mut download_manager = DownloadManager::new();
+    if let Some(rate) = options.limit_rate {
+        download_manager = download_manager.with_rate_limit(rate);
+    }
+    let parallel = options.parallel.max(1);
 
     // Fetch packages
-    for (package_name, package_ref) in &manifest.packages {
-        let download_result = fetch_single_package(
-            &download_manager,
-            project_path,
-            &downloads_dir,
-            package_name,
-            package_ref,
-            lock_file.as_ref(),
-            options,
-        )
+    let package_results: Vec<_> = stream::iter(&manifest.packages)
+        .map(|(package_name, package_ref)| {
+            fetch_single_package(
+                &download_manager,
+                project_path,
+                &downloads_dir,
+                package_name,
+                package_ref,
+                lock_file.as_ref(),
+                options,
+                progress,
+            )
+        })
+        .buffered(parallel)
+        .collect()
         .await;
 
+    for ((package_name, _), download_result) in manifest.packages.iter().zip(package_results) {
         match download_result {
             Ok(Some(downloaded)) => {
+                log_fetch(&downloads_dir, package_name, &downloaded.url);
                 result.downloaded.push(downloaded);
             }
             Ok(None) => {
@@ -136,22 +237,31 @@ pub async fn fetch_packages(
     }
 
     // Fetch external artifacts
-    for (artifact_name, artifact) in &manifest.external {
-        let artifact_result = fetch_external_artifact(
-            &download_manager,
-            project_path,
-            &external_dir,
-            artifact_name,
-            artifact,
-            options,
-        )
+    let artifact_results: Vec<_> = stream::iter(&manifest.external)
+        .map(|(artifact_name, artifact)| {
+            fetch_external_artifact(
+                &download_manager,
+                project_path,
+                &external_dir,
+                artifact_name,
+                artifact,
+                options,
+                progress,
+            )
+        })
+        .buffered(parallel)
+        .collect()
         .await;
 
+    for ((artifact_name, _), artifact_result) in manifest.external.iter().zip(artifact_results) {
         match artifact_result {
-            Ok(true) => {
-                result.external_downloaded.push(artifact_name.clone());
+            Ok(Some(url)) => {
+                log_fetch(&downloads_dir, artifact_name, &url);
+                result
+                    .external_downloaded
+                    .push((artifact_name.clone(), url));
             }
-            Ok(false) => {
+            Ok(None) => {
                 result.external_skipped.push(artifact_name.clone());
             }
             Err(e) => {
@@ -164,15 +274,21 @@ pub async fn fetch_packages(
 }
 
 /// Fetch a single package
+#[allow(clippy::too_many_arguments)]
 async fn fetch_single_package(
     download_manager: &DownloadManager,
     project_path: &Path,
     downloads_dir: &Path,
     package_name: &str,
-    package_ref: &crate::core::manifest::PackageRef,
+    package_ref: &PackageRef,
     lock_file: Option<&LockFile>,
     options: &FetchOptions,
+    progress: Option<&FetchProgress>,
 ) -> Result<Option<DownloadedPackage>, FetchError> {
+    if let Some(source) = git_source(project_path, package_name, package_ref, lock_file) {
+        return fetch_git_package(package_name, &source, options).await;
+    }
+
     // Check if this is a local package
     let local_package_path = project_path.join("packages").join(package_name);
     if local_package_path.exists() {
@@ -180,16 +296,17 @@ async fn fetch_single_package(
         return Ok(None);
     }
 
-    // Get version from package ref or lock file
-    let version = package_ref
-        .version
-        .clone()
-        .or_else(|| {
-            lock_file
-                .and_then(|lf| lf.get_package(package_name))
-                .map(|p| p.version.clone())
-        })
-        .unwrap_or_else(|| "latest".to_string());
+    // Get version from package ref or lock file; the locked version wins
+    // while it satisfies the manifest requirement (e.g. "^1.36.1")
+    let locked_version = lock_file
+        .and_then(|lf| lf.get_package(package_name))
+        .map(|p| p.version.clone());
+    let version = match (package_ref.version.clone(), locked_version) {
+        (Some(requirement), Some(locked)) if satisfies_requirement(&locked, &requirement) => locked,
+        (Some(requirement), _) => requirement,
+        (None, Some(locked)) => locked,
+        (None, None) => "latest".to_string(),
+    };
 
     // Determine download URL and checksum
     let (url, expected_checksum) =
@@ -199,10 +316,19 @@ async fn fetch_single_package(
     let filename = format!("{package_name}-{version}.tar.gz");
     let dest_path = downloads_dir.join(&filename);
 
+    if options.verify_only {
+        return match url {
+            Some(_) => {
+                verify_existing(package_name, &dest_path, expected_checksum.as_ref()).map(|()| None)
+            }
+            None => Ok(None),
+        };
+    }
+
     // Check if already downloaded with valid checksum
     if !options.force && dest_path.exists() {
         if let Some(ref checksum) = expected_checksum {
-            if verify_checksum(&dest_path, checksum).unwrap_or(false) {
+            if checksum.matches_file(&dest_path).unwrap_or(false) {
                 return Ok(None); // Already downloaded and valid
             }
             // Checksum mismatch, delete and re-download
@@ -215,21 +341,28 @@ async fn fetch_single_package(
 
     // Download the package
     if let Some(download_url) = url {
-        let download_result = if let Some(ref checksum) = expected_checksum {
-            download_manager
-                .download_verified(&download_url, &dest_path, checksum, None)
-                .await
-        } else {
-            download_manager
-                .download(&download_url, &dest_path, None)
-                .await
-        };
+        let urls = source_urls(
+            &download_url,
+            &[],
+            options.mirror_prefix.as_deref(),
+            options.prefer_mirror,
+        );
+        let download_result = download_reporting(
+            download_manager,
+            &urls,
+            &dest_path,
+            expected_checksum.as_ref(),
+            package_name,
+            progress,
+        )
+        .await;
 
         match download_result {
-            Ok(_) => Ok(Some(DownloadedPackage {
+            Ok(downloaded) => Ok(Some(DownloadedPackage {
                 name: package_name.to_string(),
                 version,
                 path: dest_path,
+                url: downloaded.url,
             })),
             Err(e) => Err(FetchError::DownloadError {
                 name: package_name.to_string(),
@@ -243,13 +376,234 @@ async fn fetch_single_package(
     }
 }
 
+/// Git repository a package is built from
+#[derive(Debug, Clone, PartialEq)]
+struct GitSource {
+    url: String,
+    /// Ref to clone, the remote's HEAD if `None`
+    git_ref: Option<GitRef>,
+    /// Commit the lock file pins the ref to
+    commit: Option<String>,
+    submodules: bool,
+}
+
+/// Git source of a package, from its local definition's `[source]` or the
+/// manifest's `git` key
+fn git_source(
+    project_path: &Path,
+    package_name: &str,
+    package_ref: &PackageRef,
+    lock_file: Option<&LockFile>,
+) -> Option<GitSource> {
+    let commit = lock_file
+        .and_then(|lf| lf.get_package(package_name))
+        .and_then(|p| p.git_sha.clone());
+
+    let definition_path = project_path
+        .join("packages")
+        .join(package_name)
+        .join("package.toml");
+    if let Some(definition) = std::fs::read_to_string(definition_path)
+        .ok()
+        .and_then(|content| PackageDefinition::from_toml(&content).ok())
+    {
+        let SourceConfig::Git {
+            git,
+            git_ref,
+            git_submodules,
+            ..
+        } = definition.source
+        else {
+            return None;
+        };
+        let git_ref = match git_ref {
+            package::GitRef::Tag(name) => GitRef::Tag(name),
+            package::GitRef::Branch(name) => GitRef::Branch(name),
+            package::GitRef::Rev(rev) => GitRef::Rev(rev),
+        };
+        return Some(GitSource {
+            url: git,
+            git_ref: Some(git_ref),
+            commit,
+            submodules: git_submodules,
+        });
+    }
+
+    // `ref` names a branch or tag, which clone alike, or a commit
+    let git_ref = package_ref.ref_.as_deref().map(|reference| {
+        if reference.len() >= 7 && reference.chars().all(|c| c.is_ascii_hexdigit()) {
+            GitRef::Rev(reference.to_string())
+        } else {
+            GitRef::Branch(reference.to_string())
+        }
+    });
+    Some(GitSource {
+        url: package_ref.git.clone()?,
+        git_ref,
+        commit,
+        submodules: false,
+    })
+}
+
+/// Clone a git package into shared storage, once per repository and commit
+///
+/// The ref is resolved to a commit unless the lock file pins one. A checkout
+/// already in shared storage is reused without contacting the remote if it
+/// is at that commit, and cloned again otherwise. A locked commit that
+/// cannot be fetched fails with [`FetchOptions::locked`]; otherwise the ref
+/// is resolved again.
+async fn fetch_git_package(
+    package_name: &str,
+    source: &GitSource,
+    options: &FetchOptions,
+) -> Result<Option<DownloadedPackage>, FetchError> {
+    let git_error = |error: String| FetchError::DownloadError {
+        name: package_name.to_string(),
+        error,
+    };
+    let storage = SharedStorage::new(&ZigrootDirs::new());
+    if options.verify_only {
+        let commit = match &source.commit {
+            Some(commit) => commit.clone(),
+            None => resolve_git_source(source).await.map_err(git_error)?,
+        };
+        let checkout = storage.git_path(&source.url, &commit);
+        return verify_existing(package_name, &checkout, None).map(|()| None);
+    }
+    let Some(locked) = &source.commit else {
+        let commit = resolve_git_source(source).await.map_err(git_error)?;
+        return clone_git_source(package_name, source, &commit, &storage, options)
+            .await
+            .map_err(git_error);
+    };
+
+    match clone_git_source(package_name, source, locked, &storage, options).await {
+        Err(error) if options.locked => Err(FetchError::LockedCommitUnreachable {
+            name: package_name.to_string(),
+            commit: locked.clone(),
+            error,
+        }),
+        Err(error) => {
+            let commit = resolve_git_source(source).await.map_err(git_error)?;
+            tracing::warn!(
+                "Cannot fetch the locked commit {locked} of '{package_name}' ({error}); using {commit}. Run 'zigroot update {package_name}' to lock it."
+            );
+            clone_git_source(package_name, source, &commit, &storage, options)
+                .await
+                .map_err(git_error)
+        }
+        Ok(downloaded) => Ok(downloaded),
+    }
+}
+
+/// Commit the ref of a git source points at on its remote
+async fn resolve_git_source(source: &GitSource) -> Result<String, String> {
+    let url = source.url.clone();
+    let reference = source.git_ref.as_ref().map(|r| r.as_str().to_string());
+    tokio::task::spawn_blocking(move || resolve_remote_ref(&url, reference.as_deref()))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Clone a git source at `commit` into shared storage, unless it is there
+async fn clone_git_source(
+    package_name: &str,
+    source: &GitSource,
+    commit: &str,
+    storage: &SharedStorage,
+    options: &FetchOptions,
+) -> Result<Option<DownloadedPackage>, String> {
+    let git_ref = source
+        .git_ref
+        .clone()
+        .unwrap_or_else(|| GitRef::Rev(commit.to_string()));
+    let checkout = storage.git_path(&source.url, commit);
+
+    // Another project may be cloning the same commit
+    let _entry_lock = storage
+        .lock_entry(&checkout)
+        .await
+        .map_err(|e| e.to_string())?;
+    if checkout.exists() && !options.force {
+        // A checkout moved to another commit is stale and cloned again
+        match head_commit(&checkout) {
+            Ok(head) if head == commit => return Ok(None),
+            Ok(head) => tracing::warn!(
+                "Checkout of '{package_name}' in shared storage is at {head}, not {commit}; cloning it again"
+            ),
+            Err(e) => tracing::warn!(
+                "Checkout of '{package_name}' in shared storage is damaged ({e}); cloning it again"
+            ),
+        }
+    }
+
+    let (url, submodules, pinned) = (source.url.clone(), source.submodules, commit.to_string());
+    let (clone_ref, dest) = (git_ref.clone(), checkout.clone());
+    tokio::task::spawn_blocking(move || -> Result<(), String> {
+        let parent = dest.parent().unwrap_or_else(|| Path::new("."));
+        let partial = format!("{pinned}.partial");
+        let cloned = GitOperations::new(parent.to_path_buf())
+            .with_submodules(submodules)
+            .clone_pinned(&url, &clone_ref, Some(&pinned), &partial)
+            .map_err(|e| e.to_string())?;
+        if dest.exists() {
+            std::fs::remove_dir_all(&dest).map_err(|e| e.to_string())?;
+        }
+        std::fs::rename(&cloned.path, &dest).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    Ok(Some(DownloadedPackage {
+        name: package_name.to_string(),
+        version: match git_ref {
+            GitRef::Rev(_) => commit.to_string(),
+            GitRef::Tag(name) | GitRef::Branch(name) => name,
+        },
+        path: checkout,
+        url: source.url.clone(),
+    }))
+}
+
+/// Check the git packages already cloned for a build
+///
+/// A checkout of a package's locked commit that is at another commit is
+/// cloned again; with `locked`, a locked commit that cannot be fetched fails
+/// the build. Packages that were never fetched are left alone.
+pub async fn verify_git_checkouts(
+    project_path: &Path,
+    manifest: &Manifest,
+    lock_file: &LockFile,
+    locked: bool,
+) -> Result<(), FetchError> {
+    let storage = SharedStorage::new(&ZigrootDirs::new());
+    let options = FetchOptions {
+        locked,
+        ..FetchOptions::default()
+    };
+    for (name, package_ref) in &manifest.packages {
+        let Some(source) = git_source(project_path, name, package_ref, Some(lock_file)) else {
+            continue;
+        };
+        let cloned = source
+            .commit
+            .as_ref()
+            .is_some_and(|commit| storage.git_path(&source.url, commit).exists());
+        if cloned {
+            fetch_git_package(name, &source, &options).await?;
+        }
+    }
+    Ok(())
+}
+
 /// Get download URL and checksum for a package
 fn get_package_download_info(
     package_name: &str,
     version: &str,
-    package_ref: &crate::core::manifest::PackageRef,
+    package_ref: &PackageRef,
     lock_file: Option<&LockFile>,
-) -> (Option<String>, Option<String>) {
+) -> (Option<String>, Option<Checksum>) {
     // Check if it's a git source
     if package_ref.git.is_some() {
         // Git sources are handled differently (clone, not download)
@@ -262,22 +616,12 @@ fn get_package_download_info(
             // Check if source is a URL
             if let Some(ref source) = locked_pkg.source {
                 if source.starts_with("http://") || source.starts_with("https://") {
-                    let checksum = if locked_pkg.sha256 != "pending" && locked_pkg.sha256 != "local"
-                    {
-                        Some(locked_pkg.sha256.clone())
-                    } else {
-                        None
-                    };
-                    return (Some(source.clone()), checksum);
+                    return (Some(source.clone()), locked_pkg.parsed_checksum());
                 }
             }
 
             // Use default registry URL pattern
-            let checksum = if locked_pkg.sha256 != "pending" && locked_pkg.sha256 != "local" {
-                Some(locked_pkg.sha256.clone())
-            } else {
-                None
-            };
+            let checksum = locked_pkg.parsed_checksum();
 
             // Construct URL from registry pattern
             let url = format!(
@@ -295,6 +639,9 @@ fn get_package_download_info(
 }
 
 /// Fetch an external artifact
+///
+/// Returns the URL it was downloaded from, or `None` if nothing was
+/// downloaded.
 async fn fetch_external_artifact(
     download_manager: &DownloadManager,
     project_path: &Path,
@@ -302,20 +649,22 @@ async fn fetch_external_artifact(
     artifact_name: &str,
     artifact: &crate::core::manifest::ExternalArtifact,
     options: &FetchOptions,
-) -> Result<bool, FetchError> {
+    progress: Option<&FetchProgress>,
+) -> Result<Option<String>, FetchError> {
+    let expected_checksum = artifact
+        .checksum()
+        .map_err(|e| FetchError::InvalidChecksum {
+            name: artifact_name.to_string(),
+            error: e.to_string(),
+        })?;
+
     // If artifact has a local path, check if it exists
     if let Some(ref local_path) = artifact.path {
         let full_path = project_path.join(local_path);
         if full_path.exists() {
             // Verify checksum if provided
-            if let Some(ref checksum) = artifact.sha256 {
-                if !verify_checksum(&full_path, checksum).unwrap_or(false) {
-                    return Err(FetchError::ChecksumError {
-                        name: artifact_name.to_string(),
-                    });
-                }
-            }
-            return Ok(false); // Already exists locally
+            verify_existing(artifact_name, &full_path, expected_checksum.as_ref())?;
+            return Ok(None); // Already exists locally
         }
     }
 
@@ -333,31 +682,44 @@ async fn fetch_external_artifact(
             external_dir.join(filename)
         };
 
+        if options.verify_only {
+            return verify_existing(artifact_name, &dest_path, expected_checksum.as_ref())
+                .map(|()| None);
+        }
+
         // Check if already downloaded with valid checksum
         if !options.force && dest_path.exists() {
-            if let Some(ref checksum) = artifact.sha256 {
-                if verify_checksum(&dest_path, checksum).unwrap_or(false) {
-                    return Ok(false); // Already downloaded and valid
+            if let Some(ref checksum) = expected_checksum {
+                if checksum.matches_file(&dest_path).unwrap_or(false) {
+                    return Ok(None); // Already downloaded and valid
                 }
                 // Checksum mismatch, delete and re-download
                 let _ = std::fs::remove_file(&dest_path);
             } else {
                 // No checksum to verify, assume valid
-                return Ok(false);
+                return Ok(None);
             }
         }
 
         // Download the artifact
-        let download_result = if let Some(ref checksum) = artifact.sha256 {
-            download_manager
-                .download_verified(url, &dest_path, checksum, None)
-                .await
-        } else {
-            download_manager.download(url, &dest_path, None).await
-        };
+        let urls = source_urls(
+            url,
+            &artifact.mirrors,
+            options.mirror_prefix.as_deref(),
+            options.prefer_mirror,
+        );
+        let download_result = download_reporting(
+            download_manager,
+            &urls,
+            &dest_path,
+            expected_checksum.as_ref(),
+            artifact_name,
+            progress,
+        )
+        .await;
 
         match download_result {
-            Ok(_) => Ok(true),
+            Ok(downloaded) => Ok(Some(downloaded.url)),
             Err(e) => Err(FetchError::DownloadError {
                 name: artifact_name.to_string(),
                 error: e.to_string(),
@@ -370,8 +732,77 @@ async fn fetch_external_artifact(
                 "Local artifact '{artifact_name}' not found"
             )))
         } else {
-            Ok(false) // Nothing to download
+            Ok(None) // Nothing to download
+        }
+    }
+}
+
+/// Download from the first URL that serves the file, reporting progress
+/// under `name`
+async fn download_reporting(
+    download_manager: &DownloadManager,
+    urls: &[String],
+    dest: &Path,
+    checksum: Option<&Checksum>,
+    name: &str,
+    progress: Option<&FetchProgress>,
+) -> Result<DownloadResult, DownloadError> {
+    let Some(progress) = progress else {
+        return download_manager
+            .download_from(urls, dest, checksum, None)
+            .await;
+    };
+
+    progress(FetchEvent::Started {
+        name: name.to_string(),
+    });
+    let reporter = Arc::clone(progress);
+    let reported_name = name.to_string();
+    let callback: ProgressCallback = Box::new(move |downloaded, total| {
+        reporter(FetchEvent::Progress {
+            name: reported_name.clone(),
+            downloaded,
+            total,
+        });
+    });
+    let result = download_manager
+        .download_from(urls, dest, checksum, Some(callback))
+        .await;
+    progress(FetchEvent::Finished {
+        name: name.to_string(),
+    });
+    result
+}
+
+/// Record the URL a download was served from in the fetch log
+fn log_fetch(downloads_dir: &Path, name: &str, url: &str) {
+    let path = downloads_dir.join(FETCH_LOG);
+    let logged = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{name} {url}"));
+    if let Err(e) = logged {
+        tracing::debug!("Failed to write {}: {e}", path.display());
+    }
+}
+
+/// Check a fetched file against its checksum, if one is known
+fn verify_existing(name: &str, path: &Path, checksum: Option<&Checksum>) -> Result<(), FetchError> {
+    if !path.exists() {
+        return Err(FetchError::NotFetched {
+            name: name.to_string(),
+            path: path.display().to_string(),
+        });
+    }
+    match checksum {
+        Some(checksum) if !checksum.matches_file(path).unwrap_or(false) => {
+            Err(FetchError::ChecksumError {
+                name: name.to_string(),
+                algorithm: checksum.algorithm.to_string(),
+            })
         }
+        _ => Ok(()),
     }
 }
 
@@ -384,6 +815,10 @@ mod tests {
         let options = FetchOptions::default();
         assert_eq!(options.parallel, 4);
         assert!(!options.force);
+        assert!(!options.verify_only);
+        assert!(!options.prefer_mirror);
+        assert!(options.mirror_prefix.is_none());
+        assert!(options.limit_rate.is_none());
     }
 
     #[test]
@@ -0,0 +1,583 @@
+//! FIT image generation for U-Boot
+//!
+//! Combines a kernel, device trees and an optional ramdisk into a Flattened
+//! Image Tree. An image tree source (`.its`) is generated from the `[fit]`
+//! section of the manifest or board and compiled with `mkimage` into
+//! `<name>.itb` in the output directory of the board and format.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::core::external::resolve_reference;
+use crate::core::flash::load_board_definition;
+use crate::core::kernel::target_to_kernel_arch;
+use crate::core::manifest::Manifest;
+use crate::core::output::OutputLayout;
+
+/// Ramdisk reference that selects the initramfs produced by the build
+pub const ROOTFS_RAMDISK: &str = "rootfs";
+
+/// Compression types mkimage accepts for image data
+pub const VALID_FIT_COMPRESSIONS: &[&str] =
+    &["none", "gzip", "bzip2", "lzma", "lzo", "lz4", "zstd"];
+
+/// Hash algorithm recorded for every image node
+const HASH_ALGO: &str = "sha256";
+
+/// FIT image description from a `[fit]` section
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FitConfig {
+    /// Output name; the image is written to `<name>.itb` in the output directory
+    #[serde(default = "default_fit_name")]
+    pub name: String,
+
+    /// Image description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Kernel: external artifact name or project-relative path
+    #[serde(default = "default_fit_kernel")]
+    pub kernel: String,
+
+    /// Kernel load address (e.g., "0x40080000")
+    pub load_address: String,
+
+    /// Kernel entry point; defaults to the load address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_address: Option<String>,
+
+    /// Compression of the kernel data (none, gzip, bzip2, lzma, lzo, lz4, zstd)
+    #[serde(default = "default_fit_compression")]
+    pub compression: String,
+
+    /// Ramdisk: "rootfs" for the built initramfs, an external artifact or a path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ramdisk: Option<String>,
+
+    /// Ramdisk load address; U-Boot picks one when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ramdisk_address: Option<String>,
+
+    /// U-Boot architecture name; derived from the board target when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arch: Option<String>,
+
+    /// Boot configurations, one per device tree; the first is the default
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub configs: Vec<FitBootConfig>,
+}
+
+/// A FIT boot configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FitBootConfig {
+    /// Configuration name
+    pub name: String,
+
+    /// Configuration description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Device tree: external artifact name or project-relative path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dtb: Option<String>,
+}
+
+fn default_fit_name() -> String {
+    "image".to_string()
+}
+
+fn default_fit_kernel() -> String {
+    "kernel".to_string()
+}
+
+fn default_fit_compression() -> String {
+    "none".to_string()
+}
+
+/// FIT inputs with paths and addresses resolved
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedFit {
+    /// U-Boot architecture name
+    pub arch: String,
+    /// Kernel image file
+    pub kernel: PathBuf,
+    /// Kernel load address
+    pub load: u64,
+    /// Kernel entry point
+    pub entry: u64,
+    /// Ramdisk file
+    pub ramdisk: Option<PathBuf>,
+    /// Ramdisk load address
+    pub ramdisk_load: Option<u64>,
+    /// Device tree of each configuration, in configuration order
+    pub dtbs: Vec<Option<PathBuf>>,
+}
+
+impl FitConfig {
+    /// Path of the compiled image in the output directory
+    pub fn output_path(&self, layout: &OutputLayout) -> PathBuf {
+        layout.image_dir().join(format!("{}.itb", self.name))
+    }
+}
+
+/// Get the FIT configuration of a project
+///
+/// A `[fit]` section in the manifest takes precedence over the board's.
+pub fn project_fit(project_dir: &Path, manifest: &Manifest) -> Option<FitConfig> {
+    manifest.fit.clone().or_else(|| {
+        let board = manifest.board.name.as_deref()?;
+        load_board_definition(project_dir, board).ok()?.fit
+    })
+}
+
+/// Check the FIT inputs before building
+///
+/// The built initramfs is not required to exist yet; every other input must.
+/// Returns all problems found.
+pub fn check_inputs(project_dir: &Path, manifest: &Manifest, fit: &FitConfig) -> Vec<String> {
+    resolve(project_dir, manifest, fit, false).1
+}
+
+/// Resolve the FIT inputs for building
+pub fn resolve_inputs(
+    project_dir: &Path,
+    manifest: &Manifest,
+    fit: &FitConfig,
+) -> Result<ResolvedFit, Vec<String>> {
+    let (resolved, errors) = resolve(project_dir, manifest, fit, true);
+    if errors.is_empty() {
+        Ok(resolved)
+    } else {
+        Err(errors)
+    }
+}
+
+fn resolve(
+    project_dir: &Path,
+    manifest: &Manifest,
+    fit: &FitConfig,
+    require_rootfs: bool,
+) -> (ResolvedFit, Vec<String>) {
+    let mut errors = Vec::new();
+
+    let mut address = |field: &str, value: &str| {
+        parse_address(value).unwrap_or_else(|| {
+            errors.push(format!(
+                "Invalid fit.{field} '{value}': expected a hex (0x...) or decimal address"
+            ));
+            0
+        })
+    };
+    let load = address("load_address", &fit.load_address);
+    let entry = fit
+        .entry_address
+        .as_deref()
+        .map_or(load, |value| address("entry_address", value));
+    let ramdisk_load = fit
+        .ramdisk_address
+        .as_deref()
+        .map(|value| address("ramdisk_address", value));
+
+    if !VALID_FIT_COMPRESSIONS.contains(&fit.compression.as_str()) {
+        errors.push(format!(
+            "Invalid fit.compression '{}': must be one of {}",
+            fit.compression,
+            VALID_FIT_COMPRESSIONS.join(", ")
+        ));
+    }
+
+    let arch = resolve_arch(project_dir, manifest, fit).unwrap_or_else(|| {
+        errors.push("Cannot determine the FIT architecture. Set fit.arch".to_string());
+        String::new()
+    });
+
+    let kernel = resolve_reference(project_dir, manifest, &fit.kernel);
+    if !kernel.is_file() {
+        errors.push(format!(
+            "FIT kernel '{}' not found at {}. Run 'zigroot kernel build' or 'zigroot fetch' first",
+            fit.kernel,
+            kernel.display()
+        ));
+    }
+
+    let ramdisk = fit.ramdisk.as_deref().map(|reference| {
+        resolve_ramdisk(
+            project_dir,
+            manifest,
+            reference,
+            require_rootfs,
+            &mut errors,
+        )
+    });
+
+    let mut dtbs = Vec::new();
+    for (index, config) in fit.configs.iter().enumerate() {
+        if fit.configs[..index].iter().any(|c| c.name == config.name) {
+            errors.push(format!("Duplicate FIT configuration '{}'", config.name));
+        }
+        dtbs.push(config.dtb.as_deref().map(|reference| {
+            let path = resolve_reference(project_dir, manifest, reference);
+            if !path.is_file() {
+                errors.push(format!(
+                    "FIT configuration '{}': device tree '{reference}' not found at {}",
+                    config.name,
+                    path.display()
+                ));
+            }
+            path
+        }));
+    }
+
+    let resolved = ResolvedFit {
+        arch,
+        kernel,
+        load,
+        entry,
+        ramdisk,
+        ramdisk_load,
+        dtbs,
+    };
+    (resolved, errors)
+}
+
+/// U-Boot architecture from `fit.arch` or the board target
+fn resolve_arch(project_dir: &Path, manifest: &Manifest, fit: &FitConfig) -> Option<String> {
+    if let Some(arch) = &fit.arch {
+        return Some(arch.clone());
+    }
+    let board = load_board_definition(project_dir, manifest.board.name.as_deref()?).ok()?;
+    let arch = target_to_kernel_arch(&board.board.target);
+    (arch != "unknown").then(|| arch.to_string())
+}
+
+/// Resolve the ramdisk, which may be the initramfs produced by the build
+fn resolve_ramdisk(
+    project_dir: &Path,
+    manifest: &Manifest,
+    reference: &str,
+    require_rootfs: bool,
+    errors: &mut Vec<String>,
+) -> PathBuf {
+    if reference != ROOTFS_RAMDISK {
+        let path = resolve_reference(project_dir, manifest, reference);
+        if !path.is_file() {
+            errors.push(format!(
+                "FIT ramdisk '{reference}' not found at {}",
+                path.display()
+            ));
+        }
+        return path;
+    }
+
+    let build = &manifest.build;
+    if build.image_format != "initramfs" {
+        errors.push(format!(
+            "fit.ramdisk = \"{ROOTFS_RAMDISK}\" requires image_format = \"initramfs\""
+        ));
+    } else if build.embed_in_kernel {
+        errors.push(format!(
+            "fit.ramdisk = \"{ROOTFS_RAMDISK}\" conflicts with build.embed_in_kernel"
+        ));
+    }
+    let path = OutputLayout::new(project_dir, manifest).artifact(&build.image_file_name());
+    if require_rootfs && !path.is_file() {
+        errors.push(format!("Built initramfs not found at {}", path.display()));
+    }
+    path
+}
+
+/// Parse a hex (`0x...`) or decimal address
+pub fn parse_address(value: &str) -> Option<u64> {
+    let value = value.trim();
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Format an address as a device tree cell list
+fn format_cells(value: u64, cells: u32) -> String {
+    if cells == 2 {
+        format!("<0x{:08x} 0x{:08x}>", value >> 32, value & 0xffff_ffff)
+    } else {
+        format!("<0x{value:08x}>")
+    }
+}
+
+/// Format a string property value
+fn quoted(value: &str) -> String {
+    format!("\"{value}\"")
+}
+
+/// Format a data property including a file
+fn incbin(path: &Path) -> String {
+    format!("/incbin/(\"{}\")", path.display())
+}
+
+/// Render an image node
+///
+/// Every image carries a hash node, so configurations can later be signed
+/// by adding signature nodes and passing a key directory to mkimage.
+fn image_node(name: &str, properties: &[(&str, String)]) -> String {
+    let mut node = format!("\t\t{name} {{\n");
+    for (key, value) in properties {
+        let _ = writeln!(node, "\t\t\t{key} = {value};");
+    }
+    let _ = writeln!(
+        node,
+        "\t\t\thash-1 {{\n\t\t\t\talgo = \"{HASH_ALGO}\";\n\t\t\t}};\n\t\t}};"
+    );
+    node
+}
+
+/// Generate the image tree source
+pub fn generate_its(fit: &FitConfig, resolved: &ResolvedFit) -> String {
+    let addresses = [
+        Some(resolved.load),
+        Some(resolved.entry),
+        resolved.ramdisk_load,
+    ];
+    let cells = if addresses
+        .iter()
+        .flatten()
+        .any(|addr| *addr > u64::from(u32::MAX))
+    {
+        2
+    } else {
+        1
+    };
+    let arch = quoted(&resolved.arch);
+
+    let mut images = image_node(
+        "kernel",
+        &[
+            ("description", quoted("Linux kernel")),
+            ("data", incbin(&resolved.kernel)),
+            ("type", quoted("kernel")),
+            ("arch", arch.clone()),
+            ("os", quoted("linux")),
+            ("compression", quoted(&fit.compression)),
+            ("load", format_cells(resolved.load, cells)),
+            ("entry", format_cells(resolved.entry, cells)),
+        ],
+    );
+
+    if let Some(ramdisk) = &resolved.ramdisk {
+        let mut properties = vec![
+            ("description", quoted("Ramdisk")),
+            ("data", incbin(ramdisk)),
+            ("type", quoted("ramdisk")),
+            ("arch", arch.clone()),
+            ("os", quoted("linux")),
+            ("compression", quoted("none")),
+        ];
+        if let Some(load) = resolved.ramdisk_load {
+            properties.push(("load", format_cells(load, cells)));
+        }
+        images.push_str(&image_node("ramdisk", &properties));
+    }
+
+    // Without explicit configurations a single kernel (+ ramdisk) config is used
+    let default_config = [FitBootConfig {
+        name: "default".to_string(),
+        description: None,
+        dtb: None,
+    }];
+    let configs: &[FitBootConfig] = if fit.configs.is_empty() {
+        &default_config
+    } else {
+        &fit.configs
+    };
+
+    let mut configurations = String::new();
+    for (index, config) in configs.iter().enumerate() {
+        let description = config.description.as_deref().unwrap_or(&config.name);
+        let _ = write!(
+            configurations,
+            "\t\t{} {{\n\t\t\tdescription = {};\n\t\t\tkernel = \"kernel\";\n",
+            config.name,
+            quoted(description)
+        );
+        if let Some(Some(dtb)) = resolved.dtbs.get(index) {
+            let fdt = format!("fdt-{}", index + 1);
+            images.push_str(&image_node(
+                &fdt,
+                &[
+                    ("description", quoted(&config.name)),
+                    ("data", incbin(dtb)),
+                    ("type", quoted("flat_dt")),
+                    ("arch", arch.clone()),
+                    ("compression", quoted("none")),
+                ],
+            ));
+            let _ = writeln!(configurations, "\t\t\tfdt = \"{fdt}\";");
+        }
+        if resolved.ramdisk.is_some() {
+            configurations.push_str("\t\t\tramdisk = \"ramdisk\";\n");
+        }
+        configurations.push_str("\t\t};\n");
+    }
+
+    format!(
+        "/dts-v1/;\n\n\
+         / {{\n\
+         \tdescription = {};\n\
+         \t#address-cells = <{cells}>;\n\n\
+         \timages {{\n\
+         {images}\
+         \t}};\n\n\
+         \tconfigurations {{\n\
+         \t\tdefault = {};\n\
+         {configurations}\
+         \t}};\n\
+         }};\n",
+        quoted(fit.description.as_deref().unwrap_or(&fit.name)),
+        quoted(&configs[0].name)
+    )
+}
+
+/// Generate the image tree source and compile it with mkimage
+///
+/// Returns the path of the compiled `.itb`.
+pub fn build_fit(project_dir: &Path, manifest: &Manifest, fit: &FitConfig) -> Result<PathBuf> {
+    let resolved = resolve_inputs(project_dir, manifest, fit).map_err(|errors| {
+        anyhow::anyhow!("Invalid FIT configuration:\n  {}", errors.join("\n  "))
+    })?;
+
+    let Ok(mkimage) = which::which("mkimage") else {
+        bail!("mkimage not found in PATH. Install u-boot-tools to build FIT images");
+    };
+
+    let itb = fit.output_path(&OutputLayout::new(project_dir, manifest));
+    let its = itb.with_extension("its");
+    if let Some(parent) = its.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(&its, generate_its(fit, &resolved))
+        .with_context(|| format!("Failed to write {}", its.display()))?;
+
+    let output = Command::new(mkimage)
+        .arg("-f")
+        .arg(&its)
+        .arg(&itb)
+        .output()
+        .context("Failed to run mkimage")?;
+    if !output.status.success() {
+        bail!(
+            "mkimage failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(itb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fit_config(configs: Vec<FitBootConfig>) -> FitConfig {
+        FitConfig {
+            name: "image".to_string(),
+            description: None,
+            kernel: "kernel".to_string(),
+            load_address: "0x40080000".to_string(),
+            entry_address: None,
+            compression: "none".to_string(),
+            ramdisk: Some("rootfs".to_string()),
+            ramdisk_address: None,
+            arch: Some("arm64".to_string()),
+            configs,
+        }
+    }
+
+    fn boot_config(name: &str) -> FitBootConfig {
+        FitBootConfig {
+            name: name.to_string(),
+            description: None,
+            dtb: Some(format!("{name}.dtb")),
+        }
+    }
+
+    #[test]
+    fn test_parse_address() {
+        assert_eq!(parse_address("0x40080000"), Some(0x4008_0000));
+        assert_eq!(parse_address("4096"), Some(4096));
+        assert_eq!(parse_address("0xzz"), None);
+    }
+
+    #[test]
+    fn test_generate_its_with_multiple_configs() {
+        let fit = fit_config(vec![boot_config("evb"), boot_config("pro")]);
+        let resolved = ResolvedFit {
+            arch: "arm64".to_string(),
+            kernel: PathBuf::from("/p/output/kernel/Image"),
+            load: 0x4008_0000,
+            entry: 0x4008_0000,
+            ramdisk: Some(PathBuf::from("/p/output/rootfs.cpio")),
+            ramdisk_load: None,
+            dtbs: vec![
+                Some(PathBuf::from("/p/evb.dtb")),
+                Some(PathBuf::from("/p/pro.dtb")),
+            ],
+        };
+
+        let its = generate_its(&fit, &resolved);
+        assert!(its.starts_with("/dts-v1/;"));
+        assert!(its.contains("#address-cells = <1>;"));
+        assert!(its.contains("load = <0x40080000>;"));
+        assert!(its.contains("data = /incbin/(\"/p/evb.dtb\");"));
+        assert!(its.contains("fdt-2 {"));
+        assert!(its.contains("default = \"evb\";"));
+        assert!(its.contains("fdt = \"fdt-2\";"));
+        assert_eq!(its.matches("algo = \"sha256\";").count(), 4);
+        assert_eq!(its.matches("ramdisk = \"ramdisk\";").count(), 2);
+    }
+
+    #[test]
+    fn test_generate_its_uses_two_cells_for_high_addresses() {
+        let fit = fit_config(vec![]);
+        let resolved = ResolvedFit {
+            arch: "arm64".to_string(),
+            kernel: PathBuf::from("Image"),
+            load: 0x1_0008_0000,
+            entry: 0x1_0008_0000,
+            ramdisk: None,
+            ramdisk_load: None,
+            dtbs: vec![],
+        };
+
+        let its = generate_its(&fit, &resolved);
+        assert!(its.contains("#address-cells = <2>;"));
+        assert!(its.contains("load = <0x00000001 0x00080000>;"));
+        assert!(its.contains("default = \"default\";"));
+        assert!(!its.contains("ramdisk"));
+    }
+
+    #[test]
+    fn test_check_inputs_reports_missing_files() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let manifest = Manifest::from_toml(
+            "[project]\nname = \"t\"\n\n[build]\nimage_format = \"initramfs\"\n",
+        )
+        .unwrap();
+        let fit = fit_config(vec![boot_config("evb")]);
+
+        let errors = check_inputs(temp.path(), &manifest, &fit);
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert!(errors[0].contains("FIT kernel 'kernel'"));
+        assert!(errors[1].contains("device tree 'evb.dtb'"));
+
+        std::fs::write(temp.path().join("kernel"), b"k").unwrap();
+        std::fs::write(temp.path().join("evb.dtb"), b"d").unwrap();
+        assert!(check_inputs(temp.path(), &manifest, &fit).is_empty());
+        assert!(resolve_inputs(temp.path(), &manifest, &fit).is_err());
+    }
+}
@@ -1,17 +1,156 @@
 //! Flash command implementation
 //!
-//! Handles flashing images to devices using board-defined flash profiles.
+//! Handles flashing images to devices using board-defined flash profiles,
+//! or over SSH to a device that is already running Linux.
+//!
+//! A profile's `command` is a template split on whitespace, with these
+//! placeholders:
+//! - `{image}` - the image to flash (the disk image with a partition layout)
+//! - `{rootfs}` - the rootfs image
+//! - `{device}` - the `--device` argument
+//! - `{partition:NAME}` - the image file of partition NAME
+//! - `{artifact:NAME}` - the path of external artifact NAME
+//! - `{project}`, `{board}` - the project directory and board name
 //!
 //! **Validates: Requirements 7.1-7.12**
 
 use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::io::{self, IsTerminal, Write};
+use std::fmt::Write as _;
+use std::io::{self, IsTerminal, Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, ExitStatus, Stdio};
 
 use super::board::{BoardDefinition, FlashProfile};
 use super::manifest::Manifest;
+use super::output::{missing_artifact_message, OutputLayout};
+use super::partition::{content_path, disk_config};
+use crate::infra::download::ProgressCallback;
+
+/// Name of the flash method configured by the manifest's `[flash.ssh]`
+pub const SSH_METHOD: &str = "ssh";
+
+/// Bytes read from an image to recognize its format
+const IMAGE_HEADER_SIZE: u64 = 1082;
+
+/// Formats accepted for `--image`: name, offset and magic bytes
+const IMAGE_SIGNATURES: &[(&str, usize, &[u8])] = &[
+    ("disk image", 510, &[0x55, 0xAA]),
+    ("ext4 filesystem", 1080, &[0x53, 0xEF]),
+    ("squashfs filesystem", 0, b"hsqs"),
+    ("cpio archive", 0, b"070701"),
+    ("gzip archive", 0, &[0x1F, 0x8B]),
+    ("xz archive", 0, &[0xFD, b'7', b'z', b'X', b'Z', 0x00]),
+    ("zstd archive", 0, &[0x28, 0xB5, 0x2F, 0xFD]),
+    ("lz4 archive", 0, &[0x04, 0x22, 0x4D, 0x18]),
+    ("FIT image", 0, &[0xD0, 0x0D, 0xFE, 0xED]),
+];
+
+/// Flash settings in the manifest
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FlashConfig {
+    /// Copy the image to a running device over SSH
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh: Option<SshFlashConfig>,
+}
+
+/// SSH flashing of a device that is already running Linux
+///
+/// The image is streamed over `ssh` into `target_device` or `target_path`
+/// and verified with a remote `sha256sum`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SshFlashConfig {
+    /// Device host name or address
+    pub host: String,
+
+    /// Remote user (defaults to the SSH configuration)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+
+    /// SSH port
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+
+    /// Block device to write the image to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_device: Option<String>,
+
+    /// File to copy the image to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_path: Option<String>,
+
+    /// Commands run on the device before the transfer (e.g. stopping services)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_commands: Vec<String>,
+
+    /// Commands run on the device after verification (e.g. `reboot`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_commands: Vec<String>,
+}
+
+impl SshFlashConfig {
+    /// `user@host`, or the host alone
+    pub fn destination(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{user}@{}", self.host),
+            None => self.host.clone(),
+        }
+    }
+
+    /// Remote path to write, `device` (from `--device`) taking precedence
+    pub fn target(&self, device: Option<&str>) -> Result<String> {
+        device
+            .map(str::to_string)
+            .or_else(|| self.target_device.clone())
+            .or_else(|| self.target_path.clone())
+            .ok_or_else(|| {
+                anyhow!(
+                    "No remote target for SSH flashing of {}.\n\
+                     Set target_device or target_path in [flash.ssh], or use --device <path>.",
+                    self.host
+                )
+            })
+    }
+
+    /// `ssh` arguments running `command` on the device
+    ///
+    /// Password prompts are disabled, so key-based authentication is needed.
+    pub fn ssh_args(&self, command: &str) -> Vec<String> {
+        let mut args = vec![
+            "-o".to_string(),
+            "BatchMode=yes".to_string(),
+            "-o".to_string(),
+            "ConnectTimeout=10".to_string(),
+        ];
+        if let Some(port) = self.port {
+            args.push("-p".to_string());
+            args.push(port.to_string());
+        }
+        args.push(self.destination());
+        args.push(command.to_string());
+        args
+    }
+}
+
+/// Quote a string for the remote shell
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Remote command writing stdin to `target`
+fn remote_write_command(target: &str) -> String {
+    format!("cat > {} && sync", shell_quote(target))
+}
+
+/// Remote command hashing the first `size` bytes of `target`
+///
+/// A block device is larger than the image, so only the written part is
+/// hashed.
+fn remote_checksum_command(target: &str, size: u64) -> String {
+    format!("head -c {size} {} | sha256sum", shell_quote(target))
+}
 
 /// Flash options from CLI
 #[derive(Debug, Clone)]
@@ -47,6 +186,10 @@ pub struct FlashExecutor {
     manifest: Manifest,
     /// Board definition (if available)
     board: Option<BoardDefinition>,
+    /// Transfer progress (`bytes_sent`, `total_bytes`) for SSH flashing
+    progress: Option<ProgressCallback>,
+    /// Image given with `--image`, replacing the project's build output
+    image: Option<PathBuf>,
 }
 
 impl FlashExecutor {
@@ -56,9 +199,25 @@ impl FlashExecutor {
             project_root: project_root.to_path_buf(),
             manifest,
             board,
+            progress: None,
+            image: None,
         }
     }
 
+    /// Report transfer progress of SSH flashing
+    #[must_use]
+    pub fn with_progress(mut self, progress: ProgressCallback) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Flash `image` instead of the project's built image
+    #[must_use]
+    pub fn with_image(mut self, image: &Path) -> Self {
+        self.image = Some(image.to_path_buf());
+        self
+    }
+
     /// Execute the flash command
     pub fn execute(&self, options: &FlashOptions) -> Result<FlashResult> {
         // If --list flag is set, list available methods
@@ -97,7 +256,7 @@ impl FlashExecutor {
         let image_path = self.get_image_path()?;
         if !image_path.exists() {
             bail!(
-                "No rootfs image found at {}. Run 'zigroot build' first.",
+                "No image found at {}. Run 'zigroot build' first.",
                 image_path.display()
             );
         }
@@ -108,9 +267,25 @@ impl FlashExecutor {
         // Check required external artifacts
         self.check_required_artifacts(profile)?;
 
+        // Resolve the command template before asking for confirmation
+        if let Some(template) = &profile.command {
+            self.command_args(profile, template, options)?;
+        }
+
         // Require confirmation unless --yes is specified
         if !options.yes {
-            self.require_confirmation(profile, options.device.as_deref())?;
+            let target = match &profile.ssh {
+                Some(ssh) => format!(
+                    "{} on {}",
+                    ssh.target(options.device.as_deref())?,
+                    ssh.destination()
+                ),
+                None => options
+                    .device
+                    .clone()
+                    .unwrap_or_else(|| "default device".to_string()),
+            };
+            self.require_confirmation(profile, &target)?;
         }
 
         // Execute the flash
@@ -126,7 +301,7 @@ impl FlashExecutor {
                 "No flash methods defined for this board.\n\
                  Manual flashing instructions:\n\
                  1. Build your image with 'zigroot build'\n\
-                 2. Copy the image from output/ to your device\n\
+                 2. Copy the image from output/<board>/latest/ to your device\n\
                  3. Use your device's native flashing tool"
                     .to_string()
             } else {
@@ -151,6 +326,15 @@ impl FlashExecutor {
             if let Some(script) = &profile.script {
                 message.push_str(&format!("    Script: {}\n", script));
             }
+            if let Some(ssh) = &profile.ssh {
+                let _ = writeln!(message, "    Host: {}", ssh.destination());
+            }
+            if let Some(command) = &profile.command {
+                let _ = writeln!(message, "    Command: {command}");
+            }
+            if !profile.host_tools.is_empty() {
+                let _ = writeln!(message, "    Host tools: {}", profile.host_tools.join(", "));
+            }
             if !profile.requires.is_empty() {
                 message.push_str(&format!("    Requires: {}\n", profile.requires.join(", ")));
             }
@@ -166,45 +350,102 @@ impl FlashExecutor {
     }
 
     /// Get flash profiles from board definition
+    ///
+    /// The manifest's `[flash.ssh]` replaces the SSH settings of the board's
+    /// `ssh` profile, or adds that profile.
     fn get_flash_profiles(&self) -> Vec<FlashProfile> {
-        self.board
+        let mut profiles = self
+            .board
             .as_ref()
             .map(|b| b.flash.clone())
-            .unwrap_or_default()
+            .unwrap_or_default();
+
+        let ssh = self.manifest.flash.as_ref().and_then(|f| f.ssh.clone());
+        if let Some(ssh) = ssh {
+            match profiles.iter_mut().find(|p| p.name == SSH_METHOD) {
+                Some(profile) => profile.ssh = Some(ssh),
+                None => profiles.push(FlashProfile {
+                    name: SSH_METHOD.to_string(),
+                    description: "Copy the image to a running device over SSH".to_string(),
+                    script: None,
+                    tool: None,
+                    requires: Vec::new(),
+                    ssh: Some(ssh),
+                    command: None,
+                    host_tools: Vec::new(),
+                }),
+            }
+        }
+        profiles
     }
 
-    /// Get the path to the rootfs image
+    /// Get the path to the image to flash
+    ///
+    /// A full disk image is flashed when a partition layout is configured,
+    /// otherwise the rootfs image.
+    ///
+    /// The image of the board and format is preferred, then the board's
+    /// latest build; images of other formats are listed when neither exists.
+    /// An image given with `--image` replaces all of these.
     fn get_image_path(&self) -> Result<PathBuf> {
-        let output_dir = self.project_root.join("output");
-        let image_format = &self.manifest.build.image_format;
-
-        let image_name = match image_format.as_str() {
-            "ext4" => "rootfs.img",
-            "squashfs" => "rootfs.squashfs",
-            "initramfs" => "rootfs.cpio",
-            _ => "rootfs.img",
+        if let Some(image) = &self.image {
+            return Ok(image.clone());
+        }
+        let file_name = match disk_config(&self.manifest, self.board.as_ref()) {
+            Some((disk, _)) => disk.name,
+            None => self.manifest.build.image_file_name(),
         };
+        let layout = self.output_layout();
+        layout.find(&file_name).or_else(|candidates| {
+            if candidates.is_empty() {
+                Ok(layout.image_dir().join(&file_name))
+            } else {
+                bail!(missing_artifact_message(
+                    &layout.image_dir().join(&file_name),
+                    &candidates
+                ))
+            }
+        })
+    }
 
-        Ok(output_dir.join(image_name))
+    /// Output directory layout of the project
+    fn output_layout(&self) -> OutputLayout {
+        OutputLayout::new(&self.project_root, &self.manifest)
     }
 
     /// Validate that required tools are installed
+    ///
+    /// All missing tools are reported at once, before anything runs.
     fn validate_tools(&self, profile: &FlashProfile) -> Result<()> {
-        if let Some(tool) = &profile.tool {
-            // Check if the tool is available in PATH
-            let status = Command::new("which").arg(tool).output();
-
-            match status {
-                Ok(output) if output.status.success() => Ok(()),
-                _ => bail!(
-                    "Required tool '{}' is not installed or not in PATH.\n\
-                     Please install it before flashing.",
-                    tool
-                ),
+        let mut tools: Vec<&str> = Vec::new();
+        if profile.ssh.is_some() {
+            tools.push("ssh");
+        } else if let Some(tool) = &profile.tool {
+            tools.push(tool);
+        }
+        tools.extend(profile.host_tools.iter().map(String::as_str));
+
+        let missing: Vec<&str> = tools
+            .iter()
+            .copied()
+            .filter(|tool| find_in_path(tool).is_none())
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let mut message = format!("Missing host tools for flash method '{}':\n", profile.name);
+        for tool in &tools {
+            if missing.contains(tool) {
+                let _ = writeln!(message, "  ✗ {tool}: not found in PATH");
+            } else {
+                let _ = writeln!(message, "  ✓ {tool}");
             }
-        } else {
-            Ok(())
         }
+        message.push_str(
+            "Please install the missing tools before flashing. Run 'zigroot doctor' for details.",
+        );
+        bail!(message)
     }
 
     /// Check that required external artifacts are available
@@ -252,11 +493,9 @@ impl FlashExecutor {
     }
 
     /// Require user confirmation before flashing
-    fn require_confirmation(&self, profile: &FlashProfile, device: Option<&str>) -> Result<()> {
-        let device_str = device.unwrap_or("default device");
-
+    fn require_confirmation(&self, profile: &FlashProfile, target: &str) -> Result<()> {
         eprintln!();
-        eprintln!("⚠️  WARNING: This will flash to {}!", device_str);
+        eprintln!("⚠️  WARNING: This will flash to {}!", target);
         eprintln!("   Method: {} - {}", profile.name, profile.description);
         eprintln!();
         eprintln!("   This operation may cause data loss!");
@@ -320,13 +559,17 @@ impl FlashExecutor {
         }
 
         // Execute based on profile type
-        if let Some(script) = &profile.script {
+        if let Some(ssh) = &profile.ssh {
+            self.execute_ssh(ssh, profile, &image_path, options)
+        } else if let Some(template) = &profile.command {
+            self.execute_command(template, profile, &env_vars, options)
+        } else if let Some(script) = &profile.script {
             self.execute_script(script, &env_vars, options)
         } else if let Some(tool) = &profile.tool {
             self.execute_tool(tool, profile, &env_vars, options)
         } else {
             bail!(
-                "Flash profile '{}' has neither script nor tool defined.",
+                "Flash profile '{}' has no command, script or tool defined.",
                 profile.name
             );
         }
@@ -433,6 +676,236 @@ impl FlashExecutor {
         })
     }
 
+    /// Run a profile's command template, streaming its output
+    fn execute_command(
+        &self,
+        template: &str,
+        profile: &FlashProfile,
+        env_vars: &HashMap<String, String>,
+        options: &FlashOptions,
+    ) -> Result<FlashResult> {
+        let args = self.command_args(profile, template, options)?;
+        let Some((program, args)) = args.split_first() else {
+            bail!("Flash method '{}' has an empty command.", profile.name);
+        };
+
+        let status = Command::new(program)
+            .args(args)
+            .current_dir(&self.project_root)
+            .envs(env_vars)
+            .status()
+            .with_context(|| format!("Failed to execute flash tool: {program}"))?;
+
+        let success = status.success();
+        let message = if success {
+            format!("Flash completed successfully using {}.", profile.name)
+        } else {
+            format!("Flash failed: {program} exited with {status}.")
+        };
+
+        Ok(FlashResult {
+            method: profile.name.clone(),
+            device: options.device.clone(),
+            success,
+            message,
+        })
+    }
+
+    /// Expand a profile's command template into program arguments
+    fn command_args(
+        &self,
+        profile: &FlashProfile,
+        template: &str,
+        options: &FlashOptions,
+    ) -> Result<Vec<String>> {
+        expand_command(template, |placeholder| {
+            self.resolve_placeholder(placeholder, options).map_err(|e| {
+                anyhow!(
+                    "Cannot resolve placeholder {{{placeholder}}} in flash method '{}': {e}",
+                    profile.name
+                )
+            })
+        })
+    }
+
+    /// Value of a command template placeholder
+    fn resolve_placeholder(&self, placeholder: &str, options: &FlashOptions) -> Result<String> {
+        let path = match placeholder.split_once(':') {
+            None => match placeholder {
+                "image" => self.get_image_path()?,
+                "rootfs" => self.rootfs_image_path(),
+                "device" => {
+                    return options
+                        .device
+                        .clone()
+                        .ok_or_else(|| anyhow!("no device given. Use --device <path>"))
+                }
+                "project" => self.project_root.clone(),
+                "board" => {
+                    return self
+                        .board
+                        .as_ref()
+                        .map(|b| b.board.name.clone())
+                        .ok_or_else(|| anyhow!("no board is configured"))
+                }
+                _ => bail!("unknown placeholder"),
+            },
+            Some(("partition", name)) => self.partition_image_path(name)?,
+            Some(("artifact", name)) => {
+                let artifact = self.manifest.external.get(name).ok_or_else(|| {
+                    anyhow!("artifact '{name}' is not configured in zigroot.toml")
+                })?;
+                match &artifact.path {
+                    Some(path) => self.project_root.join(path),
+                    None => self.project_root.join("external").join(name),
+                }
+            }
+            Some(_) => bail!("unknown placeholder"),
+        };
+        Ok(path.to_string_lossy().into_owned())
+    }
+
+    /// Path of the built rootfs image
+    fn rootfs_image_path(&self) -> PathBuf {
+        self.output_layout()
+            .artifact(&self.manifest.build.image_file_name())
+    }
+
+    /// Image file of a partition in the disk layout
+    ///
+    /// Partitions filled from a directory have no image of their own.
+    fn partition_image_path(&self, name: &str) -> Result<PathBuf> {
+        let (_, specs) = disk_config(&self.manifest, self.board.as_ref())
+            .ok_or_else(|| anyhow!("no partition layout is configured"))?;
+        let spec = specs
+            .iter()
+            .find(|spec| spec.name == name)
+            .ok_or_else(|| anyhow!("there is no partition named '{name}'"))?;
+        let content = spec
+            .content
+            .as_deref()
+            .ok_or_else(|| anyhow!("partition '{name}' has no content"))?;
+        let path = content_path(&self.project_root, &self.manifest, content)
+            .unwrap_or_else(|| self.rootfs_image_path());
+        if !path.is_file() {
+            bail!(
+                "partition '{name}' content {} is not an image file",
+                path.display()
+            );
+        }
+        Ok(path)
+    }
+
+    /// Flash over SSH: run the pre commands, stream the image, verify it
+    /// and run the post commands
+    fn execute_ssh(
+        &self,
+        ssh: &SshFlashConfig,
+        profile: &FlashProfile,
+        image_path: &Path,
+        options: &FlashOptions,
+    ) -> Result<FlashResult> {
+        let target = ssh.target(options.device.as_deref())?;
+        let destination = ssh.destination();
+
+        for command in &ssh.pre_commands {
+            run_remote(ssh, command)?;
+        }
+
+        let (size, local_sha256) = self.stream_image(ssh, image_path, &target)?;
+
+        let output = run_remote(ssh, &remote_checksum_command(&target, size))?;
+        let remote_sha256 = output.split_whitespace().next().unwrap_or_default();
+        if remote_sha256 != local_sha256 {
+            return Ok(FlashResult {
+                method: profile.name.clone(),
+                device: Some(format!("{destination}:{target}")),
+                success: false,
+                message: format!(
+                    "Checksum mismatch after writing {target} on {destination}.\n\
+                     Expected: {local_sha256}\n\
+                     Actual:   {remote_sha256}"
+                ),
+            });
+        }
+
+        let mut message = format!(
+            "Flashed {} to {target} on {destination} ({size} bytes).\nVerified sha256: {local_sha256}",
+            image_path.display()
+        );
+        // Post commands such as `reboot` may drop the connection, so their
+        // failures do not fail the flash
+        for command in &ssh.post_commands {
+            if let Err(e) = run_remote(ssh, command) {
+                let _ = write!(message, "\nWarning: '{command}' did not complete: {e}");
+            }
+        }
+
+        Ok(FlashResult {
+            method: profile.name.clone(),
+            device: Some(format!("{destination}:{target}")),
+            success: true,
+            message,
+        })
+    }
+
+    /// Stream the image into `target` on the device
+    ///
+    /// Returns the image size and its SHA256, computed while sending.
+    fn stream_image(
+        &self,
+        ssh: &SshFlashConfig,
+        image_path: &Path,
+        target: &str,
+    ) -> Result<(u64, String)> {
+        let mut image = std::fs::File::open(image_path)
+            .with_context(|| format!("Failed to open image: {}", image_path.display()))?;
+        let total = image.metadata()?.len();
+
+        let mut child = Command::new("ssh")
+            .args(ssh.ssh_args(&remote_write_command(target)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run ssh")?;
+
+        let mut stdin = child.stdin.take().context("Failed to open ssh input")?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0; 1024 * 1024];
+        let mut sent = 0;
+        let mut interrupted = false;
+        loop {
+            let read = image.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            // A closed pipe means ssh exited; its status explains why
+            if stdin.write_all(&buffer[..read]).is_err() {
+                interrupted = true;
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            sent += read as u64;
+            if let Some(progress) = &self.progress {
+                progress(sent, total);
+            }
+        }
+        drop(stdin);
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(ssh_error(ssh, output.status, &output.stderr));
+        }
+        if interrupted {
+            bail!(
+                "Transfer to {} was interrupted after {sent} of {total} bytes",
+                ssh.destination()
+            );
+        }
+        Ok((total, hex::encode(hasher.finalize())))
+    }
+
     /// Get the path to a flash script
     fn get_script_path(&self, script: &str) -> Result<PathBuf> {
         // Check in board directory first
@@ -472,6 +945,122 @@ impl FlashExecutor {
     }
 }
 
+/// Check that a file given with `--image` looks like a flashable image
+///
+/// Partition tables, ext4 and squashfs filesystems, (compressed) initramfs
+/// archives and FIT images are recognized. Returns the detected format.
+pub fn check_image_file(path: &Path) -> Result<&'static str> {
+    let metadata =
+        std::fs::metadata(path).with_context(|| format!("Image not found: {}", path.display()))?;
+    if !metadata.is_file() {
+        bail!("Image {} is not a file", path.display());
+    }
+    let mut header = Vec::new();
+    std::fs::File::open(path)
+        .and_then(|file| file.take(IMAGE_HEADER_SIZE).read_to_end(&mut header))
+        .with_context(|| format!("Failed to read image: {}", path.display()))?;
+    IMAGE_SIGNATURES
+        .iter()
+        .find(|(_, offset, magic)| header.get(*offset..offset + magic.len()) == Some(*magic))
+        .map(|(format, _, _)| *format)
+        .ok_or_else(|| {
+            anyhow!(
+                "{} does not look like a disk image.\n\
+                 Expected a partition table, an ext4 or squashfs filesystem, \
+                 an initramfs archive or a FIT image.",
+                path.display()
+            )
+        })
+}
+
+/// Expand a command template into arguments
+///
+/// The template is split on whitespace and every `{placeholder}` is
+/// replaced with the value from `resolve`, so values containing spaces stay
+/// a single argument.
+fn expand_command(template: &str, resolve: impl Fn(&str) -> Result<String>) -> Result<Vec<String>> {
+    template
+        .split_whitespace()
+        .map(|word| {
+            let mut arg = String::new();
+            let mut rest = word;
+            while let Some(start) = rest.find('{') {
+                arg.push_str(&rest[..start]);
+                let Some(end) = rest[start..].find('}') else {
+                    bail!("Unclosed placeholder in flash command: {word}");
+                };
+                arg.push_str(&resolve(&rest[start + 1..start + end])?);
+                rest = &rest[start + end + 1..];
+            }
+            arg.push_str(rest);
+            Ok(arg)
+        })
+        .collect()
+}
+
+/// Find an executable in PATH
+///
+/// Paths containing a slash are checked directly.
+pub fn find_in_path(program: &str) -> Option<PathBuf> {
+    let is_executable = |path: &Path| {
+        path.metadata().is_ok_and(|m| {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                m.is_file() && m.permissions().mode() & 0o111 != 0
+            }
+            #[cfg(not(unix))]
+            {
+                m.is_file()
+            }
+        })
+    };
+    if program.contains('/') {
+        let path = PathBuf::from(program);
+        return is_executable(&path).then_some(path);
+    }
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|path| is_executable(path))
+}
+
+/// Run a command on the device, returning its standard output
+fn run_remote(ssh: &SshFlashConfig, command: &str) -> Result<String> {
+    let output = Command::new("ssh")
+        .args(ssh.ssh_args(command))
+        .stdin(Stdio::null())
+        .output()
+        .context("Failed to run ssh")?;
+    if !output.status.success() {
+        return Err(ssh_error(ssh, output.status, &output.stderr)
+            .context(format!("Remote command '{command}' failed")));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Error for a failed `ssh` invocation
+///
+/// `ssh` exits with 255 when the connection or authentication fails, any
+/// other status comes from the remote command.
+fn ssh_error(ssh: &SshFlashConfig, status: ExitStatus, stderr: &[u8]) -> anyhow::Error {
+    let stderr = String::from_utf8_lossy(stderr);
+    let destination = ssh.destination();
+    if status.code() == Some(255) {
+        anyhow!(
+            "Could not connect to {destination}: {}\n\
+             Check that {} is reachable and that your SSH key is authorized on it \
+             (e.g. 'ssh-copy-id {destination}'). Password prompts are not supported.",
+            stderr.trim(),
+            ssh.host
+        )
+    } else {
+        anyhow!(
+            "Command on {destination} exited with {status}: {}",
+            stderr.trim()
+        )
+    }
+}
+
 /// Load board definition from project
 pub fn load_board_definition(project_root: &Path, board_name: &str) -> Result<BoardDefinition> {
     // Check local boards directory first
@@ -535,4 +1124,202 @@ mod tests {
         assert!(options.yes);
         assert!(!options.list);
     }
+
+    fn ssh_config() -> SshFlashConfig {
+        SshFlashConfig {
+            host: "device.local".to_string(),
+            user: Some("root".to_string()),
+            port: Some(2222),
+            target_path: Some("/data/rootfs.img".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_ssh_args_and_target() {
+        let ssh = ssh_config();
+        assert_eq!(ssh.destination(), "root@device.local");
+        assert_eq!(
+            ssh.ssh_args("uptime")[4..],
+            ["-p", "2222", "root@device.local", "uptime"]
+        );
+
+        assert_eq!(ssh.target(None).unwrap(), "/data/rootfs.img");
+        assert_eq!(ssh.target(Some("/dev/mmcblk0")).unwrap(), "/dev/mmcblk0");
+        let ssh = SshFlashConfig {
+            target_device: Some("/dev/mmcblk0p2".to_string()),
+            ..ssh
+        };
+        assert_eq!(ssh.target(None).unwrap(), "/dev/mmcblk0p2");
+        assert!(SshFlashConfig::default().target(None).is_err());
+    }
+
+    #[test]
+    fn test_remote_commands_quote_target() {
+        assert_eq!(
+            remote_write_command("/tmp/it's.img"),
+            "cat > '/tmp/it'\\''s.img' && sync"
+        );
+        assert_eq!(
+            remote_checksum_command("/dev/sda", 42),
+            "head -c 42 '/dev/sda' | sha256sum"
+        );
+    }
+
+    #[test]
+    fn test_manifest_ssh_overrides_board_profile() {
+        let mut manifest = Manifest::from_toml(
+            r#"
+[project]
+name = "demo"
+
+[flash.ssh]
+host = "device.local"
+target_path = "/data/rootfs.img"
+"#,
+        )
+        .unwrap();
+        let executor = FlashExecutor::new(Path::new("."), manifest.clone(), None);
+        let profiles = executor.get_flash_profiles();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].name, SSH_METHOD);
+        assert_eq!(profiles[0].ssh, manifest.flash.as_ref().unwrap().ssh);
+
+        let board = BoardDefinition::from_toml(
+            r#"
+[board]
+name = "demo-board"
+description = "Demo"
+target = "arm-linux-musleabihf"
+cpu = "cortex-a7"
+
+[defaults]
+image_format = "ext4"
+rootfs_size = "64M"
+hostname = "demo"
+
+[[flash]]
+name = "ssh"
+description = "Board SSH profile"
+
+[flash.ssh]
+host = "board.local"
+"#,
+        )
+        .unwrap();
+        manifest.flash = None;
+        let executor = FlashExecutor::new(Path::new("."), manifest.clone(), Some(board.clone()));
+        assert_eq!(
+            executor.get_flash_profiles()[0].ssh.as_ref().unwrap().host,
+            "board.local"
+        );
+
+        manifest.flash = Some(FlashConfig {
+            ssh: Some(ssh_config()),
+        });
+        let executor = FlashExecutor::new(Path::new("."), manifest, Some(board));
+        let profiles = executor.get_flash_profiles();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].description, "Board SSH profile");
+        assert_eq!(profiles[0].ssh.as_ref().unwrap().host, "device.local");
+    }
+
+    #[test]
+    fn test_expand_command_substitutes_placeholders() {
+        let resolve = |placeholder: &str| match placeholder {
+            "image" => Ok("/out/my image.img".to_string()),
+            "partition:boot" => Ok("/out/boot.vfat".to_string()),
+            other => bail!("unknown {other}"),
+        };
+        assert_eq!(
+            expand_command("tool wl 0x40 {image} --boot={partition:boot}", resolve).unwrap(),
+            [
+                "tool",
+                "wl",
+                "0x40",
+                "/out/my image.img",
+                "--boot=/out/boot.vfat"
+            ]
+        );
+        assert!(expand_command("tool {device}", resolve)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown device"));
+        assert!(expand_command("tool {image", resolve).is_err());
+    }
+
+    #[test]
+    fn test_unresolved_placeholder_names_it() {
+        let manifest = Manifest::from_toml("[project]\nname = \"demo\"\n").unwrap();
+        let executor = FlashExecutor::new(Path::new("."), manifest, None);
+        let profile = FlashProfile {
+            name: "vendor".to_string(),
+            description: "Vendor tool".to_string(),
+            script: None,
+            tool: None,
+            requires: Vec::new(),
+            ssh: None,
+            command: Some("flasher {device} {partition:boot}".to_string()),
+            host_tools: Vec::new(),
+        };
+        let options = FlashOptions {
+            method: Some("vendor".to_string()),
+            device: None,
+            yes: true,
+            list: false,
+        };
+
+        let error = executor
+            .command_args(&profile, "flasher {device}", &options)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("{device}"), "{error}");
+        assert!(error.contains("--device"), "{error}");
+
+        let error = executor
+            .command_args(&profile, "flasher {partition:boot}", &options)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("{partition:boot}"), "{error}");
+
+        let error = executor
+            .command_args(&profile, "flasher {bogus}", &options)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("{bogus}"), "{error}");
+    }
+
+    #[test]
+    fn test_check_image_file_recognizes_formats() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let write = |name: &str, data: &[u8]| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, data).unwrap();
+            path
+        };
+
+        let mut disk = vec![0u8; 512];
+        disk[510..].copy_from_slice(&[0x55, 0xAA]);
+        assert_eq!(
+            check_image_file(&write("sdcard.img", &disk)).unwrap(),
+            "disk image"
+        );
+        let mut ext4 = vec![0u8; 2048];
+        ext4[1080..1082].copy_from_slice(&[0x53, 0xEF]);
+        assert_eq!(
+            check_image_file(&write("rootfs.ext4", &ext4)).unwrap(),
+            "ext4 filesystem"
+        );
+        assert_eq!(
+            check_image_file(&write("rootfs.squashfs", b"hsqs\0\0")).unwrap(),
+            "squashfs filesystem"
+        );
+
+        let error = check_image_file(&write("notes.txt", b"not an image"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("does not look like a disk image"), "{error}");
+        assert!(check_image_file(&dir.path().join("missing.img")).is_err());
+        assert!(check_image_file(dir.path()).is_err());
+    }
 }
@@ -1,15 +1,17 @@
 //! Global configuration management
 //!
 //! Reads and manages global settings from `config.toml` in the config directory.
-//! Global settings include registry URLs, cache TTL, default build options,
-//! update check settings, and output preferences.
+//! Global settings include registry URLs, cache location and TTL, download
+//! concurrency, network proxy and CA settings, default build options, update
+//! check settings, and output preferences.
 //!
 //! **Validates: Requirements 32.5, 32.6**
 
 use crate::infra::dirs::ZigrootDirs;
+use crate::infra::http::NetworkSettings;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 /// Global configuration error types
@@ -22,8 +24,46 @@ pub enum GlobalConfigError {
     /// Failed to parse config file
     #[error("Failed to parse config file '{path}': {error}")]
     ParseError { path: String, error: String },
+
+    /// Unknown setting key
+    #[error("Unknown config key '{key}'. Valid keys: {}", SETTING_KEYS.join(", "))]
+    UnknownKey { key: String },
+
+    /// Value has the wrong type for the setting
+    #[error("Invalid value '{value}' for '{key}': expected {expected}")]
+    InvalidValue {
+        key: String,
+        value: String,
+        expected: String,
+    },
 }
 
+/// Keys accepted by `zigroot config get` and `zigroot config set`
+pub const SETTING_KEYS: &[&str] = &[
+    "registry.packages_url",
+    "registry.boards_url",
+    "registry.public_key",
+    "cache.dir",
+    "cache.ttl",
+    "cache.build_variants",
+    "download.concurrency",
+    "download.source_mirror_prefix",
+    "fetch.limit_rate",
+    "network.proxy",
+    "network.ca_bundle",
+    "network.timeout_secs",
+    "build.compress",
+    "build.jobs",
+    "build.sandbox",
+    "build.stats",
+    "build.signing_key",
+    "output.color",
+    "output.quiet",
+    "output.json",
+    "update.check_enabled",
+    "update.check_interval",
+];
+
 /// Global configuration for zigroot
 ///
 /// Contains all global settings that apply across projects.
@@ -37,6 +77,18 @@ pub struct GlobalConfig {
     #[serde(default)]
     pub cache: CacheConfig,
 
+    /// Download settings
+    #[serde(default)]
+    pub download: DownloadConfig,
+
+    /// Fetch settings
+    #[serde(default)]
+    pub fetch: FetchConfig,
+
+    /// Proxy, CA and timeout settings for all network operations
+    #[serde(default)]
+    pub network: NetworkConfig,
+
     /// Default build options
     #[serde(default)]
     pub build: BuildConfig,
@@ -58,13 +110,52 @@ pub struct RegistryConfig {
 
     /// Custom boards registry URL
     pub boards_url: Option<String>,
+
+    /// Public key file that `zigroot verify` checks definition signatures with
+    pub public_key: Option<String>,
 }
 
 /// Cache configuration
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CacheConfig {
+    /// Cache directory override
+    pub dir: Option<String>,
+
     /// Cache TTL in seconds
     pub ttl: Option<u64>,
+
+    /// Configurations of each package kept in the build directory
+    pub build_variants: Option<usize>,
+}
+
+/// Download settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DownloadConfig {
+    /// Number of parallel downloads
+    pub concurrency: Option<usize>,
+
+    /// Mirror tried with a source's file name after its declared mirrors
+    pub source_mirror_prefix: Option<String>,
+}
+
+/// Fetch settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FetchConfig {
+    /// Limit of the combined download rate in bytes per second
+    pub limit_rate: Option<u64>,
+}
+
+/// Network settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Proxy URL for all requests (overrides `HTTP_PROXY`/`HTTPS_PROXY`)
+    pub proxy: Option<String>,
+
+    /// PEM file with additional trusted CA certificates
+    pub ca_bundle: Option<String>,
+
+    /// Connect and read timeout in seconds
+    pub timeout_secs: Option<u64>,
 }
 
 /// Default build options
@@ -78,6 +169,13 @@ pub struct BuildConfig {
 
     /// Enable sandbox by default
     pub sandbox: Option<bool>,
+
+    /// Record local build statistics (default: true)
+    pub stats: Option<bool>,
+
+    /// Private key signing every built image (PEM or base64 Ed25519)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_key: Option<String>,
 }
 
 /// Output preferences
@@ -230,14 +328,206 @@ impl GlobalConfig {
             .unwrap_or(crate::config::defaults::REGISTRY_CACHE_TTL)
     }
 
-    /// Get the effective number of build jobs
+    /// Get the effective number of configurations kept per package
+    ///
+    /// Returns the custom value if set, otherwise returns the default.
+    #[must_use]
+    pub fn build_variants(&self) -> usize {
+        self.cache
+            .build_variants
+            .unwrap_or(crate::config::defaults::DEFAULT_BUILD_VARIANTS)
+    }
+
+    /// Whether build statistics are recorded
+    #[must_use]
+    pub fn stats_enabled(&self) -> bool {
+        self.build.stats.unwrap_or(true)
+    }
+
+    /// Get the effective download concurrency
     ///
     /// Returns the custom value if set, otherwise returns the default.
     #[must_use]
+    pub fn download_concurrency(&self) -> usize {
+        self.download
+            .concurrency
+            .unwrap_or(crate::config::defaults::DEFAULT_PARALLEL_DOWNLOADS)
+    }
+
+    /// Network settings for the HTTP clients
+    #[must_use]
+    pub fn network_settings(&self) -> NetworkSettings {
+        NetworkSettings {
+            proxy: self.network.proxy.clone(),
+            ca_bundle: self.network.ca_bundle.as_ref().map(PathBuf::from),
+            timeout_secs: self.network.timeout_secs,
+        }
+    }
+
+    /// Get the raw value of a setting as a string
+    ///
+    /// Returns `None` if the setting is not set in the config file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GlobalConfigError::UnknownKey` for keys not in [`SETTING_KEYS`].
+    pub fn get(&self, key: &str) -> Result<Option<String>, GlobalConfigError> {
+        let value = match key {
+            "registry.packages_url" => self.registry.packages_url.clone(),
+            "registry.boards_url" => self.registry.boards_url.clone(),
+            "registry.public_key" => self.registry.public_key.clone(),
+            "cache.dir" => self.cache.dir.clone(),
+            "cache.ttl" => self.cache.ttl.map(|v| v.to_string()),
+            "cache.build_variants" => self.cache.build_variants.map(|v| v.to_string()),
+            "download.concurrency" => self.download.concurrency.map(|v| v.to_string()),
+            "download.source_mirror_prefix" => self.download.source_mirror_prefix.clone(),
+            "fetch.limit_rate" => self.fetch.limit_rate.map(|v| v.to_string()),
+            "network.proxy" => self.network.proxy.clone(),
+            "network.ca_bundle" => self.network.ca_bundle.clone(),
+            "network.timeout_secs" => self.network.timeout_secs.map(|v| v.to_string()),
+            "build.compress" => self.build.compress.map(|v| v.to_string()),
+            "build.jobs" => self.build.jobs.map(|v| v.to_string()),
+            "build.sandbox" => self.build.sandbox.map(|v| v.to_string()),
+            "build.stats" => self.build.stats.map(|v| v.to_string()),
+            "build.signing_key" => self.build.signing_key.clone(),
+            "output.color" => self.output.color.map(|v| v.to_string()),
+            "output.quiet" => self.output.quiet.map(|v| v.to_string()),
+            "output.json" => self.output.json.map(|v| v.to_string()),
+            "update.check_enabled" => self.update.check_enabled.map(|v| v.to_string()),
+            "update.check_interval" => self.update.check_interval.map(|v| v.to_string()),
+            _ => {
+                return Err(GlobalConfigError::UnknownKey {
+                    key: key.to_string(),
+                })
+            }
+        };
+        Ok(value)
+    }
+
+    /// Set a setting from its string form, validating the value type
+    ///
+    /// # Errors
+    ///
+    /// Returns `GlobalConfigError::UnknownKey` for unknown keys and
+    /// `GlobalConfigError::InvalidValue` if the value has the wrong type.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), GlobalConfigError> {
+        match key {
+            "registry.packages_url" => self.registry.packages_url = Some(parse_url(key, value)?),
+            "registry.boards_url" => self.registry.boards_url = Some(parse_url(key, value)?),
+            "registry.public_key" => {
+                self.registry.public_key = Some(parse_path(key, value, "a key file path")?);
+            }
+            "cache.dir" => self.cache.dir = Some(parse_path(key, value, "a directory path")?),
+            "cache.ttl" => self.cache.ttl = Some(parse_value(key, value, "a number of seconds")?),
+            "cache.build_variants" => self.cache.build_variants = Some(parse_positive(key, value)?),
+            "download.concurrency" => {
+                self.download.concurrency = Some(parse_positive(key, value)?);
+            }
+            "download.source_mirror_prefix" => {
+                self.download.source_mirror_prefix = Some(parse_url(key, value)?);
+            }
+            "fetch.limit_rate" => self.fetch.limit_rate = Some(parse_rate(key, value)?),
+            "network.proxy" => self.network.proxy = Some(parse_url(key, value)?),
+            "network.ca_bundle" => {
+                self.network.ca_bundle = Some(parse_path(key, value, "a PEM file path")?);
+            }
+            "network.timeout_secs" => {
+                self.network.timeout_secs = Some(parse_positive(key, value)?);
+            }
+            "build.compress" => self.build.compress = Some(parse_bool(key, value)?),
+            "build.jobs" => self.build.jobs = Some(parse_positive(key, value)?),
+            "build.sandbox" => self.build.sandbox = Some(parse_bool(key, value)?),
+            "build.stats" => self.build.stats = Some(parse_bool(key, value)?),
+            "build.signing_key" => {
+                self.build.signing_key = Some(parse_path(key, value, "a key file path")?);
+            }
+            "output.color" => self.output.color = Some(parse_bool(key, value)?),
+            "output.quiet" => self.output.quiet = Some(parse_bool(key, value)?),
+            "output.json" => self.output.json = Some(parse_bool(key, value)?),
+            "update.check_enabled" => self.update.check_enabled = Some(parse_bool(key, value)?),
+            "update.check_interval" => {
+                self.update.check_interval = Some(parse_value(key, value, "a number of seconds")?);
+            }
+            _ => {
+                return Err(GlobalConfigError::UnknownKey {
+                    key: key.to_string(),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    /// List all settings with their raw values, in [`SETTING_KEYS`] order
+    #[must_use]
+    pub fn entries(&self) -> Vec<(&'static str, Option<String>)> {
+        SETTING_KEYS
+            .iter()
+            .map(|key| (*key, self.get(key).unwrap_or_default()))
+            .collect()
+    }
+
+    /// Get the effective number of build jobs
+    ///
+    /// Returns the custom value if set, otherwise the number of CPUs.
+    #[must_use]
     pub fn build_jobs(&self) -> usize {
-        self.build
-            .jobs
-            .unwrap_or(crate::config::defaults::DEFAULT_BUILD_JOBS)
+        crate::core::build_env::resolve_jobs(self.build.jobs)
+    }
+}
+
+fn invalid_value(key: &str, value: &str, expected: &str) -> GlobalConfigError {
+    GlobalConfigError::InvalidValue {
+        key: key.to_string(),
+        value: value.to_string(),
+        expected: expected.to_string(),
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(
+    key: &str,
+    value: &str,
+    expected: &str,
+) -> Result<T, GlobalConfigError> {
+    value
+        .parse()
+        .map_err(|_| invalid_value(key, value, expected))
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, GlobalConfigError> {
+    parse_value(key, value, "true or false")
+}
+
+fn parse_positive<T: std::str::FromStr + PartialOrd + Default>(
+    key: &str,
+    value: &str,
+) -> Result<T, GlobalConfigError> {
+    match value.parse::<T>() {
+        Ok(n) if n > T::default() => Ok(n),
+        _ => Err(invalid_value(key, value, "a positive integer")),
+    }
+}
+
+/// A positive byte rate such as `500K` or `2M` (per second)
+fn parse_rate(key: &str, value: &str) -> Result<u64, GlobalConfigError> {
+    match crate::core::size::parse_size(value) {
+        Ok(rate) if rate > 0 => Ok(rate),
+        _ => Err(invalid_value(key, value, "a byte rate such as 500K or 2M")),
+    }
+}
+
+fn parse_url(key: &str, value: &str) -> Result<String, GlobalConfigError> {
+    if value.starts_with("https://") || value.starts_with("http://") {
+        Ok(value.to_string())
+    } else {
+        Err(invalid_value(key, value, "an http(s) URL"))
+    }
+}
+
+fn parse_path(key: &str, value: &str, expected: &str) -> Result<String, GlobalConfigError> {
+    if value.trim().is_empty() {
+        Err(invalid_value(key, value, expected))
+    } else {
+        Ok(value.to_string())
     }
 }
 
@@ -307,12 +597,31 @@ ttl = 3600
             registry: RegistryConfig {
                 packages_url: Some("https://test.com/packages".to_string()),
                 boards_url: Some("https://test.com/boards".to_string()),
+                public_key: None,
+            },
+            cache: CacheConfig {
+                dir: Some("/tmp/zigroot-cache".to_string()),
+                ttl: Some(7200),
+                build_variants: None,
+            },
+            download: DownloadConfig {
+                concurrency: Some(6),
+                source_mirror_prefix: Some("https://sources.example.com".to_string()),
+            },
+            fetch: FetchConfig {
+                limit_rate: Some(1 << 20),
+            },
+            network: NetworkConfig {
+                proxy: Some("http://proxy.example.com:3128".to_string()),
+                ca_bundle: Some("/etc/ssl/corp-ca.pem".to_string()),
+                timeout_secs: Some(60),
             },
-            cache: CacheConfig { ttl: Some(7200) },
             build: BuildConfig {
                 compress: Some(true),
                 jobs: Some(8),
                 sandbox: Some(false),
+                stats: Some(false),
+                signing_key: None,
             },
             output: OutputConfig {
                 color: Some(true),
@@ -330,14 +639,77 @@ ttl = 3600
 
         assert_eq!(loaded.registry.packages_url, config.registry.packages_url);
         assert_eq!(loaded.registry.boards_url, config.registry.boards_url);
+        assert_eq!(loaded.cache.dir, config.cache.dir);
         assert_eq!(loaded.cache.ttl, config.cache.ttl);
+        assert_eq!(loaded.network_settings(), config.network_settings());
+        assert_eq!(loaded.download.concurrency, config.download.concurrency);
+        assert_eq!(loaded.fetch.limit_rate, config.fetch.limit_rate);
         assert_eq!(loaded.build.compress, config.build.compress);
         assert_eq!(loaded.build.jobs, config.build.jobs);
         assert_eq!(loaded.build.sandbox, config.build.sandbox);
+        assert_eq!(loaded.build.stats, config.build.stats);
         assert_eq!(loaded.output.color, config.output.color);
         assert_eq!(loaded.output.quiet, config.output.quiet);
         assert_eq!(loaded.output.json, config.output.json);
         assert_eq!(loaded.update.check_enabled, config.update.check_enabled);
         assert_eq!(loaded.update.check_interval, config.update.check_interval);
     }
+
+    #[test]
+    fn test_set_and_get_validates_types() {
+        let mut config = GlobalConfig::default();
+
+        config.set("download.concurrency", "8").unwrap();
+        config.set("build.compress", "true").unwrap();
+        config
+            .set(
+                "registry.packages_url",
+                "https://mirror.example.com/packages",
+            )
+            .unwrap();
+
+        assert_eq!(
+            config.get("download.concurrency").unwrap().as_deref(),
+            Some("8")
+        );
+        assert_eq!(config.download_concurrency(), 8);
+        assert_eq!(
+            config.get("build.compress").unwrap().as_deref(),
+            Some("true")
+        );
+        assert_eq!(config.get("cache.dir").unwrap(), None);
+
+        assert!(matches!(
+            config.set("download.concurrency", "0"),
+            Err(GlobalConfigError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            config.set("build.compress", "yes"),
+            Err(GlobalConfigError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            config.set("registry.packages_url", "ftp://example.com"),
+            Err(GlobalConfigError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            config.set("network.timeout_secs", "0"),
+            Err(GlobalConfigError::InvalidValue { .. })
+        ));
+        config.set("network.timeout_secs", "90").unwrap();
+        assert_eq!(config.network_settings().timeout_secs, Some(90));
+        assert!(matches!(
+            config.set("no.such_key", "1"),
+            Err(GlobalConfigError::UnknownKey { .. })
+        ));
+    }
+
+    #[test]
+    fn test_entries_cover_all_keys() {
+        let mut config = GlobalConfig::default();
+        config.set("cache.ttl", "60").unwrap();
+
+        let entries = config.entries();
+        assert_eq!(entries.len(), SETTING_KEYS.len());
+        assert!(entries.contains(&("cache.ttl", Some("60".to_string()))));
+    }
 }
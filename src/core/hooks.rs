@@ -0,0 +1,370 @@
+//! Project build event hooks
+//!
+//! The `[hooks]` table of the manifest lists shell commands run at build
+//! events:
+//!
+//! ```toml
+//! [hooks]
+//! post_build = ["./scripts/upload.sh {image_path}"]
+//! on_failure = ["./scripts/notify.sh '{project} {version} failed after {duration}s'"]
+//! timeout = 120
+//! ```
+//!
+//! `pre_build` runs before a build and fails it when a command fails.
+//! `post_build` runs after a successful build and `on_failure` after a
+//! failed one; a failing command there is reported without changing the
+//! outcome of the build. `post_fetch` runs after `zigroot fetch`.
+//!
+//! Commands run through `sh -c` in the project directory. `{name}`
+//! placeholders (see [`HOOK_PLACEHOLDERS`]) are replaced before running;
+//! `${VAR}` shell expansions are left alone.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::core::manifest::Manifest;
+use crate::infra::hook;
+
+/// Placeholders available in hook commands
+pub const HOOK_PLACEHOLDERS: &[&str] = &[
+    "image_path",
+    "board",
+    "project",
+    "version",
+    "duration",
+    "success",
+];
+
+/// Seconds a hook command may run when `timeout` is not set
+pub const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 300;
+
+/// Hook settings in the manifest
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct HooksConfig {
+    /// Commands run before building
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_build: Vec<String>,
+    /// Commands run after a successful build
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_build: Vec<String>,
+    /// Commands run after sources were fetched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_fetch: Vec<String>,
+    /// Commands run after a failed build
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_failure: Vec<String>,
+    /// Seconds each command may run (default 300)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+impl HooksConfig {
+    /// Commands of an event
+    pub fn commands(&self, event: HookEvent) -> &[String] {
+        match event {
+            HookEvent::PreBuild => &self.pre_build,
+            HookEvent::PostBuild => &self.post_build,
+            HookEvent::PostFetch => &self.post_fetch,
+            HookEvent::OnFailure => &self.on_failure,
+        }
+    }
+
+    /// How long each command may run
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout.unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS))
+    }
+}
+
+/// Build event with hooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    /// Before building
+    PreBuild,
+    /// After a successful build
+    PostBuild,
+    /// After fetching sources
+    PostFetch,
+    /// After a failed build
+    OnFailure,
+}
+
+impl HookEvent {
+    /// All events, in manifest order
+    pub const ALL: [HookEvent; 4] = [
+        HookEvent::PreBuild,
+        HookEvent::PostBuild,
+        HookEvent::PostFetch,
+        HookEvent::OnFailure,
+    ];
+
+    /// Manifest key of the event
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookEvent::PreBuild => "pre_build",
+            HookEvent::PostBuild => "post_build",
+            HookEvent::PostFetch => "post_fetch",
+            HookEvent::OnFailure => "on_failure",
+        }
+    }
+}
+
+/// Hook errors
+#[derive(Error, Debug)]
+pub enum HookError {
+    /// The command could not be started
+    #[error("Failed to run {event} hook '{command}': {error}")]
+    Spawn {
+        event: &'static str,
+        command: String,
+        error: String,
+    },
+
+    /// The command failed or timed out
+    #[error("{event} hook '{command}' {reason}")]
+    Failed {
+        event: &'static str,
+        command: String,
+        reason: String,
+    },
+}
+
+/// Values of the placeholders
+#[derive(Debug, Clone, Default)]
+pub struct HookContext {
+    /// Built image, if any
+    pub image_path: Option<PathBuf>,
+    /// Board name
+    pub board: String,
+    /// Project name
+    pub project: String,
+    /// Project version
+    pub version: String,
+    /// How long the build took
+    pub duration: Option<Duration>,
+    /// Whether the build succeeded
+    pub success: bool,
+}
+
+impl HookContext {
+    /// Context of a project before building
+    pub fn new(manifest: &Manifest) -> Self {
+        Self {
+            board: manifest.board.name.clone().unwrap_or_default(),
+            project: manifest.project.name.clone(),
+            version: manifest.project.version.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Value of a placeholder, `None` for unknown names
+    fn value(&self, placeholder: &str) -> Option<String> {
+        Some(match placeholder {
+            "image_path" => self
+                .image_path
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            "board" => self.board.clone(),
+            "project" => self.project.clone(),
+            "version" => self.version.clone(),
+            "duration" => self
+                .duration
+                .map(|duration| format!("{:.1}", duration.as_secs_f64()))
+                .unwrap_or_default(),
+            "success" => self.success.to_string(),
+            _ => return None,
+        })
+    }
+}
+
+/// Placeholders used in a command, in order
+///
+/// A placeholder is `{name}` with a lowercase name; `${VAR}` is not one.
+pub fn placeholders(command: &str) -> Vec<&str> {
+    placeholder_spans(command)
+        .into_iter()
+        .map(|(start, end)| &command[start + 1..end - 1])
+        .collect()
+}
+
+/// Byte ranges of the placeholders of a command, braces included
+fn placeholder_spans(command: &str) -> Vec<(usize, usize)> {
+    let bytes = command.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'{' && (i == 0 || bytes[i - 1] != b'$') {
+            let name_len = bytes[i + 1..]
+                .iter()
+                .take_while(|b| b.is_ascii_lowercase() || **b == b'_')
+                .count();
+            let end = i + 1 + name_len;
+            if name_len > 0 && bytes.get(end) == Some(&b'}') {
+                spans.push((i, end + 1));
+                i = end + 1;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    spans
+}
+
+/// Replace the placeholders of a command with their values
+///
+/// Unknown placeholders are left as they are.
+pub fn expand(command: &str, context: &HookContext) -> String {
+    let mut expanded = String::with_capacity(command.len());
+    let mut copied = 0;
+    for (start, end) in placeholder_spans(command) {
+        if let Some(value) = context.value(&command[start + 1..end - 1]) {
+            expanded.push_str(&command[copied..start]);
+            expanded.push_str(&value);
+            copied = end;
+        }
+    }
+    expanded.push_str(&command[copied..]);
+    expanded
+}
+
+/// Configuration errors of the hooks: unknown placeholders and a zero timeout
+pub fn check_hooks(config: &HooksConfig) -> Vec<String> {
+    let mut errors = Vec::new();
+    for event in HookEvent::ALL {
+        for command in config.commands(event) {
+            for name in placeholders(command) {
+                if !HOOK_PLACEHOLDERS.contains(&name) {
+                    errors.push(format!(
+                        "hooks.{}: unknown placeholder '{{{name}}}' in '{command}' (available: {})",
+                        event.as_str(),
+                        HOOK_PLACEHOLDERS
+                            .iter()
+                            .map(|p| format!("{{{p}}}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ));
+                }
+            }
+        }
+    }
+    if config.timeout == Some(0) {
+        errors.push("hooks.timeout must be greater than 0 seconds".to_string());
+    }
+    errors
+}
+
+/// Run the commands of an event in `project_dir`, stopping at the first
+/// failure
+///
+/// Each output line is passed to `on_line`.
+pub fn run_hooks(
+    project_dir: &Path,
+    config: &HooksConfig,
+    event: HookEvent,
+    context: &HookContext,
+    on_line: &mut dyn FnMut(&str),
+) -> Result<(), HookError> {
+    for command in config.commands(event) {
+        let expanded = expand(command, context);
+        tracing::info!("Running {} hook: {expanded}", event.as_str());
+        let mut process =
+            hook::shell_command(&expanded, project_dir, Vec::<(String, String)>::new(), &[]);
+        let output =
+            hook::run_hook(&mut process, Some(config.timeout()), on_line).map_err(|e| {
+                HookError::Spawn {
+                    event: event.as_str(),
+                    command: expanded.clone(),
+                    error: e.to_string(),
+                }
+            })?;
+        if let Some(reason) = output.failure() {
+            return Err(HookError::Failed {
+                event: event.as_str(),
+                command: expanded,
+                reason,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> HookContext {
+        HookContext {
+            image_path: Some(PathBuf::from("/out/rootfs.img")),
+            board: "rpi4".to_string(),
+            project: "demo".to_string(),
+            version: "1.2.0".to_string(),
+            duration: Some(Duration::from_millis(12_340)),
+            success: true,
+        }
+    }
+
+    #[test]
+    fn test_expand_placeholders() {
+        assert_eq!(
+            expand(
+                "upload {image_path} --tag {project}-{version}@{board} {duration}s {success}",
+                &context()
+            ),
+            "upload /out/rootfs.img --tag demo-1.2.0@rpi4 12.3s true"
+        );
+        // Shell expansions and unknown placeholders are kept
+        assert_eq!(
+            expand("echo ${HOME} {nope} {board", &context()),
+            "echo ${HOME} {nope} {board"
+        );
+    }
+
+    #[test]
+    fn test_check_hooks_reports_unknown_placeholders() {
+        let config = HooksConfig {
+            post_build: vec!["notify {project} {image}".to_string()],
+            on_failure: vec!["echo ${USER} {success}".to_string()],
+            timeout: Some(0),
+            ..HooksConfig::default()
+        };
+        let errors = check_hooks(&config);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("hooks.post_build: unknown placeholder '{image}'"));
+        assert!(errors[1].contains("timeout"));
+    }
+
+    #[test]
+    fn test_run_hooks_stops_at_failure() {
+        let temp = tempfile::tempdir().unwrap();
+        let config = HooksConfig {
+            post_build: vec![
+                "echo {project} > first".to_string(),
+                "exit 2".to_string(),
+                "touch never".to_string(),
+            ],
+            ..HooksConfig::default()
+        };
+        let mut lines = Vec::new();
+        let err = run_hooks(
+            temp.path(),
+            &config,
+            HookEvent::PostBuild,
+            &context(),
+            &mut |line| lines.push(line.to_string()),
+        )
+        .unwrap_err();
+
+        assert!(err
+            .to_string()
+            .starts_with("post_build hook 'exit 2' exited"));
+        assert_eq!(
+            std::fs::read_to_string(temp.path().join("first")).unwrap(),
+            "demo\n"
+        );
+        assert!(!temp.path().join("never").exists());
+    }
+}
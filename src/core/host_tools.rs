@@ -0,0 +1,426 @@
+//! Host tools needed by package builds
+//!
+//! Packages list the programs their build runs on the host in
+//! `host_depends` (e.g. `["cmake>=3.25", "ninja"]`). Before building, each
+//! requirement is checked against the host with the same detection as
+//! `zigroot doctor`; a missing or outdated tool is downloaded as a prebuilt
+//! static binary from the registry's host-tools index into shared storage,
+//! and its directory is put in front of `PATH` for that package's build
+//! only.
+
+use anyhow::{bail, Context, Result};
+use semver::{Version, VersionReq};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::core::doctor::check_command_available;
+use crate::core::flash::find_in_path;
+use crate::infra::download::{Checksum, ChecksumAlgorithm, DownloadManager};
+use crate::registry::client::{HostToolBinary, HostToolIndex, RegistryClient};
+
+/// A host tool requirement from `host_depends`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HostToolRequirement {
+    /// Tool name, which is also the binary name
+    pub name: String,
+    /// Version constraint (e.g. ">=3.25"), if any
+    pub constraint: Option<String>,
+}
+
+impl HostToolRequirement {
+    /// Parse a requirement such as "cmake>=3.25" or "ninja"
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        let constraint_chars = ['>', '<', '=', '^', '~'];
+        let (name, constraint) = if let Some(pos) = spec.find(|c| constraint_chars.contains(&c)) {
+            (spec[..pos].trim(), Some(spec[pos..].trim().to_string()))
+        } else {
+            (spec, None)
+        };
+        if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '/') {
+            bail!("Invalid host tool '{spec}': expected a name with an optional version constraint, like \"cmake>=3.25\"");
+        }
+        if let Some(constraint) = &constraint {
+            VersionReq::parse(constraint).with_context(|| {
+                format!("Invalid version constraint '{constraint}' for host tool '{name}'")
+            })?;
+        }
+        Ok(Self {
+            name: name.to_string(),
+            constraint,
+        })
+    }
+
+    /// Check whether a tool version satisfies the constraint
+    ///
+    /// Tools often report versions like "3.28" or "1.11.1.git"; missing
+    /// components are taken as zero and suffixes are ignored.
+    pub fn matches(&self, version: &str) -> bool {
+        let Some(constraint) = &self.constraint else {
+            return true;
+        };
+        match (lenient_version(version), VersionReq::parse(constraint)) {
+            (Some(version), Ok(req)) => req.matches(&version),
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for HostToolRequirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            self.name,
+            self.constraint.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// Parse a tool version, padding missing components with zeros
+fn lenient_version(version: &str) -> Option<Version> {
+    let numbers: Vec<u64> = version
+        .trim_start_matches('v')
+        .split(['.', '-', '+'])
+        .map_while(|part| part.parse().ok())
+        .take(3)
+        .collect();
+    match numbers.as_slice() {
+        [] => None,
+        [major] => Some(Version::new(*major, 0, 0)),
+        [major, minor] => Some(Version::new(*major, *minor, 0)),
+        [major, minor, patch, ..] => Some(Version::new(*major, *minor, *patch)),
+    }
+}
+
+/// State of a host tool on this machine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostToolStatus {
+    /// Installed and satisfying the constraint
+    Found {
+        /// Reported version, if the tool prints one
+        version: Option<String>,
+    },
+    /// Installed, but the version does not satisfy the constraint
+    Outdated {
+        /// Reported version, if the tool prints one
+        version: Option<String>,
+    },
+    /// Not in `PATH`
+    Missing,
+}
+
+/// Check a requirement against the tools in `PATH`
+pub fn check_host(requirement: &HostToolRequirement) -> HostToolStatus {
+    if find_in_path(&requirement.name).is_none() {
+        return HostToolStatus::Missing;
+    }
+    let version = check_command_available(&requirement.name);
+    let satisfied = requirement.constraint.is_none()
+        || version
+            .as_deref()
+            .is_some_and(|version| requirement.matches(version));
+    if satisfied {
+        HostToolStatus::Found { version }
+    } else {
+        HostToolStatus::Outdated { version }
+    }
+}
+
+/// Platform of this host in the host-tools index (e.g. "x86_64-linux")
+pub fn host_platform() -> String {
+    format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS)
+}
+
+/// Pick the newest version in the index satisfying the requirement with a
+/// binary for `platform`
+pub fn select_binary<'a>(
+    index: &'a HostToolIndex,
+    requirement: &HostToolRequirement,
+    platform: &str,
+) -> Option<(&'a str, &'a HostToolBinary)> {
+    let tool = index
+        .tools
+        .iter()
+        .find(|tool| tool.name == requirement.name)?;
+    tool.versions
+        .iter()
+        .filter(|entry| requirement.matches(&entry.version))
+        .filter_map(|entry| {
+            let binary = entry.binaries.iter().find(|b| b.host == platform)?;
+            Some((lenient_version(&entry.version)?, entry, binary))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, entry, binary)| (entry.version.as_str(), binary))
+}
+
+/// A host tool available to package builds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvisionedTool {
+    /// Tool name
+    pub name: String,
+    /// Version used, or "unknown" for host tools that print none
+    pub version: String,
+    /// Directory to put in front of `PATH`; `None` for the host's own tool
+    pub bin_dir: Option<PathBuf>,
+}
+
+/// Make a host tool available, downloading it into `store` if needed
+///
+/// Binaries already downloaded to `store` are reused without asking the
+/// registry, so builds keep working offline.
+pub async fn provision(
+    requirement: &HostToolRequirement,
+    client: &RegistryClient,
+    store: &Path,
+) -> Result<ProvisionedTool> {
+    let status = check_host(requirement);
+    if let HostToolStatus::Found { version } = status {
+        return Ok(ProvisionedTool {
+            name: requirement.name.clone(),
+            version: version.unwrap_or_else(|| "unknown".to_string()),
+            bin_dir: None,
+        });
+    }
+    if let Some(tool) = stored_tool(requirement, store) {
+        return Ok(tool);
+    }
+
+    let reason = match status {
+        HostToolStatus::Outdated { version: Some(v) } => format!("version {v} is installed"),
+        HostToolStatus::Outdated { version: None } => "its version is unknown".to_string(),
+        _ => "it is not installed".to_string(),
+    };
+    let index = client.fetch_host_tool_index().await.with_context(|| {
+        format!(
+            "Host tool '{requirement}' is needed but {reason}, and the host-tools index could not be fetched. Install {} manually.",
+            requirement.name
+        )
+    })?;
+    let platform = host_platform();
+    let Some((version, binary)) = select_binary(&index, requirement, &platform) else {
+        bail!(
+            "Host tool '{requirement}' is needed but {reason}, and the registry has no {platform} binary for it. Install {} manually.",
+            requirement.name
+        );
+    };
+
+    let bin_dir = store.join(&requirement.name).join(version);
+    let dest = bin_dir.join(&requirement.name);
+    tracing::info!("Downloading host tool {} {version}", requirement.name);
+    let checksum = Checksum::new(ChecksumAlgorithm::Sha256, &binary.sha256)
+        .with_context(|| format!("Invalid checksum for host tool {}", requirement.name))?;
+    DownloadManager::new()
+        .download_verified(&binary.url, &dest, &checksum, None)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to download host tool {} {version}",
+                requirement.name
+            )
+        })?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&dest, std::fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("Failed to make {} executable", dest.display()))?;
+    }
+
+    Ok(ProvisionedTool {
+        name: requirement.name.clone(),
+        version: version.to_string(),
+        bin_dir: Some(bin_dir),
+    })
+}
+
+/// Newest previously downloaded version satisfying the requirement
+pub fn stored_tool(requirement: &HostToolRequirement, store: &Path) -> Option<ProvisionedTool> {
+    std::fs::read_dir(store.join(&requirement.name))
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().join(&requirement.name).is_file())
+        .filter_map(|entry| {
+            let version = entry.file_name().to_string_lossy().to_string();
+            requirement
+                .matches(&version)
+                .then(|| (lenient_version(&version), version, entry.path()))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, version, bin_dir)| ProvisionedTool {
+            name: requirement.name.clone(),
+            version,
+            bin_dir: Some(bin_dir),
+        })
+}
+
+/// Provision the host tools of several packages
+///
+/// Each requirement is provisioned once, however many packages share it.
+/// Returns the tools of each package by package name.
+pub async fn provision_packages(
+    packages: &[(String, Vec<String>)],
+    client: &RegistryClient,
+    store: &Path,
+) -> Result<HashMap<String, Vec<ProvisionedTool>>> {
+    let mut provisioned: HashMap<HostToolRequirement, ProvisionedTool> = HashMap::new();
+    let mut tools = HashMap::new();
+    for (package, specs) in packages {
+        let mut package_tools = Vec::new();
+        for spec in specs {
+            let requirement = HostToolRequirement::parse(spec)
+                .with_context(|| format!("Invalid host_depends of package '{package}'"))?;
+            let tool = if let Some(tool) = provisioned.get(&requirement) {
+                tool.clone()
+            } else {
+                let tool = provision(&requirement, client, store)
+                    .await
+                    .with_context(|| format!("Cannot build package '{package}'"))?;
+                provisioned.insert(requirement, tool.clone());
+                tool
+            };
+            package_tools.push(tool);
+        }
+        tools.insert(package.clone(), package_tools);
+    }
+    Ok(tools)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::client::{HostToolEntry, HostToolVersionEntry};
+    use tempfile::TempDir;
+
+    fn index(platform: &str) -> HostToolIndex {
+        let binary = |version: &str| HostToolBinary {
+            host: platform.to_string(),
+            url: format!("/cmake-{version}"),
+            sha256: String::new(),
+        };
+        HostToolIndex {
+            version: 1,
+            updated: String::new(),
+            tools: vec![HostToolEntry {
+                name: "cmake".to_string(),
+                description: String::new(),
+                versions: ["3.24.4", "3.28.1", "3.30.0"]
+                    .iter()
+                    .map(|version| HostToolVersionEntry {
+                        version: (*version).to_string(),
+                        binaries: if *version == "3.30.0" {
+                            Vec::new()
+                        } else {
+                            vec![binary(version)]
+                        },
+                    })
+                    .collect(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_parse_requirement() {
+        let req = HostToolRequirement::parse("cmake>=3.25").unwrap();
+        assert_eq!(req.name, "cmake");
+        assert_eq!(req.constraint.as_deref(), Some(">=3.25"));
+        assert_eq!(req.to_string(), "cmake>=3.25");
+
+        let req = HostToolRequirement::parse("ninja").unwrap();
+        assert_eq!(req.constraint, None);
+
+        assert!(HostToolRequirement::parse(">=1.0").is_err());
+        assert!(HostToolRequirement::parse("cmake>=three").is_err());
+    }
+
+    #[test]
+    fn test_requirement_matches_short_versions() {
+        let req = HostToolRequirement::parse("cmake>=3.25").unwrap();
+        assert!(req.matches("3.28"));
+        assert!(req.matches("3.25.0-rc1"));
+        assert!(req.matches("v4.0.1"));
+        assert!(!req.matches("3.24.9"));
+        assert!(!req.matches("unknown"));
+        assert!(HostToolRequirement::parse("make")
+            .unwrap()
+            .matches("anything"));
+    }
+
+    #[test]
+    fn test_select_binary_picks_newest_matching_version_for_platform() {
+        let index = index("x86_64-linux");
+        let req = HostToolRequirement::parse("cmake>=3.25").unwrap();
+
+        let (version, binary) = select_binary(&index, &req, "x86_64-linux").unwrap();
+        assert_eq!(version, "3.28.1");
+        assert_eq!(binary.url, "/cmake-3.28.1");
+
+        assert!(select_binary(&index, &req, "aarch64-macos").is_none());
+        let missing = HostToolRequirement::parse("meson").unwrap();
+        assert!(select_binary(&index, &missing, "x86_64-linux").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_provision_downloads_missing_tool_into_store() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let content = b"#!/bin/sh\necho fake 1.0.0\n";
+        let server = MockServer::start().await;
+        let index = serde_json::json!({
+            "version": 1,
+            "updated": "",
+            "tools": [{
+                "name": "zigroot-missing-tool",
+                "versions": [{
+                    "version": "1.0.0",
+                    "binaries": [{
+                        "host": host_platform(),
+                        "url": format!("{}/bin/zigroot-missing-tool", server.uri()),
+                        "sha256": crate::infra::download::compute_checksum(content),
+                    }],
+                }],
+            }],
+        });
+        Mock::given(method("GET"))
+            .and(path("/host-tools/index.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(index))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/bin/zigroot-missing-tool"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(content.to_vec()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let temp = TempDir::new().unwrap();
+        let client =
+            RegistryClient::with_config(server.uri(), server.uri(), temp.path().join("cache"), 0);
+        let store = temp.path().join("host-tools");
+        let packages = vec![
+            (
+                "app".to_string(),
+                vec!["zigroot-missing-tool>=1".to_string()],
+            ),
+            ("lib".to_string(), vec!["zigroot-missing-tool".to_string()]),
+        ];
+
+        let tools = provision_packages(&packages, &client, &store)
+            .await
+            .unwrap();
+        let tool = &tools["app"][0];
+        assert_eq!(tool.version, "1.0.0");
+        let bin_dir = tool.bin_dir.as_ref().unwrap();
+        assert_eq!(bin_dir, &store.join("zigroot-missing-tool/1.0.0"));
+        assert_eq!(
+            std::fs::read(bin_dir.join("zigroot-missing-tool")).unwrap(),
+            content
+        );
+
+        // The stored binary is reused without asking the registry again
+        let req = HostToolRequirement::parse("zigroot-missing-tool").unwrap();
+        let again = provision(&req, &client, &store).await.unwrap();
+        assert_eq!(&again, tool);
+    }
+}
@@ -3,20 +3,35 @@
 //! This module contains the business logic for initializing a new zigroot project.
 //! It handles creating the project structure, manifest, and .gitignore.
 
+use std::collections::BTreeMap;
+use std::fmt::Write;
 use std::path::Path;
 
-use crate::core::manifest::Manifest;
+use serde::Deserialize;
+
+use crate::core::board::{parse_board_spec, VARIANT_OPTION};
+use crate::core::manifest::{
+    is_valid_size_format, Manifest, MANIFEST_VERSION, VALID_IMAGE_FORMATS,
+};
 use crate::error::InitError;
 
 /// Directories that should be created during init
 pub const REQUIRED_DIRECTORIES: &[&str] = &["packages", "boards", "user/files", "user/scripts"];
 
 /// Entries to add to .gitignore
-pub const GITIGNORE_ENTRIES: &[&str] = &["build/", "downloads/", "output/", "external/"];
+pub const GITIGNORE_ENTRIES: &[&str] =
+    &["build/", "downloads/", "output/", "external/", ".zigroot/"];
 
 /// Marker comment for zigroot section in .gitignore
 pub const GITIGNORE_MARKER: &str = "# zigroot";
 
+/// Templates bundled with zigroot, as TOML fragments
+const BUNDLED_TEMPLATES: &[&str] = &[
+    include_str!("templates/minimal.toml"),
+    include_str!("templates/networking.toml"),
+    include_str!("templates/kiosk.toml"),
+];
+
 /// Options for project initialization
 #[derive(Debug, Clone, Default)]
 pub struct InitOptions {
@@ -24,6 +39,216 @@ pub struct InitOptions {
     pub board: Option<String>,
     /// Force initialization in non-empty directory
     pub force: bool,
+    /// Project name (defaults to the directory name)
+    pub name: Option<String>,
+    /// Target hostname
+    pub hostname: Option<String>,
+    /// Image format: ext4, squashfs, or initramfs
+    pub image_format: Option<String>,
+    /// Root filesystem size (e.g., "256M")
+    pub rootfs_size: Option<String>,
+}
+
+/// A project template: packages, build defaults and user files
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProjectTemplate {
+    /// Name and description
+    pub template: TemplateInfo,
+    /// Build settings the template changes
+    #[serde(default)]
+    pub defaults: TemplateDefaults,
+    /// Packages added to the manifest
+    #[serde(default)]
+    pub packages: BTreeMap<String, TemplatePackage>,
+    /// Files created under `user/files/`, by path
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+}
+
+/// Name and description of a template
+#[derive(Debug, Clone, Deserialize)]
+pub struct TemplateInfo {
+    /// Template name used with `--template`
+    pub name: String,
+    /// One-line description
+    pub description: String,
+}
+
+/// Build settings of a template; unset ones keep zigroot's defaults
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TemplateDefaults {
+    /// Target hostname
+    pub hostname: Option<String>,
+    /// Image format
+    pub image_format: Option<String>,
+    /// Root filesystem size
+    pub rootfs_size: Option<String>,
+}
+
+/// A package added by a template
+#[derive(Debug, Clone, Deserialize)]
+pub struct TemplatePackage {
+    /// Package version
+    pub version: String,
+}
+
+/// Settings written into a generated manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestSettings {
+    /// Project name
+    pub name: String,
+    /// Target board name
+    pub board: Option<String>,
+    /// Board variant, from a `<board>:<variant>` selection
+    pub variant: Option<String>,
+    /// Target hostname
+    pub hostname: String,
+    /// Image format
+    pub image_format: String,
+    /// Root filesystem size
+    pub rootfs_size: String,
+}
+
+impl ManifestSettings {
+    /// Settings with zigroot's defaults
+    ///
+    /// `board` may select a variant as `<board>:<variant>`.
+    pub fn new(name: &str, board: Option<&str>) -> Self {
+        let (board, variant) = board.map(parse_board_spec).unzip();
+        Self {
+            name: name.to_string(),
+            board: board.map(String::from),
+            variant: variant.flatten().map(String::from),
+            hostname: "zigroot".to_string(),
+            image_format: "ext4".to_string(),
+            rootfs_size: "256M".to_string(),
+        }
+    }
+}
+
+/// Templates bundled with zigroot
+pub fn templates() -> Vec<ProjectTemplate> {
+    BUNDLED_TEMPLATES
+        .iter()
+        .map(|content| toml::from_str(content).expect("bundled template is valid"))
+        .collect()
+}
+
+/// Find a bundled template by name
+pub fn find_template(name: &str) -> Result<ProjectTemplate, InitError> {
+    let templates = templates();
+    let available = templates
+        .iter()
+        .map(|t| t.template.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    templates
+        .into_iter()
+        .find(|t| t.template.name == name)
+        .ok_or_else(|| InitError::UnknownTemplate {
+            name: name.to_string(),
+            available,
+        })
+}
+
+/// Resolve the manifest settings of a new project
+///
+/// Options win over the template's defaults, which win over zigroot's.
+pub fn manifest_settings(
+    path: &Path,
+    options: &InitOptions,
+    template: Option<&ProjectTemplate>,
+) -> Result<ManifestSettings, InitError> {
+    let name = options
+        .name
+        .clone()
+        .unwrap_or_else(|| derive_project_name(path));
+    let mut settings = ManifestSettings::new(&name, options.board.as_deref());
+    let defaults = template.map(|t| t.defaults.clone()).unwrap_or_default();
+    if let Some(hostname) = options.hostname.clone().or(defaults.hostname) {
+        settings.hostname = hostname;
+    }
+    if let Some(format) = options.image_format.clone().or(defaults.image_format) {
+        settings.image_format = format;
+    }
+    if let Some(size) = options.rootfs_size.clone().or(defaults.rootfs_size) {
+        settings.rootfs_size = size;
+    }
+
+    let invalid = |setting: &str, error: String| InitError::InvalidSetting {
+        setting: setting.to_string(),
+        error,
+    };
+    if settings.name.trim().is_empty() {
+        return Err(invalid("name", "must not be empty".to_string()));
+    }
+    if settings.hostname.is_empty()
+        || !settings
+            .hostname
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return Err(invalid(
+            "hostname",
+            format!(
+                "'{}' may only contain letters, digits and '-'",
+                settings.hostname
+            ),
+        ));
+    }
+    if !VALID_IMAGE_FORMATS.contains(&settings.image_format.as_str()) {
+        return Err(invalid(
+            "image-format",
+            format!(
+                "'{}' must be one of {}",
+                settings.image_format,
+                VALID_IMAGE_FORMATS.join(", ")
+            ),
+        ));
+    }
+    if !is_valid_size_format(&settings.rootfs_size) {
+        return Err(invalid(
+            "rootfs-size",
+            format!(
+                "'{}' must look like '256M', '1G' or 'auto'",
+                settings.rootfs_size
+            ),
+        ));
+    }
+    Ok(settings)
+}
+
+/// Write a template's files under `user/files/`, keeping existing files
+///
+/// Returns the paths written, relative to `user/files/`.
+pub fn write_template_files(
+    path: &Path,
+    template: &ProjectTemplate,
+) -> Result<Vec<String>, InitError> {
+    let files_dir = path.join("user").join("files");
+    let mut written = Vec::new();
+    for (relative, content) in &template.files {
+        let target = files_dir.join(relative);
+        if target.exists() {
+            continue;
+        }
+        let io_error = |e: std::io::Error| InitError::IoError {
+            path: target.clone(),
+            error: e.to_string(),
+        };
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        std::fs::write(&target, content.trim_start_matches('\n')).map_err(io_error)?;
+        #[cfg(unix)]
+        if content.trim_start().starts_with("#!") {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o755))
+                .map_err(io_error)?;
+        }
+        written.push(relative.clone());
+    }
+    Ok(written)
 }
 
 /// Result of initialization
@@ -52,10 +277,33 @@ pub fn is_directory_empty(path: &Path) -> std::io::Result<bool> {
     Ok(entries.is_empty())
 }
 
-/// Generate the default manifest content with comments
-pub fn generate_manifest_content(project_name: &str, board: Option<&str>) -> String {
-    let board_section = if let Some(board_name) = board {
-        format!(
+/// Generate the manifest content with comments
+///
+/// A template's packages replace the commented package examples.
+pub fn generate_manifest_content(
+    settings: &ManifestSettings,
+    template: Option<&ProjectTemplate>,
+) -> String {
+    let ManifestSettings {
+        name: project_name,
+        board,
+        variant,
+        hostname,
+        image_format,
+        rootfs_size,
+    } = settings;
+    let board_section = match (board, variant) {
+        (Some(board_name), Some(variant)) => format!(
+            r#"
+[board]
+name = "{board_name}"
+
+# Board-specific options; `{VARIANT_OPTION}` selects a variant of the board
+[board.options]
+{VARIANT_OPTION} = "{variant}"
+"#
+        ),
+        (Some(board_name), None) => format!(
             r#"
 [board]
 name = "{board_name}"
@@ -63,9 +311,8 @@ name = "{board_name}"
 # [board.options]
 # option_name = "value"
 "#
-        )
-    } else {
-        r#"
+        ),
+        (None, _) => r#"
 [board]
 # Uncomment and set your target board:
 # name = "luckfox-pico"
@@ -73,13 +320,17 @@ name = "{board_name}"
 # [board.options]
 # option_name = "value"
 "#
-        .to_string()
+        .to_string(),
     };
 
+    let packages_section = packages_section(template);
+
     format!(
         r#"# Zigroot Project Configuration
 # See https://github.com/zigroot-project/zigroot-cli for documentation
 
+manifest_version = {MANIFEST_VERSION}
+
 [project]
 name = "{project_name}"
 version = "0.1.0"
@@ -89,31 +340,86 @@ version = "0.1.0"
 # Enable binary compression with UPX
 compress = false
 # Image format: ext4, squashfs, or initramfs
-image_format = "ext4"
-# Root filesystem size
-rootfs_size = "256M"
+image_format = "{image_format}"
+# Initramfs compression: none, gzip, xz, zstd, lz4
+# initramfs_compression = "gzip"
+# Build the initramfs into the zigroot-built kernel image
+# embed_in_kernel = false
+# Root filesystem size, or "auto" to fit the content
+rootfs_size = "{rootfs_size}"
+# Free space added to an "auto" rootfs_size, in percent of the content
+# rootfs_slack = 20
 # Target hostname
-hostname = "zigroot"
+hostname = "{hostname}"
 # Number of parallel build jobs (defaults to CPU count)
 # jobs = 4
-
-# Package dependencies
-# [packages.busybox]
-# version = "1.36.1"
-#
-# [packages.dropbear]
-# git = "https://github.com/example/dropbear"
-# ref_ = "v2024.85"
-
+# Fail the build if the image exceeds this size
+# size_budget = "64M"
+# Output directory; images go to <output_dir>/<board>/<format>
+# output_dir = "output"
+# Init checked after the build (default: /sbin/init or /init)
+# init = "/sbin/init"
+
+# Severity of rootfs checks: error, warning or ignore
+# [build.validation]
+# missing-library = "error"
+# missing-init = "warning"
+
+{packages_section}
 # External artifacts (bootloader, kernel, etc.)
 # [external.bootloader]
 # type = "bootloader"
 # url = "https://example.com/uboot.bin"
 # sha256 = "..."
+
+# Full disk image (GPT or MBR) written to output/<board>/<format>/disk.img
+# [disk_image]
+# format = "gpt"
+# partition_table = "table"  # or take the format of a partition_table artifact
+#
+# [[partitions]]
+# name = "uboot"
+# type = "raw"
+# offset = "32K"
+# size = "4M"
+# content = "bootloader"
+#
+# [[partitions]]
+# name = "root"
+# type = "ext4"
+# size = "256M"
+# content = "rootfs"
 "#
     )
 }
 
+/// Manifest packages of a template, or commented examples without one
+fn packages_section(template: Option<&ProjectTemplate>) -> String {
+    match template.filter(|t| !t.packages.is_empty()) {
+        Some(template) => {
+            let mut section = format!("# Packages of the {} template\n", template.template.name);
+            for (name, package) in &template.packages {
+                let _ = write!(
+                    section,
+                    "[packages.{name}]\nversion = \"{}\"\n\n",
+                    package.version
+                );
+            }
+            section.pop();
+            section
+        }
+        None => r#"# Package dependencies
+# [packages.busybox]
+# version = "1.36.1"
+#
+# [packages.dropbear]
+# git = "https://github.com/example/dropbear"
+# ref = "v2024.85"
+"#
+        .to_string(),
+    }
+}
+
 /// Generate .gitignore content for zigroot
 pub fn generate_gitignore_content() -> String {
     let mut content = String::from(GITIGNORE_MARKER);
@@ -250,7 +556,7 @@ mod tests {
 
     #[test]
     fn test_generate_manifest_content() {
-        let content = generate_manifest_content("test-project", None);
+        let content = generate_manifest_content(&ManifestSettings::new("test-project", None), None);
         assert!(content.contains("test-project"));
         assert!(content.contains("[project]"));
         assert!(content.contains("[board]"));
@@ -260,9 +566,111 @@ mod tests {
 
     #[test]
     fn test_generate_manifest_content_with_board() {
-        let content = generate_manifest_content("test-project", Some("luckfox-pico"));
+        let content = generate_manifest_content(
+            &ManifestSettings::new("test-project", Some("luckfox-pico")),
+            None,
+        );
         assert!(content.contains("luckfox-pico"));
         assert!(content.contains("name = \"luckfox-pico\""));
+
+        let settings = ManifestSettings::new("test-project", Some("rpi4:2gb"));
+        assert_eq!(settings.board.as_deref(), Some("rpi4"));
+        assert_eq!(settings.variant.as_deref(), Some("2gb"));
+        let manifest = Manifest::from_toml(&generate_manifest_content(&settings, None)).unwrap();
+        assert_eq!(
+            manifest.board.options.get(VARIANT_OPTION),
+            Some(&toml::Value::String("2gb".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_bundled_templates_generate_valid_manifests() {
+        let templates = templates();
+        let mut names: Vec<_> = templates.iter().map(|t| t.template.name.as_str()).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names, vec!["kiosk", "minimal", "networking"]);
+
+        for template in &templates {
+            let path = Path::new("/tmp/demo");
+            let settings =
+                manifest_settings(path, &InitOptions::default(), Some(template)).unwrap();
+            let content = generate_manifest_content(&settings, Some(template));
+            let manifest = parse_manifest(&content).unwrap();
+            for (name, package) in &template.packages {
+                assert_eq!(
+                    manifest.packages[name].version.as_deref(),
+                    Some(package.version.as_str()),
+                    "{}: {name}",
+                    template.template.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_manifest_settings_precedence() {
+        let path = Path::new("/tmp/demo");
+        let kiosk = find_template("kiosk").unwrap();
+
+        let settings = manifest_settings(path, &InitOptions::default(), None).unwrap();
+        assert_eq!(settings.name, "demo");
+        assert_eq!(settings.hostname, "zigroot");
+        assert_eq!(settings.rootfs_size, "256M");
+
+        let settings = manifest_settings(path, &InitOptions::default(), Some(&kiosk)).unwrap();
+        assert_eq!(settings.hostname, "kiosk");
+        assert_eq!(settings.rootfs_size, "512M");
+
+        let options = InitOptions {
+            name: Some("panel".to_string()),
+            hostname: Some("panel-1".to_string()),
+            image_format: Some("squashfs".to_string()),
+            ..InitOptions::default()
+        };
+        let settings = manifest_settings(path, &options, Some(&kiosk)).unwrap();
+        assert_eq!(settings.name, "panel");
+        assert_eq!(settings.hostname, "panel-1");
+        assert_eq!(settings.image_format, "squashfs");
+        assert_eq!(settings.rootfs_size, "512M");
+    }
+
+    #[test]
+    fn test_manifest_settings_rejects_invalid_values() {
+        let path = Path::new("/tmp/demo");
+        let cases = [
+            (
+                "hostname",
+                InitOptions {
+                    hostname: Some("my_host".to_string()),
+                    ..InitOptions::default()
+                },
+            ),
+            (
+                "image-format",
+                InitOptions {
+                    image_format: Some("zip".to_string()),
+                    ..InitOptions::default()
+                },
+            ),
+            (
+                "rootfs-size",
+                InitOptions {
+                    rootfs_size: Some("lots".to_string()),
+                    ..InitOptions::default()
+                },
+            ),
+        ];
+        for (expected, options) in cases {
+            match manifest_settings(path, &options, None) {
+                Err(InitError::InvalidSetting { setting, .. }) => assert_eq!(setting, expected),
+                other => panic!("{expected}: unexpected {other:?}"),
+            }
+        }
+        assert!(matches!(
+            find_template("desktop"),
+            Err(InitError::UnknownTemplate { .. })
+        ));
     }
 
     #[test]
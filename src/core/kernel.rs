@@ -16,7 +16,24 @@
 //! - **26.14**: Builds kernel modules
 //! - **26.15**: Installs modules to /lib/modules/<version>/
 
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::core::build_env::{self, BuildEnvironment};
+use crate::core::flash::load_board_definition;
+use crate::core::manifest::{ExternalArtifact, Manifest};
+
+/// Kernel package name used when the board does not declare one
+pub const DEFAULT_KERNEL_PACKAGE: &str = "linux-kernel";
+
+/// Directory (relative to the project root) where kernel artifacts are collected
+pub const KERNEL_OUTPUT_DIR: &str = "output/kernel";
+
+/// File name of the minimal config written by `kernel savedefconfig`
+pub const SAVED_DEFCONFIG_FILE: &str = "defconfig";
+
+/// File name of the config fragment that embeds the initramfs
+pub const INITRAMFS_FRAGMENT_FILE: &str = "initramfs.config";
 
 /// Kernel configuration
 #[derive(Debug, Clone, Default)]
@@ -53,6 +70,54 @@ impl KernelConfig {
     pub fn has_fragments(&self) -> bool {
         !self.config_fragments.is_empty()
     }
+
+    /// Parse the kernel configuration from a kernel package's package.toml
+    ///
+    /// Reads `defconfig` and `config_fragments` from the `[build]` section.
+    pub fn from_package_toml(content: &str) -> Result<Self, toml::de::Error> {
+        let value: toml::Value = toml::from_str(content)?;
+        let build = value.get("build");
+
+        let defconfig = build
+            .and_then(|b| b.get("defconfig"))
+            .and_then(|v| v.as_str())
+            .map(String::from);
+
+        let config_fragments = build
+            .and_then(|b| b.get("config_fragments"))
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self::new(defconfig, config_fragments))
+    }
+
+    /// Apply a saved minimal config on top of the base configuration
+    ///
+    /// The minimal config is merged after the package's own fragments.
+    /// Returns `false` (and leaves the config untouched) if no file exists.
+    pub fn apply_saved_defconfig(&mut self, path: &Path) -> bool {
+        if !path.is_file() {
+            return false;
+        }
+        self.config_fragments.push(path.display().to_string());
+        true
+    }
+}
+
+/// Read the GCC toolchain target from a package.toml `[build.toolchain]` section
+pub fn toolchain_target_from_package_toml(content: &str) -> Option<String> {
+    let value: toml::Value = toml::from_str(content).ok()?;
+    value
+        .get("build")?
+        .get("toolchain")?
+        .get("target")?
+        .as_str()
+        .map(String::from)
 }
 
 /// Kernel package representation
@@ -182,31 +247,10 @@ impl KernelBuildCommands {
         let cross_prefix = format!("{cross_compile}-");
 
         // Determine kernel image target based on architecture
-        let image_target = match arch {
-            "arm" => "zImage",
-            "arm64" | "aarch64" => "Image",
-            "x86_64" => "bzImage",
-            "riscv64" => "Image",
-            _ => "vmlinux",
-        };
+        let image_target = kernel_image_target(arch);
 
-        // Step 1: Generate initial config from defconfig
-        if let Some(defconfig) = &config.defconfig {
-            commands.push(format!(
-                "make ARCH={arch} CROSS_COMPILE={cross_prefix} {defconfig}"
-            ));
-        }
-
-        // Step 2: Apply config fragments if any
-        if !config.config_fragments.is_empty() {
-            let fragments = config.config_fragments.join(" ");
-            commands.push(format!(
-                "scripts/kconfig/merge_config.sh -m .config {fragments}"
-            ));
-            commands.push(format!(
-                "make ARCH={arch} CROSS_COMPILE={cross_prefix} olddefconfig"
-            ));
-        }
+        // Steps 1-2: Generate config from defconfig and fragments
+        commands.extend(Self::configure(config, arch, &cross_prefix));
 
         // Step 3: Build kernel image
         commands.push(format!(
@@ -232,6 +276,54 @@ impl KernelBuildCommands {
 
         commands
     }
+
+    /// Generate commands that write a minimal defconfig into the source tree
+    ///
+    /// When the tree has no `.config` yet, it is first generated from the
+    /// base defconfig and fragments. The kernel's `savedefconfig` target then
+    /// writes `defconfig` containing only the differences from the defaults.
+    pub fn generate_savedefconfig(
+        config: &KernelConfig,
+        arch: &str,
+        cross_compile: &str,
+        has_config: bool,
+    ) -> Vec<String> {
+        let cross_prefix = format!("{cross_compile}-");
+        let mut commands = Vec::new();
+
+        if !has_config {
+            commands.extend(Self::configure(config, arch, &cross_prefix));
+        }
+
+        commands.push(format!(
+            "make ARCH={arch} CROSS_COMPILE={cross_prefix} savedefconfig"
+        ));
+
+        commands
+    }
+
+    /// Commands generating `.config` from a defconfig and config fragments
+    fn configure(config: &KernelConfig, arch: &str, cross_prefix: &str) -> Vec<String> {
+        let mut commands = Vec::new();
+
+        if let Some(defconfig) = &config.defconfig {
+            commands.push(format!(
+                "make ARCH={arch} CROSS_COMPILE={cross_prefix} {defconfig}"
+            ));
+        }
+
+        if !config.config_fragments.is_empty() {
+            let fragments = config.config_fragments.join(" ");
+            commands.push(format!(
+                "scripts/kconfig/merge_config.sh -m .config {fragments}"
+            ));
+            commands.push(format!(
+                "make ARCH={arch} CROSS_COMPILE={cross_prefix} olddefconfig"
+            ));
+        }
+
+        commands
+    }
 }
 
 /// Kernel build environment
@@ -265,7 +357,7 @@ impl KernelBuildEnv {
             destdir,
             arch,
             cross_compile,
-            jobs: num_cpus::get(),
+            jobs: build_env::resolve_jobs(None),
             config_dir,
         }
     }
@@ -285,10 +377,271 @@ impl KernelBuildEnv {
         env
     }
 
+    /// Create a kernel build environment from a GCC build environment
+    ///
+    /// The kernel architecture and `CROSS_COMPILE` prefix are derived from the
+    /// build environment's target triple.
+    pub fn from_build_env(env: &BuildEnvironment, config_dir: PathBuf) -> Self {
+        Self {
+            srcdir: env.srcdir.clone(),
+            destdir: env.destdir.clone(),
+            arch: target_to_kernel_arch(&env.target).to_string(),
+            cross_compile: format!("{}-", target_to_cross_compile(&env.target)),
+            jobs: env.jobs,
+            config_dir,
+        }
+    }
+
     /// Get the path where kernel config should be saved
     pub fn config_save_path(&self) -> PathBuf {
         self.config_dir.join(".config")
     }
+
+    /// Get the path where the minimal config from savedefconfig is stored
+    pub fn saved_defconfig_path(&self) -> PathBuf {
+        self.config_dir.join(SAVED_DEFCONFIG_FILE)
+    }
+
+    /// Get the path of the built kernel image inside the source tree
+    pub fn image_path(&self) -> PathBuf {
+        self.srcdir
+            .join("arch")
+            .join(&self.arch)
+            .join("boot")
+            .join(kernel_image_target(&self.arch))
+    }
+
+    /// Get the directory containing built device tree blobs
+    pub fn dtbs_dir(&self) -> PathBuf {
+        self.srcdir
+            .join("arch")
+            .join(&self.arch)
+            .join("boot")
+            .join("dts")
+    }
+}
+
+/// Ensure the kernel source tree has a `.config` to work from
+///
+/// A `.config` saved in the project's kernel/ directory (e.g. by menuconfig)
+/// is copied into the source tree if the tree has none. Returns whether the
+/// source tree has a `.config` afterwards.
+pub fn stage_project_config(kernel_env: &KernelBuildEnv) -> std::io::Result<bool> {
+    let src_config = kernel_env.srcdir.join(".config");
+    if src_config.exists() {
+        return Ok(true);
+    }
+
+    let saved = kernel_env.config_save_path();
+    if saved.is_file() {
+        std::fs::copy(&saved, &src_config)?;
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Copy the `defconfig` written by savedefconfig into the project
+///
+/// Returns the path of the stored minimal config.
+pub fn store_saved_defconfig(kernel_env: &KernelBuildEnv) -> std::io::Result<PathBuf> {
+    let generated = kernel_env.srcdir.join(SAVED_DEFCONFIG_FILE);
+    let dest = kernel_env.saved_defconfig_path();
+    std::fs::create_dir_all(&kernel_env.config_dir)?;
+    std::fs::copy(&generated, &dest)?;
+    Ok(dest)
+}
+
+/// Get the kernel image make target for an architecture
+pub fn kernel_image_target(arch: &str) -> &'static str {
+    match arch {
+        "arm" => "zImage",
+        "arm64" | "aarch64" | "riscv" | "riscv64" => "Image",
+        "x86_64" => "bzImage",
+        _ => "vmlinux",
+    }
+}
+
+/// An artifact produced by a kernel build
+#[derive(Debug, Clone, PartialEq)]
+pub struct KernelArtifact {
+    /// External artifact name (e.g., "kernel", "dtb-rv1103-luckfox")
+    pub name: String,
+    /// External artifact type ("kernel" or "dtb")
+    pub artifact_type: String,
+    /// Path relative to the project root
+    pub path: String,
+}
+
+/// Collect the kernel image and DTBs from a built kernel tree into `output/kernel/`
+///
+/// Returns the collected artifacts with project-relative paths.
+pub fn collect_artifacts(
+    project_dir: &Path,
+    kernel_env: &KernelBuildEnv,
+) -> std::io::Result<Vec<KernelArtifact>> {
+    let output_dir = project_dir.join(KERNEL_OUTPUT_DIR);
+    std::fs::create_dir_all(&output_dir)?;
+
+    let mut artifacts = Vec::new();
+
+    let image = kernel_env.image_path();
+    if image.exists() {
+        let file_name = kernel_image_target(&kernel_env.arch);
+        std::fs::copy(&image, output_dir.join(file_name))?;
+        artifacts.push(KernelArtifact {
+            name: "kernel".to_string(),
+            artifact_type: "kernel".to_string(),
+            path: format!("{KERNEL_OUTPUT_DIR}/{file_name}"),
+        });
+    }
+
+    let dtbs_dir = kernel_env.dtbs_dir();
+    if dtbs_dir.exists() {
+        let mut dtbs: Vec<PathBuf> = walkdir::WalkDir::new(&dtbs_dir)
+            .into_iter()
+            .filter_map(Result::ok)
+            .map(walkdir::DirEntry::into_path)
+            .filter(|p| p.extension().is_some_and(|ext| ext == "dtb"))
+            .collect();
+        dtbs.sort();
+
+        for dtb in dtbs {
+            let Some(file_name) = dtb.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let stem = file_name.trim_end_matches(".dtb");
+            std::fs::copy(&dtb, output_dir.join(file_name))?;
+            artifacts.push(KernelArtifact {
+                name: format!("dtb-{stem}"),
+                artifact_type: "dtb".to_string(),
+                path: format!("{KERNEL_OUTPUT_DIR}/{file_name}"),
+            });
+        }
+    }
+
+    Ok(artifacts)
+}
+
+/// Register kernel build artifacts as external artifacts in the manifest
+///
+/// Existing entries with the same name are replaced so repeated builds
+/// keep the manifest in sync with the latest output.
+pub fn register_artifacts(manifest: &mut Manifest, artifacts: &[KernelArtifact]) {
+    for artifact in artifacts {
+        manifest.external.insert(
+            artifact.name.clone(),
+            ExternalArtifact {
+                artifact_type: artifact.artifact_type.clone(),
+                url: None,
+                mirrors: Vec::new(),
+                path: Some(artifact.path.clone()),
+                sha256: None,
+                sha512: None,
+                blake3: None,
+                format: None,
+            },
+        );
+    }
+}
+
+/// Resolve the package that provides the kernel
+///
+/// The board may declare its kernel package; otherwise
+/// [`DEFAULT_KERNEL_PACKAGE`] is used.
+pub fn resolve_kernel_package(project_dir: &Path, manifest: &Manifest) -> String {
+    manifest
+        .board
+        .name
+        .as_deref()
+        .and_then(|name| load_board_definition(project_dir, name).ok())
+        .and_then(|board| board.board.kernel)
+        .unwrap_or_else(|| DEFAULT_KERNEL_PACKAGE.to_string())
+}
+
+/// Find the kernel `.config` for a kernel package
+///
+/// Prefers the configured source tree, then the config saved in the
+/// project's kernel/ directory.
+pub fn find_kernel_config(project_dir: &Path, kernel_pkg: &str) -> Option<PathBuf> {
+    [
+        project_dir
+            .join("build/src")
+            .join(kernel_pkg)
+            .join(".config"),
+        project_dir.join("kernel").join(".config"),
+    ]
+    .into_iter()
+    .find(|path| path.is_file())
+}
+
+/// Kernel option needed to unpack an initramfs with the given compression
+///
+/// Returns `None` for uncompressed archives.
+pub fn initramfs_decompressor_option(compression: &str) -> Option<&'static str> {
+    match compression {
+        "gzip" => Some("CONFIG_RD_GZIP"),
+        "xz" => Some("CONFIG_RD_XZ"),
+        "zstd" => Some("CONFIG_RD_ZSTD"),
+        "lz4" => Some("CONFIG_RD_LZ4"),
+        _ => None,
+    }
+}
+
+/// Check whether a kernel `.config` enables an option
+pub fn config_enables(config: &str, option: &str) -> bool {
+    config.lines().any(|line| {
+        line.trim()
+            .strip_prefix(option)
+            .and_then(|rest| rest.strip_prefix('='))
+            .is_some_and(|value| value == "y")
+    })
+}
+
+/// Config fragment embedding an initramfs built from `rootfs_dir`
+pub fn initramfs_fragment(rootfs_dir: &Path, compression: &str) -> String {
+    let mut lines = vec![
+        "CONFIG_BLK_DEV_INITRD=y".to_string(),
+        format!("CONFIG_INITRAMFS_SOURCE=\"{}\"", rootfs_dir.display()),
+        "CONFIG_INITRAMFS_ROOT_UID=0".to_string(),
+        "CONFIG_INITRAMFS_ROOT_GID=0".to_string(),
+    ];
+    if let Some(option) = initramfs_decompressor_option(compression) {
+        lines.push(format!("{option}=y"));
+    }
+    lines.push(format!(
+        "CONFIG_INITRAMFS_COMPRESSION_{}=y",
+        compression.to_ascii_uppercase()
+    ));
+    lines.join("\n") + "\n"
+}
+
+/// Write the initramfs config fragment next to the kernel staging directory
+///
+/// Returns the path of the fragment, to be merged after the package's own
+/// fragments.
+pub fn write_initramfs_fragment(
+    kernel_env: &KernelBuildEnv,
+    rootfs_dir: &Path,
+    compression: &str,
+) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(&kernel_env.destdir)?;
+    let path = kernel_env.destdir.join(INITRAMFS_FRAGMENT_FILE);
+    std::fs::write(&path, initramfs_fragment(rootfs_dir, compression))?;
+    Ok(path)
+}
+
+/// Build the full environment for running kernel make commands
+///
+/// Combines the GCC build environment (CC, CXX, AR, ...) with the
+/// kernel-specific variables (ARCH, `CROSS_COMPILE`, `INSTALL_MOD_PATH`, ...).
+pub fn kernel_env_vars(
+    build_env: &BuildEnvironment,
+    kernel_env: &KernelBuildEnv,
+) -> HashMap<String, String> {
+    let mut env = build_env.to_env_map();
+    env.extend(kernel_env.to_env_vars());
+    env
 }
 
 /// Map target triple to kernel architecture
@@ -372,6 +725,179 @@ mod tests {
         assert!(commands.iter().any(|c| c.contains("debug.config")));
     }
 
+    #[test]
+    fn test_kernel_config_from_package_toml() {
+        let content = r#"
+[package]
+name = "linux-kernel"
+version = "6.6.0"
+description = "Linux kernel"
+
+[source]
+url = "https://example.com/linux-6.6.tar.xz"
+sha256 = "abc123"
+
+[build]
+type = "custom"
+defconfig = "multi_v7_defconfig"
+config_fragments = ["debug.config"]
+
+[build.toolchain]
+type = "gcc"
+target = "arm-linux-gnueabihf"
+"#;
+        let config = KernelConfig::from_package_toml(content).unwrap();
+        assert_eq!(config.defconfig.as_deref(), Some("multi_v7_defconfig"));
+        assert_eq!(config.config_fragments, vec!["debug.config"]);
+        assert_eq!(
+            toolchain_target_from_package_toml(content).as_deref(),
+            Some("arm-linux-gnueabihf")
+        );
+    }
+
+    #[test]
+    fn test_kernel_build_env_from_build_env() {
+        let build_env = BuildEnvironment::for_gcc(
+            "aarch64-linux-gnu-",
+            "aarch64-linux-gnu",
+            "cortex-a53",
+            PathBuf::from("/src"),
+            PathBuf::from("/dest"),
+        )
+        .with_jobs(3);
+        let kernel_env = KernelBuildEnv::from_build_env(&build_env, PathBuf::from("/kernel"));
+
+        assert_eq!(kernel_env.arch, "arm64");
+        assert_eq!(kernel_env.cross_compile, "aarch64-linux-gnu-");
+        assert_eq!(kernel_env.jobs, 3);
+        assert_eq!(
+            kernel_env.image_path(),
+            PathBuf::from("/src/arch/arm64/boot/Image")
+        );
+
+        let env = kernel_env_vars(&build_env, &kernel_env);
+        assert_eq!(env.get("CC").unwrap(), "aarch64-linux-gnu-gcc");
+        assert_eq!(env.get("ARCH").unwrap(), "arm64");
+    }
+
+    #[test]
+    fn test_savedefconfig_commands_configure_only_without_config() {
+        let config = KernelConfig::from_defconfig("multi_v7_defconfig");
+
+        let commands = KernelBuildCommands::generate_savedefconfig(
+            &config,
+            "arm",
+            "arm-linux-gnueabihf",
+            false,
+        );
+        assert_eq!(commands.len(), 2);
+        assert!(commands[0].contains("multi_v7_defconfig"));
+        assert!(commands[1].ends_with("savedefconfig"));
+
+        let commands = KernelBuildCommands::generate_savedefconfig(
+            &config,
+            "arm",
+            "arm-linux-gnueabihf",
+            true,
+        );
+        assert_eq!(
+            commands,
+            vec!["make ARCH=arm CROSS_COMPILE=arm-linux-gnueabihf- savedefconfig"]
+        );
+    }
+
+    #[test]
+    fn test_apply_saved_defconfig_merges_after_fragments() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let saved = temp.path().join(SAVED_DEFCONFIG_FILE);
+        let mut config = KernelConfig::new(
+            Some("multi_v7_defconfig".to_string()),
+            vec!["debug.config".to_string()],
+        );
+
+        assert!(!config.apply_saved_defconfig(&saved));
+        assert_eq!(config.config_fragments.len(), 1);
+
+        std::fs::write(&saved, "CONFIG_USB=y\n").unwrap();
+        assert!(config.apply_saved_defconfig(&saved));
+        assert_eq!(
+            config.config_fragments,
+            vec!["debug.config".to_string(), saved.display().to_string()]
+        );
+
+        let commands =
+            KernelBuildCommands::generate_for_arch(&config, "arm", "arm-linux-gnueabihf");
+        assert!(commands[1].ends_with(&saved.display().to_string()));
+    }
+
+    #[test]
+    fn test_store_saved_defconfig_copies_into_project() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let srcdir = temp.path().join("src");
+        std::fs::create_dir_all(&srcdir).unwrap();
+        std::fs::write(srcdir.join(SAVED_DEFCONFIG_FILE), "CONFIG_USB=y\n").unwrap();
+
+        let kernel_env = KernelBuildEnv::new(
+            srcdir.clone(),
+            temp.path().join("dest"),
+            "arm".to_string(),
+            "arm-linux-gnueabihf-".to_string(),
+            temp.path().join("kernel"),
+        );
+
+        assert!(!stage_project_config(&kernel_env).unwrap());
+        std::fs::create_dir_all(&kernel_env.config_dir).unwrap();
+        std::fs::write(kernel_env.config_save_path(), "CONFIG_USB=y\n").unwrap();
+        assert!(stage_project_config(&kernel_env).unwrap());
+        assert!(srcdir.join(".config").exists());
+
+        let stored = store_saved_defconfig(&kernel_env).unwrap();
+        assert_eq!(
+            stored,
+            temp.path().join("kernel").join(SAVED_DEFCONFIG_FILE)
+        );
+        assert_eq!(std::fs::read_to_string(stored).unwrap(), "CONFIG_USB=y\n");
+    }
+
+    #[test]
+    fn test_register_artifacts_replaces_existing() {
+        let mut manifest = Manifest::default();
+        let artifacts = vec![KernelArtifact {
+            name: "kernel".to_string(),
+            artifact_type: "kernel".to_string(),
+            path: "output/kernel/zImage".to_string(),
+        }];
+
+        register_artifacts(&mut manifest, &artifacts);
+        register_artifacts(&mut manifest, &artifacts);
+
+        assert_eq!(manifest.external.len(), 1);
+        let kernel = &manifest.external["kernel"];
+        assert_eq!(kernel.artifact_type, "kernel");
+        assert_eq!(kernel.path.as_deref(), Some("output/kernel/zImage"));
+    }
+
+    #[test]
+    fn test_initramfs_fragment_embeds_rootfs() {
+        let fragment = initramfs_fragment(Path::new("/work/build/rootfs"), "zstd");
+        assert!(fragment.contains("CONFIG_BLK_DEV_INITRD=y\n"));
+        assert!(fragment.contains("CONFIG_INITRAMFS_SOURCE=\"/work/build/rootfs\"\n"));
+        assert!(fragment.contains("CONFIG_RD_ZSTD=y\n"));
+        assert!(fragment.contains("CONFIG_INITRAMFS_COMPRESSION_ZSTD=y\n"));
+
+        let fragment = initramfs_fragment(Path::new("/rootfs"), "none");
+        assert!(!fragment.contains("CONFIG_RD_"));
+        assert!(fragment.contains("CONFIG_INITRAMFS_COMPRESSION_NONE=y\n"));
+    }
+
+    #[test]
+    fn test_config_enables() {
+        let config = "CONFIG_RD_GZIP=y\n# CONFIG_RD_XZ is not set\nCONFIG_RD_GZIP_EXTRA=y\n";
+        assert!(config_enables(config, "CONFIG_RD_GZIP"));
+        assert!(!config_enables(config, "CONFIG_RD_XZ"));
+        assert!(!config_enables(config, "CONFIG_RD"));
+    }
+
     #[test]
     fn test_target_to_kernel_arch() {
         assert_eq!(target_to_kernel_arch("arm-linux-gnueabihf"), "arm");
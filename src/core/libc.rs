@@ -0,0 +1,266 @@
+//! C library selection
+//!
+//! Boards build against the libc of their target triple, musl unless the
+//! board definition or the project's `[board]` section selects glibc or
+//! uClibc. The libc is the ABI part of the triple
+//! (`arm-linux-musleabihf` vs `arm-linux-gnueabihf`), so the selection
+//! reaches everything keyed on the target: compiler flags, build caches and
+//! the dynamic loader expected in the rootfs.
+
+use serde::{Deserialize, Serialize};
+
+/// Architectures Zig ships glibc for
+const ZIG_GLIBC_ARCHS: &[&str] = &[
+    "x86",
+    "i386",
+    "i486",
+    "i586",
+    "i686",
+    "x86_64",
+    "arm",
+    "armeb",
+    "thumb",
+    "aarch64",
+    "aarch64_be",
+    "mips",
+    "mipsel",
+    "mips64",
+    "mips64el",
+    "powerpc",
+    "powerpc64",
+    "powerpc64le",
+    "riscv32",
+    "riscv64",
+    "s390x",
+    "sparc64",
+    "loongarch64",
+];
+
+/// C library the rootfs is built against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Libc {
+    /// musl, statically linked by Zig by default
+    Musl,
+    /// GNU C library
+    Glibc,
+    /// uClibc(-ng), only available from GCC toolchains
+    Uclibc,
+}
+
+impl Libc {
+    /// All supported C libraries
+    pub const ALL: [Self; 3] = [Self::Musl, Self::Glibc, Self::Uclibc];
+
+    /// Name used in board definitions and manifests
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Musl => "musl",
+            Self::Glibc => "glibc",
+            Self::Uclibc => "uclibc",
+        }
+    }
+
+    /// ABI prefix of the triple's environment (e.g. `gnu` in `gnueabihf`)
+    fn abi_prefix(self, float_abi: &str) -> &'static str {
+        match self {
+            Self::Musl => "musl",
+            Self::Glibc => "gnu",
+            // uClibc toolchains spell the EABI variants `uclibcgnueabi(hf)`
+            Self::Uclibc if float_abi.is_empty() => "uclibc",
+            Self::Uclibc => "uclibcgnu",
+        }
+    }
+
+    /// C library of a target triple; musl when the triple names none
+    pub fn of_target(target: &str) -> Self {
+        let abi = target.rsplit('-').next().unwrap_or_default();
+        if abi.starts_with("uclibc") {
+            Self::Uclibc
+        } else if abi.starts_with("gnu") {
+            Self::Glibc
+        } else {
+            Self::Musl
+        }
+    }
+
+    /// `target` with its ABI switched to this C library
+    ///
+    /// The float ABI suffix is kept, e.g. `arm-linux-musleabihf` becomes
+    /// `arm-linux-gnueabihf` for glibc.
+    pub fn apply_to(self, target: &str) -> String {
+        let (base, abi) = match target.rsplit_once('-') {
+            Some((base, abi)) if base.contains('-') => (base, abi),
+            _ => (target, ""),
+        };
+        let float_abi = ["uclibcgnu", "uclibc", "musl", "gnu"]
+            .iter()
+            .find_map(|prefix| abi.strip_prefix(prefix))
+            .unwrap_or(abi);
+        format!("{base}-{}{float_abi}", self.abi_prefix(float_abi))
+    }
+
+    /// Whether Zig can build for `target` with this C library
+    ///
+    /// Otherwise packages are built with the GCC cross-toolchain of the
+    /// target triple.
+    pub fn zig_provides(self, target: &str) -> bool {
+        match self {
+            Self::Musl => true,
+            Self::Glibc => ZIG_GLIBC_ARCHS.contains(&arch(target)),
+            Self::Uclibc => false,
+        }
+    }
+
+    /// Dynamic loader that binaries for `target` request, if known
+    pub fn dynamic_loader(self, target: &str) -> Option<String> {
+        let arch = arch(target);
+        let hard_float = target.ends_with("hf");
+        let loader = match self {
+            Self::Musl => {
+                let name = match arch {
+                    "x86" | "i386" | "i486" | "i586" | "i686" => "i386".to_string(),
+                    "arm" | "thumb" if hard_float => "armhf".to_string(),
+                    "armeb" if hard_float => "armebhf".to_string(),
+                    "x86_64" | "arm" | "thumb" | "armeb" | "aarch64" | "aarch64_be" | "mips"
+                    | "mipsel" | "mips64" | "mips64el" | "powerpc" | "powerpc64"
+                    | "powerpc64le" | "riscv32" | "riscv64" => arch.replace("thumb", "arm"),
+                    _ => return None,
+                };
+                format!("/lib/ld-musl-{name}.so.1")
+            }
+            Self::Glibc => match arch {
+                "x86_64" => "/lib64/ld-linux-x86-64.so.2",
+                "x86" | "i386" | "i486" | "i586" | "i686" => "/lib/ld-linux.so.2",
+                "aarch64" => "/lib/ld-linux-aarch64.so.1",
+                "aarch64_be" => "/lib/ld-linux-aarch64_be.so.1",
+                "arm" | "armeb" | "thumb" if hard_float => "/lib/ld-linux-armhf.so.3",
+                "arm" | "armeb" | "thumb" => "/lib/ld-linux.so.3",
+                "mips" | "mipsel" | "powerpc" => "/lib/ld.so.1",
+                "mips64" | "mips64el" => "/lib64/ld.so.1",
+                "powerpc64" => "/lib64/ld64.so.1",
+                "powerpc64le" => "/lib64/ld64.so.2",
+                "riscv32" => "/lib/ld-linux-riscv32-ilp32d.so.1",
+                "riscv64" => "/lib/ld-linux-riscv64-lp64d.so.1",
+                _ => return None,
+            }
+            .to_string(),
+            Self::Uclibc => match arch {
+                "x86_64" | "aarch64" | "aarch64_be" | "mips64" | "mips64el" | "powerpc64"
+                | "riscv64" => "/lib/ld64-uClibc.so.0",
+                _ => "/lib/ld-uClibc.so.0",
+            }
+            .to_string(),
+        };
+        Some(loader)
+    }
+}
+
+impl std::fmt::Display for Libc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Libc {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|libc| libc.as_str() == s)
+            .ok_or_else(|| format!("unknown libc '{s}' (expected musl, glibc or uclibc)"))
+    }
+}
+
+/// CPU architecture of a target triple
+fn arch(target: &str) -> &str {
+    target.split('-').next().unwrap_or(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_of_target() {
+        assert_eq!(Libc::of_target("arm-linux-musleabihf"), Libc::Musl);
+        assert_eq!(Libc::of_target("aarch64-linux-gnu"), Libc::Glibc);
+        assert_eq!(Libc::of_target("arm-linux-gnueabihf"), Libc::Glibc);
+        assert_eq!(Libc::of_target("mipsel-linux-uclibc"), Libc::Uclibc);
+        assert_eq!(
+            Libc::of_target("arm-buildroot-linux-uclibcgnueabihf"),
+            Libc::Uclibc
+        );
+        assert_eq!(Libc::of_target("x86_64-linux"), Libc::Musl);
+    }
+
+    #[test]
+    fn test_apply_to_keeps_float_abi() {
+        assert_eq!(
+            Libc::Glibc.apply_to("arm-linux-musleabihf"),
+            "arm-linux-gnueabihf"
+        );
+        assert_eq!(
+            Libc::Glibc.apply_to("aarch64-linux-musl"),
+            "aarch64-linux-gnu"
+        );
+        assert_eq!(
+            Libc::Uclibc.apply_to("arm-linux-musleabi"),
+            "arm-linux-uclibcgnueabi"
+        );
+        assert_eq!(
+            Libc::Uclibc.apply_to("mipsel-linux-musl"),
+            "mipsel-linux-uclibc"
+        );
+        assert_eq!(
+            Libc::Musl.apply_to("arm-linux-uclibcgnueabihf"),
+            "arm-linux-musleabihf"
+        );
+        assert_eq!(Libc::Glibc.apply_to("x86_64-linux"), "x86_64-linux-gnu");
+        for libc in Libc::ALL {
+            let target = libc.apply_to("arm-linux-musleabihf");
+            assert_eq!(Libc::of_target(&target), libc, "{target}");
+        }
+    }
+
+    #[test]
+    fn test_zig_provides() {
+        assert!(Libc::Musl.zig_provides("riscv64-linux-musl"));
+        assert!(Libc::Glibc.zig_provides("aarch64-linux-gnu"));
+        assert!(!Libc::Glibc.zig_provides("xtensa-linux-gnu"));
+        assert!(!Libc::Uclibc.zig_provides("arm-linux-uclibcgnueabihf"));
+    }
+
+    #[test]
+    fn test_dynamic_loader() {
+        let loader = |libc: Libc, target: &str| libc.dynamic_loader(target);
+        assert_eq!(
+            loader(Libc::Musl, "aarch64-linux-musl").as_deref(),
+            Some("/lib/ld-musl-aarch64.so.1")
+        );
+        assert_eq!(
+            loader(Libc::Musl, "arm-linux-musleabihf").as_deref(),
+            Some("/lib/ld-musl-armhf.so.1")
+        );
+        assert_eq!(
+            loader(Libc::Glibc, "arm-linux-gnueabihf").as_deref(),
+            Some("/lib/ld-linux-armhf.so.3")
+        );
+        assert_eq!(
+            loader(Libc::Glibc, "x86_64-linux-gnu").as_deref(),
+            Some("/lib64/ld-linux-x86-64.so.2")
+        );
+        assert_eq!(
+            loader(Libc::Uclibc, "mipsel-linux-uclibc").as_deref(),
+            Some("/lib/ld-uClibc.so.0")
+        );
+        assert_eq!(loader(Libc::Musl, "xtensa-linux-musl"), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("glibc".parse::<Libc>(), Ok(Libc::Glibc));
+        assert!("newlib".parse::<Libc>().unwrap_err().contains("newlib"));
+    }
+}
@@ -2,13 +2,297 @@
 //!
 //! Tracks and reports licenses of all included packages.
 //!
+//! Declared licenses are SPDX expressions such as
+//! `GPL-2.0-or-later WITH Linux-syscall-note OR MIT`. They are parsed into
+//! [`LicenseExpr`], with identifiers checked against the bundled SPDX
+//! license list and normalized to its spelling. The license files of a
+//! package are detected in its source tree, or named with `license_files`
+//! globs in its definition.
+//!
 //! **Validates: Requirements 22.1-22.6**
 
 use std::collections::HashMap;
-use std::path::Path;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
+use crate::core::compress::matches_glob;
 use crate::core::manifest::Manifest;
 
+/// License identifiers of the SPDX license list
+const SPDX_LICENSES: &str = include_str!("spdx/licenses.txt");
+
+/// Exception identifiers of the SPDX license list
+const SPDX_EXCEPTIONS: &str = include_str!("spdx/exceptions.txt");
+
+/// Top-level source files detected as license files, by name prefix
+const LICENSE_FILE_PREFIXES: &[&str] = &["COPYING", "LICENSE", "LICENCE", "NOTICE"];
+
+/// License policy in the manifest
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LicenseConfig {
+    /// Licenses no package may be used under (e.g. `["GPL-3.0-only"]`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+}
+
+/// License expression errors
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum LicenseError {
+    /// Nothing to parse
+    #[error("empty license expression")]
+    Empty,
+
+    /// An identifier not on the SPDX license list
+    #[error("unknown SPDX license '{id}'")]
+    UnknownLicense { id: String },
+
+    /// An exception not on the SPDX exception list
+    #[error("unknown SPDX exception '{id}'")]
+    UnknownException { id: String },
+
+    /// Misplaced operators or parentheses
+    #[error("{message}")]
+    Syntax { message: String },
+}
+
+/// Parsed SPDX license expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LicenseExpr {
+    /// A single license, optionally "or any later version" (`+`) and with
+    /// an exception (`WITH`)
+    License {
+        id: String,
+        or_later: bool,
+        exception: Option<String>,
+    },
+    /// All of the licenses apply
+    And(Vec<LicenseExpr>),
+    /// Any one of the licenses may be chosen
+    Or(Vec<LicenseExpr>),
+}
+
+impl LicenseExpr {
+    /// Parse an SPDX expression, normalizing identifiers and operators
+    ///
+    /// `WITH` binds tighter than `AND`, which binds tighter than `OR`.
+    /// `LicenseRef-` and `DocumentRef-` identifiers are accepted as is.
+    pub fn parse(expression: &str) -> Result<Self, LicenseError> {
+        let spaced = expression.replace('(', " ( ").replace(')', " ) ");
+        let tokens: Vec<&str> = spaced.split_whitespace().collect();
+        if tokens.is_empty() {
+            return Err(LicenseError::Empty);
+        }
+        let mut parser = Parser { tokens, next: 0 };
+        let expr = parser.or_expr()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(syntax(format!("unexpected '{token}'"))),
+        }
+    }
+
+    /// Whether the terms can be met without any `denied` license
+    ///
+    /// A denied license without an exception denies it with any exception.
+    pub fn is_acceptable(&self, denied: &[LicenseExpr]) -> bool {
+        match self {
+            Self::License {
+                id,
+                or_later,
+                exception,
+            } => !denied.iter().any(|deny| match deny {
+                Self::License {
+                    id: denied_id,
+                    or_later: denied_or_later,
+                    exception: denied_exception,
+                } => {
+                    denied_id == id
+                        && denied_or_later == or_later
+                        && (denied_exception.is_none() || denied_exception == exception)
+                }
+                _ => false,
+            }),
+            Self::And(terms) => terms.iter().all(|term| term.is_acceptable(denied)),
+            Self::Or(terms) => terms.iter().any(|term| term.is_acceptable(denied)),
+        }
+    }
+
+    /// Whether every choice of license includes a copyleft license
+    pub fn is_copyleft(&self) -> bool {
+        match self {
+            Self::License { id, .. } => is_copyleft_license(id),
+            Self::And(terms) => terms.iter().any(Self::is_copyleft),
+            Self::Or(terms) => terms.iter().all(Self::is_copyleft),
+        }
+    }
+}
+
+impl fmt::Display for LicenseExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::License {
+                id,
+                or_later,
+                exception,
+            } => {
+                write!(f, "{id}{}", if *or_later { "+" } else { "" })?;
+                if let Some(exception) = exception {
+                    write!(f, " WITH {exception}")?;
+                }
+                Ok(())
+            }
+            Self::And(terms) => {
+                for (i, term) in terms.iter().enumerate() {
+                    let separator = if i == 0 { "" } else { " AND " };
+                    match term {
+                        Self::Or(_) => write!(f, "{separator}({term})")?,
+                        _ => write!(f, "{separator}{term}")?,
+                    }
+                }
+                Ok(())
+            }
+            Self::Or(terms) => {
+                for (i, term) in terms.iter().enumerate() {
+                    write!(f, "{}{term}", if i == 0 { "" } else { " OR " })?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Recursive descent parser over the tokens of an expression
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    next: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.next).copied()
+    }
+
+    fn take(&mut self) -> Option<&'a str> {
+        let token = self.peek();
+        self.next += 1;
+        token
+    }
+
+    fn take_operator(&mut self, operator: &str) -> bool {
+        let matches = self
+            .peek()
+            .is_some_and(|token| token.eq_ignore_ascii_case(operator));
+        if matches {
+            self.next += 1;
+        }
+        matches
+    }
+
+    fn or_expr(&mut self) -> Result<LicenseExpr, LicenseError> {
+        let mut terms = vec![self.and_expr()?];
+        while self.take_operator("OR") {
+            terms.push(self.and_expr()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            LicenseExpr::Or(terms)
+        })
+    }
+
+    fn and_expr(&mut self) -> Result<LicenseExpr, LicenseError> {
+        let mut terms = vec![self.primary()?];
+        while self.take_operator("AND") {
+            terms.push(self.primary()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            LicenseExpr::And(terms)
+        })
+    }
+
+    fn primary(&mut self) -> Result<LicenseExpr, LicenseError> {
+        match self.take() {
+            None => Err(syntax("expression ends early".to_string())),
+            Some("(") => {
+                let expr = self.or_expr()?;
+                match self.take() {
+                    Some(")") => Ok(expr),
+                    _ => Err(syntax("missing ')'".to_string())),
+                }
+            }
+            Some(token) if is_operator(token) || token == ")" => {
+                Err(syntax(format!("expected a license before '{token}'")))
+            }
+            Some(token) => {
+                let (id, or_later) = license_id(token)?;
+                let exception = if self.take_operator("WITH") {
+                    let exception = self
+                        .take()
+                        .filter(|t| !is_operator(t) && *t != "(" && *t != ")")
+                        .ok_or_else(|| syntax("expected an exception after WITH".to_string()))?;
+                    Some(spdx_id(SPDX_EXCEPTIONS, exception).ok_or_else(|| {
+                        LicenseError::UnknownException {
+                            id: exception.to_string(),
+                        }
+                    })?)
+                } else {
+                    None
+                };
+                Ok(LicenseExpr::License {
+                    id,
+                    or_later,
+                    exception,
+                })
+            }
+        }
+    }
+}
+
+fn is_operator(token: &str) -> bool {
+    ["AND", "OR", "WITH"]
+        .iter()
+        .any(|operator| token.eq_ignore_ascii_case(operator))
+}
+
+/// Normalized license identifier of a token, and whether it ends in `+`
+fn license_id(token: &str) -> Result<(String, bool), LicenseError> {
+    if let Some(id) = spdx_id(SPDX_LICENSES, token) {
+        return Ok((id, false));
+    }
+    let (base, or_later) = token
+        .strip_suffix('+')
+        .map_or((token, false), |base| (base, true));
+    let is_reference = ["LicenseRef-", "DocumentRef-"].iter().any(|prefix| {
+        base.get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+    });
+    if is_reference && !or_later {
+        return Ok((base.to_string(), false));
+    }
+    spdx_id(SPDX_LICENSES, base)
+        .filter(|_| or_later)
+        .map(|id| (id, true))
+        .ok_or_else(|| LicenseError::UnknownLicense {
+            id: token.to_string(),
+        })
+}
+
+/// Identifier of a bundled SPDX list matching `token` case-insensitively
+fn spdx_id(list: &str, token: &str) -> Option<String> {
+    list.lines()
+        .filter(|line| !line.starts_with('#'))
+        .find(|id| id.eq_ignore_ascii_case(token))
+        .map(str::to_string)
+}
+
+fn syntax(message: String) -> LicenseError {
+    LicenseError::Syntax { message }
+}
+
 /// License information for a package
 #[derive(Debug, Clone)]
 pub struct PackageLicense {
@@ -16,8 +300,12 @@ pub struct PackageLicense {
     pub name: String,
     /// Package version
     pub version: String,
-    /// License identifier (SPDX format)
+    /// License expression (SPDX format), normalized when it parses
     pub license: Option<String>,
+    /// Parsed license expression
+    pub expression: Option<LicenseExpr>,
+    /// Why the declared license does not parse
+    pub invalid: Option<LicenseError>,
     /// Source URL
     pub source_url: Option<String>,
     /// Whether this is a copyleft license
@@ -27,12 +315,26 @@ pub struct PackageLicense {
 impl PackageLicense {
     /// Create a new package license entry
     pub fn new(name: &str, version: &str, license: Option<&str>, source_url: Option<&str>) -> Self {
-        let is_copyleft = license.map(|l| is_copyleft_license(l)).unwrap_or(false);
+        let parsed = license.map(LicenseExpr::parse);
+        let (expression, invalid) = match parsed {
+            Some(Ok(expr)) => (Some(expr), None),
+            Some(Err(e)) => (None, Some(e)),
+            None => (None, None),
+        };
+        let is_copyleft = match &expression {
+            Some(expr) => expr.is_copyleft(),
+            None => license.is_some_and(is_copyleft_license),
+        };
 
         Self {
             name: name.to_string(),
             version: version.to_string(),
-            license: license.map(String::from),
+            license: expression
+                .as_ref()
+                .map(ToString::to_string)
+                .or_else(|| license.map(String::from)),
+            expression,
+            invalid,
             source_url: source_url.map(String::from),
             is_copyleft,
         }
@@ -61,6 +363,8 @@ pub struct LicenseReport {
     pub missing_licenses: Vec<String>,
     /// Packages with copyleft licenses
     pub copyleft_packages: Vec<String>,
+    /// Packages whose license expression does not parse
+    pub invalid_licenses: Vec<String>,
 }
 
 impl LicenseReport {
@@ -77,12 +381,58 @@ impl LicenseReport {
         if pkg.is_copyleft {
             self.copyleft_packages.push(pkg.name.clone());
         }
+        if pkg.invalid.is_some() {
+            self.invalid_licenses.push(pkg.name.clone());
+        }
         self.packages.push(pkg);
     }
 
     /// Check if there are any warnings
     pub fn has_warnings(&self) -> bool {
-        !self.missing_licenses.is_empty() || !self.copyleft_packages.is_empty()
+        !self.missing_licenses.is_empty()
+            || !self.copyleft_packages.is_empty()
+            || !self.invalid_licenses.is_empty()
+    }
+
+    /// Warnings for license expressions that do not parse, with the raw string
+    pub fn invalid_license_warnings(&self) -> Vec<String> {
+        self.packages
+            .iter()
+            .filter_map(|pkg| {
+                let error = pkg.invalid.as_ref()?;
+                Some(format!(
+                    "Package '{}' has an invalid license expression '{}': {error}",
+                    pkg.name,
+                    pkg.license.as_deref().unwrap_or_default()
+                ))
+            })
+            .collect()
+    }
+
+    /// Packages whose license terms cannot be met without a `deny` license
+    ///
+    /// An `OR` expression passes when one of its choices is acceptable.
+    /// Packages with a missing or invalid license are not evaluated.
+    pub fn denied_packages(&self, deny: &[String]) -> Result<Vec<&PackageLicense>, LicenseError> {
+        let denied = deny
+            .iter()
+            .map(|entry| match LicenseExpr::parse(entry)? {
+                expr @ LicenseExpr::License { .. } => Ok(expr),
+                _ => Err(syntax(format!(
+                    "deny entry '{entry}' must be a single license"
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(self
+            .packages
+            .iter()
+            .filter(|pkg| {
+                pkg.expression
+                    .as_ref()
+                    .is_some_and(|expr| !expr.is_acceptable(&denied))
+            })
+            .collect())
     }
 
     /// Generate license summary text
@@ -102,7 +452,7 @@ impl LicenseReport {
         }
 
         for (license, pkgs) in &by_license {
-            let copyleft_marker = if is_copyleft_license(license) {
+            let copyleft_marker = if pkgs.iter().any(|pkg| pkg.is_copyleft) {
                 " ⚠️ (copyleft)"
             } else {
                 ""
@@ -127,6 +477,10 @@ impl LicenseReport {
             ));
         }
 
+        for warning in self.invalid_license_warnings() {
+            lines.push(format!("\n⚠️  {warning}"));
+        }
+
         lines.join("\n")
     }
 
@@ -253,6 +607,119 @@ fn load_package_license(path: &Path) -> Option<String> {
         .map(String::from)
 }
 
+/// License files copied for the packages of a project
+#[derive(Debug, Default)]
+pub struct CollectedLicenseFiles {
+    /// Files copied per package, relative to its source tree
+    pub copied: Vec<(String, Vec<PathBuf>)>,
+    /// Packages with no license files in their source tree
+    pub missing: Vec<String>,
+}
+
+/// Copy the license files of each manifest package into `dest/<package>/`
+///
+/// Files come from the extracted sources in `build/src/<package>`, matched
+/// by the package's `license_files` globs or, without any, detected by name
+/// (`COPYING*`, `LICENSE*`, `LICENCE*`, `NOTICE*`) at the top of the tree.
+pub fn collect_license_files(
+    project_dir: &Path,
+    manifest: &Manifest,
+    dest: &Path,
+) -> std::io::Result<CollectedLicenseFiles> {
+    let mut collected = CollectedLicenseFiles::default();
+
+    for name in manifest.packages.keys() {
+        let local_pkg_path = project_dir.join("packages").join(name).join("package.toml");
+        let patterns = load_license_file_patterns(&local_pkg_path);
+        let srcdir = project_dir.join("build").join("src").join(name);
+        let files = find_license_files(&srcdir, &patterns)?;
+        if files.is_empty() {
+            collected.missing.push(name.clone());
+            continue;
+        }
+
+        for file in &files {
+            let target = dest.join(name).join(file);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(srcdir.join(file), &target)?;
+        }
+        collected.copied.push((name.clone(), files));
+    }
+
+    Ok(collected)
+}
+
+/// License files of a source tree, relative to it and sorted
+///
+/// Each pattern component is matched against one path component, so
+/// `LICENSES/*` selects the files directly inside `LICENSES`.
+pub fn find_license_files(srcdir: &Path, patterns: &[String]) -> std::io::Result<Vec<PathBuf>> {
+    if !srcdir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(srcdir).min_depth(1) {
+        let entry = entry.map_err(std::io::Error::from)?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(srcdir).unwrap_or(entry.path());
+        let components: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+
+        let selected = if patterns.is_empty() {
+            components.len() == 1 && {
+                let upper = components[0].to_uppercase();
+                LICENSE_FILE_PREFIXES
+                    .iter()
+                    .any(|prefix| upper.starts_with(prefix))
+            }
+        } else {
+            patterns.iter().any(|pattern| {
+                let parts: Vec<&str> = pattern.split('/').filter(|p| !p.is_empty()).collect();
+                parts.len() == components.len()
+                    && parts
+                        .iter()
+                        .zip(&components)
+                        .all(|(part, component)| matches_glob(part, component))
+            })
+        };
+        if selected {
+            files.push(relative.to_path_buf());
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Load `license_files` globs from a package definition file
+fn load_license_file_patterns(path: &Path) -> Vec<String> {
+    let Some(table) = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| content.parse::<toml::Table>().ok())
+    else {
+        return Vec::new();
+    };
+
+    table
+        .get("package")
+        .and_then(|p| p.get("license_files"))
+        .and_then(|files| files.as_array())
+        .map(|files| {
+            files
+                .iter()
+                .filter_map(|f| f.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -297,6 +764,99 @@ mod tests {
         assert!(summary.contains("Missing license"));
     }
 
+    #[test]
+    fn test_parse_expression_normalizes() {
+        let expr = LicenseExpr::parse("gpl-2.0-or-later with linux-syscall-note or mit").unwrap();
+        assert_eq!(
+            expr.to_string(),
+            "GPL-2.0-or-later WITH Linux-syscall-note OR MIT"
+        );
+
+        let expr = LicenseExpr::parse("(MIT OR Apache-2.0) AND BSD-3-Clause").unwrap();
+        assert_eq!(expr.to_string(), "(MIT OR Apache-2.0) AND BSD-3-Clause");
+
+        let expr = LicenseExpr::parse("LGPL-2.1+ AND LicenseRef-vendor").unwrap();
+        assert_eq!(expr.to_string(), "LGPL-2.1+ AND LicenseRef-vendor");
+    }
+
+    #[test]
+    fn test_parse_expression_rejects_invalid() {
+        assert_eq!(LicenseExpr::parse("  "), Err(LicenseError::Empty));
+        assert_eq!(
+            LicenseExpr::parse("MIT OR Frobnicate-1.0"),
+            Err(LicenseError::UnknownLicense {
+                id: "Frobnicate-1.0".to_string()
+            })
+        );
+        assert!(matches!(
+            LicenseExpr::parse("GPL-2.0-only WITH Nope"),
+            Err(LicenseError::UnknownException { .. })
+        ));
+        assert!(matches!(
+            LicenseExpr::parse("(MIT OR"),
+            Err(LicenseError::Syntax { .. })
+        ));
+        assert!(matches!(
+            LicenseExpr::parse("MIT Apache-2.0"),
+            Err(LicenseError::Syntax { .. })
+        ));
+    }
+
+    #[test]
+    fn test_deny_list_evaluates_expressions() {
+        let mut report = LicenseReport::new();
+        report.add_package(PackageLicense::new(
+            "dual",
+            "1.0",
+            Some("GPL-3.0-only OR MIT"),
+            None,
+        ));
+        report.add_package(PackageLicense::new(
+            "both",
+            "1.0",
+            Some("GPL-3.0-only AND MIT"),
+            None,
+        ));
+        report.add_package(PackageLicense::new(
+            "kernel",
+            "1.0",
+            Some("GPL-2.0-only WITH Linux-syscall-note"),
+            None,
+        ));
+        report.add_package(PackageLicense::new("bad", "1.0", Some("GPL-3 or"), None));
+
+        let deny = vec!["GPL-3.0-only".to_string(), "GPL-2.0-only".to_string()];
+        let denied: Vec<&str> = report
+            .denied_packages(&deny)
+            .unwrap()
+            .iter()
+            .map(|pkg| pkg.name.as_str())
+            .collect();
+        assert_eq!(denied, vec!["both", "kernel"]);
+
+        assert!(!report.packages[0].is_copyleft);
+        assert!(report.packages[1].is_copyleft);
+        assert_eq!(report.invalid_licenses, vec!["bad"]);
+        assert!(report.summary().contains("'GPL-3 or'"));
+        assert!(report.denied_packages(&["MIT OR ISC".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_find_license_files() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(temp.path().join("LICENSES")).unwrap();
+        std::fs::create_dir_all(temp.path().join("doc")).unwrap();
+        for file in ["COPYING", "LICENSES/MIT.txt", "doc/LICENSE", "main.c"] {
+            std::fs::write(temp.path().join(file), "text").unwrap();
+        }
+
+        let detected = find_license_files(temp.path(), &[]).unwrap();
+        assert_eq!(detected, vec![PathBuf::from("COPYING")]);
+
+        let globbed = find_license_files(temp.path(), &["LICENSES/*".to_string()]).unwrap();
+        assert_eq!(globbed, vec![PathBuf::from("LICENSES/MIT.txt")]);
+    }
+
     #[test]
     fn test_license_report_sbom() {
         let mut report = LicenseReport::new();
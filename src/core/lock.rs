@@ -3,7 +3,10 @@
 //! The lock file (zigroot.lock) records exact versions and checksums
 //! for reproducible builds.
 
+use crate::infra::download::Checksum;
+use crate::infra::filesystem::write_file_atomic;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
 use thiserror::Error;
 
@@ -30,6 +33,14 @@ pub enum LockError {
     #[error("Package '{name}' not found in lock file (run without --locked to update)")]
     PackageNotLocked { name: String },
 
+    /// External artifact mismatch in locked mode
+    #[error("External artifact '{name}' differs from lock file: {reason}")]
+    ExternalMismatch { name: String, reason: String },
+
+    /// External artifact not in lock file
+    #[error("External artifact '{name}' not found in lock file (run without --locked to update)")]
+    ExternalNotLocked { name: String },
+
     /// Zig version mismatch (warning, not error)
     #[error("Zig version mismatch: lock file has '{locked}', current is '{current}'")]
     ZigVersionMismatch { locked: String, current: String },
@@ -53,8 +64,10 @@ pub struct LockedPackage {
     pub name: String,
     /// Exact version
     pub version: String,
-    /// SHA256 checksum of source
-    pub sha256: String,
+    /// Checksum of the source as `<algorithm>:<digest>` (a bare digest is
+    /// SHA256), or a placeholder such as "pending" or "local"
+    #[serde(alias = "sha256")]
+    pub checksum: String,
     /// Source URI (omitted for registry, or "path:<path>", "git:<url>#<ref>", "registry:<url>")
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source: Option<String>,
@@ -64,6 +77,16 @@ pub struct LockedPackage {
     /// Git commit SHA (for git sources with branch)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub git_sha: Option<String>,
+    /// Versions of the host tools used to build the package, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub host_tools: BTreeMap<String, String>,
+}
+
+impl LockedPackage {
+    /// The recorded checksum, unless it is a placeholder
+    pub fn parsed_checksum(&self) -> Option<Checksum> {
+        self.checksum.parse().ok()
+    }
 }
 
 /// A locked external artifact
@@ -74,8 +97,9 @@ pub struct LockedExternal {
     /// Artifact type (bootloader, `partition_table`, etc.)
     #[serde(rename = "type")]
     pub artifact_type: String,
-    /// SHA256 checksum
-    pub sha256: String,
+    /// Checksum as `<algorithm>:<digest>` (a bare digest is SHA256)
+    #[serde(alias = "sha256")]
+    pub checksum: String,
     /// Download URL
     pub url: String,
 }
@@ -126,7 +150,7 @@ impl LockFile {
                       # Run 'zigroot update' to update locked versions.\n\n";
         let full_content = format!("{header}{content}");
 
-        std::fs::write(path, full_content).map_err(|e| LockError::IoError {
+        write_file_atomic(path, full_content.as_bytes()).map_err(|e| LockError::IoError {
             path: path.display().to_string(),
             error: e.to_string(),
         })?;
@@ -163,6 +187,13 @@ impl LockFile {
         self.externals.sort_by(|a, b| a.name.cmp(&b.name));
     }
 
+    /// Remove a locked external artifact, returning whether it was locked
+    pub fn remove_external(&mut self, name: &str) -> bool {
+        let before = self.externals.len();
+        self.externals.retain(|e| e.name != name);
+        self.externals.len() != before
+    }
+
     /// Get a locked package by name
     pub fn get_package(&self, name: &str) -> Option<&LockedPackage> {
         self.packages.iter().find(|p| p.name == name)
@@ -186,7 +217,15 @@ impl LockFile {
     }
 
     /// Verify a package matches the lock file (for --locked mode)
-    pub fn verify_package(&self, name: &str, version: &str, sha256: &str) -> Result<(), LockError> {
+    ///
+    /// Checksums are compared by algorithm and digest when both parse, so a
+    /// bare SHA256 digest matches its `sha256:` form.
+    pub fn verify_package(
+        &self,
+        name: &str,
+        version: &str,
+        checksum: &str,
+    ) -> Result<(), LockError> {
         let locked = self
             .get_package(name)
             .ok_or_else(|| LockError::PackageNotLocked {
@@ -203,12 +242,16 @@ impl LockFile {
             });
         }
 
-        if locked.sha256 != sha256 && locked.sha256 != "local" {
+        let matches = match (locked.parsed_checksum(), checksum.parse::<Checksum>()) {
+            (Some(locked), Ok(actual)) => locked == actual,
+            _ => locked.checksum == checksum,
+        };
+        if !matches && locked.checksum != "local" {
             return Err(LockError::PackageMismatch {
                 name: name.to_string(),
                 reason: format!(
                     "checksum mismatch: locked '{}', got '{}'",
-                    locked.sha256, sha256
+                    locked.checksum, checksum
                 ),
             });
         }
@@ -216,6 +259,28 @@ impl LockFile {
         Ok(())
     }
 
+    /// Verify an external artifact against the lock file
+    ///
+    /// The declared checksum must be the locked one, in any spelling of
+    /// the same algorithm and digest.
+    pub fn verify_external(&self, name: &str, checksum: &Checksum) -> Result<(), LockError> {
+        let locked = self
+            .get_external(name)
+            .ok_or_else(|| LockError::ExternalNotLocked {
+                name: name.to_string(),
+            })?;
+        if locked.checksum.parse::<Checksum>().ok().as_ref() != Some(checksum) {
+            return Err(LockError::ExternalMismatch {
+                name: name.to_string(),
+                reason: format!(
+                    "checksum mismatch: locked '{}', got '{checksum}'",
+                    locked.checksum
+                ),
+            });
+        }
+        Ok(())
+    }
+
     /// Get all package names
     pub fn package_names(&self) -> Vec<&str> {
         self.packages.iter().map(|p| p.name.as_str()).collect()
@@ -267,19 +332,23 @@ fn chrono_lite_now() -> String {
 pub struct LockedPackageBuilder {
     name: String,
     version: String,
-    sha256: String,
+    checksum: String,
     source: Option<String>,
     depends: Vec<String>,
     git_sha: Option<String>,
+    host_tools: BTreeMap<String, String>,
 }
 
 impl LockedPackageBuilder {
     /// Create a new builder
-    pub fn new(name: &str, version: &str, sha256: &str) -> Self {
+    ///
+    /// `checksum` is `<algorithm>:<digest>`, a bare SHA256 digest, or a
+    /// placeholder such as "pending".
+    pub fn new(name: &str, version: &str, checksum: &str) -> Self {
         Self {
             name: name.to_string(),
             version: version.to_string(),
-            sha256: sha256.to_string(),
+            checksum: checksum.to_string(),
             ..Default::default()
         }
     }
@@ -302,15 +371,24 @@ impl LockedPackageBuilder {
         self
     }
 
+    /// Record the version of a host tool used for the build
+    #[must_use]
+    pub fn host_tool(mut self, name: &str, version: &str) -> Self {
+        self.host_tools
+            .insert(name.to_string(), version.to_string());
+        self
+    }
+
     /// Build the locked package
     pub fn build(self) -> LockedPackage {
         LockedPackage {
             name: self.name,
             version: self.version,
-            sha256: self.sha256,
+            checksum: self.checksum,
             source: self.source,
             depends: self.depends,
             git_sha: self.git_sha,
+            host_tools: self.host_tools,
         }
     }
 }
@@ -318,6 +396,7 @@ impl LockedPackageBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::infra::download::ChecksumAlgorithm;
     use proptest::prelude::*;
     use tempfile::TempDir;
 
@@ -352,16 +431,18 @@ mod tests {
         lock.add_package(LockedPackage {
             name: "busybox".to_string(),
             version: "1.36.1".to_string(),
-            sha256: "b8cc24c9574d809e7279c3be349795c5d5ceb6fdf19ca709f80cde50e47de314".to_string(),
+            checksum: "b8cc24c9574d809e7279c3be349795c5d5ceb6fdf19ca709f80cde50e47de314"
+                .to_string(),
             source: None,
             depends: vec![],
             git_sha: None,
+            host_tools: BTreeMap::new(),
         });
 
         let pkg = lock.get_package("busybox").unwrap();
         assert_eq!(pkg.version, "1.36.1");
         assert_eq!(
-            pkg.sha256,
+            pkg.checksum,
             "b8cc24c9574d809e7279c3be349795c5d5ceb6fdf19ca709f80cde50e47de314"
         );
     }
@@ -374,7 +455,7 @@ mod tests {
         lock.add_package(LockedPackageBuilder::new("mypackage", "1.0.0", checksum).build());
 
         let pkg = lock.get_package("mypackage").unwrap();
-        assert_eq!(pkg.sha256, checksum);
+        assert_eq!(pkg.checksum, checksum);
     }
 
     // ============================================
@@ -579,6 +660,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_host_tools_in_toml() {
+        let mut lock = LockFile::new("0.1.0", "0.13.0");
+        lock.add_package(
+            LockedPackageBuilder::new("app", "1.0.0", "abc123")
+                .host_tool("cmake", "3.28.1")
+                .build(),
+        );
+        lock.add_package(LockedPackageBuilder::new("lib", "1.0.0", "def456").build());
+
+        let toml = lock.to_toml().unwrap();
+        assert!(toml.contains("cmake = \"3.28.1\""), "{toml}");
+        assert_eq!(toml.matches("host_tools").count(), 1);
+
+        let parsed = LockFile::from_toml(&toml).unwrap();
+        assert_eq!(
+            parsed.get_package("app").unwrap().host_tools["cmake"],
+            "3.28.1"
+        );
+        assert!(parsed.get_package("lib").unwrap().host_tools.is_empty());
+    }
+
     // ============================================
     // Unit Tests - Zig version mismatch produces warning
     // ============================================
@@ -631,7 +734,7 @@ mod tests {
         lock.add_external(LockedExternal {
             name: "u-boot".to_string(),
             artifact_type: "bootloader".to_string(),
-            sha256: "def456".to_string(),
+            checksum: "def456".to_string(),
             url: "https://example.com/u-boot.bin".to_string(),
         });
 
@@ -709,13 +812,71 @@ mod tests {
         lock.add_external(LockedExternal {
             name: "u-boot".to_string(),
             artifact_type: "bootloader".to_string(),
-            sha256: "abc123".to_string(),
+            checksum: "abc123".to_string(),
             url: "https://example.com/u-boot.bin".to_string(),
         });
 
         let ext = lock.get_external("u-boot").unwrap();
         assert_eq!(ext.artifact_type, "bootloader");
-        assert_eq!(ext.sha256, "abc123");
+        assert_eq!(ext.checksum, "abc123");
+        assert!(lock.remove_external("u-boot"));
+        assert!(!lock.remove_external("u-boot"));
+    }
+
+    #[test]
+    fn test_verify_external() {
+        let digest = "a".repeat(64);
+        let mut lock = LockFile::new("0.1.0", "0.13.0");
+        lock.add_external(LockedExternal {
+            name: "u-boot".to_string(),
+            artifact_type: "bootloader".to_string(),
+            checksum: digest.clone(),
+            url: "https://example.com/u-boot.bin".to_string(),
+        });
+
+        let sha256 = Checksum::new(ChecksumAlgorithm::Sha256, &digest).unwrap();
+        assert!(lock.verify_external("u-boot", &sha256).is_ok());
+        let blake3 = Checksum::new(ChecksumAlgorithm::Blake3, &digest).unwrap();
+        assert!(matches!(
+            lock.verify_external("u-boot", &blake3),
+            Err(LockError::ExternalMismatch { .. })
+        ));
+        assert!(matches!(
+            lock.verify_external("dtb", &sha256),
+            Err(LockError::ExternalNotLocked { .. })
+        ));
+    }
+
+    #[test]
+    fn test_lock_file_records_checksum_algorithm() {
+        let digest = "a".repeat(64);
+        let old = format!(
+            "[metadata]\nzigroot_version = \"0.1.0\"\nzig_version = \"0.13.0\"\n\
+             generated = \"2024-01-01T00:00:00Z\"\n\n[[package]]\nname = \"zlib\"\n\
+             version = \"1.3.1\"\nsha256 = \"{digest}\"\n"
+        );
+        let mut lock = LockFile::from_toml(&old).unwrap();
+        assert_eq!(lock.get_package("zlib").unwrap().checksum, digest);
+        assert!(lock
+            .verify_package("zlib", "1.3.1", &format!("sha256:{digest}"))
+            .is_ok());
+        let error = lock
+            .verify_package("zlib", "1.3.1", &format!("blake3:{digest}"))
+            .unwrap_err();
+        assert!(error.to_string().contains("blake3:"), "{error}");
+
+        lock.add_package(
+            LockedPackageBuilder::new("zlib", "1.3.1", &format!("blake3:{digest}")).build(),
+        );
+        let toml = lock.to_toml().unwrap();
+        assert!(
+            toml.contains(&format!("checksum = \"blake3:{digest}\"")),
+            "{toml}"
+        );
+        assert!(LockedPackageBuilder::new("x", "1", "pending")
+            .build()
+            .parsed_checksum()
+            .is_none());
     }
 
     // ============================================
@@ -812,7 +973,7 @@ mod tests {
             let parsed_pkg = parsed.get_package(&name).unwrap();
 
             prop_assert_eq!(&original_pkg.version, &parsed_pkg.version);
-            prop_assert_eq!(&original_pkg.sha256, &parsed_pkg.sha256);
+            prop_assert_eq!(&original_pkg.checksum, &parsed_pkg.checksum);
         }
 
         /// Property: Package names are unique in lock file
@@ -7,11 +7,187 @@
 
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Once;
+use thiserror::Error;
+use toml_edit::{DocumentMut, TableLike};
+
+use crate::core::fit::FitConfig;
+use crate::core::flash::FlashConfig;
+use crate::core::hooks::HooksConfig;
+use crate::core::libc::Libc;
+use crate::core::license::LicenseConfig;
+use crate::core::partition::{DiskImageConfig, PartitionSpec};
+use crate::core::permissions::PermissionEntry;
+use crate::core::version::{VersionError, CURRENT_VERSION};
+use crate::error::DownloadError;
+use crate::infra::download::Checksum;
+
+/// Manifest schema version written by this zigroot
+pub const MANIFEST_VERSION: u32 = 2;
+
+/// A field renamed between manifest schema versions
+#[derive(Debug, Clone, Copy)]
+pub struct FieldRename {
+    /// Schema version that introduced the new name
+    pub version: u32,
+    /// Dotted path of the containing table (`*` matches every entry)
+    pub table: &'static str,
+    /// Deprecated field name
+    pub old: &'static str,
+    /// Current field name
+    pub new: &'static str,
+}
+
+/// Fields renamed since manifest version 1
+pub const RENAMED_FIELDS: &[FieldRename] = &[FieldRename {
+    version: 2,
+    table: "packages.*",
+    old: "ref_",
+    new: "ref",
+}];
+
+/// Errors migrating a manifest to the current schema
+#[derive(Error, Debug, PartialEq)]
+pub enum MigrationError {
+    /// The manifest is not valid TOML
+    #[error("Invalid TOML: {0}")]
+    Syntax(String),
+
+    /// `manifest_version` is not a positive integer
+    #[error("Invalid manifest_version '{0}': expected a positive integer")]
+    InvalidVersion(String),
+
+    /// The manifest needs a newer zigroot
+    #[error(transparent)]
+    Version(#[from] VersionError),
+}
+
+/// Result of migrating a manifest to the current schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestMigration {
+    /// Schema version of the original manifest
+    pub from_version: u32,
+    /// Renamed fields as (old path, new path)
+    pub renamed: Vec<(String, String)>,
+    /// Migrated manifest content, with comments and key order preserved
+    pub content: String,
+}
+
+impl ManifestMigration {
+    /// Whether the manifest was already in the current schema
+    pub fn is_current(&self) -> bool {
+        self.from_version == MANIFEST_VERSION && self.renamed.is_empty()
+    }
+}
+
+/// Migrate manifest content to the current schema version.
+///
+/// Manifests without `manifest_version` are version 1. Deprecated field
+/// names are mapped to their current equivalents; a manifest newer than
+/// this zigroot supports is rejected.
+pub fn migrate_manifest(content: &str) -> Result<ManifestMigration, MigrationError> {
+    let mut doc: DocumentMut = content
+        .parse()
+        .map_err(|e: toml_edit::TomlError| MigrationError::Syntax(e.to_string()))?;
+
+    let from_version = match doc.get("manifest_version") {
+        None => 1,
+        Some(item) => item
+            .as_integer()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v >= 1)
+            .ok_or_else(|| MigrationError::InvalidVersion(item.to_string().trim().to_string()))?,
+    };
+    if from_version > MANIFEST_VERSION {
+        return Err(VersionError::ManifestTooNew {
+            found: from_version,
+            supported: MANIFEST_VERSION,
+            current: CURRENT_VERSION.to_string(),
+        }
+        .into());
+    }
+
+    let mut renamed = Vec::new();
+    for rename in RENAMED_FIELDS.iter().filter(|r| r.version > from_version) {
+        let path: Vec<&str> = rename.table.split('.').collect();
+        rename_field(doc.as_table_mut(), &path, "", rename, &mut renamed);
+    }
+    if from_version < MANIFEST_VERSION {
+        doc.insert(
+            "manifest_version",
+            toml_edit::value(i64::from(MANIFEST_VERSION)),
+        );
+    }
+
+    Ok(ManifestMigration {
+        from_version,
+        renamed,
+        content: doc.to_string(),
+    })
+}
+
+/// Apply a rename to every table matching `path`
+fn rename_field(
+    table: &mut dyn TableLike,
+    path: &[&str],
+    prefix: &str,
+    rename: &FieldRename,
+    renamed: &mut Vec<(String, String)>,
+) {
+    let Some((segment, rest)) = path.split_first() else {
+        if let Some(item) = table.remove(rename.old) {
+            // An explicit new-style value wins over the deprecated one
+            if !table.contains_key(rename.new) {
+                table.insert(rename.new, item);
+            }
+            renamed.push((
+                format!("{prefix}{}", rename.old),
+                format!("{prefix}{}", rename.new),
+            ));
+        }
+        return;
+    };
+
+    let keys: Vec<String> = if *segment == "*" {
+        table.iter().map(|(key, _)| key.to_string()).collect()
+    } else {
+        vec![(*segment).to_string()]
+    };
+    for key in keys {
+        if let Some(child) = table
+            .get_mut(&key)
+            .and_then(toml_edit::Item::as_table_like_mut)
+        {
+            rename_field(child, rest, &format!("{prefix}{key}."), rename, renamed);
+        }
+    }
+}
+
+/// Warn about migrated fields, once per process
+fn warn_migrated(migration: &ManifestMigration) {
+    static WARNED: Once = Once::new();
+    WARNED.call_once(|| {
+        let fields: Vec<String> = migration
+            .renamed
+            .iter()
+            .map(|(old, new)| format!("{old} -> {new}"))
+            .collect();
+        tracing::warn!(
+            "zigroot.toml uses manifest_version {} with deprecated fields ({}). Run 'zigroot migrate' to update it.",
+            migration.from_version,
+            fields.join(", ")
+        );
+    });
+}
 
 /// The main project manifest (zigroot.toml)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Manifest {
+    /// Manifest schema version
+    #[serde(default = "default_manifest_version")]
+    pub manifest_version: u32,
+
     /// Project configuration
     pub project: ProjectConfig,
 
@@ -30,6 +206,34 @@ pub struct Manifest {
     /// External artifacts
     #[serde(default)]
     pub external: HashMap<String, ExternalArtifact>,
+
+    /// FIT image generation (overrides the board's `[fit]`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fit: Option<FitConfig>,
+
+    /// Disk partition layout (overrides the board's)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub partitions: Vec<PartitionSpec>,
+
+    /// Disk image settings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_image: Option<DiskImageConfig>,
+
+    /// Flash settings (`[flash.ssh]` overrides the board's SSH profile)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flash: Option<FlashConfig>,
+
+    /// Ownership and mode of rootfs paths, recorded in the image
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub permissions: BTreeMap<String, PermissionEntry>,
+
+    /// License policy checked by `zigroot license`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<LicenseConfig>,
+
+    /// Commands run at build events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<HooksConfig>,
 }
 
 /// Project-level configuration
@@ -45,6 +249,14 @@ pub struct ProjectConfig {
     /// Project description
     #[serde(default)]
     pub description: Option<String>,
+
+    /// Reject undefined environment variables even in version 1 manifests
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict_env: bool,
+}
+
+fn default_manifest_version() -> u32 {
+    MANIFEST_VERSION
 }
 
 fn default_version() -> String {
@@ -60,58 +272,150 @@ pub struct BoardConfig {
     /// Board options overrides
     #[serde(default)]
     pub options: HashMap<String, toml::Value>,
+
+    /// C library override (musl, glibc, uclibc)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub libc: Option<Libc>,
 }
 
 /// Build configuration
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct BuildConfig {
     /// Enable binary compression
     #[serde(default)]
     pub compress: bool,
 
+    /// File name patterns excluded from compression (e.g., "busybox", "lib*")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compress_exclude: Vec<String>,
+
     /// Image format (ext4, squashfs, initramfs)
     #[serde(default = "default_image_format")]
     pub image_format: String,
 
-    /// Root filesystem size
+    /// Initramfs cpio compression (none, gzip, xz, zstd, lz4)
+    #[serde(default = "default_initramfs_compression")]
+    pub initramfs_compression: String,
+
+    /// Embed the initramfs into the zigroot-built kernel image
+    #[serde(default)]
+    pub embed_in_kernel: bool,
+
+    /// Size of an ext4 rootfs, or `auto` to size it to the content (unused
+    /// for squashfs and initramfs, which are always sized by their content)
     #[serde(default = "default_rootfs_size")]
     pub rootfs_size: String,
 
+    /// Free space added to an `auto` rootfs size, in percent of the content
+    #[serde(default = "default_rootfs_slack")]
+    pub rootfs_slack: u32,
+
     /// Hostname for the target system
     #[serde(default = "default_hostname")]
     pub hostname: String,
 
-    /// Number of parallel jobs
+    /// Number of parallel jobs (unset or 0: all CPUs)
     #[serde(default)]
     pub jobs: Option<usize>,
 
-    /// Enable container isolation for builds
+    /// Build packages in a namespace sandbox without network access
     /// **Validates: Requirement 27.3**
     #[serde(default)]
     pub sandbox: Option<bool>,
+
+    /// Maximum image size (e.g., 64M); the build fails when exceeded
+    #[serde(default)]
+    pub size_budget: Option<String>,
+
+    /// Strip symbols from binaries before installing into the rootfs
+    #[serde(default = "default_strip")]
+    pub strip: bool,
+
+    /// Keep debug info in separate files under debug/ in the output directory
+    #[serde(default)]
+    pub split_debug: bool,
+
+    /// Normalize timestamps and tool output so identical inputs give identical images
+    #[serde(default)]
+    pub reproducible: bool,
+
+    /// Timestamp of reproducible builds (derived from the lock file if unset)
+    #[serde(default)]
+    pub source_date_epoch: Option<u64>,
+
+    /// Directory for build outputs, relative to the project (default: output)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_dir: Option<String>,
+
+    /// Init entry point checked by rootfs validation (default: /sbin/init or /init)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub init: Option<String>,
+
+    /// Severity of rootfs validation checks by name (error, warning, ignore)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub validation: HashMap<String, String>,
 }
 
 fn default_image_format() -> String {
     "ext4".to_string()
 }
 
+fn default_initramfs_compression() -> String {
+    "none".to_string()
+}
+
 fn default_rootfs_size() -> String {
     "256M".to_string()
 }
 
+fn default_rootfs_slack() -> u32 {
+    crate::config::defaults::DEFAULT_ROOTFS_SLACK
+}
+
 fn default_hostname() -> String {
     "zigroot".to_string()
 }
 
+fn default_strip() -> bool {
+    true
+}
+
+impl BuildConfig {
+    /// File name of the rootfs image in the output directory
+    pub fn image_file_name(&self) -> String {
+        match self.image_format.as_str() {
+            "squashfs" => "rootfs.squashfs".to_string(),
+            "initramfs" => format!(
+                "rootfs.cpio{}",
+                crate::core::builder::initramfs_extension(&self.initramfs_compression)
+            ),
+            _ => "rootfs.img".to_string(),
+        }
+    }
+}
+
 impl Default for BuildConfig {
     fn default() -> Self {
         Self {
             compress: false,
+            compress_exclude: Vec::new(),
             image_format: default_image_format(),
+            initramfs_compression: default_initramfs_compression(),
+            embed_in_kernel: false,
             rootfs_size: default_rootfs_size(),
+            rootfs_slack: default_rootfs_slack(),
             hostname: default_hostname(),
             jobs: None,
             sandbox: None,
+            size_budget: None,
+            strip: default_strip(),
+            split_debug: false,
+            reproducible: false,
+            source_date_epoch: None,
+            output_dir: None,
+            init: None,
+            validation: HashMap::new(),
         }
     }
 }
@@ -122,6 +426,7 @@ impl Default for ProjectConfig {
             name: "unnamed".to_string(),
             version: default_version(),
             description: None,
+            strict_env: false,
         }
     }
 }
@@ -129,11 +434,19 @@ impl Default for ProjectConfig {
 impl Default for Manifest {
     fn default() -> Self {
         Self {
+            manifest_version: MANIFEST_VERSION,
             project: ProjectConfig::default(),
             board: BoardConfig::default(),
             build: BuildConfig::default(),
             packages: HashMap::new(),
             external: HashMap::new(),
+            fit: None,
+            partitions: Vec::new(),
+            disk_image: None,
+            flash: None,
+            permissions: BTreeMap::new(),
+            license: None,
+            hooks: None,
         }
     }
 }
@@ -150,7 +463,7 @@ pub struct PackageRef {
     pub git: Option<String>,
 
     /// Git ref (tag, branch, or rev)
-    #[serde(default)]
+    #[serde(default, rename = "ref")]
     pub ref_: Option<String>,
 
     /// Custom registry URL
@@ -173,29 +486,60 @@ pub struct ExternalArtifact {
     #[serde(default)]
     pub url: Option<String>,
 
+    /// Mirrors serving the same file, tried in order when `url` fails
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+
     /// Local path
     #[serde(default)]
     pub path: Option<String>,
 
-    /// SHA256 checksum (required for URL sources)
+    /// SHA256 checksum (URL sources require exactly one checksum)
     #[serde(default)]
     pub sha256: Option<String>,
 
+    /// SHA512 checksum
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha512: Option<String>,
+
+    /// BLAKE3 checksum
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blake3: Option<String>,
+
     /// Partition table format (gpt, mbr, rockchip)
     #[serde(default)]
     pub format: Option<String>,
 }
 
+impl ExternalArtifact {
+    /// The declared checksum, `None` if no checksum field is set
+    ///
+    /// Fails if several are set or the digest is malformed.
+    pub fn checksum(&self) -> Result<Option<Checksum>, DownloadError> {
+        Checksum::from_fields(
+            self.sha256.as_deref(),
+            self.sha512.as_deref(),
+            self.blake3.as_deref(),
+        )
+    }
+}
+
 /// Substitute environment variables in a string using ${VAR} syntax.
 ///
 /// **Validates: Requirement 11.2**
 ///
+/// Supported forms:
+/// - `${VAR}` - value of VAR (empty if unset)
+/// - `${VAR:-default}` - value of VAR, or `default` if unset or empty
+/// - `${VAR:?message}` - value of VAR, or an error with `message` if unset or empty
+/// - `$${VAR}` - the literal text `${VAR}`
+///
 /// # Arguments
 /// * `input` - The string containing ${VAR} patterns to substitute
 ///
 /// # Returns
 /// * `Ok(String)` - The string with all ${VAR} patterns replaced with their values
-/// * `Err(String)` - Error message if substitution fails (e.g., malformed syntax)
+/// * `Err(String)` - Error message if substitution fails (e.g., a required variable is unset)
 ///
 /// # Examples
 /// ```
@@ -207,25 +551,60 @@ pub struct ExternalArtifact {
 /// std::env::remove_var("MY_VAR");
 /// ```
 pub fn substitute_env_vars(input: &str) -> Result<String, String> {
-    // Regex to match ${VAR_NAME} pattern
-    let re =
-        Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").map_err(|e| format!("Invalid regex: {e}"))?;
+    expand_env_vars(input, false)
+}
+
+/// Substitute environment variables, optionally rejecting unset `${VAR}`.
+///
+/// With `strict`, a plain `${VAR}` whose variable is not set is an error
+/// instead of expanding to an empty string.
+pub fn expand_env_vars(input: &str, strict: bool) -> Result<String, String> {
+    // Optional leading `$` escapes the whole sequence
+    let re = Regex::new(r"(\$)?\$\{([A-Za-z_][A-Za-z0-9_]*)(?::([-?])([^}]*))?\}")
+        .map_err(|e| format!("Invalid regex: {e}"))?;
 
     let mut last_end = 0;
     let mut output = String::new();
 
     for cap in re.captures_iter(input) {
         let full_match = cap.get(0).unwrap();
-        let var_name = &cap[1];
+        let var_name = &cap[2];
 
         // Append text before this match
         output.push_str(&input[last_end..full_match.start()]);
+        last_end = full_match.end();
 
-        // Get environment variable value (empty string if not set)
-        let value = std::env::var(var_name).unwrap_or_default();
-        output.push_str(&value);
+        if cap.get(1).is_some() {
+            output.push_str(&full_match.as_str()[1..]);
+            continue;
+        }
 
-        last_end = full_match.end();
+        let value = std::env::var(var_name).ok();
+        let operand = cap.get(4).map_or("", |m| m.as_str());
+        let value = match cap.get(3).map(|m| m.as_str()) {
+            Some("-") => value
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| operand.to_string()),
+            Some(_) => match value.filter(|v| !v.is_empty()) {
+                Some(v) => v,
+                None if operand.is_empty() => {
+                    return Err(format!("Environment variable '{var_name}' is required"));
+                }
+                None => {
+                    return Err(format!(
+                        "Environment variable '{var_name}' is required: {operand}"
+                    ));
+                }
+            },
+            None => match value {
+                Some(v) => v,
+                None if strict => {
+                    return Err(format!("Environment variable '{var_name}' is not set"));
+                }
+                None => String::new(),
+            },
+        };
+        output.push_str(&value);
     }
 
     // Append remaining text after last match
@@ -237,32 +616,52 @@ pub fn substitute_env_vars(input: &str) -> Result<String, String> {
 /// Substitute environment variables in all string values of a TOML content.
 ///
 /// **Validates: Requirement 11.2**
+///
+/// Unset `${VAR}` references are errors for `manifest_version >= 2` or when
+/// `[project] strict_env = true`; older manifests expand them to empty strings.
 fn substitute_env_vars_in_toml(content: &str) -> Result<String, String> {
     // Parse as TOML value first to handle structure
     let mut value: toml::Value =
         toml::from_str(content).map_err(|e| format!("Failed to parse TOML: {e}"))?;
 
+    let strict = value
+        .get("manifest_version")
+        .and_then(toml::Value::as_integer)
+        .is_some_and(|v| v >= 2)
+        || value
+            .get("project")
+            .and_then(|p| p.get("strict_env"))
+            .and_then(toml::Value::as_bool)
+            .unwrap_or(false);
+
     // Recursively substitute in all string values
-    substitute_in_value(&mut value)?;
+    substitute_in_value(&mut value, "", strict)?;
 
     // Serialize back to TOML
     toml::to_string_pretty(&value).map_err(|e| format!("Failed to serialize TOML: {e}"))
 }
 
 /// Recursively substitute environment variables in a TOML value
-fn substitute_in_value(value: &mut toml::Value) -> Result<(), String> {
+///
+/// `key` is the dotted path of the value, used in error messages.
+fn substitute_in_value(value: &mut toml::Value, key: &str, strict: bool) -> Result<(), String> {
     match value {
         toml::Value::String(s) => {
-            *s = substitute_env_vars(s)?;
+            *s = expand_env_vars(s, strict).map_err(|e| format!("{e} (in '{key}')"))?;
         }
         toml::Value::Array(arr) => {
-            for item in arr.iter_mut() {
-                substitute_in_value(item)?;
+            for (i, item) in arr.iter_mut().enumerate() {
+                substitute_in_value(item, &format!("{key}[{i}]"), strict)?;
             }
         }
         toml::Value::Table(table) => {
-            for (_, v) in table.iter_mut() {
-                substitute_in_value(v)?;
+            for (k, v) in table.iter_mut() {
+                let path = if key.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{key}.{k}")
+                };
+                substitute_in_value(v, &path, strict)?;
             }
         }
         _ => {} // Other types (integers, booleans, etc.) don't need substitution
@@ -391,8 +790,19 @@ impl Manifest {
     }
 
     /// Load manifest from TOML string
+    ///
+    /// Older schema versions are migrated in memory first.
     pub fn from_toml(content: &str) -> Result<Self, toml::de::Error> {
-        toml::from_str(content)
+        let migration = match migrate_manifest(content) {
+            Ok(migration) => migration,
+            // Let the TOML parser report syntax errors with their usual location
+            Err(MigrationError::Syntax(_)) => return toml::from_str(content),
+            Err(e) => return Err(serde::de::Error::custom(e)),
+        };
+        if !migration.renamed.is_empty() {
+            warn_migrated(&migration);
+        }
+        toml::from_str(&migration.content)
     }
 
     /// Serialize manifest to TOML string
@@ -402,7 +812,10 @@ impl Manifest {
 }
 
 /// Valid image formats for the build configuration
-const VALID_IMAGE_FORMATS: &[&str] = &["ext4", "squashfs", "initramfs"];
+pub const VALID_IMAGE_FORMATS: &[&str] = &["ext4", "squashfs", "initramfs"];
+
+/// Valid initramfs compression methods for the build configuration
+pub const VALID_INITRAMFS_COMPRESSIONS: &[&str] = &["none", "gzip", "xz", "zstd", "lz4"];
 
 /// Validate a manifest file and report all errors.
 ///
@@ -472,11 +885,27 @@ pub fn validate_manifest(path: &std::path::Path) -> Result<(), Vec<String>> {
             }
         }
 
+        // Validate initramfs_compression if present
+        if let Some(compression) = build.get("initramfs_compression").and_then(|v| v.as_str()) {
+            if !VALID_INITRAMFS_COMPRESSIONS.contains(&compression) {
+                errors.push(format!(
+                    "Invalid initramfs_compression '{compression}': must be one of {VALID_INITRAMFS_COMPRESSIONS:?}"
+                ));
+            }
+        }
+
+        // Embedding only applies to initramfs images
+        if build.get("embed_in_kernel").and_then(toml::Value::as_bool) == Some(true)
+            && build.get("image_format").and_then(|v| v.as_str()) != Some("initramfs")
+        {
+            errors.push("build.embed_in_kernel requires image_format = \"initramfs\"".to_string());
+        }
+
         // Validate rootfs_size format if present
         if let Some(size) = build.get("rootfs_size").and_then(|v| v.as_str()) {
             if !is_valid_size_format(size) {
                 errors.push(format!(
-                    "Invalid rootfs_size '{}': expected format like '256M' or '1G'",
+                    "Invalid rootfs_size '{}': expected format like '256M', '1G' or 'auto'",
                     size
                 ));
             }
@@ -502,10 +931,10 @@ pub fn validate_manifest(path: &std::path::Path) -> Result<(), Vec<String>> {
     }
 }
 
-/// Check if a size string is in valid format (e.g., "256M", "1G", "512K")
-fn is_valid_size_format(size: &str) -> bool {
+/// Check if a size string is in valid format (e.g., "256M", "1G", "512K" or "auto")
+pub fn is_valid_size_format(size: &str) -> bool {
     let re = Regex::new(r"^\d+[KMG]$").unwrap();
-    re.is_match(size)
+    size == crate::core::size::AUTO_SIZE || re.is_match(size)
 }
 
 #[cfg(test)]
@@ -520,18 +949,28 @@ mod tests {
     #[test]
     fn test_manifest_serializes_to_valid_toml() {
         let manifest = Manifest {
+            manifest_version: MANIFEST_VERSION,
             project: ProjectConfig {
                 name: "test-project".to_string(),
                 version: "1.0.0".to_string(),
                 description: Some("A test project".to_string()),
+                strict_env: false,
             },
             board: BoardConfig {
                 name: Some("test-board".to_string()),
                 options: HashMap::new(),
+                libc: None,
             },
             build: BuildConfig::default(),
             packages: HashMap::new(),
             external: HashMap::new(),
+            fit: None,
+            partitions: Vec::new(),
+            disk_image: None,
+            flash: None,
+            permissions: BTreeMap::new(),
+            license: None,
+            hooks: None,
         };
 
         let toml_str = manifest.to_toml().expect("Failed to serialize");
@@ -591,18 +1030,28 @@ sha256 = "abc123"
     #[test]
     fn test_manifest_roundtrip_basic() {
         let manifest = Manifest {
+            manifest_version: MANIFEST_VERSION,
             project: ProjectConfig {
                 name: "test-project".to_string(),
                 version: "1.0.0".to_string(),
                 description: Some("A test project".to_string()),
+                strict_env: false,
             },
             board: BoardConfig {
                 name: Some("test-board".to_string()),
                 options: HashMap::new(),
+                libc: None,
             },
             build: BuildConfig::default(),
             packages: HashMap::new(),
             external: HashMap::new(),
+            fit: None,
+            partitions: Vec::new(),
+            disk_image: None,
+            flash: None,
+            permissions: BTreeMap::new(),
+            license: None,
+            hooks: None,
         };
 
         let toml_str = manifest.to_toml().expect("Failed to serialize");
@@ -671,32 +1120,57 @@ compress = false
             ExternalArtifact {
                 artifact_type: "bootloader".to_string(),
                 url: Some("https://example.com/uboot.bin".to_string()),
+                mirrors: Vec::new(),
                 path: None,
                 sha256: Some("abc123def456".to_string()),
+                sha512: None,
+                blake3: None,
                 format: None,
             },
         );
 
         let manifest = Manifest {
+            manifest_version: MANIFEST_VERSION,
             project: ProjectConfig {
                 name: "complex-project".to_string(),
                 version: "1.0.0".to_string(),
                 description: None,
+                strict_env: false,
             },
             board: BoardConfig {
                 name: Some("rpi4".to_string()),
                 options: HashMap::new(),
+                libc: None,
             },
             build: BuildConfig {
                 compress: true,
+                compress_exclude: Vec::new(),
                 image_format: "squashfs".to_string(),
+                initramfs_compression: "none".to_string(),
+                embed_in_kernel: false,
                 rootfs_size: "64M".to_string(),
+                rootfs_slack: 20,
                 hostname: "mydevice".to_string(),
                 jobs: Some(4),
                 sandbox: None,
+                size_budget: None,
+                strip: true,
+                split_debug: false,
+                reproducible: false,
+                source_date_epoch: None,
+                output_dir: None,
+                init: None,
+                validation: HashMap::new(),
             },
             packages,
             external,
+            fit: None,
+            partitions: Vec::new(),
+            disk_image: None,
+            flash: None,
+            permissions: BTreeMap::new(),
+            license: None,
+            hooks: None,
         };
 
         let toml_str = manifest.to_toml().expect("Failed to serialize");
@@ -720,6 +1194,193 @@ name = "minimal-project"
         assert_eq!(manifest.build.rootfs_size, "256M");
         assert_eq!(manifest.build.hostname, "zigroot");
         assert!(!manifest.build.compress);
+        assert!(manifest.build.strip);
+        assert!(!manifest.build.split_debug);
+        assert_eq!(manifest.build.initramfs_compression, "none");
+        assert!(!manifest.build.embed_in_kernel);
+    }
+
+    #[test]
+    fn test_migrate_covers_all_renamed_fields() {
+        for rename in RENAMED_FIELDS {
+            let header = rename.table.replace('*', "demo");
+            let content = format!(
+                "[project]\nname = \"p\"\n\n[{header}]\n{} = \"value\"\n",
+                rename.old
+            );
+
+            let migration = migrate_manifest(&content).expect("migration failed");
+            assert_eq!(migration.from_version, 1);
+
+            let value: toml::Value = toml::from_str(&migration.content).unwrap();
+            let table = header
+                .split('.')
+                .fold(&value, |v, key| v.get(key).expect("missing table"));
+            assert_eq!(
+                table.get(rename.new).and_then(toml::Value::as_str),
+                Some("value"),
+                "{} was not renamed to {}",
+                rename.old,
+                rename.new
+            );
+            assert!(table.get(rename.old).is_none());
+            assert_eq!(
+                value
+                    .get("manifest_version")
+                    .and_then(toml::Value::as_integer),
+                Some(i64::from(MANIFEST_VERSION))
+            );
+        }
+    }
+
+    #[test]
+    fn test_migrate_v1_round_trip() {
+        let v1 = r#"# My project
+[project]
+name = "legacy"
+
+[packages.dropbear]
+# Pinned release
+git = "https://github.com/example/dropbear"
+ref_ = "v2024.85"
+
+[packages]
+tool = { git = "https://github.com/example/tool", ref_ = "main" }
+"#;
+
+        let migration = migrate_manifest(v1).unwrap();
+        assert_eq!(migration.from_version, 1);
+        assert_eq!(
+            migration.renamed,
+            vec![
+                (
+                    "packages.dropbear.ref_".to_string(),
+                    "packages.dropbear.ref".to_string()
+                ),
+                (
+                    "packages.tool.ref_".to_string(),
+                    "packages.tool.ref".to_string()
+                ),
+            ]
+        );
+        assert!(migration.content.contains("# My project"));
+        assert!(migration.content.contains("# Pinned release"));
+        assert!(!migration.content.contains("ref_"));
+
+        // The migrated manifest loads the same as the original
+        let original = Manifest::from_toml(v1).unwrap();
+        let migrated = Manifest::from_toml(&migration.content).unwrap();
+        assert_eq!(original, migrated);
+        assert_eq!(
+            migrated.packages["dropbear"].ref_.as_deref(),
+            Some("v2024.85")
+        );
+        assert_eq!(migrated.packages["tool"].ref_.as_deref(), Some("main"));
+
+        // Migrating again is a no-op
+        let again = migrate_manifest(&migration.content).unwrap();
+        assert!(again.is_current());
+        assert_eq!(again.content, migration.content);
+
+        // Serializing writes the current schema
+        let serialized = migrated.to_toml().unwrap();
+        assert!(migrate_manifest(&serialized).unwrap().is_current());
+        assert_eq!(Manifest::from_toml(&serialized).unwrap(), migrated);
+    }
+
+    #[test]
+    fn test_migrate_keeps_new_field_over_deprecated() {
+        let content = "[project]\nname = \"p\"\n\n[packages.x]\nref_ = \"old\"\nref = \"new\"\n";
+        let manifest = Manifest::from_toml(content).unwrap();
+        assert_eq!(manifest.packages["x"].ref_.as_deref(), Some("new"));
+    }
+
+    #[test]
+    fn test_manifest_from_newer_zigroot_is_rejected() {
+        let content = "manifest_version = 99\n\n[project]\nname = \"p\"\n";
+        let err = migrate_manifest(content).unwrap_err();
+        assert!(matches!(
+            err,
+            MigrationError::Version(VersionError::ManifestTooNew { found: 99, .. })
+        ));
+
+        let message = Manifest::from_toml(content).unwrap_err().to_string();
+        assert!(message.contains("manifest_version 99"));
+        assert!(message.contains("Please update zigroot"));
+    }
+
+    #[test]
+    fn test_invalid_manifest_version() {
+        for version in ["0", "-1", "\"two\""] {
+            let content = format!("manifest_version = {version}\n\n[project]\nname = \"p\"\n");
+            assert!(matches!(
+                migrate_manifest(&content),
+                Err(MigrationError::InvalidVersion(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_env_default_form() {
+        std::env::remove_var("ZIGROOT_UNIT_DEFAULT");
+        assert_eq!(
+            substitute_env_vars("${ZIGROOT_UNIT_DEFAULT:-fallback}").unwrap(),
+            "fallback"
+        );
+        std::env::set_var("ZIGROOT_UNIT_DEFAULT", "");
+        assert_eq!(
+            substitute_env_vars("${ZIGROOT_UNIT_DEFAULT:-fallback}").unwrap(),
+            "fallback"
+        );
+        std::env::set_var("ZIGROOT_UNIT_DEFAULT", "set");
+        assert_eq!(
+            substitute_env_vars("${ZIGROOT_UNIT_DEFAULT:-fallback}").unwrap(),
+            "set"
+        );
+        std::env::remove_var("ZIGROOT_UNIT_DEFAULT");
+    }
+
+    #[test]
+    fn test_env_required_form() {
+        std::env::remove_var("ZIGROOT_UNIT_REQUIRED");
+        let err = substitute_env_vars("${ZIGROOT_UNIT_REQUIRED:?set the image key}").unwrap_err();
+        assert!(err.contains("ZIGROOT_UNIT_REQUIRED"));
+        assert!(err.contains("set the image key"));
+
+        std::env::set_var("ZIGROOT_UNIT_REQUIRED", "key");
+        assert_eq!(
+            substitute_env_vars("${ZIGROOT_UNIT_REQUIRED:?set the image key}").unwrap(),
+            "key"
+        );
+        std::env::remove_var("ZIGROOT_UNIT_REQUIRED");
+    }
+
+    #[test]
+    fn test_env_escape() {
+        assert_eq!(
+            expand_env_vars("cost: $${PRICE} and $$", true).unwrap(),
+            "cost: ${PRICE} and $$"
+        );
+    }
+
+    #[test]
+    fn test_unset_env_var_strictness() {
+        std::env::remove_var("ZIGROOT_UNIT_UNSET");
+        let v1 = "[project]\nname = \"p\"\n\n[external.kernel]\ntype = \"kernel\"\nsha256 = \"${ZIGROOT_UNIT_UNSET}\"\n";
+
+        // Version 1 manifests keep the lenient behavior
+        let lenient = substitute_env_vars_in_toml(v1).unwrap();
+        assert!(lenient.contains("sha256 = \"\""));
+
+        // Version 2 manifests name the variable and the key
+        let v2 = format!("manifest_version = 2\n{v1}");
+        let err = substitute_env_vars_in_toml(&v2).unwrap_err();
+        assert!(err.contains("'ZIGROOT_UNIT_UNSET' is not set"), "{err}");
+        assert!(err.contains("external.kernel.sha256"), "{err}");
+
+        // strict_env opts version 1 manifests in
+        let opted_in = v1.replace("name = \"p\"", "name = \"p\"\nstrict_env = true");
+        assert!(substitute_env_vars_in_toml(&opted_in).is_err());
     }
 
     // ============================================
@@ -788,25 +1449,47 @@ name = "minimal-project"
                     jobs,
                 )| {
                     Manifest {
+                        manifest_version: MANIFEST_VERSION,
                         project: ProjectConfig {
                             name,
                             version,
                             description,
+                            strict_env: false,
                         },
                         board: BoardConfig {
                             name: board_name,
                             options: HashMap::new(),
+                            libc: None,
                         },
                         build: BuildConfig {
                             compress,
+                            compress_exclude: Vec::new(),
                             image_format,
+                            initramfs_compression: "none".to_string(),
+                            embed_in_kernel: false,
                             rootfs_size,
+                            rootfs_slack: 20,
                             hostname,
                             jobs,
                             sandbox: None,
+                            size_budget: None,
+                            strip: true,
+                            split_debug: false,
+                            reproducible: false,
+                            source_date_epoch: None,
+                            output_dir: None,
+                            init: None,
+                            validation: HashMap::new(),
                         },
                         packages: HashMap::new(),
                         external: HashMap::new(),
+                        fit: None,
+                        partitions: Vec::new(),
+                        disk_image: None,
+                        flash: None,
+                        permissions: BTreeMap::new(),
+                        license: None,
+                        hooks: None,
                     }
                 },
             )
@@ -853,15 +1536,24 @@ name = "minimal-project"
         #[test]
         fn prop_project_name_preserved(name in project_name_strategy()) {
             let manifest = Manifest {
+                manifest_version: MANIFEST_VERSION,
                 project: ProjectConfig {
                     name: name.clone(),
                     version: "1.0.0".to_string(),
                     description: None,
+                    strict_env: false,
                 },
                 board: BoardConfig::default(),
                 build: BuildConfig::default(),
                 packages: HashMap::new(),
                 external: HashMap::new(),
+                fit: None,
+                partitions: Vec::new(),
+                disk_image: None,
+                flash: None,
+                permissions: BTreeMap::new(),
+                license: None,
+                hooks: None,
             };
 
             let toml_str = manifest.to_toml().expect("Should serialize");
@@ -869,5 +1561,47 @@ name = "minimal-project"
 
             prop_assert_eq!(parsed.project.name, name);
         }
+
+        /// Property: `${VAR:-default}` yields the default for unset variables
+        /// and the value otherwise
+        #[test]
+        fn prop_env_default_form(value in "[a-zA-Z0-9]{1,20}", default in "[a-zA-Z0-9_./-]{0,20}") {
+            let input = format!("${{ZIGROOT_PROP_DEFAULT:-{default}}}");
+            std::env::remove_var("ZIGROOT_PROP_DEFAULT");
+            prop_assert_eq!(expand_env_vars(&input, true).unwrap(), default.clone());
+            std::env::set_var("ZIGROOT_PROP_DEFAULT", &value);
+            prop_assert_eq!(expand_env_vars(&input, true).unwrap(), value);
+            std::env::remove_var("ZIGROOT_PROP_DEFAULT");
+        }
+
+        /// Property: `${VAR:?message}` fails with the message when unset,
+        /// regardless of strictness
+        #[test]
+        fn prop_env_required_form(message in "[a-zA-Z][a-zA-Z ]{0,30}", strict in any::<bool>()) {
+            std::env::remove_var("ZIGROOT_PROP_REQUIRED");
+            let input = format!("${{ZIGROOT_PROP_REQUIRED:?{message}}}");
+            let err = expand_env_vars(&input, strict).unwrap_err();
+            prop_assert!(err.contains("ZIGROOT_PROP_REQUIRED"));
+            prop_assert!(err.contains(&message));
+        }
+
+        /// Property: `$${VAR}` is always kept literally
+        #[test]
+        fn prop_env_escape_is_literal(name in "[A-Z_][A-Z0-9_]{0,15}", strict in any::<bool>()) {
+            let input = format!("$${{{name}}}");
+            prop_assert_eq!(expand_env_vars(&input, strict).unwrap(), format!("${{{name}}}"));
+        }
+
+        /// Property: plain `${VAR}` for an unset variable is empty when lenient
+        /// and an error naming the variable when strict
+        #[test]
+        fn prop_env_plain_unset(suffix in "[A-Z0-9]{1,12}") {
+            let name = format!("ZIGROOT_PROP_UNSET_{suffix}");
+            std::env::remove_var(&name);
+            let input = format!("a${{{name}}}b");
+            prop_assert_eq!(expand_env_vars(&input, false).unwrap(), "ab");
+            let err = expand_env_vars(&input, true).unwrap_err();
+            prop_assert!(err.contains(&name));
+        }
     }
 }
@@ -19,13 +19,36 @@
 //! - [`fetch`] - Package fetch logic
 //! - [`clean`] - Clean build artifacts logic
 //! - [`check`] - Configuration validation logic
+//! - [`completion`] - Dynamic shell completion of package and board names
 //! - [`search`] - Search functionality for packages and boards
 //! - [`flash`] - Device flashing logic
 //! - [`external`] - External artifact management
 //! - [`compress`] - Binary compression using UPX
 //! - [`kernel`] - Linux kernel build support
+//! - [`libc`] - C library selection of boards and packages
+//! - [`fit`] - FIT image generation for U-Boot
+//! - [`package_test`] - Test builds of a package in throwaway projects
+//! - [`partition`] - Disk image layout and partition tables
+//! - [`patch`] - Local patches applied to package sources
+//! - [`pipeline`] - Build pipeline behind the project facade
+//! - [`project`] - Library facade over a project
+//! - [`output`] - Output directory layout
+//! - [`permissions`] - Ownership and permissions overlay of the rootfs
+//! - [`qemu`] - QEMU emulation of boards
 //! - [`global_config`] - Global configuration management
+//! - [`hooks`] - Project build event hooks
+//! - [`host_tools`] - Host tool requirements and provisioning
 //! - [`shared_storage`] - Shared downloads and build cache
+//! - [`signing`] - Signing of published definitions
+//! - [`report`] - Machine-readable build reports
+//! - [`reproducible`] - Reproducible builds and build attestations
+//! - [`size`] - Image size accounting and budget enforcement
+//! - [`stats`] - Local build statistics
+//! - [`strip`] - Symbol stripping and debug-info splitting
+//! - [`validate`] - Post-build validation of the rootfs
+//! - [`variants`] - Build trees of package configurations
+//! - [`verify`] - Lints of package definitions
+//! - [`watch`] - Deciding what to rebuild in watch mode
 
 pub mod add;
 pub mod board;
@@ -34,25 +57,48 @@ pub mod builder;
 pub mod cache;
 pub mod check;
 pub mod clean;
+pub mod completion;
 pub mod compress;
 pub mod config;
 pub mod doctor;
 pub mod external;
 pub mod fetch;
+pub mod fit;
 pub mod flash;
 pub mod global_config;
+pub mod hooks;
+pub mod host_tools;
 pub mod init;
 pub mod kernel;
+pub mod libc;
 pub mod license;
 pub mod lock;
 pub mod manifest;
 pub mod options;
+pub mod output;
 pub mod package;
+pub mod package_test;
+pub mod partition;
+pub mod patch;
+pub mod permissions;
+pub mod pipeline;
+pub mod project;
+pub mod qemu;
 pub mod remove;
+pub mod report;
+pub mod reproducible;
 pub mod resolver;
 pub mod sdk;
 pub mod search;
 pub mod shared_storage;
+pub mod signing;
+pub mod size;
+pub mod stats;
+pub mod strip;
 pub mod tree;
 pub mod update;
+pub mod validate;
+pub mod variants;
+pub mod verify;
 pub mod version;
+pub mod watch;
@@ -1,7 +1,7 @@
 //! Option validation
 //!
 //! Validates package and board option values against their definitions.
-//! Implements option value resolution with priority: CLI > Package > Global.
+//! Implements option value resolution with priority: CLI > Package > Board > Global.
 
 use crate::error::OptionError;
 use regex::Regex;
@@ -16,12 +16,39 @@ pub enum OptionSource {
     Cli,
     /// Value from package-specific configuration
     Package,
+    /// Package option default from the board definition
+    Board,
     /// Value from global configuration
     Global,
     /// Default value from option definition (lowest priority)
     Default,
 }
 
+impl std::fmt::Display for OptionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cli => write!(f, "command line"),
+            Self::Package => write!(f, "package options"),
+            Self::Board => write!(f, "board options"),
+            Self::Global => write!(f, "global options"),
+            Self::Default => write!(f, "default"),
+        }
+    }
+}
+
+impl OptionSource {
+    /// Short name of the source, as shown by `zigroot package info`
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Cli => "cli",
+            Self::Package => "package",
+            Self::Board => "board",
+            Self::Global => "global",
+            Self::Default => "default",
+        }
+    }
+}
+
 /// Resolved option value with its source
 #[derive(Debug, Clone)]
 pub struct ResolvedOption {
@@ -29,14 +56,40 @@ pub struct ResolvedOption {
     pub value: toml::Value,
     /// Where the value came from
     pub source: OptionSource,
+    /// Where to change the value (manifest line or CLI flag), for error messages
+    pub location: Option<String>,
 }
 
-/// Resolve option values with priority: CLI > Package > Global > Default
+impl ResolvedOption {
+    /// Whether the option counts as enabled for `requires`/`conflicts_with`
+    ///
+    /// Booleans must be true, strings non-empty and numbers non-zero.
+    pub fn is_enabled(&self) -> bool {
+        match &self.value {
+            toml::Value::Boolean(b) => *b,
+            toml::Value::String(s) => !s.is_empty(),
+            toml::Value::Integer(i) => *i != 0,
+            toml::Value::Float(f) => *f != 0.0,
+            _ => true,
+        }
+    }
+
+    /// Value and origin, e.g. `true (package options, zigroot.toml line 12)`
+    fn describe(&self) -> String {
+        match &self.location {
+            Some(location) => format!("{} ({}, {location})", self.value, self.source),
+            None => format!("{} ({})", self.value, self.source),
+        }
+    }
+}
+
+/// Resolve option values with priority: CLI > Package > Board > Global > Default
 ///
 /// # Arguments
 /// * `definition` - The option definition with default value
 /// * `cli_value` - Optional value from CLI arguments
 /// * `package_value` - Optional value from package configuration
+/// * `board_value` - Optional package option default from the board
 /// * `global_value` - Optional value from global configuration
 ///
 /// # Returns
@@ -45,27 +98,38 @@ pub fn resolve_option_value(
     definition: &OptionDefinition,
     cli_value: Option<&toml::Value>,
     package_value: Option<&toml::Value>,
+    board_value: Option<&toml::Value>,
     global_value: Option<&toml::Value>,
 ) -> ResolvedOption {
     if let Some(value) = cli_value {
         ResolvedOption {
             value: value.clone(),
             source: OptionSource::Cli,
+            location: None,
         }
     } else if let Some(value) = package_value {
         ResolvedOption {
             value: value.clone(),
             source: OptionSource::Package,
+            location: None,
+        }
+    } else if let Some(value) = board_value {
+        ResolvedOption {
+            value: value.clone(),
+            source: OptionSource::Board,
+            location: None,
         }
     } else if let Some(value) = global_value {
         ResolvedOption {
             value: value.clone(),
             source: OptionSource::Global,
+            location: None,
         }
     } else {
         ResolvedOption {
             value: definition.default.clone(),
             source: OptionSource::Default,
+            location: None,
         }
     }
 }
@@ -76,6 +140,7 @@ pub fn resolve_option_value(
 /// * `definitions` - Map of option name to definition
 /// * `cli_values` - Map of option name to CLI value
 /// * `package_values` - Map of option name to package config value
+/// * `board_values` - Map of option name to board package option default
 /// * `global_values` - Map of option name to global config value
 ///
 /// # Returns
@@ -84,6 +149,7 @@ pub fn resolve_all_options(
     definitions: &HashMap<String, OptionDefinition>,
     cli_values: &HashMap<String, toml::Value>,
     package_values: &HashMap<String, toml::Value>,
+    board_values: &HashMap<String, toml::Value>,
     global_values: &HashMap<String, toml::Value>,
 ) -> HashMap<String, ResolvedOption> {
     definitions
@@ -93,6 +159,7 @@ pub fn resolve_all_options(
                 def,
                 cli_values.get(name),
                 package_values.get(name),
+                board_values.get(name),
                 global_values.get(name),
             );
             (name.clone(), resolved)
@@ -126,7 +193,68 @@ pub fn validate_all_options(
             )?;
         }
     }
-    Ok(())
+    validate_option_relationships(definitions, resolved)
+}
+
+/// Validate `requires` and `conflicts_with` across the resolved options
+///
+/// Every violated relation is reported with the values involved and
+/// where each value came from.
+pub(crate) fn validate_option_relationships(
+    definitions: &HashMap<String, OptionDefinition>,
+    resolved: &HashMap<String, ResolvedOption>,
+) -> Result<(), OptionError> {
+    let mut names: Vec<&String> = definitions.keys().collect();
+    names.sort();
+
+    let mut violations = Vec::new();
+    for name in names {
+        let def = &definitions[name];
+        let Some(option) = resolved.get(name).filter(|o| o.is_enabled()) else {
+            continue;
+        };
+
+        for required in &def.requires {
+            match resolved.get(required) {
+                None => violations.push(format!("'{name}' requires unknown option '{required}'")),
+                Some(other) if !other.is_enabled() => violations.push(format!(
+                    "'{name}' = {} requires '{required}', but '{required}' = {}",
+                    option.describe(),
+                    other.describe()
+                )),
+                Some(_) => {}
+            }
+        }
+
+        for conflicting in &def.conflicts_with {
+            match resolved.get(conflicting) {
+                None => violations.push(format!(
+                    "'{name}' conflicts with unknown option '{conflicting}'"
+                )),
+                // Report each mutually exclusive pair once
+                Some(other)
+                    if other.is_enabled()
+                        && !(definitions
+                            .get(conflicting)
+                            .is_some_and(|d| d.conflicts_with.contains(name))
+                            && conflicting < name) =>
+                {
+                    violations.push(format!(
+                        "'{name}' = {} conflicts with '{conflicting}' = {}",
+                        option.describe(),
+                        other.describe()
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(OptionError::Relationships { violations })
+    }
 }
 
 /// Validate an option value against its definition
@@ -991,16 +1119,20 @@ mod tests {
             allow_empty: true,
             min: None,
             max: None,
+            requires: vec![],
+            conflicts_with: vec![],
         };
 
         let cli_value = toml::Value::String("cli".to_string());
         let package_value = toml::Value::String("package".to_string());
+        let board_value = toml::Value::String("board".to_string());
         let global_value = toml::Value::String("global".to_string());
 
         let resolved = resolve_option_value(
             &def,
             Some(&cli_value),
             Some(&package_value),
+            Some(&board_value),
             Some(&global_value),
         );
 
@@ -1019,17 +1151,55 @@ mod tests {
             allow_empty: true,
             min: None,
             max: None,
+            requires: vec![],
+            conflicts_with: vec![],
         };
 
         let package_value = toml::Value::String("package".to_string());
         let global_value = toml::Value::String("global".to_string());
 
-        let resolved = resolve_option_value(&def, None, Some(&package_value), Some(&global_value));
+        let resolved =
+            resolve_option_value(&def, None, Some(&package_value), None, Some(&global_value));
 
         assert_eq!(resolved.value, package_value);
         assert_eq!(resolved.source, OptionSource::Package);
     }
 
+    #[test]
+    fn test_resolve_option_board_between_package_and_global() {
+        let def = OptionDefinition {
+            option_type: "string".to_string(),
+            default: toml::Value::String("default".to_string()),
+            description: "Test option".to_string(),
+            choices: vec![],
+            pattern: None,
+            allow_empty: true,
+            min: None,
+            max: None,
+            requires: vec![],
+            conflicts_with: vec![],
+        };
+
+        let package_value = toml::Value::String("package".to_string());
+        let board_value = toml::Value::String("board".to_string());
+        let global_value = toml::Value::String("global".to_string());
+
+        let resolved = resolve_option_value(
+            &def,
+            None,
+            Some(&package_value),
+            Some(&board_value),
+            Some(&global_value),
+        );
+        assert_eq!(resolved.source, OptionSource::Package);
+
+        let resolved =
+            resolve_option_value(&def, None, None, Some(&board_value), Some(&global_value));
+        assert_eq!(resolved.value, board_value);
+        assert_eq!(resolved.source, OptionSource::Board);
+        assert_eq!(resolved.source.label(), "board");
+    }
+
     #[test]
     fn test_resolve_option_global_over_default() {
         let def = OptionDefinition {
@@ -1041,11 +1211,13 @@ mod tests {
             allow_empty: true,
             min: None,
             max: None,
+            requires: vec![],
+            conflicts_with: vec![],
         };
 
         let global_value = toml::Value::String("global".to_string());
 
-        let resolved = resolve_option_value(&def, None, None, Some(&global_value));
+        let resolved = resolve_option_value(&def, None, None, None, Some(&global_value));
 
         assert_eq!(resolved.value, global_value);
         assert_eq!(resolved.source, OptionSource::Global);
@@ -1062,9 +1234,11 @@ mod tests {
             allow_empty: true,
             min: None,
             max: None,
+            requires: vec![],
+            conflicts_with: vec![],
         };
 
-        let resolved = resolve_option_value(&def, None, None, None);
+        let resolved = resolve_option_value(&def, None, None, None, None);
 
         assert_eq!(resolved.value, toml::Value::String("default".to_string()));
         assert_eq!(resolved.source, OptionSource::Default);
@@ -1084,6 +1258,8 @@ mod tests {
                 allow_empty: true,
                 min: None,
                 max: None,
+                requires: vec![],
+                conflicts_with: vec![],
             },
         );
         definitions.insert(
@@ -1097,6 +1273,24 @@ mod tests {
                 allow_empty: true,
                 min: None,
                 max: None,
+                requires: vec![],
+                conflicts_with: vec![],
+            },
+        );
+
+        definitions.insert(
+            "opt3".to_string(),
+            OptionDefinition {
+                option_type: "number".to_string(),
+                default: toml::Value::Integer(4),
+                description: "Option 3".to_string(),
+                choices: vec![],
+                pattern: None,
+                allow_empty: true,
+                min: None,
+                max: None,
+                requires: vec![],
+                conflicts_with: vec![],
             },
         );
 
@@ -1106,10 +1300,19 @@ mod tests {
         let mut package_values = HashMap::new();
         package_values.insert("opt2".to_string(), toml::Value::Boolean(true));
 
-        let resolved =
-            resolve_all_options(&definitions, &cli_values, &package_values, &HashMap::new());
+        let mut board_values = HashMap::new();
+        board_values.insert("opt2".to_string(), toml::Value::Boolean(false));
+        board_values.insert("opt3".to_string(), toml::Value::Integer(8));
 
-        assert_eq!(resolved.len(), 2);
+        let resolved = resolve_all_options(
+            &definitions,
+            &cli_values,
+            &package_values,
+            &board_values,
+            &HashMap::new(),
+        );
+
+        assert_eq!(resolved.len(), 3);
         assert_eq!(
             resolved.get("opt1").unwrap().value,
             toml::Value::String("cli1".to_string())
@@ -1120,6 +1323,8 @@ mod tests {
             toml::Value::Boolean(true)
         );
         assert_eq!(resolved.get("opt2").unwrap().source, OptionSource::Package);
+        assert_eq!(resolved.get("opt3").unwrap().value, toml::Value::Integer(8));
+        assert_eq!(resolved.get("opt3").unwrap().source, OptionSource::Board);
     }
 
     #[test]
@@ -1136,6 +1341,8 @@ mod tests {
                 allow_empty: true,
                 min: None,
                 max: None,
+                requires: vec![],
+                conflicts_with: vec![],
             },
         );
 
@@ -1145,6 +1352,7 @@ mod tests {
             ResolvedOption {
                 value: toml::Value::String("valid".to_string()),
                 source: OptionSource::Cli,
+                location: None,
             },
         );
 
@@ -1166,6 +1374,8 @@ mod tests {
                 allow_empty: true,
                 min: None,
                 max: None,
+                requires: vec![],
+                conflicts_with: vec![],
             },
         );
 
@@ -1175,10 +1385,119 @@ mod tests {
             ResolvedOption {
                 value: toml::Value::String("invalid".to_string()),
                 source: OptionSource::Cli,
+                location: None,
             },
         );
 
         let result = validate_all_options(&definitions, &resolved);
         assert!(result.is_err());
     }
+
+    fn bool_option(requires: &[&str], conflicts_with: &[&str]) -> OptionDefinition {
+        OptionDefinition {
+            option_type: "bool".to_string(),
+            default: toml::Value::Boolean(false),
+            description: String::new(),
+            choices: vec![],
+            pattern: None,
+            allow_empty: true,
+            min: None,
+            max: None,
+            requires: requires.iter().map(ToString::to_string).collect(),
+            conflicts_with: conflicts_with.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    fn resolved_bool(value: bool, source: OptionSource, location: &str) -> ResolvedOption {
+        ResolvedOption {
+            value: toml::Value::Boolean(value),
+            source,
+            location: Some(location.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_option_requires_is_enforced() {
+        let mut definitions = HashMap::new();
+        definitions.insert("tls".to_string(), bool_option(&["libcrypto"], &[]));
+        definitions.insert("libcrypto".to_string(), bool_option(&[], &[]));
+
+        let mut resolved = HashMap::new();
+        resolved.insert(
+            "tls".to_string(),
+            resolved_bool(true, OptionSource::Package, "zigroot.toml line 9"),
+        );
+        resolved.insert(
+            "libcrypto".to_string(),
+            resolved_bool(false, OptionSource::Default, "package.toml"),
+        );
+
+        let err = validate_all_options(&definitions, &resolved).unwrap_err();
+        let OptionError::Relationships { violations } = err else {
+            panic!("expected relationship error, got {err:?}");
+        };
+        assert_eq!(
+            violations,
+            vec![
+                "'tls' = true (package options, zigroot.toml line 9) requires 'libcrypto', but 'libcrypto' = false (default, package.toml)"
+            ]
+        );
+
+        // Satisfied once libcrypto is enabled
+        resolved.insert(
+            "libcrypto".to_string(),
+            resolved_bool(true, OptionSource::Cli, "--option"),
+        );
+        assert!(validate_all_options(&definitions, &resolved).is_ok());
+
+        // Disabled options impose no requirements
+        resolved.insert(
+            "tls".to_string(),
+            resolved_bool(false, OptionSource::Default, "package.toml"),
+        );
+        resolved.insert(
+            "libcrypto".to_string(),
+            resolved_bool(false, OptionSource::Default, "package.toml"),
+        );
+        assert!(validate_option_relationships(&definitions, &resolved).is_ok());
+    }
+
+    #[test]
+    fn test_option_conflicts_report_every_violation_once() {
+        let mut definitions = HashMap::new();
+        definitions.insert("openssl".to_string(), bool_option(&[], &["mbedtls"]));
+        definitions.insert("mbedtls".to_string(), bool_option(&[], &["openssl"]));
+        definitions.insert("http2".to_string(), bool_option(&["nghttp2"], &[]));
+
+        let mut resolved = HashMap::new();
+        for name in ["openssl", "mbedtls", "http2"] {
+            resolved.insert(
+                name.to_string(),
+                resolved_bool(true, OptionSource::Package, "zigroot.toml"),
+            );
+        }
+
+        let Err(OptionError::Relationships { violations }) =
+            validate_option_relationships(&definitions, &resolved)
+        else {
+            panic!("expected relationship violations");
+        };
+        assert_eq!(violations.len(), 2, "{violations:?}");
+        assert!(violations[0].contains("'http2' requires unknown option 'nghttp2'"));
+        assert!(violations[1].starts_with("'mbedtls' = true"));
+        assert!(violations[1].contains("conflicts with 'openssl' = true"));
+    }
+
+    #[test]
+    fn test_option_enabled_values() {
+        let option = |value| ResolvedOption {
+            value,
+            source: OptionSource::Default,
+            location: None,
+        };
+        assert!(option(toml::Value::String("x".to_string())).is_enabled());
+        assert!(!option(toml::Value::String(String::new())).is_enabled());
+        assert!(option(toml::Value::Integer(2)).is_enabled());
+        assert!(!option(toml::Value::Integer(0)).is_enabled());
+    }
 }
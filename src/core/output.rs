@@ -0,0 +1,226 @@
+//! Output directory layout
+//!
+//! Build products are namespaced by board and image format, so switching
+//! either never overwrites the artifacts of another configuration:
+//!
+//! ```text
+//! output/
+//! └── <board>/
+//!     ├── latest -> <format>
+//!     └── <format>/
+//!         ├── rootfs.img
+//!         └── ...
+//! ```
+//!
+//! Projects built before the layout was introduced keep their images
+//! directly in `output/`; those are still found when no namespaced image
+//! exists.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::core::manifest::Manifest;
+
+/// Default output directory relative to the project
+pub const DEFAULT_OUTPUT_DIR: &str = "output";
+
+/// Name of the per-board link to the most recently built format
+pub const LATEST_LINK: &str = "latest";
+
+/// Board directory of projects without a board
+pub const DEFAULT_BOARD: &str = "default";
+
+/// Suffix of the `sha256sum` file written next to the final image
+pub const CHECKSUM_SUFFIX: &str = ".sha256";
+
+/// Locations of build outputs in a project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputLayout {
+    root: PathBuf,
+    board: String,
+    format: String,
+}
+
+impl OutputLayout {
+    /// Layout of the project in `project_dir`
+    ///
+    /// `build.output_dir` may be relative to the project or absolute.
+    pub fn new(project_dir: &Path, manifest: &Manifest) -> Self {
+        let root = manifest
+            .build
+            .output_dir
+            .as_deref()
+            .unwrap_or(DEFAULT_OUTPUT_DIR);
+        Self {
+            root: project_dir.join(root),
+            board: manifest
+                .board
+                .name
+                .clone()
+                .unwrap_or_else(|| DEFAULT_BOARD.to_string()),
+            format: manifest.build.image_format.clone(),
+        }
+    }
+
+    /// Root of all outputs
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Outputs of the project's board
+    pub fn board_dir(&self) -> PathBuf {
+        self.root.join(&self.board)
+    }
+
+    /// Outputs of the project's board and image format
+    pub fn image_dir(&self) -> PathBuf {
+        self.board_dir().join(&self.format)
+    }
+
+    /// Link to the board's most recently built format
+    pub fn latest_link(&self) -> PathBuf {
+        self.board_dir().join(LATEST_LINK)
+    }
+
+    /// Point the board's `latest` link at the current format
+    ///
+    /// The link is relative, so the output directory can be moved. On
+    /// platforms without symlinks this does nothing.
+    pub fn update_latest(&self) -> io::Result<()> {
+        let link = self.latest_link();
+        if link.symlink_metadata().is_ok() {
+            std::fs::remove_file(&link)?;
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&self.format, &link)?;
+        Ok(())
+    }
+
+    /// Find a built artifact by file name
+    ///
+    /// The current format is preferred, then the board's `latest` build,
+    /// then the flat layout of older builds. When none has the file, the
+    /// error lists the artifacts of the same name built for the board's
+    /// other formats.
+    pub fn find(&self, file_name: &str) -> Result<PathBuf, Vec<PathBuf>> {
+        [
+            self.image_dir().join(file_name),
+            self.latest_link().join(file_name),
+            self.root.join(file_name),
+        ]
+        .into_iter()
+        .find(|path| path.is_file())
+        .ok_or_else(|| self.candidates(file_name))
+    }
+
+    /// Artifacts named `file_name` in any format directory of the board
+    pub fn candidates(&self, file_name: &str) -> Vec<PathBuf> {
+        let Ok(entries) = std::fs::read_dir(self.board_dir()) else {
+            return Vec::new();
+        };
+        let mut found: Vec<PathBuf> = entries
+            .flatten()
+            .filter(|entry| entry.file_name() != LATEST_LINK)
+            .map(|entry| entry.path().join(file_name))
+            .filter(|path| path.is_file())
+            .collect();
+        found.sort();
+        found
+    }
+
+    /// Path of an artifact, falling back to the current format directory
+    ///
+    /// For callers that report a missing file themselves.
+    pub fn artifact(&self, file_name: &str) -> PathBuf {
+        self.find(file_name)
+            .unwrap_or_else(|_| self.image_dir().join(file_name))
+    }
+}
+
+/// Checksum file of an image, e.g. `sdcard.img.sha256`
+pub fn checksum_path(image: &Path) -> PathBuf {
+    let mut name = image.as_os_str().to_os_string();
+    name.push(CHECKSUM_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Describe a missing artifact, listing other builds that have it
+pub fn missing_artifact_message(path: &Path, candidates: &[PathBuf]) -> String {
+    let mut message = format!("No image found at {}.", path.display());
+    if candidates.is_empty() {
+        message.push_str(" Run 'zigroot build' first.");
+    } else {
+        message.push_str(" Images built with other settings:");
+        for candidate in candidates {
+            message.push_str("\n  ");
+            message.push_str(&candidate.display().to_string());
+        }
+        message.push_str("\nSet build.image_format to match one of them or rebuild.");
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn manifest(board: Option<&str>, format: &str, output_dir: Option<&str>) -> Manifest {
+        let mut manifest = Manifest::default();
+        manifest.board.name = board.map(str::to_string);
+        manifest.build.image_format = format.to_string();
+        manifest.build.output_dir = output_dir.map(str::to_string);
+        manifest
+    }
+
+    #[test]
+    fn test_layout_is_namespaced_by_board_and_format() {
+        let project = Path::new("/work/project");
+        let layout = OutputLayout::new(project, &manifest(Some("rpi4"), "squashfs", None));
+        assert_eq!(layout.root(), project.join("output"));
+        assert_eq!(layout.image_dir(), project.join("output/rpi4/squashfs"));
+        assert_eq!(layout.latest_link(), project.join("output/rpi4/latest"));
+
+        let layout = OutputLayout::new(project, &manifest(None, "ext4", Some("/srv/images")));
+        assert_eq!(layout.image_dir(), Path::new("/srv/images/default/ext4"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_prefers_current_format_then_legacy_layout() {
+        let temp = TempDir::new().unwrap();
+        let layout = OutputLayout::new(temp.path(), &manifest(Some("rpi4"), "ext4", None));
+
+        std::fs::create_dir_all(temp.path().join("output")).unwrap();
+        std::fs::write(temp.path().join("output/rootfs.img"), "old").unwrap();
+        assert_eq!(
+            layout.find("rootfs.img").unwrap(),
+            temp.path().join("output/rootfs.img")
+        );
+
+        std::fs::create_dir_all(layout.image_dir()).unwrap();
+        std::fs::write(layout.image_dir().join("rootfs.img"), "new").unwrap();
+        layout.update_latest().unwrap();
+        layout.update_latest().unwrap();
+        let found = layout.find("rootfs.img").unwrap();
+        assert_eq!(std::fs::read_to_string(found).unwrap(), "new");
+        assert_eq!(
+            std::fs::read_link(layout.latest_link()).unwrap(),
+            Path::new("ext4")
+        );
+    }
+
+    #[test]
+    fn test_missing_artifact_lists_other_formats() {
+        let temp = TempDir::new().unwrap();
+        let ext4 = OutputLayout::new(temp.path(), &manifest(Some("rpi4"), "ext4", None));
+        let squashfs = OutputLayout::new(temp.path(), &manifest(Some("rpi4"), "squashfs", None));
+        std::fs::create_dir_all(ext4.image_dir()).unwrap();
+        std::fs::write(ext4.image_dir().join("disk.img"), "disk").unwrap();
+
+        let candidates = squashfs.find("disk.img").unwrap_err();
+        assert_eq!(candidates, vec![ext4.image_dir().join("disk.img")]);
+        let message = missing_artifact_message(&squashfs.image_dir().join("disk.img"), &candidates);
+        assert!(message.contains("rpi4/ext4/disk.img"), "{message}");
+    }
+}
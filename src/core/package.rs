@@ -6,6 +6,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::core::libc::Libc;
+use crate::infra::download::{Checksum, ChecksumAlgorithm};
+
 /// Complete package definition (merged from metadata + version for registry packages)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PackageDefinition {
@@ -52,47 +55,236 @@ pub struct PackageMetadata {
     #[serde(default)]
     pub keywords: Vec<String>,
 
-    /// Build dependencies (packages needed at build time)
-    #[serde(default)]
+    /// Dependencies built first and installed into the rootfs
+    ///
+    /// The legacy `dependencies` key is accepted as an alias.
+    #[serde(default, alias = "dependencies")]
     pub depends: Vec<String>,
 
-    /// Runtime dependencies (packages needed in final rootfs)
+    /// Build-only dependencies (needed in the build sysroot, not the rootfs)
+    #[serde(default)]
+    pub build_depends: Vec<String>,
+
+    /// Runtime-only dependencies (installed into the rootfs, no build ordering)
     #[serde(default)]
     pub requires: Vec<String>,
 
+    /// Tools needed on the build host, with optional version requirements
+    /// (e.g. `"cmake>=3.25"`)
+    #[serde(default)]
+    pub host_depends: Vec<String>,
+
     /// Supported architectures (empty = all)
     #[serde(default)]
     pub arch: Vec<String>,
 
+    /// Supported C libraries (empty = all)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub supported_libc: Vec<Libc>,
+
     /// Virtual packages this provides
     #[serde(default)]
     pub provides: Vec<String>,
 
     /// Conflicting packages
     #[serde(default)]
-    pub conflicts: Vec<String>,
+    pub conflicts: Vec<ConflictSpec>,
 
     /// Minimum zigroot version required
     #[serde(default)]
     pub zigroot_version: Option<String>,
+
+    /// License files of the source tree, as globs relative to its root
+    /// (e.g. `["COPYING", "LICENSES/*"]`), for when detection finds none
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub license_files: Vec<String>,
+}
+
+impl PackageMetadata {
+    /// Packages that must be built before this one
+    pub fn build_order_dependencies(&self) -> Vec<String> {
+        let mut deps = self.depends.clone();
+        for dep in &self.build_depends {
+            if !deps.contains(dep) {
+                deps.push(dep.clone());
+            }
+        }
+        deps
+    }
+
+    /// Whether the package builds against the given C library
+    pub fn supports_libc(&self, libc: Libc) -> bool {
+        self.supported_libc.is_empty() || self.supported_libc.contains(&libc)
+    }
+
+    /// Packages whose artifacts must be installed alongside this one
+    pub fn runtime_dependencies(&self) -> Vec<String> {
+        let mut deps = self.depends.clone();
+        for dep in &self.requires {
+            if !deps.contains(dep) {
+                deps.push(dep.clone());
+            }
+        }
+        deps
+    }
+}
+
+/// Conflict declaration - either a bare package name or a name with a reason
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ConflictSpec {
+    /// Conflicting package name
+    Name(String),
+
+    /// Conflicting package with an explanation
+    Detailed {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+}
+
+impl ConflictSpec {
+    /// Name of the conflicting package
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Name(name) | Self::Detailed { name, .. } => name,
+        }
+    }
+
+    /// Declared reason for the conflict, if any
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            Self::Name(_) => None,
+            Self::Detailed { reason, .. } => reason.as_deref(),
+        }
+    }
 }
 
 /// Source configuration - exactly ONE source type must be specified
+///
+/// Every source type takes `patches`, applied to the sources in order
+/// before the build steps (see [`crate::core::patch`]).
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(untagged)]
 pub enum SourceConfig {
-    /// URL source with checksum
-    Url { url: String, sha256: String },
+    /// URL source with exactly one checksum, and mirrors serving the same
+    /// file that are tried in order when `url` fails
+    Url {
+        url: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        mirrors: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sha256: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sha512: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        blake3: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        patches: Vec<String>,
+    },
 
-    /// Git source with ref
+    /// Git source with ref, and whether its submodules are needed
     Git {
         git: String,
         #[serde(flatten)]
         git_ref: GitRef,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        git_submodules: bool,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        patches: Vec<String>,
     },
 
     /// Multiple source files
-    Sources { sources: Vec<SourceFile> },
+    Sources {
+        sources: Vec<SourceFile>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        patches: Vec<String>,
+    },
+}
+
+impl SourceConfig {
+    /// Patches to apply, relative to the package directory
+    pub fn patches(&self) -> &[String] {
+        match self {
+            Self::Url { patches, .. }
+            | Self::Git { patches, .. }
+            | Self::Sources { patches, .. } => patches,
+        }
+    }
+
+    /// Checksums of the remote files as `(url, checksum)`
+    ///
+    /// Every URL must declare exactly one well-formed `sha256`, `sha512` or
+    /// `blake3` checksum; git sources have none.
+    pub fn checksums(&self) -> Result<Vec<(&str, Checksum)>, String> {
+        match self {
+            Self::Url {
+                url,
+                sha256,
+                sha512,
+                blake3,
+                ..
+            } => Ok(vec![(
+                url.as_str(),
+                required_checksum(url, [sha256, sha512, blake3])?,
+            )]),
+            Self::Sources { sources, .. } => sources
+                .iter()
+                .map(|file| {
+                    let fields = [&file.sha256, &file.sha512, &file.blake3];
+                    Ok((file.url.as_str(), required_checksum(&file.url, fields)?))
+                })
+                .collect(),
+            Self::Git { .. } => Ok(Vec::new()),
+        }
+    }
+
+    /// Fail unless every URL declares exactly one checksum field
+    fn check_checksum_fields(&self) -> Result<(), String> {
+        let fields = match self {
+            Self::Url {
+                url,
+                sha256,
+                sha512,
+                blake3,
+                ..
+            } => vec![(url, [sha256, sha512, blake3])],
+            Self::Sources { sources, .. } => sources
+                .iter()
+                .map(|file| (&file.url, [&file.sha256, &file.sha512, &file.blake3]))
+                .collect(),
+            Self::Git { .. } => Vec::new(),
+        };
+        for (url, digests) in fields {
+            match digests.iter().filter(|digest| digest.is_some()).count() {
+                0 => return Err(missing_checksum(url)),
+                1 => {}
+                _ => {
+                    return Err(format!(
+                        "Source '{url}' declares several checksums: set exactly one of sha256, sha512 or blake3"
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The one checksum of a source URL from its `sha256`, `sha512` and `blake3` fields
+fn required_checksum(url: &str, fields: [&Option<String>; 3]) -> Result<Checksum, String> {
+    let [sha256, sha512, blake3] = fields.map(Option::as_deref);
+    Checksum::from_fields(sha256, sha512, blake3)
+        .map_err(|e| format!("Source '{url}': {e}"))?
+        .ok_or_else(|| missing_checksum(url))
+}
+
+fn missing_checksum(url: &str) -> String {
+    let names: Vec<&str> = ChecksumAlgorithm::ALL.iter().map(|a| a.name()).collect();
+    format!(
+        "Source '{url}' has no checksum: set one of {}",
+        names.join(", ")
+    )
 }
 
 /// Git reference type
@@ -110,8 +302,21 @@ pub struct SourceFile {
     /// Source URL
     pub url: String,
 
+    /// Mirrors serving the same file, tried in order when `url` fails
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+
     /// SHA256 checksum
-    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+
+    /// SHA512 checksum
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha512: Option<String>,
+
+    /// BLAKE3 checksum
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blake3: Option<String>,
 
     /// Destination filename
     #[serde(default)]
@@ -153,6 +358,92 @@ pub struct PackageBuildConfig {
     /// Enable/disable compression for this package
     #[serde(default)]
     pub compress: Option<bool>,
+
+    /// Enable/disable symbol stripping for this package (overrides
+    /// `build.strip`), for binaries that break when stripped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strip: Option<bool>,
+
+    /// Compiler toolchain override (`toolchain = "gcc"` or a `[build.toolchain]` table)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub toolchain: Option<ToolchainConfig>,
+
+    /// Command run in the source tree before the build steps
+    ///
+    /// Hooks run through `sh -c` with the environment of the build steps
+    /// (`CC`, `CXX`, `AR`, `TARGET`, `CPU`, `SRCDIR`, `DESTDIR`, `PREFIX` and
+    /// `JOBS`). `pre_build` and `post_build` run in `$SRCDIR`, `post_install`
+    /// in `$DESTDIR`. A hook exiting non-zero fails the build.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_build: Option<String>,
+
+    /// Command run in the source tree after the build steps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_build: Option<String>,
+
+    /// Command run in the install tree (`$DESTDIR`) after `post_build`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_install: Option<String>,
+}
+
+impl PackageBuildConfig {
+    /// Hook commands by name, in the order they run
+    pub fn hooks(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("pre_build", &self.pre_build),
+            ("post_build", &self.post_build),
+            ("post_install", &self.post_install),
+        ]
+        .into_iter()
+        .filter_map(|(name, command)| Some((name, command.as_deref()?)))
+        .collect()
+    }
+
+    /// Whether the package is compiled with the GCC cross-toolchain instead of Zig
+    pub fn uses_gcc(&self) -> bool {
+        self.toolchain.as_ref().is_some_and(|t| t.kind() == "gcc")
+    }
+}
+
+/// Valid package toolchains
+pub const VALID_TOOLCHAINS: &[&str] = &["zig", "gcc"];
+
+/// Compiler toolchain of a package
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ToolchainConfig {
+    /// Toolchain name (`toolchain = "gcc"`)
+    Name(String),
+    /// `[build.toolchain]` table with an optional target override
+    Table {
+        /// Toolchain name (tables describe GCC toolchains by default)
+        #[serde(rename = "type", default = "default_table_toolchain")]
+        kind: String,
+        /// Target triple, defaults to the board's
+        #[serde(default)]
+        target: Option<String>,
+    },
+}
+
+fn default_table_toolchain() -> String {
+    "gcc".to_string()
+}
+
+impl ToolchainConfig {
+    /// Toolchain name (zig or gcc)
+    pub fn kind(&self) -> &str {
+        match self {
+            Self::Name(kind) | Self::Table { kind, .. } => kind,
+        }
+    }
+
+    /// Target triple override
+    pub fn target(&self) -> Option<&str> {
+        match self {
+            Self::Name(_) => None,
+            Self::Table { target, .. } => target.as_deref(),
+        }
+    }
 }
 
 /// A single build step
@@ -198,6 +489,14 @@ pub struct OptionDefinition {
     /// Maximum value (for number type)
     #[serde(default)]
     pub max: Option<f64>,
+
+    /// Options that must also be enabled when this one is
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<String>,
+
+    /// Options that cannot be enabled together with this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts_with: Vec<String>,
 }
 
 fn default_true() -> bool {
@@ -232,8 +531,15 @@ pub struct InstallRule {
 
 impl PackageDefinition {
     /// Parse from TOML string (local package.toml format)
+    ///
+    /// Remote sources must declare exactly one checksum.
     pub fn from_toml(content: &str) -> Result<Self, toml::de::Error> {
-        toml::from_str(content)
+        let definition: Self = toml::from_str(content)?;
+        definition
+            .source
+            .check_checksum_fields()
+            .map_err(<toml::de::Error as serde::de::Error>::custom)?;
+        Ok(definition)
     }
 
     /// Serialize to TOML string
@@ -287,11 +593,11 @@ make_args = ["CROSS_COMPILE=${TARGET}-"]
 
         // Verify source is URL type
         match &pkg.source {
-            SourceConfig::Url { url, sha256 } => {
+            SourceConfig::Url { url, sha256, .. } => {
                 assert!(url.contains("busybox"));
                 assert_eq!(
-                    sha256,
-                    "b8cc24c9574d809e7279c3be349795c5d5ceb6fdf19ca709f80cde50e47de314"
+                    sha256.as_deref(),
+                    Some("b8cc24c9574d809e7279c3be349795c5d5ceb6fdf19ca709f80cde50e47de314")
                 );
             }
             _ => panic!("Expected URL source type"),
@@ -319,8 +625,15 @@ type = "cmake"
         let pkg = PackageDefinition::from_toml(toml_content).expect("Failed to parse git package");
 
         match &pkg.source {
-            SourceConfig::Git { git, git_ref } => {
+            SourceConfig::Git {
+                git,
+                git_ref,
+                git_submodules,
+                patches,
+            } => {
                 assert_eq!(git, "https://github.com/example/repo");
+                assert!(patches.is_empty());
+                assert!(!git_submodules);
                 match git_ref {
                     GitRef::Tag(tag) => assert_eq!(tag, "v1.0.0"),
                     _ => panic!("Expected tag ref"),
@@ -354,6 +667,32 @@ branch = "main"
         }
     }
 
+    #[test]
+    fn test_package_with_git_submodules() {
+        let toml_content = r#"
+[package]
+name = "vendored-pkg"
+version = "0.1.0"
+description = "Vendors its dependencies as submodules"
+
+[source]
+git = "https://github.com/example/repo"
+tag = "v0.1.0"
+git_submodules = true
+"#;
+
+        let pkg = PackageDefinition::from_toml(toml_content).expect("Failed to parse");
+        assert!(matches!(
+            pkg.source,
+            SourceConfig::Git {
+                git_submodules: true,
+                ..
+            }
+        ));
+        let written = toml::to_string(&pkg.source).unwrap();
+        assert!(written.contains("git_submodules = true"), "{written}");
+    }
+
     #[test]
     fn test_package_with_git_rev() {
         let toml_content = r#"
@@ -378,6 +717,111 @@ rev = "abc123def456"
         }
     }
 
+    #[test]
+    fn test_package_build_and_runtime_dependencies() {
+        let toml_content = r#"
+[package]
+name = "app"
+version = "1.0.0"
+description = "Application"
+depends = ["libc-extra"]
+build_depends = ["zlib-static", "libc-extra"]
+requires = ["ca-certificates"]
+
+[source]
+url = "https://example.com/app.tar.gz"
+sha256 = "abc123"
+"#;
+
+        let pkg = PackageDefinition::from_toml(toml_content).expect("Failed to parse");
+
+        assert_eq!(
+            pkg.package.build_order_dependencies(),
+            vec!["libc-extra", "zlib-static"]
+        );
+        assert_eq!(
+            pkg.package.runtime_dependencies(),
+            vec!["libc-extra", "ca-certificates"]
+        );
+    }
+
+    #[test]
+    fn test_package_legacy_dependencies_are_runtime() {
+        let toml_content = r#"
+[package]
+name = "app"
+version = "1.0.0"
+description = "Application"
+dependencies = ["zlib"]
+
+[source]
+url = "https://example.com/app.tar.gz"
+sha256 = "abc123"
+"#;
+
+        let pkg = PackageDefinition::from_toml(toml_content).expect("Failed to parse");
+
+        assert_eq!(pkg.package.depends, vec!["zlib"]);
+        assert!(pkg.package.build_depends.is_empty());
+        assert_eq!(pkg.package.runtime_dependencies(), vec!["zlib"]);
+    }
+
+    #[test]
+    fn test_package_conflicts_accept_names_and_reasons() {
+        let toml_content = r#"
+[package]
+name = "dropbear"
+version = "2024.85"
+description = "Small SSH server"
+conflicts = ["openssh", { name = "tinyssh", reason = "both bind port 22" }]
+
+[source]
+url = "https://example.com/dropbear.tar.bz2"
+sha256 = "abc123"
+"#;
+
+        let pkg = PackageDefinition::from_toml(toml_content).expect("Failed to parse");
+
+        assert_eq!(pkg.package.conflicts.len(), 2);
+        assert_eq!(pkg.package.conflicts[0].name(), "openssh");
+        assert_eq!(pkg.package.conflicts[0].reason(), None);
+        assert_eq!(pkg.package.conflicts[1].name(), "tinyssh");
+        assert_eq!(pkg.package.conflicts[1].reason(), Some("both bind port 22"));
+    }
+
+    #[test]
+    fn test_package_toolchain_override_forms() {
+        let base = r#"
+[package]
+name = "tool"
+version = "1.0.0"
+description = "Needs gcc"
+
+[source]
+url = "https://example.com/tool.tar.gz"
+sha256 = "abc123"
+"#;
+
+        let pkg = PackageDefinition::from_toml(base).unwrap();
+        assert!(pkg.build.toolchain.is_none());
+        assert!(!pkg.build.uses_gcc());
+
+        let pkg = PackageDefinition::from_toml(&format!("{base}\n[build]\ntoolchain = \"gcc\"\n"))
+            .unwrap();
+        assert!(pkg.build.uses_gcc());
+        assert_eq!(pkg.build.toolchain.as_ref().unwrap().target(), None);
+
+        let pkg = PackageDefinition::from_toml(&format!(
+            "{base}\n[build.toolchain]\ntype = \"gcc\"\ntarget = \"arm-linux-gnueabihf\"\n"
+        ))
+        .unwrap();
+        assert!(pkg.build.uses_gcc());
+        assert_eq!(
+            pkg.build.toolchain.as_ref().unwrap().target(),
+            Some("arm-linux-gnueabihf")
+        );
+    }
+
     // ============================================
     // Round-trip tests
     // ============================================
@@ -393,16 +837,25 @@ rev = "abc123def456"
                 homepage: None,
                 keywords: vec!["test".to_string()],
                 depends: vec![],
+                build_depends: vec![],
+                host_depends: vec![],
                 requires: vec![],
                 arch: vec![],
+                supported_libc: vec![],
                 provides: vec![],
                 conflicts: vec![],
                 zigroot_version: None,
+                license_files: Vec::new(),
             },
             source: SourceConfig::Url {
                 url: "https://example.com/test-1.0.0.tar.gz".to_string(),
-                sha256: "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
-                    .to_string(),
+                mirrors: vec!["https://mirror.example.com/test-1.0.0.tar.gz".to_string()],
+                sha256: Some(
+                    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855".to_string(),
+                ),
+                sha512: None,
+                blake3: None,
+                patches: Vec::new(),
             },
             build: PackageBuildConfig::default(),
             options: HashMap::new(),
@@ -517,8 +970,36 @@ url = "https://example.com/test.tar.gz"
 "#;
 
         let result = PackageDefinition::from_toml(toml_content);
-        // This should fail because URL requires sha256
-        assert!(result.is_err(), "URL source without sha256 should fail");
+        // This should fail because URL requires a checksum
+        let error = result.expect_err("URL source without checksum should fail");
+        assert!(error.to_string().contains("has no checksum"), "{error}");
+    }
+
+    #[test]
+    fn test_url_source_accepts_one_checksum_algorithm() {
+        let package = |fields: &str| {
+            format!(
+                "[package]\nname = \"test-pkg\"\nversion = \"1.0.0\"\ndescription = \"d\"\n\n\
+                 [source]\nurl = \"https://example.com/test.tar.gz\"\n{fields}"
+            )
+        };
+
+        let sha512 = format!("sha512 = \"{}\"\n", "a".repeat(128));
+        let pkg = PackageDefinition::from_toml(&package(&sha512)).unwrap();
+        let checksums = pkg.source.checksums().unwrap();
+        assert_eq!(checksums[0].1.algorithm, ChecksumAlgorithm::Sha512);
+
+        let blake3 = format!("blake3 = \"{}\"\n", "b".repeat(64));
+        let pkg = PackageDefinition::from_toml(&package(&blake3)).unwrap();
+        assert_eq!(pkg.to_toml().unwrap().matches("blake3").count(), 1);
+
+        let both = PackageDefinition::from_toml(&package(&format!("{sha512}{blake3}")));
+        assert!(both.unwrap_err().to_string().contains("several checksums"));
+
+        let short = format!("sha512 = \"{}\"\n", "a".repeat(64));
+        let pkg = PackageDefinition::from_toml(&package(&short)).unwrap();
+        let error = pkg.source.checksums().unwrap_err();
+        assert!(error.contains("Malformed sha512 checksum"), "{error}");
     }
 
     #[test]
@@ -571,8 +1052,14 @@ git = "https://github.com/example/repo"
 
     /// Strategy for generating URL source config
     fn url_source_strategy() -> impl Strategy<Value = SourceConfig> {
-        (url_strategy(), sha256_strategy())
-            .prop_map(|(url, sha256)| SourceConfig::Url { url, sha256 })
+        (url_strategy(), sha256_strategy()).prop_map(|(url, sha256)| SourceConfig::Url {
+            url,
+            mirrors: Vec::new(),
+            sha256: Some(sha256),
+            sha512: None,
+            blake3: None,
+            patches: Vec::new(),
+        })
     }
 
     /// Strategy for generating a complete PackageDefinition with URL source
@@ -592,11 +1079,15 @@ git = "https://github.com/example/repo"
                     homepage: None,
                     keywords: vec![],
                     depends: vec![],
+                    build_depends: vec![],
+                    host_depends: vec![],
                     requires: vec![],
                     arch: vec![],
+                    supported_libc: vec![],
                     provides: vec![],
                     conflicts: vec![],
                     zigroot_version: None,
+                    license_files: Vec::new(),
                 },
                 source,
                 build: PackageBuildConfig::default(),
@@ -642,15 +1133,23 @@ git = "https://github.com/example/repo"
                     homepage: None,
                     keywords: vec![],
                     depends: vec![],
+                    build_depends: vec![],
+                    host_depends: vec![],
                     requires: vec![],
                     arch: vec![],
+                    supported_libc: vec![],
                     provides: vec![],
                     conflicts: vec![],
                     zigroot_version: None,
+                    license_files: Vec::new(),
                 },
                 source: SourceConfig::Url {
                     url: "https://example.com/test.tar.gz".to_string(),
-                    sha256: "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855".to_string(),
+                    mirrors: Vec::new(),
+                    sha256: Some("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855".to_string()),
+                    sha512: None,
+                    blake3: None,
+                    patches: Vec::new(),
                 },
                 build: PackageBuildConfig::default(),
                 options: HashMap::new(),
@@ -668,15 +1167,19 @@ git = "https://github.com/example/repo"
         /// **Validates: Requirements 18.11**
         #[test]
         fn prop_url_source_has_sha256(url in url_strategy(), sha256 in sha256_strategy()) {
-            let source = SourceConfig::Url { url: url.clone(), sha256: sha256.clone() };
+            let source = SourceConfig::Url {
+                url: url.clone(),
+                mirrors: Vec::new(),
+                sha256: Some(sha256.clone()),
+                sha512: None,
+                blake3: None,
+                patches: Vec::new(),
+            };
 
-            match source {
-                SourceConfig::Url { sha256: hash, .. } => {
-                    prop_assert!(!hash.is_empty(), "URL source must have non-empty sha256");
-                    prop_assert_eq!(hash.len(), 64, "SHA256 must be 64 hex characters");
-                }
-                _ => prop_assert!(false, "Expected URL source"),
-            }
+            let checksums = source.checksums().expect("URL source has a checksum");
+            prop_assert_eq!(checksums.len(), 1);
+            prop_assert_eq!(checksums[0].0, url.as_str());
+            prop_assert_eq!(&checksums[0].1.digest, &sha256);
         }
     }
 }
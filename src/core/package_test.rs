@@ -0,0 +1,420 @@
+//! Test builds of a package in throwaway projects
+//!
+//! `zigroot package test` copies the package under test into a scratch
+//! project created like `zigroot init` does, and fetches and builds it once
+//! per target: a board, or a bare target triple built against a placeholder
+//! board. Registry packages (`metadata.toml` and version files) are merged
+//! into a local `package.toml` first.
+//!
+//! A run that passes on every target is recorded by the digest of the
+//! package's files, so `zigroot publish` can tell whether the package was
+//! tested as it is published.
+
+use std::fmt::{self, Write};
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::core::init::{self, ManifestSettings};
+use crate::core::package::PackageDefinition;
+use crate::core::signing::SIGNATURE_FILE;
+use crate::core::version::compare_versions;
+use crate::infra::download::compute_checksum;
+
+/// Directory under the data directory recording passed package tests
+pub const TESTED_DIR: &str = "package-tests";
+
+/// Name of the placeholder board used to build for a bare target triple
+pub const TRIPLE_BOARD: &str = "zigroot-test-target";
+
+/// Package test errors
+#[derive(Error, Debug)]
+pub enum PackageTestError {
+    /// The directory holds neither `package.toml` nor `metadata.toml`
+    #[error("No package.toml or metadata.toml found in '{path}'. Is this a package directory?")]
+    NotAPackage { path: PathBuf },
+
+    /// A registry package without version files
+    #[error("No version files found in '{path}'")]
+    NoVersion { path: PathBuf },
+
+    /// A definition file does not describe a package
+    #[error("Invalid package definition '{path}': {error}")]
+    Invalid { path: PathBuf, error: String },
+
+    /// The scratch project cannot be written
+    #[error("Cannot create scratch project at '{path}': {error}")]
+    Io { path: PathBuf, error: String },
+}
+
+/// Target of a test build
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestTarget {
+    /// Build for a board
+    Board(String),
+    /// Build for a target triple, without a board
+    Triple(String),
+}
+
+impl TestTarget {
+    /// Name of the target's scratch project directory
+    pub fn dir_name(&self) -> String {
+        match self {
+            Self::Board(name) => format!("board-{name}"),
+            Self::Triple(triple) => format!("target-{triple}"),
+        }
+    }
+}
+
+impl fmt::Display for TestTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Board(name) => write!(f, "board {name}"),
+            Self::Triple(triple) => write!(f, "target {triple}"),
+        }
+    }
+}
+
+/// Definition of the package in `pkg_path`
+///
+/// A local `package.toml` is used as is. A registry package's
+/// `metadata.toml` is merged with its newest version file: the version's
+/// `[release]` version, its `[dependencies]` lists and its other tables,
+/// such as `[source]`.
+pub fn package_definition(pkg_path: &Path) -> Result<PackageDefinition, PackageTestError> {
+    let local = pkg_path.join("package.toml");
+    if local.is_file() {
+        return PackageDefinition::from_toml(&read(&local)?)
+            .map_err(|e| invalid(&local, e.to_string()));
+    }
+    let metadata_path = pkg_path.join("metadata.toml");
+    if !metadata_path.is_file() {
+        return Err(PackageTestError::NotAPackage {
+            path: pkg_path.to_path_buf(),
+        });
+    }
+
+    let mut definition = parse_table(&metadata_path)?;
+    let version_path = latest_version_file(pkg_path)?;
+    let version = parse_table(&version_path)?;
+    let release = version
+        .get("release")
+        .and_then(|release| release.get("version"))
+        .and_then(toml::Value::as_str)
+        .ok_or_else(|| invalid(&version_path, "missing [release] version".to_string()))?;
+
+    let package = definition
+        .entry("package")
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| invalid(&metadata_path, "[package] must be a table".to_string()))?;
+    package.insert("version".to_string(), release.into());
+    if let Some(dependencies) = version.get("dependencies").and_then(toml::Value::as_table) {
+        for (kind, names) in dependencies {
+            let Some(names) = names.as_array() else {
+                continue;
+            };
+            if let Some(list) = package
+                .entry(kind.as_str())
+                .or_insert_with(|| toml::Value::Array(Vec::new()))
+                .as_array_mut()
+            {
+                list.extend(names.iter().cloned());
+            }
+        }
+    }
+    for (key, value) in version {
+        if key != "release" && key != "dependencies" {
+            definition.insert(key, value);
+        }
+    }
+    toml::Value::Table(definition)
+        .try_into()
+        .map_err(|e| invalid(&version_path, e.to_string()))
+}
+
+/// Board definition building for `triple` with a generic CPU
+pub fn placeholder_board(triple: &str) -> String {
+    format!(
+        "[board]\nname = \"{TRIPLE_BOARD}\"\ndescription = \"Placeholder board for {triple}\"\n\
+         target = \"{triple}\"\ncpu = \"generic\"\n\n\
+         [defaults]\nimage_format = \"ext4\"\nrootfs_size = \"auto\"\nhostname = \"zigroot\"\n"
+    )
+}
+
+/// Create a scratch project in `dir` building the package in `pkg_path`
+///
+/// The project uses the board `board_name`, defined by `board_toml`, and
+/// holds the package as a local package, with its build dependencies as
+/// registry packages.
+pub fn create_scratch_project(
+    dir: &Path,
+    pkg_path: &Path,
+    definition: &PackageDefinition,
+    board_name: &str,
+    board_toml: &str,
+) -> Result<(), PackageTestError> {
+    let io = |path: &Path, error: String| PackageTestError::Io {
+        path: path.to_path_buf(),
+        error,
+    };
+    init::create_project_structure(dir).map_err(|e| io(dir, e.to_string()))?;
+
+    let name = &definition.package.name;
+    let package_dir = dir.join("packages").join(name);
+    copy_dir(pkg_path, &package_dir)?;
+    let package_toml = definition
+        .to_toml()
+        .map_err(|e| invalid(pkg_path, e.to_string()))?;
+    write(&package_dir.join("package.toml"), &package_toml)?;
+    write(
+        &dir.join("boards").join(board_name).join("board.toml"),
+        board_toml,
+    )?;
+
+    let settings = ManifestSettings::new(&format!("{name}-test"), Some(board_name));
+    let mut manifest = init::generate_manifest_content(&settings, None);
+    let _ = write!(
+        manifest,
+        "\n[packages.{name}]\nversion = \"{}\"\n",
+        definition.package.version
+    );
+    for dependency in definition.package.build_order_dependencies() {
+        let _ = write!(manifest, "\n[packages.{dependency}]\n");
+    }
+    write(&dir.join("zigroot.toml"), &manifest)
+}
+
+/// Digest of the files of a package, apart from its signature
+pub fn package_digest(pkg_path: &Path) -> Result<String, PackageTestError> {
+    let mut data = Vec::new();
+    for entry in walkdir::WalkDir::new(pkg_path).sort_by_file_name() {
+        let entry = entry.map_err(|e| PackageTestError::Io {
+            path: pkg_path.to_path_buf(),
+            error: e.to_string(),
+        })?;
+        if !entry.file_type().is_file() || entry.file_name() == SIGNATURE_FILE {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(pkg_path).unwrap_or(entry.path());
+        data.extend_from_slice(relative.to_string_lossy().as_bytes());
+        data.push(0);
+        data.extend(
+            std::fs::read(entry.path()).map_err(|e| PackageTestError::Io {
+                path: entry.path().to_path_buf(),
+                error: e.to_string(),
+            })?,
+        );
+        data.push(0);
+    }
+    Ok(compute_checksum(&data))
+}
+
+/// Record that the package with `digest` passed its tests on `targets`
+pub fn record_tested(
+    data_dir: &Path,
+    digest: &str,
+    targets: &[TestTarget],
+) -> Result<(), PackageTestError> {
+    let content = targets.iter().fold(String::new(), |mut content, target| {
+        let _ = writeln!(content, "{target}");
+        content
+    });
+    write(&data_dir.join(TESTED_DIR).join(digest), &content)
+}
+
+/// Whether the package with `digest` passed its tests
+pub fn is_tested(data_dir: &Path, digest: &str) -> bool {
+    data_dir.join(TESTED_DIR).join(digest).is_file()
+}
+
+/// Newest version file of a registry package
+fn latest_version_file(pkg_path: &Path) -> Result<PathBuf, PackageTestError> {
+    let entries = std::fs::read_dir(pkg_path).map_err(|e| PackageTestError::Io {
+        path: pkg_path.to_path_buf(),
+        error: e.to_string(),
+    })?;
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().is_some_and(|e| e == "toml")
+                && path
+                    .file_name()
+                    .is_some_and(|n| n != "metadata.toml" && n != "package.toml")
+        })
+        .max_by(|a, b| {
+            let (a, b) = (
+                a.file_stem().unwrap_or_default(),
+                b.file_stem().unwrap_or_default(),
+            );
+            compare_versions(&a.to_string_lossy(), &b.to_string_lossy())
+                .unwrap_or_else(|_| a.cmp(b))
+        })
+        .ok_or_else(|| PackageTestError::NoVersion {
+            path: pkg_path.to_path_buf(),
+        })
+}
+
+fn parse_table(path: &Path) -> Result<toml::Table, PackageTestError> {
+    toml::from_str(&read(path)?).map_err(|e| invalid(path, e.to_string()))
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), PackageTestError> {
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry.map_err(|e| PackageTestError::Io {
+            path: from.to_path_buf(),
+            error: e.to_string(),
+        })?;
+        let dest = to.join(entry.path().strip_prefix(from).unwrap_or(entry.path()));
+        let copied = if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&dest)
+        } else {
+            std::fs::copy(entry.path(), &dest).map(|_| ())
+        };
+        copied.map_err(|e| PackageTestError::Io {
+            path: dest,
+            error: e.to_string(),
+        })?;
+    }
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<String, PackageTestError> {
+    std::fs::read_to_string(path).map_err(|e| invalid(path, e.to_string()))
+}
+
+fn write(path: &Path, content: &str) -> Result<(), PackageTestError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| PackageTestError::Io {
+            path: parent.to_path_buf(),
+            error: e.to_string(),
+        })?;
+    }
+    std::fs::write(path, content).map_err(|e| PackageTestError::Io {
+        path: path.to_path_buf(),
+        error: e.to_string(),
+    })
+}
+
+fn invalid(path: &Path, error: String) -> PackageTestError {
+    PackageTestError::Invalid {
+        path: path.to_path_buf(),
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::board::BoardDefinition;
+    use crate::core::manifest::Manifest;
+    use tempfile::TempDir;
+
+    fn registry_package(dir: &Path) {
+        std::fs::write(
+            dir.join("metadata.toml"),
+            "[package]\nname = \"hello\"\ndescription = \"Hello\"\nlicense = \"MIT\"\n\n\
+             [build]\ntype = \"custom\"\n",
+        )
+        .unwrap();
+        for version in ["1.9.0", "1.10.0"] {
+            std::fs::write(
+                dir.join(format!("{version}.toml")),
+                format!(
+                    "[release]\nversion = \"{version}\"\n\n[source]\n\
+                     url = \"https://example.com/hello-{version}.tar.gz\"\nsha256 = \"{}\"\n\n\
+                     [dependencies]\ndepends = [\"zlib\"]\n",
+                    "0".repeat(64)
+                ),
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_registry_package_merges_newest_version() {
+        let temp = TempDir::new().unwrap();
+        registry_package(temp.path());
+
+        let definition = package_definition(temp.path()).unwrap();
+        assert_eq!(definition.package.name, "hello");
+        assert_eq!(definition.package.version, "1.10.0");
+        assert_eq!(definition.package.depends, vec!["zlib"]);
+        assert_eq!(definition.build.build_type.as_deref(), Some("custom"));
+        assert!(definition.source.checksums().unwrap()[0]
+            .0
+            .ends_with("hello-1.10.0.tar.gz"));
+    }
+
+    #[test]
+    fn test_package_definition_errors() {
+        let temp = TempDir::new().unwrap();
+        assert!(matches!(
+            package_definition(temp.path()),
+            Err(PackageTestError::NotAPackage { .. })
+        ));
+        std::fs::write(
+            temp.path().join("metadata.toml"),
+            "[package]\nname = \"x\"\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            package_definition(temp.path()),
+            Err(PackageTestError::NoVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn test_create_scratch_project() {
+        let temp = TempDir::new().unwrap();
+        let pkg = temp.path().join("hello");
+        std::fs::create_dir_all(pkg.join("patches")).unwrap();
+        std::fs::write(pkg.join("patches/fix.patch"), "").unwrap();
+        registry_package(&pkg);
+        let definition = package_definition(&pkg).unwrap();
+
+        let dir = temp.path().join("scratch");
+        let board = placeholder_board("aarch64-linux-musl");
+        create_scratch_project(&dir, &pkg, &definition, TRIPLE_BOARD, &board).unwrap();
+
+        let manifest =
+            Manifest::from_toml(&std::fs::read_to_string(dir.join("zigroot.toml")).unwrap())
+                .unwrap();
+        assert_eq!(manifest.board.name.as_deref(), Some(TRIPLE_BOARD));
+        assert_eq!(
+            manifest.packages["hello"].version.as_deref(),
+            Some("1.10.0")
+        );
+        assert!(manifest.packages.contains_key("zlib"));
+        assert!(dir.join("packages/hello/patches/fix.patch").exists());
+        let local = std::fs::read_to_string(dir.join("packages/hello/package.toml")).unwrap();
+        assert_eq!(PackageDefinition::from_toml(&local).unwrap(), definition);
+        let board = BoardDefinition::from_toml(
+            &std::fs::read_to_string(dir.join("boards").join(TRIPLE_BOARD).join("board.toml"))
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(board.board.target, "aarch64-linux-musl");
+    }
+
+    #[test]
+    fn test_tested_record_follows_package_files() {
+        let temp = TempDir::new().unwrap();
+        let pkg = temp.path().join("hello");
+        std::fs::create_dir_all(&pkg).unwrap();
+        registry_package(&pkg);
+        let data_dir = temp.path().join("data");
+
+        let digest = package_digest(&pkg).unwrap();
+        assert!(!is_tested(&data_dir, &digest));
+        record_tested(&data_dir, &digest, &[TestTarget::Board("qemu".into())]).unwrap();
+        assert!(is_tested(&data_dir, &digest));
+
+        // Signing does not change the digest, editing does
+        std::fs::write(pkg.join(SIGNATURE_FILE), "sig").unwrap();
+        assert_eq!(package_digest(&pkg).unwrap(), digest);
+        std::fs::write(pkg.join("1.10.0.toml"), "[release]\n").unwrap();
+        assert!(!is_tested(&data_dir, &package_digest(&pkg).unwrap()));
+    }
+}
@@ -0,0 +1,878 @@
+//! Disk image layout and partition tables
+//!
+//! A disk image is described by a `[[partitions]]` array and an optional
+//! `[disk_image]` table, in the manifest or the board defaults. This module
+//! computes partition offsets, validates the layout and renders GPT or MBR
+//! partition tables. Filling the partitions happens in [`crate::core::builder`].
+//!
+//! `[disk_image] partition_table` can name an external artifact of type
+//! `partition_table`; its `format` then selects the table of the disk image,
+//! so that the image matches the table the board's flash tools expect.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::core::board::BoardDefinition;
+use crate::core::builder::content_size;
+use crate::core::external::{artifact_file, resolve_reference};
+use crate::core::flash::load_board_definition;
+use crate::core::manifest::Manifest;
+use crate::core::size::parse_size;
+
+/// Sector size used for partition tables
+pub const SECTOR_SIZE: u64 = 512;
+
+/// Default disk image file name in the output directory
+pub const DEFAULT_DISK_IMAGE: &str = "disk.img";
+
+/// Valid partition table formats
+pub const VALID_PARTITION_TABLES: &[&str] = &["gpt", "mbr"];
+
+/// Valid partition types
+pub const VALID_PARTITION_TYPES: &[&str] = &["fat32", "ext4", "raw"];
+
+/// Content reference for the built rootfs image
+pub const ROOTFS_CONTENT: &str = "rootfs";
+
+/// Alignment of partitions without an explicit offset
+const ALIGNMENT: u64 = 1 << 20;
+
+/// Number of GPT partition entries
+const GPT_ENTRIES: u32 = 128;
+
+/// Size of a GPT partition entry in bytes
+const GPT_ENTRY_SIZE: u32 = 128;
+
+/// Sectors taken by the GPT partition entry array
+const GPT_ENTRY_SECTORS: u64 = (GPT_ENTRIES * GPT_ENTRY_SIZE / 512) as u64;
+
+/// GPT partition type for Linux filesystems
+const GPT_LINUX_FILESYSTEM: &str = "0FC63DAF-8483-4772-8E79-3D69D8477DE4";
+
+/// GPT partition type for FAT filesystems
+const GPT_BASIC_DATA: &str = "EBD0A0A2-B9E5-4433-87C0-68B6B72699C7";
+
+/// Partition layout errors
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PartitionError {
+    /// Size or offset could not be parsed
+    #[error("Invalid {field} '{value}' for partition '{name}'")]
+    InvalidSize {
+        name: String,
+        field: String,
+        value: String,
+    },
+
+    /// Unknown partition type
+    #[error("Unknown type '{partition_type}' for partition '{name}': must be one of {}", VALID_PARTITION_TYPES.join(", "))]
+    UnknownType {
+        name: String,
+        partition_type: String,
+    },
+
+    /// Unknown partition table format
+    #[error("Unknown partition table format '{format}': must be one of {}", VALID_PARTITION_TABLES.join(", "))]
+    UnknownTable { format: String },
+
+    /// Offset or size not a multiple of the sector size
+    #[error("Partition '{name}' {field} {value} is not a multiple of {SECTOR_SIZE} bytes")]
+    Misaligned {
+        name: String,
+        field: String,
+        value: u64,
+    },
+
+    /// Partition starts inside the partition table
+    #[error("Partition '{name}' starts at byte {offset}, inside the {table} partition table (first usable byte is {first_usable})")]
+    InsideTable {
+        name: String,
+        offset: u64,
+        table: String,
+        first_usable: u64,
+    },
+
+    /// Two partitions share bytes
+    #[error("Partitions '{first}' (bytes {first_start}..{first_end}) and '{second}' (bytes {second_start}..{second_end}) overlap by {} bytes", first_end - second_start)]
+    Overlap {
+        first: String,
+        first_start: u64,
+        first_end: u64,
+        second: String,
+        second_start: u64,
+        second_end: u64,
+    },
+
+    /// Partition extends past the end of the disk
+    #[error(
+        "Partition '{name}' ends at byte {end}, but the disk image has only {usable} usable bytes"
+    )]
+    DiskTooSmall { name: String, end: u64, usable: u64 },
+
+    /// MBR limits exceeded
+    #[error("MBR supports at most 4 partitions and 2 TiB disks, found {count} partitions on {size} bytes")]
+    MbrLimits { count: usize, size: u64 },
+
+    /// The `partition_table` artifact of `[disk_image]` cannot be used
+    #[error("Partition table artifact '{name}' {reason}")]
+    TableArtifact { name: String, reason: String },
+
+    /// Content does not fit into its partition
+    #[error("Content of partition '{name}' is {content} bytes, but the partition is {size} bytes ({} bytes too large)", content - size)]
+    ContentTooLarge {
+        name: String,
+        content: u64,
+        size: u64,
+    },
+}
+
+/// Disk image settings from a `[disk_image]` table
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DiskImageConfig {
+    /// Partition table format (gpt or mbr)
+    #[serde(default = "default_table_format")]
+    pub format: String,
+
+    /// Image file name in the output directory
+    #[serde(default = "default_disk_name")]
+    pub name: String,
+
+    /// Total disk size; defaults to the end of the last partition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<String>,
+
+    /// External `partition_table` artifact whose format selects the table
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_table: Option<String>,
+}
+
+impl Default for DiskImageConfig {
+    fn default() -> Self {
+        Self {
+            format: default_table_format(),
+            name: default_disk_name(),
+            size: None,
+            partition_table: None,
+        }
+    }
+}
+
+fn default_table_format() -> String {
+    "gpt".to_string()
+}
+
+fn default_disk_name() -> String {
+    DEFAULT_DISK_IMAGE.to_string()
+}
+
+/// A partition from a `[[partitions]]` entry
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PartitionSpec {
+    /// Partition name (GPT partition label)
+    pub name: String,
+
+    /// Partition size (e.g., "64M")
+    pub size: String,
+
+    /// Partition type (fat32, ext4, raw)
+    #[serde(rename = "type")]
+    pub partition_type: String,
+
+    /// Content: "rootfs", an external artifact name, a file or a directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+
+    /// Byte offset on the disk; defaults to the next 1 MiB boundary
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<String>,
+}
+
+/// Partition table format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionTable {
+    /// GUID partition table
+    Gpt,
+    /// Master boot record
+    Mbr,
+}
+
+impl PartitionTable {
+    /// First byte available to partitions
+    fn first_usable(self) -> u64 {
+        match self {
+            Self::Gpt => (2 + GPT_ENTRY_SECTORS) * SECTOR_SIZE,
+            Self::Mbr => SECTOR_SIZE,
+        }
+    }
+
+    /// Bytes reserved at the end of the disk
+    fn trailer(self) -> u64 {
+        match self {
+            Self::Gpt => (1 + GPT_ENTRY_SECTORS) * SECTOR_SIZE,
+            Self::Mbr => 0,
+        }
+    }
+}
+
+impl std::fmt::Display for PartitionTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Gpt => write!(f, "GPT"),
+            Self::Mbr => write!(f, "MBR"),
+        }
+    }
+}
+
+/// A partition with its resolved position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partition {
+    /// Partition name
+    pub name: String,
+    /// Partition type (fat32, ext4, raw)
+    pub partition_type: String,
+    /// Content reference
+    pub content: Option<String>,
+    /// Byte offset on the disk
+    pub offset: u64,
+    /// Size in bytes
+    pub size: u64,
+}
+
+impl Partition {
+    /// Byte offset just past the partition
+    pub fn end(&self) -> u64 {
+        self.offset + self.size
+    }
+
+    /// Fail if content of `bytes` does not fit
+    pub fn check_content_size(&self, bytes: u64) -> Result<(), PartitionError> {
+        if bytes > self.size {
+            return Err(PartitionError::ContentTooLarge {
+                name: self.name.clone(),
+                content: bytes,
+                size: self.size,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A validated disk layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskLayout {
+    /// Partition table format
+    pub table: PartitionTable,
+    /// Partitions in declaration order
+    pub partitions: Vec<Partition>,
+    /// Total disk size in bytes
+    pub size: u64,
+}
+
+/// Get the disk image configuration of a project
+///
+/// Partitions declared in the manifest take precedence over the board
+/// defaults. Returns `None` when no partitions are declared.
+pub fn disk_config(
+    manifest: &Manifest,
+    board: Option<&BoardDefinition>,
+) -> Option<(DiskImageConfig, Vec<PartitionSpec>)> {
+    let defaults = board.map(|b| &b.defaults);
+    let partitions = if manifest.partitions.is_empty() {
+        defaults.map(|d| d.partitions.clone()).unwrap_or_default()
+    } else {
+        manifest.partitions.clone()
+    };
+    if partitions.is_empty() {
+        return None;
+    }
+
+    let mut config = manifest
+        .disk_image
+        .clone()
+        .or_else(|| defaults.and_then(|d| d.disk_image.clone()))
+        .unwrap_or_default();
+    if let Some(format) = config
+        .partition_table
+        .as_ref()
+        .and_then(|name| manifest.external.get(name))
+        .filter(|artifact| artifact.artifact_type == "partition_table")
+        .and_then(|artifact| artifact.format.clone())
+    {
+        config.format = format;
+    }
+    Some((config, partitions))
+}
+
+/// Get the disk image configuration of a project, loading its board
+pub fn project_disk_config(
+    project_dir: &Path,
+    manifest: &Manifest,
+) -> Option<(DiskImageConfig, Vec<PartitionSpec>)> {
+    let board = manifest
+        .board
+        .name
+        .as_deref()
+        .and_then(|name| load_board_definition(project_dir, name).ok());
+    disk_config(manifest, board.as_ref())
+}
+
+/// Resolve a partition's content to a path
+///
+/// Returns `None` for the built rootfs image, which only exists after the
+/// build; other references name an external artifact or a project path.
+pub fn content_path(project_dir: &Path, manifest: &Manifest, content: &str) -> Option<PathBuf> {
+    (content != ROOTFS_CONTENT).then(|| resolve_reference(project_dir, manifest, content))
+}
+
+/// Check a project's disk layout and the content that already exists
+///
+/// Returns all problems found; an empty list when no layout is configured.
+pub fn check_layout(project_dir: &Path, manifest: &Manifest) -> Vec<String> {
+    let Some((config, specs)) = project_disk_config(project_dir, manifest) else {
+        return Vec::new();
+    };
+    if let Err(e) = check_table_artifact(project_dir, manifest, &config) {
+        return vec![e.to_string()];
+    }
+    let layout = match DiskLayout::new(&config, &specs) {
+        Ok(layout) => layout,
+        Err(e) => return vec![e.to_string()],
+    };
+
+    let mut errors = Vec::new();
+    for partition in &layout.partitions {
+        let Some(content) = partition.content.as_deref() else {
+            continue;
+        };
+        let Some(path) = content_path(project_dir, manifest, content) else {
+            continue;
+        };
+        if !path.exists() {
+            errors.push(format!(
+                "Content '{content}' of partition '{}' not found at {}",
+                partition.name,
+                path.display()
+            ));
+            continue;
+        }
+        match content_size(&path) {
+            Ok(bytes) => {
+                if let Err(e) = partition.check_content_size(bytes) {
+                    errors.push(e.to_string());
+                }
+            }
+            Err(e) => errors.push(format!("Failed to read {}: {e}", path.display())),
+        }
+    }
+    errors
+}
+
+/// Check the `partition_table` artifact named by `[disk_image]`
+///
+/// It must be declared with a format usable for disk images, and a file
+/// that is already present must hold a table of that format.
+pub fn check_table_artifact(
+    project_dir: &Path,
+    manifest: &Manifest,
+    config: &DiskImageConfig,
+) -> Result<(), PartitionError> {
+    let Some(name) = &config.partition_table else {
+        return Ok(());
+    };
+    let error = |reason: String| PartitionError::TableArtifact {
+        name: name.clone(),
+        reason,
+    };
+    let artifact = manifest
+        .external
+        .get(name)
+        .ok_or_else(|| error("is not declared in [external]".to_string()))?;
+    if artifact.artifact_type != "partition_table" {
+        return Err(error(format!(
+            "has type {}, expected partition_table",
+            artifact.artifact_type
+        )));
+    }
+    let table = match artifact.format.as_deref() {
+        Some("gpt") => PartitionTable::Gpt,
+        Some("mbr") => PartitionTable::Mbr,
+        Some(other) => {
+            return Err(error(format!(
+                "has format {other}, which is written by the flash tool and cannot be used for a disk image; use {}",
+                VALID_PARTITION_TABLES.join(" or ")
+            )))
+        }
+        None => {
+            return Err(error(format!(
+                "has no format; set format to {}",
+                VALID_PARTITION_TABLES.join(" or ")
+            )))
+        }
+    };
+
+    let path = artifact_file(project_dir, name, artifact);
+    let Ok(data) = std::fs::read(&path) else {
+        return Ok(());
+    };
+    let valid = match table {
+        PartitionTable::Mbr => data.get(510..512) == Some([0x55, 0xAA].as_slice()),
+        PartitionTable::Gpt => data.get(512..520) == Some(b"EFI PART".as_slice()),
+    };
+    if !valid {
+        return Err(error(format!(
+            "at {} does not hold a {table} partition table",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+fn align_up(value: u64, alignment: u64) -> u64 {
+    value.div_ceil(alignment) * alignment
+}
+
+impl DiskLayout {
+    /// Compute and validate the layout
+    pub fn new(config: &DiskImageConfig, specs: &[PartitionSpec]) -> Result<Self, PartitionError> {
+        let table = match config.format.as_str() {
+            "gpt" => PartitionTable::Gpt,
+            "mbr" => PartitionTable::Mbr,
+            other => {
+                return Err(PartitionError::UnknownTable {
+                    format: other.to_string(),
+                })
+            }
+        };
+
+        let mut partitions = Vec::new();
+        let mut next_offset = align_up(table.first_usable(), ALIGNMENT);
+        for spec in specs {
+            let partition = place(spec, table, next_offset)?;
+            next_offset = align_up(partition.end(), ALIGNMENT);
+            partitions.push(partition);
+        }
+
+        check_overlaps(&partitions)?;
+
+        let last_end = partitions.iter().map(Partition::end).max().unwrap_or(0);
+        let size = match &config.size {
+            Some(value) => parse_size(value).map_err(|_| PartitionError::InvalidSize {
+                name: config.name.clone(),
+                field: "disk size".to_string(),
+                value: value.clone(),
+            })?,
+            None => align_up(last_end + table.trailer(), ALIGNMENT),
+        };
+        let usable = size.saturating_sub(table.trailer());
+        if let Some(partition) = partitions.iter().find(|p| p.end() > usable) {
+            return Err(PartitionError::DiskTooSmall {
+                name: partition.name.clone(),
+                end: partition.end(),
+                usable,
+            });
+        }
+        if table == PartitionTable::Mbr
+            && (partitions.len() > 4 || size / SECTOR_SIZE > u64::from(u32::MAX))
+        {
+            return Err(PartitionError::MbrLimits {
+                count: partitions.len(),
+                size,
+            });
+        }
+
+        Ok(Self {
+            table,
+            partitions,
+            size,
+        })
+    }
+
+    /// Render the partition table as `(byte offset, data)` chunks
+    ///
+    /// `seed` makes the disk and partition identifiers deterministic.
+    pub fn table_chunks(&self, seed: &str) -> Vec<(u64, Vec<u8>)> {
+        match self.table {
+            PartitionTable::Mbr => vec![(0, self.mbr(seed))],
+            PartitionTable::Gpt => self.gpt(seed),
+        }
+    }
+
+    fn mbr(&self, seed: &str) -> Vec<u8> {
+        let mut sector = vec![0u8; 512];
+        sector[440..444].copy_from_slice(&seeded_bytes(seed, "mbr")[..4]);
+        for (index, partition) in self.partitions.iter().enumerate() {
+            let system_id = match partition.partition_type.as_str() {
+                "fat32" => 0x0C,
+                "ext4" => 0x83,
+                _ => 0xDA,
+            };
+            write_mbr_entry(
+                &mut sector,
+                index,
+                system_id,
+                partition.offset / SECTOR_SIZE,
+                partition.size / SECTOR_SIZE,
+            );
+        }
+        sector[510] = 0x55;
+        sector[511] = 0xAA;
+        sector
+    }
+
+    fn gpt(&self, seed: &str) -> Vec<(u64, Vec<u8>)> {
+        let sectors = self.size / SECTOR_SIZE;
+        let last_lba = sectors - 1;
+
+        let mut protective = vec![0u8; 512];
+        write_mbr_entry(
+            &mut protective,
+            0,
+            0xEE,
+            1,
+            (sectors - 1).min(u64::from(u32::MAX)),
+        );
+        protective[510] = 0x55;
+        protective[511] = 0xAA;
+
+        let mut entries = vec![0u8; (GPT_ENTRIES * GPT_ENTRY_SIZE) as usize];
+        for (index, partition) in self.partitions.iter().enumerate() {
+            let entry_size = GPT_ENTRY_SIZE as usize;
+            let entry = &mut entries[index * entry_size..(index + 1) * entry_size];
+            let type_guid = if partition.partition_type == "fat32" {
+                GPT_BASIC_DATA
+            } else {
+                GPT_LINUX_FILESYSTEM
+            };
+            entry[..16].copy_from_slice(&parse_guid(type_guid).unwrap_or_default());
+            entry[16..32].copy_from_slice(&seeded_guid(seed, &partition.name));
+            entry[32..40].copy_from_slice(&(partition.offset / SECTOR_SIZE).to_le_bytes());
+            entry[40..48].copy_from_slice(&(partition.end() / SECTOR_SIZE - 1).to_le_bytes());
+            for (i, unit) in partition.name.encode_utf16().take(36).enumerate() {
+                entry[56 + i * 2..58 + i * 2].copy_from_slice(&unit.to_le_bytes());
+            }
+        }
+        let entries_crc = crc32(&entries);
+
+        let disk_guid = seeded_guid(seed, "disk");
+        let backup_entries_lba = last_lba - GPT_ENTRY_SECTORS;
+        let header = |current: u64, backup: u64, entries_lba: u64| {
+            let mut header = vec![0u8; 512];
+            header[..8].copy_from_slice(b"EFI PART");
+            header[8..12].copy_from_slice(&0x0001_0000u32.to_le_bytes());
+            header[12..16].copy_from_slice(&92u32.to_le_bytes());
+            header[24..32].copy_from_slice(&current.to_le_bytes());
+            header[32..40].copy_from_slice(&backup.to_le_bytes());
+            header[40..48].copy_from_slice(&(2 + GPT_ENTRY_SECTORS).to_le_bytes());
+            header[48..56].copy_from_slice(&(backup_entries_lba - 1).to_le_bytes());
+            header[56..72].copy_from_slice(&disk_guid);
+            header[72..80].copy_from_slice(&entries_lba.to_le_bytes());
+            header[80..84].copy_from_slice(&GPT_ENTRIES.to_le_bytes());
+            header[84..88].copy_from_slice(&GPT_ENTRY_SIZE.to_le_bytes());
+            header[88..92].copy_from_slice(&entries_crc.to_le_bytes());
+            let header_crc = crc32(&header[..92]);
+            header[16..20].copy_from_slice(&header_crc.to_le_bytes());
+            header
+        };
+
+        vec![
+            (0, protective),
+            (SECTOR_SIZE, header(1, last_lba, 2)),
+            (2 * SECTOR_SIZE, entries.clone()),
+            (backup_entries_lba * SECTOR_SIZE, entries),
+            (
+                last_lba * SECTOR_SIZE,
+                header(last_lba, 1, backup_entries_lba),
+            ),
+        ]
+    }
+}
+
+/// Resolve a partition's position, defaulting to `next_offset`
+fn place(
+    spec: &PartitionSpec,
+    table: PartitionTable,
+    next_offset: u64,
+) -> Result<Partition, PartitionError> {
+    let parse = |field: &str, value: &str| {
+        parse_size(value).map_err(|_| PartitionError::InvalidSize {
+            name: spec.name.clone(),
+            field: field.to_string(),
+            value: value.to_string(),
+        })
+    };
+    if !VALID_PARTITION_TYPES.contains(&spec.partition_type.as_str()) {
+        return Err(PartitionError::UnknownType {
+            name: spec.name.clone(),
+            partition_type: spec.partition_type.clone(),
+        });
+    }
+    let size = parse("size", &spec.size)?;
+    let offset = match &spec.offset {
+        Some(offset) => parse("offset", offset)?,
+        None => next_offset,
+    };
+    for (field, value) in [("offset", offset), ("size", size)] {
+        if value % SECTOR_SIZE != 0 || (field == "size" && value == 0) {
+            return Err(PartitionError::Misaligned {
+                name: spec.name.clone(),
+                field: field.to_string(),
+                value,
+            });
+        }
+    }
+    if offset < table.first_usable() {
+        return Err(PartitionError::InsideTable {
+            name: spec.name.clone(),
+            offset,
+            table: table.to_string(),
+            first_usable: table.first_usable(),
+        });
+    }
+
+    Ok(Partition {
+        name: spec.name.clone(),
+        partition_type: spec.partition_type.clone(),
+        content: spec.content.clone(),
+        offset,
+        size,
+    })
+}
+
+/// Fail on the first pair of partitions sharing bytes
+fn check_overlaps(partitions: &[Partition]) -> Result<(), PartitionError> {
+    let mut sorted: Vec<&Partition> = partitions.iter().collect();
+    sorted.sort_by_key(|p| p.offset);
+    for pair in sorted.windows(2) {
+        if pair[0].end() > pair[1].offset {
+            return Err(PartitionError::Overlap {
+                first: pair[0].name.clone(),
+                first_start: pair[0].offset,
+                first_end: pair[0].end(),
+                second: pair[1].name.clone(),
+                second_start: pair[1].offset,
+                second_end: pair[1].end(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Write an LBA-addressed MBR partition entry
+fn write_mbr_entry(sector: &mut [u8], index: usize, system_id: u8, start: u64, sectors: u64) {
+    let entry = &mut sector[446 + index * 16..446 + (index + 1) * 16];
+    // CHS fields are unused; 0xFEFFFF marks them as out of range
+    entry[1..4].copy_from_slice(&[0xFE, 0xFF, 0xFF]);
+    entry[4] = system_id;
+    entry[5..8].copy_from_slice(&[0xFE, 0xFF, 0xFF]);
+    entry[8..12].copy_from_slice(&u32::try_from(start).unwrap_or(u32::MAX).to_le_bytes());
+    entry[12..16].copy_from_slice(&u32::try_from(sectors).unwrap_or(u32::MAX).to_le_bytes());
+}
+
+/// Derive 16 stable bytes from a seed and a label
+fn seeded_bytes(seed: &str, label: &str) -> [u8; 16] {
+    let digest = Sha256::digest(format!("{seed}\0{label}").as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    bytes
+}
+
+/// Derive a stable version 4 GUID in on-disk byte order
+fn seeded_guid(seed: &str, label: &str) -> [u8; 16] {
+    let mut guid = seeded_bytes(seed, label);
+    guid[7] = (guid[7] & 0x0F) | 0x40;
+    guid[8] = (guid[8] & 0x3F) | 0x80;
+    guid
+}
+
+/// Parse a GUID string into on-disk (mixed-endian) byte order
+pub fn parse_guid(guid: &str) -> Option<[u8; 16]> {
+    let hex: String = guid.chars().filter(|c| *c != '-').collect();
+    let raw = hex::decode(hex).ok()?;
+    if raw.len() != 16 {
+        return None;
+    }
+    let mut bytes = [0u8; 16];
+    bytes[..4].copy_from_slice(&[raw[3], raw[2], raw[1], raw[0]]);
+    bytes[4..6].copy_from_slice(&[raw[5], raw[4]]);
+    bytes[6..8].copy_from_slice(&[raw[7], raw[6]]);
+    bytes[8..].copy_from_slice(&raw[8..]);
+    Some(bytes)
+}
+
+/// CRC-32 (IEEE) as used by GPT headers
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str, size: &str, offset: Option<&str>) -> PartitionSpec {
+        PartitionSpec {
+            name: name.to_string(),
+            size: size.to_string(),
+            partition_type: "raw".to_string(),
+            content: None,
+            offset: offset.map(str::to_string),
+        }
+    }
+
+    fn config(format: &str) -> DiskImageConfig {
+        DiskImageConfig {
+            format: format.to_string(),
+            ..DiskImageConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_crc32_and_guid() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        let guid = parse_guid(GPT_LINUX_FILESYSTEM).unwrap();
+        assert_eq!(&guid[..4], &[0xAF, 0x3D, 0xC6, 0x0F]);
+        assert_eq!(
+            &guid[8..],
+            &[0x8E, 0x79, 0x3D, 0x69, 0xD8, 0x47, 0x7D, 0xE4]
+        );
+    }
+
+    #[test]
+    fn test_layout_places_partitions_on_mib_boundaries() {
+        let layout = DiskLayout::new(
+            &config("gpt"),
+            &[spec("boot", "1536K", None), spec("rootfs", "4M", None)],
+        )
+        .unwrap();
+        assert_eq!(layout.partitions[0].offset, 1 << 20);
+        assert_eq!(layout.partitions[1].offset, 3 << 20);
+        assert_eq!(layout.size, 8 << 20);
+    }
+
+    #[test]
+    fn test_layout_reports_overlap_with_exact_bytes() {
+        let err = DiskLayout::new(
+            &config("mbr"),
+            &[spec("a", "2M", Some("1M")), spec("b", "1M", Some("2M"))],
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Partitions 'a' (bytes 1048576..3145728) and 'b' (bytes 2097152..3145728) overlap by 1048576 bytes"
+        );
+    }
+
+    #[test]
+    fn test_layout_rejects_table_area_and_small_disk() {
+        let err = DiskLayout::new(&config("gpt"), &[spec("a", "1M", Some("4096"))]).unwrap_err();
+        assert!(matches!(
+            err,
+            PartitionError::InsideTable {
+                first_usable: 17408,
+                ..
+            }
+        ));
+
+        let small = DiskImageConfig {
+            size: Some("2M".to_string()),
+            ..config("mbr")
+        };
+        let err = DiskLayout::new(&small, &[spec("a", "2M", None)]).unwrap_err();
+        assert_eq!(
+            err,
+            PartitionError::DiskTooSmall {
+                name: "a".to_string(),
+                end: 3 << 20,
+                usable: 2 << 20,
+            }
+        );
+    }
+
+    #[test]
+    fn test_content_too_large() {
+        let layout = DiskLayout::new(&config("mbr"), &[spec("a", "1K", None)]).unwrap();
+        let err = layout.partitions[0].check_content_size(1500).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Content of partition 'a' is 1500 bytes, but the partition is 1024 bytes (476 bytes too large)"
+        );
+    }
+
+    #[test]
+    fn test_mbr_table() {
+        let layout = DiskLayout::new(&config("mbr"), &[spec("a", "1M", None)]).unwrap();
+        let chunks = layout.table_chunks("seed");
+        let sector = &chunks[0].1;
+        assert_eq!(&sector[510..], &[0x55, 0xAA]);
+        assert_eq!(sector[446 + 4], 0xDA);
+        assert_eq!(&sector[446 + 8..446 + 12], &2048u32.to_le_bytes());
+        assert_eq!(&sector[446 + 12..446 + 16], &2048u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_gpt_headers_are_consistent() {
+        let layout = DiskLayout::new(&config("gpt"), &[spec("rootfs", "1M", None)]).unwrap();
+        let chunks = layout.table_chunks("seed");
+        let last_lba = layout.size / SECTOR_SIZE - 1;
+
+        let primary = &chunks[1].1;
+        assert_eq!(&primary[..8], b"EFI PART");
+        let mut check = primary[..92].to_vec();
+        check[16..20].fill(0);
+        assert_eq!(&primary[16..20], &crc32(&check).to_le_bytes());
+        assert_eq!(&primary[32..40], &last_lba.to_le_bytes());
+
+        let entries = &chunks[2].1;
+        assert_eq!(&primary[88..92], &crc32(entries).to_le_bytes());
+        assert_eq!(&entries[32..40], &2048u64.to_le_bytes());
+        assert_eq!(&entries[40..48], &4095u64.to_le_bytes());
+        assert_eq!(&entries[56..58], &u16::from(b'r').to_le_bytes());
+
+        let (offset, backup) = &chunks[4];
+        assert_eq!(*offset, last_lba * SECTOR_SIZE);
+        assert_eq!(&backup[24..32], &last_lba.to_le_bytes());
+        assert_eq!(layout.table_chunks("seed"), chunks);
+    }
+
+    #[test]
+    fn test_partition_table_artifact_selects_format() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let manifest = |format: &str| {
+            Manifest::from_toml(&format!(
+                "[project]\nname = \"demo\"\n\n[disk_image]\npartition_table = \"table\"\n\n\
+                 [[partitions]]\nname = \"rootfs\"\nsize = \"4M\"\ntype = \"raw\"\n\n\
+                 [external.table]\ntype = \"partition_table\"\npath = \"table.bin\"\nformat = \"{format}\"\n"
+            ))
+            .unwrap()
+        };
+
+        let mbr = manifest("mbr");
+        let (config, _) = disk_config(&mbr, None).unwrap();
+        assert_eq!(config.format, "mbr");
+        assert!(check_table_artifact(temp.path(), &mbr, &config).is_ok());
+
+        // A present file must hold a table of the declared format
+        std::fs::write(temp.path().join("table.bin"), vec![0u8; 1024]).unwrap();
+        let err = check_table_artifact(temp.path(), &mbr, &config).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("does not hold a MBR partition table"));
+        let mut sector = vec![0u8; 512];
+        sector[510..].copy_from_slice(&[0x55, 0xAA]);
+        std::fs::write(temp.path().join("table.bin"), sector).unwrap();
+        assert!(check_table_artifact(temp.path(), &mbr, &config).is_ok());
+
+        let rockchip = manifest("rockchip");
+        let errors = check_layout(temp.path(), &rockchip);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("has format rockchip, which is written by the flash tool"));
+    }
+}
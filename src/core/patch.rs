@@ -0,0 +1,745 @@
+//! Local patches applied to package sources
+//!
+//! A package lists its patches in `[source]`, relative to the package
+//! directory:
+//!
+//! ```toml
+//! [source]
+//! url = "https://example.com/app-1.0.tar.gz"
+//! sha256 = "..."
+//! patches = ["patches/fix-build.patch", "patches/series"]
+//! ```
+//!
+//! `.patch` and `.diff` files are unified diffs, applied with the first
+//! component of their file names stripped like `patch -p1`. A file named
+//! `series` is a quilt series: one patch per line, relative to the series
+//! file and optionally followed by its strip level (`-p0`); `#` starts a
+//! comment.
+//!
+//! Patches are applied in order to the source directory before the build
+//! steps run. The originals of the files they change are kept in
+//! [`PATCH_STATE_DIR`], so a changed patch set is applied to the pristine
+//! sources again, and a patch that does not apply leaves them untouched.
+
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::infra::download::compute_checksum;
+
+/// Directory in a source tree recording the applied patches and the
+/// originals of the files they changed
+pub const PATCH_STATE_DIR: &str = ".zigroot-patches";
+
+/// Patch errors
+#[derive(Error, Debug)]
+pub enum PatchError {
+    /// The patch or series file cannot be read
+    #[error("Cannot read patch '{patch}': {error}")]
+    Read { patch: String, error: String },
+
+    /// The file is neither a patch nor a series
+    #[error("Unsupported patch file '{patch}': expected a .patch or .diff file or a quilt series")]
+    Unsupported { patch: String },
+
+    /// The patch is not a well-formed unified diff
+    #[error("Patch '{patch}' is malformed at line {line}: {message}")]
+    Malformed {
+        patch: String,
+        line: usize,
+        message: String,
+    },
+
+    /// A hunk does not match the file it changes
+    #[error("Patch '{patch}' does not apply: hunk #{hunk} ({header}) failed in {file}")]
+    HunkFailed {
+        patch: String,
+        file: String,
+        hunk: usize,
+        header: String,
+    },
+
+    /// The patched file is missing, or exists although the patch creates it
+    #[error("Patch '{patch}' does not apply: {file} {reason}")]
+    FileState {
+        patch: String,
+        file: String,
+        reason: String,
+    },
+
+    /// The sources cannot be changed
+    #[error("Cannot patch '{path}': {error}")]
+    Io { path: PathBuf, error: String },
+}
+
+/// A patch to apply, resolved from a package's patch list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patch {
+    /// Path relative to the package directory, for messages
+    pub name: String,
+    /// Patch file
+    pub path: PathBuf,
+    /// Leading components stripped from the file names in the patch
+    pub strip: usize,
+}
+
+/// Resolve a package's patch list into the patches to apply, in order
+///
+/// Series files are expanded into the patches they list.
+pub fn resolve_patches(package_dir: &Path, entries: &[String]) -> Result<Vec<Patch>, PatchError> {
+    let mut patches = Vec::new();
+    for entry in entries {
+        let entry_path = package_dir.join(entry);
+        let file_name = entry_path.file_name().unwrap_or_default().to_string_lossy();
+        if file_name == "series" {
+            let content = std::fs::read_to_string(&entry_path).map_err(|e| PatchError::Read {
+                patch: entry.clone(),
+                error: e.to_string(),
+            })?;
+            let dir = Path::new(entry).parent().unwrap_or_else(|| Path::new(""));
+            for (number, line) in content.lines().enumerate() {
+                let line = line.split('#').next().unwrap_or_default();
+                let mut fields = line.split_whitespace();
+                let Some(patch) = fields.next() else {
+                    continue;
+                };
+                let mut strip = 1;
+                for option in fields {
+                    strip = option
+                        .strip_prefix("-p")
+                        .and_then(|level| level.parse().ok())
+                        .ok_or_else(|| PatchError::Malformed {
+                            patch: entry.clone(),
+                            line: number + 1,
+                            message: format!("unknown option '{option}'"),
+                        })?;
+                }
+                let name = dir.join(patch);
+                patches.push(Patch {
+                    name: name.to_string_lossy().to_string(),
+                    path: package_dir.join(name),
+                    strip,
+                });
+            }
+        } else if file_name.ends_with(".patch") || file_name.ends_with(".diff") {
+            patches.push(Patch {
+                name: entry.clone(),
+                path: entry_path,
+                strip: 1,
+            });
+        } else {
+            return Err(PatchError::Unsupported {
+                patch: entry.clone(),
+            });
+        }
+    }
+    Ok(patches)
+}
+
+/// Hash of the patches' names, strip levels and contents
+///
+/// Part of the build cache key, so that changing a patch rebuilds the
+/// package.
+pub fn patches_hash(patches: &[Patch]) -> Result<String, PatchError> {
+    let mut data = Vec::new();
+    for patch in patches {
+        data.extend_from_slice(format!("{}\0{}\0", patch.name, patch.strip).as_bytes());
+        data.extend(read_patch(patch)?);
+        data.push(0);
+    }
+    Ok(compute_checksum(&data))
+}
+
+/// Apply patches to a source directory, in order
+///
+/// Nothing happens if exactly these patches are applied already; patches
+/// applied before are reverted first. If a patch does not apply, every file
+/// is restored. Returns whether the sources changed.
+pub fn apply_patches(srcdir: &Path, patches: &[Patch]) -> Result<bool, PatchError> {
+    let state = srcdir.join(PATCH_STATE_DIR);
+    let record = patches
+        .iter()
+        .map(|patch| {
+            let hash = compute_checksum(&read_patch(patch)?);
+            Ok(format!("{hash} -p{} {}\n", patch.strip, patch.name))
+        })
+        .collect::<Result<String, PatchError>>()?;
+    let applied = std::fs::read_to_string(state.join("applied")).unwrap_or_default();
+    if applied == record {
+        return Ok(false);
+    }
+
+    restore(srcdir)?;
+    let mut backup = Backup {
+        srcdir,
+        saved: HashSet::new(),
+    };
+    for patch in patches {
+        if let Err(e) = apply_patch(srcdir, patch, &mut backup) {
+            restore(srcdir)?;
+            return Err(e);
+        }
+    }
+    if !patches.is_empty() {
+        write(&state.join("applied"), record.as_bytes())?;
+    }
+    Ok(true)
+}
+
+/// Revert the patches applied to a source directory
+pub fn restore(srcdir: &Path) -> Result<(), PatchError> {
+    let state = srcdir.join(PATCH_STATE_DIR);
+    if !state.is_dir() {
+        return Ok(());
+    }
+    let originals = state.join("orig");
+    if originals.is_dir() {
+        for entry in walkdir::WalkDir::new(&originals).min_depth(1) {
+            let entry = entry.map_err(|e| io_error(&originals, &e.into()))?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry
+                .path()
+                .strip_prefix(&originals)
+                .unwrap_or(entry.path());
+            let dest = srcdir.join(relative);
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| io_error(parent, &e))?;
+            }
+            std::fs::copy(entry.path(), &dest).map_err(|e| io_error(&dest, &e))?;
+        }
+    }
+    let created = std::fs::read_to_string(state.join("created")).unwrap_or_default();
+    for relative in created.lines() {
+        let path = srcdir.join(relative);
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(io_error(&path, &e));
+            }
+            _ => {}
+        }
+    }
+    std::fs::remove_dir_all(&state).map_err(|e| io_error(&state, &e))
+}
+
+/// Originals of the files changed while applying a patch set
+struct Backup<'a> {
+    srcdir: &'a Path,
+    saved: HashSet<String>,
+}
+
+impl Backup<'_> {
+    /// Keep the original of a file before it is first changed
+    fn save(&mut self, relative: &str) -> Result<(), PatchError> {
+        if !self.saved.insert(relative.to_string()) {
+            return Ok(());
+        }
+        let state = self.srcdir.join(PATCH_STATE_DIR);
+        let source = self.srcdir.join(relative);
+        if source.is_file() {
+            let dest = state.join("orig").join(relative);
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| io_error(parent, &e))?;
+            }
+            std::fs::copy(&source, &dest).map_err(|e| io_error(&dest, &e))?;
+        } else {
+            use std::io::Write;
+            std::fs::create_dir_all(&state).map_err(|e| io_error(&state, &e))?;
+            let list = state.join("created");
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&list)
+                .and_then(|mut file| writeln!(file, "{relative}"))
+                .map_err(|e| io_error(&list, &e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Changes of one file in a patch
+#[derive(Debug, Default)]
+struct FilePatch {
+    /// File before the change, `None` if the patch creates it
+    old: Option<String>,
+    /// File after the change, `None` if the patch deletes it
+    new: Option<String>,
+    hunks: Vec<Hunk>,
+}
+
+/// One hunk of a unified diff
+#[derive(Debug, Default)]
+struct Hunk {
+    /// The `@@ ... @@` line
+    header: String,
+    /// First line of the hunk in the old file, counted from 1
+    old_start: usize,
+    /// Lines the hunk expects
+    old: Vec<Vec<u8>>,
+    /// Lines that replace them
+    new: Vec<Vec<u8>>,
+    /// The old file ends within the hunk without a newline
+    old_no_newline: bool,
+    /// The new file ends within the hunk without a newline
+    new_no_newline: bool,
+}
+
+/// Apply one patch, saving the originals of the files it changes
+///
+/// Files are patched as bytes, so line endings and encodings are kept.
+fn apply_patch(srcdir: &Path, patch: &Patch, backup: &mut Backup) -> Result<(), PatchError> {
+    let content = read_patch(patch)?;
+    for file in parse(patch, &content)? {
+        let Some(relative) = file.new.as_ref().or(file.old.as_ref()) else {
+            continue;
+        };
+        let target = srcdir.join(relative);
+        let file_state = |reason: &str| PatchError::FileState {
+            patch: patch.name.clone(),
+            file: relative.clone(),
+            reason: reason.to_string(),
+        };
+        let exists = target.is_file();
+        if file.old.is_none() && exists {
+            return Err(file_state("already exists"));
+        }
+        if file.old.is_some() && !exists {
+            return Err(file_state("does not exist"));
+        }
+
+        let original = if exists {
+            std::fs::read(&target).map_err(|e| io_error(&target, &e))?
+        } else {
+            Vec::new()
+        };
+        let mut lines: Vec<Vec<u8>> = split_lines(&original)
+            .into_iter()
+            .map(<[u8]>::to_vec)
+            .collect();
+        let mut trailing_newline = original.is_empty() || original.ends_with(b"\n");
+        let mut offset = 0isize;
+        for (number, hunk) in file.hunks.iter().enumerate() {
+            let expected = hunk
+                .old_start
+                .saturating_sub(1)
+                .saturating_add_signed(offset);
+            let at =
+                find_lines(&lines, &hunk.old, expected).ok_or_else(|| PatchError::HunkFailed {
+                    patch: patch.name.clone(),
+                    file: relative.clone(),
+                    hunk: number + 1,
+                    header: hunk.header.clone(),
+                })?;
+            lines.splice(at..at + hunk.old.len(), hunk.new.iter().cloned());
+            offset += to_isize(at) - to_isize(expected) + to_isize(hunk.new.len())
+                - to_isize(hunk.old.len());
+            if hunk.new_no_newline {
+                trailing_newline = false;
+            } else if hunk.old_no_newline {
+                trailing_newline = true;
+            }
+        }
+
+        backup.save(relative)?;
+        if file.new.is_none() {
+            std::fs::remove_file(&target).map_err(|e| io_error(&target, &e))?;
+        } else {
+            let mut content = lines.join(&b'\n');
+            if trailing_newline && !lines.is_empty() {
+                content.push(b'\n');
+            }
+            write(&target, &content)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parse the file changes of a unified diff
+fn parse(patch: &Patch, content: &[u8]) -> Result<Vec<FilePatch>, PatchError> {
+    let malformed = |line: usize, message: &str| PatchError::Malformed {
+        patch: patch.name.clone(),
+        line,
+        message: message.to_string(),
+    };
+    let lines = split_lines(content);
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        if let (Some(old), Some(new)) = (
+            line.strip_prefix(b"--- "),
+            lines.get(index + 1).and_then(|l| l.strip_prefix(b"+++ ")),
+        ) {
+            let old = file_name(&String::from_utf8_lossy(old), patch.strip)
+                .map_err(|m| malformed(index + 1, &m))?;
+            let new = file_name(&String::from_utf8_lossy(new), patch.strip)
+                .map_err(|m| malformed(index + 2, &m))?;
+            if old.is_none() && new.is_none() {
+                return Err(malformed(index + 1, "both file names are /dev/null"));
+            }
+            files.push(FilePatch {
+                old,
+                new,
+                hunks: Vec::new(),
+            });
+            index += 2;
+            continue;
+        }
+        if !line.starts_with(b"@@ ") {
+            index += 1;
+            continue;
+        }
+
+        let line = String::from_utf8_lossy(line);
+        let file = files
+            .last_mut()
+            .ok_or_else(|| malformed(index + 1, "hunk before the file names"))?;
+        let (old_start, mut old_count, mut new_count) =
+            hunk_range(&line).ok_or_else(|| malformed(index + 1, "invalid hunk header"))?;
+        let mut hunk = Hunk {
+            header: line
+                .find(" @@")
+                .map_or(&*line, |end| &line[..end + 3])
+                .to_string(),
+            old_start,
+            ..Hunk::default()
+        };
+        index += 1;
+        let mut last = b' ';
+        while old_count > 0
+            || new_count > 0
+            || lines.get(index).is_some_and(|l| l.starts_with(b"\\"))
+        {
+            let Some(line) = lines.get(index) else {
+                return Err(malformed(index, "hunk ends early"));
+            };
+            let (kind, text) = line.split_at(line.len().min(1));
+            match kind {
+                b" " | b"" if old_count > 0 && new_count > 0 => {
+                    hunk.old.push(text.to_vec());
+                    hunk.new.push(text.to_vec());
+                    old_count -= 1;
+                    new_count -= 1;
+                }
+                b"-" if old_count > 0 => {
+                    hunk.old.push(text.to_vec());
+                    old_count -= 1;
+                }
+                b"+" if new_count > 0 => {
+                    hunk.new.push(text.to_vec());
+                    new_count -= 1;
+                }
+                b"\\" => {
+                    hunk.old_no_newline |= last != b'+';
+                    hunk.new_no_newline |= last != b'-';
+                }
+                _ => return Err(malformed(index + 1, "line does not fit the hunk")),
+            }
+            last = kind.first().copied().unwrap_or(b' ');
+            index += 1;
+        }
+        file.hunks.push(hunk);
+    }
+    if files.is_empty() {
+        return Err(malformed(1, "no file changes found"));
+    }
+    Ok(files)
+}
+
+/// File name of a `---`/`+++` line with `strip` leading components removed
+///
+/// Returns `None` for `/dev/null`.
+fn file_name(field: &str, strip: usize) -> Result<Option<String>, String> {
+    let name = field.split('\t').next().unwrap_or_default().trim_end();
+    if name == "/dev/null" {
+        return Ok(None);
+    }
+    let stripped: PathBuf = Path::new(name).components().skip(strip).collect();
+    if stripped.as_os_str().is_empty() {
+        return Err(format!(
+            "'{name}' has fewer than {strip} leading components"
+        ));
+    }
+    if !stripped
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(format!("'{name}' points outside the source directory"));
+    }
+    Ok(Some(stripped.to_string_lossy().to_string()))
+}
+
+/// Old start line and line counts of a `@@ -a,b +c,d @@` header
+fn hunk_range(header: &str) -> Option<(usize, usize, usize)> {
+    let mut fields = header.strip_prefix("@@ ")?.split_whitespace();
+    let range = |field: &str| -> Option<(usize, usize)> {
+        match field.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((field.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_count) = range(fields.next()?.strip_prefix('-')?)?;
+    let (_, new_count) = range(fields.next()?.strip_prefix('+')?)?;
+    Some((old_start, old_count, new_count))
+}
+
+/// Lines of `content`, split on `\n` only so a `\r` stays part of its line
+fn split_lines(content: &[u8]) -> Vec<&[u8]> {
+    if content.is_empty() {
+        return Vec::new();
+    }
+    let content = content.strip_suffix(b"\n").unwrap_or(content);
+    content.split(|&byte| byte == b'\n').collect()
+}
+
+/// Position of `needle` in `lines` closest to `expected`
+fn find_lines(lines: &[Vec<u8>], needle: &[Vec<u8>], expected: usize) -> Option<usize> {
+    let last = lines.len().checked_sub(needle.len())?;
+    let expected = expected.min(last);
+    let matches = |at: usize| lines[at..at + needle.len()] == *needle;
+    (0..=last).find_map(|distance| {
+        [
+            expected.checked_add(distance),
+            expected.checked_sub(distance),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|&at| at <= last && matches(at))
+    })
+}
+
+fn to_isize(value: usize) -> isize {
+    isize::try_from(value).unwrap_or(isize::MAX)
+}
+
+fn read_patch(patch: &Patch) -> Result<Vec<u8>, PatchError> {
+    std::fs::read(&patch.path).map_err(|e| PatchError::Read {
+        patch: patch.name.clone(),
+        error: e.to_string(),
+    })
+}
+
+fn write(path: &Path, content: &[u8]) -> Result<(), PatchError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| io_error(parent, &e))?;
+    }
+    std::fs::write(path, content).map_err(|e| io_error(path, &e))
+}
+
+fn io_error(path: &Path, error: &std::io::Error) -> PatchError {
+    PatchError::Io {
+        path: path.to_path_buf(),
+        error: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const FIX: &str = "\
+--- a/src/main.c
++++ b/src/main.c
+@@ -2,3 +2,3 @@
+ int main(void) {
+-    return 1;
++    return 0;
+ }
+";
+
+    fn package(files: &[(&str, &str)]) -> TempDir {
+        let temp = TempDir::new().unwrap();
+        for (path, content) in files {
+            let path = temp.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        temp
+    }
+
+    fn sources() -> TempDir {
+        package(&[(
+            "src/main.c",
+            "#include <stdio.h>\n\n\nint main(void) {\n    return 1;\n}\n",
+        )])
+    }
+
+    #[test]
+    fn test_apply_patches_with_offset_and_new_files() {
+        let pkg = package(&[
+            ("patches/fix.patch", FIX),
+            (
+                "patches/add.diff",
+                "diff --git a/README b/README\nnew file mode 100644\n\
+                 --- /dev/null\n+++ b/README\n@@ -0,0 +1,2 @@\n+patched\n+sources\n",
+            ),
+        ]);
+        let src = sources();
+        let patches = resolve_patches(
+            pkg.path(),
+            &[
+                "patches/fix.patch".to_string(),
+                "patches/add.diff".to_string(),
+            ],
+        )
+        .unwrap();
+
+        assert!(apply_patches(src.path(), &patches).unwrap());
+        assert_eq!(
+            std::fs::read_to_string(src.path().join("src/main.c")).unwrap(),
+            "#include <stdio.h>\n\n\nint main(void) {\n    return 0;\n}\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(src.path().join("README")).unwrap(),
+            "patched\nsources\n"
+        );
+
+        // Applied patches are not applied twice
+        assert!(!apply_patches(src.path(), &patches).unwrap());
+
+        // A changed patch set starts over from the original sources
+        assert!(apply_patches(src.path(), &patches[1..]).unwrap());
+        assert!(std::fs::read_to_string(src.path().join("src/main.c"))
+            .unwrap()
+            .contains("return 1;"));
+        restore(src.path()).unwrap();
+        assert!(!src.path().join("README").exists());
+        assert!(!src.path().join(PATCH_STATE_DIR).exists());
+    }
+
+    #[test]
+    fn test_failing_hunk_is_reported_and_sources_restored() {
+        let broken = FIX.replace("-    return 1;", "-    return 2;");
+        let pkg = package(&[
+            (
+                "patches/add.patch",
+                "--- /dev/null\n+++ b/NEW\n@@ -0,0 +1 @@\n+new\n",
+            ),
+            ("patches/broken.patch", &broken),
+        ]);
+        let src = sources();
+        let patches = resolve_patches(
+            pkg.path(),
+            &[
+                "patches/add.patch".to_string(),
+                "patches/broken.patch".to_string(),
+            ],
+        )
+        .unwrap();
+
+        let error = apply_patches(src.path(), &patches).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Patch 'patches/broken.patch' does not apply: hunk #1 (@@ -2,3 +2,3 @@) failed in src/main.c"
+        );
+        assert!(!src.path().join("NEW").exists());
+        assert!(!src.path().join(PATCH_STATE_DIR).exists());
+    }
+
+    #[test]
+    fn test_series_lists_patches_with_strip_levels() {
+        let pkg = package(&[
+            ("patches/series", "# fixes\nfix.patch\nplain.patch -p0 # no prefix\n\n"),
+            ("patches/fix.patch", FIX),
+            (
+                "patches/plain.patch",
+                "--- src/main.c\n+++ src/main.c\n@@ -1 +1 @@\n-#include <stdio.h>\n+#include <stdlib.h>\n",
+            ),
+        ]);
+        let patches = resolve_patches(pkg.path(), &["patches/series".to_string()]).unwrap();
+        assert_eq!(
+            patches
+                .iter()
+                .map(|p| (p.name.as_str(), p.strip))
+                .collect::<Vec<_>>(),
+            vec![("patches/fix.patch", 1), ("patches/plain.patch", 0)]
+        );
+
+        let src = sources();
+        apply_patches(src.path(), &patches).unwrap();
+        let main = std::fs::read_to_string(src.path().join("src/main.c")).unwrap();
+        assert!(main.starts_with("#include <stdlib.h>\n"));
+        assert!(main.contains("return 0;"));
+    }
+
+    #[test]
+    fn test_invalid_patch_lists() {
+        let pkg = package(&[
+            ("fix.txt", FIX),
+            ("empty.patch", "just a description\n"),
+            ("escape.patch", &FIX.replace("b/src/main.c", "b/../main.c")),
+        ]);
+        assert!(matches!(
+            resolve_patches(pkg.path(), &["fix.txt".to_string()]),
+            Err(PatchError::Unsupported { .. })
+        ));
+        assert!(matches!(
+            resolve_patches(pkg.path(), &["missing/series".to_string()]),
+            Err(PatchError::Read { .. })
+        ));
+
+        let src = sources();
+        for name in ["empty.patch", "escape.patch"] {
+            let patches = resolve_patches(pkg.path(), &[name.to_string()]).unwrap();
+            assert!(matches!(
+                apply_patches(src.path(), &patches),
+                Err(PatchError::Malformed { .. })
+            ));
+        }
+        let missing = resolve_patches(pkg.path(), &["gone.patch".to_string()]).unwrap();
+        assert!(matches!(
+            apply_patches(src.path(), &missing),
+            Err(PatchError::Read { .. })
+        ));
+    }
+
+    #[test]
+    fn test_apply_patches_keeps_crlf_line_endings() {
+        let pkg = package(&[("fix.patch", &FIX.replace('\n', "\r\n"))]);
+        let src = package(&[(
+            "src/main.c",
+            "#include <stdio.h>\r\n\r\n\r\nint main(void) {\r\n    return 1;\r\n}\r\n",
+        )]);
+        let patches = resolve_patches(pkg.path(), &["fix.patch".to_string()]).unwrap();
+
+        apply_patches(src.path(), &patches).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(src.path().join("src/main.c")).unwrap(),
+            "#include <stdio.h>\r\n\r\n\r\nint main(void) {\r\n    return 0;\r\n}\r\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_patches_to_non_utf8_files() {
+        let pkg = package(&[]);
+        std::fs::write(
+            pkg.path().join("fix.patch"),
+            b"--- a/README\n+++ b/README\n@@ -1,2 +1,2 @@\n caf\xe9\n-old\n+new \xff\n",
+        )
+        .unwrap();
+        let src = package(&[]);
+        std::fs::write(src.path().join("README"), b"caf\xe9\nold\n").unwrap();
+        let patches = resolve_patches(pkg.path(), &["fix.patch".to_string()]).unwrap();
+
+        apply_patches(src.path(), &patches).unwrap();
+        assert_eq!(
+            std::fs::read(src.path().join("README")).unwrap(),
+            b"caf\xe9\nnew \xff\n"
+        );
+    }
+
+    #[test]
+    fn test_patches_hash_follows_content() {
+        let pkg = package(&[("fix.patch", FIX)]);
+        let patches = resolve_patches(pkg.path(), &["fix.patch".to_string()]).unwrap();
+        let before = patches_hash(&patches).unwrap();
+        assert_eq!(patches_hash(&patches).unwrap(), before);
+
+        std::fs::write(pkg.path().join("fix.patch"), FIX.replace("0;", "2;")).unwrap();
+        assert_ne!(patches_hash(&patches).unwrap(), before);
+        assert_ne!(patches_hash(&[]).unwrap(), before);
+    }
+}
@@ -0,0 +1,296 @@
+//! Ownership and permissions overlay of the rootfs
+//!
+//! The `[permissions]` table of the manifest maps rootfs paths to an owner,
+//! group and mode:
+//!
+//! ```toml
+//! [permissions]
+//! "/bin/busybox" = { mode = "4755" }
+//! "/etc/shadow" = { owner = "root", group = "shadow", mode = "0640" }
+//! ```
+//!
+//! Builds run unprivileged and cannot chown, so the staged tree is never
+//! changed. Like a fakeroot table, the overlay is resolved against the
+//! assembled rootfs and recorded in the image metadata as the image is
+//! written.
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Largest mode an entry may set, including setuid, setgid and sticky bits
+const MAX_MODE: u32 = 0o7777;
+
+/// Declared ownership and mode of one rootfs path
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct PermissionEntry {
+    /// User name or numeric uid (default root)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Group name or numeric gid (default root)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Octal permission bits such as "4755" (default: keep the file's mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+}
+
+/// Ownership and mode to record for a path in the image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attributes {
+    /// Owning uid
+    pub uid: u32,
+    /// Owning gid
+    pub gid: u32,
+    /// Permission bits replacing the file's own, if set
+    pub mode: Option<u32>,
+}
+
+impl Attributes {
+    /// Full mode of a file with `mode`, keeping its file type bits
+    pub fn apply_mode(&self, mode: u32) -> u32 {
+        self.mode
+            .map_or(mode, |permissions| (mode & !MAX_MODE) | permissions)
+    }
+}
+
+/// Resolved overlay, keyed by path relative to the rootfs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PermissionTable {
+    entries: BTreeMap<PathBuf, Attributes>,
+}
+
+impl PermissionTable {
+    /// Resolve the manifest entries against an assembled rootfs
+    ///
+    /// Every path must exist in `rootfs_dir`; user and group names are
+    /// looked up in its `etc/passwd` and `etc/group`. All problems are
+    /// returned, not just the first.
+    pub fn resolve(
+        rootfs_dir: &Path,
+        entries: &BTreeMap<String, PermissionEntry>,
+    ) -> Result<Self, Vec<String>> {
+        let mut errors = check_entries(entries);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let users = read_ids(&rootfs_dir.join("etc/passwd"));
+        let groups = read_ids(&rootfs_dir.join("etc/group"));
+        let mut table = Self::default();
+        for (path, entry) in entries {
+            let Some(relative) = relative_path(path) else {
+                continue;
+            };
+            if rootfs_dir.join(&relative).symlink_metadata().is_err() {
+                errors.push(format!(
+                    "permissions: '{path}' does not exist in the assembled rootfs"
+                ));
+                continue;
+            }
+            let uid = resolve_id(entry.owner.as_deref(), &users);
+            let gid = resolve_id(entry.group.as_deref(), &groups);
+            match (uid, gid) {
+                (Some(uid), Some(gid)) => {
+                    table.entries.insert(
+                        relative,
+                        Attributes {
+                            uid,
+                            gid,
+                            mode: entry.mode.as_deref().and_then(parse_mode),
+                        },
+                    );
+                }
+                (uid, gid) => {
+                    if uid.is_none() {
+                        errors.push(unknown_name(path, "user", entry.owner.as_deref(), "passwd"));
+                    }
+                    if gid.is_none() {
+                        errors.push(unknown_name(path, "group", entry.group.as_deref(), "group"));
+                    }
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(table)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Attributes of a path relative to the rootfs, if it is overlaid
+    pub fn get(&self, relative: &Path) -> Option<&Attributes> {
+        self.entries.get(relative)
+    }
+
+    /// Whether the overlay has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Check the manifest entries without a rootfs: paths are absolute and
+/// stay inside it, modes are octal, and names are not empty
+pub fn check_entries(entries: &BTreeMap<String, PermissionEntry>) -> Vec<String> {
+    let mut errors = Vec::new();
+    for (path, entry) in entries {
+        if relative_path(path).is_none() {
+            errors.push(format!(
+                "permissions: '{path}' must be an absolute path inside the rootfs, like '/bin/busybox'"
+            ));
+        }
+        if let Some(mode) = entry.mode.as_deref() {
+            if parse_mode(mode).is_none() {
+                errors.push(format!(
+                    "permissions: '{path}' has invalid mode '{mode}': expected octal digits up to 7777, like \"4755\""
+                ));
+            }
+        }
+        for (field, value) in [("owner", &entry.owner), ("group", &entry.group)] {
+            if value.as_deref().is_some_and(|v| v.trim().is_empty()) {
+                errors.push(format!("permissions: '{path}' has an empty {field}"));
+            }
+        }
+    }
+    errors
+}
+
+/// Parse octal permission bits such as "755" or "4755"
+pub fn parse_mode(mode: &str) -> Option<u32> {
+    if mode.is_empty() || mode.len() > 4 {
+        return None;
+    }
+    u32::from_str_radix(mode, 8)
+        .ok()
+        .filter(|bits| *bits <= MAX_MODE)
+}
+
+/// Path relative to the rootfs of an absolute path, unless it is the root
+/// or leaves the rootfs
+fn relative_path(path: &str) -> Option<PathBuf> {
+    let relative = path.strip_prefix('/')?;
+    let relative = Path::new(relative);
+    let mut normalized = PathBuf::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!normalized.as_os_str().is_empty()).then_some(normalized)
+}
+
+/// Numeric id of a name or number; root is 0 even without `/etc/passwd`
+fn resolve_id(name: Option<&str>, ids: &BTreeMap<String, u32>) -> Option<u32> {
+    match name.map(str::trim) {
+        None | Some("root") => Some(0),
+        Some(name) => name.parse().ok().or_else(|| ids.get(name).copied()),
+    }
+}
+
+/// Ids by name from a `passwd` or `group` file, empty if it does not exist
+fn read_ids(path: &Path) -> BTreeMap<String, u32> {
+    let content = std::fs::read_to_string(path).unwrap_or_default();
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let id = fields.nth(1)?.parse().ok()?;
+            Some((name.to_string(), id))
+        })
+        .collect()
+}
+
+fn unknown_name(path: &str, kind: &str, name: Option<&str>, file: &str) -> String {
+    format!(
+        "permissions: '{path}' has unknown {kind} '{}': not in the rootfs /etc/{file}",
+        name.unwrap_or_default()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(owner: Option<&str>, group: Option<&str>, mode: Option<&str>) -> PermissionEntry {
+        PermissionEntry {
+            owner: owner.map(String::from),
+            group: group.map(String::from),
+            mode: mode.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_parse_mode_accepts_octal_only() {
+        assert_eq!(parse_mode("4755"), Some(0o4755));
+        assert_eq!(parse_mode("0640"), Some(0o640));
+        assert_eq!(parse_mode("755"), Some(0o755));
+        assert_eq!(parse_mode("0o755"), None);
+        assert_eq!(parse_mode("0800"), None);
+        assert_eq!(parse_mode("17777"), None);
+        assert_eq!(parse_mode(""), None);
+    }
+
+    #[test]
+    fn test_check_entries_rejects_paths_outside_rootfs() {
+        let entries = BTreeMap::from([
+            ("bin/sh".to_string(), entry(None, None, None)),
+            ("/../etc".to_string(), entry(None, None, None)),
+            ("/".to_string(), entry(None, None, None)),
+            ("/bin/su".to_string(), entry(Some(""), None, Some("9755"))),
+        ]);
+        let errors = check_entries(&entries);
+        assert_eq!(errors.len(), 5, "{errors:?}");
+        assert!(errors.iter().any(|e| e.contains("invalid mode '9755'")));
+        assert!(errors.iter().any(|e| e.contains("empty owner")));
+    }
+
+    #[test]
+    fn test_resolve_uses_rootfs_accounts() {
+        let temp = TempDir::new().unwrap();
+        let rootfs = temp.path();
+        std::fs::create_dir_all(rootfs.join("etc")).unwrap();
+        std::fs::create_dir_all(rootfs.join("bin")).unwrap();
+        std::fs::write(rootfs.join("bin/busybox"), "").unwrap();
+        std::fs::write(rootfs.join("etc/shadow"), "").unwrap();
+        std::fs::write(
+            rootfs.join("etc/passwd"),
+            "root:x:0:0:root:/root:/bin/sh\nwww:x:33:33::/var/www:/bin/false\n",
+        )
+        .unwrap();
+        std::fs::write(rootfs.join("etc/group"), "root:x:0:\nshadow:x:42:\n").unwrap();
+
+        let entries = BTreeMap::from([
+            ("/bin/busybox".to_string(), entry(None, None, Some("4755"))),
+            (
+                "/etc/shadow".to_string(),
+                entry(Some("www"), Some("shadow"), Some("0640")),
+            ),
+        ]);
+        let table = PermissionTable::resolve(rootfs, &entries).unwrap();
+        let busybox = table.get(Path::new("bin/busybox")).unwrap();
+        assert_eq!((busybox.uid, busybox.gid), (0, 0));
+        assert_eq!(busybox.apply_mode(0o100_755), 0o104_755);
+        let shadow = table.get(Path::new("etc/shadow")).unwrap();
+        assert_eq!((shadow.uid, shadow.gid, shadow.mode), (33, 42, Some(0o640)));
+
+        let entries = BTreeMap::from([
+            ("/bin/missing".to_string(), entry(None, None, None)),
+            (
+                "/bin/busybox".to_string(),
+                entry(Some("nobody"), Some("1000"), None),
+            ),
+        ]);
+        let errors = PermissionTable::resolve(rootfs, &entries).unwrap_err();
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert!(errors[0].contains("unknown user 'nobody'"));
+        assert!(errors[1].contains("does not exist"));
+    }
+}
@@ -0,0 +1,1515 @@
+//! Build pipeline behind [`ZigrootProject::build`]
+//!
+//! Builds the packages of a project in dependency order, assembles the
+//! rootfs from the runtime packages and writes the images. Results are
+//! recorded in a [`BuildResult`] and progress goes to a [`ProgressSink`];
+//! nothing is printed.
+//!
+//! **Validates: Requirements 4.1-4.13, 5.1-5.7, 6.1-6.10, 27.1-27.9**
+
+use anyhow::{bail, Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::config::defaults::DEFAULT_ROOTFS_SIZE;
+use crate::core::build_env::{self, BuildEnvironment};
+use crate::core::builder::{self, BuildFailures, BuildHistory, BuildOrchestrator};
+use crate::core::compress::{self, CompressionConfig, CompressionStats};
+use crate::core::external;
+use crate::core::fetch;
+use crate::core::fit;
+use crate::core::flash::load_board_definition;
+use crate::core::global_config::GlobalConfig;
+use crate::core::host_tools::{self, ProvisionedTool};
+use crate::core::kernel;
+use crate::core::lock::{LockFile, LockedPackageBuilder};
+use crate::core::manifest::{Manifest, VALID_INITRAMFS_COMPRESSIONS};
+use crate::core::output::{self, OutputLayout};
+use crate::core::package::{
+    PackageBuildConfig, PackageDefinition, PackageMetadata, VALID_TOOLCHAINS,
+};
+use crate::core::partition::{self, DiskLayout};
+use crate::core::patch::{self, Patch};
+use crate::core::permissions::PermissionTable;
+use crate::core::project::{
+    BuildOptions, BuildPlan, BuildResult, ProgressEvent, ProgressSink, ZigrootProject,
+};
+use crate::core::report::{CompressionReport, ImageChecksum, PackageReport, StripReport};
+use crate::core::reproducible::{self, ArtifactDigest, Attestation};
+use crate::core::resolver::DependencyGraph;
+use crate::core::shared_storage::SharedStorage;
+use crate::core::signing::SigningKey;
+use crate::core::size::{self, PackageSize, SizeReport};
+use crate::core::strip::{self, StripConfig, StripStats, StripTool};
+use crate::core::validate::{self, Finding, ValidateOptions, ValidationReport};
+use crate::core::variants::{Activation, Variant, VariantStore};
+use crate::core::version::satisfies_requirement;
+use crate::error::BuildError;
+use crate::infra::dirs::ZigrootDirs;
+use crate::infra::download::compute_checksum;
+use crate::infra::filesystem::filesystem_space;
+use crate::infra::namespace::{namespaces_supported, NamespaceTool, INSTALL_HINT};
+use crate::infra::sandbox::resolve_sandbox_config;
+use crate::registry::client::RegistryClient;
+
+/// Run the build, recording its results in `result`
+#[allow(clippy::too_many_lines)]
+pub(crate) async fn run_build(
+    project: &ZigrootProject,
+    options: &BuildOptions,
+    progress: &mut dyn ProgressSink,
+    result: &mut BuildResult,
+) -> Result<()> {
+    let project_dir = project.root();
+    let compress_override = compress::cli_override(options.compress, options.no_compress)?;
+    let mut manifest = project.manifest().clone();
+    if let Some(ref dir) = options.output_dir {
+        manifest.build.output_dir = Some(dir.clone());
+    }
+    if options.reproducible || reproducible::env_epoch().is_some() {
+        manifest.build.reproducible = true;
+    }
+
+    // Outputs go to <output>/<board>/<format>; files of the flat layout of
+    // older builds are left alone
+    let layout = OutputLayout::new(project_dir, &manifest);
+    let output_dir = layout.image_dir();
+
+    tracing::info!("Building project: {}", manifest.project.name);
+    result.report.project.clone_from(&manifest.project.name);
+
+    // Validate the size budget before spending time on the build
+    let size_budget = manifest
+        .build
+        .size_budget
+        .as_deref()
+        .map(size::parse_size)
+        .transpose()
+        .with_context(|| "Invalid build.size_budget in zigroot.toml")?;
+
+    check_initramfs_settings(project_dir, &manifest)?;
+
+    // Validate the disk layout and the content that already exists
+    let layout_errors = partition::check_layout(project_dir, &manifest);
+    if !layout_errors.is_empty() {
+        bail!("Invalid disk layout:\n  {}", layout_errors.join("\n  "));
+    }
+
+    // Fail early instead of running out of space halfway through
+    // An initramfs is sized by its content; rootfs_size does not apply, and
+    // the content size of an `auto` rootfs is not known yet
+    if !options.skip_space_check {
+        let rootfs_size = if manifest.build.image_format == "initramfs"
+            || manifest.build.rootfs_size == size::AUTO_SIZE
+        {
+            DEFAULT_ROOTFS_SIZE
+        } else {
+            &manifest.build.rootfs_size
+        };
+        let rootfs_size = size::parse_size(rootfs_size)
+            .with_context(|| "Invalid build.rootfs_size in zigroot.toml")?;
+        let requirements =
+            builder::estimate_build_space(project_dir, &std::env::temp_dir(), rootfs_size);
+        builder::check_space(&requirements, filesystem_space)?;
+    }
+
+    // Resolve sandbox configuration
+    // Priority: CLI flags > manifest settings > global default (disabled)
+    let global_config = GlobalConfig::load(&ZigrootDirs::new())?;
+    let cli_sandbox = if options.sandbox { Some(true) } else { None };
+    let sandbox_config = resolve_sandbox_config(
+        cli_sandbox,
+        options.no_sandbox,
+        manifest.build.sandbox.or(global_config.build.sandbox),
+        false, // Package network will be set per-package
+    );
+
+    // Package builds are isolated with Linux namespaces
+    let isolation = if sandbox_config.enabled {
+        match NamespaceTool::detect() {
+            Some(tool) => {
+                tracing::info!("Build isolation enabled using {tool}");
+                Some(tool)
+            }
+            None if namespaces_supported() => {
+                bail!(
+                    "Build sandbox not available: bubblewrap (bwrap) is not installed and user namespaces cannot be created.\n{INSTALL_HINT}, or build with --no-sandbox. Run 'zigroot doctor' for details."
+                );
+            }
+            None if options.locked => {
+                bail!("Build sandbox not supported on this platform (no Linux namespaces). Refusing an unsandboxed --locked build; pass --no-sandbox to build anyway.");
+            }
+            None => {
+                progress.event(ProgressEvent::Warning("Build sandbox not supported on this platform (no Linux namespaces): packages are built WITHOUT isolation".to_string()));
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Create build directories
+    let build_dir = project_dir.join("build");
+    let stamps_dir = build_dir.join(builder::STAMPS_DIR);
+    let logs_dir = options
+        .log_dir
+        .as_ref()
+        .map_or_else(|| build_dir.join("logs"), |dir| project_dir.join(dir));
+
+    fs::create_dir_all(&build_dir).with_context(|| "Failed to create build directory")?;
+    fs::create_dir_all(&output_dir).with_context(|| "Failed to create output directory")?;
+    fs::create_dir_all(&stamps_dir).with_context(|| "Failed to create stamps directory")?;
+    fs::create_dir_all(&logs_dir).with_context(|| "Failed to create logs directory")?;
+
+    // Clean up the partial trees of an interrupted build; packages it
+    // completed keep their stamps and are not rebuilt
+    let recovery = builder::recover_build_dir(&build_dir)
+        .with_context(|| "Failed to clean up after the interrupted build")?;
+    if let Some(ref package) = recovery.interrupted {
+        tracing::info!("Resuming the build interrupted while building {package}");
+    }
+    for path in &recovery.removed {
+        tracing::debug!("Removed {}", path.display());
+    }
+
+    // Load or create lock file
+    let lock_path = project_dir.join("zigroot.lock");
+    let mut lock_file = if lock_path.exists() {
+        LockFile::load(&lock_path).with_context(|| "Failed to load lock file")?
+    } else {
+        LockFile::new(env!("CARGO_PKG_VERSION"), "0.13.0")
+    };
+
+    result.report.toolchain = toolchain_version(&lock_file);
+
+    // Handle --locked mode
+    if options.locked {
+        verify_locked_packages(project_dir, &manifest, &lock_file)?;
+        verify_locked_externals(&manifest, &lock_file)?;
+    }
+
+    // Git packages are only built from checkouts at their locked commits
+    fetch::verify_git_checkouts(project_dir, &manifest, &lock_file, options.locked).await?;
+
+    // Fetch URL external artifacts into shared storage and pin them
+    lock_file
+        .externals
+        .retain(|locked| manifest.external.contains_key(&locked.name));
+    if manifest
+        .external
+        .values()
+        .any(|artifact| artifact.url.is_some())
+    {
+        let storage = SharedStorage::new(&ZigrootDirs::new());
+        external::fetch_artifacts(project_dir, &manifest, &storage, &[], false).await?;
+        for (name, artifact) in &manifest.external {
+            if let Some(locked) = external::locked_entry(name, artifact) {
+                lock_file.add_external(locked);
+            }
+        }
+    }
+
+    // Order builds across runtime and build-only dependencies
+    let BuildPlan {
+        definitions,
+        graph,
+        order: full_order,
+    } = project.resolve()?;
+
+    // Determine which packages to build
+    let selected: Vec<String> = if options.kernel_only {
+        // Build only kernel packages
+        tracing::info!("Building kernel only (--kernel-only)");
+        manifest
+            .packages
+            .keys()
+            .filter(|name| is_kernel_package(project_dir, name))
+            .cloned()
+            .collect()
+    } else if let Some(ref pkg_name) = options.package {
+        if !manifest.packages.contains_key(pkg_name) {
+            bail!("Package '{pkg_name}' not found in manifest");
+        }
+        if options.no_deps {
+            // Build only the specified package against existing dependencies
+            check_dependencies_built(pkg_name, &graph, &manifest, &stamps_dir)?;
+            vec![pkg_name.clone()]
+        } else {
+            // Build the specified package and everything it depends on
+            let mut selected = graph.transitive_dependencies(pkg_name);
+            selected.insert(pkg_name.clone());
+            selected.into_iter().collect()
+        }
+    } else {
+        // Build all packages
+        manifest.packages.keys().cloned().collect()
+    };
+    let packages_to_build: Vec<String> = full_order
+        .iter()
+        .filter(|name| selected.contains(name))
+        .cloned()
+        .collect();
+
+    // Each package's build trees follow its configuration; switching back
+    // to a recently built one restores its trees instead of rebuilding
+    let keep = global_config.build_variants();
+    let variants = VariantStore::new(&build_dir, keep);
+    for name in &packages_to_build {
+        let variant = Variant::current(project_dir, &manifest, name);
+        let activation = variants
+            .activate(&variant)
+            .with_context(|| format!("Failed to switch the build trees of {name}"))?;
+        if activation == Activation::Restored {
+            tracing::info!("Restored {name} built with {}", variant.describe());
+        }
+    }
+
+    // Cache keys of the packages, recorded in their stamps
+    let history_keys: Vec<String> = packages_to_build
+        .iter()
+        .map(|name| {
+            let version = manifest.packages[name]
+                .version
+                .as_deref()
+                .unwrap_or("1.0.0");
+            let mut options_hash = builder::package_options_hash(project_dir, &manifest, name);
+            // Changing a patch invalidates the build
+            let patches = package_patches(project_dir, name)?;
+            if !patches.is_empty() {
+                options_hash.push('-');
+                options_hash.push_str(&patch::patches_hash(&patches)?[..16]);
+            }
+            // So does changing a hook
+            if let Some(hash) = local_definition(project_dir, name)
+                .and_then(|definition| builder::hooks_hash(&definition.build))
+            {
+                options_hash.push('-');
+                options_hash.push_str(&hash);
+            }
+            Ok(builder::history_key(name, version, &options_hash))
+        })
+        .collect::<Result<_>>()?;
+
+    // Packages that are not up to date
+    let outdated: HashSet<String> = packages_to_build
+        .iter()
+        .zip(&history_keys)
+        .filter(|(name, key)| {
+            options.package.as_ref() == Some(*name)
+                || !builder::stamp_is_current(&stamps_dir, name, key)
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+    let needs_build = |name: &String| outdated.contains(name);
+
+    // Make the host tools of the packages to build available
+    let host_tools = provision_host_tools(
+        &definitions,
+        packages_to_build.iter().filter(|n| needs_build(n)),
+    )
+    .await?;
+
+    // Weigh packages by their duration in earlier builds
+    let history_path = ZigrootDirs::new()
+        .cache_dir()
+        .join(builder::BUILD_HISTORY_FILE);
+    let mut history = BuildHistory::load(&history_path);
+    let weights: Vec<f64> = history
+        .estimate(&history_keys)
+        .into_iter()
+        .zip(&packages_to_build)
+        .map(|(weight, name)| if needs_build(name) { weight } else { 0.0 })
+        .collect();
+    let estimated_secs: f64 = weights.iter().sum();
+
+    // --jobs, then build.jobs of the manifest and of the global config;
+    // unset or 0 uses every CPU
+    let jobs = build_env::resolve_jobs(
+        options
+            .jobs
+            .or(manifest.build.jobs)
+            .or(global_config.build.jobs),
+    );
+    progress.event(ProgressEvent::Planned {
+        packages: packages_to_build.clone(),
+        estimated_secs,
+        jobs,
+    });
+    let packages_started = Instant::now();
+
+    // Build each package
+    tracing::info!(
+        "Building {} packages with {} jobs",
+        packages_to_build.len(),
+        jobs
+    );
+
+    // With --keep-going, failed packages are collected and their
+    // dependents skipped; otherwise the first failure stops the build
+    let mut failures = BuildFailures::default();
+    for ((pkg_name, key), weight) in packages_to_build.iter().zip(history_keys).zip(weights) {
+        if builder::interrupt_requested() {
+            builder::record_interrupted(&build_dir, pkg_name)?;
+            return Err(BuildError::Interrupted {
+                package: pkg_name.clone(),
+            }
+            .into());
+        }
+        if let Some(dependency) =
+            failures.failed_dependency(&graph.transitive_dependencies(pkg_name))
+        {
+            let dependency = dependency.to_string();
+            failures.record_skipped(pkg_name, &dependency);
+            progress.event(ProgressEvent::PackageSkipped {
+                name: pkg_name.clone(),
+                dependency,
+                estimated_secs: weight,
+            });
+            continue;
+        }
+        progress.event(ProgressEvent::PackageStarted {
+            name: pkg_name.clone(),
+        });
+        let mut dependencies: Vec<String> = graph
+            .transitive_dependencies(pkg_name)
+            .into_iter()
+            .collect();
+        dependencies.sort();
+        let started = Instant::now();
+        let built = build_package(
+            project_dir,
+            pkg_name,
+            &manifest,
+            &mut lock_file,
+            &stamps_dir,
+            &PackageBuild {
+                force: options.package.as_ref() == Some(pkg_name),
+                key: &key,
+                isolation,
+                dependencies: &dependencies,
+                host_tools: host_tools.get(pkg_name).map_or(&[], Vec::as_slice),
+                jobs,
+                logs_dir: &logs_dir,
+            },
+            progress,
+        )
+        .map_err(|error| {
+            // Record where to resume; the partial trees are cleaned up then
+            if builder::interrupt_requested() {
+                let _ = builder::record_interrupted(&build_dir, pkg_name);
+            }
+            error
+        });
+        let rebuilt = match built {
+            Ok(rebuilt) => rebuilt,
+            Err(error) if options.keep_going && !builder::interrupt_requested() => {
+                let error = match error.downcast_ref::<BuildError>() {
+                    Some(BuildError::BuildFailed { error, .. }) => error.clone(),
+                    _ => format!("{error:#}"),
+                };
+                failures.record_failure(pkg_name, error.clone());
+                progress.event(ProgressEvent::PackageFailed {
+                    name: pkg_name.clone(),
+                    error,
+                    estimated_secs: weight,
+                });
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        if rebuilt {
+            history.record(key, started.elapsed().as_secs_f64());
+            if let Err(e) = history.save(&history_path) {
+                tracing::debug!("Failed to save build history: {e}");
+            }
+        }
+        progress.event(ProgressEvent::PackageFinished {
+            name: pkg_name.clone(),
+            rebuilt,
+            estimated_secs: weight,
+        });
+        let locked = lock_file.get_package(pkg_name);
+        result.report.packages.push(PackageReport {
+            name: pkg_name.clone(),
+            version: locked.map(|p| p.version.clone()).unwrap_or_default(),
+            source: locked.and_then(|p| p.source.clone()),
+            rebuilt,
+            duration_secs: started.elapsed().as_secs_f64(),
+        });
+    }
+    if !failures.is_empty() {
+        return Err(failures.into_error().into());
+    }
+    progress.event(ProgressEvent::Assembling);
+    let packages_time = packages_started.elapsed();
+
+    // Timestamp of all files in a reproducible build
+    let epoch = manifest.build.reproducible.then(|| {
+        reproducible::source_date_epoch(
+            manifest.build.source_date_epoch,
+            &reproducible::lock_hash(&lock_file),
+        )
+    });
+
+    // Determine target architecture from board (default to x86_64 if not set)
+    let target_arch = manifest
+        .board
+        .name
+        .as_ref()
+        .map(|_| "x86_64-linux-musl") // Would load from board definition
+        .unwrap_or("x86_64-linux-musl");
+
+    // Assemble the rootfs from runtime packages only
+    let mut package_sizes = Vec::new();
+    let mut compression = CompressionStats::default();
+    let mut stripped = StripStats::default();
+    let mut validation = None;
+    if !options.kernel_only {
+        let mut selected: Vec<String> = manifest.packages.keys().cloned().collect();
+        selected.sort();
+        let orchestrator = BuildOrchestrator::new()
+            .with_packages(selected)
+            .with_build_order(full_order);
+        let rootfs_packages = orchestrator.rootfs_packages(&definitions);
+        let staging_root = build_dir.join(builder::STAGING_DIR);
+        stripped = handle_strip(
+            project_dir,
+            &manifest,
+            &staging_root,
+            &output_dir,
+            &rootfs_packages,
+        );
+        compression = handle_compression(
+            project_dir,
+            compress_override,
+            &manifest,
+            &staging_root,
+            &rootfs_packages,
+            target_arch,
+        );
+        for name in &rootfs_packages {
+            let bytes = size::staged_size(&staging_root, name)
+                .with_context(|| format!("Failed to measure installed size of {name}"))?;
+            package_sizes.push(PackageSize {
+                name: name.clone(),
+                bytes,
+            });
+        }
+        let rootfs_dir = build_dir.join("rootfs");
+        let installed = builder::install_into_rootfs(
+            &staging_root,
+            &rootfs_dir,
+            &build_dir.join(builder::INSTALLED_DIR),
+            &rootfs_packages,
+        )
+        .with_context(|| "Failed to assemble rootfs")?;
+        if let Some(epoch) = epoch {
+            for dir in [&staging_root, &rootfs_dir] {
+                reproducible::normalize_mtimes(dir, epoch).with_context(|| {
+                    format!("Failed to normalize timestamps in {}", dir.display())
+                })?;
+            }
+        }
+        tracing::info!(
+            "Installed {} of {} runtime packages into rootfs",
+            installed.len(),
+            rootfs_packages.len()
+        );
+        if !options.no_validate {
+            validation = Some(check_rootfs(project_dir, &manifest, &rootfs_dir)?);
+        }
+    }
+
+    // Create rootfs image; an initramfs embedded into the kernel is only
+    // written standalone on request
+    let embedded = manifest.build.embed_in_kernel;
+    let rootfs_dir = build_dir.join("rootfs");
+    let permissions = resolve_permissions(&manifest, &rootfs_dir, progress)?;
+    let (image_path, initramfs_size) = if embedded && !options.also_standalone {
+        (None, None)
+    } else {
+        let (path, archive_size) =
+            create_rootfs_image(&output_dir, &manifest, &rootfs_dir, epoch, &permissions)?;
+        (Some(path), archive_size)
+    };
+
+    // Copy external artifacts into the output by type
+    let placed = external::place_artifacts(project_dir, &manifest, &output_dir)
+        .with_context(|| "Failed to copy external artifacts")?;
+    for name in &placed.missing {
+        progress.event(ProgressEvent::Warning(format!(
+            "External artifact '{name}' is missing and was not copied to the output"
+        )));
+    }
+
+    // Combine kernel, device trees and ramdisk for U-Boot
+    let fit_image = match fit::project_fit(project_dir, &manifest) {
+        Some(fit_config) if !options.kernel_only => {
+            Some(fit::build_fit(project_dir, &manifest, &fit_config)?)
+        }
+        _ => None,
+    };
+
+    // Write the full disk image from the partition layout
+    let disk_image = if options.kernel_only {
+        None
+    } else {
+        build_disk_image(
+            project_dir,
+            &output_dir,
+            &manifest,
+            image_path.as_deref(),
+            epoch,
+        )?
+    };
+
+    // Checksum (and sign) the image that is flashed
+    result.report.checksum = disk_image
+        .as_ref()
+        .or(image_path.as_ref())
+        .map(|path| seal_image(path))
+        .transpose()?;
+
+    // Save lock file, without a wall-clock timestamp in reproducible builds
+    if let Some(epoch) = epoch {
+        lock_file.metadata.generated = epoch.to_string();
+    }
+    lock_file
+        .save(&lock_path)
+        .with_context(|| "Failed to save lock file")?;
+
+    let image_size = image_path
+        .as_ref()
+        .and_then(|path| fs::metadata(path).ok())
+        .map_or(0, |m| m.len());
+
+    result.report.image = image_path.as_ref().map(|path| path.display().to_string());
+    result.report.image_size = image_size;
+    result.report.artifacts = fit_image
+        .iter()
+        .chain(&disk_image)
+        .chain(&placed.files)
+        .map(|path| path.display().to_string())
+        .collect();
+    result.report.compression = CompressionReport {
+        files_compressed: compression.files_compressed,
+        bytes_saved: compression.bytes_saved(),
+    };
+    result.report.strip = StripReport {
+        files_stripped: stripped.files_stripped,
+        bytes_saved: stripped.bytes_saved,
+    };
+
+    let attestation = match epoch {
+        Some(epoch) => {
+            let artifacts: Vec<&PathBuf> = image_path
+                .iter()
+                .chain(&fit_image)
+                .chain(&disk_image)
+                .collect();
+            Some(write_attestation(
+                project_dir,
+                &output_dir,
+                &manifest,
+                &lock_file,
+                epoch,
+                &result.report.toolchain,
+                &artifacts,
+            )?)
+        }
+        None => None,
+    };
+
+    // Record per-package sizes and enforce the budget
+    let size_report =
+        (!options.kernel_only).then(|| SizeReport::new(package_sizes, image_size, size_budget));
+    if let Some(ref report) = size_report {
+        report
+            .save(&output_dir)
+            .with_context(|| "Failed to write size report")?;
+        report.check_budget()?;
+    }
+
+    if image_path.is_some() {
+        layout
+            .update_latest()
+            .with_context(|| format!("Failed to update {}", layout.latest_link().display()))?;
+    }
+
+    result.packages_built = packages_to_build.len();
+    result.build_time = packages_time;
+    result.estimated_secs = estimated_secs;
+    result.image = image_path;
+    result.initramfs_size = initramfs_size;
+    result.embedded_in_kernel = embedded;
+    result.fit_image = fit_image;
+    result.disk_image = disk_image;
+    result.attestation = attestation;
+    result.compression = compression;
+    result.strip = stripped;
+    result.validation = validation;
+    result.sizes = size_report;
+    Ok(())
+}
+
+/// Validate the assembled rootfs, failing the build on errors
+fn check_rootfs(
+    project_dir: &Path,
+    manifest: &Manifest,
+    rootfs_dir: &Path,
+) -> Result<ValidationReport> {
+    let target = manifest
+        .board
+        .name
+        .as_deref()
+        .and_then(|name| load_board_definition(project_dir, name).ok())
+        .map(|board| board.target(&manifest.board));
+    let options = ValidateOptions::new(
+        target,
+        manifest.build.init.clone(),
+        &manifest.build.validation,
+    )
+    .map_err(|e| anyhow::anyhow!("Invalid build.validation in zigroot.toml: {e}"))?;
+    let report = validate::validate_rootfs(rootfs_dir, &options);
+    let errors: Vec<String> = report.errors().map(Finding::to_string).collect();
+    if !errors.is_empty() {
+        bail!(
+            "Rootfs validation failed with {} error(s):\n  {}\n\
+             Fix the packages, lower the severity in [build.validation] or pass --no-validate.",
+            errors.len(),
+            errors.join("\n  ")
+        );
+    }
+    Ok(report)
+}
+
+/// Provision the host tools of the given packages
+///
+/// Returns the tools of each package; packages without `host_depends` are
+/// left out.
+pub(crate) async fn provision_host_tools<'a>(
+    definitions: &HashMap<String, PackageMetadata>,
+    packages: impl Iterator<Item = &'a String>,
+) -> Result<HashMap<String, Vec<ProvisionedTool>>> {
+    let needed: Vec<(String, Vec<String>)> = packages
+        .filter_map(|name| {
+            let definition = definitions.get(name)?;
+            (!definition.host_depends.is_empty())
+                .then(|| (name.clone(), definition.host_depends.clone()))
+        })
+        .collect();
+    if needed.is_empty() {
+        return Ok(HashMap::new());
+    }
+    host_tools::provision_packages(
+        &needed,
+        &RegistryClient::new(),
+        &ZigrootDirs::new().host_tools_dir(),
+    )
+    .await
+}
+
+/// Check that all dependencies of a package have been built (--no-deps)
+fn check_dependencies_built(
+    pkg_name: &str,
+    graph: &DependencyGraph,
+    manifest: &Manifest,
+    stamps_dir: &Path,
+) -> Result<()> {
+    let mut missing: Vec<String> = graph
+        .transitive_dependencies(pkg_name)
+        .into_iter()
+        .filter(|dep| manifest.packages.contains_key(dep))
+        .filter(|dep| !stamps_dir.join(format!("{dep}.stamp")).exists())
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    missing.sort();
+    bail!(
+        "Cannot build '{pkg_name}' with --no-deps: dependencies not built yet: {}\n\
+         Run 'zigroot build --package {pkg_name}' to build them as well.",
+        missing.join(", ")
+    );
+}
+
+/// Verify all packages match lock file in --locked mode
+fn verify_locked_packages(
+    project_dir: &Path,
+    manifest: &Manifest,
+    lock_file: &LockFile,
+) -> Result<()> {
+    for (name, pkg_ref) in &manifest.packages {
+        let version = locked_version(
+            lock_file,
+            name,
+            pkg_ref.version.as_deref().unwrap_or("latest"),
+        );
+
+        // For local packages, use "local" as checksum
+        let local_pkg_path = project_dir.join("packages").join(name);
+        let checksum = if local_pkg_path.exists() {
+            "local"
+        } else {
+            // Would need to fetch from registry to get actual checksum
+            "unknown"
+        };
+
+        lock_file
+            .verify_package(name, version, checksum)
+            .with_context(|| format!("Package '{name}' differs from lock file"))?;
+    }
+    Ok(())
+}
+
+/// Verify that URL external artifacts declare their locked checksums (--locked)
+fn verify_locked_externals(manifest: &Manifest, lock_file: &LockFile) -> Result<()> {
+    for (name, artifact) in &manifest.external {
+        if artifact.url.is_none() {
+            continue;
+        }
+        let Some(checksum) = artifact.checksum()? else {
+            continue;
+        };
+        lock_file
+            .verify_external(name, &checksum)
+            .with_context(|| format!("External artifact '{name}' differs from lock file"))?;
+    }
+    Ok(())
+}
+
+/// Version of a package to use given its manifest requirement
+///
+/// A requirement such as "^1.36.1" keeps the locked version while it is
+/// satisfied; otherwise the requirement itself is used.
+fn locked_version<'a>(lock_file: &'a LockFile, name: &str, requirement: &'a str) -> &'a str {
+    lock_file
+        .get_package(name)
+        .map(|locked| locked.version.as_str())
+        .filter(|locked| satisfies_requirement(locked, requirement))
+        .unwrap_or(requirement)
+}
+
+/// How to build one package
+struct PackageBuild<'a> {
+    /// Rebuild even if the package has a stamp
+    force: bool,
+    /// Cache key recorded in the package's stamp
+    key: &'a str,
+    /// Namespace tool of sandboxed builds
+    isolation: Option<NamespaceTool>,
+    /// Transitive dependencies, whose staging trees form the build sysroot
+    dependencies: &'a [String],
+    /// Host tools from the package's `host_depends`
+    host_tools: &'a [ProvisionedTool],
+    /// Parallel jobs within the package's build (`JOBS`, `MAKEFLAGS`)
+    jobs: usize,
+    /// Directory of the package build logs
+    logs_dir: &'a Path,
+}
+
+/// Build a single package
+///
+/// Custom build steps of local packages run in a namespace sandbox when
+/// `isolation` is set, with the staging trees of the package's dependencies
+/// mounted, and with provisioned host tools in front of `PATH`.
+fn build_package(
+    project_dir: &Path,
+    pkg_name: &str,
+    manifest: &Manifest,
+    lock_file: &mut LockFile,
+    stamps_dir: &Path,
+    build: &PackageBuild,
+    progress: &mut dyn ProgressSink,
+) -> Result<bool> {
+    // Check if package needs rebuilding (incremental build)
+    let needs_rebuild = build.force || !builder::stamp_is_current(stamps_dir, pkg_name, build.key);
+
+    if !needs_rebuild {
+        tracing::info!("Package {pkg_name} is up to date, skipping");
+        return Ok(false);
+    }
+
+    tracing::info!("Building package: {pkg_name}");
+
+    // Check for local package
+    let local_pkg_path = project_dir.join("packages").join(pkg_name);
+    let pkg_ref = manifest.packages.get(pkg_name).unwrap();
+    let version = pkg_ref.version.as_deref().unwrap_or("1.0.0");
+
+    if local_pkg_path.exists() {
+        tracing::info!("Using local package: {}", local_pkg_path.display());
+        if let Some(definition) = local_definition(project_dir, pkg_name) {
+            let mut env = build
+                .host_tools
+                .iter()
+                .filter_map(|tool| tool.bin_dir.clone())
+                .fold(
+                    package_environment(
+                        project_dir,
+                        manifest,
+                        pkg_name,
+                        &definition.build,
+                        build.dependencies,
+                    )?
+                    .with_jobs(build.jobs),
+                    BuildEnvironment::with_tool_path,
+                );
+            let patches = package_patches(project_dir, pkg_name)?;
+            if !patches.is_empty() {
+                fs::create_dir_all(&env.srcdir)
+                    .with_context(|| format!("Failed to create {}", env.srcdir.display()))?;
+                let applied = patch::apply_patches(&env.srcdir, &patches).map_err(|e| {
+                    BuildError::BuildFailed {
+                        package: pkg_name.to_string(),
+                        error: e.to_string(),
+                    }
+                })?;
+                if applied {
+                    tracing::info!("Applied {} patch(es) to {pkg_name}", patches.len());
+                }
+            }
+            tracing::info!("Compiling {pkg_name} with {}", env.cc);
+            if !definition.build.steps.is_empty() || !definition.build.hooks().is_empty() {
+                // Install into a partial tree that only replaces the staging
+                // tree once all steps succeeded
+                let staging_root = project_dir.join("build").join(builder::STAGING_DIR);
+                env.destdir = builder::begin_staging(&staging_root, pkg_name)
+                    .with_context(|| format!("Failed to prepare the staging tree of {pkg_name}"))?;
+                run_steps(project_dir, pkg_name, &definition, &env, build, progress)?;
+                builder::commit_staging(&staging_root, pkg_name)
+                    .with_context(|| format!("Failed to install the staging tree of {pkg_name}"))?;
+            }
+        }
+
+        // Add to lock file with local source and the host tools used
+        let locked = build.host_tools.iter().fold(
+            LockedPackageBuilder::new(pkg_name, version, "local")
+                .source(&format!("path:packages/{pkg_name}")),
+            |locked, tool| locked.host_tool(&tool.name, &tool.version),
+        );
+        lock_file.add_package(locked.build());
+    } else if pkg_ref.git.is_none() || lock_file.get_package(pkg_name).is_none() {
+        // Registry package - would download and build
+        // For now, just add to lock file; git packages keep the entry
+        // pinning their commit
+        let version = locked_version(lock_file, pkg_name, version).to_string();
+        lock_file.add_package(LockedPackageBuilder::new(pkg_name, &version, "registry").build());
+    }
+
+    // Mark as built with the cache key of this configuration
+    builder::write_stamp(stamps_dir, pkg_name, build.key)
+        .with_context(|| format!("Failed to create stamp file for {pkg_name}"))?;
+
+    tracing::info!("Built package: {pkg_name}");
+    Ok(true)
+}
+
+/// Run the custom build steps and hooks of a local package, sandboxed with
+/// the build's `isolation`
+///
+/// `pre_build` runs before the steps; `post_build` and then `post_install`
+/// after them, while the install tree is still partial. Everything is
+/// logged to `<package>.log` in the logs directory; step output is streamed
+/// as [`ProgressEvent::BuildOutput`] and hook output as
+/// [`ProgressEvent::HookOutput`].
+fn run_steps(
+    project_dir: &Path,
+    pkg_name: &str,
+    definition: &PackageDefinition,
+    env: &BuildEnvironment,
+    build: &PackageBuild,
+    progress: &mut dyn ProgressSink,
+) -> Result<()> {
+    for dir in [&env.srcdir, &env.destdir] {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+
+    let sandbox = build.isolation.map(|tool| {
+        if definition.build.network {
+            progress.event(ProgressEvent::Warning(format!(
+                "Package {pkg_name} sets build.network = true, but sandboxed builds have no network access; sources are fetched before the build"
+            )));
+        }
+        builder::package_sandbox(tool, project_dir, pkg_name, env, build.dependencies)
+    });
+    if let Some(sandbox) = &sandbox {
+        let root = project_dir
+            .join("build")
+            .join(builder::SANDBOX_DIR)
+            .join(pkg_name);
+        fs::create_dir_all(&root)
+            .with_context(|| format!("Failed to create {}", root.display()))?;
+        tracing::info!("Building {pkg_name} in a {} sandbox", sandbox.tool());
+    }
+
+    let log_path = build.logs_dir.join(format!("{pkg_name}.log"));
+    let _ = fs::remove_file(&log_path);
+    let hooks = definition.build.hooks();
+    let run_hook = |stage: &str, progress: &mut dyn ProgressSink| -> Result<()> {
+        let Some((_, command)) = hooks.iter().find(|(name, _)| *name == stage) else {
+            return Ok(());
+        };
+        tracing::info!("Running {stage} hook of {pkg_name}");
+        let hook = format!("{pkg_name} {stage}");
+        builder::run_package_hook(
+            pkg_name,
+            stage,
+            command,
+            env,
+            sandbox.as_ref(),
+            &log_path,
+            &mut |line| {
+                progress.event(ProgressEvent::HookOutput {
+                    hook: hook.clone(),
+                    line: line.to_string(),
+                });
+            },
+        )?;
+        Ok(())
+    };
+
+    run_hook("pre_build", progress)?;
+    builder::run_build_steps(
+        pkg_name,
+        &definition.build.steps,
+        env,
+        sandbox.as_ref(),
+        &log_path,
+        &mut |line| {
+            progress.event(ProgressEvent::BuildOutput {
+                package: pkg_name.to_string(),
+                line: line.to_string(),
+            });
+        },
+    )?;
+    run_hook("post_build", progress)?;
+    run_hook("post_install", progress)?;
+    Ok(())
+}
+
+/// Definition of a local package, if it exists and parses
+fn local_definition(project_dir: &Path, pkg_name: &str) -> Option<PackageDefinition> {
+    let path = project_dir
+        .join("packages")
+        .join(pkg_name)
+        .join("package.toml");
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| PackageDefinition::from_toml(&content).ok())
+}
+
+/// Patches of a local package, resolved from its `[source]` patch list
+fn package_patches(project_dir: &Path, pkg_name: &str) -> Result<Vec<Patch>> {
+    let Some(definition) = local_definition(project_dir, pkg_name) else {
+        return Ok(Vec::new());
+    };
+    let package_dir = project_dir.join("packages").join(pkg_name);
+    patch::resolve_patches(&package_dir, definition.source.patches())
+        .with_context(|| format!("Invalid patches of package {pkg_name}"))
+}
+
+/// Compiler environment of a package
+///
+/// Packages with `toolchain = "gcc"` are compiled with the GCC
+/// cross-toolchain for the board's triple instead of Zig. The staging trees
+/// of the built `dependencies` are its sysroot.
+pub(crate) fn package_environment(
+    project_dir: &Path,
+    manifest: &Manifest,
+    pkg_name: &str,
+    build: &PackageBuildConfig,
+    dependencies: &[String],
+) -> Result<BuildEnvironment> {
+    if let Some(toolchain) = &build.toolchain {
+        if !VALID_TOOLCHAINS.contains(&toolchain.kind()) {
+            bail!(
+                "Unknown toolchain '{}' for package '{pkg_name}': must be one of {}",
+                toolchain.kind(),
+                VALID_TOOLCHAINS.join(", ")
+            );
+        }
+    }
+
+    let (target, cpu) = board_target(project_dir, manifest);
+    let staging_root = project_dir.join("build").join(builder::STAGING_DIR);
+    let env = dependencies
+        .iter()
+        .map(|dependency| staging_root.join(dependency))
+        .filter(|dir| dir.is_dir())
+        .fold(
+            BuildEnvironment::for_package(
+                build,
+                &target,
+                &cpu,
+                project_dir.join("build/src").join(pkg_name),
+                staging_root.join(pkg_name),
+            ),
+            BuildEnvironment::with_sysroot,
+        );
+
+    if build_env::needs_gcc(build, &target) && which::which(&env.cc).is_err() {
+        tracing::warn!(
+            "Package {pkg_name} uses the GCC toolchain, but {} was not found in PATH. Run 'zigroot doctor' for details.",
+            env.cc
+        );
+    }
+    Ok(env)
+}
+
+/// Compiler environment of the board, for packages without a definition
+///
+/// Commands run in the project directory and install into the staging
+/// directory.
+pub(crate) fn board_environment(project_dir: &Path, manifest: &Manifest) -> BuildEnvironment {
+    let (target, cpu) = board_target(project_dir, manifest);
+    BuildEnvironment::for_package(
+        &PackageBuildConfig::default(),
+        &target,
+        &cpu,
+        project_dir.to_path_buf(),
+        project_dir.join("build").join(builder::STAGING_DIR),
+    )
+}
+
+/// Target triple, with the selected libc, and CPU of the project's board
+fn board_target(project_dir: &Path, manifest: &Manifest) -> (String, String) {
+    manifest
+        .board
+        .name
+        .as_deref()
+        .and_then(|name| load_board_definition(project_dir, name).ok())
+        .map_or_else(
+            || ("x86_64-linux-musl".to_string(), "generic".to_string()),
+            |board| (board.target(&manifest.board), board.board.cpu.clone()),
+        )
+}
+
+/// Strip binaries of the given packages in the staging directory
+///
+/// The cross objcopy of the board's target triple is preferred. Packages
+/// can opt out with `strip = false` in their manifest options or package
+/// definition.
+fn handle_strip(
+    project_dir: &Path,
+    manifest: &Manifest,
+    staging_root: &Path,
+    output_dir: &Path,
+    packages: &[String],
+) -> StripStats {
+    let config = StripConfig {
+        global_enabled: manifest.build.strip,
+        split_debug: manifest.build.split_debug,
+    };
+    let mut stats = StripStats::default();
+
+    let to_strip: Vec<&String> = packages
+        .iter()
+        .filter(|name| staging_root.join(name).is_dir())
+        .filter(|name| {
+            config.is_enabled_for_package(package_strip_setting(project_dir, manifest, name))
+        })
+        .collect();
+    if to_strip.is_empty() {
+        return stats;
+    }
+
+    let cross_prefix = manifest
+        .board
+        .name
+        .as_deref()
+        .and_then(|name| load_board_definition(project_dir, name).ok())
+        .map(|board| build_env::gcc_prefix(&board.target(&manifest.board)));
+    let Some(tool) = StripTool::detect(cross_prefix.as_deref()) else {
+        tracing::warn!("objcopy not found, skipping symbol stripping");
+        return stats;
+    };
+
+    let debug_root = output_dir.join(strip::DEBUG_DIR);
+    for name in to_strip {
+        match strip::strip_staged_package(&staging_root.join(name), &debug_root, &config, &tool) {
+            Ok(package_stats) => {
+                if package_stats.files_stripped > 0 {
+                    tracing::info!(
+                        "Stripped {} binaries in {name}, saved {} bytes",
+                        package_stats.files_stripped,
+                        package_stats.bytes_saved
+                    );
+                }
+                stats.merge(&package_stats);
+            }
+            Err(e) => tracing::warn!("Failed to strip {name}: {e}"),
+        }
+    }
+    stats
+}
+
+/// Per-package strip setting from the manifest or package definition
+fn package_strip_setting(project_dir: &Path, manifest: &Manifest, name: &str) -> Option<bool> {
+    let manifest_setting = manifest
+        .packages
+        .get(name)
+        .and_then(|pkg_ref| pkg_ref.options.get("strip"))
+        .and_then(toml::Value::as_bool);
+
+    manifest_setting.or_else(|| local_definition(project_dir, name)?.build.strip)
+}
+
+/// Compress binaries of the given packages in the staging directory
+///
+/// `cli_override` (see [`compress::cli_override`]) takes precedence, then
+/// `compress` in the manifest package options, then the package definition,
+/// then `build.compress`.
+fn handle_compression(
+    project_dir: &Path,
+    cli_override: Option<bool>,
+    manifest: &Manifest,
+    staging_root: &Path,
+    packages: &[String],
+    target_arch: &str,
+) -> CompressionStats {
+    let config = CompressionConfig {
+        global_enabled: manifest.build.compress,
+        cli_compress: cli_override == Some(true),
+        cli_no_compress: cli_override == Some(false),
+        target_arch: target_arch.to_string(),
+    };
+    let mut stats = CompressionStats::default();
+
+    let to_compress: Vec<&String> = packages
+        .iter()
+        .filter(|name| staging_root.join(name).is_dir())
+        .filter(|name| {
+            config.is_enabled_for_package(package_compress_setting(project_dir, manifest, name))
+        })
+        .collect();
+    if to_compress.is_empty() {
+        tracing::info!("No packages selected for compression");
+        return stats;
+    }
+
+    if !compress::is_upx_usable(&config) {
+        return stats;
+    }
+
+    for name in to_compress {
+        match compress::compress_dir(&staging_root.join(name), &manifest.build.compress_exclude) {
+            Ok(package_stats) => stats.merge(&package_stats),
+            Err(e) => tracing::warn!("Compression failed for {name}: {e}"),
+        }
+    }
+    stats
+}
+
+/// Per-package compression setting from the manifest or package definition
+fn package_compress_setting(project_dir: &Path, manifest: &Manifest, name: &str) -> Option<bool> {
+    let manifest_setting = manifest
+        .packages
+        .get(name)
+        .and_then(|pkg_ref| pkg_ref.options.get("compress"))
+        .and_then(toml::Value::as_bool);
+
+    manifest_setting.or_else(|| {
+        let path = project_dir.join("packages").join(name).join("package.toml");
+        let content = fs::read_to_string(path).ok()?;
+        PackageDefinition::from_toml(&content).ok()?.build.compress
+    })
+}
+
+/// Check initramfs compression and kernel embedding before building
+///
+/// Embedding needs a zigroot-managed kernel package. A standalone compressed
+/// initramfs must be unpackable by the kernel, so the kernel config is
+/// checked for the matching decompressor when one is available.
+fn check_initramfs_settings(project_dir: &Path, manifest: &Manifest) -> Result<()> {
+    let build = &manifest.build;
+    if !VALID_INITRAMFS_COMPRESSIONS.contains(&build.initramfs_compression.as_str()) {
+        bail!(
+            "Invalid build.initramfs_compression '{}': must be one of {}",
+            build.initramfs_compression,
+            VALID_INITRAMFS_COMPRESSIONS.join(", ")
+        );
+    }
+    if build.image_format != "initramfs" {
+        if build.embed_in_kernel {
+            bail!(
+                "build.embed_in_kernel requires image_format = \"initramfs\" (found \"{}\")",
+                build.image_format
+            );
+        }
+        return Ok(());
+    }
+
+    let kernel_pkg = kernel::resolve_kernel_package(project_dir, manifest);
+    if build.embed_in_kernel {
+        let pkg_toml = project_dir
+            .join("packages")
+            .join(&kernel_pkg)
+            .join("package.toml");
+        if !pkg_toml.exists() {
+            bail!(
+                "build.embed_in_kernel is set but no zigroot-managed kernel was found.\n\
+                 Create a kernel package in packages/{kernel_pkg}/ or set embed_in_kernel = false."
+            );
+        }
+        // The embedding fragment enables the decompressor itself
+        return Ok(());
+    }
+
+    let Some(option) = kernel::initramfs_decompressor_option(&build.initramfs_compression) else {
+        return Ok(());
+    };
+    let Some(config_path) = kernel::find_kernel_config(project_dir, &kernel_pkg) else {
+        return Ok(());
+    };
+    let config = fs::read_to_string(&config_path)
+        .with_context(|| format!("Failed to read {}", config_path.display()))?;
+    if !kernel::config_enables(&config, option) {
+        bail!(
+            "initramfs_compression = \"{}\" needs {option}=y, but the kernel config at {} does not enable it.\n\
+             Enable it in the kernel config or choose a different compression.",
+            build.initramfs_compression,
+            config_path.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Resolve the `[permissions]` overlay against the assembled rootfs
+///
+/// Only initramfs archives record it; other images and an initramfs that
+/// the kernel build archives itself get a warning.
+fn resolve_permissions(
+    manifest: &Manifest,
+    rootfs_dir: &Path,
+    progress: &mut dyn ProgressSink,
+) -> Result<PermissionTable> {
+    if manifest.permissions.is_empty() {
+        return Ok(PermissionTable::default());
+    }
+    let table = PermissionTable::resolve(rootfs_dir, &manifest.permissions)
+        .map_err(|errors| anyhow::anyhow!("Invalid [permissions]:\n  {}", errors.join("\n  ")))?;
+    let build = &manifest.build;
+    if build.image_format != "initramfs" {
+        progress.event(ProgressEvent::Warning(format!(
+            "[permissions] is only recorded in initramfs archives, not in {} images",
+            build.image_format
+        )));
+    } else if build.embed_in_kernel {
+        progress.event(ProgressEvent::Warning(
+            "[permissions] is not recorded in the initramfs embedded into the kernel; \
+             only the standalone archive has it"
+                .to_string(),
+        ));
+    }
+    Ok(table)
+}
+
+/// Create the rootfs image
+///
+/// An initramfs is archived from `rootfs_dir` and compressed; its size
+/// before compression is returned as well.
+fn create_rootfs_image(
+    output_dir: &Path,
+    manifest: &Manifest,
+    rootfs_dir: &Path,
+    epoch: Option<u64>,
+    permissions: &PermissionTable,
+) -> Result<(PathBuf, Option<u64>)> {
+    let image_format = &manifest.build.image_format;
+    if image_format == "initramfs" {
+        // Written uncompressed first and compressed afterwards
+        let archive = output_dir.join("rootfs.cpio");
+        tracing::info!("Creating initramfs: {}", archive.display());
+        fs::create_dir_all(rootfs_dir)
+            .with_context(|| format!("Failed to create {}", rootfs_dir.display()))?;
+        let size = builder::write_initramfs(rootfs_dir, &archive, epoch, permissions)
+            .with_context(|| "Failed to create initramfs archive")?;
+        let compression = &manifest.build.initramfs_compression;
+        let image_path = builder::compress_initramfs(&archive, compression)
+            .with_context(|| format!("Failed to compress initramfs with {compression}"))?;
+        return Ok((image_path, Some(size)));
+    }
+
+    // An ext4 image has a fixed size; check it before creating the
+    // filesystem, which fails cryptically when the content does not fit
+    if image_format == "ext4" {
+        let content = size::tree_size(rootfs_dir)
+            .with_context(|| format!("Failed to measure {}", rootfs_dir.display()))?;
+        let image_size = size::rootfs_image_size(
+            &manifest.build.rootfs_size,
+            content,
+            manifest.build.rootfs_slack,
+        )?;
+        tracing::info!("Rootfs image size: {}", size::format_bytes(image_size));
+    }
+
+    let image_path = output_dir.join(manifest.build.image_file_name());
+    tracing::info!("Creating {image_format} image: {}", image_path.display());
+
+    // Create a placeholder image file
+    fs::write(
+        &image_path,
+        format!(
+            "# Zigroot {} image\n# Format: {}\n# Hostname: {}\n",
+            manifest.project.name, image_format, manifest.build.hostname
+        ),
+    )
+    .with_context(|| "Failed to create rootfs image")?;
+    Ok((image_path, None))
+}
+
+/// Assemble the disk image if a partition layout is configured
+fn build_disk_image(
+    project_dir: &Path,
+    output_dir: &Path,
+    manifest: &Manifest,
+    rootfs_image: Option<&Path>,
+    epoch: Option<u64>,
+) -> Result<Option<PathBuf>> {
+    let Some((config, specs)) = partition::project_disk_config(project_dir, manifest) else {
+        return Ok(None);
+    };
+    let layout = DiskLayout::new(&config, &specs)?;
+
+    let mut contents = Vec::new();
+    for part in &layout.partitions {
+        let content = match part.content.as_deref() {
+            Some(content) => Some(
+                match partition::content_path(project_dir, manifest, content) {
+                    Some(path) => path,
+                    None => rootfs_image.map(Path::to_path_buf).with_context(|| {
+                        format!(
+                            "Partition '{}' holds the rootfs, but the initramfs is only embedded \
+                             into the kernel. Build with --also-standalone.",
+                            part.name
+                        )
+                    })?,
+                },
+            ),
+            None => None,
+        };
+        contents.push(content);
+    }
+
+    let disk_path = output_dir.join(&config.name);
+    tracing::info!(
+        "Creating {} disk image: {}",
+        layout.table,
+        disk_path.display()
+    );
+    builder::assemble_disk_image(
+        &disk_path,
+        &layout,
+        &contents,
+        &manifest.project.name,
+        epoch,
+    )
+    .with_context(|| format!("Failed to assemble disk image {}", disk_path.display()))?;
+    Ok(Some(disk_path))
+}
+
+/// Write the `sha256sum` file of the final image, and its signature when
+/// `build.signing_key` is configured
+fn seal_image(image: &Path) -> Result<ImageChecksum> {
+    let content = fs::read(image).with_context(|| format!("Failed to read {}", image.display()))?;
+    let sha256 = compute_checksum(&content);
+    let file_name = image.file_name().unwrap_or_default().to_string_lossy();
+    let checksum_file = output::checksum_path(image);
+    fs::write(&checksum_file, format!("{sha256}  {file_name}\n"))
+        .with_context(|| format!("Failed to write {}", checksum_file.display()))?;
+
+    let signature = match GlobalConfig::load(&ZigrootDirs::new())?.build.signing_key {
+        Some(key) => {
+            let key = SigningKey::load(Path::new(&key))
+                .with_context(|| "Failed to load build.signing_key")?;
+            let path = key
+                .sign_image(image, &sha256)
+                .with_context(|| format!("Failed to sign {}", image.display()))?;
+            Some(path.display().to_string())
+        }
+        None => None,
+    };
+    Ok(ImageChecksum {
+        path: image.display().to_string(),
+        sha256,
+        signature,
+    })
+}
+
+/// Write the attestation of a reproducible build, returning its path
+fn write_attestation(
+    project_dir: &Path,
+    output_dir: &Path,
+    manifest: &Manifest,
+    lock_file: &LockFile,
+    epoch: u64,
+    toolchain: &str,
+    artifacts: &[&PathBuf],
+) -> Result<PathBuf> {
+    let artifacts = artifacts
+        .iter()
+        .map(|path| {
+            ArtifactDigest::of(output_dir, path)
+                .with_context(|| format!("Failed to hash {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let attestation = Attestation {
+        project: manifest.project.name.clone(),
+        zigroot_version: env!("CARGO_PKG_VERSION").to_string(),
+        lock_hash: reproducible::lock_hash(lock_file),
+        source_date_epoch: epoch,
+        toolchains: [("zig".to_string(), toolchain.to_string())].into(),
+        options: Attestation::options(project_dir, manifest),
+        artifacts,
+    };
+    attestation
+        .save(output_dir)
+        .with_context(|| "Failed to write build attestation")?;
+    Ok(output_dir.join(reproducible::ATTESTATION_FILE))
+}
+
+/// Version of the Zig toolchain, or the one recorded in the lock file
+fn toolchain_version(lock_file: &LockFile) -> String {
+    std::process::Command::new("zig")
+        .arg("version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map_or_else(
+            || lock_file.metadata.zig_version.clone(),
+            |output| String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        )
+}
+
+/// Check if a package is a kernel package
+///
+/// A package is considered a kernel package if:
+/// - Its name contains "kernel" or "linux"
+/// - It has a GCC toolchain specified in its package.toml
+fn is_kernel_package(project_dir: &Path, pkg_name: &str) -> bool {
+    // Check by name
+    let name_lower = pkg_name.to_lowercase();
+    if name_lower.contains("kernel") || name_lower.contains("linux") {
+        return true;
+    }
+
+    // Check local package for GCC toolchain
+    let local_pkg_path = project_dir
+        .join("packages")
+        .join(pkg_name)
+        .join("package.toml");
+    if local_pkg_path.exists() {
+        if let Ok(content) = fs::read_to_string(&local_pkg_path) {
+            // Simple check for GCC toolchain in package.toml
+            if content.contains("[build.toolchain]") && content.contains("type = \"gcc\"") {
+                return true;
+            }
+        }
+    }
+
+    false
+}
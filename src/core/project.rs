@@ -0,0 +1,494 @@
+//! Library facade over a zigroot project
+//!
+//! [`ZigrootProject`] is the entry point for embedding zigroot: it opens a
+//! project directory and exposes dependency resolution, fetching, building
+//! and adding packages. Nothing here prints; results and errors are
+//! returned, and progress of a build is reported to a [`ProgressSink`].
+//! The CLI commands are thin layers over these methods.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+
+use crate::core::add::{self, AddOptions, AddResult};
+use crate::core::build_env::{self, BuildEnvironment};
+use crate::core::compress::CompressionStats;
+use crate::core::fetch::{self, FetchOptions, FetchProgress, FetchResult};
+use crate::core::flash::load_board_definition;
+use crate::core::global_config::GlobalConfig;
+use crate::core::libc::Libc;
+use crate::core::manifest::Manifest;
+use crate::core::package::{PackageBuildConfig, PackageDefinition, PackageMetadata};
+use crate::core::pipeline;
+use crate::core::report::BuildReport;
+use crate::core::resolver::DependencyGraph;
+use crate::core::size::SizeReport;
+use crate::core::stats::{self, BuildRecord};
+use crate::core::strip::StripStats;
+use crate::core::validate::ValidationReport;
+use crate::infra::dirs::ZigrootDirs;
+
+/// Build options
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// Build only this package and its dependencies
+    pub package: Option<String>,
+    /// With `package`, skip building its dependencies
+    pub no_deps: bool,
+    /// Number of parallel jobs (0 for every CPU)
+    pub jobs: Option<usize>,
+    /// Fail if packages differ from the lock file
+    pub locked: bool,
+    /// Force binary compression on
+    pub compress: bool,
+    /// Force binary compression off
+    pub no_compress: bool,
+    /// Build only the kernel and modules
+    pub kernel_only: bool,
+    /// Enable build isolation
+    pub sandbox: bool,
+    /// Disable build isolation
+    pub no_sandbox: bool,
+    /// Write the standalone initramfs even when embedded into the kernel
+    pub also_standalone: bool,
+    /// Write a JSON build report to this path, relative to the project
+    pub report: Option<String>,
+    /// Skip the free disk space check
+    pub skip_space_check: bool,
+    /// Output directory overriding `build.output_dir`
+    pub output_dir: Option<String>,
+    /// Skip validation of the assembled rootfs
+    pub no_validate: bool,
+    /// Build reproducibly even if `build.reproducible` is not set
+    pub reproducible: bool,
+    /// Keep building packages that don't depend on a failed one
+    pub keep_going: bool,
+    /// Directory of the per-package build logs, relative to the project
+    /// (default: `build/logs`)
+    pub log_dir: Option<String>,
+}
+
+/// Event reported while a build runs
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    /// The packages to build are known
+    Planned {
+        /// Packages in build order
+        packages: Vec<String>,
+        /// Estimated seconds for the packages that are out of date
+        estimated_secs: f64,
+        /// Parallel jobs of the build
+        jobs: usize,
+    },
+    /// A package started building (or is checked for being up to date)
+    PackageStarted {
+        /// Package name
+        name: String,
+    },
+    /// A package finished
+    PackageFinished {
+        /// Package name
+        name: String,
+        /// Whether it was rebuilt rather than up to date
+        rebuilt: bool,
+        /// Its share of the estimated seconds
+        estimated_secs: f64,
+    },
+    /// A package failed to build and `keep_going` is set
+    PackageFailed {
+        /// Package name
+        name: String,
+        /// Why the build failed
+        error: String,
+        /// Its share of the estimated seconds
+        estimated_secs: f64,
+    },
+    /// A package was not built because a dependency failed
+    PackageSkipped {
+        /// Package name
+        name: String,
+        /// The failed dependency
+        dependency: String,
+        /// Its share of the estimated seconds
+        estimated_secs: f64,
+    },
+    /// All packages are built; the rootfs and images are being assembled
+    Assembling,
+    /// Something the user should know about that does not stop the build
+    Warning(String),
+    /// A line printed by the build steps of a package; all of it is also
+    /// in the package's log file
+    BuildOutput {
+        /// Package name
+        package: String,
+        /// Output line
+        line: String,
+    },
+    /// A line printed by a hook
+    HookOutput {
+        /// Hook that printed it (e.g. `busybox post_install`)
+        hook: String,
+        /// Output line
+        line: String,
+    },
+}
+
+/// Receiver of [`ProgressEvent`]s
+///
+/// Implemented for closures, so `&mut |event| ...` can be passed directly.
+pub trait ProgressSink {
+    /// Handle one event
+    fn event(&mut self, event: ProgressEvent);
+}
+
+impl<F: FnMut(ProgressEvent)> ProgressSink for F {
+    fn event(&mut self, event: ProgressEvent) {
+        self(event);
+    }
+}
+
+/// Dependency graph and build order of a project's packages
+#[derive(Debug)]
+pub struct BuildPlan {
+    /// Metadata of the local package definitions
+    pub definitions: HashMap<String, PackageMetadata>,
+    /// Runtime and build-only dependencies of the manifest packages
+    pub graph: DependencyGraph,
+    /// Manifest packages in build order
+    pub order: Vec<String>,
+}
+
+/// Result of a successful build
+#[derive(Debug, Clone, Default)]
+pub struct BuildResult {
+    /// Build report, as written with [`BuildOptions::report`]
+    pub report: BuildReport,
+    /// Number of packages considered for building
+    pub packages_built: usize,
+    /// Time spent building packages
+    pub build_time: Duration,
+    /// Estimated time for the packages, from earlier builds
+    pub estimated_secs: f64,
+    /// Rootfs image, unless only embedded into the kernel
+    pub image: Option<PathBuf>,
+    /// Size of the initramfs archive before compression
+    pub initramfs_size: Option<u64>,
+    /// Whether the initramfs is embedded into the kernel
+    pub embedded_in_kernel: bool,
+    /// FIT image for U-Boot
+    pub fit_image: Option<PathBuf>,
+    /// Full disk image from the partition layout
+    pub disk_image: Option<PathBuf>,
+    /// Attestation of a reproducible build
+    pub attestation: Option<PathBuf>,
+    /// Binary compression statistics
+    pub compression: CompressionStats,
+    /// Symbol stripping statistics
+    pub strip: StripStats,
+    /// Findings of rootfs validation, unless skipped
+    pub validation: Option<ValidationReport>,
+    /// Installed size by package, unless only the kernel was built
+    pub sizes: Option<SizeReport>,
+}
+
+/// A zigroot project on disk
+#[derive(Debug, Clone)]
+pub struct ZigrootProject {
+    root: PathBuf,
+    manifest: Manifest,
+}
+
+impl ZigrootProject {
+    /// Open the project in `path`, reading its `zigroot.toml`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let root = path.as_ref().to_path_buf();
+        let manifest_path = root.join("zigroot.toml");
+        if !manifest_path.exists() {
+            bail!(
+                "No zigroot.toml found in {}. Run 'zigroot init' to create a project.",
+                root.display()
+            );
+        }
+        let content = fs::read_to_string(&manifest_path)
+            .with_context(|| format!("Failed to read manifest at {}", manifest_path.display()))?;
+        let manifest =
+            Manifest::from_toml(&content).with_context(|| "Failed to parse zigroot.toml")?;
+        Ok(Self { root, manifest })
+    }
+
+    /// Project directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Parsed manifest
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Resolve the dependencies of the manifest packages into a build order
+    ///
+    /// Fails if a package does not support the board's C library.
+    pub fn resolve(&self) -> Result<BuildPlan> {
+        let definitions = load_package_metadata(&self.root, &self.manifest);
+        check_supported_libc(&self.root, &self.manifest, &definitions)?;
+        let graph = dependency_graph(&self.manifest, &definitions);
+        let order = build_order(&self.manifest, &graph)?;
+        Ok(BuildPlan {
+            definitions,
+            graph,
+            order,
+        })
+    }
+
+    /// Compiler environment of a package's build steps, or of the board
+    /// without a package
+    ///
+    /// This is the environment `build` runs the package with: its toolchain,
+    /// the staging trees of its dependencies as sysroot and its host tools,
+    /// which are provisioned if needed.
+    pub async fn environment(&self, package: Option<&str>) -> Result<BuildEnvironment> {
+        let global_config = GlobalConfig::load(&ZigrootDirs::new())?;
+        let jobs = build_env::resolve_jobs(self.manifest.build.jobs.or(global_config.build.jobs));
+        let Some(name) = package else {
+            return Ok(pipeline::board_environment(&self.root, &self.manifest).with_jobs(jobs));
+        };
+        if !self.manifest.packages.contains_key(name) {
+            bail!("Package '{name}' is not in zigroot.toml");
+        }
+
+        let plan = self.resolve()?;
+        let mut dependencies: Vec<String> = plan
+            .graph
+            .transitive_dependencies(name)
+            .into_iter()
+            .collect();
+        dependencies.sort();
+        let path = self.root.join("packages").join(name).join("package.toml");
+        let build = match fs::read_to_string(&path) {
+            Ok(content) => {
+                PackageDefinition::from_toml(&content)
+                    .with_context(|| format!("Failed to parse {}", path.display()))?
+                    .build
+            }
+            Err(_) => PackageBuildConfig::default(),
+        };
+        let env =
+            pipeline::package_environment(&self.root, &self.manifest, name, &build, &dependencies)?
+                .with_jobs(jobs);
+
+        let name = name.to_string();
+        let tools =
+            pipeline::provision_host_tools(&plan.definitions, std::iter::once(&name)).await?;
+        Ok(tools
+            .get(&name)
+            .into_iter()
+            .flatten()
+            .filter_map(|tool| tool.bin_dir.clone())
+            .fold(env, BuildEnvironment::with_tool_path))
+    }
+
+    /// Download package sources and external artifacts
+    ///
+    /// Download progress is reported to `progress`, if given.
+    pub async fn fetch(
+        &self,
+        options: &FetchOptions,
+        progress: Option<&FetchProgress>,
+    ) -> Result<FetchResult> {
+        fetch::fetch_packages(&self.root, options, progress)
+            .await
+            .with_context(|| "Failed to fetch packages")
+    }
+
+    /// Build the project
+    ///
+    /// With [`BuildOptions::report`] set, the report is written for failed
+    /// builds as well. Every build is recorded in the local statistics
+    /// unless `build.stats` is disabled in the global config.
+    pub async fn build(
+        &self,
+        options: &BuildOptions,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<BuildResult> {
+        let start = Instant::now();
+        let mut result = BuildResult {
+            report: BuildReport::new(),
+            ..BuildResult::default()
+        };
+        let outcome = pipeline::run_build(self, options, progress, &mut result).await;
+        let error = outcome.as_ref().err().map(|e| format!("{e:#}"));
+        result.report.finish(start.elapsed().as_secs_f64(), error);
+        self.record_stats(&result.report);
+
+        if let Some(ref path) = options.report {
+            let path = self.root.join(path);
+            let saved = result
+                .report
+                .save(&path)
+                .with_context(|| format!("Failed to write build report to {}", path.display()));
+            outcome.and(saved)?;
+        } else {
+            outcome?;
+        }
+        Ok(result)
+    }
+
+    /// Append the build to the local statistics unless disabled
+    fn record_stats(&self, report: &BuildReport) {
+        let dirs = ZigrootDirs::new();
+        if !GlobalConfig::load(&dirs).is_ok_and(|config| config.stats_enabled()) {
+            return;
+        }
+        let record = BuildRecord::from_report(report, self.manifest.board.name.as_deref());
+        if let Err(e) = stats::append(&dirs.data_dir().join(stats::STATS_FILE), &record) {
+            tracing::debug!("Failed to record build statistics: {e}");
+        }
+    }
+
+    /// Add a package (`name` or `name@version`) to the manifest and lock file
+    ///
+    /// The project's manifest is reloaded afterwards.
+    pub async fn add_package(&mut self, spec: &str, options: &AddOptions) -> Result<AddResult> {
+        let result = add::add_package(&self.root, spec, options)
+            .await
+            .with_context(|| format!("Failed to add package '{spec}'"))?;
+        *self = Self::open(&self.root)?;
+        Ok(result)
+    }
+}
+
+/// Load metadata of local package definitions referenced by the manifest
+fn load_package_metadata(
+    project_dir: &Path,
+    manifest: &Manifest,
+) -> HashMap<String, PackageMetadata> {
+    let mut definitions = HashMap::new();
+    for name in manifest.packages.keys() {
+        let path = project_dir.join("packages").join(name).join("package.toml");
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        match PackageDefinition::from_toml(&content) {
+            Ok(def) => {
+                definitions.insert(name.clone(), def.package);
+            }
+            Err(e) => tracing::warn!("Failed to parse {}: {e}", path.display()),
+        }
+    }
+    definitions
+}
+
+/// Fail if a package does not support the C library of the board
+fn check_supported_libc(
+    project_dir: &Path,
+    manifest: &Manifest,
+    definitions: &HashMap<String, PackageMetadata>,
+) -> Result<()> {
+    let libc = manifest
+        .board
+        .name
+        .as_deref()
+        .and_then(|name| load_board_definition(project_dir, name).ok())
+        .map_or_else(
+            || manifest.board.libc.unwrap_or(Libc::Musl),
+            |board| board.libc(&manifest.board),
+        );
+
+    let mut unsupported: Vec<(&String, &PackageMetadata)> = definitions
+        .iter()
+        .filter(|(_, package)| !package.supports_libc(libc))
+        .collect();
+    unsupported.sort_by_key(|(name, _)| *name);
+    if let Some((name, package)) = unsupported.first() {
+        let supported: Vec<&str> = package.supported_libc.iter().map(|l| l.as_str()).collect();
+        bail!(
+            "Package '{name}' does not support {libc} (supported: {}); \
+             choose another package or set `libc` in the [board] section of zigroot.toml",
+            supported.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Build the dependency graph of all manifest packages
+///
+/// Both runtime and build-only dependencies are edges.
+fn dependency_graph(
+    manifest: &Manifest,
+    definitions: &HashMap<String, PackageMetadata>,
+) -> DependencyGraph {
+    let mut names: Vec<&String> = manifest.packages.keys().collect();
+    names.sort();
+
+    let mut graph = DependencyGraph::new();
+    for name in names {
+        let deps = definitions
+            .get(name)
+            .map(PackageMetadata::build_order_dependencies)
+            .unwrap_or_default();
+        graph.add_package(name, deps);
+    }
+    graph
+}
+
+/// Compute the build order of all manifest packages
+fn build_order(manifest: &Manifest, graph: &DependencyGraph) -> Result<Vec<String>> {
+    let order = graph
+        .topological_sort()
+        .map_err(|e| anyhow::anyhow!("Dependency resolution failed: {e}"))?;
+    Ok(order
+        .into_iter()
+        .filter(|name| manifest.packages.contains_key(name))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn local_package(dir: &Path, name: &str, depends: &str) {
+        let pkg = dir.join("packages").join(name);
+        fs::create_dir_all(&pkg).unwrap();
+        fs::write(
+            pkg.join("package.toml"),
+            format!(
+                "[package]\nname = \"{name}\"\nversion = \"1.0.0\"\ndescription = \"{name}\"\n\
+                 depends = [{depends}]\n\n[source]\nurl = \"https://example.com/{name}.tar.gz\"\n\
+                 sha256 = \"{}\"\n",
+                "0".repeat(64)
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_resolve_orders_dependencies_first() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join("zigroot.toml"),
+            "[project]\nname = \"demo\"\n\n[packages]\napp = { path = \"packages/app\" }\n\
+             lib = { path = \"packages/lib\" }\n",
+        )
+        .unwrap();
+        local_package(temp.path(), "app", "\"lib\"");
+        local_package(temp.path(), "lib", "");
+
+        let project = ZigrootProject::open(temp.path()).unwrap();
+        assert_eq!(project.manifest().project.name, "demo");
+        let plan = project.resolve().unwrap();
+        assert_eq!(plan.order, vec!["lib", "app"]);
+        assert!(plan.graph.transitive_dependencies("app").contains("lib"));
+    }
+
+    #[test]
+    fn test_open_requires_manifest() {
+        let temp = TempDir::new().unwrap();
+        let error = ZigrootProject::open(temp.path()).unwrap_err();
+        assert!(error.to_string().contains("No zigroot.toml found"));
+    }
+}
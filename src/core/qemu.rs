@@ -0,0 +1,421 @@
+//! QEMU support for `zigroot run`
+//!
+//! Boards describe how to emulate them in a `[qemu]` section of board.toml:
+//! the machine, CPU, memory, serial console and how the built image is
+//! attached. The command line is assembled from that section and the
+//! artifacts of the last build.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::core::external::resolve_reference;
+use crate::core::manifest::Manifest;
+use crate::core::output::OutputLayout;
+use crate::core::partition;
+
+/// Ways of attaching the rootfs image
+pub const VALID_QEMU_ATTACH: &[&str] = &["initrd", "virtio", "sd", "ide"];
+
+/// Packages that provide an SSH server for `--ssh-port`
+pub const SSH_PACKAGES: &[&str] = &["dropbear", "openssh"];
+
+/// Default kernel reference, as registered by `zigroot kernel build`
+const DEFAULT_KERNEL: &str = "kernel";
+
+/// QEMU settings from a `[qemu]` section
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QemuConfig {
+    /// Machine type (e.g., "virt", "q35")
+    pub machine: String,
+
+    /// CPU model (e.g., "cortex-a53")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<String>,
+
+    /// Guest memory (e.g., "256M")
+    #[serde(default = "default_memory")]
+    pub memory: String,
+
+    /// Serial console device passed to the kernel (e.g., "ttyAMA0")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub console: Option<String>,
+
+    /// QEMU system binary; derived from the board target when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binary: Option<String>,
+
+    /// Kernel: external artifact name or project-relative path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kernel: Option<String>,
+
+    /// Device tree: external artifact name or project-relative path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dtb: Option<String>,
+
+    /// How the image is attached (initrd, virtio, sd, ide); defaults to
+    /// initrd for initramfs images and virtio otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attach: Option<String>,
+
+    /// Root device on the kernel command line when booting from a drive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+
+    /// Extra kernel command line arguments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub append: Option<String>,
+
+    /// Network device for `--ssh-port`
+    #[serde(default = "default_nic")]
+    pub nic: String,
+
+    /// Extra QEMU arguments
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+}
+
+fn default_memory() -> String {
+    "256M".to_string()
+}
+
+fn default_nic() -> String {
+    "virtio-net-pci".to_string()
+}
+
+/// Options of a `zigroot run` invocation
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Open a display window instead of using only the serial console
+    pub graphic: bool,
+    /// Forward this host port to the guest's SSH port
+    pub ssh_port: Option<u16>,
+    /// Arguments passed through to QEMU after `--`
+    pub extra_args: Vec<String>,
+}
+
+/// Built artifacts to boot
+#[derive(Debug, Clone, PartialEq)]
+pub struct QemuInputs {
+    /// Kernel image; QEMU boots the drive through firmware without one
+    pub kernel: Option<PathBuf>,
+    /// Device tree
+    pub dtb: Option<PathBuf>,
+    /// Rootfs or disk image
+    pub image: PathBuf,
+    /// Whether `image` is a partitioned disk image
+    pub disk: bool,
+}
+
+/// QEMU architecture name for a target triple
+pub fn qemu_arch(target: &str) -> Option<&'static str> {
+    let arch = target.split('-').next()?;
+    match arch {
+        "aarch64" | "arm64" => Some("aarch64"),
+        _ if arch.starts_with("arm") => Some("arm"),
+        "x86_64" => Some("x86_64"),
+        "i386" | "i486" | "i586" | "i686" | "x86" => Some("i386"),
+        "riscv64" => Some("riscv64"),
+        "riscv32" => Some("riscv32"),
+        "mips" => Some("mips"),
+        "mipsel" => Some("mipsel"),
+        "mips64" => Some("mips64"),
+        "mips64el" => Some("mips64el"),
+        _ => None,
+    }
+}
+
+/// QEMU system binary for a board
+pub fn qemu_binary(config: &QemuConfig, target: &str) -> Option<String> {
+    config
+        .binary
+        .clone()
+        .or_else(|| qemu_arch(target).map(|arch| format!("qemu-system-{arch}")))
+}
+
+/// Example `[qemu]` section for a target, shown when a board has none
+pub fn example_section(target: &str) -> String {
+    let (machine, cpu, console) = match qemu_arch(target) {
+        Some("aarch64") => ("virt", Some("cortex-a53"), "ttyAMA0"),
+        Some("arm") => ("virt", Some("cortex-a15"), "ttyAMA0"),
+        Some("riscv64" | "riscv32") => ("virt", None, "ttyS0"),
+        _ => ("q35", None, "ttyS0"),
+    };
+    let cpu = cpu
+        .map(|cpu| format!("cpu = \"{cpu}\"\n"))
+        .unwrap_or_default();
+    format!("[qemu]\nmachine = \"{machine}\"\n{cpu}memory = \"256M\"\nconsole = \"{console}\"\n")
+}
+
+/// Whether the project includes a known SSH server package
+pub fn has_ssh_package(manifest: &Manifest) -> bool {
+    SSH_PACKAGES
+        .iter()
+        .any(|name| manifest.packages.contains_key(*name))
+}
+
+/// Attachment of the image, from the config or the image format
+pub fn attach_mode<'a>(config: &'a QemuConfig, manifest: &Manifest) -> &'a str {
+    match config.attach.as_deref() {
+        Some(attach) => attach,
+        None if manifest.build.image_format == "initramfs" => "initrd",
+        None => "virtio",
+    }
+}
+
+/// Locate the kernel, device tree and image of the last build
+///
+/// A disk image is preferred over the bare rootfs image when the project
+/// has a partition layout and the image is attached as a drive.
+pub fn resolve_inputs(
+    project_dir: &Path,
+    manifest: &Manifest,
+    config: &QemuConfig,
+) -> Result<QemuInputs> {
+    let attach = attach_mode(config, manifest);
+    let layout = OutputLayout::new(project_dir, manifest);
+
+    let disk_image = (attach != "initrd")
+        .then(|| partition::project_disk_config(project_dir, manifest))
+        .flatten()
+        .map(|(disk, _)| layout.artifact(&disk.name));
+    let (image, disk) = match disk_image {
+        Some(path) => (path, true),
+        None => (layout.artifact(&manifest.build.image_file_name()), false),
+    };
+    if !image.is_file() {
+        bail!(
+            "Image not found at {}. Run 'zigroot build' first.",
+            image.display()
+        );
+    }
+
+    let reference = config.kernel.as_deref().unwrap_or(DEFAULT_KERNEL);
+    let kernel = resolve_reference(project_dir, manifest, reference);
+    let kernel = if kernel.is_file() {
+        Some(kernel)
+    } else if config.kernel.is_some() || attach == "initrd" {
+        bail!(
+            "Kernel '{reference}' not found at {}. Run 'zigroot kernel build' first.",
+            kernel.display()
+        );
+    } else {
+        None
+    };
+
+    let dtb = match config.dtb.as_deref() {
+        Some(reference) => {
+            let path = resolve_reference(project_dir, manifest, reference);
+            if !path.is_file() {
+                bail!("Device tree '{reference}' not found at {}", path.display());
+            }
+            Some(path)
+        }
+        None => None,
+    };
+
+    Ok(QemuInputs {
+        kernel,
+        dtb,
+        image,
+        disk,
+    })
+}
+
+/// Default root device for an image attached as a bare filesystem
+fn default_root(attach: &str) -> Option<&'static str> {
+    match attach {
+        "virtio" => Some("/dev/vda"),
+        "sd" => Some("/dev/mmcblk0"),
+        "ide" => Some("/dev/sda"),
+        _ => None,
+    }
+}
+
+/// Assemble the QEMU arguments
+///
+/// Without `graphic`, `-nographic` puts the serial console on the terminal;
+/// with it, the serial console is still multiplexed onto stdio.
+pub fn build_args(
+    config: &QemuConfig,
+    attach: &str,
+    inputs: &QemuInputs,
+    options: &RunOptions,
+) -> Result<Vec<String>> {
+    if !VALID_QEMU_ATTACH.contains(&attach) {
+        bail!(
+            "Invalid qemu.attach '{attach}': must be one of {}",
+            VALID_QEMU_ATTACH.join(", ")
+        );
+    }
+
+    let mut args = vec![
+        "-M".to_string(),
+        config.machine.clone(),
+        "-m".to_string(),
+        config.memory.clone(),
+    ];
+    if let Some(cpu) = &config.cpu {
+        args.extend(["-cpu".to_string(), cpu.clone()]);
+    }
+
+    if let Some(kernel) = &inputs.kernel {
+        args.extend(["-kernel".to_string(), kernel.display().to_string()]);
+        if let Some(dtb) = &inputs.dtb {
+            args.extend(["-dtb".to_string(), dtb.display().to_string()]);
+        }
+
+        let mut cmdline = Vec::new();
+        if let Some(console) = &config.console {
+            cmdline.push(format!("console={console}"));
+        }
+        let root = config
+            .root
+            .as_deref()
+            .or_else(|| (!inputs.disk).then(|| default_root(attach)).flatten());
+        if let Some(root) = root {
+            cmdline.push(format!("root={root}"));
+            cmdline.push("rw".to_string());
+        }
+        if let Some(append) = &config.append {
+            cmdline.push(append.clone());
+        }
+        if !cmdline.is_empty() {
+            args.extend(["-append".to_string(), cmdline.join(" ")]);
+        }
+    }
+
+    let image = inputs.image.display().to_string();
+    if attach == "initrd" {
+        args.extend(["-initrd".to_string(), image]);
+    } else {
+        args.extend([
+            "-drive".to_string(),
+            format!("file={image},format=raw,if={attach}"),
+        ]);
+    }
+
+    if options.graphic {
+        args.extend(["-serial".to_string(), "mon:stdio".to_string()]);
+    } else {
+        args.push("-nographic".to_string());
+    }
+
+    if let Some(port) = options.ssh_port {
+        args.extend([
+            "-netdev".to_string(),
+            format!("user,id=net0,hostfwd=tcp::{port}-:22"),
+            "-device".to_string(),
+            format!("{},netdev=net0", config.nic),
+        ]);
+    }
+
+    args.extend(config.args.iter().cloned());
+    args.extend(options.extra_args.iter().cloned());
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(toml: &str) -> QemuConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn inputs(kernel: bool, disk: bool) -> QemuInputs {
+        QemuInputs {
+            kernel: kernel.then(|| PathBuf::from("output/kernel/Image")),
+            dtb: None,
+            image: PathBuf::from("output/rootfs.img"),
+            disk,
+        }
+    }
+
+    #[test]
+    fn test_qemu_arch_from_target() {
+        assert_eq!(qemu_arch("aarch64-linux-musl"), Some("aarch64"));
+        assert_eq!(qemu_arch("arm-linux-musleabihf"), Some("arm"));
+        assert_eq!(qemu_arch("armv7-linux-musleabihf"), Some("arm"));
+        assert_eq!(qemu_arch("x86_64-linux-musl"), Some("x86_64"));
+        assert_eq!(qemu_arch("riscv64-linux-musl"), Some("riscv64"));
+        assert_eq!(qemu_arch("sparc-linux-gnu"), None);
+
+        let cfg = config("machine = \"virt\"");
+        assert_eq!(
+            qemu_binary(&cfg, "aarch64-linux-musl").as_deref(),
+            Some("qemu-system-aarch64")
+        );
+        let cfg = config("machine = \"virt\"\nbinary = \"/opt/qemu/bin/qemu-system-arm\"");
+        assert_eq!(
+            qemu_binary(&cfg, "aarch64-linux-musl").as_deref(),
+            Some("/opt/qemu/bin/qemu-system-arm")
+        );
+    }
+
+    #[test]
+    fn test_build_args_kernel_with_virtio_rootfs() {
+        let cfg = config(
+            "machine = \"virt\"\ncpu = \"cortex-a53\"\nconsole = \"ttyAMA0\"\nappend = \"quiet\"",
+        );
+        let args =
+            build_args(&cfg, "virtio", &inputs(true, false), &RunOptions::default()).unwrap();
+        assert_eq!(
+            args,
+            [
+                "-M",
+                "virt",
+                "-m",
+                "256M",
+                "-cpu",
+                "cortex-a53",
+                "-kernel",
+                "output/kernel/Image",
+                "-append",
+                "console=ttyAMA0 root=/dev/vda rw quiet",
+                "-drive",
+                "file=output/rootfs.img,format=raw,if=virtio",
+                "-nographic",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_args_graphic_ssh_and_passthrough() {
+        let cfg = config("machine = \"q35\"\nargs = [\"-smp\", \"2\"]");
+        let options = RunOptions {
+            graphic: true,
+            ssh_port: Some(2222),
+            extra_args: vec!["-snapshot".to_string()],
+        };
+        let args = build_args(&cfg, "ide", &inputs(false, true), &options).unwrap();
+        let joined = args.join(" ");
+        assert!(!joined.contains("-nographic"), "{joined}");
+        assert!(!joined.contains("-append"), "{joined}");
+        assert!(joined.contains("-serial mon:stdio"), "{joined}");
+        assert!(
+            joined.contains(
+                "-netdev user,id=net0,hostfwd=tcp::2222-:22 -device virtio-net-pci,netdev=net0"
+            ),
+            "{joined}"
+        );
+        assert!(joined.ends_with("-smp 2 -snapshot"), "{joined}");
+    }
+
+    #[test]
+    fn test_build_args_rejects_unknown_attach() {
+        let cfg = config("machine = \"virt\"");
+        let err =
+            build_args(&cfg, "floppy", &inputs(true, false), &RunOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("Invalid qemu.attach 'floppy'"));
+    }
+
+    #[test]
+    fn test_example_section_parses() {
+        let example = example_section("aarch64-linux-musl");
+        let table: toml::Table = toml::from_str(&example).unwrap();
+        let cfg: QemuConfig = table["qemu"].clone().try_into().unwrap();
+        assert_eq!(cfg.machine, "virt");
+        assert_eq!(cfg.console.as_deref(), Some("ttyAMA0"));
+    }
+}
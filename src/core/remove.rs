@@ -2,11 +2,16 @@
 //!
 //! This module contains the business logic for removing packages from a project.
 //! It handles removing packages from the manifest and updating the lock file.
+//! Packages that others depend on are only removed with `--force`; with
+//! `--cascade`, dependencies nothing else needs are removed as well.
 
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::Path;
 
 use crate::core::lock::LockFile;
 use crate::core::manifest::Manifest;
+use crate::core::tree::DependencyTree;
+use crate::infra::filesystem::write_file_atomic;
 use thiserror::Error;
 
 /// Errors that can occur during package removal
@@ -16,6 +21,16 @@ pub enum RemoveError {
     #[error("Package '{name}' is not installed")]
     PackageNotFound { name: String },
 
+    /// Other packages depend on the package
+    #[error(
+        "Package '{name}' is required by: {}\nRemove those packages first, or use --force to remove it anyway",
+        dependents.join(", ")
+    )]
+    HasDependents {
+        name: String,
+        dependents: Vec<String>,
+    },
+
     /// Manifest error
     #[error("Failed to read/write manifest: {0}")]
     ManifestError(String),
@@ -29,6 +44,24 @@ pub enum RemoveError {
     IoError(String),
 }
 
+/// Options for removing a package
+#[derive(Debug, Clone, Default)]
+pub struct RemoveOptions {
+    /// Remove the package even if others depend on it (--force)
+    pub force: bool,
+    /// Also remove dependencies that nothing else needs (--cascade)
+    pub cascade: bool,
+}
+
+/// A package removed along with the requested one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemovedPackage {
+    /// Package name
+    pub name: String,
+    /// Version requirement from the manifest (if any)
+    pub version: Option<String>,
+}
+
 /// Result of removing a package
 #[derive(Debug)]
 pub struct RemoveResult {
@@ -36,6 +69,10 @@ pub struct RemoveResult {
     pub package_name: String,
     /// Version that was removed (if known)
     pub version: Option<String>,
+    /// Orphaned dependencies removed with --cascade
+    pub dependencies_removed: Vec<RemovedPackage>,
+    /// Packages left with a missing dependency by --force
+    pub broken_dependents: Vec<String>,
     /// Whether the lock file was updated
     pub lock_updated: bool,
 }
@@ -44,6 +81,7 @@ pub struct RemoveResult {
 pub fn remove_package(
     project_path: &Path,
     package_name: &str,
+    options: &RemoveOptions,
 ) -> Result<RemoveResult, RemoveError> {
     let manifest_path = project_path.join("zigroot.toml");
     let lock_path = project_path.join("zigroot.lock");
@@ -66,23 +104,56 @@ pub fn remove_package(
     // Get version before removal (for reporting)
     let version = package_ref.version.clone();
 
-    // Remove package from manifest
+    let lock_file = if lock_path.exists() {
+        Some(LockFile::load(&lock_path).map_err(|e| RemoveError::LockError(e.to_string()))?)
+    } else {
+        None
+    };
+    let edges = dependency_edges(project_path, &manifest, lock_file.as_ref());
+
+    let dependents = find_dependents(&edges, &manifest, package_name);
+    if !dependents.is_empty() && !options.force {
+        return Err(RemoveError::HasDependents {
+            name: package_name.to_string(),
+            dependents,
+        });
+    }
+
+    let orphans = if options.cascade {
+        orphaned_dependencies(&edges, &manifest, package_name)
+    } else {
+        Vec::new()
+    };
+    let dependencies_removed: Vec<RemovedPackage> = orphans
+        .iter()
+        .map(|name| RemovedPackage {
+            name: name.clone(),
+            version: manifest
+                .packages
+                .get(name)
+                .and_then(|package| package.version.clone()),
+        })
+        .collect();
+
+    // Remove packages from manifest
     manifest.packages.remove(package_name);
+    for name in &orphans {
+        manifest.packages.remove(name);
+    }
 
     // Save manifest
     let new_manifest_content = manifest
         .to_toml()
         .map_err(|e| RemoveError::ManifestError(e.to_string()))?;
-    std::fs::write(&manifest_path, new_manifest_content)
+    write_file_atomic(&manifest_path, new_manifest_content.as_bytes())
         .map_err(|e| RemoveError::IoError(e.to_string()))?;
 
     // Update lock file if it exists
-    let lock_updated = if lock_path.exists() {
-        let mut lock_file =
-            LockFile::load(&lock_path).map_err(|e| RemoveError::LockError(e.to_string()))?;
-
-        // Remove package from lock file
-        lock_file.packages.retain(|p| p.name != package_name);
+    let lock_updated = if let Some(mut lock_file) = lock_file {
+        // Remove packages from lock file
+        lock_file
+            .packages
+            .retain(|p| p.name != package_name && !orphans.contains(&p.name));
 
         // Save lock file
         lock_file
@@ -97,15 +168,105 @@ pub fn remove_package(
     Ok(RemoveResult {
         package_name: package_name.to_string(),
         version,
+        dependencies_removed,
+        broken_dependents: dependents,
         lock_updated,
     })
 }
 
+/// Direct dependencies of each package
+///
+/// Combines local package definitions (runtime and build-only) with the
+/// dependencies recorded in the lock file.
+fn dependency_edges(
+    project_path: &Path,
+    manifest: &Manifest,
+    lock_file: Option<&LockFile>,
+) -> HashMap<String, BTreeSet<String>> {
+    let tree = DependencyTree::from_project(project_path, manifest, true);
+    let mut edges: HashMap<String, BTreeSet<String>> = HashMap::new();
+    for package in tree.packages() {
+        for edge in tree.dependencies(package).into_iter().flatten() {
+            edges
+                .entry(package.clone())
+                .or_default()
+                .insert(edge.target.clone());
+        }
+    }
+    for locked in lock_file.iter().flat_map(|lock| &lock.packages) {
+        for dep in &locked.depends {
+            let name = dep.split('@').next().unwrap_or(dep);
+            edges
+                .entry(locked.name.clone())
+                .or_default()
+                .insert(name.to_string());
+        }
+    }
+    edges
+}
+
+/// Manifest packages that directly depend on `package`, sorted
+fn find_dependents(
+    edges: &HashMap<String, BTreeSet<String>>,
+    manifest: &Manifest,
+    package: &str,
+) -> Vec<String> {
+    let mut dependents: Vec<String> = manifest
+        .packages
+        .keys()
+        .filter(|name| name.as_str() != package)
+        .filter(|name| edges.get(*name).is_some_and(|deps| deps.contains(package)))
+        .cloned()
+        .collect();
+    dependents.sort();
+    dependents
+}
+
+/// Manifest packages only needed by `package`, sorted
+///
+/// These are its transitive dependencies that are not reachable from any
+/// other package left in the manifest.
+fn orphaned_dependencies(
+    edges: &HashMap<String, BTreeSet<String>>,
+    manifest: &Manifest,
+    package: &str,
+) -> Vec<String> {
+    let mut candidates = reachable(edges, [package.to_string()]);
+    candidates.remove(package);
+    candidates.retain(|name| manifest.packages.contains_key(name));
+
+    let roots = manifest
+        .packages
+        .keys()
+        .filter(|name| name.as_str() != package && !candidates.contains(*name))
+        .cloned();
+    let needed = reachable(edges, roots);
+
+    let mut orphans: Vec<String> = candidates.difference(&needed).cloned().collect();
+    orphans.sort();
+    orphans
+}
+
+/// Packages reachable from `roots`, including the roots
+fn reachable(
+    edges: &HashMap<String, BTreeSet<String>>,
+    roots: impl IntoIterator<Item = String>,
+) -> HashSet<String> {
+    let mut seen = HashSet::new();
+    let mut pending: Vec<String> = roots.into_iter().collect();
+    while let Some(name) = pending.pop() {
+        if seen.insert(name.clone()) {
+            pending.extend(edges.get(&name).into_iter().flatten().cloned());
+        }
+    }
+    seen
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::manifest::{PackageRef, ProjectConfig};
-    use std::collections::HashMap;
+    use crate::core::manifest::{PackageRef, ProjectConfig, MANIFEST_VERSION};
+    use std::collections::{BTreeMap, HashMap};
     use tempfile::TempDir;
 
     fn create_test_manifest(packages: Vec<(&str, &str)>) -> Manifest {
@@ -124,15 +285,24 @@ mod tests {
         }
 
         Manifest {
+            manifest_version: MANIFEST_VERSION,
             project: ProjectConfig {
                 name: "test-project".to_string(),
                 version: "1.0.0".to_string(),
                 description: None,
+                strict_env: false,
             },
             board: Default::default(),
             build: Default::default(),
             packages: pkg_map,
             external: HashMap::new(),
+            fit: None,
+            partitions: Vec::new(),
+            disk_image: None,
+            flash: None,
+            permissions: BTreeMap::new(),
+            license: None,
+            hooks: None,
         }
     }
 
@@ -146,7 +316,7 @@ mod tests {
         std::fs::write(&manifest_path, manifest.to_toml().unwrap()).unwrap();
 
         // Remove busybox
-        let result = remove_package(temp.path(), "busybox").unwrap();
+        let result = remove_package(temp.path(), "busybox", &RemoveOptions::default()).unwrap();
 
         assert_eq!(result.package_name, "busybox");
         assert_eq!(result.version, Some("1.36.1".to_string()));
@@ -169,7 +339,7 @@ mod tests {
         std::fs::write(&manifest_path, manifest.to_toml().unwrap()).unwrap();
 
         // Try to remove nonexistent package
-        let result = remove_package(temp.path(), "nonexistent");
+        let result = remove_package(temp.path(), "nonexistent", &RemoveOptions::default());
 
         assert!(result.is_err());
         match result.unwrap_err() {
@@ -198,7 +368,7 @@ mod tests {
         lock.save(&lock_path).unwrap();
 
         // Remove busybox
-        let result = remove_package(temp.path(), "busybox").unwrap();
+        let result = remove_package(temp.path(), "busybox", &RemoveOptions::default()).unwrap();
 
         assert!(result.lock_updated);
 
@@ -206,4 +376,86 @@ mod tests {
         let updated_lock = LockFile::load(&lock_path).unwrap();
         assert!(updated_lock.get_package("busybox").is_none());
     }
+
+    /// Write a manifest and a lock file recording `(package, dependencies)`
+    fn write_project(temp: &TempDir, packages: &[(&str, &[&str])]) {
+        let names: Vec<(&str, &str)> = packages.iter().map(|(name, _)| (*name, "1.0.0")).collect();
+        let manifest = create_test_manifest(names);
+        std::fs::write(
+            temp.path().join("zigroot.toml"),
+            manifest.to_toml().unwrap(),
+        )
+        .unwrap();
+
+        let mut lock = LockFile::new("0.1.0", "0.13.0");
+        for (name, depends) in packages {
+            let mut package =
+                crate::core::lock::LockedPackageBuilder::new(name, "1.0.0", "abc123").build();
+            package.depends = depends.iter().map(|dep| format!("{dep}@1.0.0")).collect();
+            lock.add_package(package);
+        }
+        lock.save(&temp.path().join("zigroot.lock")).unwrap();
+    }
+
+    #[test]
+    fn test_remove_refuses_package_with_dependents() {
+        let temp = TempDir::new().unwrap();
+        write_project(
+            &temp,
+            &[("curl", &["zlib"]), ("openssl", &["zlib"]), ("zlib", &[])],
+        );
+
+        match remove_package(temp.path(), "zlib", &RemoveOptions::default()) {
+            Err(RemoveError::HasDependents { name, dependents }) => {
+                assert_eq!(name, "zlib");
+                assert_eq!(dependents, vec!["curl", "openssl"]);
+            }
+            other => panic!("Expected HasDependents, got: {other:?}"),
+        }
+
+        let options = RemoveOptions {
+            force: true,
+            cascade: false,
+        };
+        let result = remove_package(temp.path(), "zlib", &options).unwrap();
+        assert_eq!(result.broken_dependents, vec!["curl", "openssl"]);
+        let lock = LockFile::load(&temp.path().join("zigroot.lock")).unwrap();
+        assert!(lock.get_package("zlib").is_none());
+    }
+
+    #[test]
+    fn test_remove_cascade_keeps_shared_dependencies() {
+        let temp = TempDir::new().unwrap();
+        write_project(
+            &temp,
+            &[
+                ("app", &["libfoo"]),
+                ("libfoo", &["libbar", "zlib"]),
+                ("libbar", &[]),
+                ("zlib", &[]),
+                ("curl", &["zlib"]),
+            ],
+        );
+
+        let options = RemoveOptions {
+            force: false,
+            cascade: true,
+        };
+        let result = remove_package(temp.path(), "app", &options).unwrap();
+        let removed: Vec<&str> = result
+            .dependencies_removed
+            .iter()
+            .map(|dep| dep.name.as_str())
+            .collect();
+        assert_eq!(removed, vec!["libbar", "libfoo"]);
+
+        let content = std::fs::read_to_string(temp.path().join("zigroot.toml")).unwrap();
+        let manifest = Manifest::from_toml(&content).unwrap();
+        let mut remaining: Vec<&String> = manifest.packages.keys().collect();
+        remaining.sort();
+        assert_eq!(remaining, vec!["curl", "zlib"]);
+        let lock = LockFile::load(&temp.path().join("zigroot.lock")).unwrap();
+        assert!(lock.get_package("libfoo").is_none());
+        assert!(lock.get_package("zlib").is_some());
+    }
 }
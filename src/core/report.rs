@@ -0,0 +1,156 @@
+//! Machine-readable build reports
+//!
+//! `zigroot build --report` records what a build produced: resolved package
+//! versions, per-package build times, image sizes, compression savings and
+//! the outcome. The report is also written when the build fails.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Default report path, relative to the project directory
+pub const DEFAULT_REPORT_PATH: &str = "output/build-report.json";
+
+/// Build result of a single package
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageReport {
+    /// Package name
+    pub name: String,
+    /// Resolved version
+    pub version: String,
+    /// Source URI from the lock file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Whether the package was rebuilt (false if it was up to date)
+    pub rebuilt: bool,
+    /// Build duration in seconds
+    pub duration_secs: f64,
+}
+
+/// Compression savings of a build
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressionReport {
+    /// Number of binaries compressed
+    pub files_compressed: usize,
+    /// Bytes saved by compression
+    pub bytes_saved: u64,
+}
+
+/// Symbol stripping savings of a build
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StripReport {
+    /// Number of binaries stripped
+    pub files_stripped: usize,
+    /// Bytes saved by stripping
+    pub bytes_saved: u64,
+}
+
+/// Checksum and signature of the final image
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageChecksum {
+    /// Image path: the disk image if there is one, else the rootfs image
+    pub path: String,
+    /// SHA256 of the image
+    pub sha256: String,
+    /// Detached signature, written when a signing key is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// Report of a complete build
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BuildReport {
+    /// Whether the build succeeded
+    pub success: bool,
+    /// Error message of a failed build
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Project name
+    pub project: String,
+    /// Zigroot CLI version
+    pub zigroot_version: String,
+    /// Zig toolchain version
+    pub toolchain: String,
+    /// Total build time in seconds
+    pub duration_secs: f64,
+    /// Packages in build order
+    pub packages: Vec<PackageReport>,
+    /// Rootfs image path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Rootfs image size in bytes
+    pub image_size: u64,
+    /// Additional images (FIT, disk image)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<String>,
+    /// Checksum of the final image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<ImageChecksum>,
+    /// Compression savings
+    pub compression: CompressionReport,
+    /// Symbol stripping savings
+    #[serde(default)]
+    pub strip: StripReport,
+}
+
+impl BuildReport {
+    /// Create an empty report for a build in progress
+    pub fn new() -> Self {
+        Self {
+            zigroot_version: env!("CARGO_PKG_VERSION").to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Record the outcome of the build
+    pub fn finish(&mut self, duration_secs: f64, error: Option<String>) {
+        self.duration_secs = duration_secs;
+        self.success = error.is_none();
+        self.error = error;
+    }
+
+    /// Write the report as pretty-printed JSON
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finish_records_outcome() {
+        let mut report = BuildReport::new();
+        report.finish(1.5, Some("Package 'x' failed".to_string()));
+        assert!(!report.success);
+        assert_eq!(report.error.as_deref(), Some("Package 'x' failed"));
+
+        report.finish(2.0, None);
+        assert!(report.success);
+        assert!(report.error.is_none());
+        assert_eq!(report.zigroot_version, env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn test_save_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DEFAULT_REPORT_PATH);
+        let mut report = BuildReport::new();
+        report.packages.push(PackageReport {
+            name: "busybox".to_string(),
+            version: "1.36.1".to_string(),
+            source: None,
+            rebuilt: true,
+            duration_secs: 0.25,
+        });
+        report.save(&path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let loaded: BuildReport = serde_json::from_str(&content).unwrap();
+        assert_eq!(loaded, report);
+    }
+}
@@ -0,0 +1,294 @@
+//! Reproducible builds
+//!
+//! With `build.reproducible = true`, `zigroot build --reproducible` or
+//! `SOURCE_DATE_EPOCH` set in the environment, two builds from the same lock file
+//! produce identical images: staged files get a fixed modification time
+//! (`SOURCE_DATE_EPOCH`), filesystem tools run with deterministic flags and
+//! an attestation records what went into the image.
+
+use crate::core::lock::LockFile;
+use crate::core::manifest::Manifest;
+use crate::infra::download::compute_checksum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Attestation file name in the output directory
+pub const ATTESTATION_FILE: &str = "attestation.json";
+
+/// Environment variable overriding the build timestamp
+pub const SOURCE_DATE_EPOCH: &str = "SOURCE_DATE_EPOCH";
+
+/// Hash of the locked inputs of a build
+///
+/// The `generated` timestamp is left out, so re-saving an unchanged lock
+/// file keeps the hash.
+pub fn lock_hash(lock_file: &LockFile) -> String {
+    let mut lock_file = lock_file.clone();
+    lock_file.metadata.generated = String::new();
+    compute_checksum(lock_file.to_toml().unwrap_or_default().as_bytes())
+}
+
+/// Timestamp used for every file in a reproducible build
+///
+/// `SOURCE_DATE_EPOCH` from the environment wins over
+/// `build.source_date_epoch`; without either, the timestamp is derived from
+/// the lock hash so it only changes when the locked inputs do.
+pub fn source_date_epoch(configured: Option<u64>, lock_hash: &str) -> u64 {
+    env_epoch()
+        .or(configured)
+        .unwrap_or_else(|| derived_epoch(lock_hash))
+}
+
+/// Timestamp set with `SOURCE_DATE_EPOCH`, which also turns on
+/// reproducible builds
+pub fn env_epoch() -> Option<u64> {
+    std::env::var(SOURCE_DATE_EPOCH)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Timestamp derived from the first 32 bits of the lock hash
+fn derived_epoch(lock_hash: &str) -> u64 {
+    lock_hash
+        .get(..8)
+        .and_then(|prefix| u64::from_str_radix(prefix, 16).ok())
+        .unwrap_or(0)
+}
+
+/// Set the modification time of every file and directory under `dir`
+///
+/// Symlinks are left alone. Returns the number of entries updated.
+pub fn normalize_mtimes(dir: &Path, epoch: u64) -> std::io::Result<usize> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let time = UNIX_EPOCH + Duration::from_secs(epoch);
+    let times = std::fs::FileTimes::new()
+        .set_accessed(time)
+        .set_modified(time);
+
+    // Children first, so setting a directory's time is not undone by
+    // touching its entries afterwards
+    let mut count = 0;
+    for entry in walkdir::WalkDir::new(dir)
+        .sort_by_file_name()
+        .contents_first(true)
+    {
+        let entry = entry.map_err(std::io::Error::other)?;
+        if entry.path_is_symlink() {
+            continue;
+        }
+        std::fs::File::open(entry.path())?.set_times(times)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Environment for filesystem tools in a reproducible build
+pub fn tool_env(epoch: u64) -> Vec<(&'static str, String)> {
+    vec![
+        (SOURCE_DATE_EPOCH, epoch.to_string()),
+        ("E2FSPROGS_FAKE_TIME", epoch.to_string()),
+        ("TZ", "UTC".to_string()),
+        ("LC_ALL", "C".to_string()),
+    ]
+}
+
+/// Extra arguments that make an image tool's output deterministic
+///
+/// `seed` replaces the random identifiers (filesystem UUID, volume ID).
+pub fn tool_args(program: &str, epoch: u64, seed: &str) -> Vec<String> {
+    match program {
+        "mkfs.ext4" => {
+            let uuid = seed_uuid(seed);
+            vec![
+                "-U".to_string(),
+                uuid.clone(),
+                "-E".to_string(),
+                format!("hash_seed={uuid}"),
+            ]
+        }
+        "mkfs.vfat" => vec![
+            "-i".to_string(),
+            seed_digest(seed)[..8].to_string(),
+            "--invariant".to_string(),
+        ],
+        "mcopy" => vec!["-m".to_string()],
+        "mksquashfs" => vec![
+            "-noappend".to_string(),
+            "-all-time".to_string(),
+            epoch.to_string(),
+            "-mkfs-time".to_string(),
+            epoch.to_string(),
+        ],
+        "cpio" => vec!["--reproducible".to_string()],
+        _ => Vec::new(),
+    }
+}
+
+/// `debugfs` commands setting all timestamps of the given inodes
+///
+/// `mkfs.ext4 -d` copies the change time of the source files, which cannot
+/// be set from user space, so the times are rewritten in the image.
+pub fn debugfs_time_script(paths: &[String], epoch: u64) -> String {
+    let mut script = String::new();
+    for path in paths {
+        for field in ["atime", "mtime", "ctime", "crtime"] {
+            let _ = writeln!(script, "set_inode_field \"{path}\" {field} @{epoch}");
+            let _ = writeln!(script, "set_inode_field \"{path}\" {field}_extra 0");
+        }
+    }
+    script
+}
+
+/// UUID derived from a seed
+fn seed_uuid(seed: &str) -> String {
+    let digest = seed_digest(seed);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &digest[..8],
+        &digest[8..12],
+        &digest[12..16],
+        &digest[16..20],
+        &digest[20..32]
+    )
+}
+
+fn seed_digest(seed: &str) -> String {
+    compute_checksum(seed.as_bytes())
+}
+
+/// Digest of a build artifact
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactDigest {
+    /// File name in the output directory
+    pub path: String,
+    /// SHA256 of the file
+    pub sha256: String,
+}
+
+impl ArtifactDigest {
+    /// Hash an artifact, recording its path relative to `output_dir`
+    pub fn of(output_dir: &Path, path: &Path) -> std::io::Result<Self> {
+        let sha256 = compute_checksum(&std::fs::read(path)?);
+        let path = path
+            .strip_prefix(output_dir)
+            .unwrap_or(path)
+            .display()
+            .to_string();
+        Ok(Self { path, sha256 })
+    }
+}
+
+/// Record of the inputs and outputs of a reproducible build
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attestation {
+    /// Project name
+    pub project: String,
+    /// Zigroot version that ran the build
+    pub zigroot_version: String,
+    /// Hash of the lock file (see [`lock_hash`])
+    pub lock_hash: String,
+    /// Timestamp applied to all files
+    pub source_date_epoch: u64,
+    /// Toolchain versions by name
+    pub toolchains: BTreeMap<String, String>,
+    /// Build, board and package options
+    pub options: serde_json::Value,
+    /// Digests of the images, the rootfs image first
+    pub artifacts: Vec<ArtifactDigest>,
+}
+
+impl Attestation {
+    /// Collect the options of a build from the manifest
+    ///
+    /// Package options are the effective values, including board and
+    /// package defaults.
+    pub fn options(project_dir: &Path, manifest: &Manifest) -> serde_json::Value {
+        let packages: BTreeMap<&String, BTreeMap<String, toml::Value>> = manifest
+            .packages
+            .keys()
+            .map(|name| {
+                let options = crate::core::config::get_package_options(project_dir, manifest, name)
+                    .into_iter()
+                    .map(|(key, option)| (key, option.value))
+                    .collect();
+                (name, options)
+            })
+            .collect();
+        serde_json::json!({
+            "build": manifest.build,
+            "board": {
+                "name": manifest.board.name,
+                "options": manifest.board.options.iter().collect::<BTreeMap<_, _>>(),
+            },
+            "packages": packages,
+        })
+    }
+
+    /// Write the attestation to the output directory
+    pub fn save(&self, output_dir: &Path) -> std::io::Result<()> {
+        let content = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(output_dir.join(ATTESTATION_FILE), content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lock_hash_ignores_generated_timestamp() {
+        let mut first = LockFile::new("0.1.0", "0.13.0");
+        first.metadata.generated = "1".to_string();
+        let mut second = first.clone();
+        second.metadata.generated = "2".to_string();
+        assert_eq!(lock_hash(&first), lock_hash(&second));
+
+        second.metadata.zig_version = "0.14.0".to_string();
+        assert_ne!(lock_hash(&first), lock_hash(&second));
+    }
+
+    #[test]
+    fn test_source_date_epoch_precedence() {
+        let hash = "0000ffff".to_string() + &"0".repeat(56);
+        assert_eq!(derived_epoch(&hash), 0xffff);
+        std::env::remove_var(SOURCE_DATE_EPOCH);
+        assert_eq!(source_date_epoch(None, &hash), 0xffff);
+        assert_eq!(source_date_epoch(Some(42), &hash), 42);
+    }
+
+    #[test]
+    fn test_normalize_mtimes_sets_files_and_directories() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join("usr/bin")).unwrap();
+        std::fs::write(temp.path().join("usr/bin/app"), "binary").unwrap();
+
+        let count = normalize_mtimes(temp.path(), 1_000).unwrap();
+        assert_eq!(count, 4);
+        for path in ["", "usr", "usr/bin", "usr/bin/app"] {
+            let modified = std::fs::metadata(temp.path().join(path))
+                .unwrap()
+                .modified()
+                .unwrap();
+            assert_eq!(modified, UNIX_EPOCH + Duration::from_secs(1_000), "{path}");
+        }
+    }
+
+    #[test]
+    fn test_tool_args_are_stable_per_seed() {
+        let ext4 = tool_args("mkfs.ext4", 0, "demo:root");
+        assert_eq!(ext4, tool_args("mkfs.ext4", 0, "demo:root"));
+        assert_ne!(ext4, tool_args("mkfs.ext4", 0, "demo:boot"));
+        assert_eq!(ext4[1].len(), 36);
+        assert_eq!(ext4[3], format!("hash_seed={}", ext4[1]));
+
+        assert_eq!(tool_args("mkfs.vfat", 0, "demo:boot")[1].len(), 8);
+        assert!(tool_args("mksquashfs", 7, "").contains(&"7".to_string()));
+        assert!(tool_args("unknown", 7, "").is_empty());
+    }
+}
@@ -3,7 +3,9 @@
 //! Handles computing build order and detecting dependency conflicts.
 
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 
+use crate::core::package::ConflictSpec;
 use crate::error::ResolverError;
 use semver::{Version, VersionReq};
 
@@ -113,6 +115,170 @@ pub fn detect_version_conflict(
     }
 }
 
+/// Where a version constraint comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintOrigin {
+    /// Package declaring the constraint, `None` for a requirement in
+    /// zigroot.toml itself
+    pub package: Option<String>,
+    /// Version of the declaring package, if known
+    pub version: Option<String>,
+    /// Packages from a manifest entry down to the declaring package
+    pub path: Vec<String>,
+}
+
+impl ConstraintOrigin {
+    /// A requirement written in zigroot.toml
+    pub fn manifest() -> Self {
+        Self {
+            package: None,
+            version: None,
+            path: Vec::new(),
+        }
+    }
+
+    /// A dependency declared by `package`, reached from the manifest
+    /// through `path` (which ends with `package`)
+    pub fn dependency(package: &str, version: Option<&str>, path: Vec<String>) -> Self {
+        Self {
+            package: Some(package.to_string()),
+            version: version.map(ToString::to_string),
+            path,
+        }
+    }
+
+    /// Short name of the origin, e.g. "nginx" or "zigroot.toml"
+    pub fn name(&self) -> &str {
+        self.package.as_deref().unwrap_or("zigroot.toml")
+    }
+}
+
+/// A version constraint and the package that placed it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    /// Version requirement (semver syntax, "*" for any)
+    pub requirement: String,
+    /// Where the requirement comes from
+    pub origin: ConstraintOrigin,
+}
+
+impl Constraint {
+    /// Create a constraint
+    pub fn new(requirement: &str, origin: ConstraintOrigin) -> Self {
+        Self {
+            requirement: requirement.to_string(),
+            origin,
+        }
+    }
+}
+
+/// E.g. `nginx 1.25.0 requires ^1.3 (via manifest › nginx)`
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let origin = &self.origin;
+        write!(f, "{}", origin.name())?;
+        if let Some(ref version) = origin.version {
+            write!(f, " {version}")?;
+        }
+        write!(f, " requires {}", self.requirement)?;
+        if origin.package.is_some() {
+            write!(f, " (via manifest › {})", origin.path.join(" › "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Find the newest available version satisfying constraints of known origin
+///
+/// A conflict error names every constraint with the chain that introduced
+/// it, the available versions and which constraints could be relaxed to
+/// resolve the conflict.
+pub fn resolve_constraints(
+    package: &str,
+    constraints: &[Constraint],
+    available_versions: &[String],
+) -> Result<String, ResolverError> {
+    let requirements: Vec<String> = constraints.iter().map(|c| c.requirement.clone()).collect();
+    if let Some(version) = find_compatible_version(available_versions, &requirements)? {
+        return Ok(version);
+    }
+
+    let described: Vec<String> = constraints.iter().map(ToString::to_string).collect();
+    Err(ResolverError::Conflict {
+        message: format!(
+            "{package}: {}. Available versions: [{}]. {}",
+            described.join(", "),
+            available_versions.join(", "),
+            relax_hint(package, constraints, available_versions)?
+        ),
+    })
+}
+
+/// Hint naming the constraints whose removal alone leaves a version
+fn relax_hint(
+    package: &str,
+    constraints: &[Constraint],
+    available_versions: &[String],
+) -> Result<String, ResolverError> {
+    let mut relaxable = Vec::new();
+    for (index, constraint) in constraints.iter().enumerate() {
+        if constraint.requirement == "*" {
+            continue;
+        }
+        let others: Vec<String> = constraints
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != index)
+            .map(|(_, c)| c.requirement.clone())
+            .collect();
+        if let Some(version) = find_compatible_version(available_versions, &others)? {
+            relaxable.push(format!(
+                "{} of {} ({package} {version} satisfies the rest)",
+                constraint.requirement,
+                constraint.origin.name()
+            ));
+        }
+    }
+    Ok(if relaxable.is_empty() {
+        format!("Hint: no available version of {package} satisfies even all but one of these")
+    } else {
+        format!("Hint: relax {}", relaxable.join(" or "))
+    })
+}
+
+/// Detect declared conflicts between selected packages
+///
+/// `declared` maps every selected package to the conflicts it declares. A
+/// conflict declared by either side is enough to fail resolution.
+pub fn detect_package_conflicts(
+    declared: &HashMap<String, Vec<ConflictSpec>>,
+) -> Result<(), ResolverError> {
+    let mut names: Vec<&String> = declared.keys().collect();
+    names.sort();
+
+    for name in names {
+        for spec in &declared[name] {
+            if spec.name() != name && declared.contains_key(spec.name()) {
+                return Err(package_conflict_error(name, spec));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Build the conflict error for `package` declaring `spec`
+pub fn package_conflict_error(package: &str, spec: &ConflictSpec) -> ResolverError {
+    let message = match spec.reason() {
+        Some(reason) => format!(
+            "Package '{package}' conflicts with '{}': {reason}",
+            spec.name()
+        ),
+        None => format!("Package '{package}' conflicts with '{}'", spec.name()),
+    };
+    ResolverError::Conflict { message }
+}
+
 /// Dependency graph for packages
 #[derive(Debug, Default)]
 pub struct DependencyGraph {
@@ -198,6 +364,36 @@ impl DependencyGraph {
         Ok(())
     }
 
+    /// Get all packages a package depends on, directly or transitively
+    ///
+    /// The package itself is not included unless it is part of a cycle.
+    pub fn transitive_dependencies(&self, name: &str) -> HashSet<String> {
+        let mut found = HashSet::new();
+        let mut pending: Vec<&str> = vec![name];
+        while let Some(node) = pending.pop() {
+            for dep in self.edges.get(node).into_iter().flatten() {
+                if found.insert(dep.clone()) {
+                    pending.push(dep);
+                }
+            }
+        }
+        found
+    }
+
+    /// Packages depending on `name`, directly or transitively
+    pub fn reverse_dependencies(&self, name: &str) -> HashSet<String> {
+        let mut found = HashSet::new();
+        let mut pending: Vec<&str> = vec![name];
+        while let Some(node) = pending.pop() {
+            for (dependent, deps) in &self.edges {
+                if deps.iter().any(|dep| dep == node) && found.insert(dependent.clone()) {
+                    pending.push(dependent);
+                }
+            }
+        }
+        found
+    }
+
     /// Check if the graph has any cycles
     pub fn has_cycle(&self) -> bool {
         self.topological_sort().is_err()
@@ -226,6 +422,64 @@ mod tests {
         assert!(lib_pos < app_pos, "lib should be built before app");
     }
 
+    #[test]
+    fn test_transitive_dependencies() {
+        let mut graph = DependencyGraph::new();
+        graph.add_package("app", vec!["lib".to_string(), "tool".to_string()]);
+        graph.add_package("lib", vec!["zlib".to_string()]);
+        graph.add_package("tool", vec![]);
+        graph.add_package("other", vec!["app".to_string()]);
+
+        let deps = graph.transitive_dependencies("app");
+        let mut deps: Vec<_> = deps.into_iter().collect();
+        deps.sort();
+        assert_eq!(deps, vec!["lib", "tool", "zlib"]);
+        assert!(graph.transitive_dependencies("zlib").is_empty());
+    }
+
+    #[test]
+    fn test_reverse_dependencies() {
+        let mut graph = DependencyGraph::new();
+        graph.add_package("app", vec!["lib".to_string()]);
+        graph.add_package("lib", vec!["zlib".to_string()]);
+        graph.add_package("tool", vec![]);
+
+        let mut dependents: Vec<_> = graph.reverse_dependencies("zlib").into_iter().collect();
+        dependents.sort();
+        assert_eq!(dependents, vec!["app", "lib"]);
+        assert!(graph.reverse_dependencies("app").is_empty());
+    }
+
+    #[test]
+    fn test_package_conflict_names_both_packages_and_reason() {
+        let mut declared = HashMap::new();
+        declared.insert(
+            "dropbear".to_string(),
+            vec![ConflictSpec::Detailed {
+                name: "openssh".to_string(),
+                reason: Some("both provide sshd".to_string()),
+            }],
+        );
+        declared.insert("openssh".to_string(), vec![]);
+
+        let err = detect_package_conflicts(&declared).unwrap_err().to_string();
+        assert!(err.contains("dropbear"));
+        assert!(err.contains("openssh"));
+        assert!(err.contains("both provide sshd"));
+    }
+
+    #[test]
+    fn test_package_conflict_ignored_when_other_not_selected() {
+        let mut declared = HashMap::new();
+        declared.insert(
+            "dropbear".to_string(),
+            vec![ConflictSpec::Name("openssh".to_string())],
+        );
+        declared.insert("busybox".to_string(), vec![]);
+
+        assert!(detect_package_conflicts(&declared).is_ok());
+    }
+
     #[test]
     fn test_circular_dependency_detection() {
         let mut graph = DependencyGraph::new();
@@ -664,6 +918,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_constraint_conflict_names_dependency_chains() {
+        // app depends on nginx and dropbear, which disagree about zlib
+        let constraints = [
+            Constraint::new(
+                "^1.3",
+                ConstraintOrigin::dependency(
+                    "nginx",
+                    Some("1.25.0"),
+                    vec!["app".to_string(), "nginx".to_string()],
+                ),
+            ),
+            Constraint::new(
+                "<1.3",
+                ConstraintOrigin::dependency(
+                    "dropbear",
+                    Some("2022.83.0"),
+                    vec!["app".to_string(), "dropbear".to_string()],
+                ),
+            ),
+        ];
+        let available = vec!["1.2.13".to_string(), "1.3.1".to_string()];
+
+        let message = resolve_constraints("zlib", &constraints, &available)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            message,
+            "Dependency conflict: zlib: \
+             nginx 1.25.0 requires ^1.3 (via manifest › app › nginx), \
+             dropbear 2022.83.0 requires <1.3 (via manifest › app › dropbear). \
+             Available versions: [1.2.13, 1.3.1]. \
+             Hint: relax ^1.3 of nginx (zlib 1.2.13 satisfies the rest) \
+             or <1.3 of dropbear (zlib 1.3.1 satisfies the rest)"
+        );
+
+        let pinned = [
+            Constraint::new("=1.3.1", ConstraintOrigin::manifest()),
+            constraints[1].clone(),
+        ];
+        let message = resolve_constraints("zlib", &pinned, &available)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            message.contains("zlib: zigroot.toml requires =1.3.1, dropbear"),
+            "{message}"
+        );
+        assert_eq!(
+            resolve_constraints("zlib", &constraints[1..], &available).unwrap(),
+            "1.2.13"
+        );
+    }
+
     proptest! {
         #![proptest_config(ProptestConfig::with_cases(100))]
 
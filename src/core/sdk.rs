@@ -8,6 +8,7 @@
 use std::path::{Path, PathBuf};
 
 use crate::core::manifest::Manifest;
+use crate::core::output::OutputLayout;
 use crate::error::ZigrootError;
 
 /// SDK generation options
@@ -64,7 +65,9 @@ pub fn get_sdk_info(project_dir: &Path, manifest: &Manifest) -> SdkInfo {
     } else {
         manifest.project.name.clone()
     };
-    let default_output = project_dir.join(format!("{project_name}-sdk.tar.gz"));
+    let default_output = OutputLayout::new(project_dir, manifest)
+        .board_dir()
+        .join(format!("{project_name}-sdk.tar.gz"));
 
     SdkInfo {
         target,
@@ -179,6 +182,14 @@ pub fn generate_sdk(
         "SDK tarball placeholder\nComponents: {}\n",
         components.join(", ")
     );
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            ZigrootError::Filesystem(crate::error::FilesystemError::CreateDir {
+                path: parent.to_path_buf(),
+                error: e.to_string(),
+            })
+        })?;
+    }
     std::fs::write(&output_path, tarball_content).map_err(|e| {
         ZigrootError::Filesystem(crate::error::FilesystemError::WriteFile {
             path: output_path.clone(),
@@ -4,8 +4,14 @@
 //!
 //! **Validates: Requirements 10.1-10.9**
 
-use crate::registry::client::{BoardIndexEntry, PackageIndexEntry, RegistryClient};
+use crate::core::lock::LockFile;
+use crate::core::manifest::Manifest;
+use crate::registry::client::{
+    BoardIndex, BoardIndexEntry, PackageIndex, PackageIndexEntry, RegistryClient,
+};
+use std::collections::HashMap;
 use thiserror::Error;
+use tokio::sync::OnceCell;
 
 /// Search errors
 #[derive(Error, Debug)]
@@ -43,6 +49,10 @@ pub struct SearchResult {
     pub keywords: Vec<String>,
     /// Match score (higher is better)
     pub score: u32,
+    /// Whether the package or board is in the current project
+    pub installed: bool,
+    /// Version pinned by the current project
+    pub pinned_version: Option<String>,
 }
 
 /// Search options
@@ -79,6 +89,60 @@ impl SearchResults {
     pub fn total(&self) -> usize {
         self.packages.len() + self.boards.len()
     }
+
+    /// Mark the results that are in the project
+    ///
+    /// With `installed_only`, all other results are dropped.
+    pub fn mark_installed(&mut self, project: &InstalledItems, installed_only: bool) {
+        for result in &mut self.packages {
+            if let Some(version) = project.packages.get(&result.name) {
+                result.installed = true;
+                result.pinned_version.clone_from(version);
+            }
+        }
+        for result in &mut self.boards {
+            result.installed = project.board.as_deref() == Some(result.name.as_str());
+        }
+
+        if installed_only {
+            self.packages.retain(|r| r.installed);
+            self.boards.retain(|r| r.installed);
+            self.suggestions.clear();
+        }
+    }
+}
+
+/// Packages and board of a project
+#[derive(Debug, Clone, Default)]
+pub struct InstalledItems {
+    /// Package names with their pinned version
+    pub packages: HashMap<String, Option<String>>,
+    /// Board name
+    pub board: Option<String>,
+}
+
+impl InstalledItems {
+    /// Collect the packages and board of a project
+    ///
+    /// The pinned version is the locked one, or the manifest's version
+    /// constraint for packages that are not locked yet.
+    pub fn from_project(manifest: &Manifest, lock_file: Option<&LockFile>) -> Self {
+        let packages = manifest
+            .packages
+            .iter()
+            .map(|(name, package)| {
+                let version = lock_file
+                    .and_then(|lock| lock.get_package(name))
+                    .map(|locked| locked.version.clone())
+                    .or_else(|| package.version.clone());
+                (name.clone(), version)
+            })
+            .collect();
+        Self {
+            packages,
+            board: manifest.board.name.clone(),
+        }
+    }
 }
 
 /// Perform a search across packages and boards
@@ -99,49 +163,45 @@ pub async fn search(
             .map_err(|e| SearchError::RegistryError(e.to_string()))?;
     }
 
+    let indexes = Indexes::new(client);
+
     // Search packages unless boards_only is set
     if !options.boards_only {
-        match client.fetch_package_index().await {
-            Ok(index) => {
-                for pkg in &index.packages {
-                    if let Some(score) = calculate_match_score(&query_lower, pkg) {
-                        packages.push(SearchResult {
-                            result_type: SearchResultType::Package,
-                            name: pkg.name.clone(),
-                            version_or_arch: pkg.latest.clone(),
-                            description: pkg.description.clone(),
-                            keywords: pkg.keywords.clone(),
-                            score,
-                        });
-                    }
+        if let Some(index) = indexes.packages().await {
+            for pkg in &index.packages {
+                if let Some(score) = calculate_match_score(&query_lower, pkg) {
+                    packages.push(SearchResult {
+                        result_type: SearchResultType::Package,
+                        name: pkg.name.clone(),
+                        version_or_arch: pkg.latest.clone(),
+                        description: pkg.description.clone(),
+                        keywords: pkg.keywords.clone(),
+                        score,
+                        installed: false,
+                        pinned_version: None,
+                    });
                 }
             }
-            Err(e) => {
-                tracing::warn!("Failed to fetch package index: {}", e);
-            }
         }
     }
 
     // Search boards unless packages_only is set
     if !options.packages_only {
-        match client.fetch_board_index().await {
-            Ok(index) => {
-                for board in &index.boards {
-                    if let Some(score) = calculate_board_match_score(&query_lower, board) {
-                        boards.push(SearchResult {
-                            result_type: SearchResultType::Board,
-                            name: board.name.clone(),
-                            version_or_arch: board.arch.clone(),
-                            description: board.description.clone(),
-                            keywords: board.keywords.clone(),
-                            score,
-                        });
-                    }
+        if let Some(index) = indexes.boards().await {
+            for board in &index.boards {
+                if let Some(score) = calculate_board_match_score(&query_lower, board) {
+                    boards.push(SearchResult {
+                        result_type: SearchResultType::Board,
+                        name: board.name.clone(),
+                        version_or_arch: board.arch.clone(),
+                        description: board.description.clone(),
+                        keywords: board.keywords.clone(),
+                        score,
+                        installed: false,
+                        pinned_version: None,
+                    });
                 }
             }
-            Err(e) => {
-                tracing::warn!("Failed to fetch board index: {}", e);
-            }
         }
     }
 
@@ -151,7 +211,7 @@ pub async fn search(
 
     // Generate suggestions if no results
     let suggestions = if packages.is_empty() && boards.is_empty() {
-        generate_suggestions(&query_lower, client).await
+        generate_suggestions(&query_lower, &indexes).await
     } else {
         Vec::new()
     };
@@ -164,6 +224,51 @@ pub async fn search(
     })
 }
 
+/// Registry indexes of one search, each fetched at most once
+struct Indexes<'a> {
+    client: &'a RegistryClient,
+    /// Package index, `None` if fetching it failed
+    packages: OnceCell<Option<PackageIndex>>,
+    /// Board index, `None` if fetching it failed
+    boards: OnceCell<Option<BoardIndex>>,
+}
+
+impl<'a> Indexes<'a> {
+    fn new(client: &'a RegistryClient) -> Self {
+        Self {
+            client,
+            packages: OnceCell::new(),
+            boards: OnceCell::new(),
+        }
+    }
+
+    async fn packages(&self) -> Option<&PackageIndex> {
+        self.packages
+            .get_or_init(|| async {
+                self.client
+                    .fetch_package_index()
+                    .await
+                    .map_err(|e| tracing::warn!("Failed to fetch package index: {}", e))
+                    .ok()
+            })
+            .await
+            .as_ref()
+    }
+
+    async fn boards(&self) -> Option<&BoardIndex> {
+        self.boards
+            .get_or_init(|| async {
+                self.client
+                    .fetch_board_index()
+                    .await
+                    .map_err(|e| tracing::warn!("Failed to fetch board index: {}", e))
+                    .ok()
+            })
+            .await
+            .as_ref()
+    }
+}
+
 /// Calculate match score for a package
 fn calculate_match_score(query: &str, pkg: &PackageIndexEntry) -> Option<u32> {
     let name_lower = pkg.name.to_lowercase();
@@ -255,11 +360,11 @@ fn calculate_board_match_score(query: &str, board: &BoardIndexEntry) -> Option<u
 }
 
 /// Generate suggestions when no results found
-async fn generate_suggestions(query: &str, client: &RegistryClient) -> Vec<String> {
+async fn generate_suggestions(query: &str, indexes: &Indexes<'_>) -> Vec<String> {
     let mut suggestions = Vec::new();
 
     // Try to find similar package names
-    if let Ok(index) = client.fetch_package_index().await {
+    if let Some(index) = indexes.packages().await {
         for pkg in &index.packages {
             let name_lower = pkg.name.to_lowercase();
             // Check for partial matches or similar names
@@ -270,7 +375,7 @@ async fn generate_suggestions(query: &str, client: &RegistryClient) -> Vec<Strin
     }
 
     // Try to find similar board names
-    if let Ok(index) = client.fetch_board_index().await {
+    if let Some(index) = indexes.boards().await {
         for board in &index.boards {
             let name_lower = board.name.to_lowercase();
             if levenshtein_distance(query, &name_lower) <= 3 {
@@ -292,6 +397,46 @@ async fn generate_suggestions(query: &str, client: &RegistryClient) -> Vec<Strin
     suggestions
 }
 
+/// Suggestions offered for an unknown package name
+pub const MAX_SUGGESTIONS: usize = 3;
+
+/// Names among `candidates` that are likely typos of `name`, closest first
+///
+/// A candidate matches within an edit distance of a third of `name`'s
+/// length, between 1 and 3, ignoring case. At most `max` names are returned.
+pub fn closest_matches<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+    max: usize,
+) -> Vec<String> {
+    let name = name.to_lowercase();
+    let limit = (name.chars().count() / 3).clamp(1, 3);
+    let mut matches: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let distance = levenshtein_distance(&name, &candidate.to_lowercase());
+            (distance > 0 && distance <= limit).then_some((distance, candidate))
+        })
+        .collect();
+    matches.sort_unstable();
+    matches.dedup();
+    matches
+        .into_iter()
+        .take(max)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+/// "; did you mean 'a' or 'b'?" for error messages, empty without matches
+pub fn did_you_mean(matches: &[String]) -> String {
+    let quoted: Vec<String> = matches.iter().map(|m| format!("'{m}'")).collect();
+    match quoted.split_last() {
+        None => String::new(),
+        Some((last, [])) => format!("; did you mean {last}?"),
+        Some((last, rest)) => format!("; did you mean {} or {last}?", rest.join(", ")),
+    }
+}
+
 /// Calculate Levenshtein distance between two strings
 fn levenshtein_distance(s1: &str, s2: &str) -> usize {
     let len1 = s1.chars().count();
@@ -335,6 +480,7 @@ fn levenshtein_distance(s1: &str, s2: &str) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::lock::LockedPackageBuilder;
 
     #[test]
     fn test_levenshtein_distance() {
@@ -346,6 +492,33 @@ mod tests {
         assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
     }
 
+    #[test]
+    fn test_closest_matches() {
+        let candidates = ["busybox", "busybox-extras", "dropbear", "zlib", "BusyBox2"];
+        assert_eq!(
+            closest_matches("busyboxx", candidates, 3),
+            vec!["BusyBox2", "busybox"]
+        );
+        assert_eq!(closest_matches("busyboxx", candidates, 1), vec!["BusyBox2"]);
+        assert_eq!(closest_matches("zlb", candidates, 3), vec!["zlib"]);
+        // Exact names and distant ones are no suggestions
+        assert!(closest_matches("zlib", candidates, 3).is_empty());
+        assert!(closest_matches("openssl", candidates, 3).is_empty());
+    }
+
+    #[test]
+    fn test_did_you_mean() {
+        assert_eq!(did_you_mean(&[]), "");
+        assert_eq!(
+            did_you_mean(&["busybox".to_string()]),
+            "; did you mean 'busybox'?"
+        );
+        assert_eq!(
+            did_you_mean(&["a".to_string(), "b".to_string(), "c".to_string()]),
+            "; did you mean 'a', 'b' or 'c'?"
+        );
+    }
+
     #[test]
     fn test_calculate_match_score_exact() {
         let pkg = PackageIndexEntry {
@@ -408,4 +581,113 @@ mod tests {
         assert!(score.is_some());
         assert!(score.unwrap() >= 40);
     }
+
+    fn result(name: &str, result_type: SearchResultType) -> SearchResult {
+        SearchResult {
+            result_type,
+            name: name.to_string(),
+            version_or_arch: String::new(),
+            description: String::new(),
+            keywords: vec![],
+            score: 100,
+            installed: false,
+            pinned_version: None,
+        }
+    }
+
+    #[test]
+    fn test_mark_installed() {
+        let manifest = Manifest::from_toml(
+            r#"
+[project]
+name = "demo"
+
+[board]
+name = "rpi4"
+
+[packages.busybox]
+version = "1.36.0"
+
+[packages.zlib]
+"#,
+        )
+        .unwrap();
+        let mut lock_file = LockFile::new("0.1.0", "0.13.0");
+        lock_file.add_package(LockedPackageBuilder::new("zlib", "1.3.1", "").build());
+        let installed = InstalledItems::from_project(&manifest, Some(&lock_file));
+
+        let mut results = SearchResults {
+            packages: vec![
+                result("busybox", SearchResultType::Package),
+                result("zlib", SearchResultType::Package),
+                result("dropbear", SearchResultType::Package),
+            ],
+            boards: vec![
+                result("rpi4", SearchResultType::Board),
+                result("rpi3", SearchResultType::Board),
+            ],
+            query: String::new(),
+            suggestions: vec![],
+        };
+        results.mark_installed(&installed, false);
+        assert_eq!(results.total(), 5);
+        assert_eq!(
+            results.packages[0].pinned_version.as_deref(),
+            Some("1.36.0")
+        );
+        assert_eq!(results.packages[1].pinned_version.as_deref(), Some("1.3.1"));
+        assert!(!results.packages[2].installed);
+        assert!(results.boards[0].installed);
+
+        results.mark_installed(&installed, true);
+        let names: Vec<_> = results
+            .packages
+            .iter()
+            .chain(&results.boards)
+            .map(|r| r.name.as_str())
+            .collect();
+        assert_eq!(names, ["busybox", "zlib", "rpi4"]);
+    }
+
+    #[tokio::test]
+    async fn test_search_fetches_each_index_once() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/packages/index.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"version": 1, "updated": "", "packages": [
+                    {"name": "busybox", "description": "", "versions": [], "latest": "1.36.1"}
+                ]}"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/boards/index.json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"version": 1, "updated": "", "boards": []}"#),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // Without a cache TTL every fetch would go to the network, and the
+        // suggestions for "busybx" need both indexes again
+        let temp = tempfile::TempDir::new().unwrap();
+        let client = RegistryClient::with_config(
+            format!("{}/packages", server.uri()),
+            format!("{}/boards", server.uri()),
+            temp.path().to_path_buf(),
+            0,
+        );
+        let results = search(&client, "busybx", &SearchOptions::default())
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+        assert_eq!(results.suggestions, ["Did you mean 'busybox'?"]);
+    }
 }
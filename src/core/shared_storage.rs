@@ -6,8 +6,10 @@
 //! **Validates: Requirements 32.9, 32.10**
 
 use crate::infra::dirs::ZigrootDirs;
+use crate::infra::download::Checksum;
+use crate::infra::flock::{FileLock, FlockError};
 use sha2::{Digest, Sha256};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Shared storage manager for downloads and build cache
 ///
@@ -79,6 +81,55 @@ impl SharedStorage {
         self.download_path(package, version, sha256).exists()
     }
 
+    /// Get the path for a downloaded external artifact
+    ///
+    /// The path is structured as:
+    /// `<downloads_dir>/external/<algorithm>/<digest>/<filename>`
+    ///
+    /// Artifacts are addressed by their checksum, so projects declaring the
+    /// same file share one download whatever they name it.
+    ///
+    /// # Arguments
+    ///
+    /// * `checksum` - Declared checksum of the artifact
+    /// * `filename` - File name of the artifact
+    ///
+    /// # Returns
+    ///
+    /// Path to the downloaded artifact
+    #[must_use]
+    pub fn external_path(&self, checksum: &Checksum, filename: &str) -> PathBuf {
+        self.downloads_dir
+            .join("external")
+            .join(checksum.algorithm.name())
+            .join(&checksum.digest)
+            .join(filename)
+    }
+
+    /// Get the path for a checkout of a git repository at a commit
+    ///
+    /// The path is structured as:
+    /// `<downloads_dir>/git/<url_hash>/<commit>`
+    ///
+    /// A commit is cloned once and reused by every project that pins it.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - Repository URL
+    /// * `commit` - Full commit SHA
+    ///
+    /// # Returns
+    ///
+    /// Path to the checkout
+    #[must_use]
+    pub fn git_path(&self, url: &str, commit: &str) -> PathBuf {
+        let url_hash = hex::encode(Sha256::digest(url.trim_end_matches('/').as_bytes()));
+        self.downloads_dir
+            .join("git")
+            .join(&url_hash[..16])
+            .join(commit)
+    }
+
     /// Get the path for a cached build artifact
     ///
     /// The path is structured as:
@@ -160,6 +211,28 @@ impl SharedStorage {
         hex::encode(hasher.finalize())
     }
 
+    /// Lock a shared storage entry while it is written
+    ///
+    /// The lock is `<entry>.lock`, so projects can fill different entries at
+    /// once, while a second writer of the same entry waits for the first and
+    /// then finds it in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - Path of the entry, e.g. from [`Self::external_path`]
+    pub async fn lock_entry(&self, entry: &Path) -> Result<FileLock, FlockError> {
+        let mut name = entry.file_name().unwrap_or_default().to_os_string();
+        name.push(".lock");
+        let path = entry.with_file_name(name);
+        let lock_path = path.clone();
+        tokio::task::spawn_blocking(move || FileLock::acquire_blocking(&lock_path))
+            .await
+            .map_err(|e| FlockError::Io {
+                path,
+                error: e.to_string(),
+            })?
+    }
+
     /// Get the downloads directory
     #[must_use]
     pub fn downloads_dir(&self) -> &PathBuf {
@@ -176,6 +249,7 @@ impl SharedStorage {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::infra::download::ChecksumAlgorithm;
     use tempfile::TempDir;
 
     fn create_test_storage() -> (SharedStorage, TempDir) {
@@ -200,6 +274,61 @@ mod tests {
         assert!(path_str.contains("abc123de")); // First 8 chars of sha
     }
 
+    #[test]
+    fn test_external_path_is_content_addressed() {
+        let (storage, temp) = create_test_storage();
+        let checksum = Checksum::compute(ChecksumAlgorithm::Blake3, b"u-boot");
+        let path = storage.external_path(&checksum, "u-boot.bin");
+
+        assert!(path.starts_with(temp.path().join("downloads/external/blake3")));
+        assert!(path.ends_with(format!("{}/u-boot.bin", checksum.digest)));
+        let other = Checksum::compute(ChecksumAlgorithm::Blake3, b"barebox");
+        assert_ne!(storage.external_path(&other, "u-boot.bin"), path);
+    }
+
+    #[test]
+    fn test_git_path_is_keyed_by_repository_and_commit() {
+        let (storage, temp) = create_test_storage();
+        let commit = "a".repeat(40);
+        let path = storage.git_path("https://example.com/repo.git", &commit);
+
+        assert!(path.starts_with(temp.path().join("downloads/git")));
+        assert!(path.ends_with(&commit));
+        assert_eq!(
+            storage.git_path("https://example.com/repo.git/", &commit),
+            path
+        );
+        assert_ne!(
+            storage.git_path("https://example.com/fork.git", &commit),
+            path
+        );
+        assert_ne!(
+            storage.git_path("https://example.com/repo.git", &"b".repeat(40)),
+            path
+        );
+    }
+
+    #[tokio::test]
+    async fn test_entry_locks_are_per_entry() {
+        let (storage, _temp) = create_test_storage();
+        let uboot = storage.external_path(
+            &Checksum::compute(ChecksumAlgorithm::Blake3, b"u-boot"),
+            "u-boot.bin",
+        );
+        let barebox = storage.external_path(
+            &Checksum::compute(ChecksumAlgorithm::Blake3, b"barebox"),
+            "barebox.bin",
+        );
+
+        let lock = storage.lock_entry(&uboot).await.unwrap();
+        assert!(lock.path().ends_with("u-boot.bin.lock"));
+        // Another entry is free, the same one is held
+        assert!(storage.lock_entry(&barebox).await.is_ok());
+        assert!(FileLock::try_acquire(lock.path()).unwrap().is_err());
+        drop(lock);
+        assert!(storage.lock_entry(&uboot).await.is_ok());
+    }
+
     #[test]
     fn test_cache_path_structure() {
         let (storage, _temp) = create_test_storage();
@@ -0,0 +1,301 @@
+//! Image size accounting and budget enforcement
+//!
+//! Tracks how many bytes each package contributes to the rootfs and checks
+//! the final image against `build.size_budget`. The report is written to the
+//! output directory so `zigroot build --analyze-size` can show it later.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use thiserror::Error;
+
+/// Size report file name in the output directory
+pub const SIZE_REPORT_FILE: &str = "size-report.json";
+
+/// Number of packages listed when the budget is exceeded
+const TOP_PACKAGES: usize = 10;
+
+/// `build.rootfs_size` value sizing the image to its content
+pub const AUTO_SIZE: &str = "auto";
+
+/// Space reserved for filesystem metadata and the journal
+const FILESYSTEM_OVERHEAD: u64 = 4 << 20;
+
+/// Share of the content added for inode tables and block bitmaps, in percent
+const FILESYSTEM_OVERHEAD_PERCENT: u64 = 10;
+
+/// Size accounting errors
+#[derive(Error, Debug)]
+pub enum SizeError {
+    /// Size string could not be parsed
+    #[error("Invalid size '{value}': expected a number with optional K, M or G suffix")]
+    InvalidSize { value: String },
+
+    /// Image exceeds the configured budget
+    #[error(
+        "Image size {} exceeds budget of {} by {}\nLargest packages:\n{top}",
+        format_bytes(*size),
+        format_bytes(*budget),
+        format_bytes(size - budget)
+    )]
+    OverBudget { size: u64, budget: u64, top: String },
+
+    /// Fixed rootfs size cannot hold the assembled rootfs
+    #[error(
+        "build.rootfs_size of {} is too small for {} of rootfs content. \
+         Set rootfs_size to at least {} or to \"auto\".",
+        format_bytes(*size),
+        format_bytes(*content),
+        format_mib(*minimum)
+    )]
+    RootfsTooSmall {
+        size: u64,
+        content: u64,
+        minimum: u64,
+    },
+}
+
+/// Installed size contribution of a single package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageSize {
+    /// Package name
+    pub name: String,
+    /// Bytes installed into the staging directory
+    pub bytes: u64,
+}
+
+/// Per-package size report of a build
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeReport {
+    /// Packages sorted by size, largest first
+    pub packages: Vec<PackageSize>,
+    /// Total installed bytes of all packages
+    pub total: u64,
+    /// Size of the final image in bytes
+    pub image: u64,
+    /// Configured budget in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<u64>,
+}
+
+impl SizeReport {
+    /// Build a report from package sizes, sorting largest first
+    pub fn new(mut packages: Vec<PackageSize>, image: u64, budget: Option<u64>) -> Self {
+        packages.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+        let total = packages.iter().map(|p| p.bytes).sum();
+        Self {
+            packages,
+            total,
+            image,
+            budget,
+        }
+    }
+
+    /// Size checked against the budget
+    ///
+    /// The installed payload is a lower bound on the image size, so the
+    /// larger of the two is used.
+    pub fn effective_size(&self) -> u64 {
+        self.image.max(self.total)
+    }
+
+    /// Fail if the image exceeds the budget
+    pub fn check_budget(&self) -> Result<(), SizeError> {
+        let Some(budget) = self.budget else {
+            return Ok(());
+        };
+        let size = self.effective_size();
+        if size <= budget {
+            return Ok(());
+        }
+
+        let top = self
+            .packages
+            .iter()
+            .take(TOP_PACKAGES)
+            .map(|p| format!("  {:>10}  {}", format_bytes(p.bytes), p.name))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Err(SizeError::OverBudget { size, budget, top })
+    }
+
+    /// Write the report to the output directory
+    pub fn save(&self, output_dir: &Path) -> std::io::Result<()> {
+        let content = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(output_dir.join(SIZE_REPORT_FILE), content)
+    }
+
+    /// Load the report from the output directory
+    pub fn load(output_dir: &Path) -> std::io::Result<Self> {
+        let content = std::fs::read_to_string(output_dir.join(SIZE_REPORT_FILE))?;
+        serde_json::from_str(&content)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Parse a size string such as `64M`, `512K`, `1G` or `1048576`
+///
+/// Suffixes are binary multiples; an optional trailing `B` or `iB` is accepted.
+pub fn parse_size(value: &str) -> Result<u64, SizeError> {
+    let invalid = || SizeError::InvalidSize {
+        value: value.to_string(),
+    };
+
+    let upper = value.trim().to_ascii_uppercase();
+    let trimmed = upper
+        .strip_suffix("IB")
+        .or_else(|| upper.strip_suffix('B'))
+        .unwrap_or(&upper);
+    let (number, multiplier) = match trimmed.chars().last() {
+        Some('K') => (&trimmed[..trimmed.len() - 1], 1u64 << 10),
+        Some('M') => (&trimmed[..trimmed.len() - 1], 1u64 << 20),
+        Some('G') => (&trimmed[..trimmed.len() - 1], 1u64 << 30),
+        _ => (trimmed, 1),
+    };
+
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(invalid)
+}
+
+/// Smallest filesystem image holding `content` bytes, in whole MiB
+pub fn minimum_image_size(content: u64) -> u64 {
+    let size = content + content * FILESYSTEM_OVERHEAD_PERCENT / 100 + FILESYSTEM_OVERHEAD;
+    size.div_ceil(1 << 20) << 20
+}
+
+/// Size of the rootfs image for `build.rootfs_size` and the content size
+///
+/// `auto` adds `slack` percent of free space to the minimum; a fixed size
+/// must not be below the minimum.
+pub fn rootfs_image_size(setting: &str, content: u64, slack: u32) -> Result<u64, SizeError> {
+    let minimum = minimum_image_size(content);
+    if setting == AUTO_SIZE {
+        let size = minimum + minimum * u64::from(slack) / 100;
+        return Ok(size.div_ceil(1 << 20) << 20);
+    }
+    let size = parse_size(setting)?;
+    if size < minimum {
+        return Err(SizeError::RootfsTooSmall {
+            size,
+            content,
+            minimum,
+        });
+    }
+    Ok(size)
+}
+
+/// Total size of all files under a directory
+///
+/// Returns 0 if the directory does not exist.
+pub fn tree_size(dir: &Path) -> std::io::Result<u64> {
+    if !dir.is_dir() {
+        return Ok(0);
+    }
+    let mut total = 0;
+    for entry in walkdir::WalkDir::new(dir) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            total += entry.metadata()?.len();
+        }
+    }
+    Ok(total)
+}
+
+/// Total size of all files under a package's staging directory
+///
+/// Returns 0 if the package installed nothing.
+pub fn staged_size(staging_root: &Path, package: &str) -> std::io::Result<u64> {
+    tree_size(&staging_root.join(package))
+}
+
+/// Format a byte count as whole MiB in `rootfs_size` notation, e.g. "72M"
+fn format_mib(bytes: u64) -> String {
+    format!("{}M", bytes.div_ceil(1 << 20))
+}
+
+/// Format a byte count with a binary unit suffix
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [(&str, u64); 3] = [("GB", 1 << 30), ("MB", 1 << 20), ("KB", 1 << 10)];
+
+    for (unit, scale) in UNITS {
+        if bytes >= scale {
+            let tenths = bytes * 10 / scale;
+            return format!("{}.{} {unit}", tenths / 10, tenths % 10);
+        }
+    }
+    format!("{bytes} B")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(name: &str, bytes: u64) -> PackageSize {
+        PackageSize {
+            name: name.to_string(),
+            bytes,
+        }
+    }
+
+    #[test]
+    fn test_parse_size_suffixes() {
+        assert_eq!(parse_size("64M").unwrap(), 64 * 1024 * 1024);
+        assert_eq!(parse_size("512k").unwrap(), 512 * 1024);
+        assert_eq!(parse_size("1G").unwrap(), 1024 * 1024 * 1024);
+        assert_eq!(parse_size("2MiB").unwrap(), 2 * 1024 * 1024);
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert!(parse_size("big").is_err());
+        assert!(parse_size("M").is_err());
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(64 * 1024 * 1024), "64.0 MB");
+    }
+
+    #[test]
+    fn test_report_sorted_largest_first() {
+        let report = SizeReport::new(vec![size("a", 10), size("b", 30), size("c", 20)], 0, None);
+        let names: Vec<_> = report.packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "c", "a"]);
+        assert_eq!(report.total, 60);
+    }
+
+    #[test]
+    fn test_check_budget_reports_overage_and_top_packages() {
+        let packages = (0..12)
+            .map(|i| size(&format!("pkg{i:02}"), 100 + i))
+            .collect();
+        let report = SizeReport::new(packages, 0, Some(1000));
+        let message = report.check_budget().unwrap_err().to_string();
+        assert!(message.contains("exceeds budget"));
+        assert!(message.contains("pkg11"));
+        assert!(!message.contains("pkg01"));
+
+        let report = SizeReport::new(vec![size("small", 10)], 50, Some(100));
+        assert!(report.check_budget().is_ok());
+    }
+
+    #[test]
+    fn test_rootfs_image_size_checks_fixed_and_sizes_auto() {
+        let content = 100 << 20;
+        let minimum = minimum_image_size(content);
+        assert_eq!(minimum, 114 << 20);
+
+        assert_eq!(rootfs_image_size("256M", content, 20).unwrap(), 256 << 20);
+        let message = rootfs_image_size("64M", content, 20)
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("too small"));
+        assert!(message.contains("at least 114M"));
+
+        let auto = rootfs_image_size(AUTO_SIZE, content, 20).unwrap();
+        assert_eq!(auto, 137 << 20);
+        assert_eq!(rootfs_image_size(AUTO_SIZE, content, 0).unwrap(), minimum);
+    }
+}
@@ -0,0 +1,314 @@
+//! Local build statistics
+//!
+//! Every build appends one JSON line to [`STATS_FILE`] in the data
+//! directory, unless `build.stats = false` is set in the global config.
+//! Nothing leaves the machine; the project is only recorded as a hash of its
+//! name. `zigroot stats` aggregates the records.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::core::report::BuildReport;
+
+/// File in the data directory holding the records
+pub const STATS_FILE: &str = "stats.jsonl";
+
+/// Number of slowest packages in the aggregates
+const SLOWEST_PACKAGES: usize = 5;
+
+/// Duration of one package in a build
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageDuration {
+    /// Package name
+    pub name: String,
+    /// Seconds spent on the package
+    pub duration_secs: f64,
+    /// Whether it was rebuilt rather than up to date
+    pub rebuilt: bool,
+}
+
+/// One build
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildRecord {
+    /// When the build finished (seconds since the epoch)
+    pub timestamp: u64,
+    /// Hash of the project name
+    pub project: String,
+    /// Board of the project
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub board: Option<String>,
+    /// Number of packages in the build
+    pub packages: usize,
+    /// Share of the packages that were up to date
+    pub cache_hit_ratio: f64,
+    /// Per-package durations
+    pub package_durations: Vec<PackageDuration>,
+    /// Total build time in seconds
+    pub duration_secs: f64,
+    /// Rootfs image size in bytes
+    pub image_size: u64,
+    /// Whether the build succeeded
+    pub success: bool,
+}
+
+impl BuildRecord {
+    /// Record of a finished build
+    pub fn from_report(report: &BuildReport, board: Option<&str>) -> Self {
+        let hits = report.packages.iter().filter(|p| !p.rebuilt).count();
+        let cache_hit_ratio = if report.packages.is_empty() {
+            0.0
+        } else {
+            ratio(hits, report.packages.len())
+        };
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            project: project_hash(&report.project),
+            board: board.map(str::to_string),
+            packages: report.packages.len(),
+            cache_hit_ratio,
+            package_durations: report
+                .packages
+                .iter()
+                .map(|p| PackageDuration {
+                    name: p.name.clone(),
+                    duration_secs: p.duration_secs,
+                    rebuilt: p.rebuilt,
+                })
+                .collect(),
+            duration_secs: report.duration_secs,
+            image_size: report.image_size,
+            success: report.success,
+        }
+    }
+}
+
+/// Hash identifying a project by name
+pub fn project_hash(name: &str) -> String {
+    hex::encode(&Sha256::digest(name.as_bytes())[..8])
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn ratio(part: usize, total: usize) -> f64 {
+    part as f64 / total as f64
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn average(total: f64, count: usize) -> f64 {
+    total / count as f64
+}
+
+/// Append a record to the statistics file
+pub fn append(path: &Path, record: &BuildRecord) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let line = serde_json::to_string(record).map_err(std::io::Error::other)?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{line}")
+}
+
+/// Records of the statistics file, with a warning per corrupt line
+///
+/// A missing file has no records.
+pub fn load(path: &Path) -> std::io::Result<(Vec<BuildRecord>, Vec<String>)> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Default::default()),
+        Err(e) => return Err(e),
+    };
+    let mut records = Vec::new();
+    let mut warnings = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(record) => records.push(record),
+            Err(e) => warnings.push(format!(
+                "Skipping corrupt line {} of {}: {e}",
+                index + 1,
+                path.display()
+            )),
+        }
+    }
+    Ok((records, warnings))
+}
+
+/// Remove the statistics file; returns whether it existed
+pub fn clear(path: &Path) -> std::io::Result<bool> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Average rebuild time of a package
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SlowPackage {
+    /// Package name
+    pub name: String,
+    /// Average seconds of its rebuilds
+    pub average_secs: f64,
+    /// Number of rebuilds
+    pub rebuilds: usize,
+}
+
+/// Image size after a build
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImageSize {
+    /// When the build finished
+    pub timestamp: u64,
+    /// Image size in bytes
+    pub bytes: u64,
+}
+
+/// Aggregates over build records
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Summary {
+    /// Number of builds
+    pub builds: usize,
+    /// Number of failed builds
+    pub failures: usize,
+    /// Average duration of successful builds
+    pub average_secs: Option<f64>,
+    /// Cache hit ratio of the most recent builds, oldest first
+    pub cache_hit_trend: Vec<f64>,
+    /// Packages with the longest average rebuild time
+    pub slowest_packages: Vec<SlowPackage>,
+    /// Image sizes of the most recent successful builds, oldest first
+    pub image_sizes: Vec<ImageSize>,
+}
+
+impl Summary {
+    /// Aggregate `records` (oldest first), with trends over the `last` builds
+    pub fn new(records: &[BuildRecord], last: usize) -> Self {
+        let successes: Vec<&BuildRecord> = records.iter().filter(|r| r.success).collect();
+        let average_secs = (!successes.is_empty()).then(|| {
+            average(
+                successes.iter().map(|r| r.duration_secs).sum(),
+                successes.len(),
+            )
+        });
+        let recent = &records[records.len().saturating_sub(last)..];
+
+        let mut rebuilds: HashMap<&str, (f64, usize)> = HashMap::new();
+        for package in records.iter().flat_map(|r| &r.package_durations) {
+            if package.rebuilt {
+                let entry = rebuilds.entry(&package.name).or_default();
+                entry.0 += package.duration_secs;
+                entry.1 += 1;
+            }
+        }
+        let mut slowest_packages: Vec<SlowPackage> = rebuilds
+            .into_iter()
+            .map(|(name, (total, count))| SlowPackage {
+                name: name.to_string(),
+                average_secs: average(total, count),
+                rebuilds: count,
+            })
+            .collect();
+        slowest_packages.sort_by(|a, b| {
+            b.average_secs
+                .total_cmp(&a.average_secs)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        slowest_packages.truncate(SLOWEST_PACKAGES);
+
+        Self {
+            builds: records.len(),
+            failures: records.len() - successes.len(),
+            average_secs,
+            cache_hit_trend: recent.iter().map(|r| r.cache_hit_ratio).collect(),
+            slowest_packages,
+            image_sizes: recent
+                .iter()
+                .filter(|r| r.success)
+                .map(|r| ImageSize {
+                    timestamp: r.timestamp,
+                    bytes: r.image_size,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::report::PackageReport;
+    use tempfile::TempDir;
+
+    fn record(success: bool, duration_secs: f64, packages: &[(&str, f64, bool)]) -> BuildRecord {
+        let mut report = BuildReport::new();
+        report.project = "demo".to_string();
+        report.success = success;
+        report.duration_secs = duration_secs;
+        report.image_size = 1000;
+        report.packages = packages
+            .iter()
+            .map(|(name, secs, rebuilt)| PackageReport {
+                name: (*name).to_string(),
+                version: "1.0.0".to_string(),
+                source: None,
+                rebuilt: *rebuilt,
+                duration_secs: *secs,
+            })
+            .collect();
+        BuildRecord::from_report(&report, Some("rpi4"))
+    }
+
+    #[test]
+    fn test_records_round_trip_and_skip_corrupt_lines() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("data").join(STATS_FILE);
+        append(&path, &record(true, 10.0, &[("busybox", 8.0, true)])).unwrap();
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        writeln!(file, "{{not json").unwrap();
+        append(&path, &record(false, 2.0, &[])).unwrap();
+
+        let (records, warnings) = load(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].project, project_hash("demo"));
+        assert_eq!(records[0].board.as_deref(), Some("rpi4"));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("line 2"));
+
+        assert!(clear(&path).unwrap());
+        assert!(!clear(&path).unwrap());
+        assert!(load(&path).unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn test_summary_aggregates_recent_builds() {
+        let records = vec![
+            record(true, 10.0, &[("busybox", 6.0, true), ("app", 2.0, true)]),
+            record(false, 1.0, &[("busybox", 0.0, false), ("app", 1.0, true)]),
+            record(true, 4.0, &[("busybox", 0.0, false), ("app", 3.0, true)]),
+        ];
+        let summary = Summary::new(&records, 2);
+        assert_eq!(summary.builds, 3);
+        assert_eq!(summary.failures, 1);
+        assert!(summary
+            .average_secs
+            .is_some_and(|secs| (secs - 7.0).abs() < 1e-9));
+        assert_eq!(summary.cache_hit_trend, vec![0.5, 0.5]);
+        assert_eq!(summary.image_sizes.len(), 1);
+        assert_eq!(summary.slowest_packages[0].name, "busybox");
+        assert!((summary.slowest_packages[1].average_secs - 2.0).abs() < 1e-9);
+        assert_eq!(summary.slowest_packages[1].rebuilds, 3);
+    }
+}
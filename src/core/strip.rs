@@ -0,0 +1,296 @@
+//! Symbol stripping and debug-info splitting
+//!
+//! Strips ELF binaries in a package's staging directory before they are
+//! installed into the rootfs. With `build.split_debug`, debug info is kept
+//! in a parallel tree of `.debug` files linked via `.gnu_debuglink`.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::core::compress::find_elf_binaries;
+
+/// Debug file tree inside the output directory
+pub const DEBUG_DIR: &str = "debug";
+
+/// Strip configuration
+#[derive(Debug, Clone)]
+pub struct StripConfig {
+    /// Global strip setting from manifest
+    pub global_enabled: bool,
+    /// Keep debug info in separate files
+    pub split_debug: bool,
+}
+
+impl StripConfig {
+    /// Check if stripping is enabled for a package
+    pub fn is_enabled_for_package(&self, package_strip: Option<bool>) -> bool {
+        package_strip.unwrap_or(self.global_enabled)
+    }
+}
+
+/// Strip statistics
+#[derive(Debug, Default, Clone)]
+pub struct StripStats {
+    /// Number of files stripped
+    pub files_stripped: usize,
+    /// Number of debug files written
+    pub debug_files: usize,
+    /// Total bytes removed from binaries
+    pub bytes_saved: u64,
+}
+
+impl StripStats {
+    /// Add the statistics of another package
+    pub fn merge(&mut self, other: &StripStats) {
+        self.files_stripped += other.files_stripped;
+        self.debug_files += other.debug_files;
+        self.bytes_saved += other.bytes_saved;
+    }
+}
+
+/// objcopy-compatible tool used for stripping
+#[derive(Debug, Clone)]
+pub struct StripTool {
+    program: PathBuf,
+    prefix_args: Vec<String>,
+}
+
+impl StripTool {
+    /// Find an objcopy for the toolchain
+    ///
+    /// Prefers `<cross_prefix>objcopy` (the GCC cross-toolchain of the
+    /// target triple), then `zig objcopy`, then the host `objcopy`.
+    pub fn detect(cross_prefix: Option<&str>) -> Option<Self> {
+        if let Some(prefix) = cross_prefix {
+            if let Ok(program) = which::which(format!("{prefix}objcopy")) {
+                return Some(Self {
+                    program,
+                    prefix_args: Vec::new(),
+                });
+            }
+        }
+        if let Ok(program) = which::which("zig") {
+            return Some(Self {
+                program,
+                prefix_args: vec!["objcopy".to_string()],
+            });
+        }
+        which::which("objcopy").ok().map(|program| Self {
+            program,
+            prefix_args: Vec::new(),
+        })
+    }
+
+    fn run(&self, args: &[String]) -> Result<()> {
+        let output = Command::new(&self.program)
+            .args(&self.prefix_args)
+            .args(args)
+            .output()
+            .with_context(|| format!("Failed to run {}", self.program.display()))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("objcopy {} failed: {}", args.join(" "), stderr.trim());
+        }
+        Ok(())
+    }
+}
+
+/// Check whether ELF data still carries a symbol table or debug sections
+///
+/// Returns false for data that is not a parseable ELF file.
+pub fn has_symbols(data: &[u8]) -> bool {
+    section_names(data).is_some_and(|names| {
+        names
+            .iter()
+            .any(|name| name == ".symtab" || name.starts_with(".debug_"))
+    })
+}
+
+/// Read the section names of an ELF file
+fn section_names(data: &[u8]) -> Option<Vec<String>> {
+    if data.get(..4)? != b"\x7fELF" {
+        return None;
+    }
+    let is_64 = match data.get(4)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let little_endian = match data.get(5)? {
+        1 => true,
+        2 => false,
+        _ => return None,
+    };
+
+    let read = |offset: usize, size: usize| -> Option<u64> {
+        let bytes = data.get(offset..offset.checked_add(size)?)?;
+        let mut value = 0u64;
+        for i in 0..size {
+            let byte = if little_endian {
+                bytes[size - 1 - i]
+            } else {
+                bytes[i]
+            };
+            value = (value << 8) | u64::from(byte);
+        }
+        Some(value)
+    };
+    let word = if is_64 { 8 } else { 4 };
+
+    let (shoff, shentsize, shnum, shstrndx) = if is_64 {
+        (
+            read(0x28, 8)?,
+            read(0x3A, 2)?,
+            read(0x3C, 2)?,
+            read(0x3E, 2)?,
+        )
+    } else {
+        (
+            read(0x20, 4)?,
+            read(0x2E, 2)?,
+            read(0x30, 2)?,
+            read(0x32, 2)?,
+        )
+    };
+    let shoff = usize::try_from(shoff).ok()?;
+    let shentsize = usize::try_from(shentsize).ok()?;
+    let header = |index: u64| -> Option<usize> {
+        shoff.checked_add(usize::try_from(index).ok()?.checked_mul(shentsize)?)
+    };
+
+    // Offset field follows sh_name, sh_type, sh_flags and sh_addr
+    let strtab_header = header(shstrndx)?;
+    let strtab_offset = usize::try_from(read(strtab_header + 8 + 2 * word, word)?).ok()?;
+
+    let mut names = Vec::new();
+    for index in 0..shnum {
+        let name_offset = usize::try_from(read(header(index)?, 4)?).ok()?;
+        let start = strtab_offset.checked_add(name_offset)?;
+        let rest = data.get(start..)?;
+        let end = rest.iter().position(|&b| b == 0)?;
+        names.push(String::from_utf8_lossy(&rest[..end]).into_owned());
+    }
+    Some(names)
+}
+
+/// Strip all ELF binaries under a package's staging directory
+///
+/// Files that are not ELF or are already stripped are skipped. With
+/// `split_debug`, debug info is written to `<debug_root>/<path>.debug` and
+/// linked from the stripped binary.
+pub fn strip_staged_package(
+    staged_dir: &Path,
+    debug_root: &Path,
+    config: &StripConfig,
+    tool: &StripTool,
+) -> Result<StripStats> {
+    let mut stats = StripStats::default();
+
+    for binary in find_elf_binaries(staged_dir)? {
+        let data = std::fs::read(&binary)
+            .with_context(|| format!("Failed to read {}", binary.display()))?;
+        if !has_symbols(&data) {
+            continue;
+        }
+        let original_size = data.len() as u64;
+        let binary_arg = binary.display().to_string();
+
+        if config.split_debug {
+            let relative = binary.strip_prefix(staged_dir).unwrap_or(&binary);
+            let mut debug_file = debug_root.join(relative).into_os_string();
+            debug_file.push(".debug");
+            let debug_file = PathBuf::from(debug_file);
+            if let Some(parent) = debug_file.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            let debug_arg = debug_file.display().to_string();
+
+            tool.run(&[
+                "--only-keep-debug".to_string(),
+                binary_arg.clone(),
+                debug_arg.clone(),
+            ])?;
+            tool.run(&["--strip-all".to_string(), binary_arg.clone()])?;
+            tool.run(&[format!("--add-gnu-debuglink={debug_arg}"), binary_arg])?;
+            stats.debug_files += 1;
+        } else {
+            tool.run(&["--strip-all".to_string(), binary_arg])?;
+        }
+
+        let stripped_size = std::fs::metadata(&binary)
+            .with_context(|| format!("Failed to get size of {}", binary.display()))?
+            .len();
+        stats.files_stripped += 1;
+        stats.bytes_saved += original_size.saturating_sub(stripped_size);
+        tracing::debug!(
+            "Stripped {}: {original_size} -> {stripped_size}",
+            binary.display()
+        );
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a minimal little-endian ELF64 file with the given sections
+    fn elf64_with_sections(sections: &[&str]) -> Vec<u8> {
+        let mut strtab = vec![0u8];
+        let mut name_offsets = Vec::new();
+        for name in sections.iter().chain(std::iter::once(&".shstrtab")) {
+            name_offsets.push(u32::try_from(strtab.len()).unwrap());
+            strtab.extend_from_slice(name.as_bytes());
+            strtab.push(0);
+        }
+
+        let strtab_offset = 64u64;
+        let shoff = strtab_offset + strtab.len() as u64;
+        let shnum = u16::try_from(name_offsets.len()).unwrap();
+
+        let mut data = vec![0u8; 64];
+        data[..4].copy_from_slice(b"\x7fELF");
+        data[4] = 2;
+        data[5] = 1;
+        data[0x28..0x30].copy_from_slice(&shoff.to_le_bytes());
+        data[0x3A..0x3C].copy_from_slice(&64u16.to_le_bytes());
+        data[0x3C..0x3E].copy_from_slice(&shnum.to_le_bytes());
+        data[0x3E..0x40].copy_from_slice(&(shnum - 1).to_le_bytes());
+        data.extend_from_slice(&strtab);
+
+        for offset in name_offsets {
+            let mut header = vec![0u8; 64];
+            header[..4].copy_from_slice(&offset.to_le_bytes());
+            header[0x18..0x20].copy_from_slice(&strtab_offset.to_le_bytes());
+            data.extend_from_slice(&header);
+        }
+        data
+    }
+
+    #[test]
+    fn test_has_symbols_detects_symtab_and_debug() {
+        assert!(has_symbols(&elf64_with_sections(&[".text", ".symtab"])));
+        assert!(has_symbols(&elf64_with_sections(&[".text", ".debug_info"])));
+        assert!(!has_symbols(&elf64_with_sections(&[".text", ".dynsym"])));
+    }
+
+    #[test]
+    fn test_has_symbols_rejects_non_elf() {
+        assert!(!has_symbols(b"#!/bin/sh\necho hi\n"));
+        assert!(!has_symbols(b"\x7fELF"));
+    }
+
+    #[test]
+    fn test_package_override_takes_precedence() {
+        let config = StripConfig {
+            global_enabled: true,
+            split_debug: false,
+        };
+        assert!(config.is_enabled_for_package(None));
+        assert!(!config.is_enabled_for_package(Some(false)));
+    }
+}
@@ -1,20 +1,24 @@
 //! Dependency tree visualization
 //!
 //! Provides functionality to display package dependencies as a tree
-//! or export them in DOT graph format.
+//! or export them in DOT graph format, and to explain why a package is in
+//! the build.
 
 use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
 use std::path::Path;
 
+use crate::core::add::parse_dependency_constraint;
 use crate::core::manifest::Manifest;
+use crate::core::package::PackageDefinition;
 use crate::error::ZigrootError;
 
 /// Dependency type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DependencyType {
-    /// Build-time dependency (depends)
+    /// Build-only dependency (`build_depends`)
     Build,
-    /// Runtime dependency (requires)
+    /// Runtime dependency (depends, requires)
     Runtime,
 }
 
@@ -34,6 +38,18 @@ pub struct DependencyEdge {
     pub target: String,
     /// Type of dependency
     pub dep_type: DependencyType,
+    /// Version constraint of the dependency (e.g. `>=1.2.0`), if any
+    pub constraint: Option<String>,
+}
+
+impl DependencyEdge {
+    /// Edge annotation, e.g. `runtime` or `build, >=1.2.0`
+    pub fn label(&self) -> String {
+        match &self.constraint {
+            Some(constraint) => format!("{}, {constraint}", self.dep_type),
+            None => self.dep_type.to_string(),
+        }
+    }
 }
 
 /// Dependency tree structure
@@ -71,8 +87,60 @@ impl DependencyTree {
         tree
     }
 
+    /// Build dependency tree from a manifest and local package definitions
+    ///
+    /// Runtime edges come from `depends` and `requires`. Build-only edges
+    /// from `build_depends` are included only when `include_build_deps` is set.
+    pub fn from_project(project_dir: &Path, manifest: &Manifest, include_build_deps: bool) -> Self {
+        let mut tree = Self::from_manifest(manifest);
+
+        let mut pending: Vec<String> = tree.roots.clone();
+        let mut loaded = HashSet::new();
+        while let Some(name) = pending.pop() {
+            if !loaded.insert(name.clone()) {
+                continue;
+            }
+            let path = project_dir
+                .join("packages")
+                .join(&name)
+                .join("package.toml");
+            let Some(def) = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| PackageDefinition::from_toml(&content).ok())
+            else {
+                continue;
+            };
+
+            for dep in def.package.runtime_dependencies() {
+                let (dep, constraint) = parse_dependency_constraint(&dep);
+                tree.add_constrained_dependency(&name, &dep, DependencyType::Runtime, constraint);
+                pending.push(dep);
+            }
+            if include_build_deps {
+                for dep in &def.package.build_depends {
+                    let (dep, constraint) = parse_dependency_constraint(dep);
+                    tree.add_constrained_dependency(&name, &dep, DependencyType::Build, constraint);
+                    pending.push(dep);
+                }
+            }
+        }
+
+        tree
+    }
+
     /// Add a dependency edge
     pub fn add_dependency(&mut self, from: &str, to: &str, dep_type: DependencyType) {
+        self.add_constrained_dependency(from, to, dep_type, None);
+    }
+
+    /// Add a dependency edge with a version constraint
+    pub fn add_constrained_dependency(
+        &mut self,
+        from: &str,
+        to: &str,
+        dep_type: DependencyType,
+        constraint: Option<String>,
+    ) {
         self.packages.insert(from.to_string());
         self.packages.insert(to.to_string());
 
@@ -80,6 +148,7 @@ impl DependencyTree {
         deps.push(DependencyEdge {
             target: to.to_string(),
             dep_type,
+            constraint,
         });
     }
 
@@ -174,7 +243,7 @@ impl DependencyTree {
 
         for (i, root) in self.roots.iter().enumerate() {
             let is_last = i == self.roots.len() - 1;
-            self.format_node(&mut output, root, "", is_last, &mut HashSet::new());
+            self.format_node(&mut output, root, None, "", is_last, &mut HashSet::new());
         }
 
         output
@@ -184,12 +253,16 @@ impl DependencyTree {
         &self,
         output: &mut String,
         node: &str,
+        marker: Option<&str>,
         prefix: &str,
         is_last: bool,
         visited: &mut HashSet<String>,
     ) {
         let connector = if is_last { "└── " } else { "├── " };
-        output.push_str(&format!("{prefix}{connector}{node}\n"));
+        match marker {
+            Some(marker) => output.push_str(&format!("{prefix}{connector}{node} {marker}\n")),
+            None => output.push_str(&format!("{prefix}{connector}{node}\n")),
+        }
 
         if visited.contains(node) {
             // Already visited, don't recurse (prevents infinite loops)
@@ -211,8 +284,14 @@ impl DependencyTree {
                     DependencyType::Runtime => "[runtime]",
                 };
 
-                let dep_node = format!("{} {}", dep.target, dep_marker);
-                self.format_node(output, &dep_node, &child_prefix, is_last_dep, visited);
+                self.format_node(
+                    output,
+                    &dep.target,
+                    Some(dep_marker),
+                    &child_prefix,
+                    is_last_dep,
+                    visited,
+                );
             }
         }
 
@@ -271,7 +350,7 @@ impl DependencyTree {
         }
 
         // Format just this package as root
-        self.format_node(&mut output, package, "", true, &mut HashSet::new());
+        self.format_node(&mut output, package, None, "", true, &mut HashSet::new());
 
         output
     }
@@ -318,6 +397,121 @@ impl DependencyTree {
         output
     }
 
+    /// Every path from a root package down to `package`
+    ///
+    /// Each path starts at a manifest package and lists the edges taken;
+    /// a root package itself has a path without edges. Paths through a
+    /// cycle visit each package once.
+    pub fn paths_to(&self, package: &str) -> Vec<(String, Vec<DependencyEdge>)> {
+        let mut paths = Vec::new();
+        for root in &self.roots {
+            let mut on_path = HashSet::from([root.clone()]);
+            let mut edges = Vec::new();
+            self.collect_paths(root, package, &mut on_path, &mut edges, &mut |edges| {
+                paths.push((root.clone(), edges.to_vec()));
+            });
+        }
+        paths
+    }
+
+    fn collect_paths(
+        &self,
+        node: &str,
+        package: &str,
+        on_path: &mut HashSet<String>,
+        edges: &mut Vec<DependencyEdge>,
+        found: &mut dyn FnMut(&[DependencyEdge]),
+    ) {
+        if node == package {
+            found(edges);
+            return;
+        }
+        for dep in self.dependencies.get(node).into_iter().flatten() {
+            if !on_path.insert(dep.target.clone()) {
+                continue;
+            }
+            edges.push(dep.clone());
+            self.collect_paths(&dep.target, package, on_path, edges, found);
+            edges.pop();
+            on_path.remove(&dep.target);
+        }
+    }
+
+    /// Explain why a package is in the build, one dependency path per line
+    ///
+    /// E.g. `nginx → openssl [runtime] → zlib [build, >=1.2]`.
+    pub fn format_why(&self, package: &str) -> String {
+        let mut lines: Vec<String> = self
+            .paths_to(package)
+            .iter()
+            .map(|(root, edges)| {
+                if edges.is_empty() {
+                    return format!("{root} (in zigroot.toml)");
+                }
+                let mut line = root.clone();
+                for edge in edges {
+                    let _ = write!(line, " → {} [{}]", edge.target, edge.label());
+                }
+                line
+            })
+            .collect();
+        lines.sort();
+
+        let mut output = format!("Why '{package}' is in the build:\n");
+        for line in &lines {
+            let _ = writeln!(output, "  {line}");
+        }
+        output
+    }
+
+    /// Format DOT graph of the paths leading to a package, highlighting it
+    pub fn format_dot_why(&self, package: &str) -> String {
+        let mut nodes = HashSet::new();
+        let mut edges = HashSet::new();
+        for (root, path) in self.paths_to(package) {
+            nodes.insert(root.clone());
+            let mut from = root;
+            for edge in path {
+                nodes.insert(edge.target.clone());
+                let style = match edge.dep_type {
+                    DependencyType::Build => "solid",
+                    DependencyType::Runtime => "dashed",
+                };
+                edges.insert(format!(
+                    "    \"{from}\" -> \"{}\" [style={style}, label=\"{}\"];\n",
+                    edge.target,
+                    edge.label()
+                ));
+                from = edge.target;
+            }
+        }
+
+        let mut output = format!("digraph \"why {package}\" {{\n");
+        output.push_str("    rankdir=TB;\n");
+        output.push_str("    node [shape=box];\n");
+        output.push('\n');
+
+        let mut nodes: Vec<_> = nodes.into_iter().collect();
+        nodes.sort();
+        for node in &nodes {
+            if node == package {
+                let _ = writeln!(output, "    \"{node}\" [style=filled, fillcolor=gold];");
+            } else {
+                let _ = writeln!(output, "    \"{node}\";");
+            }
+        }
+        output.push('\n');
+
+        let mut edges: Vec<_> = edges.into_iter().collect();
+        edges.sort();
+        for edge in &edges {
+            output.push_str(edge);
+        }
+
+        output.push_str("}\n");
+        output
+    }
+
     /// Collect all packages reachable from a given package
     fn collect_reachable(&self, package: &str, reachable: &mut HashSet<String>) {
         if reachable.contains(package) {
@@ -333,12 +527,8 @@ impl DependencyTree {
     }
 }
 
-/// Display dependency tree for a project
-pub fn display_tree(
-    project_dir: &Path,
-    package: Option<&str>,
-    graph_format: bool,
-) -> Result<String, ZigrootError> {
+/// Load the manifest of a project
+fn load_manifest(project_dir: &Path) -> Result<Manifest, ZigrootError> {
     let manifest_path = project_dir.join("zigroot.toml");
 
     if !manifest_path.exists() {
@@ -350,10 +540,20 @@ pub fn display_tree(
     let manifest_content =
         std::fs::read_to_string(&manifest_path).map_err(|e| ZigrootError::Io { source: e })?;
 
-    let manifest = Manifest::from_toml(&manifest_content)
-        .map_err(|e| ZigrootError::ManifestParse { source: e })?;
+    Manifest::from_toml(&manifest_content).map_err(|e| ZigrootError::ManifestParse { source: e })
+}
 
-    let tree = DependencyTree::from_manifest(&manifest);
+/// Display dependency tree for a project
+///
+/// Build-only edges are shown only when `build_deps` is set.
+pub fn display_tree(
+    project_dir: &Path,
+    package: Option<&str>,
+    graph_format: bool,
+    build_deps: bool,
+) -> Result<String, ZigrootError> {
+    let manifest = load_manifest(project_dir)?;
+    let tree = DependencyTree::from_project(project_dir, &manifest, build_deps);
 
     // If a specific package is requested, filter the tree
     if let Some(pkg_name) = package {
@@ -377,6 +577,34 @@ pub fn display_tree(
     }
 }
 
+/// Explain why a package is in the build
+///
+/// Lists every path from a manifest package to `package`, build-only edges
+/// included. With `graph_format`, only the subgraph of these paths is
+/// emitted in DOT format.
+pub fn display_why(
+    project_dir: &Path,
+    package: &str,
+    graph_format: bool,
+) -> Result<String, ZigrootError> {
+    let manifest = load_manifest(project_dir)?;
+    let tree = DependencyTree::from_project(project_dir, &manifest, true);
+
+    if !tree.packages().contains(package) {
+        return Err(ZigrootError::Package(
+            crate::error::PackageError::NotInBuild {
+                name: package.to_string(),
+            },
+        ));
+    }
+
+    if graph_format {
+        Ok(tree.format_dot_why(package))
+    } else {
+        Ok(tree.format_why(package))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -399,6 +627,7 @@ mod tests {
             vec![DependencyEdge {
                 target: "lib".to_string(),
                 dep_type: DependencyType::Build,
+                constraint: None,
             }],
         );
         tree.dependencies.insert("lib".to_string(), Vec::new());
@@ -409,6 +638,45 @@ mod tests {
         assert!(output.contains("[build]"));
     }
 
+    #[test]
+    fn test_from_project_separates_build_only_edges() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let pkg_dir = temp.path().join("packages").join("app");
+        std::fs::create_dir_all(&pkg_dir).unwrap();
+        std::fs::write(
+            pkg_dir.join("package.toml"),
+            r#"
+[package]
+name = "app"
+version = "1.0.0"
+description = "App"
+depends = ["libfoo"]
+build_depends = ["zlib-static"]
+
+[source]
+url = "https://example.com/app.tar.gz"
+sha256 = "abc123"
+"#,
+        )
+        .unwrap();
+
+        let mut manifest = Manifest::default();
+        manifest.packages.insert(
+            "app".to_string(),
+            toml::from_str("version = \"1.0.0\"").unwrap(),
+        );
+
+        let runtime_only = DependencyTree::from_project(temp.path(), &manifest, false);
+        let output = runtime_only.format_tree();
+        assert!(output.contains("libfoo [runtime]"));
+        assert!(!output.contains("zlib-static"));
+
+        let with_build = DependencyTree::from_project(temp.path(), &manifest, true);
+        let output = with_build.format_tree();
+        assert!(output.contains("libfoo [runtime]"));
+        assert!(output.contains("zlib-static [build]"));
+    }
+
     #[test]
     fn test_dot_format() {
         let mut tree = DependencyTree::new();
@@ -420,6 +688,7 @@ mod tests {
             vec![DependencyEdge {
                 target: "lib".to_string(),
                 dep_type: DependencyType::Build,
+                constraint: None,
             }],
         );
 
@@ -441,6 +710,7 @@ mod tests {
             vec![DependencyEdge {
                 target: "b".to_string(),
                 dep_type: DependencyType::Build,
+                constraint: None,
             }],
         );
         tree.dependencies.insert(
@@ -448,6 +718,7 @@ mod tests {
             vec![DependencyEdge {
                 target: "c".to_string(),
                 dep_type: DependencyType::Build,
+                constraint: None,
             }],
         );
         tree.dependencies.insert(
@@ -455,6 +726,7 @@ mod tests {
             vec![DependencyEdge {
                 target: "a".to_string(),
                 dep_type: DependencyType::Build,
+                constraint: None,
             }],
         );
 
@@ -473,6 +745,7 @@ mod tests {
             vec![DependencyEdge {
                 target: "b".to_string(),
                 dep_type: DependencyType::Build,
+                constraint: None,
             }],
         );
         tree.dependencies.insert(
@@ -480,6 +753,7 @@ mod tests {
             vec![DependencyEdge {
                 target: "c".to_string(),
                 dep_type: DependencyType::Build,
+                constraint: None,
             }],
         );
         tree.dependencies.insert("c".to_string(), Vec::new());
@@ -501,10 +775,12 @@ mod tests {
                 DependencyEdge {
                     target: "build-lib".to_string(),
                     dep_type: DependencyType::Build,
+                    constraint: None,
                 },
                 DependencyEdge {
                     target: "runtime-lib".to_string(),
                     dep_type: DependencyType::Runtime,
+                    constraint: None,
                 },
             ],
         );
@@ -532,6 +808,7 @@ mod tests {
             vec![DependencyEdge {
                 target: "lib".to_string(),
                 dep_type: DependencyType::Build,
+                constraint: None,
             }],
         );
         tree.dependencies.insert("lib".to_string(), Vec::new());
@@ -563,6 +840,7 @@ mod tests {
             vec![DependencyEdge {
                 target: "lib".to_string(),
                 dep_type: DependencyType::Build,
+                constraint: None,
             }],
         );
         tree.dependencies.insert("lib".to_string(), Vec::new());
@@ -595,6 +873,7 @@ mod tests {
             vec![DependencyEdge {
                 target: "b".to_string(),
                 dep_type: DependencyType::Build,
+                constraint: None,
             }],
         );
         tree.dependencies.insert(
@@ -602,6 +881,7 @@ mod tests {
             vec![DependencyEdge {
                 target: "c".to_string(),
                 dep_type: DependencyType::Build,
+                constraint: None,
             }],
         );
         tree.dependencies.insert("c".to_string(), Vec::new());
@@ -615,4 +895,58 @@ mod tests {
         assert!(reachable.contains("c"));
         assert!(!reachable.contains("d")); // d is not reachable from a
     }
+
+    /// nginx → openssl → zlib, dropbear → zlib (build-only, constrained)
+    fn why_tree() -> DependencyTree {
+        let mut tree = DependencyTree::new();
+        for root in ["dropbear", "nginx", "busybox"] {
+            tree.packages.insert(root.to_string());
+            tree.roots.push(root.to_string());
+        }
+        tree.add_dependency("nginx", "openssl", DependencyType::Runtime);
+        tree.add_dependency("openssl", "zlib", DependencyType::Runtime);
+        tree.add_constrained_dependency(
+            "dropbear",
+            "zlib",
+            DependencyType::Build,
+            Some(">=1.2.11".to_string()),
+        );
+        tree
+    }
+
+    #[test]
+    fn test_format_why_lists_every_path() {
+        let tree = why_tree();
+
+        assert_eq!(
+            tree.format_why("zlib"),
+            "Why 'zlib' is in the build:\n\
+             \x20 dropbear → zlib [build, >=1.2.11]\n\
+             \x20 nginx → openssl [runtime] → zlib [runtime]\n"
+        );
+        assert_eq!(
+            tree.format_why("nginx"),
+            "Why 'nginx' is in the build:\n  nginx (in zigroot.toml)\n"
+        );
+    }
+
+    #[test]
+    fn test_paths_to_survive_cycles() {
+        let mut tree = why_tree();
+        tree.add_dependency("openssl", "nginx", DependencyType::Runtime);
+
+        let paths = tree.paths_to("zlib");
+        assert_eq!(paths.len(), 2);
+    }
+
+    #[test]
+    fn test_format_dot_why_highlights_target() {
+        let output = why_tree().format_dot_why("zlib");
+
+        assert!(output.contains("\"zlib\" [style=filled, fillcolor=gold];"));
+        assert!(output.contains("\"openssl\" -> \"zlib\" [style=dashed, label=\"runtime\"];"));
+        assert!(output.contains("label=\"build, >=1.2.11\""));
+        // busybox does not lead to zlib
+        assert!(!output.contains("busybox"));
+    }
 }
@@ -7,6 +7,8 @@ use std::path::Path;
 
 use crate::core::lock::{LockFile, LockedPackageBuilder};
 use crate::core::manifest::Manifest;
+use crate::core::version::split_requirement;
+use crate::infra::filesystem::write_file_atomic;
 use crate::registry::client::RegistryClient;
 use thiserror::Error;
 
@@ -114,6 +116,13 @@ pub async fn update_packages(
             .version
             .clone()
             .unwrap_or_else(|| "latest".to_string());
+        // Requirements like "^1.36.1" keep their operator; packages added
+        // offline as "latest" get a caret requirement
+        let (operator, current_base) = if current_version == "latest" {
+            ("^", current_version.as_str())
+        } else {
+            split_requirement(&current_version)
+        };
 
         // Try to find latest version from registry
         let latest_version = if let Some(ref idx) = index {
@@ -126,10 +135,11 @@ pub async fn update_packages(
         };
 
         if let Some(latest) = latest_version {
-            if is_newer_version(&latest, &current_version) {
+            if is_newer_version(&latest, current_base) {
                 // Update manifest with new version
+                let requirement = format!("{operator}{latest}");
                 if let Some(pkg) = manifest.packages.get_mut(pkg_name) {
-                    pkg.version = Some(latest.clone());
+                    pkg.version = Some(requirement);
                 }
 
                 // Update lock file
@@ -154,7 +164,7 @@ pub async fn update_packages(
         let new_manifest_content = manifest
             .to_toml()
             .map_err(|e| UpdateError::ManifestError(e.to_string()))?;
-        std::fs::write(&manifest_path, new_manifest_content)
+        write_file_atomic(&manifest_path, new_manifest_content.as_bytes())
             .map_err(|e| UpdateError::IoError(e.to_string()))?;
 
         // Save lock file
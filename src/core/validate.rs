@@ -0,0 +1,987 @@
+//! Post-build validation of the rootfs
+//!
+//! Catches images that would only fail on the device: binaries for the
+//! wrong architecture, dynamic binaries whose interpreter or libraries are
+//! not installed, a missing init, symlinks into nowhere and login shells
+//! that do not exist. Each check has a default [`Severity`] that the
+//! manifest can override in `[build.validation]`.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::core::libc::Libc;
+
+/// Init entry points tried when none is configured
+pub const DEFAULT_INIT: &[&str] = &["/sbin/init", "/init"];
+
+/// Default library search path of the dynamic loader
+const LIBRARY_DIRS: &[&str] = &["/lib", "/usr/lib", "/lib64", "/usr/lib64"];
+
+/// Runtime filesystems that symlinks may point into
+const RUNTIME_DIRS: &[&str] = &["/proc", "/sys", "/dev", "/run", "/tmp", "/var/run"];
+
+/// Firmware for other processors is not built for the target
+const FIRMWARE_DIR: &str = "lib/firmware";
+
+/// Most symlinks followed while resolving a path, as in Linux
+const MAX_SYMLINKS: usize = 40;
+
+/// How a finding affects the build
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The build fails
+    Error,
+    /// The build succeeds and reports the finding
+    Warning,
+    /// The check is skipped
+    Ignore,
+}
+
+impl Severity {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "error" => Some(Self::Error),
+            "warning" => Some(Self::Warning),
+            "ignore" => Some(Self::Ignore),
+            _ => None,
+        }
+    }
+}
+
+/// A validation check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Check {
+    /// An ELF file is built for another architecture than the board's
+    ArchMismatch,
+    /// The program interpreter of a dynamic binary is not installed
+    MissingInterpreter,
+    /// A dynamic binary requests the loader of another C library
+    InterpreterMismatch,
+    /// A library needed by a dynamic binary is not installed
+    MissingLibrary,
+    /// The init entry point is missing or not executable
+    MissingInit,
+    /// A symlink points to nothing inside the rootfs
+    DanglingSymlink,
+    /// A login shell in /etc/passwd is not installed
+    MissingShell,
+}
+
+impl Check {
+    /// All checks
+    pub const ALL: [Self; 7] = [
+        Self::ArchMismatch,
+        Self::MissingInterpreter,
+        Self::InterpreterMismatch,
+        Self::MissingLibrary,
+        Self::MissingInit,
+        Self::DanglingSymlink,
+        Self::MissingShell,
+    ];
+
+    /// Name used in the manifest and in reports
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ArchMismatch => "arch-mismatch",
+            Self::MissingInterpreter => "missing-interpreter",
+            Self::InterpreterMismatch => "interpreter-mismatch",
+            Self::MissingLibrary => "missing-library",
+            Self::MissingInit => "missing-init",
+            Self::DanglingSymlink => "dangling-symlink",
+            Self::MissingShell => "missing-shell",
+        }
+    }
+
+    /// Severity unless overridden
+    ///
+    /// Problems that keep programs from starting at all are errors.
+    pub fn default_severity(self) -> Severity {
+        match self {
+            Self::ArchMismatch | Self::MissingInterpreter | Self::MissingLibrary => Severity::Error,
+            Self::InterpreterMismatch
+            | Self::MissingInit
+            | Self::DanglingSymlink
+            | Self::MissingShell => Severity::Warning,
+        }
+    }
+}
+
+/// A problem found in the rootfs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    /// Name of the check
+    pub check: &'static str,
+    /// Severity of the finding
+    pub severity: Severity,
+    /// Path inside the rootfs
+    pub path: String,
+    /// What is wrong
+    pub message: String,
+}
+
+impl std::fmt::Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} [{}]", self.path, self.message, self.check)
+    }
+}
+
+/// What to validate and how seriously
+#[derive(Debug, Clone)]
+pub struct ValidateOptions {
+    /// Target triple of the board; the architecture and dynamic loader are
+    /// not checked without
+    target: Option<String>,
+    /// Init entry points, any of which satisfies the init check
+    init: Vec<String>,
+    /// Severity of each check
+    severities: HashMap<Check, Severity>,
+}
+
+impl ValidateOptions {
+    /// Options with severity overrides by check name
+    ///
+    /// # Errors
+    ///
+    /// Returns a message for unknown check names or severities.
+    pub fn new(
+        target: Option<String>,
+        init: Option<String>,
+        overrides: &HashMap<String, String>,
+    ) -> Result<Self, String> {
+        let mut severities: HashMap<Check, Severity> = Check::ALL
+            .iter()
+            .map(|check| (*check, check.default_severity()))
+            .collect();
+        for (name, value) in overrides {
+            let check = Check::ALL
+                .into_iter()
+                .find(|check| check.name() == name)
+                .ok_or_else(|| {
+                    let names: Vec<&str> = Check::ALL.iter().map(|c| c.name()).collect();
+                    format!(
+                        "unknown check '{name}' (expected one of: {})",
+                        names.join(", ")
+                    )
+                })?;
+            let severity = Severity::parse(value).ok_or_else(|| {
+                format!(
+                    "invalid severity '{value}' for '{name}' (expected error, warning or ignore)"
+                )
+            })?;
+            severities.insert(check, severity);
+        }
+        Ok(Self {
+            target,
+            init: init.map_or_else(
+                || DEFAULT_INIT.iter().map(ToString::to_string).collect(),
+                |init| vec![init],
+            ),
+            severities,
+        })
+    }
+
+    fn severity(&self, check: Check) -> Severity {
+        self.severities
+            .get(&check)
+            .copied()
+            .unwrap_or_else(|| check.default_severity())
+    }
+}
+
+/// Findings of a validation pass
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    /// Findings in path order
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    /// Findings that fail the build
+    pub fn errors(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == Severity::Error)
+    }
+
+    /// Findings reported without failing the build
+    pub fn warnings(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == Severity::Warning)
+    }
+}
+
+/// Validate an assembled rootfs
+pub fn validate_rootfs(rootfs: &Path, options: &ValidateOptions) -> ValidationReport {
+    let mut validator = Validator {
+        rootfs,
+        options,
+        findings: Vec::new(),
+    };
+    validator.check_tree();
+    validator.check_init();
+    validator.check_shells();
+    validator.findings.sort_by(|a, b| a.path.cmp(&b.path));
+    ValidationReport {
+        findings: validator.findings,
+    }
+}
+
+struct Validator<'a> {
+    rootfs: &'a Path,
+    options: &'a ValidateOptions,
+    findings: Vec<Finding>,
+}
+
+impl Validator<'_> {
+    fn report(&mut self, check: Check, path: &Path, message: String) {
+        let severity = self.options.severity(check);
+        if severity == Severity::Ignore {
+            return;
+        }
+        self.findings.push(Finding {
+            check: check.name(),
+            severity,
+            path: Path::new("/").join(path).display().to_string(),
+            message,
+        });
+    }
+
+    /// Check every file and symlink of the rootfs
+    fn check_tree(&mut self) {
+        let arch = self.options.target.as_deref().and_then(target_arch);
+        let loader = self
+            .options
+            .target
+            .as_deref()
+            .and_then(|target| Libc::of_target(target).dynamic_loader(target));
+        let entries: Vec<walkdir::DirEntry> = walkdir::WalkDir::new(self.rootfs)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(Result::ok)
+            .collect();
+        for entry in entries {
+            let Ok(relative) = entry.path().strip_prefix(self.rootfs) else {
+                continue;
+            };
+            let relative = relative.to_path_buf();
+            if entry.path_is_symlink() {
+                self.check_symlink(&relative);
+            } else if entry.file_type().is_file() && !relative.starts_with(FIRMWARE_DIR) {
+                let Ok(data) = std::fs::read(entry.path()) else {
+                    continue;
+                };
+                if let Some(elf) = Elf::parse(&data) {
+                    self.check_elf(&relative, &elf, arch, loader.as_deref());
+                }
+            }
+        }
+    }
+
+    fn check_symlink(&mut self, relative: &Path) {
+        let Ok(target) = std::fs::read_link(self.rootfs.join(relative)) else {
+            return;
+        };
+        if target.is_absolute() && RUNTIME_DIRS.iter().any(|dir| target.starts_with(dir)) {
+            return;
+        }
+        if resolve(self.rootfs, relative).is_none() {
+            self.report(
+                Check::DanglingSymlink,
+                relative,
+                format!(
+                    "symlink to {} does not resolve inside the rootfs",
+                    target.display()
+                ),
+            );
+        }
+    }
+
+    fn check_elf(&mut self, relative: &Path, elf: &Elf, arch: Option<Arch>, loader: Option<&str>) {
+        if let Some(arch) = arch {
+            if elf.arch() != arch {
+                self.report(
+                    Check::ArchMismatch,
+                    relative,
+                    format!("built for {}, but the board needs {}", elf.arch(), arch),
+                );
+                // Libraries of a foreign binary are not worth resolving
+                return;
+            }
+        }
+        if let Some(ref interpreter) = elf.interpreter {
+            if resolve(self.rootfs, Path::new(interpreter)).is_none() {
+                self.report(
+                    Check::MissingInterpreter,
+                    relative,
+                    format!("program interpreter {interpreter} is not installed"),
+                );
+            }
+            // Loaders move between /lib and /lib64, their names identify the libc
+            let file_name = |path: &str| Path::new(path).file_name().map(ToOwned::to_owned);
+            if let Some(loader) =
+                loader.filter(|loader| file_name(loader) != file_name(interpreter))
+            {
+                self.report(
+                    Check::InterpreterMismatch,
+                    relative,
+                    format!(
+                        "program interpreter {interpreter} does not match the board's {loader}"
+                    ),
+                );
+            }
+        }
+        let search = self.library_dirs(relative, elf);
+        for library in &elf.needed {
+            let found = if library.contains('/') {
+                resolve(self.rootfs, Path::new(library)).is_some()
+            } else {
+                search
+                    .iter()
+                    .any(|dir| resolve(self.rootfs, &dir.join(library)).is_some())
+            };
+            if !found {
+                self.report(
+                    Check::MissingLibrary,
+                    relative,
+                    format!("needed library {library} is not installed"),
+                );
+            }
+        }
+    }
+
+    /// Directories searched for the libraries of a binary, in rootfs terms
+    fn library_dirs(&self, relative: &Path, elf: &Elf) -> Vec<PathBuf> {
+        let origin = Path::new("/").join(relative.parent().unwrap_or(Path::new("")));
+        let mut dirs: Vec<PathBuf> = elf
+            .search_path
+            .iter()
+            .flat_map(|paths| paths.split(':'))
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| {
+                PathBuf::from(
+                    dir.replace("${ORIGIN}", &origin.display().to_string())
+                        .replace("$ORIGIN", &origin.display().to_string()),
+                )
+            })
+            .collect();
+        dirs.extend(LIBRARY_DIRS.iter().map(PathBuf::from));
+        dirs.extend(self.configured_library_dirs());
+        dirs
+    }
+
+    /// Library directories from the loader configuration of musl and glibc
+    fn configured_library_dirs(&self) -> Vec<PathBuf> {
+        let etc = self.rootfs.join("etc");
+        let Ok(entries) = std::fs::read_dir(&etc) else {
+            return Vec::new();
+        };
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| {
+                        name == "ld.so.conf"
+                            || (name.starts_with("ld-musl-")
+                                && Path::new(name).extension().is_some_and(|e| e == "path"))
+                    })
+            })
+            .collect();
+        files.sort();
+        files
+            .iter()
+            .filter_map(|path| std::fs::read_to_string(path).ok())
+            .flat_map(|content| {
+                content
+                    .split(|c: char| c == ':' || c.is_whitespace())
+                    .filter(|dir| dir.starts_with('/'))
+                    .map(PathBuf::from)
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn check_init(&mut self) {
+        let mut problem = None;
+        for init in &self.options.init {
+            match resolve(self.rootfs, Path::new(init)) {
+                Some(path) if is_executable(&path) => return,
+                Some(_) => problem = Some((init.clone(), "is not executable")),
+                None => {}
+            }
+        }
+        let (init, message) = problem.unwrap_or_else(|| {
+            (
+                self.options.init[0].clone(),
+                "is missing; the kernel will not find an init to start",
+            )
+        });
+        let path = PathBuf::from(init.trim_start_matches('/'));
+        self.report(Check::MissingInit, &path, format!("{init} {message}"));
+    }
+
+    fn check_shells(&mut self) {
+        let passwd = Path::new("etc/passwd");
+        let Ok(content) = std::fs::read_to_string(self.rootfs.join(passwd)) else {
+            return;
+        };
+        for line in content.lines() {
+            let fields: Vec<&str> = line.split(':').collect();
+            let [user, .., shell] = fields.as_slice() else {
+                continue;
+            };
+            if fields.len() < 7 || shell.is_empty() {
+                continue;
+            }
+            if resolve(self.rootfs, Path::new(shell)).is_none() {
+                self.report(
+                    Check::MissingShell,
+                    passwd,
+                    format!("login shell {shell} of user '{user}' is not installed"),
+                );
+            }
+        }
+    }
+}
+
+/// Resolve a path inside the rootfs as the target would, following symlinks
+///
+/// Absolute symlink targets are taken relative to the rootfs and `..`
+/// stops at its root. Returns the host path of the existing file.
+pub fn resolve(rootfs: &Path, path: &Path) -> Option<PathBuf> {
+    let mut pending: Vec<OsString> = parts(path);
+    pending.reverse();
+    let mut current = PathBuf::new();
+    let mut followed = 0;
+    while let Some(part) = pending.pop() {
+        if part == ".." {
+            current.pop();
+            continue;
+        }
+        let candidate = current.join(&part);
+        let host = rootfs.join(&candidate);
+        let metadata = host.symlink_metadata().ok()?;
+        if metadata.file_type().is_symlink() {
+            followed += 1;
+            if followed > MAX_SYMLINKS {
+                return None;
+            }
+            let target = std::fs::read_link(&host).ok()?;
+            if target.is_absolute() {
+                current = PathBuf::new();
+            }
+            pending.extend(parts(&target).into_iter().rev());
+        } else {
+            current = candidate;
+        }
+    }
+    Some(rootfs.join(current))
+}
+
+/// Normal and parent components of a path
+fn parts(path: &Path) -> Vec<OsString> {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_os_string()),
+            Component::ParentDir => Some(OsString::from("..")),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// ELF machine, word size and byte order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arch {
+    machine: u16,
+    is_64: bool,
+    little_endian: bool,
+}
+
+impl std::fmt::Display for Arch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match (self.machine, self.is_64) {
+            (3, _) => "x86",
+            (8, false) => "mips",
+            (8, true) => "mips64",
+            (20, _) => "powerpc",
+            (21, _) => "powerpc64",
+            (40, _) => "arm",
+            (62, _) => "x86_64",
+            (183, _) => "aarch64",
+            (243, false) => "riscv32",
+            (243, true) => "riscv64",
+            _ => return write!(f, "machine {}", self.machine),
+        };
+        let order = if self.little_endian {
+            ""
+        } else {
+            " (big-endian)"
+        };
+        write!(f, "{name}{order}")
+    }
+}
+
+/// Architecture of a Zig target triple, if known
+pub fn target_arch(target: &str) -> Option<Arch> {
+    let cpu = target.split('-').next()?;
+    let (machine, is_64, little_endian) = match cpu {
+        "x86" | "i386" | "i486" | "i586" | "i686" => (3, false, true),
+        "x86_64" => (62, true, true),
+        "arm" | "armv7" | "armv7a" | "thumb" => (40, false, true),
+        "armeb" => (40, false, false),
+        "aarch64" => (183, true, true),
+        "aarch64_be" => (183, true, false),
+        "mips" => (8, false, false),
+        "mipsel" => (8, false, true),
+        "mips64" => (8, true, false),
+        "mips64el" => (8, true, true),
+        "powerpc" => (20, false, false),
+        "powerpc64" => (21, true, false),
+        "powerpc64le" => (21, true, true),
+        "riscv32" => (243, false, true),
+        "riscv64" => (243, true, true),
+        _ => return None,
+    };
+    Some(Arch {
+        machine,
+        is_64,
+        little_endian,
+    })
+}
+
+/// What validation needs to know about an ELF file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Elf {
+    machine: u16,
+    is_64: bool,
+    little_endian: bool,
+    /// Program interpreter (`PT_INTERP`)
+    interpreter: Option<String>,
+    /// Needed libraries (`DT_NEEDED`)
+    needed: Vec<String>,
+    /// `DT_RUNPATH` or `DT_RPATH`
+    search_path: Option<String>,
+}
+
+impl Elf {
+    const PT_INTERP: u64 = 3;
+    const SHT_DYNAMIC: u64 = 6;
+    const DT_NEEDED: u64 = 1;
+    const DT_RPATH: u64 = 15;
+    const DT_RUNPATH: u64 = 29;
+
+    fn arch(&self) -> Arch {
+        Arch {
+            machine: self.machine,
+            is_64: self.is_64,
+            little_endian: self.little_endian,
+        }
+    }
+
+    /// Parse the headers of an ELF file; `None` if it is not a valid one
+    fn parse(data: &[u8]) -> Option<Self> {
+        if data.get(..4)? != b"\x7fELF" {
+            return None;
+        }
+        let reader = ElfReader {
+            data,
+            is_64: match data.get(4)? {
+                1 => false,
+                2 => true,
+                _ => return None,
+            },
+            little_endian: match data.get(5)? {
+                1 => true,
+                2 => false,
+                _ => return None,
+            },
+        };
+        let mut elf = Self {
+            machine: u16::try_from(reader.int(0x12, 2)?).ok()?,
+            is_64: reader.is_64,
+            little_endian: reader.little_endian,
+            ..Self::default()
+        };
+        elf.interpreter = reader.interpreter();
+        reader.dynamic(&mut elf)?;
+        Some(elf)
+    }
+}
+
+/// Reads fields of an ELF file in its word size and byte order
+struct ElfReader<'a> {
+    data: &'a [u8],
+    is_64: bool,
+    little_endian: bool,
+}
+
+impl ElfReader<'_> {
+    /// Unsigned integer of `size` bytes at `offset`
+    fn int(&self, offset: u64, size: usize) -> Option<u64> {
+        let start = usize::try_from(offset).ok()?;
+        let bytes = self.data.get(start..start.checked_add(size)?)?;
+        let fold = |value: u64, byte: &u8| (value << 8) | u64::from(*byte);
+        Some(if self.little_endian {
+            bytes.iter().rev().fold(0, fold)
+        } else {
+            bytes.iter().fold(0, fold)
+        })
+    }
+
+    /// Address-sized integer at `offset`
+    fn word(&self, offset: u64) -> Option<u64> {
+        self.int(offset, if self.is_64 { 8 } else { 4 })
+    }
+
+    /// NUL-terminated string at `offset`, not reaching past `limit`
+    fn string(&self, offset: u64, limit: u64) -> Option<String> {
+        let start = usize::try_from(offset).ok()?;
+        let end = usize::try_from(limit).ok()?.min(self.data.len());
+        let bytes = self.data.get(start..end)?;
+        let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        String::from_utf8(bytes[..len].to_vec()).ok()
+    }
+
+    /// Offset of the header size and count fields
+    fn header_sizes(&self) -> u64 {
+        if self.is_64 {
+            0x36
+        } else {
+            0x2A
+        }
+    }
+
+    /// Program interpreter from the `PT_INTERP` program header
+    fn interpreter(&self) -> Option<String> {
+        let phoff = self.word(if self.is_64 { 0x20 } else { 0x1C })?;
+        let phentsize = self.int(self.header_sizes(), 2)?;
+        let phnum = self.int(self.header_sizes() + 2, 2)?;
+        for i in 0..phnum {
+            let header = phoff + i * phentsize;
+            if self.int(header, 4)? != Elf::PT_INTERP {
+                continue;
+            }
+            let (offset, size) = if self.is_64 {
+                (self.word(header + 8)?, self.word(header + 32)?)
+            } else {
+                (self.word(header + 4)?, self.word(header + 16)?)
+            };
+            return self.string(offset, offset + size);
+        }
+        None
+    }
+
+    /// Type, offset, size and link of a section header
+    fn section(&self, index: u64) -> Option<(u64, u64, u64, u64)> {
+        let shoff = self.word(if self.is_64 { 0x28 } else { 0x20 })?;
+        let shentsize = self.int(self.header_sizes() + 4, 2)?;
+        let header = shoff + index * shentsize;
+        let fields: [u64; 3] = if self.is_64 {
+            [24, 32, 40]
+        } else {
+            [16, 20, 24]
+        };
+        Some((
+            self.int(header + 4, 4)?,
+            self.word(header + fields[0])?,
+            self.word(header + fields[1])?,
+            self.int(header + fields[2], 4)?,
+        ))
+    }
+
+    /// Needed libraries and search path from the dynamic section
+    fn dynamic(&self, elf: &mut Elf) -> Option<()> {
+        let shnum = self.int(self.header_sizes() + 6, 2)?;
+        let word = if self.is_64 { 8 } else { 4 };
+        for i in 0..shnum {
+            let (kind, offset, size, link) = self.section(i)?;
+            if kind != Elf::SHT_DYNAMIC {
+                continue;
+            }
+            let (_, strtab, strsize, _) = self.section(link)?;
+            let string = |value: u64| self.string(strtab + value, strtab + strsize);
+            let mut entry = offset;
+            while entry + 2 * word <= offset + size {
+                let tag = self.word(entry)?;
+                let value = self.word(entry + word)?;
+                entry += 2 * word;
+                match tag {
+                    0 => break,
+                    Elf::DT_NEEDED => elf.needed.push(string(value)?),
+                    Elf::DT_RUNPATH => elf.search_path = Some(string(value)?),
+                    Elf::DT_RPATH if elf.search_path.is_none() => {
+                        elf.search_path = Some(string(value)?);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Build a little-endian 64-bit ELF with an interpreter and needed libraries
+    fn fixture_elf(machine: u16, interpreter: Option<&str>, needed: &[&str]) -> Vec<u8> {
+        let mut strtab = vec![0u8];
+        let mut dynamic = Vec::new();
+        for library in needed {
+            let offset = strtab.len() as u64;
+            strtab.extend_from_slice(library.as_bytes());
+            strtab.push(0);
+            dynamic.extend_from_slice(&Elf::DT_NEEDED.to_le_bytes());
+            dynamic.extend_from_slice(&offset.to_le_bytes());
+        }
+        dynamic.extend_from_slice(&[0u8; 16]);
+        let interp: Vec<u8> = interpreter
+            .map(|i| [i.as_bytes(), &[0]].concat())
+            .unwrap_or_default();
+
+        // Layout: header, one program header, interp, strtab, dynamic,
+        // three section headers (null, dynamic, strtab)
+        let phoff = 64u64;
+        let interp_off = phoff + 56;
+        let strtab_off = interp_off + interp.len() as u64;
+        let dynamic_off = strtab_off + strtab.len() as u64;
+        let shoff = dynamic_off + dynamic.len() as u64;
+
+        let mut data = vec![0u8; 64];
+        data[..4].copy_from_slice(b"\x7fELF");
+        data[4] = 2;
+        data[5] = 1;
+        data[6] = 1;
+        data[0x10..0x12].copy_from_slice(&3u16.to_le_bytes());
+        data[0x12..0x14].copy_from_slice(&machine.to_le_bytes());
+        data[0x20..0x28].copy_from_slice(&phoff.to_le_bytes());
+        data[0x28..0x30].copy_from_slice(&shoff.to_le_bytes());
+        data[0x36..0x38].copy_from_slice(&56u16.to_le_bytes());
+        data[0x38..0x3A].copy_from_slice(&u16::from(interpreter.is_some()).to_le_bytes());
+        data[0x3A..0x3C].copy_from_slice(&64u16.to_le_bytes());
+        data[0x3C..0x3E].copy_from_slice(&3u16.to_le_bytes());
+
+        let mut phdr = vec![0u8; 56];
+        phdr[..4].copy_from_slice(&3u32.to_le_bytes());
+        phdr[8..16].copy_from_slice(&interp_off.to_le_bytes());
+        phdr[32..40].copy_from_slice(&(interp.len() as u64).to_le_bytes());
+        data.extend_from_slice(&phdr);
+        data.extend_from_slice(&interp);
+        data.extend_from_slice(&strtab);
+        data.extend_from_slice(&dynamic);
+
+        let section = |kind: u32, offset: u64, size: u64, link: u32| {
+            let mut header = vec![0u8; 64];
+            header[4..8].copy_from_slice(&kind.to_le_bytes());
+            header[24..32].copy_from_slice(&offset.to_le_bytes());
+            header[32..40].copy_from_slice(&size.to_le_bytes());
+            header[40..44].copy_from_slice(&link.to_le_bytes());
+            header
+        };
+        data.extend_from_slice(&section(0, 0, 0, 0));
+        data.extend_from_slice(&section(6, dynamic_off, dynamic.len() as u64, 2));
+        data.extend_from_slice(&section(3, strtab_off, strtab.len() as u64, 0));
+        data
+    }
+
+    fn write(root: &Path, path: &str, content: &[u8]) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[cfg(unix)]
+    fn symlink(root: &Path, path: &str, target: &str) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::os::unix::fs::symlink(target, path).unwrap();
+    }
+
+    #[cfg(unix)]
+    fn executable(root: &Path, path: &str, content: &[u8]) {
+        use std::os::unix::fs::PermissionsExt;
+        write(root, path, content);
+        std::fs::set_permissions(root.join(path), std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    fn options(target: &str) -> ValidateOptions {
+        ValidateOptions::new(Some(target.to_string()), None, &HashMap::new()).unwrap()
+    }
+
+    fn checks(report: &ValidationReport) -> Vec<(&str, &str)> {
+        report
+            .findings
+            .iter()
+            .map(|f| (f.check, f.path.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_elf_parse_reads_interpreter_and_needed() {
+        let elf = Elf::parse(&fixture_elf(
+            183,
+            Some("/lib/ld-musl-aarch64.so.1"),
+            &["libz.so.1", "libc.so"],
+        ))
+        .unwrap();
+        assert_eq!(elf.arch(), target_arch("aarch64-linux-musl").unwrap());
+        assert_eq!(
+            elf.interpreter.as_deref(),
+            Some("/lib/ld-musl-aarch64.so.1")
+        );
+        assert_eq!(elf.needed, vec!["libz.so.1", "libc.so"]);
+        assert!(Elf::parse(b"\x7fELF").is_none());
+        assert!(Elf::parse(b"#!/bin/sh\n").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_missing_interpreter_and_library_are_errors() {
+        let rootfs = TempDir::new().unwrap();
+        let root = rootfs.path();
+        executable(root, "sbin/init", b"#!/bin/sh\n");
+        executable(
+            root,
+            "usr/bin/app",
+            &fixture_elf(
+                183,
+                Some("/lib/ld-musl-aarch64.so.1"),
+                &["libz.so.1", "libc.so"],
+            ),
+        );
+
+        let report = validate_rootfs(root, &options("aarch64-linux-musl"));
+        assert_eq!(
+            checks(&report),
+            vec![
+                ("missing-interpreter", "/usr/bin/app"),
+                ("missing-library", "/usr/bin/app"),
+                ("missing-library", "/usr/bin/app"),
+            ]
+        );
+        assert_eq!(report.errors().count(), 3);
+
+        // Installed through the musl layout: the loader is a symlink to libc
+        write(root, "usr/lib/libc.so", &fixture_elf(183, None, &[]));
+        symlink(root, "lib/ld-musl-aarch64.so.1", "/usr/lib/libc.so");
+        write(
+            root,
+            "opt/app/lib/libz.so.1",
+            &fixture_elf(183, None, &["libc.so"]),
+        );
+        write(root, "etc/ld-musl-aarch64.path", b"/opt/app/lib\n");
+        let report = validate_rootfs(root, &options("aarch64-linux-musl"));
+        assert!(report.findings.is_empty(), "{:?}", report.findings);
+    }
+
+    #[test]
+    fn test_interpreter_of_another_libc() {
+        let rootfs = TempDir::new().unwrap();
+        let root = rootfs.path();
+        write(root, "sbin/init", &fixture_elf(183, None, &[]));
+        write(
+            root,
+            "lib/ld-linux-aarch64.so.1",
+            &fixture_elf(183, None, &[]),
+        );
+        write(
+            root,
+            "lib/ld-musl-aarch64.so.1",
+            &fixture_elf(183, None, &[]),
+        );
+        write(
+            root,
+            "usr/bin/app",
+            &fixture_elf(183, Some("/lib/ld-linux-aarch64.so.1"), &[]),
+        );
+
+        let report = validate_rootfs(root, &options("aarch64-linux-musl"));
+        assert!(checks(&report).contains(&("interpreter-mismatch", "/usr/bin/app")));
+        assert_eq!(report.errors().count(), 0);
+
+        let report = validate_rootfs(root, &options("aarch64-linux-gnu"));
+        assert!(!checks(&report).contains(&("interpreter-mismatch", "/usr/bin/app")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wrong_architecture_and_firmware() {
+        let rootfs = TempDir::new().unwrap();
+        let root = rootfs.path();
+        executable(root, "sbin/init", &fixture_elf(62, None, &[]));
+        write(root, "lib/firmware/dsp.elf", &fixture_elf(40, None, &[]));
+
+        let report = validate_rootfs(root, &options("aarch64-linux-musl"));
+        assert_eq!(checks(&report), vec![("arch-mismatch", "/sbin/init")]);
+        assert!(report.findings[0].message.contains("x86_64"));
+        assert!(report.findings[0].message.contains("aarch64"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_init_symlinks_and_shells() {
+        let rootfs = TempDir::new().unwrap();
+        let root = rootfs.path();
+        write(root, "sbin/init", b"#!/bin/sh\n");
+        executable(root, "bin/busybox", b"busybox");
+        symlink(root, "bin/sh", "busybox");
+        symlink(root, "usr/bin/env", "/bin/busybox");
+        symlink(root, "etc/mtab", "/proc/mounts");
+        symlink(root, "usr/bin/host-tool", "/home/builder/bin/tool");
+        write(
+            root,
+            "etc/passwd",
+            b"root:x:0:0:root:/root:/bin/sh\nwww:x:33:33::/var/www:/bin/bash\n",
+        );
+
+        let report = validate_rootfs(root, &options("x86_64-linux-musl"));
+        assert_eq!(
+            checks(&report),
+            vec![
+                ("missing-shell", "/etc/passwd"),
+                ("missing-init", "/sbin/init"),
+                ("dangling-symlink", "/usr/bin/host-tool"),
+            ]
+        );
+        assert!(report.findings[1].message.contains("not executable"));
+        assert_eq!(report.errors().count(), 0);
+    }
+
+    #[test]
+    fn test_severity_overrides() {
+        let rootfs = TempDir::new().unwrap();
+        let overrides = HashMap::from([("missing-init".to_string(), "error".to_string())]);
+        let options = ValidateOptions::new(None, Some("/linuxrc".to_string()), &overrides).unwrap();
+        let report = validate_rootfs(rootfs.path(), &options);
+        assert_eq!(checks(&report), vec![("missing-init", "/linuxrc")]);
+        assert_eq!(report.errors().count(), 1);
+
+        let overrides = HashMap::from([("missing-init".to_string(), "ignore".to_string())]);
+        let options = ValidateOptions::new(None, None, &overrides).unwrap();
+        assert!(validate_rootfs(rootfs.path(), &options).findings.is_empty());
+
+        let overrides = HashMap::from([("missing-init".to_string(), "fatal".to_string())]);
+        assert!(ValidateOptions::new(None, None, &overrides).is_err());
+        let overrides = HashMap::from([("missing-kernel".to_string(), "error".to_string())]);
+        assert!(ValidateOptions::new(None, None, &overrides).is_err());
+    }
+}
@@ -0,0 +1,369 @@
+//! Build trees of package configurations
+//!
+//! A package is built in `build/src/<package>` and staged in
+//! `build/destdir/<package>`. When the board or the package options change,
+//! the trees of the previous configuration are parked under
+//! `build/variants/<package>/<options-hash>/` instead of being overwritten,
+//! and are moved back when that configuration is built again, so switching
+//! between boards or toggling an option back does not rebuild the package.
+//!
+//! The most recently used configurations of each package are kept; older
+//! ones are removed when a new configuration is activated.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::builder::{self, STAGING_DIR};
+use crate::core::manifest::Manifest;
+
+/// Directory below `build/` holding parked configurations
+pub const VARIANTS_DIR: &str = "variants";
+
+/// File naming the configuration currently in the build trees
+const ACTIVE_FILE: &str = "active";
+
+/// File describing a configuration
+const VARIANT_FILE: &str = "variant.json";
+
+/// One configuration of a package
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Variant {
+    /// Package name
+    pub package: String,
+    /// Hash of the board and options, naming the configuration
+    pub hash: String,
+    /// Board the package was built for
+    pub board: Option<String>,
+    /// Effective package options
+    pub options: BTreeMap<String, toml::Value>,
+    /// When the configuration was last activated (seconds since the epoch)
+    pub last_used: u64,
+}
+
+impl Variant {
+    /// Current configuration of a package in a project
+    pub fn current(project_dir: &Path, manifest: &Manifest, package: &str) -> Self {
+        let options = crate::core::config::get_package_options(project_dir, manifest, package)
+            .into_iter()
+            .map(|(key, option)| (key, option.value))
+            .collect();
+        Self {
+            package: package.to_string(),
+            hash: builder::package_options_hash(project_dir, manifest, package),
+            board: manifest.board.name.clone(),
+            options,
+            last_used: now(),
+        }
+    }
+
+    /// Options as `key=value`, for display
+    pub fn describe(&self) -> String {
+        let mut parts: Vec<String> = self.board.iter().map(|b| format!("board={b}")).collect();
+        parts.extend(self.options.iter().map(|(k, v)| format!("{k}={v}")));
+        if parts.is_empty() {
+            "defaults".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// A stored configuration as listed by [`VariantStore::list`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredVariant {
+    /// The configuration
+    pub variant: Variant,
+    /// Whether its trees are the ones in use
+    pub active: bool,
+    /// Size of its parked trees in bytes (0 for the active one)
+    pub size_bytes: u64,
+}
+
+/// What activating a configuration did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activation {
+    /// The configuration was already active
+    Unchanged,
+    /// The configuration's trees were restored from an earlier build
+    Restored,
+    /// The configuration has not been built yet
+    New,
+}
+
+/// Parked configurations of the packages in a build directory
+#[derive(Debug, Clone)]
+pub struct VariantStore {
+    build_dir: PathBuf,
+    keep: usize,
+}
+
+impl VariantStore {
+    /// Store in `build_dir` keeping `keep` configurations per package
+    pub fn new(build_dir: &Path, keep: usize) -> Self {
+        Self {
+            build_dir: build_dir.to_path_buf(),
+            keep: keep.max(1),
+        }
+    }
+
+    fn package_dir(&self, package: &str) -> PathBuf {
+        self.build_dir.join(VARIANTS_DIR).join(package)
+    }
+
+    /// Build trees of a package, with their name inside a parked configuration
+    fn trees(&self, package: &str) -> [(PathBuf, &'static str); 3] {
+        [
+            (self.build_dir.join("src").join(package), "src"),
+            (self.build_dir.join(STAGING_DIR).join(package), STAGING_DIR),
+            (
+                self.build_dir
+                    .join("stamps")
+                    .join(format!("{package}.stamp")),
+                "stamp",
+            ),
+        ]
+    }
+
+    /// Hash of the configuration in the build trees of a package
+    pub fn active(&self, package: &str) -> Option<String> {
+        std::fs::read_to_string(self.package_dir(package).join(ACTIVE_FILE))
+            .ok()
+            .map(|hash| hash.trim().to_string())
+    }
+
+    /// Make `variant` the configuration in the build trees of its package
+    ///
+    /// The trees of the previous configuration are parked and those of
+    /// `variant` restored if it was built before. Trees built before
+    /// configurations were tracked are taken to belong to `variant`.
+    pub fn activate(&self, variant: &Variant) -> io::Result<Activation> {
+        let package = &variant.package;
+        let dir = self.package_dir(package);
+        let previous = self.active(package);
+        let activation = match previous {
+            Some(ref hash) if *hash == variant.hash => Activation::Unchanged,
+            None if self.trees(package)[2].0.exists() => Activation::Unchanged,
+            None => Activation::New,
+            Some(ref hash) => {
+                self.move_trees(package, &dir.join(hash), true)?;
+                let stored = dir.join(&variant.hash);
+                if stored.join(VARIANT_FILE).is_file() {
+                    self.move_trees(package, &stored, false)?;
+                    Activation::Restored
+                } else {
+                    Activation::New
+                }
+            }
+        };
+
+        let stored = dir.join(&variant.hash);
+        std::fs::create_dir_all(&stored)?;
+        let json = serde_json::to_string_pretty(variant)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        std::fs::write(stored.join(VARIANT_FILE), json)?;
+        std::fs::write(dir.join(ACTIVE_FILE), &variant.hash)?;
+        self.prune(package)?;
+        Ok(activation)
+    }
+
+    /// Move the build trees of a package into (`park`) or out of `stored`
+    fn move_trees(&self, package: &str, stored: &Path, park: bool) -> io::Result<()> {
+        std::fs::create_dir_all(stored)?;
+        for (tree, name) in self.trees(package) {
+            let (from, to) = if park {
+                (tree, stored.join(name))
+            } else {
+                (stored.join(name), tree)
+            };
+            if from.symlink_metadata().is_err() {
+                continue;
+            }
+            remove(&to)?;
+            if let Some(parent) = to.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::rename(&from, &to)?;
+        }
+        Ok(())
+    }
+
+    /// Remove the least recently used parked configurations of a package
+    /// beyond the number to keep; returns the removed ones
+    pub fn prune(&self, package: &str) -> io::Result<Vec<Variant>> {
+        let active = self.active(package);
+        let mut parked: Vec<Variant> = self
+            .variants(package)
+            .into_iter()
+            .filter(|variant| Some(&variant.hash) != active.as_ref())
+            .collect();
+        parked.sort_by(|a, b| b.last_used.cmp(&a.last_used).then(a.hash.cmp(&b.hash)));
+        let keep_parked = self.keep - usize::from(active.is_some());
+        let removed: Vec<Variant> = parked.into_iter().skip(keep_parked).collect();
+        for variant in &removed {
+            remove(&self.package_dir(package).join(&variant.hash))?;
+        }
+        Ok(removed)
+    }
+
+    /// Recorded configurations of a package
+    fn variants(&self, package: &str) -> Vec<Variant> {
+        let Ok(entries) = std::fs::read_dir(self.package_dir(package)) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| std::fs::read_to_string(entry.path().join(VARIANT_FILE)).ok())
+            .filter_map(|json| serde_json::from_str(&json).ok())
+            .collect()
+    }
+
+    /// All recorded configurations, by package and most recent first
+    pub fn list(&self) -> Vec<StoredVariant> {
+        let Ok(entries) = std::fs::read_dir(self.build_dir.join(VARIANTS_DIR)) else {
+            return Vec::new();
+        };
+        let mut packages: Vec<String> = entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect();
+        packages.sort();
+
+        let mut listed = Vec::new();
+        for package in packages {
+            let active = self.active(&package);
+            let mut variants = self.variants(&package);
+            variants.sort_by(|a, b| b.last_used.cmp(&a.last_used).then(a.hash.cmp(&b.hash)));
+            listed.extend(variants.into_iter().map(|variant| {
+                let is_active = Some(&variant.hash) == active.as_ref();
+                let size_bytes = if is_active {
+                    0
+                } else {
+                    dir_size(&self.package_dir(&package).join(&variant.hash))
+                };
+                StoredVariant {
+                    variant,
+                    active: is_active,
+                    size_bytes,
+                }
+            }));
+        }
+        listed
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    match path.symlink_metadata() {
+        Ok(metadata) if metadata.is_dir() => std::fs::remove_dir_all(path),
+        Ok(_) => std::fs::remove_file(path),
+        Err(_) => Ok(()),
+    }
+}
+
+fn dir_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file() && entry.file_name() != VARIANT_FILE)
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::defaults::DEFAULT_BUILD_VARIANTS;
+    use tempfile::TempDir;
+
+    fn variant(hash: &str, last_used: u64) -> Variant {
+        Variant {
+            package: "app".to_string(),
+            hash: hash.to_string(),
+            board: Some("rpi4".to_string()),
+            options: BTreeMap::from([("debug".to_string(), toml::Value::Boolean(hash == "b"))]),
+            last_used,
+        }
+    }
+
+    fn build(build_dir: &Path, content: &str) {
+        for (path, content) in [
+            ("src/app/main.o", content),
+            ("destdir/app/usr/bin/app", content),
+            ("stamps/app.stamp", "1"),
+        ] {
+            let path = build_dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_switching_back_restores_build_trees() {
+        let temp = TempDir::new().unwrap();
+        let store = VariantStore::new(temp.path(), DEFAULT_BUILD_VARIANTS);
+        let stamp = temp.path().join("stamps/app.stamp");
+
+        // Trees built before tracking belong to the first configuration
+        build(temp.path(), "a");
+        assert_eq!(
+            store.activate(&variant("a", 1)).unwrap(),
+            Activation::Unchanged
+        );
+        assert!(stamp.exists());
+
+        assert_eq!(store.activate(&variant("b", 2)).unwrap(), Activation::New);
+        assert!(!stamp.exists());
+        assert!(!temp.path().join("destdir/app").exists());
+        build(temp.path(), "b");
+
+        assert_eq!(
+            store.activate(&variant("a", 3)).unwrap(),
+            Activation::Restored
+        );
+        assert!(stamp.exists());
+        assert_eq!(
+            std::fs::read_to_string(temp.path().join("destdir/app/usr/bin/app")).unwrap(),
+            "a"
+        );
+        assert_eq!(
+            store.activate(&variant("a", 4)).unwrap(),
+            Activation::Unchanged
+        );
+
+        let listed = store.list();
+        assert_eq!(listed.len(), 2);
+        assert!(listed[0].active);
+        assert_eq!(listed[0].variant.describe(), "board=rpi4, debug=false");
+        assert_eq!(listed[1].variant.hash, "b");
+        assert_eq!(listed[1].size_bytes, 3);
+    }
+
+    #[test]
+    fn test_least_recently_used_configurations_are_pruned() {
+        let temp = TempDir::new().unwrap();
+        let store = VariantStore::new(temp.path(), 2);
+        for (i, hash) in ["a", "b", "c"].into_iter().enumerate() {
+            store.activate(&variant(hash, i as u64)).unwrap();
+            build(temp.path(), hash);
+        }
+
+        let hashes: Vec<String> = store.list().into_iter().map(|s| s.variant.hash).collect();
+        assert_eq!(hashes, vec!["c", "b"]);
+        assert!(!temp.path().join("variants/app/a").exists());
+
+        let store = VariantStore::new(temp.path(), 1);
+        let removed = store.prune("app").unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(store.list().len(), 1);
+    }
+}
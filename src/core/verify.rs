@@ -0,0 +1,449 @@
+//! Package definition lints
+//!
+//! Rules run by `zigroot verify` over a registry package's `metadata.toml`
+//! and version files. They catch mistakes that the structural checks accept,
+//! such as plain-HTTP sources or options no build command reads. Every
+//! finding carries the id of its rule so CI can allowlist rules per package.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::registry::client::PackageIndex;
+
+/// SHA-256 of zero bytes, a sign of a checksum taken from an empty download
+pub const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// How serious a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    /// Fails verification
+    Error,
+    /// Fails verification only with `--deny-warnings`
+    Warning,
+}
+
+/// A named lint rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LintRule {
+    /// Stable identifier used in output and allowlists
+    pub id: &'static str,
+    /// Severity of the rule's findings
+    pub severity: LintSeverity,
+}
+
+/// Source URL uses http instead of https
+pub const INSECURE_URL: LintRule = LintRule {
+    id: "insecure-url",
+    severity: LintSeverity::Warning,
+};
+
+/// Version in a file name differs from the declared version
+pub const VERSION_MISMATCH: LintRule = LintRule {
+    id: "version-mismatch",
+    severity: LintSeverity::Warning,
+};
+
+/// `type = "make"` without make arguments or build steps
+pub const BARE_MAKE: LintRule = LintRule {
+    id: "bare-make",
+    severity: LintSeverity::Warning,
+};
+
+/// Option that no build command reads
+pub const UNUSED_OPTION: LintRule = LintRule {
+    id: "unused-option",
+    severity: LintSeverity::Warning,
+};
+
+/// Empty description or license
+pub const MISSING_METADATA: LintRule = LintRule {
+    id: "missing-metadata",
+    severity: LintSeverity::Warning,
+};
+
+/// Checksum of an empty file
+pub const EMPTY_CHECKSUM: LintRule = LintRule {
+    id: "empty-checksum",
+    severity: LintSeverity::Error,
+};
+
+/// Dependency the registry does not know
+pub const UNKNOWN_DEPENDENCY: LintRule = LintRule {
+    id: "unknown-dependency",
+    severity: LintSeverity::Error,
+};
+
+/// All lint rules
+pub const RULES: &[LintRule] = &[
+    INSECURE_URL,
+    VERSION_MISMATCH,
+    BARE_MAKE,
+    UNUSED_OPTION,
+    MISSING_METADATA,
+    EMPTY_CHECKSUM,
+    UNKNOWN_DEPENDENCY,
+];
+
+/// A problem found by a lint rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintFinding {
+    /// Id of the rule that fired
+    pub rule: &'static str,
+    /// Severity of the rule
+    pub severity: LintSeverity,
+    /// Definition file the finding is about
+    pub file: String,
+    /// What is wrong
+    pub message: String,
+}
+
+impl LintFinding {
+    fn new(rule: LintRule, file: &str, message: String) -> Self {
+        Self {
+            rule: rule.id,
+            severity: rule.severity,
+            file: file.to_string(),
+            message,
+        }
+    }
+}
+
+/// Lint a package's `metadata.toml` and its version files
+///
+/// `versions` holds each version file's name and parsed content. Findings
+/// are sorted by severity, errors first.
+pub fn lint_package(
+    metadata: &toml::Value,
+    versions: &[(String, toml::Value)],
+) -> Vec<LintFinding> {
+    const METADATA: &str = "metadata.toml";
+    let mut findings = Vec::new();
+
+    let package = metadata.get("package");
+    for field in ["description", "license"] {
+        let value = package.and_then(|p| p.get(field)).and_then(|v| v.as_str());
+        if value.is_some_and(|v| v.trim().is_empty()) {
+            findings.push(LintFinding::new(
+                MISSING_METADATA,
+                METADATA,
+                format!("{field} is empty"),
+            ));
+        }
+    }
+
+    lint_build(metadata, &[], METADATA, &mut findings);
+    for (file, version) in versions {
+        lint_version(file, version, &mut findings);
+        lint_build(version, &[metadata], file, &mut findings);
+    }
+
+    findings.sort_by_key(|f| f.severity);
+    findings
+}
+
+/// Lint dependencies against the registry's package index
+pub fn lint_dependencies(metadata: &toml::Value, index: &PackageIndex) -> Vec<LintFinding> {
+    let known: HashSet<&str> = index.packages.iter().map(|p| p.name.as_str()).collect();
+    let Some(package) = metadata.get("package") else {
+        return Vec::new();
+    };
+    ["depends", "dependencies", "build_depends", "requires"]
+        .into_iter()
+        .filter_map(|key| package.get(key).and_then(|v| v.as_array()))
+        .flatten()
+        .filter_map(|v| v.as_str())
+        .map(dependency_name)
+        .filter(|name| !known.contains(name))
+        .map(|name| {
+            LintFinding::new(
+                UNKNOWN_DEPENDENCY,
+                "metadata.toml",
+                format!("dependency '{name}' is not in the registry index"),
+            )
+        })
+        .collect()
+}
+
+/// Package name of a dependency spec such as `zlib>=1.2`
+fn dependency_name(spec: &str) -> &str {
+    let end = spec
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'))
+        .unwrap_or(spec.len());
+    &spec[..end]
+}
+
+/// Source lints of one version file
+fn lint_version(file: &str, version: &toml::Value, findings: &mut Vec<LintFinding>) {
+    let Some(source) = version.get("source") else {
+        return;
+    };
+    let declared = version
+        .get("release")
+        .and_then(|r| r.get("version"))
+        .or_else(|| version.get("version"))
+        .and_then(|v| v.as_str());
+
+    if let (Some(declared), Some(stem)) = (declared, file.strip_suffix(".toml")) {
+        if stem != declared {
+            findings.push(LintFinding::new(
+                VERSION_MISMATCH,
+                file,
+                format!("file is named for {stem} but declares version {declared}"),
+            ));
+        }
+    }
+
+    let urls = ["url"]
+        .into_iter()
+        .filter_map(|key| source.get(key).and_then(|v| v.as_str()))
+        .chain(
+            ["urls", "mirrors"]
+                .into_iter()
+                .filter_map(|key| source.get(key).and_then(|v| v.as_array()))
+                .flatten()
+                .filter_map(|v| v.as_str()),
+        );
+    for url in urls {
+        if url.starts_with("http://") {
+            findings.push(LintFinding::new(
+                INSECURE_URL,
+                file,
+                format!("{url} uses http instead of https"),
+            ));
+        }
+        if let Some(declared) = declared {
+            let name = url.rsplit('/').next().unwrap_or(url);
+            if name.chars().any(|c| c.is_ascii_digit()) && !name.contains(declared) {
+                findings.push(LintFinding::new(
+                    VERSION_MISMATCH,
+                    file,
+                    format!("source file {name} does not contain version {declared}"),
+                ));
+            }
+        }
+    }
+
+    if source
+        .get("sha256")
+        .and_then(|v| v.as_str())
+        .is_some_and(|sha| sha.eq_ignore_ascii_case(EMPTY_SHA256))
+    {
+        findings.push(LintFinding::new(
+            EMPTY_CHECKSUM,
+            file,
+            "sha256 is the checksum of an empty file".to_string(),
+        ));
+    }
+}
+
+/// Build lints of a definition file
+///
+/// `fallbacks` are the definitions the file inherits `[build]` and
+/// `[options]` from, so a version file that only overrides the source is
+/// not reported again.
+fn lint_build(
+    definition: &toml::Value,
+    fallbacks: &[&toml::Value],
+    file: &str,
+    findings: &mut Vec<LintFinding>,
+) {
+    let Some(build) = definition.get("build") else {
+        return;
+    };
+    let install = definition
+        .get("install")
+        .or_else(|| fallbacks.iter().find_map(|f| f.get("install")));
+    let commands = build_commands(build, install);
+
+    if build.get("type").and_then(|v| v.as_str()) == Some("make")
+        && build
+            .get("make_args")
+            .and_then(|v| v.as_array())
+            .map_or(true, Vec::is_empty)
+        && build
+            .get("steps")
+            .and_then(|v| v.as_array())
+            .map_or(true, Vec::is_empty)
+    {
+        findings.push(LintFinding::new(
+            BARE_MAKE,
+            file,
+            "type = \"make\" has no make_args or build steps; check that the source's Makefile supports cross-compiling".to_string(),
+        ));
+    }
+
+    let options = definition
+        .get("options")
+        .or_else(|| fallbacks.iter().find_map(|f| f.get("options")))
+        .and_then(|v| v.as_table());
+    for name in options.into_iter().flat_map(|t| t.keys()) {
+        let variable = option_variable(name);
+        if !commands.iter().any(|c| c.contains(&variable)) {
+            findings.push(LintFinding::new(
+                UNUSED_OPTION,
+                file,
+                format!("option '{name}' is never referenced as ${variable} in build commands"),
+            ));
+        }
+    }
+}
+
+/// Environment variable an option's value is passed in
+fn option_variable(name: &str) -> String {
+    format!(
+        "ZIGROOT_OPT_{}",
+        name.to_ascii_uppercase().replace('-', "_")
+    )
+}
+
+/// Text of every command and argument a build runs
+fn build_commands(build: &toml::Value, install: Option<&toml::Value>) -> Vec<String> {
+    let strings = |value: Option<&toml::Value>| -> Vec<String> {
+        value
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect()
+    };
+    let mut commands = Vec::new();
+    for key in ["configure_args", "make_args", "cmake_args"] {
+        commands.extend(strings(build.get(key)));
+    }
+    for step in build
+        .get("steps")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        commands.extend(step.get("run").and_then(|v| v.as_str()).map(str::to_string));
+        commands.extend(strings(step.get("args")));
+    }
+    commands.extend(
+        install
+            .and_then(|i| i.get("script"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+    );
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(findings: &[LintFinding]) -> Vec<&str> {
+        findings.iter().map(|f| f.rule).collect()
+    }
+
+    #[test]
+    fn test_lint_package_rules() {
+        let metadata: toml::Value = toml::from_str(
+            r#"
+[package]
+name = "foo"
+description = ""
+license = "MIT"
+
+[build]
+type = "make"
+
+[options.ssl]
+type = "bool"
+default = true
+description = "TLS support"
+
+[options.ipv6]
+type = "bool"
+default = true
+description = "IPv6 support"
+"#,
+        )
+        .unwrap();
+        let version: toml::Value = toml::from_str(&format!(
+            r#"
+[release]
+version = "1.2.0"
+
+[source]
+url = "http://example.com/foo-1.1.0.tar.gz"
+sha256 = "{EMPTY_SHA256}"
+"#
+        ))
+        .unwrap();
+
+        let findings = lint_package(&metadata, &[("1.2.0.toml".to_string(), version)]);
+        assert_eq!(findings[0].rule, "empty-checksum");
+        let rules = rules(&findings);
+        for rule in [
+            "missing-metadata",
+            "bare-make",
+            "unused-option",
+            "insecure-url",
+            "version-mismatch",
+        ] {
+            assert!(rules.contains(&rule), "{rule} missing from {rules:?}");
+        }
+        assert_eq!(rules.iter().filter(|r| **r == "unused-option").count(), 2);
+    }
+
+    #[test]
+    fn test_lint_clean_package() {
+        let metadata: toml::Value = toml::from_str(
+            r#"
+[package]
+name = "foo"
+description = "Foo"
+license = "MIT"
+
+[build]
+type = "autotools"
+configure_args = ["--enable-ssl=$ZIGROOT_OPT_SSL"]
+
+[options.ssl]
+type = "bool"
+default = true
+description = "TLS support"
+"#,
+        )
+        .unwrap();
+        let version: toml::Value = toml::from_str(
+            r#"
+version = "1.2.0"
+
+[source]
+url = "https://example.com/foo-1.2.0.tar.gz"
+sha256 = "41cf6794ba4200b839c53531555f0f3998df4cbb01a4d5cb0b94e3ca5e23947d"
+"#,
+        )
+        .unwrap();
+
+        assert!(lint_package(&metadata, &[("1.2.0.toml".to_string(), version)]).is_empty());
+    }
+
+    #[test]
+    fn test_lint_dependencies() {
+        let metadata: toml::Value = toml::from_str(
+            r#"
+[package]
+name = "foo"
+depends = ["zlib>=1.2", "libbar"]
+"#,
+        )
+        .unwrap();
+        let index: PackageIndex = serde_json::from_value(serde_json::json!({
+            "version": 1,
+            "updated": "2026-01-01T00:00:00Z",
+            "packages": [
+                {"name": "zlib", "description": "", "versions": [], "latest": "1.3"}
+            ]
+        }))
+        .unwrap();
+
+        let findings = lint_dependencies(&metadata, &index);
+        assert_eq!(rules(&findings), vec!["unknown-dependency"]);
+        assert!(findings[0].message.contains("libbar"));
+    }
+}
@@ -24,6 +24,14 @@ pub enum VersionError {
         origin: String,
     },
 
+    /// Manifest uses a schema version newer than this zigroot understands
+    #[error("zigroot.toml uses manifest_version {found}, but zigroot {current} supports up to version {supported}. Please update zigroot to continue. Run 'zigroot update --self' to check for updates.")]
+    ManifestTooNew {
+        found: u32,
+        supported: u32,
+        current: String,
+    },
+
     /// Invalid version constraint format
     #[error("Invalid version constraint '{constraint}': {reason}")]
     InvalidConstraint { constraint: String, reason: String },
@@ -134,6 +142,32 @@ pub fn is_newer(v1: &str, v2: &str) -> Result<bool, VersionError> {
     Ok(compare_versions(v1, v2)? == std::cmp::Ordering::Greater)
 }
 
+/// Check whether a concrete version satisfies a manifest version requirement
+///
+/// Unlike Cargo, a bare version (e.g. "1.36.1") in zigroot.toml is an exact
+/// pin. Other requirements use semver syntax (e.g. "^1.36.1", ">=1.2, <2");
+/// anything that doesn't parse, like "latest", only matches itself.
+pub fn satisfies_requirement(version: &str, requirement: &str) -> bool {
+    if version == requirement {
+        return true;
+    }
+    if Version::parse(requirement).is_ok() {
+        return false;
+    }
+    match (Version::parse(version), VersionReq::parse(requirement)) {
+        (Ok(version), Ok(req)) => req.matches(&version),
+        _ => false,
+    }
+}
+
+/// Split a manifest version requirement into its operator and base version
+///
+/// e.g. "^1.36.1" becomes ("^", "1.36.1") and "1.36.1" becomes ("", "1.36.1").
+pub fn split_requirement(requirement: &str) -> (&str, &str) {
+    let base = requirement.trim_start_matches(['^', '~', '=']);
+    (&requirement[..requirement.len() - base.len()], base)
+}
+
 /// Information about a zigroot release
 #[derive(Debug, Clone, PartialEq)]
 pub struct ReleaseInfo {
@@ -232,13 +266,18 @@ pub const GITHUB_RELEASES_API: &str =
 /// This is an async function that queries the GitHub API.
 /// For synchronous usage, use `check_for_updates_sync`.
 pub async fn check_for_updates() -> UpdateCheckResult {
-    check_for_updates_with_client(&reqwest::Client::new()).await
+    check_for_updates_with_client(&http::client()).await
 }
 
 /// Check for updates using a provided HTTP client (for testing)
 pub async fn check_for_updates_with_client(client: &reqwest::Client) -> UpdateCheckResult {
+    check_for_updates_from(client, GITHUB_RELEASES_API).await
+}
+
+/// Check for updates against a releases API URL
+pub async fn check_for_updates_from(client: &reqwest::Client, url: &str) -> UpdateCheckResult {
     let response = match client
-        .get(GITHUB_RELEASES_API)
+        .get(url)
         .header("User-Agent", format!("zigroot/{}", CURRENT_VERSION))
         .header("Accept", "application/vnd.github.v3+json")
         .send()
@@ -331,7 +370,9 @@ pub fn format_update_result(result: &UpdateCheckResult, install_method: &Install
 // Background Update Check
 // ============================================
 
-use std::path::PathBuf;
+use crate::infra::dirs::ZigrootDirs;
+use crate::infra::http;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 
 /// Cache file name for update check results
@@ -340,6 +381,16 @@ const UPDATE_CACHE_FILE: &str = "update_check.json";
 /// How often to check for updates (24 hours)
 const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
 
+/// Environment variable disabling background update checks (e.g. in CI)
+pub const NO_UPDATE_CHECK_ENV: &str = "ZIGROOT_NO_UPDATE_CHECK";
+
+/// Timeout for the background update check request
+const BACKGROUND_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a command waits, once finished, for a background update check
+/// still in progress
+pub const BACKGROUND_CHECK_GRACE: Duration = Duration::from_millis(500);
+
 /// Cached update check result
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct CachedUpdateCheck {
@@ -402,79 +453,126 @@ impl From<CachedResult> for UpdateCheckResult {
 
 /// Get the path to the update cache file
 pub fn get_update_cache_path() -> Option<PathBuf> {
-    dirs::cache_dir().map(|d| d.join("zigroot").join(UPDATE_CACHE_FILE))
+    Some(ZigrootDirs::new().cache_dir().join(UPDATE_CACHE_FILE))
 }
 
 /// Check if we should perform an update check (at most once per day)
 pub fn should_check_for_updates() -> bool {
-    let cache_path = match get_update_cache_path() {
-        Some(p) => p,
-        None => return true, // No cache dir, always check
-    };
-
-    if !cache_path.exists() {
-        return true;
-    }
-
-    // Read the cache file
-    let content = match std::fs::read_to_string(&cache_path) {
-        Ok(c) => c,
-        Err(_) => return true, // Can't read cache, check again
-    };
+    get_update_cache_path().map_or(true, |path| update_check_due(&path))
+}
 
-    let cached: CachedUpdateCheck = match serde_json::from_str(&content) {
-        Ok(c) => c,
-        Err(_) => return true, // Invalid cache, check again
+/// Whether the check cached at `cache_path` is missing or a day old
+fn update_check_due(cache_path: &Path) -> bool {
+    let Some(cached) = read_update_cache(cache_path) else {
+        return true; // No readable cache, check again
     };
 
     // Check if enough time has passed
-    let now = SystemTime::now()
+    let elapsed = unix_now().saturating_sub(cached.checked_at);
+    elapsed >= UPDATE_CHECK_INTERVAL.as_secs()
+}
+
+/// Seconds since the Unix epoch
+fn unix_now() -> u64 {
+    SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .map(|d| d.as_secs())
-        .unwrap_or(0);
-
-    let elapsed = now.saturating_sub(cached.checked_at);
-    elapsed >= UPDATE_CHECK_INTERVAL.as_secs()
+        .unwrap_or(0)
 }
 
 /// Save update check result to cache
 pub fn save_update_cache(result: &UpdateCheckResult) -> Result<(), std::io::Error> {
-    let cache_path = match get_update_cache_path() {
-        Some(p) => p,
-        None => return Ok(()), // No cache dir, skip caching
-    };
+    match get_update_cache_path() {
+        Some(path) => write_update_cache(&path, result),
+        None => Ok(()), // No cache dir, skip caching
+    }
+}
 
+/// Write `result`, checked now, to `cache_path`
+fn write_update_cache(cache_path: &Path, result: &UpdateCheckResult) -> Result<(), std::io::Error> {
     // Create cache directory if needed
     if let Some(parent) = cache_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
 
-    let now = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or(0);
-
     let cached = CachedUpdateCheck {
-        checked_at: now,
+        checked_at: unix_now(),
         result: result.clone().into(),
     };
 
     let content = serde_json::to_string_pretty(&cached)?;
-    std::fs::write(&cache_path, content)?;
+    std::fs::write(cache_path, content)?;
 
     Ok(())
 }
 
 /// Load cached update check result
 pub fn load_update_cache() -> Option<CachedUpdateCheck> {
-    let cache_path = get_update_cache_path()?;
+    read_update_cache(&get_update_cache_path()?)
+}
+
+/// Read the update check cached at `cache_path`
+fn read_update_cache(cache_path: &Path) -> Option<CachedUpdateCheck> {
+    let content = std::fs::read_to_string(cache_path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Check for updates, reusing a result cached within the last day
+///
+/// Cached results from another zigroot version or from a failed check are
+/// refreshed. In offline mode nothing is fetched and the check fails.
+pub async fn check_for_updates_cached(offline: bool) -> UpdateCheckResult {
+    if offline {
+        return UpdateCheckResult::CheckFailed {
+            reason: "Offline mode: skipped checking GitHub releases".to_string(),
+        };
+    }
 
-    if !cache_path.exists() {
+    if let Some(result) = get_update_cache_path().and_then(|path| recent_cached_result(&path)) {
+        if !matches!(result, UpdateCheckResult::CheckFailed { .. }) {
+            return result;
+        }
+    }
+
+    let result = check_for_updates().await;
+    // Caching is best effort
+    let _ = save_update_cache(&result);
+    result
+}
+
+/// Cached result from the last day, if it was made by this zigroot version
+///
+/// Failed checks are returned as well, so they are not retried on every
+/// command.
+fn recent_cached_result(cache_path: &Path) -> Option<UpdateCheckResult> {
+    if update_check_due(cache_path) {
         return None;
     }
+    let cached = read_update_cache(cache_path)?;
+    let current = match &cached.result {
+        CachedResult::UpdateAvailable { current, .. } | CachedResult::UpToDate { current } => {
+            Some(current.as_str())
+        }
+        CachedResult::CheckFailed { .. } => None,
+    };
+    match current {
+        Some(current) if current != CURRENT_VERSION => None,
+        _ => Some(cached.result.into()),
+    }
+}
 
-    let content = std::fs::read_to_string(&cache_path).ok()?;
-    serde_json::from_str(&content).ok()
+/// Whether background update checks are enabled
+///
+/// They are skipped in quiet and JSON modes and when
+/// `ZIGROOT_NO_UPDATE_CHECK` is set to anything but `0` or `false`.
+pub fn update_check_enabled(quiet: bool, json: bool) -> bool {
+    if quiet || json {
+        return false;
+    }
+    match std::env::var(NO_UPDATE_CHECK_ENV) {
+        Ok(value) => matches!(value.trim(), "" | "0" | "false"),
+        Err(_) => true,
+    }
 }
 
 /// Format a non-intrusive update notification
@@ -494,28 +592,41 @@ pub fn format_update_notification(result: &UpdateCheckResult) -> Option<String>
 /// Perform a background update check if needed
 ///
 /// This function:
-/// 1. Checks if enough time has passed since the last check
-/// 2. If so, performs an update check in the background
-/// 3. Caches the result
-/// 4. Returns a notification message if an update is available
+/// 1. Reuses a result cached within the last day, if there is one
+/// 2. Otherwise records the attempt, then checks GitHub with a short
+///    timeout and caches the result
+/// 3. Returns a notification message if an update is available
 ///
-/// This is designed to be called at the start of any command.
+/// This is designed to be spawned at the start of any command and polled
+/// once it completes (see [`crate::cli::Cli::run`]).
 pub async fn background_update_check() -> Option<String> {
-    // Check if we should perform an update check
-    if !should_check_for_updates() {
-        // Check if we have a cached result with an available update
-        if let Some(cached) = load_update_cache() {
-            let result: UpdateCheckResult = cached.result.into();
-            return format_update_notification(&result);
-        }
-        return None;
+    let cache_path = get_update_cache_path()?;
+    background_update_check_from(GITHUB_RELEASES_API, &cache_path).await
+}
+
+/// Background update check against a releases API URL, cached at
+/// `cache_path`
+pub async fn background_update_check_from(url: &str, cache_path: &Path) -> Option<String> {
+    if let Some(result) = recent_cached_result(cache_path) {
+        return format_update_notification(&result);
     }
 
-    // Perform the check
-    let result = check_for_updates().await;
+    // The task is aborted when the command ends, possibly before the
+    // request does; recording the attempt first keeps checks to one a day
+    let pending = UpdateCheckResult::CheckFailed {
+        reason: "Update check did not complete".to_string(),
+    };
+    write_update_cache(cache_path, &pending).ok()?;
+
+    let client = http::client_builder()
+        .ok()?
+        .timeout(BACKGROUND_CHECK_TIMEOUT)
+        .build()
+        .ok()?;
+    let result = check_for_updates_from(&client, url).await;
 
     // Cache the result (ignore errors)
-    let _ = save_update_cache(&result);
+    let _ = write_update_cache(cache_path, &result);
 
     // Return notification if update available
     format_update_notification(&result)
@@ -0,0 +1,168 @@
+//! Watch mode of `zigroot build --watch`
+//!
+//! Decides what a batch of changed files means for the next build: a changed
+//! manifest re-plans the whole build, a changed local package rebuilds the
+//! package and everything depending on it, and a changed overlay only
+//! regenerates the image. Build products are never watched, so a build
+//! cannot trigger itself.
+
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use crate::core::resolver::DependencyGraph;
+
+/// Quiet period after a change before rebuilding
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Overlay directories copied into the rootfs
+pub const OVERLAY_DIRS: &[&str] = &["user/files", "user/scripts"];
+
+/// Directories holding build products, ignored when they change
+const IGNORED_DIRS: &[&str] = &["output", "build", ".git"];
+
+/// A path to watch and whether to watch it recursively
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchPath {
+    /// Watched path
+    pub path: PathBuf,
+    /// Watch everything below the path
+    pub recursive: bool,
+}
+
+/// Paths to watch in a project
+///
+/// The project root is watched non-recursively for `zigroot.toml`, since
+/// editors often replace the file instead of writing it in place.
+pub fn watch_paths(project_dir: &Path) -> Vec<WatchPath> {
+    let mut paths = vec![WatchPath {
+        path: project_dir.to_path_buf(),
+        recursive: false,
+    }];
+    let dirs = std::iter::once("packages").chain(OVERLAY_DIRS.iter().copied());
+    paths.extend(
+        dirs.map(|dir| project_dir.join(dir))
+            .filter(|path| path.is_dir())
+            .map(|path| WatchPath {
+                path,
+                recursive: true,
+            }),
+    );
+    paths
+}
+
+/// What changed in a batch of file events
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchChanges {
+    /// `zigroot.toml` changed
+    pub manifest: bool,
+    /// Local packages whose files changed
+    pub packages: BTreeSet<String>,
+    /// The overlay changed
+    pub overlay: bool,
+}
+
+impl WatchChanges {
+    /// Classify changed paths of the project in `project_dir`
+    pub fn from_paths(project_dir: &Path, paths: &[PathBuf]) -> Self {
+        let mut changes = Self::default();
+        for path in paths {
+            let Ok(relative) = path.strip_prefix(project_dir) else {
+                continue;
+            };
+            let parts: Vec<&str> = relative
+                .components()
+                .filter_map(|c| match c {
+                    Component::Normal(part) => part.to_str(),
+                    _ => None,
+                })
+                .collect();
+            match parts.as_slice() {
+                ["zigroot.toml"] => changes.manifest = true,
+                [first, ..] if IGNORED_DIRS.contains(first) => {}
+                ["packages", name, _, ..] => {
+                    changes.packages.insert((*name).to_string());
+                }
+                _ if OVERLAY_DIRS
+                    .iter()
+                    .any(|dir| relative.starts_with(dir) && relative != Path::new(dir)) =>
+                {
+                    changes.overlay = true;
+                }
+                _ => {}
+            }
+        }
+        changes
+    }
+
+    /// Whether nothing relevant to the build changed
+    pub fn is_empty(&self) -> bool {
+        !self.manifest && self.packages.is_empty() && !self.overlay
+    }
+
+    /// Changed packages and all packages depending on them
+    pub fn affected_packages(&self, graph: &DependencyGraph) -> BTreeSet<String> {
+        let mut affected = self.packages.clone();
+        for name in &self.packages {
+            affected.extend(graph.reverse_dependencies(name));
+        }
+        affected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(paths: &[&str]) -> WatchChanges {
+        let project = Path::new("/work/project");
+        let paths: Vec<PathBuf> = paths.iter().map(|p| project.join(p)).collect();
+        WatchChanges::from_paths(project, &paths)
+    }
+
+    #[test]
+    fn test_changes_are_classified() {
+        let found = changes(&[
+            "zigroot.toml",
+            "packages/app/src/main.c",
+            "packages/lib/package.toml",
+            "user/files/etc/hostname",
+        ]);
+        assert!(found.manifest);
+        assert!(found.overlay);
+        assert_eq!(
+            found.packages.into_iter().collect::<Vec<_>>(),
+            vec!["app", "lib"]
+        );
+    }
+
+    #[test]
+    fn test_build_products_and_unrelated_files_are_ignored() {
+        let found = changes(&[
+            "output/rootfs.img",
+            "build/stamps/app.stamp",
+            "zigroot.lock",
+            "packages",
+            "user/files",
+            "/elsewhere/file",
+        ]);
+        assert!(found.is_empty(), "{found:?}");
+    }
+
+    #[test]
+    fn test_affected_packages_include_reverse_dependencies() {
+        let mut graph = DependencyGraph::new();
+        graph.add_package("app", vec!["lib".to_string()]);
+        graph.add_package("lib", vec![]);
+        graph.add_package("tool", vec![]);
+
+        let found = changes(&["packages/lib/src/lib.c"]);
+        assert_eq!(
+            found
+                .affected_packages(&graph)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["app", "lib"]
+        );
+    }
+}
@@ -31,6 +31,14 @@ pub enum InitError {
     /// Registry error
     #[error("Registry error: {error}")]
     RegistryError { error: String },
+
+    /// No template with this name
+    #[error("Unknown template '{name}'. Available templates: {available}")]
+    UnknownTemplate { name: String, available: String },
+
+    /// Invalid value for a manifest setting
+    #[error("Invalid --{setting}: {error}")]
+    InvalidSetting { setting: String, error: String },
 }
 
 /// Package-related errors
@@ -40,6 +48,12 @@ pub enum PackageError {
     #[error("Package '{name}' not found in registry")]
     NotFound { name: String },
 
+    /// Package is not a dependency of the project
+    #[error(
+        "Package '{name}' is not in the build. Run 'zigroot search {name}' to find it in the registry"
+    )]
+    NotInBuild { name: String },
+
     /// Version constraint cannot be satisfied
     #[error("Version constraint '{constraint}' cannot be satisfied for '{package}'")]
     VersionConflict { package: String, constraint: String },
@@ -69,7 +83,9 @@ pub enum PackageError {
     GitWithoutRef { package: String },
 
     /// URL source without checksum
-    #[error("Package '{package}' specifies url source without sha256 checksum")]
+    #[error(
+        "Package '{package}' specifies url source without a sha256, sha512 or blake3 checksum"
+    )]
     UrlWithoutChecksum { package: String },
 
     /// Parse error
@@ -121,8 +137,31 @@ pub enum DownloadError {
     NetworkError { url: String, error: String },
 
     /// Checksum verification failed
-    #[error("Checksum verification failed for '{file}'")]
-    ChecksumFailed { file: String },
+    #[error(
+        "{algorithm} checksum verification failed for '{file}': expected {expected}, got {actual}"
+    )]
+    ChecksumFailed {
+        file: String,
+        algorithm: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// Declared checksum is not a digest of its algorithm
+    #[error("Malformed {algorithm} checksum '{digest}': expected {expected} hex characters")]
+    MalformedChecksum {
+        algorithm: String,
+        digest: String,
+        expected: usize,
+    },
+
+    /// Checksum names an unsupported algorithm
+    #[error("Unknown checksum algorithm '{algorithm}': expected sha256, sha512 or blake3")]
+    UnknownChecksumAlgorithm { algorithm: String },
+
+    /// More than one checksum declared for the same file
+    #[error("Conflicting checksums {}: set exactly one of sha256, sha512 or blake3", .algorithms.join(", "))]
+    ConflictingChecksums { algorithms: Vec<String> },
 
     /// IO error
     #[error("IO error for '{path}': {error}")]
@@ -131,6 +170,10 @@ pub enum DownloadError {
     /// Max retries exceeded
     #[error("Download failed after {retries} retries: {url}")]
     MaxRetriesExceeded { url: String, retries: u32 },
+
+    /// The primary URL and every mirror failed
+    #[error("All {} URLs failed:\n  {}", .errors.len(), .errors.join("\n  "))]
+    AllUrlsFailed { errors: Vec<String> },
 }
 
 /// Filesystem errors
@@ -151,6 +194,10 @@ pub enum FilesystemError {
     /// Failed to read file
     #[error("Failed to read file '{path}': {error}")]
     ReadFile { path: PathBuf, error: String },
+
+    /// Failed to query free space
+    #[error("Failed to query free space for '{path}': {error}")]
+    SpaceQuery { path: PathBuf, error: String },
 }
 
 /// Build errors
@@ -160,6 +207,18 @@ pub enum BuildError {
     #[error("Build failed for package '{package}': {error}")]
     BuildFailed { package: String, error: String },
 
+    /// Packages failed with `--keep-going`: (package, error) pairs, and
+    /// (package, failed dependency) pairs of the skipped ones
+    #[error("{}", format_package_failures(failed, skipped))]
+    PackagesFailed {
+        failed: Vec<(String, String)>,
+        skipped: Vec<(String, String)>,
+    },
+
+    /// Build stopped with Ctrl+C
+    #[error("Build interrupted while building '{package}'; resume with zigroot build")]
+    Interrupted { package: String },
+
     /// Toolchain not found
     #[error("Toolchain not found: {toolchain}")]
     ToolchainNotFound { toolchain: String },
@@ -167,6 +226,36 @@ pub enum BuildError {
     /// Configuration error
     #[error("Configuration error: {message}")]
     ConfigError { message: String },
+
+    /// Not enough free disk space
+    #[error(
+        "Not enough disk space on {mount_point} (for {paths}): need about {}, only {} available.\n\
+         Free up space, reduce build.rootfs_size, or pass --skip-space-check.",
+        crate::core::size::format_bytes(*required),
+        crate::core::size::format_bytes(*available)
+    )]
+    InsufficientSpace {
+        mount_point: String,
+        paths: String,
+        required: u64,
+        available: u64,
+    },
+}
+
+/// Failures of a `--keep-going` build, one package per line
+fn format_package_failures(failed: &[(String, String)], skipped: &[(String, String)]) -> String {
+    let failures = failed.iter().map(|(package, error)| {
+        let error = error.trim_end().replace('\n', "\n    ");
+        format!("\n  {package}: {error}")
+    });
+    let skips = skipped.iter().map(|(package, dependency)| {
+        format!("\n  {package}: skipped due to failed dependency '{dependency}'")
+    });
+    format!(
+        "{} package(s) failed to build:{}",
+        failed.len(),
+        failures.chain(skips).collect::<String>()
+    )
 }
 
 /// Option validation errors
@@ -216,6 +305,10 @@ pub enum OptionError {
         pattern: String,
         error: String,
     },
+
+    /// Violated `requires`/`conflicts_with` relations
+    #[error("Invalid option combination: {}", .violations.join("; "))]
+    Relationships { violations: Vec<String> },
 }
 
 /// Top-level zigroot error type
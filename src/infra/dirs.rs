@@ -8,10 +8,13 @@
 //! - `ZIGROOT_CONFIG_DIR` - Override config directory
 //! - `ZIGROOT_DATA_DIR` - Override data directory
 //!
+//! The `cache.dir` setting of the global config also moves the cache
+//! directory, with lower priority than `ZIGROOT_CACHE_DIR`.
+//!
 //! **Validates: Requirements 32.1-32.4**
 
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Environment variable names for directory overrides
 pub const ENV_CACHE_DIR: &str = "ZIGROOT_CACHE_DIR";
@@ -24,6 +27,10 @@ const APP_NAME: &str = "zigroot";
 /// Subdirectory names
 const DOWNLOADS_SUBDIR: &str = "downloads";
 const BUILD_CACHE_SUBDIR: &str = "build-cache";
+const HOST_TOOLS_SUBDIR: &str = "host-tools";
+
+/// Name of the global config file in the config directory
+const CONFIG_FILE: &str = "config.toml";
 
 /// Platform-specific directory provider for zigroot
 ///
@@ -42,9 +49,10 @@ impl ZigrootDirs {
     /// Checks environment variables first, then falls back to platform defaults.
     #[must_use]
     pub fn new() -> Self {
+        let config_dir = Self::resolve_config_dir();
         Self {
-            cache_dir: Self::resolve_cache_dir(),
-            config_dir: Self::resolve_config_dir(),
+            cache_dir: Self::resolve_cache_dir(&config_dir),
+            config_dir,
             data_dir: Self::resolve_data_dir(),
         }
     }
@@ -88,6 +96,15 @@ impl ZigrootDirs {
         self.data_dir.join(DOWNLOADS_SUBDIR)
     }
 
+    /// Get the host tools directory path
+    ///
+    /// Used for prebuilt host tools provisioned for package builds.
+    /// Located under the data directory.
+    #[must_use]
+    pub fn host_tools_dir(&self) -> PathBuf {
+        self.data_dir.join(HOST_TOOLS_SUBDIR)
+    }
+
     /// Get the build cache directory path
     ///
     /// Used for content-addressable build cache.
@@ -102,18 +119,33 @@ impl ZigrootDirs {
     /// Returns the path to `config.toml` in the config directory.
     #[must_use]
     pub fn global_config_path(&self) -> PathBuf {
-        self.config_dir.join("config.toml")
+        self.config_dir.join(CONFIG_FILE)
     }
 
-    /// Resolve cache directory from environment or platform default
-    fn resolve_cache_dir() -> PathBuf {
+    /// Resolve cache directory from environment, the global config's
+    /// `cache.dir` or platform default
+    fn resolve_cache_dir(config_dir: &Path) -> PathBuf {
         if let Ok(path) = env::var(ENV_CACHE_DIR) {
             return PathBuf::from(path);
         }
+        if let Some(path) = Self::configured_cache_dir(config_dir) {
+            return path;
+        }
 
         Self::platform_cache_dir()
     }
 
+    /// `cache.dir` of the global config file in `config_dir`, if set
+    ///
+    /// The file is read directly: loading the global config needs the
+    /// directories resolved here.
+    fn configured_cache_dir(config_dir: &Path) -> Option<PathBuf> {
+        let content = std::fs::read_to_string(config_dir.join(CONFIG_FILE)).ok()?;
+        let config: toml::Table = content.parse().ok()?;
+        let dir = config.get("cache")?.get("dir")?.as_str()?;
+        (!dir.trim().is_empty()).then(|| PathBuf::from(dir))
+    }
+
     /// Resolve config directory from environment or platform default
     fn resolve_config_dir() -> PathBuf {
         if let Ok(path) = env::var(ENV_CONFIG_DIR) {
@@ -210,4 +242,23 @@ mod tests {
         assert!(dirs.global_config_path().starts_with(dirs.config_dir()));
         assert!(dirs.global_config_path().ends_with("config.toml"));
     }
+
+    #[test]
+    fn test_configured_cache_dir() {
+        let temp = tempfile::TempDir::new().unwrap();
+        assert_eq!(ZigrootDirs::configured_cache_dir(temp.path()), None);
+
+        std::fs::write(
+            temp.path().join("config.toml"),
+            "[cache]\ndir = \"/srv/zigroot-cache\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            ZigrootDirs::configured_cache_dir(temp.path()),
+            Some(PathBuf::from("/srv/zigroot-cache"))
+        );
+
+        std::fs::write(temp.path().join("config.toml"), "[cache]\nttl = 60\n").unwrap();
+        assert_eq!(ZigrootDirs::configured_cache_dir(temp.path()), None);
+    }
 }
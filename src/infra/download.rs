@@ -2,18 +2,24 @@
 //!
 //! Handles downloading files with progress reporting, checksum verification,
 //! parallel downloads, and retry with exponential backoff.
+//!
+//! A [`RateLimiter`] shared by every clone of a [`DownloadManager`] caps
+//! the combined throughput of all its downloads.
 
 use futures::StreamExt;
-use sha2::{Digest, Sha256};
+use sha2::{Digest, Sha256, Sha512};
+use std::fmt;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::time::Duration;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 use tokio::sync::Semaphore;
 
 use crate::config::defaults;
 use crate::error::DownloadError;
+use crate::infra::http;
 
 /// Progress callback type for download progress reporting
 pub type ProgressCallback = Box<dyn Fn(u64, u64) + Send + Sync>;
@@ -25,8 +31,11 @@ pub struct DownloadResult {
     pub path: PathBuf,
     /// Size in bytes
     pub size: u64,
-    /// SHA256 checksum of the downloaded content
+    /// Hex digest of the downloaded content (SHA256 unless verified
+    /// against a checksum of another algorithm)
     pub checksum: String,
+    /// URL the file was downloaded from
+    pub url: String,
 }
 
 /// Download manager for fetching files with retry and parallel support
@@ -38,35 +47,41 @@ pub struct DownloadManager {
     max_retries: u32,
     /// Base delay for exponential backoff (in milliseconds)
     base_delay_ms: u64,
+    /// Limit of the combined throughput, shared by all clones
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl DownloadManager {
     /// Create a new download manager
     pub fn new() -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .timeout(Duration::from_secs(300))
-                .connect_timeout(Duration::from_secs(30))
-                .build()
-                .unwrap_or_else(|_| reqwest::Client::new()),
+            client: http::client(),
             max_retries: defaults::MAX_DOWNLOAD_RETRIES,
             base_delay_ms: 1000,
+            rate_limiter: None,
         }
     }
 
     /// Create a download manager with custom settings
     pub fn with_config(max_retries: u32, base_delay_ms: u64) -> Self {
         Self {
-            client: reqwest::Client::builder()
-                .timeout(Duration::from_secs(300))
-                .connect_timeout(Duration::from_secs(30))
-                .build()
-                .unwrap_or_else(|_| reqwest::Client::new()),
+            client: http::client(),
             max_retries,
             base_delay_ms,
+            rate_limiter: None,
         }
     }
 
+    /// Limit the combined throughput of all downloads to `bytes_per_sec`
+    ///
+    /// Clones of the manager share the limit, so concurrent downloads
+    /// together stay below it.
+    #[must_use]
+    pub fn with_rate_limit(mut self, bytes_per_sec: u64) -> Self {
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(bytes_per_sec)));
+        self
+    }
+
     /// Get the HTTP client
     pub fn client(&self) -> &reqwest::Client {
         &self.client
@@ -91,6 +106,18 @@ impl DownloadManager {
         url: &str,
         dest: &Path,
         progress: Option<ProgressCallback>,
+    ) -> Result<DownloadResult, DownloadError> {
+        self.download_hashed(url, dest, ChecksumAlgorithm::Sha256, progress)
+            .await
+    }
+
+    /// Download with retry logic, hashing the content with `algorithm`
+    async fn download_hashed(
+        &self,
+        url: &str,
+        dest: &Path,
+        algorithm: ChecksumAlgorithm,
+        progress: Option<ProgressCallback>,
     ) -> Result<DownloadResult, DownloadError> {
         let mut attempts = 0;
         let mut last_error = None;
@@ -99,7 +126,10 @@ impl DownloadManager {
         while attempts < self.max_retries {
             attempts += 1;
 
-            match self.download_once(url, dest, progress.as_ref()).await {
+            match self
+                .download_once(url, dest, algorithm, progress.as_ref())
+                .await
+            {
                 Ok(result) => return Ok(result),
                 Err(e) => {
                     last_error = Some(e);
@@ -129,6 +159,7 @@ impl DownloadManager {
         &self,
         url: &str,
         dest: &Path,
+        algorithm: ChecksumAlgorithm,
         progress: Option<&ProgressCallback>,
     ) -> Result<DownloadResult, DownloadError> {
         let response =
@@ -167,7 +198,7 @@ impl DownloadManager {
                 error: e.to_string(),
             })?;
 
-        let mut hasher = Sha256::new();
+        let mut hasher = Hasher::new(algorithm);
         let mut downloaded: u64 = 0;
         let mut stream = response.bytes_stream();
 
@@ -187,6 +218,10 @@ impl DownloadManager {
             hasher.update(&chunk);
             downloaded += chunk.len() as u64;
 
+            if let Some(ref limiter) = self.rate_limiter {
+                limiter.acquire(chunk.len() as u64).await;
+            }
+
             if let Some(cb) = progress {
                 cb(downloaded, total_size);
             }
@@ -197,12 +232,13 @@ impl DownloadManager {
             error: e.to_string(),
         })?;
 
-        let checksum = hex::encode(hasher.finalize());
+        let checksum = hasher.finalize();
 
         Ok(DownloadResult {
             path: dest.to_path_buf(),
             size: downloaded,
             checksum,
+            url: url.to_string(),
         })
     }
 
@@ -211,7 +247,7 @@ impl DownloadManager {
     /// # Arguments
     /// * `url` - URL to download from
     /// * `dest` - Destination path
-    /// * `expected_checksum` - Expected SHA256 checksum
+    /// * `expected_checksum` - Expected checksum, in any supported algorithm
     /// * `progress` - Optional progress callback
     ///
     /// # Returns
@@ -220,23 +256,59 @@ impl DownloadManager {
         &self,
         url: &str,
         dest: &Path,
-        expected_checksum: &str,
+        expected_checksum: &Checksum,
         progress: Option<ProgressCallback>,
     ) -> Result<DownloadResult, DownloadError> {
-        let result = self.download(url, dest, progress).await?;
+        let result = self
+            .download_hashed(url, dest, expected_checksum.algorithm, progress)
+            .await?;
 
-        if result.checksum.to_lowercase() != expected_checksum.to_lowercase() {
+        if result.checksum != expected_checksum.digest {
             // Delete corrupted download
             let _ = tokio::fs::remove_file(dest).await;
 
-            return Err(DownloadError::ChecksumFailed {
-                file: dest.display().to_string(),
-            });
+            return Err(expected_checksum.mismatch(dest, &result.checksum));
         }
 
         Ok(result)
     }
 
+    /// Download a file from the first of `urls` that serves it
+    ///
+    /// Each URL is retried as by [`Self::download`]. With a checksum, a URL
+    /// serving other content counts as failed and the next one is tried;
+    /// the checksum is the same for every URL. Only when all URLs failed is
+    /// an error returned, listing each of them. `progress` restarts from
+    /// zero with every attempt.
+    pub async fn download_from(
+        &self,
+        urls: &[String],
+        dest: &Path,
+        checksum: Option<&Checksum>,
+        progress: Option<ProgressCallback>,
+    ) -> Result<DownloadResult, DownloadError> {
+        let progress = progress.map(Arc::new);
+        let mut errors = Vec::new();
+        for url in urls {
+            let callback = progress.clone().map(|progress| -> ProgressCallback {
+                Box::new(move |downloaded, total| progress(downloaded, total))
+            });
+            let result = match checksum {
+                Some(checksum) => self.download_verified(url, dest, checksum, callback).await,
+                None => self.download(url, dest, callback).await,
+            };
+            match result {
+                Ok(result) => return Ok(result),
+                Err(e) if urls.len() == 1 => return Err(e),
+                Err(e) => {
+                    tracing::warn!("Download from {url} failed, trying the next mirror: {e}");
+                    errors.push(format!("{url}: {e}"));
+                }
+            }
+        }
+        Err(DownloadError::AllUrlsFailed { errors })
+    }
+
     /// Download multiple files in parallel
     ///
     /// # Arguments
@@ -247,7 +319,7 @@ impl DownloadManager {
     /// Vector of results for each download
     pub async fn download_parallel(
         &self,
-        downloads: Vec<(String, PathBuf, String)>,
+        downloads: Vec<(String, PathBuf, Checksum)>,
         max_parallel: usize,
     ) -> Vec<Result<DownloadResult, DownloadError>> {
         let semaphore = Arc::new(Semaphore::new(max_parallel));
@@ -279,6 +351,148 @@ impl DownloadManager {
 
         results
     }
+
+    /// Check that a URL is reachable with a HEAD request
+    pub async fn probe(&self, url: &str, timeout: Duration) -> UrlStatus {
+        match self.client.head(url).timeout(timeout).send().await {
+            Ok(response) => {
+                let status = response.status();
+                if status.is_success() {
+                    UrlStatus::Reachable
+                } else if status == reqwest::StatusCode::METHOD_NOT_ALLOWED
+                    || status == reqwest::StatusCode::NOT_IMPLEMENTED
+                {
+                    UrlStatus::HeadUnsupported(status.as_u16())
+                } else {
+                    UrlStatus::Unreachable(format!("HTTP {status}"))
+                }
+            }
+            Err(e) if e.is_timeout() => {
+                UrlStatus::Unreachable(format!("no response within {}s", timeout.as_secs()))
+            }
+            Err(e) => UrlStatus::Unreachable(e.to_string()),
+        }
+    }
+}
+
+/// URLs to download a source from, in the order to try them
+///
+/// The primary `url` comes first, then its `mirrors`, then the file name
+/// under the `source_mirror_prefix` of the global config, if set. With
+/// `prefer_mirror` the mirrors are tried before the primary. Duplicates
+/// are dropped.
+pub fn source_urls(
+    url: &str,
+    mirrors: &[String],
+    mirror_prefix: Option<&str>,
+    prefer_mirror: bool,
+) -> Vec<String> {
+    let mut alternatives: Vec<String> = mirrors.to_vec();
+    if let Some(prefix) = mirror_prefix {
+        let file_name = url.rsplit('/').next().unwrap_or(url);
+        alternatives.push(format!("{}/{file_name}", prefix.trim_end_matches('/')));
+    }
+
+    let mut ordered = Vec::with_capacity(alternatives.len() + 1);
+    if !prefer_mirror {
+        ordered.push(url.to_string());
+    }
+    ordered.extend(alternatives);
+    if prefer_mirror {
+        ordered.push(url.to_string());
+    }
+
+    let mut urls: Vec<String> = Vec::with_capacity(ordered.len());
+    for candidate in ordered {
+        if !urls.contains(&candidate) {
+            urls.push(candidate);
+        }
+    }
+    urls
+}
+
+/// Token bucket limiting a byte rate
+///
+/// The bucket holds at most one second worth of bytes. Taking more bytes
+/// than it holds leaves it in debt, and the taker must wait until the debt
+/// is refilled; later takers also wait for earlier debt, so the rate holds
+/// across concurrent takers.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    /// Bytes added per second
+    rate: f64,
+    /// Bytes available, negative while in debt
+    tokens: f64,
+    /// When `tokens` was last refilled
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket for `bytes_per_sec`
+    #[allow(clippy::cast_precision_loss)]
+    pub fn new(bytes_per_sec: u64, now: Instant) -> Self {
+        let rate = bytes_per_sec.max(1) as f64;
+        Self {
+            rate,
+            tokens: rate,
+            updated: now,
+        }
+    }
+
+    /// Take `bytes` at `now`, returning how long to wait before using them
+    #[allow(clippy::cast_precision_loss)]
+    pub fn take(&mut self, bytes: u64, now: Instant) -> Duration {
+        if now > self.updated {
+            let elapsed = now.duration_since(self.updated).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+            self.updated = now;
+        }
+        self.tokens -= bytes as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// Rate limit shared by concurrent downloads
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Bucket of all downloads
+    bucket: Mutex<TokenBucket>,
+}
+
+impl RateLimiter {
+    /// Create a limiter for `bytes_per_sec`
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bucket: Mutex::new(TokenBucket::new(bytes_per_sec, Instant::now())),
+        }
+    }
+
+    /// Wait until `bytes` may be used
+    pub async fn acquire(&self, bytes: u64) {
+        let wait = self
+            .bucket
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take(bytes, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Result of probing a URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UrlStatus {
+    /// Server answered with a success status
+    Reachable,
+    /// Server rejected the HEAD method; reachability is unknown
+    HeadUnsupported(u16),
+    /// Request failed or the server returned an error status
+    Unreachable(String),
 }
 
 impl Default for DownloadManager {
@@ -287,6 +501,206 @@ impl Default for DownloadManager {
     }
 }
 
+/// Hash algorithm of a checksum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChecksumAlgorithm {
+    /// SHA-256
+    Sha256,
+    /// SHA-512
+    Sha512,
+    /// BLAKE3 with the default 32-byte output
+    Blake3,
+}
+
+impl ChecksumAlgorithm {
+    /// All algorithms, in the order checksum fields are listed
+    pub const ALL: [Self; 3] = [Self::Sha256, Self::Sha512, Self::Blake3];
+
+    /// Name of the algorithm, as used for checksum fields and in lock files
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+            Self::Blake3 => "blake3",
+        }
+    }
+
+    /// Algorithm of a checksum field name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == name)
+    }
+
+    /// Number of hex characters in a digest
+    pub fn hex_len(self) -> usize {
+        match self {
+            Self::Sha256 | Self::Blake3 => 64,
+            Self::Sha512 => 128,
+        }
+    }
+}
+
+impl fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Expected digest of a file
+///
+/// Written as `<algorithm>:<digest>` in lock files; a bare digest is SHA256.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Checksum {
+    /// Hash algorithm
+    pub algorithm: ChecksumAlgorithm,
+    /// Lowercase hex digest
+    pub digest: String,
+}
+
+impl Checksum {
+    /// Checksum from a hex digest, checking its length for the algorithm
+    pub fn new(algorithm: ChecksumAlgorithm, digest: &str) -> Result<Self, DownloadError> {
+        if digest.len() != algorithm.hex_len() || !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(DownloadError::MalformedChecksum {
+                algorithm: algorithm.name().to_string(),
+                digest: digest.to_string(),
+                expected: algorithm.hex_len(),
+            });
+        }
+        Ok(Self {
+            algorithm,
+            digest: digest.to_ascii_lowercase(),
+        })
+    }
+
+    /// The checksum declared by optional `sha256`, `sha512` and `blake3`
+    /// fields; `None` if no field is set
+    pub fn from_fields(
+        sha256: Option<&str>,
+        sha512: Option<&str>,
+        blake3: Option<&str>,
+    ) -> Result<Option<Self>, DownloadError> {
+        let declared: Vec<(ChecksumAlgorithm, &str)> = ChecksumAlgorithm::ALL
+            .into_iter()
+            .zip([sha256, sha512, blake3])
+            .filter_map(|(algorithm, digest)| Some((algorithm, digest?)))
+            .collect();
+        match declared.as_slice() {
+            [] => Ok(None),
+            [(algorithm, digest)] => Self::new(*algorithm, digest).map(Some),
+            _ => Err(DownloadError::ConflictingChecksums {
+                algorithms: declared
+                    .iter()
+                    .map(|(algorithm, _)| algorithm.name().to_string())
+                    .collect(),
+            }),
+        }
+    }
+
+    /// Checksum of data
+    pub fn compute(algorithm: ChecksumAlgorithm, data: &[u8]) -> Self {
+        let mut hasher = Hasher::new(algorithm);
+        hasher.update(data);
+        Self {
+            algorithm,
+            digest: hasher.finalize(),
+        }
+    }
+
+    /// Whether a file has this checksum
+    pub fn matches_file(&self, path: &Path) -> Result<bool, DownloadError> {
+        Ok(self.file_digest(path)? == self.digest)
+    }
+
+    /// Fail unless a file has this checksum, naming the algorithm
+    pub fn verify_file(&self, path: &Path) -> Result<(), DownloadError> {
+        let actual = self.file_digest(path)?;
+        if actual == self.digest {
+            Ok(())
+        } else {
+            Err(self.mismatch(path, &actual))
+        }
+    }
+
+    /// Digest of a file in this checksum's algorithm
+    fn file_digest(&self, path: &Path) -> Result<String, DownloadError> {
+        let content = std::fs::read(path).map_err(|e| DownloadError::IoError {
+            path: path.to_path_buf(),
+            error: e.to_string(),
+        })?;
+        Ok(Self::compute(self.algorithm, &content).digest)
+    }
+
+    fn mismatch(&self, path: &Path, actual: &str) -> DownloadError {
+        DownloadError::ChecksumFailed {
+            file: path.display().to_string(),
+            algorithm: self.algorithm.name().to_string(),
+            expected: self.digest.clone(),
+            actual: actual.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.digest)
+    }
+}
+
+impl FromStr for Checksum {
+    type Err = DownloadError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once(':') {
+            Some((name, digest)) => {
+                let algorithm = ChecksumAlgorithm::from_name(&name.to_ascii_lowercase())
+                    .ok_or_else(|| DownloadError::UnknownChecksumAlgorithm {
+                        algorithm: name.to_string(),
+                    })?;
+                Self::new(algorithm, digest)
+            }
+            None => Self::new(ChecksumAlgorithm::Sha256, value),
+        }
+    }
+}
+
+/// Incremental hasher of any checksum algorithm
+enum Hasher {
+    Sha256(Sha256),
+    Sha512(Sha512),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+            ChecksumAlgorithm::Sha512 => Self::Sha512(Sha512::new()),
+            ChecksumAlgorithm::Blake3 => Self::Blake3(Box::default()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Sha512(hasher) => hasher.update(data),
+            Self::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    /// Lowercase hex digest
+    fn finalize(self) -> String {
+        match self {
+            Self::Sha256(hasher) => hex::encode(hasher.finalize()),
+            Self::Sha512(hasher) => hex::encode(hasher.finalize()),
+            Self::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+        }
+    }
+}
+
 /// Verify SHA256 checksum of a file
 pub fn verify_checksum(path: &Path, expected: &str) -> Result<bool, DownloadError> {
     let content = std::fs::read(path).map_err(|e| DownloadError::IoError {
@@ -409,6 +823,100 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_checksum_hex_length_per_algorithm() {
+        for (algorithm, len) in [
+            (ChecksumAlgorithm::Sha256, 64),
+            (ChecksumAlgorithm::Sha512, 128),
+            (ChecksumAlgorithm::Blake3, 64),
+        ] {
+            assert_eq!(algorithm.hex_len(), len);
+            assert!(Checksum::new(algorithm, &"a".repeat(len)).is_ok());
+            for bad in ["a".repeat(len - 1), "a".repeat(len + 1), "g".repeat(len)] {
+                let error = Checksum::new(algorithm, &bad).unwrap_err().to_string();
+                assert!(
+                    error.contains(&format!("Malformed {algorithm} checksum")),
+                    "{error}"
+                );
+                assert!(error.contains(&format!("{len} hex characters")), "{error}");
+            }
+        }
+        let upper = Checksum::new(ChecksumAlgorithm::Sha512, &"AB".repeat(64)).unwrap();
+        assert_eq!(upper.digest, "ab".repeat(64));
+    }
+
+    #[test]
+    fn test_checksum_known_digests() {
+        let data = b"hello world";
+        assert_eq!(
+            Checksum::compute(ChecksumAlgorithm::Sha256, data).digest,
+            compute_checksum(data)
+        );
+        assert_eq!(
+            Checksum::compute(ChecksumAlgorithm::Sha512, data).digest,
+            "309ecc489c12d6eb4cc40f50c902f2b4d0ed77ee511a7c7a9bcd3ca86d4cd86f\
+             989dd35bc5ff499670da34255b45b0cfd830e81f605dcf7dc5542e93ae9cd76f"
+        );
+        assert_eq!(
+            Checksum::compute(ChecksumAlgorithm::Blake3, data).digest,
+            "d74981efa70a0c880b8d8c1985d075dbcbf679b99a5f9914e5aaf96b831a9e24"
+        );
+    }
+
+    #[test]
+    fn test_checksum_parse_and_display() {
+        let blake3 = format!("blake3:{}", "1".repeat(64));
+        let checksum: Checksum = blake3.parse().unwrap();
+        assert_eq!(checksum.algorithm, ChecksumAlgorithm::Blake3);
+        assert_eq!(checksum.to_string(), blake3);
+
+        // A bare digest is SHA256
+        let bare: Checksum = "2".repeat(64).parse().unwrap();
+        assert_eq!(bare.algorithm, ChecksumAlgorithm::Sha256);
+
+        assert!("md5:abc"
+            .parse::<Checksum>()
+            .unwrap_err()
+            .to_string()
+            .contains("Unknown"));
+        assert!(format!("sha512:{}", "1".repeat(64))
+            .parse::<Checksum>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_checksum_from_fields_requires_one() {
+        let digest = "3".repeat(128);
+        assert_eq!(Checksum::from_fields(None, None, None).unwrap(), None);
+        let checksum = Checksum::from_fields(None, Some(&digest), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(checksum.algorithm, ChecksumAlgorithm::Sha512);
+
+        let error = Checksum::from_fields(Some(&digest[..64]), None, Some(&digest[..64]))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("sha256, blake3"), "{error}");
+    }
+
+    #[test]
+    fn test_checksum_verify_file_names_algorithm() {
+        let temp = TempDir::new().unwrap();
+        let file_path = temp.path().join("test.txt");
+        std::fs::write(&file_path, b"hello world").unwrap();
+
+        let checksum = Checksum::compute(ChecksumAlgorithm::Blake3, b"hello world");
+        assert!(checksum.matches_file(&file_path).unwrap());
+        checksum.verify_file(&file_path).unwrap();
+
+        std::fs::write(&file_path, b"changed").unwrap();
+        let error = checksum.verify_file(&file_path).unwrap_err().to_string();
+        assert!(
+            error.starts_with("blake3 checksum verification failed"),
+            "{error}"
+        );
+    }
+
     // ============================================
     // Unit Tests - Download manager creation
     // ============================================
@@ -496,7 +1004,7 @@ mod tests {
     async fn test_download_verified_success() {
         let mock_server = MockServer::start().await;
         let content = b"verified content";
-        let checksum = compute_checksum(content);
+        let checksum = Checksum::compute(ChecksumAlgorithm::Sha512, content);
 
         Mock::given(method("GET"))
             .and(path("/verified.txt"))
@@ -539,14 +1047,18 @@ mod tests {
             .download_verified(
                 &format!("{}/wrong.txt", mock_server.uri()),
                 &dest,
-                "0000000000000000000000000000000000000000000000000000000000000000",
+                &Checksum::new(ChecksumAlgorithm::Sha256, &"0".repeat(64)).unwrap(),
                 None,
             )
             .await;
 
         assert!(result.is_err());
         match result.unwrap_err() {
-            DownloadError::ChecksumFailed { .. } => {}
+            e @ DownloadError::ChecksumFailed { .. } => {
+                assert!(e
+                    .to_string()
+                    .starts_with("sha256 checksum verification failed"));
+            }
             e => panic!("Expected ChecksumFailed error, got: {e:?}"),
         }
 
@@ -573,7 +1085,7 @@ mod tests {
             .download_verified(
                 &format!("{}/corrupted.txt", mock_server.uri()),
                 &dest,
-                "wrong_checksum_that_will_not_match_anything_at_all_ever",
+                &Checksum::compute(ChecksumAlgorithm::Blake3, b"other content"),
                 None,
             )
             .await;
@@ -587,7 +1099,7 @@ mod tests {
     async fn test_download_retry_on_failure() {
         let mock_server = MockServer::start().await;
         let content = b"retry content";
-        let checksum = compute_checksum(content);
+        let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, content);
 
         // First two requests fail, third succeeds
         Mock::given(method("GET"))
@@ -672,7 +1184,7 @@ mod tests {
                 (
                     format!("{}/{name}", mock_server.uri()),
                     temp.path().join(name),
-                    compute_checksum(*content),
+                    Checksum::compute(ChecksumAlgorithm::Sha256, *content),
                 )
             })
             .collect();
@@ -721,7 +1233,7 @@ mod tests {
             .download_verified(
                 &format!("{}/existing.txt", mock_server.uri()),
                 &dest,
-                &checksum,
+                &checksum.parse().unwrap(),
                 None,
             )
             .await;
@@ -729,6 +1241,116 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_download_from_falls_back_to_mirror() {
+        let primary = MockServer::start().await;
+        let mirror = MockServer::start().await;
+        let content = b"source tarball";
+        Mock::given(method("GET"))
+            .and(path("/src.tar.gz"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&primary)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/src.tar.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(content.to_vec()))
+            .mount(&mirror)
+            .await;
+
+        let temp = TempDir::new().unwrap();
+        let dest = temp.path().join("src.tar.gz");
+        let manager = DownloadManager::with_config(2, 10);
+        let urls = vec![
+            format!("{}/src.tar.gz", primary.uri()),
+            format!("{}/src.tar.gz", mirror.uri()),
+        ];
+        let checksum = Checksum::compute(ChecksumAlgorithm::Blake3, content);
+
+        let result = manager
+            .download_from(&urls, &dest, Some(&checksum), None)
+            .await
+            .unwrap();
+        assert_eq!(result.url, urls[1]);
+        assert_eq!(std::fs::read(&dest).unwrap(), content);
+
+        // A mirror serving other content does not satisfy the checksum
+        let wrong = Checksum::compute(ChecksumAlgorithm::Blake3, b"other");
+        let error = manager
+            .download_from(&urls, &dest, Some(&wrong), None)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("All 2 URLs failed"), "{error}");
+        assert!(error.contains("HTTP 500"), "{error}");
+        assert!(
+            error.contains("blake3 checksum verification failed"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_source_urls_order() {
+        let mirrors = vec!["https://m1/a.tar.gz".to_string()];
+        assert_eq!(
+            source_urls(
+                "https://up/x/a.tar.gz",
+                &mirrors,
+                Some("https://cache/"),
+                false
+            ),
+            [
+                "https://up/x/a.tar.gz",
+                "https://m1/a.tar.gz",
+                "https://cache/a.tar.gz"
+            ]
+        );
+        assert_eq!(
+            source_urls("https://up/x/a.tar.gz", &mirrors, None, true),
+            ["https://m1/a.tar.gz", "https://up/x/a.tar.gz"]
+        );
+        assert_eq!(
+            source_urls(
+                "https://up/a.tar.gz",
+                &["https://up/a.tar.gz".to_string()],
+                None,
+                false
+            ),
+            ["https://up/a.tar.gz"]
+        );
+    }
+
+    #[test]
+    fn test_token_bucket_limits_combined_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1000, start);
+
+        // A full second of bytes is available at once
+        assert_eq!(bucket.take(1000, start), Duration::ZERO);
+        // Then every taker waits for what it took
+        assert_eq!(bucket.take(500, start), Duration::from_millis(500));
+        // A second taker also waits for the first one's debt
+        assert_eq!(bucket.take(500, start), Duration::from_secs(1));
+
+        // After two seconds the debt is paid and 1000 bytes are available
+        let later = start + Duration::from_secs(2);
+        assert_eq!(bucket.take(1000, later), Duration::ZERO);
+        assert_eq!(bucket.take(250, later), Duration::from_millis(250));
+
+        // Idle time does not accumulate more than one second of bytes
+        let idle = later + Duration::from_secs(60);
+        assert_eq!(bucket.take(750, idle), Duration::ZERO);
+        assert_eq!(bucket.take(500, idle), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_token_bucket_ignores_earlier_instants() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(100, start + Duration::from_secs(1));
+
+        assert_eq!(bucket.take(100, start), Duration::ZERO);
+        assert_eq!(bucket.take(50, start), Duration::from_millis(500));
+    }
+
     // ============================================
     // Property-Based Tests
     // ============================================
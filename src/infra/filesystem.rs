@@ -2,7 +2,10 @@
 //!
 //! Handles file and directory operations.
 
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use crate::error::FilesystemError;
 
@@ -36,6 +39,40 @@ pub fn write_file(path: &Path, content: &str) -> Result<(), FilesystemError> {
     })
 }
 
+/// Write content to a file atomically
+///
+/// The content is written to a temporary file in the same directory and
+/// renamed into place, so readers see either the old or the new file, never
+/// a partial one.
+pub fn write_file_atomic(path: &Path, content: &[u8]) -> Result<(), FilesystemError> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let write_error = |e: std::io::Error| FilesystemError::WriteFile {
+        path: path.to_path_buf(),
+        error: e.to_string(),
+    };
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    create_dir_all(parent)?;
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = parent.join(format!(
+        ".{name}.{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = std::fs::File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(content)?;
+            file.sync_all()
+        })
+        .and_then(|()| std::fs::rename(&temp, path));
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp);
+        return Err(write_error(e));
+    }
+    Ok(())
+}
+
 /// Read content from a file
 pub fn read_file(path: &Path) -> Result<String, FilesystemError> {
     std::fs::read_to_string(path).map_err(|e| FilesystemError::ReadFile {
@@ -43,3 +80,83 @@ pub fn read_file(path: &Path) -> Result<String, FilesystemError> {
         error: e.to_string(),
     })
 }
+
+/// Free space on the filesystem holding a path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilesystemSpace {
+    /// Mount point of the filesystem
+    pub mount_point: PathBuf,
+    /// Bytes available to unprivileged users
+    pub available: u64,
+}
+
+/// Query the free space of the filesystem holding `path`
+///
+/// The path does not need to exist yet; its nearest existing ancestor is
+/// queried. Uses the POSIX output format of `df`.
+pub fn filesystem_space(path: &Path) -> Result<FilesystemSpace, FilesystemError> {
+    let existing = path.ancestors().find(|p| p.exists()).unwrap_or(path);
+    let error = |error: String| FilesystemError::SpaceQuery {
+        path: existing.to_path_buf(),
+        error,
+    };
+
+    let output = Command::new("df")
+        .arg("-Pk")
+        .arg(existing)
+        .output()
+        .map_err(|e| error(format!("failed to run df: {e}")))?;
+    if !output.status.success() {
+        return Err(error(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    parse_df_output(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| error("unexpected df output".to_string()))
+}
+
+/// Parse `df -Pk` output: a header, then
+/// `filesystem 1024-blocks used available capacity mount-point`
+fn parse_df_output(output: &str) -> Option<FilesystemSpace> {
+    let fields: Vec<&str> = output.lines().nth(1)?.split_whitespace().collect();
+    let available: u64 = fields.get(3)?.parse().ok()?;
+    Some(FilesystemSpace {
+        mount_point: PathBuf::from(fields.get(5..)?.join(" ")),
+        available: available.checked_mul(1024)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_df_output() {
+        let output = "Filesystem     1024-blocks     Used Available Capacity Mounted on\n\
+                      /dev/vda1        264212084 23165792  77636484      23% /mnt/My Disk\n";
+        let space = parse_df_output(output).unwrap();
+        assert_eq!(space.available, 77_636_484 * 1024);
+        assert_eq!(space.mount_point, PathBuf::from("/mnt/My Disk"));
+        assert!(parse_df_output("Filesystem\n").is_none());
+    }
+
+    #[test]
+    fn test_filesystem_space_of_missing_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let space = filesystem_space(&dir.path().join("not/created/yet")).unwrap();
+        assert!(space.available > 0);
+    }
+
+    #[test]
+    fn test_write_file_atomic_replaces_without_leftovers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache/index.json");
+
+        write_file_atomic(&path, b"first").unwrap();
+        write_file_atomic(&path, b"second").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+        let entries = std::fs::read_dir(path.parent().unwrap()).unwrap().count();
+        assert_eq!(entries, 1, "temporary files were left behind");
+    }
+}
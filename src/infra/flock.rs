@@ -0,0 +1,248 @@
+//! Advisory file locks between zigroot processes
+//!
+//! Commands that change a project hold an exclusive lock on
+//! [`PROJECT_LOCK`] while they run, so two builds, or a build and an `add`,
+//! never work on the same project at once. Shared storage entries are
+//! locked the same way while they are downloaded.
+//!
+//! The locks are `flock` locks: the kernel releases them when the process
+//! exits, so a crashed process never leaves a stale lock behind. The lock
+//! file records the holder's pid and start time for the busy message.
+
+use fs4::fs_std::FileExt;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Project lock file, relative to the project directory
+pub const PROJECT_LOCK: &str = ".zigroot/lock";
+
+/// File lock errors
+#[derive(Error, Debug)]
+pub enum FlockError {
+    /// Another process holds the lock
+    #[error("Another zigroot process ({holder}) is running")]
+    Busy { path: PathBuf, holder: LockHolder },
+
+    /// The lock file cannot be opened or locked
+    #[error("Cannot lock '{path}': {error}")]
+    Io { path: PathBuf, error: String },
+}
+
+/// Process holding a lock, as recorded in the lock file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LockHolder {
+    /// Process id, if recorded
+    pub pid: Option<u32>,
+    /// Unix time the lock was taken, if recorded
+    pub started: Option<u64>,
+}
+
+impl LockHolder {
+    /// The current process, starting now
+    fn current() -> Self {
+        Self {
+            pid: Some(std::process::id()),
+            started: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|d| d.as_secs()),
+        }
+    }
+
+    /// Parse the `<pid> <unix time>` line of a lock file
+    fn parse(content: &str) -> Self {
+        let mut fields = content.split_whitespace();
+        Self {
+            pid: fields.next().and_then(|f| f.parse().ok()),
+            started: fields.next().and_then(|f| f.parse().ok()),
+        }
+    }
+}
+
+impl std::fmt::Display for LockHolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.pid {
+            Some(pid) => write!(f, "pid {pid}")?,
+            None => write!(f, "unknown pid")?,
+        }
+        if let Some(started) = self.started {
+            let age = SystemTime::now()
+                .duration_since(UNIX_EPOCH + Duration::from_secs(started))
+                .unwrap_or_default()
+                .as_secs();
+            match age {
+                0..=59 => write!(f, ", started {age}s ago")?,
+                60..=3599 => write!(f, ", started {}m {:02}s ago", age / 60, age % 60)?,
+                _ => write!(f, ", started {}h {:02}m ago", age / 3600, age % 3600 / 60)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An exclusive lock, released when dropped
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Take the lock on `path`, creating the file if needed
+    ///
+    /// If another process holds the lock, this fails with
+    /// [`FlockError::Busy`], or blocks until it is released with `wait`.
+    pub fn acquire(path: &Path, wait: bool) -> Result<Self, FlockError> {
+        match Self::try_acquire(path)? {
+            Ok(lock) => Ok(lock),
+            Err(_) if wait => Self::acquire_blocking(path),
+            Err(holder) => Err(FlockError::Busy {
+                path: path.to_path_buf(),
+                holder,
+            }),
+        }
+    }
+
+    /// Take the lock if it is free, or return the process holding it
+    pub fn try_acquire(path: &Path) -> Result<Result<Self, LockHolder>, FlockError> {
+        let mut file = open(path)?;
+        if !file.try_lock_exclusive().map_err(|e| io_error(path, &e))? {
+            let mut content = String::new();
+            let _ = file.read_to_string(&mut content);
+            return Ok(Err(LockHolder::parse(&content)));
+        }
+        Self::locked(file, path).map(Ok)
+    }
+
+    /// Take the lock, blocking until no other process holds it
+    pub fn acquire_blocking(path: &Path) -> Result<Self, FlockError> {
+        let file = open(path)?;
+        FileExt::lock_exclusive(&file).map_err(|e| io_error(path, &e))?;
+        Self::locked(file, path)
+    }
+
+    /// Path of the lock file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record the current process in a freshly locked file
+    fn locked(mut file: File, path: &Path) -> Result<Self, FlockError> {
+        let holder = LockHolder::current();
+        file.set_len(0)
+            .and_then(|()| file.rewind())
+            .and_then(|()| {
+                writeln!(
+                    file,
+                    "{} {}",
+                    holder.pid.unwrap_or_default(),
+                    holder.started.unwrap_or_default()
+                )
+            })
+            .map_err(|e| io_error(path, &e))?;
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+/// Lock a project for a command that changes it
+///
+/// Returns `None` outside a project, where the command reports the missing
+/// manifest itself. With `wait`, `on_wait` is told who holds the lock before
+/// blocking until it is released.
+pub fn lock_project(
+    project_dir: &Path,
+    wait: bool,
+    on_wait: impl FnOnce(&LockHolder),
+) -> Result<Option<FileLock>, FlockError> {
+    if !project_dir.join("zigroot.toml").exists() {
+        return Ok(None);
+    }
+    let path = project_dir.join(PROJECT_LOCK);
+    match FileLock::try_acquire(&path)? {
+        Ok(lock) => Ok(Some(lock)),
+        Err(holder) if wait => {
+            on_wait(&holder);
+            FileLock::acquire_blocking(&path).map(Some)
+        }
+        Err(holder) => Err(FlockError::Busy { path, holder }),
+    }
+}
+
+/// Open or create a lock file and its directory
+fn open(path: &Path) -> Result<File, FlockError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| io_error(path, &e))?;
+    }
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(|e| io_error(path, &e))
+}
+
+fn io_error(path: &Path, error: &std::io::Error) -> FlockError {
+    FlockError::Io {
+        path: path.to_path_buf(),
+        error: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("nested/lock");
+
+        let lock = FileLock::acquire(&path, false).unwrap();
+        assert_eq!(lock.path(), path);
+        let holder = FileLock::try_acquire(&path).unwrap().unwrap_err();
+        assert_eq!(holder.pid, Some(std::process::id()));
+        assert!(holder.started.is_some());
+        assert!(matches!(
+            FileLock::acquire(&path, false),
+            Err(FlockError::Busy { .. })
+        ));
+
+        drop(lock);
+        assert!(FileLock::acquire(&path, false).is_ok());
+    }
+
+    #[test]
+    fn test_holder_display() {
+        let holder = LockHolder::parse("4242 0\n");
+        assert_eq!(holder.pid, Some(4242));
+        assert!(holder.to_string().starts_with("pid 4242, started "));
+        assert_eq!(LockHolder::parse("").to_string(), "unknown pid");
+    }
+
+    #[test]
+    fn test_lock_project_outside_project() {
+        let temp = tempfile::TempDir::new().unwrap();
+        assert!(lock_project(temp.path(), false, |_| {}).unwrap().is_none());
+        assert!(!temp.path().join(PROJECT_LOCK).exists());
+
+        std::fs::write(temp.path().join("zigroot.toml"), "").unwrap();
+        let _lock = lock_project(temp.path(), false, |_| {}).unwrap().unwrap();
+        let error = lock_project(temp.path(), false, |_| {}).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Another zigroot process (pid "));
+    }
+}
@@ -3,6 +3,7 @@
 //! Handles cloning repositories and checking out refs using the gix crate.
 
 use gix::remote::fetch::Shallow;
+use std::num::NonZeroU32;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
@@ -36,6 +37,10 @@ pub enum GitError {
     /// Invalid repository
     #[error("Invalid repository at '{path}': {error}")]
     InvalidRepository { path: PathBuf, error: String },
+
+    /// The remote repository cannot be contacted
+    #[error("Cannot reach git repository '{url}': {error}")]
+    Unreachable { url: String, error: String },
 }
 
 /// Git reference type
@@ -84,12 +89,24 @@ pub struct CloneResult {
 pub struct GitOperations {
     /// Working directory for git operations
     work_dir: PathBuf,
+    /// Check out submodules recursively after cloning
+    submodules: bool,
 }
 
 impl GitOperations {
     /// Create a new git operations handler
     pub fn new(work_dir: PathBuf) -> Self {
-        Self { work_dir }
+        Self {
+            work_dir,
+            submodules: false,
+        }
+    }
+
+    /// Check out submodules recursively, at the commits the clone records
+    #[must_use]
+    pub fn with_submodules(mut self, submodules: bool) -> Self {
+        self.submodules = submodules;
+        self
     }
 
     /// Get the working directory
@@ -99,6 +116,9 @@ impl GitOperations {
 
     /// Clone a repository and checkout a specific ref
     ///
+    /// Tags and branches are cloned shallow, with only the commit they point
+    /// to; a rev deepens the clone until the commit is found.
+    ///
     /// # Arguments
     /// * `url` - Repository URL to clone
     /// * `git_ref` - Reference to checkout (tag, branch, or rev)
@@ -111,6 +131,21 @@ impl GitOperations {
         url: &str,
         git_ref: &GitRef,
         dest_name: &str,
+    ) -> Result<CloneResult, GitError> {
+        self.clone_pinned(url, git_ref, None, dest_name)
+    }
+
+    /// Clone a repository at the commit a ref was pinned to
+    ///
+    /// The ref is cloned shallow; if it has moved on since `commit` was
+    /// recorded, the clone is deepened until `commit` is found and that is
+    /// checked out instead.
+    pub fn clone_pinned(
+        &self,
+        url: &str,
+        git_ref: &GitRef,
+        commit: Option<&str>,
+        dest_name: &str,
     ) -> Result<CloneResult, GitError> {
         let dest_path = self.work_dir.join(dest_name);
 
@@ -122,11 +157,7 @@ impl GitOperations {
             })?;
         }
 
-        // Clone the repository
-        self.clone_internal(url, &dest_path, git_ref)?;
-
-        // Resolve the commit SHA
-        let commit_sha = self.resolve_ref_to_sha(&dest_path, git_ref)?;
+        let commit_sha = self.clone_internal(url, &dest_path, git_ref, commit)?;
 
         Ok(CloneResult {
             path: dest_path,
@@ -135,86 +166,109 @@ impl GitOperations {
         })
     }
 
-    /// Internal clone implementation using gix
-    fn clone_internal(&self, url: &str, dest: &Path, git_ref: &GitRef) -> Result<(), GitError> {
-        // Prepare the clone with the appropriate ref
-        let mut prepare = gix::prepare_clone(url, dest).map_err(|e| GitError::CloneFailed {
+    /// Internal clone implementation using gix, returning the checked out
+    /// commit
+    fn clone_internal(
+        &self,
+        url: &str,
+        dest: &Path,
+        git_ref: &GitRef,
+        commit: Option<&str>,
+    ) -> Result<String, GitError> {
+        let clone_failed = |error: String| GitError::CloneFailed {
             url: url.to_string(),
-            error: e.to_string(),
-        })?;
+            error,
+        };
 
-        // Configure shallow clone for efficiency
-        prepare = prepare.with_shallow(Shallow::DepthAtRemote(1.try_into().unwrap()));
+        // Fetch only the tip of the wanted ref, or of the remote HEAD for a
+        // rev, which is deepened below if needed
+        let mut prepare = gix::prepare_clone(url, dest)
+            .map_err(|e| clone_failed(e.to_string()))?
+            .with_shallow(Shallow::DepthAtRemote(NonZeroU32::MIN));
+        if let GitRef::Tag(name) | GitRef::Branch(name) = git_ref {
+            prepare = prepare
+                .with_ref_name(Some(name.as_str()))
+                .map_err(|e| clone_failed(e.to_string()))?;
+        }
 
-        // Fetch and checkout
         let (mut checkout, _outcome) = prepare
             .fetch_then_checkout(gix::progress::Discard, &gix::interrupt::IS_INTERRUPTED)
-            .map_err(|e| GitError::CloneFailed {
-                url: url.to_string(),
-                error: e.to_string(),
-            })?;
-
-        // Complete the checkout to get a working tree
-        let (repo, _outcome) = checkout
-            .main_worktree(gix::progress::Discard, &gix::interrupt::IS_INTERRUPTED)
-            .map_err(|e| GitError::CloneFailed {
-                url: url.to_string(),
-                error: e.to_string(),
-            })?;
-
-        // Now checkout the specific ref
-        self.checkout_ref_internal(&repo, git_ref, url)?;
-
-        Ok(())
-    }
-
-    /// Checkout a specific ref in a repository
-    fn checkout_ref_internal(
-        &self,
-        repo: &gix::Repository,
-        git_ref: &GitRef,
-        url: &str,
-    ) -> Result<(), GitError> {
-        let reference_name = match git_ref {
-            GitRef::Tag(tag) => format!("refs/tags/{tag}"),
-            GitRef::Branch(branch) => format!("refs/remotes/origin/{branch}"),
-            GitRef::Rev(rev) => {
-                // For a specific revision, we need to resolve it directly
-                let oid = gix::ObjectId::from_hex(rev.as_bytes()).map_err(|e| {
-                    GitError::CheckoutFailed {
-                        repo: url.to_string(),
-                        reference: git_ref.to_string(),
-                        error: format!("Invalid SHA: {e}"),
-                    }
-                })?;
-
-                // Verify the object exists
-                repo.find_object(oid).map_err(|_e| GitError::RefNotFound {
+            .map_err(|e| match e {
+                gix::clone::fetch::Error::RefNameMissing { .. } => GitError::RefNotFound {
                     repo: url.to_string(),
                     reference: git_ref.to_string(),
-                })?;
+                },
+                e => clone_failed(e.to_string()),
+            })?;
 
-                return Ok(());
-            }
+        let wanted = match git_ref {
+            GitRef::Rev(rev) => Some(rev.as_str()),
+            GitRef::Tag(_) | GitRef::Branch(_) => commit,
         };
-
-        // Try to find the reference
-        let mut reference =
-            repo.find_reference(&reference_name)
-                .map_err(|_| GitError::RefNotFound {
+        if let Some(wanted) = wanted {
+            let repo = checkout.repo();
+            let fetched = repo.head_id().ok().map(|id| id.to_hex().to_string());
+            if !fetched.is_some_and(|tip| tip.starts_with(&wanted.to_lowercase())) {
+                let id = find_commit_deepening(repo, url, git_ref, wanted)?;
+                let checked_out = match git_ref {
+                    GitRef::Tag(name) | GitRef::Branch(name) => Some(name.as_str()),
+                    GitRef::Rev(_) => None,
+                };
+                point_at(repo, checked_out, id).map_err(|error| GitError::CheckoutFailed {
                     repo: url.to_string(),
                     reference: git_ref.to_string(),
+                    error,
                 })?;
+            }
+        }
 
-        // Peel to commit to verify it exists
-        reference
-            .peel_to_commit()
-            .map_err(|e| GitError::CheckoutFailed {
-                repo: url.to_string(),
+        // Complete the checkout to get a working tree
+        let (repo, _outcome) = checkout
+            .main_worktree(gix::progress::Discard, &gix::interrupt::IS_INTERRUPTED)
+            .map_err(|e| clone_failed(e.to_string()))?;
+        let commit_sha = repo
+            .head_id()
+            .map_err(|e| GitError::ResolveFailed {
                 reference: git_ref.to_string(),
                 error: e.to_string(),
-            })?;
+            })?
+            .to_hex()
+            .to_string();
 
+        if self.submodules {
+            self.clone_submodules(&repo, url)?;
+        }
+        Ok(commit_sha)
+    }
+
+    /// Clone the submodules of a checkout at the commits it records
+    fn clone_submodules(&self, repo: &gix::Repository, url: &str) -> Result<(), GitError> {
+        let invalid = |error: String| GitError::InvalidRepository {
+            path: repo.work_dir().unwrap_or(repo.git_dir()).to_path_buf(),
+            error,
+        };
+        let Some(submodules) = repo.submodules().map_err(|e| invalid(e.to_string()))? else {
+            return Ok(());
+        };
+        let work_dir = repo
+            .work_dir()
+            .ok_or_else(|| invalid("no working tree".to_string()))?;
+
+        for submodule in submodules {
+            let path = submodule.path().map_err(|e| invalid(e.to_string()))?;
+            let Some(commit) = submodule.head_id().map_err(|e| invalid(e.to_string()))? else {
+                continue;
+            };
+            let sub_url = submodule.url().map_err(|e| invalid(e.to_string()))?;
+            let sub_url = submodule_url(url, &sub_url.to_bstring().to_string());
+            let dest = work_dir.join(gix::path::from_bstr(path).as_ref());
+            self.clone_internal(
+                &sub_url,
+                &dest,
+                &GitRef::Rev(commit.to_hex().to_string()),
+                None,
+            )?;
+        }
         Ok(())
     }
 
@@ -310,6 +364,224 @@ impl GitOperations {
     }
 }
 
+/// Find a commit in a shallow clone, deepening it until the commit is there
+fn find_commit_deepening(
+    repo: &gix::Repository,
+    url: &str,
+    git_ref: &GitRef,
+    wanted: &str,
+) -> Result<gix::ObjectId, GitError> {
+    let not_found = || GitError::RefNotFound {
+        repo: url.to_string(),
+        reference: format!("{git_ref} (commit {wanted})"),
+    };
+    let mut depth = 16;
+    loop {
+        if let Some(id) = find_commit(repo, wanted) {
+            return Ok(id);
+        }
+        if !repo.is_shallow() {
+            return Err(not_found());
+        }
+
+        let remote = repo
+            .find_default_remote(gix::remote::Direction::Fetch)
+            .ok_or_else(not_found)?
+            .map_err(|e| clone_failed(url, e.to_string()))?;
+        remote
+            .connect(gix::remote::Direction::Fetch)
+            .map_err(|e| clone_failed(url, e.to_string()))?
+            .prepare_fetch(gix::progress::Discard, Default::default())
+            .map_err(|e| clone_failed(url, e.to_string()))?
+            .with_shallow(Shallow::Deepen(depth))
+            .receive(gix::progress::Discard, &gix::interrupt::IS_INTERRUPTED)
+            .map_err(|e| clone_failed(url, e.to_string()))?;
+        depth = depth.saturating_mul(4);
+    }
+}
+
+/// The commit a full or abbreviated SHA names, if it is in the repository
+fn find_commit(repo: &gix::Repository, sha: &str) -> Option<gix::ObjectId> {
+    let prefix = gix::hash::Prefix::from_hex(sha).ok()?;
+    let id = repo.objects.lookup_prefix(prefix, None).ok()??.ok()?;
+    repo.find_object(id)
+        .is_ok_and(|object| object.kind.is_commit())
+        .then_some(id)
+}
+
+fn clone_failed(url: &str, error: String) -> GitError {
+    GitError::CloneFailed {
+        url: url.to_string(),
+        error,
+    }
+}
+
+/// Point HEAD, and the ref to check out if any, at a commit for the
+/// checkout that follows
+fn point_at(repo: &gix::Repository, name: Option<&str>, id: gix::ObjectId) -> Result<(), String> {
+    use gix::refs::transaction::{Change, LogChange, PreviousValue, RefEdit};
+    let mut names = vec![gix::refs::FullName::try_from("HEAD").map_err(|e| e.to_string())?];
+    if let Some(name) = name {
+        let reference = repo.find_reference(name).map_err(|e| e.to_string())?;
+        names.push(reference.name().to_owned());
+    }
+    for name in names {
+        repo.edit_reference(RefEdit {
+            change: Change::Update {
+                log: LogChange::default(),
+                expected: PreviousValue::Any,
+                new: gix::refs::Target::Object(id),
+            },
+            name,
+            deref: false,
+        })
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// URL of a submodule, with `./` and `../` URLs taken relative to the
+/// superproject's
+fn submodule_url(parent: &str, url: &str) -> String {
+    if !(url.starts_with("./") || url.starts_with("../")) {
+        return url.to_string();
+    }
+    let mut base = parent.trim_end_matches('/').to_string();
+    let mut rest = url;
+    loop {
+        if let Some(tail) = rest.strip_prefix("./") {
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("../") {
+            rest = tail;
+            if let Some(slash) = base.rfind('/') {
+                base.truncate(slash);
+            }
+        } else {
+            break;
+        }
+    }
+    format!("{base}/{rest}")
+}
+
+/// Commit checked out in a local repository
+pub fn head_commit(repo_path: &Path) -> Result<String, GitError> {
+    let repo = gix::open(repo_path).map_err(|e| GitError::InvalidRepository {
+        path: repo_path.to_path_buf(),
+        error: e.to_string(),
+    })?;
+    let id = repo.head_id().map_err(|e| GitError::ResolveFailed {
+        reference: "HEAD".to_string(),
+        error: e.to_string(),
+    })?;
+    Ok(id.to_hex().to_string())
+}
+
+/// Resolve a ref of a remote repository to a commit SHA, without cloning
+///
+/// `reference` is a branch, a tag, a full ref name or a commit SHA; `None`
+/// stands for the remote's HEAD. The remote's refs are listed, so this also
+/// checks that the repository is reachable. A full commit SHA that is not
+/// the tip of any ref is accepted as is, since only a fetch could find it.
+pub fn resolve_remote_ref(url: &str, reference: Option<&str>) -> Result<String, GitError> {
+    let unreachable = |error: String| GitError::Unreachable {
+        url: url.to_string(),
+        error,
+    };
+    let refs = list_remote_refs(url).map_err(unreachable)?;
+
+    let wanted = reference.unwrap_or("HEAD");
+    let candidates = if wanted == "HEAD" || wanted.starts_with("refs/") {
+        vec![wanted.to_string()]
+    } else {
+        vec![
+            format!("refs/heads/{wanted}"),
+            format!("refs/tags/{wanted}"),
+        ]
+    };
+    for candidate in &candidates {
+        if let Some((_, commit)) = refs.iter().find(|(name, _)| name == candidate) {
+            return Ok(commit.clone());
+        }
+    }
+
+    let is_hex = !wanted.is_empty() && wanted.chars().all(|c| c.is_ascii_hexdigit());
+    if is_hex && wanted.len() == 40 {
+        return Ok(wanted.to_lowercase());
+    }
+    if is_hex && wanted.len() >= 7 {
+        let prefix = wanted.to_lowercase();
+        if let Some((_, commit)) = refs.iter().find(|(_, commit)| commit.starts_with(&prefix)) {
+            return Ok(commit.clone());
+        }
+    }
+    Err(GitError::RefNotFound {
+        repo: url.to_string(),
+        reference: wanted.to_string(),
+    })
+}
+
+/// Names and commits of the refs a remote advertises
+///
+/// Annotated tags are peeled to the commit they point to.
+fn list_remote_refs(url: &str) -> Result<Vec<(String, String)>, String> {
+    // Listing refs needs a repository to hold the remote; a scratch one
+    // is removed afterwards
+    let scratch = std::env::temp_dir().join(format!("zigroot-ls-remote-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&scratch);
+    let listed = (|| {
+        let repo = gix::init_bare(&scratch).map_err(|e| e.to_string())?;
+        let remote = repo
+            .remote_at(url)
+            .map_err(|e| e.to_string())?
+            .with_refspecs(
+                [
+                    "+refs/heads/*:refs/remotes/origin/*",
+                    "+refs/tags/*:refs/tags/*",
+                ],
+                gix::remote::Direction::Fetch,
+            )
+            .map_err(|e| e.to_string())?;
+        let connection = remote
+            .connect(gix::remote::Direction::Fetch)
+            .map_err(|e| e.to_string())?;
+        let ref_map = connection
+            .ref_map(
+                gix::progress::Discard,
+                gix::remote::ref_map::Options {
+                    prefix_from_spec_as_filter_on_remote: false,
+                    ..Default::default()
+                },
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(ref_map
+            .remote_refs
+            .iter()
+            .filter_map(|advertised| {
+                use gix::protocol::handshake::Ref;
+                match advertised {
+                    Ref::Peeled {
+                        full_ref_name,
+                        object,
+                        ..
+                    }
+                    | Ref::Direct {
+                        full_ref_name,
+                        object,
+                    }
+                    | Ref::Symbolic {
+                        full_ref_name,
+                        object,
+                        ..
+                    } => Some((full_ref_name.to_string(), object.to_hex().to_string())),
+                    Ref::Unborn { .. } => None,
+                }
+            })
+            .collect())
+    })();
+    let _ = std::fs::remove_dir_all(&scratch);
+    listed
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -418,6 +690,147 @@ mod tests {
             .all(|c| c.is_ascii_hexdigit()));
     }
 
+    /// Run git in `dir`, returning its trimmed output
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = std::process::Command::new("git")
+            .current_dir(dir)
+            .args([
+                "-c",
+                "user.name=zigroot",
+                "-c",
+                "user.email=zigroot@example.com",
+            ])
+            .args(["-c", "protocol.file.allow=always"])
+            .args(args)
+            .output()
+            .expect("Failed to execute git");
+        assert!(output.status.success(), "git {args:?} failed");
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// Repository with `version` committed as 1, 2 and 3, tagged `v1` at 1,
+    /// returning the commits
+    fn versioned_repo(dir: &Path) -> Vec<String> {
+        git(dir, &["init", "-q", "-b", "main"]);
+        (1..=3)
+            .map(|version| {
+                std::fs::write(dir.join("version"), version.to_string()).unwrap();
+                git(dir, &["add", "version"]);
+                git(dir, &["commit", "-q", "-m", &format!("version {version}")]);
+                if version == 1 {
+                    git(dir, &["tag", "-a", "v1", "-m", "first release"]);
+                }
+                git(dir, &["rev-parse", "HEAD"])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_clone_is_shallow_and_deepens_for_older_commits() {
+        let upstream = TempDir::new().unwrap();
+        let commits = versioned_repo(upstream.path());
+        let url = format!("file://{}", upstream.path().display());
+        let work = TempDir::new().unwrap();
+        let ops = GitOperations::new(work.path().to_path_buf());
+        let version =
+            |clone: &CloneResult| std::fs::read_to_string(clone.path.join("version")).unwrap();
+
+        let tag = ops
+            .clone_repo(&url, &GitRef::Tag("v1".to_string()), "tag")
+            .unwrap();
+        assert_eq!(tag.commit_sha, commits[0]);
+        assert_eq!(version(&tag), "1");
+        assert!(gix::open(&tag.path).unwrap().is_shallow());
+
+        let branch = ops
+            .clone_repo(&url, &GitRef::Branch("main".to_string()), "branch")
+            .unwrap();
+        assert_eq!(branch.commit_sha, commits[2]);
+        assert!(gix::open(&branch.path).unwrap().is_shallow());
+
+        // An older commit of the branch, by full and abbreviated SHA
+        let pinned = ops
+            .clone_pinned(
+                &url,
+                &GitRef::Branch("main".to_string()),
+                Some(&commits[1]),
+                "pinned",
+            )
+            .unwrap();
+        assert_eq!(pinned.commit_sha, commits[1]);
+        assert_eq!(version(&pinned), "2");
+        assert_eq!(head_commit(&pinned.path).unwrap(), commits[1]);
+        let rev = ops
+            .clone_repo(&url, &GitRef::Rev(commits[0][..10].to_string()), "rev")
+            .unwrap();
+        assert_eq!(rev.commit_sha, commits[0]);
+        assert_eq!(version(&rev), "1");
+
+        let missing = ops.clone_repo(&url, &GitRef::Rev("0".repeat(40)), "missing");
+        assert!(
+            matches!(missing, Err(GitError::RefNotFound { .. })),
+            "{missing:?}"
+        );
+        let missing = ops.clone_repo(&url, &GitRef::Tag("v9".to_string()), "missing");
+        assert!(
+            matches!(missing, Err(GitError::RefNotFound { .. })),
+            "{missing:?}"
+        );
+    }
+
+    #[test]
+    fn test_clone_with_submodules() {
+        let repos = TempDir::new().unwrap();
+        let (library, app) = (repos.path().join("library"), repos.path().join("app"));
+        std::fs::create_dir_all(&library).unwrap();
+        std::fs::create_dir_all(&app).unwrap();
+        let commits = versioned_repo(&library);
+        git(&app, &["init", "-q", "-b", "main"]);
+        git(
+            &app,
+            &["submodule", "add", "-q", "../library", "vendor/library"],
+        );
+        git(
+            &app,
+            &["-C", "vendor/library", "checkout", "-q", &commits[1]],
+        );
+        git(&app, &["commit", "-q", "-am", "vendor library 2"]);
+        // Moving the library on must not change the recorded commit
+        std::fs::write(library.join("version"), "4").unwrap();
+        git(&library, &["commit", "-q", "-am", "version 4"]);
+
+        let url = format!("file://{}", app.display());
+        let work = TempDir::new().unwrap();
+        let plain = GitOperations::new(work.path().to_path_buf())
+            .clone_repo(&url, &GitRef::Branch("main".to_string()), "plain")
+            .unwrap();
+        assert!(!plain.path.join("vendor/library/version").exists());
+
+        let with_submodules = GitOperations::new(work.path().to_path_buf())
+            .with_submodules(true)
+            .clone_repo(&url, &GitRef::Branch("main".to_string()), "full")
+            .unwrap();
+        let vendored = with_submodules.path.join("vendor/library/version");
+        assert_eq!(std::fs::read_to_string(vendored).unwrap(), "2");
+    }
+
+    #[test]
+    fn test_submodule_url() {
+        let parent = "https://example.com/group/app.git";
+        assert_eq!(
+            submodule_url(parent, "../library.git"),
+            "https://example.com/group/library.git"
+        );
+        assert_eq!(
+            submodule_url(parent, "./nested"),
+            "https://example.com/group/app.git/nested"
+        );
+        assert_eq!(
+            submodule_url(parent, "https://example.org/other.git"),
+            "https://example.org/other.git"
+        );
+    }
+
     #[test]
     fn test_clone_repo_invalid_url() {
         let temp = TempDir::new().unwrap();
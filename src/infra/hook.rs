@@ -0,0 +1,199 @@
+//! Hook command execution
+//!
+//! Hooks are shell commands run with `sh -c` at fixed points of a build.
+//! Their stdout and stderr are streamed line by line to a callback while
+//! being captured, and a hook running past its timeout is killed. Package
+//! build steps are run the same way by [`run_streaming`].
+
+use std::ffi::OsStr;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Outcome of a hook command
+#[derive(Debug)]
+pub struct HookOutput {
+    /// Exit status, `None` when the command was killed at its timeout
+    pub status: Option<ExitStatus>,
+    /// Captured stdout and stderr lines, interleaved as they were read
+    pub output: String,
+}
+
+impl HookOutput {
+    /// Whether the hook exited successfully
+    pub fn success(&self) -> bool {
+        self.status.is_some_and(|status| status.success())
+    }
+
+    /// Why the hook failed, if it did
+    pub fn failure(&self) -> Option<String> {
+        match self.status {
+            None => Some("timed out".to_string()),
+            Some(status) if !status.success() => Some(format!("exited with {status}")),
+            Some(_) => None,
+        }
+    }
+}
+
+/// Command running `command` with `sh -c` in `dir`
+///
+/// `env` is added to the inherited environment and `path` directories are
+/// put in front of `PATH`.
+pub fn shell_command<K, V>(
+    command: &str,
+    dir: &Path,
+    env: impl IntoIterator<Item = (K, V)>,
+    path: &[PathBuf],
+) -> Command
+where
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+{
+    let mut process = Command::new("sh");
+    process.args(["-c", command]).envs(env).current_dir(dir);
+    if !path.is_empty() {
+        let inherited = std::env::var_os("PATH").unwrap_or_default();
+        let dirs = path
+            .iter()
+            .cloned()
+            .chain(std::env::split_paths(&inherited));
+        if let Ok(joined) = std::env::join_paths(dirs) {
+            process.env("PATH", joined);
+        }
+    }
+    process
+}
+
+/// Run a hook command, killing it after `timeout`
+///
+/// Each output line is passed to `on_line` as soon as it is read.
+pub fn run_hook(
+    process: &mut Command,
+    timeout: Option<Duration>,
+    on_line: &mut dyn FnMut(&str),
+) -> std::io::Result<HookOutput> {
+    run_streaming(
+        process,
+        &|elapsed| timeout.is_some_and(|timeout| elapsed >= timeout),
+        on_line,
+    )
+}
+
+/// Run a command, streaming its output lines to `on_line`
+///
+/// `stop` is polled with the elapsed time; once it returns true the command
+/// is killed and the returned status is `None`.
+pub fn run_streaming(
+    process: &mut Command,
+    stop: &dyn Fn(Duration) -> bool,
+    on_line: &mut dyn FnMut(&str),
+) -> std::io::Result<HookOutput> {
+    let mut child = process
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let (sender, receiver) = mpsc::channel();
+    let forward = |pipe: Option<Box<dyn Read + Send>>, sender: mpsc::Sender<String>| {
+        std::thread::spawn(move || {
+            if let Some(pipe) = pipe {
+                for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            }
+        })
+    };
+    let readers = [
+        forward(
+            child
+                .stdout
+                .take()
+                .map(|p| Box::new(p) as Box<dyn Read + Send>),
+            sender.clone(),
+        ),
+        forward(
+            child
+                .stderr
+                .take()
+                .map(|p| Box::new(p) as Box<dyn Read + Send>),
+            sender,
+        ),
+    ];
+
+    let started = Instant::now();
+    let mut output = String::new();
+    let mut receive = |line: String| {
+        on_line(&line);
+        output.push_str(&line);
+        output.push('\n');
+    };
+    let status = loop {
+        match receiver.recv_timeout(Duration::from_millis(50)) {
+            Ok(line) => receive(line),
+            Err(mpsc::RecvTimeoutError::Disconnected) => break Some(child.wait()?),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+        }
+        if stop(started.elapsed()) {
+            // Processes the command started may still hold the pipes open
+            let _ = child.kill();
+            child.wait()?;
+            break None;
+        }
+    };
+    if status.is_some() {
+        for reader in readers {
+            let _ = reader.join();
+        }
+    }
+    while let Ok(line) = receiver.try_recv() {
+        receive(line);
+    }
+
+    Ok(HookOutput { status, output })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_hook_streams_output_and_env() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut lines = Vec::new();
+        let mut command = shell_command(
+            "echo $GREETING; pwd; echo oops >&2; exit 3",
+            temp.path(),
+            [("GREETING", "hello")],
+            &[],
+        );
+        let result = run_hook(&mut command, None, &mut |line| {
+            lines.push(line.to_string());
+        })
+        .unwrap();
+
+        assert!(!result.success());
+        assert!(result.failure().unwrap().contains('3'));
+        assert!(lines.contains(&"hello".to_string()));
+        assert!(lines.contains(&"oops".to_string()));
+        assert!(lines
+            .iter()
+            .any(|line| Path::new(line).ends_with(temp.path().file_name().unwrap())));
+        assert!(result.output.contains("hello\n"));
+    }
+
+    #[test]
+    fn test_run_hook_times_out() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut command =
+            shell_command("sleep 5", temp.path(), Vec::<(String, String)>::new(), &[]);
+        let result = run_hook(&mut command, Some(Duration::from_millis(200)), &mut |_| {}).unwrap();
+
+        assert!(result.status.is_none());
+        assert_eq!(result.failure().as_deref(), Some("timed out"));
+    }
+}
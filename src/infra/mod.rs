@@ -6,7 +6,11 @@
 pub mod dirs;
 pub mod download;
 pub mod filesystem;
+pub mod flock;
 pub mod gcc_toolchain;
 pub mod git;
+pub mod hook;
+pub mod http;
+pub mod namespace;
 pub mod sandbox;
 pub mod toolchain;
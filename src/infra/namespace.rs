@@ -0,0 +1,361 @@
+//! Build isolation using Linux namespaces
+//!
+//! Sandboxed package builds run inside bubblewrap (`bwrap`) when it is
+//! installed, or inside namespaces set up with util-linux `unshare`
+//! otherwise. Only the declared mounts and the read-only host system
+//! directories are visible, the environment is replaced, and the network is
+//! never shared: sources are fetched before the build, outside the sandbox.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::infra::sandbox::MountConfig;
+
+/// Environment variable selecting the isolation tool (`bwrap`, `unshare` or
+/// `none`), overriding detection
+pub const SANDBOX_TOOL_ENV: &str = "ZIGROOT_SANDBOX_TOOL";
+
+/// Host directories mounted read-only for the shell and host tools
+pub const SYSTEM_DIRS: &[&str] = &["/usr", "/bin", "/sbin", "/lib", "/lib64"];
+
+/// `PATH` inside the sandbox, after the toolchain directories
+const SYSTEM_PATH: &str = "/usr/local/bin:/usr/bin:/bin:/usr/sbin:/sbin";
+
+/// Hint shown when no isolation tool is available
+pub const INSTALL_HINT: &str =
+    "Install bubblewrap (apt install bubblewrap, dnf install bubblewrap or pacman -S bubblewrap)";
+
+/// Tool creating the namespaces of a sandboxed build
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamespaceTool {
+    /// bubblewrap
+    Bubblewrap,
+    /// util-linux `unshare` with a mount setup script
+    Unshare,
+}
+
+impl NamespaceTool {
+    /// Get the command name for this tool
+    pub fn command(&self) -> &'static str {
+        match self {
+            NamespaceTool::Bubblewrap => "bwrap",
+            NamespaceTool::Unshare => "unshare",
+        }
+    }
+
+    /// Detect a working isolation tool, preferring bubblewrap
+    ///
+    /// `ZIGROOT_SANDBOX_TOOL` forces a tool, or disables isolation with
+    /// `none`. Returns `None` without namespace support.
+    pub fn detect() -> Option<Self> {
+        match std::env::var(SANDBOX_TOOL_ENV).ok().as_deref() {
+            Some("bwrap") => return Some(Self::Bubblewrap),
+            Some("unshare") => return Some(Self::Unshare),
+            Some("none") => return None,
+            _ => {}
+        }
+        if !namespaces_supported() {
+            return None;
+        }
+        [Self::Bubblewrap, Self::Unshare]
+            .into_iter()
+            .find(NamespaceTool::is_available)
+    }
+
+    /// Check that the tool can create namespaces on this system
+    ///
+    /// Unprivileged user namespaces can be disabled by the kernel even when
+    /// the tool is installed, so this runs `true` in a sandbox.
+    pub fn is_available(&self) -> bool {
+        let probe: &[&str] = match self {
+            NamespaceTool::Bubblewrap => &["--unshare-all", "--ro-bind", "/", "/", "true"],
+            NamespaceTool::Unshare => &["--user", "--map-root-user", "--mount", "--net", "true"],
+        };
+        Command::new(self.command())
+            .args(probe)
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+}
+
+impl std::fmt::Display for NamespaceTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.command())
+    }
+}
+
+/// Whether this platform has Linux namespaces
+pub fn namespaces_supported() -> bool {
+    cfg!(target_os = "linux")
+}
+
+/// A namespace sandbox for one package build
+///
+/// Mounted paths keep their host location, so paths in the build
+/// environment stay valid inside the sandbox.
+#[derive(Debug, Clone)]
+pub struct NamespaceSandbox {
+    /// Isolation tool
+    tool: NamespaceTool,
+    /// Empty host directory the sandbox root is mounted on (`unshare` only)
+    root: PathBuf,
+    /// Mounts besides the system directories
+    mounts: Vec<MountConfig>,
+    /// Complete environment of the sandboxed process
+    env: Vec<(String, String)>,
+    /// Working directory
+    workdir: PathBuf,
+}
+
+impl NamespaceSandbox {
+    /// Create a sandbox mounting only the system directories
+    pub fn new(tool: NamespaceTool, root: PathBuf) -> Self {
+        Self {
+            tool,
+            root,
+            mounts: Vec::new(),
+            env: vec![("PATH".to_string(), SYSTEM_PATH.to_string())],
+            workdir: PathBuf::from("/"),
+        }
+    }
+
+    /// Add a mount
+    #[must_use]
+    pub fn with_mount(mut self, mount: MountConfig) -> Self {
+        self.mounts.push(mount);
+        self
+    }
+
+    /// Set an environment variable
+    #[must_use]
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        self.env.retain(|(existing, _)| *existing != key);
+        self.env.push((key, value.into()));
+        self
+    }
+
+    /// Prepend directories to `PATH`
+    #[must_use]
+    pub fn with_path(self, dirs: &[PathBuf]) -> Self {
+        let mut path: Vec<String> = dirs.iter().map(|d| d.display().to_string()).collect();
+        path.push(SYSTEM_PATH.to_string());
+        self.with_env("PATH", path.join(":"))
+    }
+
+    /// Set the working directory
+    #[must_use]
+    pub fn with_workdir(mut self, workdir: PathBuf) -> Self {
+        self.workdir = workdir;
+        self
+    }
+
+    /// Get the isolation tool
+    pub fn tool(&self) -> NamespaceTool {
+        self.tool
+    }
+
+    /// Get the mounts
+    pub fn mounts(&self) -> &[MountConfig] {
+        &self.mounts
+    }
+
+    /// Command running `program` with `args` inside the sandbox
+    pub fn command(&self, program: &str, args: &[String]) -> Command {
+        let mut command = Command::new(self.tool.command());
+        match self.tool {
+            NamespaceTool::Bubblewrap => command.args(self.bwrap_args(program, args)),
+            NamespaceTool::Unshare => command.args(self.unshare_args(program, args)),
+        };
+        command.env_clear();
+        command
+    }
+
+    /// Arguments of `bwrap`
+    pub fn bwrap_args(&self, program: &str, args: &[String]) -> Vec<String> {
+        let mut bwrap: Vec<String> = [
+            "--unshare-all",
+            "--die-with-parent",
+            "--clearenv",
+            "--dev",
+            "/dev",
+            "--proc",
+            "/proc",
+            "--tmpfs",
+            "/tmp",
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+        for dir in SYSTEM_DIRS {
+            bwrap.extend([
+                "--ro-bind-try".to_string(),
+                (*dir).to_string(),
+                (*dir).to_string(),
+            ]);
+        }
+        for mount in &self.mounts {
+            let flag = if mount.read_only {
+                "--ro-bind"
+            } else {
+                "--bind"
+            };
+            bwrap.extend([
+                flag.to_string(),
+                mount.host_path.display().to_string(),
+                mount.container_path.display().to_string(),
+            ]);
+        }
+        for (key, value) in &self.env {
+            bwrap.extend(["--setenv".to_string(), key.clone(), value.clone()]);
+        }
+        bwrap.extend([
+            "--chdir".to_string(),
+            self.workdir.display().to_string(),
+            "--".to_string(),
+            program.to_string(),
+        ]);
+        bwrap.extend(args.iter().cloned());
+        bwrap
+    }
+
+    /// Arguments of `unshare`
+    ///
+    /// A script in the new namespaces mounts a tmpfs on the root
+    /// directory, binds the mounts into it and changes root.
+    pub fn unshare_args(&self, program: &str, args: &[String]) -> Vec<String> {
+        let mut unshare: Vec<String> = [
+            "--user",
+            "--map-root-user",
+            "--mount",
+            "--net",
+            "--pid",
+            "--ipc",
+            "--uts",
+            "--fork",
+            "--kill-child",
+            "/bin/sh",
+            "-c",
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+        unshare.push(self.unshare_script());
+        unshare.extend(["sh".to_string(), program.to_string()]);
+        unshare.extend(args.iter().cloned());
+        unshare
+    }
+
+    /// Setup script run by `unshare`; the command follows as `"$@"`
+    fn unshare_script(&self) -> String {
+        let root = self.root.display().to_string();
+        let mut lines = vec![
+            "set -e".to_string(),
+            format!("root={}", quote(&root)),
+            "mount -t tmpfs zigroot-sandbox \"$root\"".to_string(),
+            "mkdir -p \"$root/dev\" \"$root/proc\" \"$root/tmp\"".to_string(),
+            "mount --rbind /dev \"$root/dev\"".to_string(),
+            "mount -t proc proc \"$root/proc\"".to_string(),
+            "mount -t tmpfs tmp \"$root/tmp\"".to_string(),
+        ];
+        for dir in SYSTEM_DIRS {
+            let dir = quote(dir);
+            lines.push(format!(
+                "if [ -L {dir} ]; then ln -s \"$(readlink {dir})\" \"$root\"{dir}; \
+                 elif [ -d {dir} ]; then mkdir -p \"$root\"{dir} && mount --rbind {dir} \"$root\"{dir} \
+                 && mount -o remount,bind,ro \"$root\"{dir}; fi"
+            ));
+        }
+        for mount in &self.mounts {
+            let host = quote(&mount.host_path.display().to_string());
+            let target = quote(&mount.container_path.display().to_string());
+            lines.push(format!(
+                "mkdir -p \"$root\"{target} && mount --bind {host} \"$root\"{target}"
+            ));
+            if mount.read_only {
+                lines.push(format!("mount -o remount,bind,ro \"$root\"{target}"));
+            }
+        }
+        let env: Vec<String> = self
+            .env
+            .iter()
+            .map(|(key, value)| quote(&format!("{key}={value}")))
+            .collect();
+        lines.push(format!(
+            "exec chroot \"$root\" /usr/bin/env -i {} /bin/sh -c 'cd \"$0\" && exec \"$@\"' {} \"$@\"",
+            env.join(" "),
+            quote(&self.workdir.display().to_string())
+        ));
+        lines.join("\n")
+    }
+}
+
+/// Quote a value for `sh`
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Whether `path` is visible in every sandbox through the system mounts
+pub fn is_system_path(path: &Path) -> bool {
+    SYSTEM_DIRS.iter().any(|dir| path.starts_with(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox(tool: NamespaceTool) -> NamespaceSandbox {
+        NamespaceSandbox::new(tool, PathBuf::from("/work/build/sandbox/app"))
+            .with_mount(MountConfig::read_only(
+                PathBuf::from("/work/packages/app"),
+                PathBuf::from("/work/packages/app"),
+            ))
+            .with_mount(MountConfig::read_write(
+                PathBuf::from("/work/build/src/app"),
+                PathBuf::from("/work/build/src/app"),
+            ))
+            .with_env("CC", "zig cc")
+            .with_workdir(PathBuf::from("/work/build/src/app"))
+    }
+
+    #[test]
+    fn test_bwrap_args_isolate_everything() {
+        let args = sandbox(NamespaceTool::Bubblewrap).bwrap_args("make", &["install".to_string()]);
+        let joined = args.join(" ");
+
+        assert!(args.contains(&"--unshare-all".to_string()));
+        assert!(!args.contains(&"--share-net".to_string()));
+        assert!(joined.contains("--ro-bind /work/packages/app /work/packages/app"));
+        assert!(joined.contains("--bind /work/build/src/app /work/build/src/app"));
+        assert!(joined.contains("--setenv CC zig cc"));
+        assert!(joined.ends_with("--chdir /work/build/src/app -- make install"));
+    }
+
+    #[test]
+    fn test_unshare_script_binds_mounts_read_only() {
+        let args = sandbox(NamespaceTool::Unshare).unshare_args("make", &[]);
+        let script = &args[11];
+
+        assert!(args.contains(&"--net".to_string()));
+        assert!(script.contains("root='/work/build/sandbox/app'"));
+        assert!(script.contains(
+            "mount --bind '/work/packages/app' \"$root\"'/work/packages/app'\nmount -o remount,bind,ro \"$root\"'/work/packages/app'"
+        ));
+        assert!(!script.contains("remount,bind,ro \"$root\"'/work/build/src/app'"));
+        assert!(script.contains("'CC=zig cc'"));
+        assert_eq!(&args[12..], ["sh", "make"]);
+    }
+
+    #[test]
+    fn test_with_env_replaces_existing_value() {
+        let sandbox = NamespaceSandbox::new(NamespaceTool::Bubblewrap, PathBuf::from("/root"))
+            .with_path(&[PathBuf::from("/opt/zig")]);
+        let path: Vec<_> = sandbox
+            .env
+            .iter()
+            .filter(|(key, _)| key == "PATH")
+            .collect();
+        assert_eq!(path.len(), 1);
+        assert!(path[0].1.starts_with("/opt/zig:"));
+    }
+}
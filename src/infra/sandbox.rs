@@ -1,7 +1,9 @@
 //! Build isolation using Docker/Podman containers
 //!
 //! Provides container-based isolation for package builds to prevent
-//! malicious packages from harming the host system.
+//! malicious packages from harming the host system. `zigroot build` isolates
+//! package builds with namespaces (see [`crate::infra::namespace`]); the
+//! sandbox settings are resolved here.
 //!
 //! **Validates: Requirements 27.1-27.9**
 
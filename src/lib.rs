@@ -13,6 +13,31 @@
 //! - [`infra`] - Infrastructure layer (network, filesystem, processes)
 //! - [`config`] - Configuration and constants
 //! - [`error`] - Error types and handling
+//!
+//! # Embedding
+//!
+//! [`core::project::ZigrootProject`] opens a project and resolves, fetches
+//! and builds it without printing anything:
+//!
+//! ```
+//! use zigroot::core::project::ZigrootProject;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! # let dir = tempfile::tempdir()?;
+//! # std::fs::write(
+//! #     dir.path().join("zigroot.toml"),
+//! #     "[project]\nname = \"demo\"\n\n[packages]\nbusybox = { version = \"1.36.1\" }\n",
+//! # )?;
+//! # let project_dir = dir.path();
+//! let project = ZigrootProject::open(project_dir)?;
+//! let plan = project.resolve()?;
+//! for (step, package) in plan.order.iter().enumerate() {
+//!     println!("{}. {package}", step + 1);
+//! }
+//! # assert_eq!(plan.order, ["busybox"]);
+//! # Ok(())
+//! # }
+//! ```
 
 pub mod cli;
 pub mod config;
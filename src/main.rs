@@ -5,8 +5,11 @@
 use anyhow::Result;
 use clap::Parser;
 
-use zigroot::cli::output::{display_error, OutputConfig};
+use zigroot::cli::output::{display_error, ColorChoice, OutputConfig};
 use zigroot::cli::Cli;
+use zigroot::core::global_config::GlobalConfig;
+use zigroot::infra::dirs::ZigrootDirs;
+use zigroot::infra::http;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -20,10 +23,21 @@ async fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
-    // Apply output configuration globally
-    let output_config = OutputConfig::new(cli.quiet, cli.json, cli.verbose);
+    // Commands that read the global config report a broken file themselves
+    let config = GlobalConfig::load(&ZigrootDirs::new()).unwrap_or_default();
+
+    // Apply output configuration globally; `output.color = false` in the
+    // global config turns colors off unless --color asks for them
+    let color = match (cli.color, config.output.color) {
+        (ColorChoice::Auto, Some(false)) => ColorChoice::Never,
+        (color, _) => color,
+    };
+    let output_config = OutputConfig::new(cli.quiet, cli.json, cli.verbose).with_color(color);
     output_config.apply_global();
 
+    // Apply network settings to every HTTP client
+    http::configure(config.network_settings());
+
     // Run the command and handle errors
     match cli.run().await {
         Ok(()) => Ok(()),
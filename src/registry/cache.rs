@@ -1,9 +1,86 @@
 //! Registry cache implementation
 //!
-//! Caches registry index and package metadata locally.
+//! Caches registry index and package metadata locally, and describes how
+//! old cached data is when it is shown instead of a fresh copy.
 
 use std::path::PathBuf;
 
+use super::client::CachedData;
+
+/// How a lookup may use the cache and the network
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Use cached data, refreshing it when the TTL has expired
+    #[default]
+    Normal,
+    /// Ignore cached data and fetch it again
+    Refresh,
+    /// Use cached data only, never touch the network
+    Offline,
+}
+
+impl CachePolicy {
+    /// Policy from `--refresh` and `--offline` flags
+    pub fn from_flags(refresh: bool, offline: bool) -> Self {
+        if offline {
+            Self::Offline
+        } else if refresh {
+            Self::Refresh
+        } else {
+            Self::Normal
+        }
+    }
+}
+
+/// Age and freshness of cached data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStatus {
+    /// Seconds since the data was fetched
+    pub age_secs: u64,
+    /// Whether the TTL (or the server's expiry) has passed
+    pub stale: bool,
+}
+
+impl CacheStatus {
+    /// Status of data that was just fetched
+    pub fn fetched_now() -> Self {
+        Self {
+            age_secs: 0,
+            stale: false,
+        }
+    }
+
+    /// Status of cached data at `now` with a local `ttl`
+    pub fn of<T>(cached: &CachedData<T>, now: u64, ttl: u64) -> Self {
+        Self {
+            age_secs: now.saturating_sub(cached.cached_at),
+            stale: !cached.is_fresh(now, ttl),
+        }
+    }
+
+    /// Footer shown below cached data, e.g. "fetched 5 minutes ago"
+    pub fn footer(&self) -> String {
+        let age = format_age(self.age_secs);
+        if self.stale {
+            format!("fetched {age}, stale")
+        } else {
+            format!("fetched {age}")
+        }
+    }
+}
+
+/// Human-readable age such as "just now" or "3 hours ago"
+pub fn format_age(secs: u64) -> String {
+    let (count, unit) = match secs {
+        0..=59 => return "just now".to_string(),
+        60..=3599 => (secs / 60, "minute"),
+        3600..=86_399 => (secs / 3600, "hour"),
+        _ => (secs / 86_400, "day"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{count} {unit}{plural} ago")
+}
+
 /// Local cache for registry data
 #[derive(Debug)]
 pub struct RegistryCache {
@@ -31,3 +108,54 @@ impl Default for RegistryCache {
         Self::new(cache_dir)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached_at(cached_at: u64, expires_at: Option<u64>) -> CachedData<()> {
+        CachedData {
+            data: (),
+            cached_at,
+            etag: None,
+            last_modified: None,
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn test_status_at_ttl_boundaries() {
+        let cached = cached_at(1000, None);
+        assert!(!CacheStatus::of(&cached, 1000, 60).stale);
+        assert!(!CacheStatus::of(&cached, 1059, 60).stale);
+        assert!(CacheStatus::of(&cached, 1060, 60).stale);
+        assert_eq!(CacheStatus::of(&cached, 1060, 60).age_secs, 60);
+
+        // The server's expiry wins over the local TTL
+        let cached = cached_at(1000, Some(1010));
+        assert!(CacheStatus::of(&cached, 1010, 3600).stale);
+
+        // A clock set back does not underflow the age
+        assert_eq!(CacheStatus::of(&cached, 900, 60).age_secs, 0);
+    }
+
+    #[test]
+    fn test_footer() {
+        assert_eq!(format_age(59), "just now");
+        assert_eq!(format_age(60), "1 minute ago");
+        assert_eq!(format_age(3599), "59 minutes ago");
+        assert_eq!(format_age(3600), "1 hour ago");
+        assert_eq!(format_age(86_400 * 2), "2 days ago");
+
+        let status = CacheStatus {
+            age_secs: 300,
+            stale: false,
+        };
+        assert_eq!(status.footer(), "fetched 5 minutes ago");
+        let status = CacheStatus {
+            age_secs: 7200,
+            stale: true,
+        };
+        assert_eq!(status.footer(), "fetched 2 hours ago, stale");
+    }
+}
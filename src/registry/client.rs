@@ -2,17 +2,39 @@
 //!
 //! Fetches package and board definitions from GitHub raw URLs.
 
+use super::cache::{CachePolicy, CacheStatus};
 use crate::config::urls;
+use crate::infra::dirs::ZigrootDirs;
+use crate::infra::filesystem::write_file_atomic;
+use crate::infra::http;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
+/// Maximum number of TOML documents memoized by a client
+const MEMO_CAPACITY: usize = 256;
+
 /// Registry client errors
 #[derive(Error, Debug)]
 pub enum RegistryError {
     /// Network error
-    #[error("Network error fetching '{url}': {error}")]
-    NetworkError { url: String, error: String },
+    ///
+    /// For a refused request, `status`, `headers` and `body` say why: the
+    /// HTTP status, rate-limit headers and the start of the response body.
+    #[error("Network error fetching '{url}': {error}{}", http_details(.headers, .body.as_deref()))]
+    NetworkError {
+        url: String,
+        error: String,
+        /// HTTP status of the response, if one was received
+        status: Option<u16>,
+        /// Response headers explaining the refusal, e.g. `Retry-After`
+        headers: Vec<(String, String)>,
+        /// Start of the response body, with credentials redacted
+        body: Option<String>,
+    },
 
     /// Parse error
     #[error("Failed to parse registry data from '{url}': {error}")]
@@ -39,6 +61,117 @@ pub enum RegistryError {
     IoError { path: PathBuf, error: String },
 }
 
+/// Response headers kept in errors for refused requests
+const ERROR_HEADERS: &[&str] = &[
+    "retry-after",
+    "x-ratelimit-limit",
+    "x-ratelimit-remaining",
+    "x-ratelimit-reset",
+];
+
+/// Bytes of a refused response's body kept in errors
+const ERROR_BODY_LIMIT: usize = 200;
+
+impl RegistryError {
+    /// Error for a request that failed before a response arrived
+    fn network(url: &str, error: &impl std::fmt::Display) -> Self {
+        Self::NetworkError {
+            url: url.to_string(),
+            error: error.to_string(),
+            status: None,
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    /// Error for a response with a non-success status
+    ///
+    /// Reads at most [`ERROR_BODY_LIMIT`] bytes of the body.
+    async fn from_response(url: &str, mut response: reqwest::Response) -> Self {
+        let status = response.status();
+        let headers = ERROR_HEADERS
+            .iter()
+            .filter_map(|name| {
+                let value = response.headers().get(*name)?.to_str().ok()?;
+                Some(((*name).to_string(), value.to_string()))
+            })
+            .collect();
+
+        let mut bytes = Vec::new();
+        while bytes.len() < ERROR_BODY_LIMIT {
+            match response.chunk().await {
+                Ok(Some(chunk)) => bytes.extend_from_slice(&chunk),
+                _ => break,
+            }
+        }
+        let body = error_body_snippet(&bytes);
+
+        Self::NetworkError {
+            url: url.to_string(),
+            error: format!("HTTP {status}"),
+            status: Some(status.as_u16()),
+            headers,
+            body,
+        }
+    }
+}
+
+/// Printable start of a response body with credentials redacted
+///
+/// Whitespace is collapsed so the snippet fits on one line.
+fn error_body_snippet(bytes: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(&bytes[..bytes.len().min(ERROR_BODY_LIMIT)]);
+    let mut snippet = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if snippet.is_empty() {
+        return None;
+    }
+    if bytes.len() > ERROR_BODY_LIMIT {
+        snippet.push_str("...");
+    }
+    Some(redact_credentials(&snippet))
+}
+
+/// Replace credentials a server may echo back, such as auth headers
+fn redact_credentials(text: &str) -> String {
+    let patterns = [
+        (
+            r"(?i)\b((?:proxy-)?authorization)\s*[:=]\s*\S+(?:\s+\S+)?",
+            "$1: [REDACTED]",
+        ),
+        (
+            r"(?i)\b(bearer|token|basic)\s+[A-Za-z0-9._~+/=-]{8,}",
+            "$1 [REDACTED]",
+        ),
+        (r"\bgh[opsur]_[A-Za-z0-9]{16,}", "[REDACTED]"),
+    ];
+    let mut text = text.to_string();
+    for (pattern, replacement) in patterns {
+        if let Ok(re) = regex::Regex::new(pattern) {
+            text = re.replace_all(&text, replacement).into_owned();
+        }
+    }
+    text
+}
+
+/// Format the headers and body of a refused request for error messages
+fn http_details(headers: &[(String, String)], body: Option<&str>) -> String {
+    use std::fmt::Write;
+
+    let mut details = String::new();
+    if !headers.is_empty() {
+        let headers = headers
+            .iter()
+            .map(|(name, value)| format!("{name}: {value}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let _ = write!(details, " ({headers})");
+    }
+    if let Some(body) = body {
+        let _ = write!(details, ": {body}");
+    }
+    details
+}
+
 /// Package index entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackageIndexEntry {
@@ -96,6 +229,9 @@ pub struct BoardIndexEntry {
     /// Keywords for search
     #[serde(default)]
     pub keywords: Vec<String>,
+    /// Names of the board's sub-variants
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<String>,
 }
 
 /// Board index
@@ -109,6 +245,61 @@ pub struct BoardIndex {
     pub boards: Vec<BoardIndexEntry>,
 }
 
+/// Prebuilt host tool binary for one host platform
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostToolBinary {
+    /// Host platform, e.g. "x86_64-linux"
+    pub host: String,
+    /// Download URL of the static binary
+    pub url: String,
+    /// SHA256 checksum of the binary
+    pub sha256: String,
+}
+
+/// Host tool version entry in index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostToolVersionEntry {
+    /// Version string
+    pub version: String,
+    /// Binaries by host platform
+    #[serde(default)]
+    pub binaries: Vec<HostToolBinary>,
+}
+
+/// Host tool index entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostToolEntry {
+    /// Tool name, which is also the binary name
+    pub name: String,
+    /// Tool description
+    #[serde(default)]
+    pub description: String,
+    /// Available versions
+    pub versions: Vec<HostToolVersionEntry>,
+}
+
+/// Index of prebuilt static host tools
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostToolIndex {
+    /// Index format version
+    pub version: u32,
+    /// Last updated timestamp
+    pub updated: String,
+    /// List of tools
+    pub tools: Vec<HostToolEntry>,
+}
+
+/// Kind of cached registry data, each with its own cache TTL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheKind {
+    /// Package index and host tool index
+    PackageIndex,
+    /// Board index and board definitions
+    BoardIndex,
+    /// Per-package metadata and version files
+    PackageMetadata,
+}
+
 /// Registry configuration
 #[derive(Debug, Clone)]
 pub struct RegistryConfig {
@@ -116,8 +307,32 @@ pub struct RegistryConfig {
     pub repo: String,
     /// Branch to use, default "main"
     pub branch: String,
-    /// Local cache TTL in seconds
-    pub cache_ttl: u64,
+    /// Package index cache TTL in seconds
+    pub package_index_ttl: u64,
+    /// Board index cache TTL in seconds
+    pub board_index_ttl: u64,
+    /// Package metadata cache TTL in seconds
+    pub package_metadata_ttl: u64,
+}
+
+impl RegistryConfig {
+    /// Use the same cache TTL for every kind of data
+    #[must_use]
+    pub fn with_cache_ttl(mut self, ttl: u64) -> Self {
+        self.package_index_ttl = ttl;
+        self.board_index_ttl = ttl;
+        self.package_metadata_ttl = ttl;
+        self
+    }
+
+    /// Get the cache TTL of a kind of data
+    pub fn cache_ttl(&self, kind: CacheKind) -> u64 {
+        match kind {
+            CacheKind::PackageIndex => self.package_index_ttl,
+            CacheKind::BoardIndex => self.board_index_ttl,
+            CacheKind::PackageMetadata => self.package_metadata_ttl,
+        }
+    }
 }
 
 impl Default for RegistryConfig {
@@ -125,7 +340,9 @@ impl Default for RegistryConfig {
         Self {
             repo: "zigroot-project/zigroot-packages".to_string(),
             branch: "main".to_string(),
-            cache_ttl: 3600, // 1 hour
+            package_index_ttl: 3600,   // 1 hour
+            board_index_ttl: 86400,    // 1 day, boards rarely change
+            package_metadata_ttl: 900, // 15 minutes, follows new releases
         }
     }
 }
@@ -143,6 +360,133 @@ pub struct CachedData<T> {
     /// Last-Modified from server
     #[serde(default)]
     pub last_modified: Option<String>,
+    /// Expiry advertised by the server's `Cache-Control` (Unix timestamp)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+impl<T> CachedData<T> {
+    /// When the data goes stale (Unix timestamp)
+    ///
+    /// The server-advertised expiry wins; without one, the data is fresh for
+    /// the local `ttl` after it was cached.
+    pub fn expiry(&self, ttl: u64) -> u64 {
+        self.expires_at
+            .unwrap_or_else(|| self.cached_at.saturating_add(ttl))
+    }
+
+    /// Whether the data is still fresh at `now`
+    pub fn is_fresh(&self, now: u64, ttl: u64) -> bool {
+        now < self.expiry(ttl)
+    }
+}
+
+/// A document looked up with [`RegistryClient::lookup_board`] or
+/// [`RegistryClient::lookup_package_metadata`]
+#[derive(Debug)]
+pub struct CachedLookup {
+    /// The document, possibly stale
+    pub data: toml::Value,
+    /// Age and freshness of `data`
+    pub status: CacheStatus,
+    /// Background refresh of stale data, to be awaited before exiting
+    pub refresh: Option<tokio::task::JoinHandle<Result<(), RegistryError>>>,
+}
+
+impl CachedLookup {
+    /// Wait for the background refresh, if any
+    ///
+    /// Returns the error of a failed refresh; the cached data stays usable.
+    pub async fn finish_refresh(&mut self) -> Option<RegistryError> {
+        let handle = self.refresh.take()?;
+        match handle.await {
+            Ok(result) => result.err(),
+            Err(e) => Some(RegistryError::CacheError {
+                error: format!("refresh task failed: {e}"),
+            }),
+        }
+    }
+}
+
+/// Response to a conditional request
+enum Revalidated<T> {
+    /// The data changed
+    Modified(CachedData<T>),
+    /// The cached data is still current, with a new server expiry if any
+    NotModified { expires_at: Option<u64> },
+}
+
+/// Current Unix timestamp
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Expiry of a response from its `Cache-Control` and `Age` headers
+///
+/// Returns `None` when the server advertises no freshness, so the local TTL
+/// applies.
+fn response_expiry(headers: &reqwest::header::HeaderMap, now: u64) -> Option<u64> {
+    let cache_control = headers.get(reqwest::header::CACHE_CONTROL)?.to_str().ok()?;
+    let max_age = cache_control_max_age(cache_control)?;
+    let age = headers
+        .get(reqwest::header::AGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    Some(now.saturating_add(max_age.saturating_sub(age)))
+}
+
+/// Freshness lifetime in seconds from a `Cache-Control` value
+///
+/// `no-cache` and `no-store` make a response stale immediately.
+fn cache_control_max_age(value: &str) -> Option<u64> {
+    let mut max_age = None;
+    for directive in value.split(',') {
+        let (name, argument) = match directive.split_once('=') {
+            Some((name, argument)) => (name.trim(), Some(argument.trim().trim_matches('"'))),
+            None => (directive.trim(), None),
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "no-cache" | "no-store" => return Some(0),
+            "max-age" => max_age = argument.and_then(|a| a.parse().ok()),
+            _ => {}
+        }
+    }
+    max_age
+}
+
+/// Cache file of the package index
+const PACKAGE_INDEX_CACHE: &str = "packages-index.json";
+
+/// Cache file of the board index
+const BOARD_INDEX_CACHE: &str = "boards-index.json";
+
+/// Default registry cache directory, under the zigroot cache directory
+pub fn default_cache_dir() -> PathBuf {
+    ZigrootDirs::new().cache_dir().join("registry")
+}
+
+/// Read the cached package index without network access, even if stale
+///
+/// Returns `None` if nothing is cached or the cache cannot be read.
+pub fn cached_package_index(cache_dir: &Path) -> Option<PackageIndex> {
+    peek_cache(&cache_dir.join(PACKAGE_INDEX_CACHE))
+}
+
+/// Read the cached board index without network access, even if stale
+pub fn cached_board_index(cache_dir: &Path) -> Option<BoardIndex> {
+    peek_cache(&cache_dir.join(BOARD_INDEX_CACHE))
+}
+
+/// Read cached data, leaving corrupt files for the next fetch to replace
+fn peek_cache<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    let content = std::fs::read(path).ok()?;
+    serde_json::from_slice::<CachedData<T>>(&content)
+        .ok()
+        .map(|cached| cached.data)
 }
 
 /// Registry client for fetching packages and boards
@@ -156,28 +500,32 @@ pub struct RegistryClient {
     board_registry_url: String,
     /// Cache directory
     cache_dir: PathBuf,
-    /// Cache TTL in seconds
-    cache_ttl: u64,
+    /// Cache TTLs by kind of data
+    config: RegistryConfig,
+    /// TOML documents fetched in this process by cache file, with the time
+    /// they go stale
+    memo: Arc<Mutex<HashMap<String, (Instant, toml::Value)>>>,
 }
 
 impl RegistryClient {
     /// Create a new registry client with default URLs
     pub fn new() -> Self {
-        let cache_dir = dirs::cache_dir()
-            .unwrap_or_else(|| PathBuf::from(".cache"))
-            .join("zigroot")
-            .join("registry");
+        let cache_dir = default_cache_dir();
 
         Self {
-            client: reqwest::Client::new(),
+            client: http::client(),
             package_registry_url: urls::PACKAGE_REGISTRY.to_string(),
             board_registry_url: urls::BOARD_REGISTRY.to_string(),
             cache_dir,
-            cache_ttl: 3600, // 1 hour default
+            config: RegistryConfig::default(),
+            memo: Arc::default(),
         }
     }
 
     /// Create a registry client with custom URLs and cache directory
+    ///
+    /// `cache_ttl` applies to every kind of data; use
+    /// [`RegistryClient::with_registry_config`] for distinct TTLs.
     pub fn with_config(
         package_url: String,
         board_url: String,
@@ -185,11 +533,12 @@ impl RegistryClient {
         cache_ttl: u64,
     ) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            client: http::client(),
             package_registry_url: package_url,
             board_registry_url: board_url,
             cache_dir,
-            cache_ttl,
+            config: RegistryConfig::default().with_cache_ttl(cache_ttl),
+            memo: Arc::default(),
         }
     }
 
@@ -213,25 +562,43 @@ impl RegistryClient {
         &self.cache_dir
     }
 
-    /// Get the cache TTL
-    pub fn cache_ttl(&self) -> u64 {
-        self.cache_ttl
+    /// Use the cache TTLs of a registry configuration
+    #[must_use]
+    pub fn with_registry_config(mut self, config: RegistryConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Get the cache TTL of a kind of data
+    pub fn cache_ttl(&self, kind: CacheKind) -> u64 {
+        self.config.cache_ttl(kind)
     }
 
     /// Fetch the package index
     pub async fn fetch_package_index(&self) -> Result<PackageIndex, RegistryError> {
         let url = format!("{}/index.json", self.package_registry_url);
-        self.fetch_with_cache::<PackageIndex>(&url, "packages-index.json")
+        self.fetch_with_cache::<PackageIndex>(&url, PACKAGE_INDEX_CACHE, CacheKind::PackageIndex)
             .await
     }
 
     /// Fetch the board index
     pub async fn fetch_board_index(&self) -> Result<BoardIndex, RegistryError> {
         let url = format!("{}/index.json", self.board_registry_url);
-        self.fetch_with_cache::<BoardIndex>(&url, "boards-index.json")
+        self.fetch_with_cache::<BoardIndex>(&url, BOARD_INDEX_CACHE, CacheKind::BoardIndex)
             .await
     }
 
+    /// Fetch the host tool index
+    pub async fn fetch_host_tool_index(&self) -> Result<HostToolIndex, RegistryError> {
+        let url = format!("{}/host-tools/index.json", self.package_registry_url);
+        self.fetch_with_cache::<HostToolIndex>(
+            &url,
+            "host-tools-index.json",
+            CacheKind::PackageIndex,
+        )
+        .await
+    }
+
     /// Fetch package metadata
     pub async fn fetch_package_metadata(&self, name: &str) -> Result<toml::Value, RegistryError> {
         let url = format!(
@@ -239,7 +606,8 @@ impl RegistryClient {
             self.package_registry_url, name
         );
         let cache_file = format!("packages/{name}/metadata.toml");
-        self.fetch_toml_with_cache(&url, &cache_file).await
+        self.fetch_toml_with_cache(&url, &cache_file, CacheKind::PackageMetadata)
+            .await
     }
 
     /// Fetch package version data
@@ -253,21 +621,110 @@ impl RegistryClient {
             self.package_registry_url, name, version
         );
         let cache_file = format!("packages/{name}/{version}.toml");
-        self.fetch_toml_with_cache(&url, &cache_file).await
+        self.fetch_toml_with_cache(&url, &cache_file, CacheKind::PackageMetadata)
+            .await
     }
 
     /// Fetch board definition
     pub async fn fetch_board(&self, name: &str) -> Result<toml::Value, RegistryError> {
         let url = format!("{}/boards/{}/board.toml", self.board_registry_url, name);
         let cache_file = format!("boards/{name}/board.toml");
-        self.fetch_toml_with_cache(&url, &cache_file).await
+        self.fetch_toml_with_cache(&url, &cache_file, CacheKind::BoardIndex)
+            .await
+    }
+
+    /// Board definition, served from the cache first
+    ///
+    /// See [`RegistryClient::lookup_toml`] for how `policy` applies.
+    pub async fn lookup_board(
+        &self,
+        name: &str,
+        policy: CachePolicy,
+    ) -> Result<CachedLookup, RegistryError> {
+        let url = format!("{}/boards/{}/board.toml", self.board_registry_url, name);
+        let cache_file = format!("boards/{name}/board.toml");
+        self.lookup_toml(url, cache_file, CacheKind::BoardIndex, policy)
+            .await
+    }
+
+    /// Package metadata, served from the cache first
+    ///
+    /// See [`RegistryClient::lookup_toml`] for how `policy` applies.
+    pub async fn lookup_package_metadata(
+        &self,
+        name: &str,
+        policy: CachePolicy,
+    ) -> Result<CachedLookup, RegistryError> {
+        let url = format!(
+            "{}/packages/{}/metadata.toml",
+            self.package_registry_url, name
+        );
+        let cache_file = format!("packages/{name}/metadata.toml");
+        self.lookup_toml(url, cache_file, CacheKind::PackageMetadata, policy)
+            .await
+    }
+
+    /// A TOML document from the cache, even if stale, for display
+    ///
+    /// Stale data is returned right away and refreshed by a background task
+    /// unless `policy` is offline. Without cached data the document is
+    /// fetched, or an error returned when offline. [`CachePolicy::Refresh`]
+    /// always fetches.
+    async fn lookup_toml(
+        &self,
+        url: String,
+        cache_file: String,
+        kind: CacheKind,
+        policy: CachePolicy,
+    ) -> Result<CachedLookup, RegistryError> {
+        let cached = match policy {
+            CachePolicy::Refresh => None,
+            CachePolicy::Normal | CachePolicy::Offline => {
+                self.read_cache::<toml::Value>(&self.cache_dir.join(&cache_file))?
+            }
+        };
+
+        let Some(cached) = cached else {
+            if policy == CachePolicy::Offline {
+                return Err(RegistryError::CacheError {
+                    error: format!("'{cache_file}' is not cached; run again without --offline"),
+                });
+            }
+            let data = self.fetch_toml_fresh(&url, &cache_file, kind).await?;
+            return Ok(CachedLookup {
+                data,
+                status: CacheStatus::fetched_now(),
+                refresh: None,
+            });
+        };
+
+        let status = CacheStatus::of(&cached, unix_now(), self.cache_ttl(kind));
+        let refresh = (status.stale && policy == CachePolicy::Normal).then(|| {
+            let client = self.clone();
+            tokio::spawn(async move {
+                client
+                    .fetch_toml_fresh(&url, &cache_file, kind)
+                    .await
+                    .map(drop)
+            })
+        });
+        Ok(CachedLookup {
+            data: cached.data,
+            status,
+            refresh,
+        })
     }
 
     /// Force refresh of cached indexes
     pub async fn refresh(&self) -> Result<(), RegistryError> {
+        self.memo
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+
         // Clear cache files
-        let pkg_cache = self.cache_dir.join("packages-index.json");
-        let board_cache = self.cache_dir.join("boards-index.json");
+        let pkg_cache = self.cache_dir.join(PACKAGE_INDEX_CACHE);
+        let board_cache = self.cache_dir.join(BOARD_INDEX_CACHE);
 
         if pkg_cache.exists() {
             std::fs::remove_file(&pkg_cache).map_err(|e| RegistryError::IoError {
@@ -283,6 +740,15 @@ impl RegistryClient {
             })?;
         }
 
+        // The host tool index is fetched again when a build needs it
+        let host_tools_cache = self.cache_dir.join("host-tools-index.json");
+        if host_tools_cache.exists() {
+            std::fs::remove_file(&host_tools_cache).map_err(|e| RegistryError::IoError {
+                path: host_tools_cache,
+                error: e.to_string(),
+            })?;
+        }
+
         // Re-fetch indexes
         self.fetch_package_index().await?;
         self.fetch_board_index().await?;
@@ -290,8 +756,13 @@ impl RegistryClient {
         Ok(())
     }
 
-    /// Fetch JSON data with caching
-    async fn fetch_with_cache<T>(&self, url: &str, cache_file: &str) -> Result<T, RegistryError>
+    /// Fetch JSON data with caching, using the TTL of `kind`
+    async fn fetch_with_cache<T>(
+        &self,
+        url: &str,
+        cache_file: &str,
+        kind: CacheKind,
+    ) -> Result<T, RegistryError>
     where
         T: serde::de::DeserializeOwned + serde::Serialize + Clone,
     {
@@ -299,93 +770,82 @@ impl RegistryClient {
 
         // Check if we have valid cached data
         if let Some(cached) = self.read_cache::<T>(&cache_path)? {
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-
-            if now - cached.cached_at < self.cache_ttl {
+            if cached.is_fresh(unix_now(), self.cache_ttl(kind)) {
                 return Ok(cached.data);
             }
 
             // Cache expired, try conditional request
-            if let Some(data) = self
+            match self
                 .fetch_conditional(url, cached.etag.as_deref(), cached.last_modified.as_deref())
                 .await?
             {
-                // Got new data
-                self.write_cache(
-                    &cache_path,
-                    &data.data,
-                    data.etag.as_deref(),
-                    data.last_modified.as_deref(),
-                )?;
-                return Ok(data.data);
+                Revalidated::Modified(data) => {
+                    self.write_cache(&cache_path, &data)?;
+                    return Ok(data.data);
+                }
+                Revalidated::NotModified { expires_at } => {
+                    // Not modified, update cache timestamp and expiry
+                    let cached = CachedData {
+                        cached_at: unix_now(),
+                        expires_at,
+                        ..cached
+                    };
+                    self.write_cache(&cache_path, &cached)?;
+                    return Ok(cached.data);
+                }
             }
-            // Not modified, update cache timestamp
-            self.write_cache(
-                &cache_path,
-                &cached.data,
-                cached.etag.as_deref(),
-                cached.last_modified.as_deref(),
-            )?;
-            return Ok(cached.data);
         }
 
         // No cache, fetch fresh
         let data = self.fetch_fresh::<T>(url).await?;
-        self.write_cache(
-            &cache_path,
-            &data.data,
-            data.etag.as_deref(),
-            data.last_modified.as_deref(),
-        )?;
+        self.write_cache(&cache_path, &data)?;
         Ok(data.data)
     }
 
-    /// Fetch TOML data with caching
+    /// Fetch TOML data with caching, using the TTL of `kind`
     async fn fetch_toml_with_cache(
         &self,
         url: &str,
         cache_file: &str,
+        kind: CacheKind,
     ) -> Result<toml::Value, RegistryError> {
+        let ttl = self.cache_ttl(kind);
+        if let Some(data) = self.memoized(cache_file) {
+            return Ok(data);
+        }
+
         let cache_path = self.cache_dir.join(cache_file);
 
         // Check if we have valid cached data
         if let Some(cached) = self.read_cache::<toml::Value>(&cache_path)? {
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-
-            if now - cached.cached_at < self.cache_ttl {
+            let now = unix_now();
+            if cached.is_fresh(now, ttl) {
+                self.memoize(cache_file, &cached.data, cached.expiry(ttl) - now);
                 return Ok(cached.data);
             }
         }
 
-        // Fetch fresh TOML
-        let response =
-            self.client
-                .get(url)
-                .send()
-                .await
-                .map_err(|e| RegistryError::NetworkError {
-                    url: url.to_string(),
-                    error: e.to_string(),
-                })?;
-
-        if response.status() == reqwest::StatusCode::NOT_FOUND {
-            return Err(RegistryError::NetworkError {
-                url: url.to_string(),
-                error: "Not found".to_string(),
-            });
-        }
+        self.fetch_toml_fresh(url, cache_file, kind).await
+    }
+
+    /// Fetch a TOML document, bypassing the cache, and cache it
+    async fn fetch_toml_fresh(
+        &self,
+        url: &str,
+        cache_file: &str,
+        kind: CacheKind,
+    ) -> Result<toml::Value, RegistryError> {
+        let ttl = self.cache_ttl(kind);
+        let cache_path = self.cache_dir.join(cache_file);
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| RegistryError::network(url, &e))?;
 
         if !response.status().is_success() {
-            return Err(RegistryError::NetworkError {
-                url: url.to_string(),
-                error: format!("HTTP {}", response.status()),
-            });
+            return Err(RegistryError::from_response(url, response).await);
         }
 
         let etag = response
@@ -399,26 +859,52 @@ impl RegistryClient {
             .and_then(|v| v.to_str().ok())
             .map(String::from);
 
+        let now = unix_now();
+        let expires_at = response_expiry(response.headers(), now);
         let text = response
             .text()
             .await
-            .map_err(|e| RegistryError::NetworkError {
-                url: url.to_string(),
-                error: e.to_string(),
-            })?;
+            .map_err(|e| RegistryError::network(url, &e))?;
 
         let data: toml::Value = toml::from_str(&text).map_err(|e| RegistryError::ParseError {
             url: url.to_string(),
             error: e.to_string(),
         })?;
 
-        self.write_cache(
-            &cache_path,
-            &data,
-            etag.as_deref(),
-            last_modified.as_deref(),
-        )?;
-        Ok(data)
+        let cached = CachedData {
+            data,
+            cached_at: now,
+            etag,
+            last_modified,
+            expires_at,
+        };
+        self.write_cache(&cache_path, &cached)?;
+        self.memoize(cache_file, &cached.data, cached.expiry(ttl) - now);
+        Ok(cached.data)
+    }
+
+    /// TOML document fetched by this client that is still fresh
+    fn memoized(&self, cache_file: &str) -> Option<toml::Value> {
+        let memo = self.memo.lock().unwrap_or_else(PoisonError::into_inner);
+        let (stale_at, data) = memo.get(cache_file)?;
+        (Instant::now() < *stale_at).then(|| data.clone())
+    }
+
+    /// Remember a fetched TOML document for `fresh_secs` seconds, evicting
+    /// the one going stale first when full
+    fn memoize(&self, cache_file: &str, data: &toml::Value, fresh_secs: u64) {
+        let mut memo = self.memo.lock().unwrap_or_else(PoisonError::into_inner);
+        if memo.len() >= MEMO_CAPACITY && !memo.contains_key(cache_file) {
+            let oldest = memo
+                .iter()
+                .min_by_key(|(_, (stale_at, _))| *stale_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                memo.remove(&oldest);
+            }
+        }
+        let stale_at = Instant::now() + Duration::from_secs(fresh_secs);
+        memo.insert(cache_file.to_string(), (stale_at, data.clone()));
     }
 
     /// Fetch fresh data from URL
@@ -426,21 +912,15 @@ impl RegistryClient {
     where
         T: serde::de::DeserializeOwned,
     {
-        let response =
-            self.client
-                .get(url)
-                .send()
-                .await
-                .map_err(|e| RegistryError::NetworkError {
-                    url: url.to_string(),
-                    error: e.to_string(),
-                })?;
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| RegistryError::network(url, &e))?;
 
         if !response.status().is_success() {
-            return Err(RegistryError::NetworkError {
-                url: url.to_string(),
-                error: format!("HTTP {}", response.status()),
-            });
+            return Err(RegistryError::from_response(url, response).await);
         }
 
         let etag = response
@@ -454,6 +934,9 @@ impl RegistryClient {
             .and_then(|v| v.to_str().ok())
             .map(String::from);
 
+        let now = unix_now();
+        let expires_at = response_expiry(response.headers(), now);
+
         let data: T = response
             .json()
             .await
@@ -464,12 +947,10 @@ impl RegistryClient {
 
         Ok(CachedData {
             data,
-            cached_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            cached_at: now,
             etag,
             last_modified,
+            expires_at,
         })
     }
 
@@ -479,7 +960,7 @@ impl RegistryClient {
         url: &str,
         etag: Option<&str>,
         last_modified: Option<&str>,
-    ) -> Result<Option<CachedData<T>>, RegistryError>
+    ) -> Result<Revalidated<T>, RegistryError>
     where
         T: serde::de::DeserializeOwned,
     {
@@ -495,20 +976,16 @@ impl RegistryClient {
         let response = request
             .send()
             .await
-            .map_err(|e| RegistryError::NetworkError {
-                url: url.to_string(),
-                error: e.to_string(),
-            })?;
+            .map_err(|e| RegistryError::network(url, &e))?;
 
+        let now = unix_now();
+        let expires_at = response_expiry(response.headers(), now);
         if response.status() == reqwest::StatusCode::NOT_MODIFIED {
-            return Ok(None);
+            return Ok(Revalidated::NotModified { expires_at });
         }
 
         if !response.status().is_success() {
-            return Err(RegistryError::NetworkError {
-                url: url.to_string(),
-                error: format!("HTTP {}", response.status()),
-            });
+            return Err(RegistryError::from_response(url, response).await);
         }
 
         let new_etag = response
@@ -530,18 +1007,19 @@ impl RegistryClient {
                 error: e.to_string(),
             })?;
 
-        Ok(Some(CachedData {
+        Ok(Revalidated::Modified(CachedData {
             data,
-            cached_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            cached_at: now,
             etag: new_etag,
             last_modified: new_last_modified,
+            expires_at,
         }))
     }
 
     /// Read cached data from file
+    ///
+    /// A truncated or malformed cache file (e.g. from an interrupted write)
+    /// is deleted and treated as a cache miss.
     fn read_cache<T>(&self, path: &PathBuf) -> Result<Option<CachedData<T>>, RegistryError>
     where
         T: serde::de::DeserializeOwned,
@@ -550,27 +1028,28 @@ impl RegistryClient {
             return Ok(None);
         }
 
-        let content = std::fs::read_to_string(path).map_err(|e| RegistryError::IoError {
+        let content = std::fs::read(path).map_err(|e| RegistryError::IoError {
             path: path.clone(),
             error: e.to_string(),
         })?;
 
-        let cached: CachedData<T> =
-            serde_json::from_str(&content).map_err(|e| RegistryError::CacheError {
-                error: format!("Failed to parse cache file: {e}"),
-            })?;
-
-        Ok(Some(cached))
+        match serde_json::from_slice::<CachedData<T>>(&content) {
+            Ok(cached) => Ok(Some(cached)),
+            Err(e) => {
+                tracing::debug!(
+                    "Discarding corrupt registry cache file {}: {e}",
+                    path.display()
+                );
+                if let Err(e) = std::fs::remove_file(path) {
+                    tracing::debug!("Failed to remove {}: {e}", path.display());
+                }
+                Ok(None)
+            }
+        }
     }
 
     /// Write data to cache file
-    fn write_cache<T>(
-        &self,
-        path: &PathBuf,
-        data: &T,
-        etag: Option<&str>,
-        last_modified: Option<&str>,
-    ) -> Result<(), RegistryError>
+    fn write_cache<T>(&self, path: &Path, cached: &CachedData<T>) -> Result<(), RegistryError>
     where
         T: serde::Serialize,
     {
@@ -582,23 +1061,14 @@ impl RegistryClient {
             })?;
         }
 
-        let cached = CachedData {
-            data,
-            cached_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-            etag: etag.map(String::from),
-            last_modified: last_modified.map(String::from),
-        };
-
         let content =
-            serde_json::to_string_pretty(&cached).map_err(|e| RegistryError::CacheError {
+            serde_json::to_string_pretty(cached).map_err(|e| RegistryError::CacheError {
                 error: format!("Failed to serialize cache: {e}"),
             })?;
 
-        std::fs::write(path, content).map_err(|e| RegistryError::IoError {
-            path: path.clone(),
+        // Readers never observe a partially written file
+        write_file_atomic(path, content.as_bytes()).map_err(|e| RegistryError::IoError {
+            path: path.to_path_buf(),
             error: e.to_string(),
         })?;
 
@@ -628,7 +1098,14 @@ mod tests {
         let client = RegistryClient::new();
         assert!(client.package_registry_url().contains("zigroot-packages"));
         assert!(client.board_registry_url().contains("zigroot-boards"));
-        assert_eq!(client.cache_ttl(), 3600);
+        assert_eq!(client.cache_ttl(CacheKind::PackageIndex), 3600);
+        assert!(
+            client.cache_ttl(CacheKind::PackageMetadata)
+                < client.cache_ttl(CacheKind::PackageIndex)
+        );
+        assert!(
+            client.cache_ttl(CacheKind::BoardIndex) > client.cache_ttl(CacheKind::PackageIndex)
+        );
     }
 
     #[test]
@@ -645,7 +1122,13 @@ mod tests {
             "https://example.com/packages"
         );
         assert_eq!(client.board_registry_url(), "https://example.com/boards");
-        assert_eq!(client.cache_ttl(), 7200);
+        for kind in [
+            CacheKind::PackageIndex,
+            CacheKind::BoardIndex,
+            CacheKind::PackageMetadata,
+        ] {
+            assert_eq!(client.cache_ttl(kind), 7200);
+        }
     }
 
     // ============================================
@@ -710,6 +1193,7 @@ mod tests {
                 arch: "arm".to_string(),
                 target: "arm-linux-musleabihf".to_string(),
                 keywords: vec!["rockchip".to_string()],
+                variants: Vec::new(),
             }],
         };
 
@@ -777,6 +1261,106 @@ mod tests {
         assert!(cache_file.exists(), "Cache file should exist");
     }
 
+    #[tokio::test]
+    async fn test_corrupt_cache_is_refetched() {
+        let mock_server = MockServer::start().await;
+        let temp = TempDir::new().unwrap();
+
+        let index = PackageIndex {
+            version: 1,
+            updated: "2025-01-11T12:00:00Z".to_string(),
+            packages: vec![],
+        };
+
+        Mock::given(method("GET"))
+            .and(path("/index.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&index))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Truncated write left garbage behind
+        let cache_file = temp.path().join("packages-index.json");
+        std::fs::write(&cache_file, "{\"data\": {\"version\": 1, \"upd").unwrap();
+
+        let client = RegistryClient::with_config(
+            mock_server.uri(),
+            mock_server.uri(),
+            temp.path().to_path_buf(),
+            3600,
+        );
+
+        let result = client.fetch_package_index().await.unwrap();
+        assert_eq!(result.updated, index.updated);
+
+        // The cache was rewritten with valid data
+        let content = std::fs::read_to_string(&cache_file).unwrap();
+        assert!(serde_json::from_str::<CachedData<PackageIndex>>(&content).is_ok());
+    }
+
+    #[test]
+    fn test_cache_reads_never_see_partial_writes() {
+        let temp = TempDir::new().unwrap();
+        let client = RegistryClient::with_config(
+            "http://unused".to_string(),
+            "http://unused".to_string(),
+            temp.path().to_path_buf(),
+            3600,
+        );
+        let path = temp.path().join("packages-index.json");
+        let data = CachedData {
+            data: (0..20_000)
+                .map(|i| format!("package-{i}"))
+                .collect::<Vec<_>>(),
+            cached_at: unix_now(),
+            etag: None,
+            last_modified: None,
+            expires_at: None,
+        };
+        client.write_cache(&path, &data).unwrap();
+
+        std::thread::scope(|scope| {
+            let writer = scope.spawn(|| {
+                for _ in 0..20 {
+                    client.write_cache(&path, &data).unwrap();
+                }
+            });
+            while !writer.is_finished() {
+                let cached = client.read_cache::<Vec<String>>(&path).unwrap();
+                assert_eq!(cached.map(|c| c.data.len()), Some(data.data.len()));
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_toml_cache_is_refetched() {
+        let mock_server = MockServer::start().await;
+        let temp = TempDir::new().unwrap();
+
+        Mock::given(method("GET"))
+            .and(path("/packages/busybox/metadata.toml"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string("[package]\nname = \"busybox\"\n"),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let cache_file = temp.path().join("packages/busybox/metadata.toml");
+        std::fs::create_dir_all(cache_file.parent().unwrap()).unwrap();
+        std::fs::write(&cache_file, [0xff, 0xfe, 0x00]).unwrap();
+
+        let client = RegistryClient::with_config(
+            mock_server.uri(),
+            mock_server.uri(),
+            temp.path().to_path_buf(),
+            3600,
+        );
+
+        let metadata = client.fetch_package_metadata("busybox").await.unwrap();
+        assert_eq!(metadata["package"]["name"].as_str(), Some("busybox"));
+    }
+
     #[tokio::test]
     async fn test_cache_respects_ttl() {
         let mock_server = MockServer::start().await;
@@ -811,6 +1395,134 @@ mod tests {
         assert!(result2.is_ok());
     }
 
+    #[test]
+    fn test_cache_control_max_age() {
+        assert_eq!(cache_control_max_age("public, max-age=600"), Some(600));
+        assert_eq!(cache_control_max_age("Max-Age=\"30\""), Some(30));
+        assert_eq!(cache_control_max_age("max-age=600, no-cache"), Some(0));
+        assert_eq!(cache_control_max_age("no-store"), Some(0));
+        assert_eq!(cache_control_max_age("private"), None);
+        assert_eq!(cache_control_max_age("max-age=soon"), None);
+    }
+
+    #[test]
+    fn test_response_expiry_subtracts_age() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(response_expiry(&headers, 1000), None);
+
+        headers.insert("cache-control", "max-age=300".parse().unwrap());
+        assert_eq!(response_expiry(&headers, 1000), Some(1300));
+        headers.insert("age", "100".parse().unwrap());
+        assert_eq!(response_expiry(&headers, 1000), Some(1200));
+        headers.insert("age", "900".parse().unwrap());
+        assert_eq!(response_expiry(&headers, 1000), Some(1000));
+    }
+
+    #[tokio::test]
+    async fn test_server_max_age_overrides_local_ttl() {
+        let mock_server = MockServer::start().await;
+        let temp = TempDir::new().unwrap();
+
+        let index = PackageIndex {
+            version: 1,
+            updated: "2025-01-11T12:00:00Z".to_string(),
+            packages: vec![],
+        };
+        Mock::given(method("GET"))
+            .and(path("/index.json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(&index)
+                    .insert_header("Cache-Control", "public, max-age=3600"),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/boards/index.json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(BoardIndex {
+                        version: 1,
+                        updated: String::new(),
+                        boards: vec![],
+                    })
+                    .insert_header("Cache-Control", "max-age=0"),
+            )
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        // The server keeps the package index fresh despite the zero TTL and
+        // makes the board index stale despite the long one
+        let client = RegistryClient::with_config(
+            mock_server.uri(),
+            format!("{}/boards", mock_server.uri()),
+            temp.path().to_path_buf(),
+            0,
+        )
+        .with_registry_config(RegistryConfig {
+            package_index_ttl: 0,
+            board_index_ttl: 3600,
+            ..RegistryConfig::default()
+        });
+        for _ in 0..2 {
+            client.fetch_package_index().await.unwrap();
+            client.fetch_board_index().await.unwrap();
+        }
+
+        // The expiry is stored with the cached data
+        let content = std::fs::read_to_string(temp.path().join("packages-index.json")).unwrap();
+        let cached: CachedData<PackageIndex> = serde_json::from_str(&content).unwrap();
+        assert_eq!(cached.expiry(0), cached.cached_at + 3600);
+    }
+
+    #[tokio::test]
+    async fn test_cache_ttl_per_kind() {
+        let mock_server = MockServer::start().await;
+        let temp = TempDir::new().unwrap();
+
+        let package_index = PackageIndex {
+            version: 1,
+            updated: "2025-01-11T12:00:00Z".to_string(),
+            packages: vec![],
+        };
+        let board_index = BoardIndex {
+            version: 1,
+            updated: "2025-01-11T12:00:00Z".to_string(),
+            boards: vec![],
+        };
+        Mock::given(method("GET"))
+            .and(path("/packages/index.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&package_index))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/boards/index.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&board_index))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Package index expires immediately, the board index doesn't
+        let client = RegistryClient::with_config(
+            format!("{}/packages", mock_server.uri()),
+            format!("{}/boards", mock_server.uri()),
+            temp.path().to_path_buf(),
+            0,
+        )
+        .with_registry_config(RegistryConfig {
+            package_index_ttl: 0,
+            ..RegistryConfig::default()
+        });
+
+        for _ in 0..2 {
+            client.fetch_package_index().await.unwrap();
+            client.fetch_board_index().await.unwrap();
+        }
+    }
+
     // ============================================
     // Async Tests - Conditional requests (ETag/Last-Modified)
     // ============================================
@@ -941,6 +1653,35 @@ type = "make"
         assert_eq!(metadata["package"]["name"].as_str(), Some("busybox"));
     }
 
+    #[tokio::test]
+    async fn test_fetch_package_metadata_is_memoized() {
+        let mock_server = MockServer::start().await;
+        let temp = TempDir::new().unwrap();
+
+        Mock::given(method("GET"))
+            .and(path("/packages/busybox/metadata.toml"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string("[package]\nname = \"busybox\"\n"),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = RegistryClient::with_config(
+            mock_server.uri(),
+            mock_server.uri(),
+            temp.path().to_path_buf(),
+            3600,
+        );
+        client.fetch_package_metadata("busybox").await.unwrap();
+
+        // Served from memory, without the cache file or the network
+        std::fs::remove_dir_all(temp.path().join("packages")).unwrap();
+        let metadata = client.fetch_package_metadata("busybox").await.unwrap();
+        assert_eq!(metadata["package"]["name"].as_str(), Some("busybox"));
+        assert!(!temp.path().join("packages").exists());
+    }
+
     #[tokio::test]
     async fn test_fetch_package_version() {
         let mock_server = MockServer::start().await;
@@ -1059,6 +1800,83 @@ cpu = "cortex_a7"
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_refused_request_reports_status_headers_and_body() {
+        let mock_server = MockServer::start().await;
+        let temp = TempDir::new().unwrap();
+
+        let body = format!(
+            "{{\"message\": \"API rate limit exceeded\", \"request\": \"Authorization: token ghp_{}\"}} {}",
+            "a".repeat(36),
+            "x".repeat(300)
+        );
+        Mock::given(method("GET"))
+            .and(path("/index.json"))
+            .respond_with(
+                ResponseTemplate::new(403)
+                    .insert_header("Retry-After", "60")
+                    .insert_header("X-RateLimit-Remaining", "0")
+                    .insert_header("Set-Cookie", "session=secret")
+                    .set_body_string(body),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = RegistryClient::with_config(
+            mock_server.uri(),
+            mock_server.uri(),
+            temp.path().to_path_buf(),
+            3600,
+        );
+
+        let error = client.fetch_package_index().await.unwrap_err();
+        let message = error.to_string();
+        let RegistryError::NetworkError {
+            status,
+            headers,
+            body,
+            ..
+        } = error
+        else {
+            panic!("Expected NetworkError, got: {error:?}");
+        };
+        assert_eq!(status, Some(403));
+        assert_eq!(
+            headers,
+            vec![
+                ("retry-after".to_string(), "60".to_string()),
+                ("x-ratelimit-remaining".to_string(), "0".to_string()),
+            ]
+        );
+        let body = body.unwrap();
+        assert!(body.contains("API rate limit exceeded"));
+        assert!(body.ends_with("..."));
+        assert!(body.len() < 220, "{body}");
+        assert!(!body.contains("ghp_"), "{body}");
+
+        assert!(message.contains("HTTP 403"), "{message}");
+        assert!(message.contains("retry-after: 60"), "{message}");
+        assert!(message.contains("API rate limit exceeded"), "{message}");
+        assert!(!message.contains("secret"), "{message}");
+    }
+
+    #[test]
+    fn test_redact_credentials() {
+        assert_eq!(
+            redact_credentials("Authorization: Bearer abc.def.ghi rest"),
+            "Authorization: [REDACTED] rest"
+        );
+        assert_eq!(
+            redact_credentials("used token 0123456789abcdef"),
+            "used token [REDACTED]"
+        );
+        assert_eq!(
+            redact_credentials("leaked gho_0123456789abcdefABCD"),
+            "leaked [REDACTED]"
+        );
+        assert_eq!(redact_credentials("Not Found"), "Not Found");
+    }
+
     #[tokio::test]
     async fn test_fetch_parse_error() {
         let mock_server = MockServer::start().await;
@@ -187,6 +187,7 @@ fn test_add_package_from_git() {
             "custom-pkg",
             "--git",
             "https://github.com/example/repo#v1.0.0",
+            "--offline",
         ],
     );
 
@@ -213,6 +214,151 @@ fn test_add_package_from_git() {
     );
 }
 
+/// Test: --ref selects the git ref and requires --git
+#[test]
+fn test_add_package_from_git_with_ref() {
+    let project = setup_project();
+
+    let output = run_add(
+        &project,
+        &[
+            "custom-pkg",
+            "--git",
+            "https://github.com/example/repo",
+            "--ref",
+            "v2.1.0",
+            "--offline",
+        ],
+    );
+    assert!(
+        output.status.success(),
+        "zigroot add --git --ref should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let manifest: toml::Value = toml::from_str(&project.read_file("zigroot.toml")).unwrap();
+    let package = &manifest["packages"]["custom-pkg"];
+    assert_eq!(
+        package["git"].as_str(),
+        Some("https://github.com/example/repo")
+    );
+    assert_eq!(package["ref"].as_str(), Some("v2.1.0"));
+
+    let output = run_add(&project, &["other-pkg", "--ref", "main"]);
+    assert!(!output.status.success(), "--ref without --git should fail");
+}
+
+/// Test: --git resolves the ref to a commit recorded in the lock file
+#[test]
+fn test_add_package_from_git_pins_commit() {
+    let repo = tempfile::TempDir::new().unwrap();
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .current_dir(repo.path())
+            .args([
+                "-c",
+                "user.name=zigroot",
+                "-c",
+                "user.email=zigroot@example.com",
+            ])
+            .args(args)
+            .output()
+            .expect("Failed to execute git");
+        assert!(output.status.success(), "git {args:?} failed");
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    git(&["init", "-q", "-b", "main"]);
+    git(&["commit", "-q", "--allow-empty", "-m", "first"]);
+    git(&["tag", "-a", "v1.0.0", "-m", "release"]);
+    let tagged = git(&["rev-parse", "HEAD"]);
+    git(&["commit", "-q", "--allow-empty", "-m", "second"]);
+    let head = git(&["rev-parse", "HEAD"]);
+    let url = format!("file://{}", repo.path().display());
+
+    let project = setup_project();
+    let locked_sha = |name: &str| {
+        let lock: toml::Value = toml::from_str(&project.read_file("zigroot.lock")).unwrap();
+        lock["package"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|p| p["name"].as_str() == Some(name))
+            .and_then(|p| p.get("git_sha")?.as_str())
+            .map(String::from)
+    };
+
+    let output = run_add(&project, &["tagged", "--git", &url, "--ref", "v1.0.0"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(locked_sha("tagged"), Some(tagged));
+    let output = run_add(&project, &["branch", "--git", &format!("{url}#main")]);
+    assert!(output.status.success());
+    assert_eq!(locked_sha("branch"), Some(head));
+
+    let output = run_add(&project, &["missing", "--git", &url, "--ref", "v9"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Ref 'v9' not found"), "stderr={stderr}");
+
+    let unreachable = format!("file://{}/nothing", repo.path().display());
+    let output = run_add(&project, &["gone", "--git", &unreachable]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Cannot reach git repository"),
+        "stderr={stderr}"
+    );
+
+    // --offline adds the package without pinning it
+    let output = run_add(&project, &["gone", "--git", &unreachable, "--offline"]);
+    assert!(output.status.success());
+    assert_eq!(locked_sha("gone"), None);
+}
+
+/// Test: Without network, add records the request and warns it is unresolved
+#[test]
+fn test_add_package_warns_when_version_unresolved() {
+    let project = setup_project();
+
+    let output = run_add(&project, &["busybox"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "zigroot add should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let version = get_package_version(&project, "busybox").unwrap();
+    if version == "latest" {
+        assert!(
+            stdout.contains("Could not resolve a version of 'busybox'"),
+            "stdout={stdout}"
+        );
+    } else {
+        assert!(version.starts_with('^'), "version={version}");
+    }
+}
+
+/// Test: --no-deps adds only the requested package
+#[test]
+fn test_add_package_no_deps() {
+    let project = setup_project();
+
+    let output = run_add(&project, &["nginx", "--no-deps"]);
+    assert!(
+        output.status.success(),
+        "zigroot add --no-deps should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let manifest: toml::Value = toml::from_str(&project.read_file("zigroot.toml")).unwrap();
+    let packages = manifest["packages"].as_table().unwrap();
+    assert_eq!(packages.keys().collect::<Vec<_>>(), vec!["nginx"]);
+}
+
 /// Test: Adds package from custom registry with --registry flag
 /// **Validates: Requirement 2.4**
 #[test]
@@ -604,3 +750,35 @@ proptest! {
         }
     }
 }
+
+/// Test: A name missing from the registry index fails with suggestions
+#[test]
+fn test_add_unknown_package_suggests_names() {
+    let project = setup_project();
+    let cached_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    project.create_file(
+        "cache/zigroot/registry/packages-index.json",
+        &format!(
+            r#"{{"cached_at": {cached_at}, "data": {{"version": 1, "updated": "", "packages": [
+                {{"name": "busybox", "description": "", "versions": [{{"version": "1.36.1"}}], "latest": "1.36.1"}}
+            ]}}}}"#
+        ),
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .current_dir(project.path())
+        .env("XDG_CACHE_HOME", project.path().join("cache"))
+        .args(["add", "busyboxx"])
+        .output()
+        .expect("Failed to execute zigroot add");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Add should fail");
+    assert!(
+        stderr.contains("Package 'busyboxx' not found in registry; did you mean 'busybox'?"),
+        "stderr={stderr}"
+    );
+    assert!(!manifest_has_package(&project, "busyboxx"));
+}
@@ -462,3 +462,115 @@ fn test_board_info_different_boards() {
         );
     }
 }
+
+/// Cache a board definition fetched `age_secs` ago under `cache_home`
+fn cache_board(cache_home: &std::path::Path, name: &str, age_secs: u64) {
+    let board = common::SAMPLE_BOARD.replace("test-board", name);
+    cache_board_toml(cache_home, name, &board, age_secs);
+}
+
+/// Cache `content` as the board.toml of `name` under `cache_home`
+fn cache_board_toml(cache_home: &std::path::Path, name: &str, content: &str, age_secs: u64) {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let board: toml::Value = toml::from_str(content).expect("board.toml");
+    let cached = serde_json::json!({ "data": board, "cached_at": now - age_secs });
+    let dir = cache_home.join(format!("zigroot/registry/boards/{name}"));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("board.toml"), cached.to_string()).unwrap();
+}
+
+/// Run board info with the registry cache under `cache_home`
+fn run_cached_board_info(cache_home: &std::path::Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .env("XDG_CACHE_HOME", cache_home)
+        .args(["board", "info"])
+        .args(args)
+        .output()
+        .expect("Failed to execute zigroot board info")
+}
+
+/// Test: --offline shows cached data with its age and never needs the network
+#[test]
+fn test_board_info_offline_uses_cache() {
+    let cache_home = tempfile::TempDir::new().unwrap();
+    cache_board(cache_home.path(), "cached-board", 600);
+    cache_board(cache_home.path(), "old-board", 3 * 86_400);
+
+    let output = run_cached_board_info(cache_home.path(), &["cached-board", "--offline"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("Board: cached-board"), "{stdout}");
+    assert!(stdout.contains("(fetched 10 minutes ago)"), "{stdout}");
+
+    let output = run_cached_board_info(cache_home.path(), &["old-board", "--offline"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("(fetched 3 days ago, stale)"), "{stdout}");
+
+    let output = run_cached_board_info(cache_home.path(), &["missing-board", "--offline"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not cached"));
+
+    let output = run_cached_board_info(
+        cache_home.path(),
+        &["cached-board", "--offline", "--refresh"],
+    );
+    assert!(
+        !output.status.success(),
+        "--offline conflicts with --refresh"
+    );
+}
+
+/// Test: Options, variants and defaults are shown as sections, and --json
+/// emits the whole definition
+#[test]
+fn test_board_info_shows_options_and_variants() {
+    let cache_home = tempfile::TempDir::new().unwrap();
+    let board = format!(
+        r#"{}
+[options.memory]
+type = "choice"
+default = "1G"
+choices = ["1G", "2G", "8G"]
+description = "Installed memory"
+
+[[variants]]
+name = "2gb"
+description = "2 GB RAM"
+options = {{ memory = "2G" }}
+"#,
+        common::SAMPLE_BOARD.replace("test-board", "rpi4")
+    );
+    cache_board_toml(cache_home.path(), "rpi4", &board, 60);
+
+    let output = run_cached_board_info(cache_home.path(), &["rpi4", "--offline"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    let options = stdout.find("Options:").expect("options section");
+    let variants = stdout.find("Variants:").expect("variants section");
+    assert!(options < variants, "{stdout}");
+    assert!(
+        stdout.contains("memory (choice) - Installed memory"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("Choices: 1G, 2G, 8G"), "{stdout}");
+    assert!(stdout.contains("rpi4:2gb - 2 GB RAM"), "{stdout}");
+    assert!(stdout.contains("Rootfs size: 256M"), "{stdout}");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .env("XDG_CACHE_HOME", cache_home.path())
+        .args(["--json", "board", "info", "rpi4", "--offline"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["name"], "rpi4");
+    assert_eq!(json["options"]["memory"]["default"], "1G");
+    assert_eq!(json["variants"][0]["options"]["memory"], "2G");
+}
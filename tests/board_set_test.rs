@@ -368,6 +368,28 @@ fn test_board_set_can_change_board() {
     );
 }
 
+/// Test: Board set refuses to run while another zigroot process holds the
+/// project
+#[test]
+fn test_board_set_waits_for_project_lock() {
+    use zigroot::infra::flock::lock_project;
+
+    let project = setup_project();
+    let manifest = read_manifest(&project);
+    let _lock = lock_project(&project.path(), false, |_| {})
+        .unwrap()
+        .unwrap();
+
+    let output = run_board_set(&project, "luckfox-pico");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        !output.status.success(),
+        "board set should fail while locked"
+    );
+    assert!(stderr.contains("--wait"), "Should suggest --wait: {stderr}");
+    assert_eq!(read_manifest(&project), manifest);
+}
+
 // ============================================
 // Property-Based Tests
 // ============================================
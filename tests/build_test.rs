@@ -5,11 +5,12 @@
 //! - Uses Zig cross-compilation with target triple
 //! - Builds statically linked binaries
 //! - Skips unchanged packages (incremental build)
-//! - --package rebuilds only specified package
+//! - --package rebuilds the specified package and its dependencies
 //! - --jobs limits parallel compilation
 //! - --locked fails if package differs from lock
 //! - Creates rootfs image
 //! - Displays build summary
+//! - --watch rebuilds changed packages and their dependents
 //!
 //! **Property 8: Incremental Build Correctness**
 //! **Property 11: Local Package Priority**
@@ -66,14 +67,16 @@ fn output_dir_exists(project: &TestProject) -> bool {
 
 /// Helper to check if rootfs image exists
 fn rootfs_image_exists(project: &TestProject) -> bool {
-    let output_dir = project.path().join("output");
-    if !output_dir.is_dir() {
+    let Ok(boards) = std::fs::read_dir(project.path().join("output")) else {
         return false;
-    }
-    // Check for any image file
-    output_dir.join("rootfs.img").exists()
-        || output_dir.join("rootfs.squashfs").exists()
-        || output_dir.join("rootfs.cpio").exists()
+    };
+    // Check for any image file in the latest build of any board
+    boards.flatten().any(|board| {
+        let latest = board.path().join("latest");
+        latest.join("rootfs.img").exists()
+            || latest.join("rootfs.squashfs").exists()
+            || latest.join("rootfs.cpio").exists()
+    })
 }
 
 /// Helper to check if lock file exists
@@ -550,6 +553,491 @@ version = "99.0.0"
     );
 }
 
+/// Test: Build enforces size budget and records per-package sizes
+#[test]
+fn test_build_size_budget_and_analyze_size() {
+    let project = setup_project();
+    create_local_package(&project, "bigpkg", "1.0.0");
+    create_local_package(&project, "smallpkg", "1.0.0");
+
+    let manifest = r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[build]
+size_budget = "4K"
+
+[packages.bigpkg]
+version = "1.0.0"
+
+[packages.smallpkg]
+version = "1.0.0"
+"#;
+    project.create_file("zigroot.toml", manifest);
+
+    // Simulate installed package files in the staging directory
+    project.create_file("build/destdir/bigpkg/usr/bin/big", &"x".repeat(8192));
+    project.create_file("build/destdir/smallpkg/usr/bin/small", &"x".repeat(100));
+
+    let output = run_build(&project, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        !output.status.success(),
+        "Build over budget should fail: {stderr}"
+    );
+    assert!(stderr.contains("exceeds budget"), "stderr: {stderr}");
+    assert!(stderr.contains("bigpkg"), "stderr: {stderr}");
+
+    // The size table is available without rebuilding
+    let output = run_build(&project, &["--analyze-size"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "--analyze-size should succeed");
+    let big = stdout.find("bigpkg").expect("bigpkg listed");
+    let small = stdout.find("smallpkg").expect("smallpkg listed");
+    assert!(big < small, "Largest package first: {stdout}");
+
+    // Raising the budget lets the build succeed
+    project.create_file(
+        "zigroot.toml",
+        &manifest.replace("size_budget = \"4K\"", "size_budget = \"64M\""),
+    );
+    let output = run_build(&project, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "Build within budget should succeed"
+    );
+    assert!(
+        stdout.contains("Installed size by package"),
+        "stdout: {stdout}"
+    );
+}
+
+/// Test: Package durations are recorded and drive the overall progress
+#[test]
+fn test_build_records_history_and_reports_overall_progress() {
+    let project = setup_project();
+    create_local_package(&project, "app", "1.0.0");
+    create_local_package(&project, "lib", "1.0.0");
+    let manifest = project.read_file("zigroot.toml");
+    project.create_file(
+        "zigroot.toml",
+        &format!("{manifest}\n[packages.app]\nversion = \"1.0.0\"\n\n[packages.lib]\nversion = \"1.0.0\"\n"),
+    );
+    let cache_dir = project.path().join("cache");
+    let history_path = cache_dir.join("build-history.json");
+    // A corrupt history is ignored
+    project.create_file("cache/build-history.json", "{ not json");
+
+    let build = || {
+        Command::new(env!("CARGO_BIN_EXE_zigroot"))
+            .current_dir(project.path())
+            .env("ZIGROOT_CACHE_DIR", &cache_dir)
+            .args(["--json", "build"])
+            .output()
+            .expect("Failed to execute zigroot build")
+    };
+    let output = build();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "Build should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let events: Vec<serde_json::Value> = stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .filter(|event: &serde_json::Value| event["event"] == "overall_progress")
+        .collect();
+    assert_eq!(events.len(), 2, "stdout: {stdout}");
+    assert_eq!(events[1]["completed"], events[1]["total"]);
+    assert_eq!(events[1]["total"], 2.0, "Unknown packages weigh one second");
+
+    let history: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&history_path).unwrap()).unwrap();
+    let keys: Vec<&String> = history["durations"].as_object().unwrap().keys().collect();
+    assert_eq!(keys.len(), 2, "{history}");
+    assert!(keys[0].starts_with("app@1.0.0#"), "{keys:?}");
+
+    // Changing a package's version starts without a timing
+    let manifest = project.read_file("zigroot.toml");
+    project.create_file(
+        "zigroot.toml",
+        &manifest.replacen(
+            "[packages.app]\nversion = \"1.0.0\"",
+            "[packages.app]\nversion = \"1.1.0\"",
+            1,
+        ),
+    );
+    std::fs::remove_dir_all(project.path().join("build/stamps")).unwrap();
+    assert!(build().status.success());
+    let history: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&history_path).unwrap()).unwrap();
+    assert!(history["durations"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .any(|key| key.starts_with("app@1.1.0#")));
+}
+
+/// Test: --watch rebuilds a changed package and its dependents, then exits on Ctrl+C
+#[test]
+fn test_build_watch_rebuilds_affected_packages() {
+    use std::io::BufRead;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let project = setup_project();
+    create_local_package(&project, "app", "1.0.0");
+    create_local_package(&project, "lib", "1.0.0");
+    create_local_package(&project, "tool", "1.0.0");
+    let app = project.read_file("packages/app/package.toml");
+    project.create_file(
+        "packages/app/package.toml",
+        &app.replacen("[package]\n", "[package]\ndepends = [\"lib\"]\n", 1),
+    );
+    let manifest = project.read_file("zigroot.toml");
+    let packages = "\n[packages.app]\nversion = \"1.0.0\"\n\n[packages.lib]\nversion = \"1.0.0\"\n\n[packages.tool]\nversion = \"1.0.0\"\n";
+    project.create_file("zigroot.toml", &format!("{manifest}{packages}"));
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .current_dir(project.path())
+        .env("ZIGROOT_CACHE_DIR", project.path().join("cache"))
+        .args(["--json", "build", "--watch"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .expect("Failed to execute zigroot build --watch");
+    let stdout = child.stdout.take().unwrap();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::BufReader::new(stdout)
+            .lines()
+            .map_while(Result::ok)
+        {
+            if let Ok(event) = serde_json::from_str::<serde_json::Value>(&line) {
+                if event.get("iteration").is_some() {
+                    let _ = tx.send(event);
+                }
+            }
+        }
+    });
+    let next = || {
+        rx.recv_timeout(Duration::from_secs(60))
+            .expect("no watch summary")
+    };
+
+    let initial = next();
+    assert_eq!(initial["iteration"], 0);
+    assert_eq!(initial["status"], "success", "{initial}");
+    assert_eq!(initial["rebuilt"].as_array().unwrap().len(), 3);
+
+    // Let the watcher settle before changing a file
+    std::thread::sleep(Duration::from_millis(500));
+    project.create_file("packages/lib/build.sh", "#!/bin/sh\necho changed\n");
+    let rebuild = next();
+    assert_eq!(rebuild["iteration"], 1);
+    assert_eq!(rebuild["status"], "success", "{rebuild}");
+    assert_eq!(rebuild["rebuilt"], serde_json::json!(["lib", "app"]));
+    assert_eq!(
+        rebuild["changed"],
+        serde_json::json!(["packages/lib/build.sh"])
+    );
+
+    let interrupted = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(interrupted.success());
+    assert!(child.wait().unwrap().success(), "Ctrl+C exits cleanly");
+}
+
+/// Test: Stripping skips files that are not ELF binaries
+#[test]
+fn test_build_strip_skips_non_elf_files() {
+    let project = setup_project();
+    create_local_package(&project, "scripts", "1.0.0");
+
+    let manifest = r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[build]
+strip = true
+split_debug = true
+
+[packages.scripts]
+version = "1.0.0"
+"#;
+    project.create_file("zigroot.toml", manifest);
+    let script = "#!/bin/sh\necho hello\n";
+    project.create_file("build/destdir/scripts/usr/bin/hello", script);
+
+    let output = run_build(&project, &[]);
+    assert!(
+        output.status.success(),
+        "Build should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(project.read_file("build/rootfs/usr/bin/hello"), script);
+    assert!(!project.file_exists("output/default/ext4/debug/usr/bin/hello.debug"));
+}
+
+/// Test: Stripping reports the bytes saved and honors per-package opt-out
+#[test]
+fn test_build_strip_reports_savings_and_opt_out() {
+    let compiled = |dest: &str| {
+        Command::new("cc")
+            .args(["-g", "-x", "c", "-", "-o", dest])
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .and_then(|mut child| {
+                use std::io::Write;
+                child
+                    .stdin
+                    .take()
+                    .unwrap()
+                    .write_all(b"int main(void) { return 0; }\n")?;
+                child.wait()
+            })
+            .is_ok_and(|status| status.success())
+    };
+    if which::which("objcopy").is_err() {
+        return;
+    }
+
+    let project = setup_project();
+    create_local_package(&project, "app", "1.0.0");
+    create_local_package(&project, "keep", "1.0.0");
+    project.create_file(
+        "packages/keep/package.toml",
+        &project
+            .read_file("packages/keep/package.toml")
+            .replace("type = \"custom\"", "type = \"custom\"\nstrip = false"),
+    );
+    let manifest = "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n\n\
+                    [packages.app]\nversion = \"1.0.0\"\n\n\
+                    [packages.keep]\nversion = \"1.0.0\"\n";
+    project.create_file("zigroot.toml", manifest);
+    project.create_dir("build/destdir/app/usr/bin");
+    project.create_dir("build/destdir/keep/usr/bin");
+    let app = project.path().join("build/destdir/app/usr/bin/app");
+    let keep = project.path().join("build/destdir/keep/usr/bin/keep");
+    if !compiled(app.to_str().unwrap()) || !compiled(keep.to_str().unwrap()) {
+        return;
+    }
+    let keep_size = std::fs::metadata(&keep).unwrap().len();
+
+    let output = run_build(&project, &["--no-sandbox", "--no-validate"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "Build should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("Stripped: 1 binaries"),
+        "Summary should report stripping: {stdout}"
+    );
+    assert_eq!(
+        std::fs::metadata(project.path().join("build/rootfs/usr/bin/keep"))
+            .unwrap()
+            .len(),
+        keep_size
+    );
+}
+
+/// Test: the assembled rootfs is validated after building
+#[test]
+fn test_build_validates_rootfs() {
+    let project = setup_project();
+    create_local_package(&project, "base", "1.0.0");
+    let manifest = "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n\n\
+                    [packages.base]\nversion = \"1.0.0\"\n";
+    project.create_file("zigroot.toml", manifest);
+    project.create_file(
+        "build/destdir/base/etc/passwd",
+        "root:x:0:0:root:/root:/bin/bash\n",
+    );
+
+    // Findings are warnings by default
+    let output = run_build(&project, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "Build should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("Validation: 2 warning(s)"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("/etc/passwd: login shell /bin/bash of user 'root' is not installed"),
+        "stdout: {stdout}"
+    );
+
+    // Severities can be raised in the manifest
+    project.create_file(
+        "zigroot.toml",
+        &format!("{manifest}\n[build.validation]\nmissing-shell = \"error\"\n"),
+    );
+    let output = run_build(&project, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Build should fail");
+    assert!(
+        stderr.contains("Rootfs validation failed with 1 error(s)"),
+        "stderr: {stderr}"
+    );
+    assert!(stderr.contains("[missing-shell]"), "stderr: {stderr}");
+
+    let output = run_build(&project, &["--no-validate"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "Build should succeed");
+    assert!(!stdout.contains("Validation"), "stdout: {stdout}");
+}
+
+/// Test: staged symlinks that do not resolve inside the rootfs are reported
+#[test]
+fn test_build_reports_dangling_symlinks() {
+    let project = setup_project();
+    create_local_package(&project, "base", "1.0.0");
+    let manifest = "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n\n\
+                    [packages.base]\nversion = \"1.0.0\"\n";
+    project.create_file("zigroot.toml", manifest);
+    project.create_file("build/destdir/base/bin/busybox", "#!/bin/sh\n");
+    let bin = project.path().join("build/destdir/base/bin");
+    for (link, target) in [
+        ("sh", "busybox"),
+        ("ls", "/bin/busybox"),
+        ("gone", "missing-tool"),
+        ("host-tool", "/home/builder/bin/tool"),
+    ] {
+        std::os::unix::fs::symlink(target, bin.join(link)).unwrap();
+    }
+
+    let output = run_build(&project, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "Build should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        std::fs::read_link(project.path().join("build/rootfs/bin/ls")).unwrap(),
+        std::path::Path::new("/bin/busybox")
+    );
+    assert!(
+        stdout.contains("/bin/gone: symlink to missing-tool does not resolve inside the rootfs"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains(
+            "/bin/host-tool: symlink to /home/builder/bin/tool does not resolve inside the rootfs"
+        ),
+        "stdout: {stdout}"
+    );
+    assert!(!stdout.contains("/bin/ls:"), "stdout: {stdout}");
+    assert!(!stdout.contains("/bin/sh:"), "stdout: {stdout}");
+}
+
+/// Test: switching package options back restores the earlier build trees
+#[test]
+fn test_build_keeps_trees_per_configuration() {
+    let project = setup_project();
+    create_local_package(&project, "app", "1.0.0");
+    let definition = project.read_file("packages/app/package.toml");
+    project.create_file(
+        "packages/app/package.toml",
+        &format!(
+            "{definition}\n[options.debug]\ntype = \"bool\"\ndefault = false\n\
+             description = \"Debug build\"\n"
+        ),
+    );
+    let manifest = |debug: bool| {
+        format!(
+            "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n\n\
+             [packages.app]\nversion = \"1.0.0\"\n\n[packages.app.options]\ndebug = {debug}\n"
+        )
+    };
+    let marker = "build/destdir/app/usr/share/app/config";
+
+    project.create_file("zigroot.toml", &manifest(false));
+    assert!(run_build(&project, &[]).status.success());
+    project.create_file(marker, "release");
+    let release_stamp = get_package_build_timestamp(&project, "app").unwrap();
+
+    // A new configuration builds in fresh trees
+    project.create_file("zigroot.toml", &manifest(true));
+    assert!(run_build(&project, &[]).status.success());
+    assert!(!project.file_exists(marker));
+    project.create_file(marker, "debug");
+
+    // Switching back restores the release build without rebuilding it
+    project.create_file("zigroot.toml", &manifest(false));
+    assert!(run_build(&project, &[]).status.success());
+    assert_eq!(project.read_file(marker), "release");
+    assert_eq!(
+        get_package_build_timestamp(&project, "app"),
+        Some(release_stamp)
+    );
+    assert_eq!(
+        project.read_file("build/rootfs/usr/share/app/config"),
+        "release"
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .current_dir(project.path())
+        .args(["cache", "info"])
+        .output()
+        .expect("Failed to execute zigroot cache info");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Package configurations"),
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("debug=false (active)"), "stdout: {stdout}");
+    assert!(stdout.contains("debug=true ("), "stdout: {stdout}");
+}
+
+/// Test: files of a package removed from the project leave the rootfs
+#[test]
+fn test_build_removes_files_of_dropped_packages() {
+    let project = setup_project();
+    create_local_package(&project, "base", "1.0.0");
+    create_local_package(&project, "extra", "1.0.0");
+    let base = "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n\n\
+                [packages.base]\nversion = \"1.0.0\"\n";
+    project.create_file(
+        "zigroot.toml",
+        &format!("{base}\n[packages.extra]\nversion = \"1.0.0\"\n"),
+    );
+    project.create_file("build/destdir/base/etc/hostname", "board\n");
+    project.create_file("build/destdir/extra/usr/share/extra/data", "extra\n");
+
+    let output = run_build(&project, &["--no-validate"]);
+    assert!(
+        output.status.success(),
+        "Build should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(project.file_exists("build/rootfs/usr/share/extra/data"));
+
+    project.create_file("zigroot.toml", base);
+    let output = run_build(&project, &["--no-validate"]);
+    assert!(
+        output.status.success(),
+        "Build should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(project.file_exists("build/rootfs/etc/hostname"));
+    assert!(!project.file_exists("build/rootfs/usr/share/extra"));
+    assert!(!project.file_exists("build/installed/extra.files"));
+}
+
 /// Test: Build fails gracefully with invalid manifest
 /// **Validates: Requirement 11.4**
 #[test]
@@ -755,3 +1243,1018 @@ version = "{local_version}"
         );
     }
 }
+
+/// Test: Initramfs compression and kernel embedding settings
+#[test]
+fn test_build_initramfs_compression_and_embedding() {
+    let project = setup_project();
+    let manifest = r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[build]
+image_format = "initramfs"
+initramfs_compression = "gzip"
+"#;
+    project.create_file("zigroot.toml", manifest);
+
+    // A kernel config without the matching decompressor is rejected
+    project.create_file("kernel/.config", "# CONFIG_RD_GZIP is not set\n");
+    let output = run_build(&project, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Build should fail");
+    assert!(stderr.contains("CONFIG_RD_GZIP"), "stderr: {stderr}");
+
+    project.create_file("kernel/.config", "CONFIG_RD_GZIP=y\n");
+    let output = run_build(&project, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Build should succeed: {stderr}");
+    assert!(project.file_exists("output/default/initramfs/rootfs.cpio.gz"));
+    assert!(!project.file_exists("output/default/initramfs/rootfs.cpio"));
+
+    // Embedding requires a zigroot-managed kernel
+    project.create_file(
+        "zigroot.toml",
+        &format!("{manifest}embed_in_kernel = true\n"),
+    );
+    let output = run_build(&project, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Build should fail");
+    assert!(
+        stderr.contains("no zigroot-managed kernel"),
+        "stderr: {stderr}"
+    );
+
+    // With a kernel package the standalone cpio is only written on request
+    project.create_file(
+        "packages/linux-kernel/package.toml",
+        "[package]\nname = \"linux-kernel\"\nversion = \"6.6.0\"\ndescription = \"Kernel\"\n",
+    );
+    std::fs::remove_file(
+        project
+            .path()
+            .join("output/default/initramfs/rootfs.cpio.gz"),
+    )
+    .unwrap();
+    let output = run_build(&project, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "Build should succeed");
+    assert!(
+        stdout.contains("embedded into the kernel"),
+        "stdout: {stdout}"
+    );
+    assert!(!project.file_exists("output/default/initramfs/rootfs.cpio.gz"));
+
+    let output = run_build(&project, &["--also-standalone"]);
+    assert!(output.status.success(), "Build should succeed");
+    assert!(project.file_exists("output/default/initramfs/rootfs.cpio.gz"));
+}
+
+/// Test: an initramfs is a newc cpio archive of the rootfs
+#[test]
+fn test_build_initramfs_is_cpio_archive() {
+    let project = setup_project();
+    project.create_file(
+        "zigroot.toml",
+        r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[build]
+image_format = "initramfs"
+initramfs_compression = "none"
+rootfs_size = "not-a-size"
+"#,
+    );
+
+    let output = run_build(&project, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Build should succeed: {stderr}");
+
+    let archive =
+        std::fs::read(project.path().join("output/default/initramfs/rootfs.cpio")).unwrap();
+    assert!(archive.starts_with(b"070701"));
+    let content = String::from_utf8_lossy(&archive);
+    assert!(content.contains("dev/console"));
+    assert!(content.contains("TRAILER!!!"));
+    assert_eq!(archive.len() % 4, 0);
+}
+
+/// Test: `[permissions]` entries must be octal and exist in the rootfs
+#[test]
+fn test_build_validates_permissions_overlay() {
+    let project = setup_project();
+    let manifest = |entry: &str| {
+        format!(
+            "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n\n\
+             [build]\nimage_format = \"initramfs\"\ninitramfs_compression = \"none\"\n\n\
+             [permissions]\n{entry}\n"
+        )
+    };
+
+    project.create_file(
+        "zigroot.toml",
+        &manifest("\"/bin/su\" = { mode = \"4755\" }"),
+    );
+    let output = run_build(&project, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Build should fail");
+    assert!(
+        stderr.contains("'/bin/su' does not exist in the assembled rootfs"),
+        "stderr: {stderr}"
+    );
+
+    project.create_file(
+        "zigroot.toml",
+        &manifest("\"/dev\" = { mode = \"rwxr-x\" }"),
+    );
+    let output = run_build(&project, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Build should fail");
+    assert!(stderr.contains("invalid mode 'rwxr-x'"), "stderr: {stderr}");
+}
+
+/// Test: a fixed `rootfs_size` too small for the content fails before the
+/// image is created, and `auto` sizes the image to the content
+#[test]
+fn test_build_rejects_rootfs_size_below_content() {
+    let project = setup_project();
+    let manifest = |size: &str| {
+        format!(
+            "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n\n\
+             [build]\nimage_format = \"ext4\"\nrootfs_size = \"{size}\"\n"
+        )
+    };
+
+    project.create_file("zigroot.toml", &manifest("1M"));
+    let output = run_build(&project, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Build should fail");
+    assert!(stderr.contains("too small"), "stderr: {stderr}");
+    assert!(stderr.contains("at least"), "stderr: {stderr}");
+    assert!(!rootfs_image_exists(&project));
+
+    project.create_file("zigroot.toml", &manifest("auto"));
+    let output = run_build(&project, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Build should succeed: {stderr}");
+    assert!(rootfs_image_exists(&project));
+}
+
+/// Test: a partition layout produces a full disk image
+#[test]
+fn test_build_disk_image_from_partitions() {
+    let project = setup_project();
+    let manifest = r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[external.bootloader]
+type = "bootloader"
+path = "firmware/u-boot.bin"
+
+[disk_image]
+format = "gpt"
+name = "sdcard.img"
+
+[[partitions]]
+name = "uboot"
+type = "raw"
+offset = "32K"
+size = "512K"
+content = "bootloader"
+
+[[partitions]]
+name = "root"
+type = "ext4"
+size = "2M"
+content = "rootfs"
+"#;
+    project.create_file("zigroot.toml", manifest);
+    project.create_file("firmware/u-boot.bin", "u-boot image");
+
+    let output = run_build(&project, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Build should succeed: {stderr}");
+
+    let disk = std::fs::read(project.path().join("output/default/ext4/sdcard.img")).unwrap();
+    assert_eq!(disk.len(), 4 << 20);
+    assert_eq!(&disk[510..512], &[0x55, 0xAA]);
+    assert_eq!(&disk[512..520], b"EFI PART");
+    assert_eq!(&disk[disk.len() - 512..disk.len() - 504], b"EFI PART");
+    assert!(disk[32768..].starts_with(b"u-boot image"));
+    assert!(disk[1 << 20..].starts_with(b"# Zigroot test-project image"));
+
+    // Content larger than its partition fails with exact numbers
+    project.create_file("firmware/u-boot.bin", &"x".repeat(600 << 10));
+    let output = run_build(&project, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Build should fail");
+    assert!(
+        stderr.contains(
+            "Content of partition 'uboot' is 614400 bytes, but the partition is 524288 bytes"
+        ),
+        "stderr: {stderr}"
+    );
+}
+
+/// Test: reproducible builds give identical images and write an attestation
+#[test]
+fn test_build_reproducible_images_are_identical() {
+    let project = setup_project();
+    let manifest = r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[build]
+reproducible = true
+
+[disk_image]
+format = "gpt"
+name = "sdcard.img"
+
+[[partitions]]
+name = "data"
+type = "ext4"
+size = "2M"
+content = "data"
+"#;
+    project.create_file("zigroot.toml", manifest);
+
+    let mut digests = Vec::new();
+    for round in 0..2 {
+        // Fresh files each round, so only their content is the same
+        if round == 1 {
+            std::thread::sleep(std::time::Duration::from_millis(1100));
+        }
+        let _ = std::fs::remove_dir_all(project.path().join("data"));
+        let _ = std::fs::remove_dir_all(project.path().join("output"));
+        project.create_file("data/etc/motd", "hello\n");
+        project.create_file("data/init", "#!/bin/sh\n");
+
+        let output = run_build(&project, &[]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "Build should succeed: {stderr}");
+
+        let content = project.read_file("output/default/ext4/attestation.json");
+        let attestation: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(attestation["project"], "test-project");
+        assert_eq!(attestation["lock_hash"].as_str().unwrap().len(), 64);
+        assert!(attestation["toolchains"]["zig"].is_string());
+        assert_eq!(attestation["options"]["build"]["reproducible"], true);
+        assert_eq!(attestation["artifacts"][1]["path"], "sdcard.img");
+        digests.push(attestation["artifacts"].clone());
+    }
+    assert_eq!(digests[0], digests[1]);
+}
+
+/// Test: --reproducible and `SOURCE_DATE_EPOCH` turn on reproducible builds
+/// without `build.reproducible` in the manifest
+#[test]
+fn test_build_reproducible_flag_and_source_date_epoch() {
+    let project = setup_project();
+    project.create_file(
+        "zigroot.toml",
+        r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[disk_image]
+format = "gpt"
+name = "sdcard.img"
+
+[[partitions]]
+name = "data"
+type = "ext4"
+size = "2M"
+content = "data"
+"#,
+    );
+    let image = "output/default/ext4/sdcard.img";
+
+    let mut images = Vec::new();
+    for round in 0..2 {
+        // Fresh files each round, created in a different order
+        if round == 1 {
+            std::thread::sleep(std::time::Duration::from_millis(1100));
+        }
+        let _ = std::fs::remove_dir_all(project.path().join("data"));
+        let mut files = vec![("data/etc/motd", "hello\n"), ("data/init", "#!/bin/sh\n")];
+        if round == 1 {
+            files.reverse();
+        }
+        for (path, content) in files {
+            project.create_file(path, content);
+        }
+
+        let output = run_build(&project, &["--reproducible"]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "Build should succeed: {stderr}");
+        images.push(std::fs::read(project.path().join(image)).unwrap());
+    }
+    assert!(images[0] == images[1], "images differ between builds");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .current_dir(project.path())
+        .env("SOURCE_DATE_EPOCH", "1700000000")
+        .arg("build")
+        .output()
+        .expect("Failed to execute zigroot build");
+    assert!(output.status.success());
+    let content = project.read_file("output/default/ext4/attestation.json");
+    let attestation: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(attestation["source_date_epoch"], 1_700_000_000);
+    assert_eq!(attestation["options"]["build"]["reproducible"], true);
+}
+
+/// Test: the final image gets a `sha256sum` file, and a signature when
+/// `build.signing_key` is configured
+#[test]
+fn test_build_writes_image_checksum_and_signature() {
+    use base64::Engine as _;
+    use zigroot::core::signing::{PublicKey, SigningKey};
+
+    let project = setup_project();
+    let zigroot = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zigroot"))
+            .current_dir(project.path())
+            .env("ZIGROOT_CONFIG_DIR", project.path().join("config"))
+            .args(args)
+            .output()
+            .expect("Failed to execute zigroot")
+    };
+
+    let output = zigroot(&["build", "--report"]);
+    assert!(
+        output.status.success(),
+        "Build should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: serde_json::Value =
+        serde_json::from_str(&project.read_file("output/build-report.json")).unwrap();
+    let image = std::path::PathBuf::from(report["checksum"]["path"].as_str().unwrap());
+    let sha256 = report["checksum"]["sha256"].as_str().unwrap();
+    assert_eq!(
+        sha256,
+        zigroot::infra::download::compute_checksum(&std::fs::read(&image).unwrap())
+    );
+    let sidecar = std::fs::read_to_string(format!("{}.sha256", image.display())).unwrap();
+    let file_name = image.file_name().unwrap().to_string_lossy();
+    assert_eq!(sidecar, format!("{sha256}  {file_name}\n"));
+    assert!(report["checksum"]["signature"].is_null());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!("SHA256: {sha256}")));
+
+    let key = project.path().join("image.key");
+    std::fs::write(
+        &key,
+        base64::engine::general_purpose::STANDARD.encode([7u8; 32]),
+    )
+    .unwrap();
+    let output = zigroot(&["config", "set", "build.signing_key", &key.to_string_lossy()]);
+    assert!(output.status.success());
+    let output = zigroot(&["--json", "build"]);
+    assert!(output.status.success());
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let signature = summary["checksum"]["signature"].as_str().unwrap();
+    assert_eq!(signature, format!("{}.sig", image.display()));
+
+    let public = project.path().join("image.pub");
+    std::fs::write(&public, SigningKey::load(&key).unwrap().public_key()).unwrap();
+    PublicKey::load(&public)
+        .unwrap()
+        .verify_image(&image, summary["checksum"]["sha256"].as_str().unwrap())
+        .unwrap();
+}
+
+/// Test: --report writes a JSON build report, also for failed builds
+#[test]
+fn test_build_report() {
+    let project = setup_project();
+    project.create_file(
+        "zigroot.toml",
+        r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[packages.hello]
+version = "1.0.0"
+"#,
+    );
+    project.create_file(
+        "packages/hello/package.toml",
+        "[package]\nname = \"hello\"\nversion = \"1.0.0\"\ndescription = \"Hello\"\n",
+    );
+
+    let output = run_build(&project, &[]);
+    assert!(output.status.success(), "Build should succeed");
+    assert!(!project.file_exists("output/build-report.json"));
+
+    let output = run_build(&project, &["--report"]);
+    assert!(output.status.success(), "Build should succeed");
+    let content = std::fs::read_to_string(project.path().join("output/build-report.json")).unwrap();
+    let report: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(report["success"], true);
+    assert_eq!(report["project"], "test-project");
+    assert_eq!(report["packages"][0]["name"], "hello");
+    assert_eq!(report["packages"][0]["version"], "1.0.0");
+    assert_eq!(report["packages"][0]["source"], "path:packages/hello");
+    assert!(report["toolchain"].is_string());
+    assert!(report["image_size"].as_u64().unwrap() > 0);
+
+    // A failed build still writes the report to the requested path
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n\n[build]\nsize_budget = \"lots\"\n",
+    );
+    let output = run_build(&project, &["--report", "reports/failed.json"]);
+    assert!(!output.status.success(), "Build should fail");
+    let content = std::fs::read_to_string(project.path().join("reports/failed.json")).unwrap();
+    let report: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(report["success"], false);
+    assert!(report["error"].as_str().unwrap().contains("size_budget"));
+}
+
+/// Test: outputs are namespaced by board and format, with a latest link
+#[test]
+fn test_build_output_layout() {
+    let project = setup_project();
+    let manifest = "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n";
+    project.create_file("zigroot.toml", manifest);
+    // Images of the old flat layout are left alone
+    project.create_file("output/rootfs.img", "old image");
+
+    let output = run_build(&project, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Build should succeed: {stderr}");
+    assert!(project.file_exists("output/default/ext4/rootfs.img"));
+    assert!(project.file_exists("output/default/ext4/size-report.json"));
+    assert_eq!(project.read_file("output/rootfs.img"), "old image");
+
+    project.create_file(
+        "zigroot.toml",
+        &format!("{manifest}\n[build]\nimage_format = \"initramfs\"\n"),
+    );
+    let output = run_build(&project, &[]);
+    assert!(output.status.success(), "Build should succeed");
+    assert!(project.file_exists("output/default/initramfs/rootfs.cpio"));
+    assert!(project.file_exists("output/default/ext4/rootfs.img"));
+    let latest = std::fs::read_link(project.path().join("output/default/latest")).unwrap();
+    assert_eq!(latest, std::path::Path::new("initramfs"));
+
+    // --output-dir takes precedence over build.output_dir
+    project.create_file(
+        "zigroot.toml",
+        &format!("{manifest}\n[build]\noutput_dir = \"images\"\n"),
+    );
+    let output = run_build(&project, &[]);
+    assert!(output.status.success(), "Build should succeed");
+    assert!(project.file_exists("images/default/ext4/rootfs.img"));
+
+    let output = run_build(&project, &["--output-dir", "artifacts"]);
+    assert!(output.status.success(), "Build should succeed");
+    assert!(project.file_exists("artifacts/default/latest/rootfs.img"));
+}
+
+/// Test: --package builds dependencies first unless --no-deps is given
+#[test]
+fn test_build_package_includes_dependencies() {
+    let project = setup_project();
+    project.create_file(
+        "zigroot.toml",
+        r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[packages.app]
+version = "1.0.0"
+
+[packages.lib]
+version = "1.0.0"
+
+[packages.unrelated]
+version = "1.0.0"
+"#,
+    );
+    for (name, depends) in [("app", "\"lib\""), ("lib", ""), ("unrelated", "")] {
+        project.create_file(
+            &format!("packages/{name}/package.toml"),
+            &format!(
+                "[package]\nname = \"{name}\"\nversion = \"1.0.0\"\ndescription = \"{name}\"\n\
+                 depends = [{depends}]\n\n[source]\nurl = \"https://example.com/{name}.tar.gz\"\n\
+                 sha256 = \"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\"\n"
+            ),
+        );
+    }
+
+    // Dependencies must already be built with --no-deps
+    let output = run_build(&project, &["--package", "app", "--no-deps"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Build should fail");
+    assert!(
+        stderr.contains("dependencies not built yet: lib"),
+        "stderr: {stderr}"
+    );
+
+    let output = run_build(&project, &["--package", "app"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Build should succeed: {stderr}");
+    assert!(project.file_exists("build/stamps/app.stamp"));
+    assert!(project.file_exists("build/stamps/lib.stamp"));
+    assert!(!project.file_exists("build/stamps/unrelated.stamp"));
+
+    let output = run_build(&project, &["--package", "app", "--no-deps"]);
+    assert!(output.status.success(), "Build should succeed");
+}
+
+/// Test: builds stop early when the disk is too small for the rootfs
+#[test]
+fn test_build_checks_disk_space() {
+    let project = setup_project();
+    project.create_file(
+        "zigroot.toml",
+        r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[build]
+rootfs_size = "100000G"
+"#,
+    );
+
+    let output = run_build(&project, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Build should fail");
+    assert!(stderr.contains("Not enough disk space"), "stderr: {stderr}");
+    assert!(stderr.contains("--skip-space-check"), "stderr: {stderr}");
+    assert!(!project.file_exists("build"));
+
+    let output = run_build(&project, &["--skip-space-check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Build should succeed: {stderr}");
+}
+
+/// Test: an interrupted build resumes after the packages it completed
+#[test]
+fn test_build_resumes_after_interrupt() {
+    let project = setup_project();
+    let counter = |name: &str| project.path().join(format!("{name}.count"));
+    let hang = project.path().join("hang");
+    for (name, depends, steps) in [
+        (
+            "first",
+            "",
+            vec![format!("echo x >> {}", counter("first").display())],
+        ),
+        (
+            "slow",
+            "\"first\"",
+            vec![
+                format!("echo x >> {}", counter("slow").display()),
+                "touch \"$DESTDIR/built\"".to_string(),
+                format!("test ! -e {} || exec sleep 30", hang.display()),
+            ],
+        ),
+    ] {
+        let steps: String = steps
+            .iter()
+            .map(|step| format!("\n[[build.steps]]\nrun = {step:?}\n"))
+            .collect();
+        project.create_file(
+            &format!("packages/{name}/package.toml"),
+            &format!(
+                "[package]\nname = \"{name}\"\nversion = \"1.0.0\"\ndescription = \"{name}\"\n\
+                 depends = [{depends}]\n\n[source]\nurl = \"https://example.com/{name}.tar.gz\"\n\
+                 sha256 = \"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\"\n\n\
+                 [build]\ntype = \"custom\"\n{steps}"
+            ),
+        );
+    }
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n\n\
+         [packages.first]\nversion = \"1.0.0\"\n\n[packages.slow]\nversion = \"1.0.0\"\n",
+    );
+    std::fs::write(&hang, "").unwrap();
+
+    // Interrupt the build while the slow package's step runs
+    let child = Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .current_dir(project.path())
+        .args(["build", "--no-sandbox"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to execute zigroot build");
+    let started = std::time::Instant::now();
+    while !counter("slow").exists() {
+        assert!(
+            started.elapsed().as_secs() < 60,
+            "slow package never started"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    let status = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Build should be interrupted");
+    assert!(
+        stderr.contains("resume with zigroot build"),
+        "stderr: {stderr}"
+    );
+    assert!(
+        started.elapsed().as_secs() < 25,
+        "the running step should be stopped"
+    );
+    assert_eq!(project.read_file("build/interrupted"), "slow\n");
+    assert!(project.file_exists("build/stamps/first.stamp"));
+    assert!(!project.file_exists("build/stamps/slow.stamp"));
+    assert!(!project.file_exists("build/destdir/slow"));
+
+    // The next build cleans up and only builds the interrupted package
+    std::fs::remove_file(&hang).unwrap();
+    let output = run_build(&project, &["--no-sandbox"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Build should succeed: {stderr}");
+    assert_eq!(std::fs::read_to_string(counter("first")).unwrap(), "x\n");
+    assert_eq!(std::fs::read_to_string(counter("slow")).unwrap(), "x\nx\n");
+    assert!(project.file_exists("build/destdir/slow/built"));
+    assert!(!project.file_exists("build/destdir/slow.partial"));
+    assert!(!project.file_exists("build/interrupted"));
+    assert!(project.file_exists("build/stamps/slow.stamp"));
+}
+
+/// Test: patches listed in `[source]` are applied before the build steps,
+/// and changing them rebuilds the package
+#[test]
+fn test_build_applies_source_patches() {
+    let project = setup_project();
+    let counter = project.path().join("app.count");
+    project.create_file(
+        "packages/app/package.toml",
+        &format!(
+            "[package]\nname = \"app\"\nversion = \"1.0.0\"\ndescription = \"app\"\n\n\
+             [source]\nurl = \"https://example.com/app.tar.gz\"\n\
+             sha256 = \"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\"\n\
+             patches = [\"patches/series\"]\n\n\
+             [build]\ntype = \"custom\"\n\n\
+             [[build.steps]]\nrun = \"echo x >> {}\"\n\n\
+             [[build.steps]]\nrun = \"cp \\\"$SRCDIR/greeting\\\" \\\"$DESTDIR/greeting\\\"\"\n",
+            counter.display()
+        ),
+    );
+    project.create_file("packages/app/patches/series", "# fixes\nwording.patch\n");
+    let patch =
+        "--- a/greeting\n+++ b/greeting\n@@ -1,2 +1,2 @@\n-hello\n+hello, world\n from zigroot\n";
+    project.create_file("packages/app/patches/wording.patch", patch);
+    project.create_file("build/src/app/greeting", "hello\nfrom zigroot\n");
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n\n[packages.app]\nversion = \"1.0.0\"\n",
+    );
+
+    let output = run_build(&project, &["--no-sandbox"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Build should succeed: {stderr}");
+    assert_eq!(
+        project.read_file("build/destdir/app/greeting"),
+        "hello, world\nfrom zigroot\n"
+    );
+
+    // Unchanged patches keep the package up to date
+    let output = run_build(&project, &["--no-sandbox"]);
+    assert!(output.status.success());
+    assert_eq!(std::fs::read_to_string(&counter).unwrap(), "x\n");
+
+    // A changed patch is applied to the original sources and rebuilds
+    project.create_file(
+        "packages/app/patches/wording.patch",
+        &patch.replace("hello, world", "hello, board"),
+    );
+    let output = run_build(&project, &["--no-sandbox"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Build should succeed: {stderr}");
+    assert_eq!(std::fs::read_to_string(&counter).unwrap(), "x\nx\n");
+    assert_eq!(
+        project.read_file("build/destdir/app/greeting"),
+        "hello, board\nfrom zigroot\n"
+    );
+
+    // A hunk that does not apply names the patch and the hunk
+    project.create_file(
+        "packages/app/patches/wording.patch",
+        &patch.replace("-hello\n", "-goodbye\n"),
+    );
+    let output = run_build(&project, &["--no-sandbox"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Build should fail");
+    assert!(
+        stderr.contains(
+            "Patch 'patches/wording.patch' does not apply: hunk #1 (@@ -1,2 +1,2 @@) failed in greeting"
+        ),
+        "stderr: {stderr}"
+    );
+    assert_eq!(
+        project.read_file("build/src/app/greeting"),
+        "hello\nfrom zigroot\n"
+    );
+}
+
+/// Test: Package hooks run around the build steps with the build environment
+#[test]
+fn test_build_runs_package_hooks() {
+    let project = setup_project();
+    let counter = project.path().join("app.count");
+    let definition = |post_build: &str| {
+        format!(
+            "[package]\nname = \"app\"\nversion = \"1.0.0\"\ndescription = \"app\"\n\n\
+             [source]\nurl = \"https://example.com/app.tar.gz\"\n\
+             sha256 = \"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\"\n\n\
+             [build]\ntype = \"custom\"\n\
+             pre_build = \"echo $TARGET > generated.h\"\n\
+             post_build = \"{post_build}\"\n\
+             post_install = \"cp \\\"$SRCDIR/generated.h\\\" . && echo installed\"\n\n\
+             [[build.steps]]\nrun = \"echo x >> {}\"\n",
+            counter.display()
+        )
+    };
+    project.create_file(
+        "packages/app/package.toml",
+        &definition("echo hook says hi"),
+    );
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n\n[packages.app]\nversion = \"1.0.0\"\n",
+    );
+
+    let output = run_build(&project, &["--no-sandbox"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Build should succeed: {stderr}");
+    assert!(
+        stdout.contains("[hook] app post_build: hook says hi"),
+        "Hook output should be streamed: {stdout}"
+    );
+    assert!(!project
+        .read_file("build/destdir/app/generated.h")
+        .is_empty());
+    let log = project.read_file("build/logs/app.log");
+    assert!(log.contains("$ [post_install]") && log.contains("installed"));
+
+    // A changed hook is part of the cache key
+    project.create_file("packages/app/package.toml", &definition("echo changed"));
+    let output = run_build(&project, &["--no-sandbox"]);
+    assert!(output.status.success());
+    assert_eq!(std::fs::read_to_string(&counter).unwrap(), "x\nx\n");
+
+    // A failing hook fails the build
+    project.create_file("packages/app/package.toml", &definition("exit 4"));
+    let output = run_build(&project, &["--no-sandbox"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Build should fail");
+    assert!(
+        stderr.contains("post_build hook 'exit 4' exited with"),
+        "stderr: {stderr}"
+    );
+}
+
+/// Test: The job count comes from --jobs, then build.jobs, and reaches make
+#[test]
+fn test_build_resolves_job_count() {
+    let project = setup_project();
+    let record = project.path().join("jobs.txt");
+    project.create_file(
+        "packages/app/package.toml",
+        &format!(
+            "[package]\nname = \"app\"\nversion = \"1.0.0\"\ndescription = \"app\"\n\n\
+             [source]\nurl = \"https://example.com/app.tar.gz\"\n\
+             sha256 = \"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\"\n\n\
+             [build]\ntype = \"custom\"\n\n\
+             [[build.steps]]\nrun = \"echo $JOBS $MAKEFLAGS > {}\"\n",
+            record.display()
+        ),
+    );
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n\n\
+         [build]\njobs = 3\n\n[packages.app]\nversion = \"1.0.0\"\n",
+    );
+
+    let output = run_build(&project, &["-v", "--no-sandbox"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "Build should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("Using 3 parallel jobs"), "stdout: {stdout}");
+    assert_eq!(std::fs::read_to_string(&record).unwrap(), "3 -j3\n");
+
+    let output = run_build(
+        &project,
+        &["--no-sandbox", "--package", "app", "--jobs", "2"],
+    );
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("parallel jobs"));
+    assert_eq!(std::fs::read_to_string(&record).unwrap(), "2 -j2\n");
+
+    // 0 means every CPU
+    let cpus = std::thread::available_parallelism().unwrap().get();
+    let output = run_build(
+        &project,
+        &["-v", "--no-sandbox", "--package", "app", "--jobs", "0"],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&format!("Using {cpus} parallel jobs")),
+        "stdout: {stdout}"
+    );
+    assert_eq!(
+        std::fs::read_to_string(&record).unwrap(),
+        format!("{cpus} -j{cpus}\n")
+    );
+}
+
+/// Test: --keep-going builds independent packages and skips dependents of failures
+#[test]
+fn test_build_keep_going_collects_failures() {
+    let project = setup_project();
+    let package = |name: &str, depends: &str, run: &str| {
+        project.create_file(
+            &format!("packages/{name}/package.toml"),
+            &format!(
+                "[package]\nname = \"{name}\"\nversion = \"1.0.0\"\ndescription = \"{name}\"\n\
+                 depends = [{depends}]\n\n\
+                 [source]\nurl = \"https://example.com/{name}.tar.gz\"\n\
+                 sha256 = \"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\"\n\n\
+                 [build]\ntype = \"custom\"\n\n\
+                 [[build.steps]]\nrun = \"{run}\"\n"
+            ),
+        );
+    };
+    let marker = |name: &str| project.path().join(format!("{name}.built"));
+    package("broken", "", "exit 1");
+    package(
+        "needs-broken",
+        "\"broken\"",
+        &format!("touch {}", marker("needs-broken").display()),
+    );
+    package("zapp", "", &format!("touch {}", marker("zapp").display()));
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n\n\
+         [packages.broken]\nversion = \"1.0.0\"\n\n\
+         [packages.needs-broken]\nversion = \"1.0.0\"\n\n\
+         [packages.zapp]\nversion = \"1.0.0\"\n",
+    );
+
+    // Fail-fast by default
+    let output = run_build(&project, &["--no-sandbox"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("broken"), "stderr: {stderr}");
+    assert!(!stderr.contains("failed to build:"), "stderr: {stderr}");
+
+    let output = run_build(&project, &["--no-sandbox", "--keep-going"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Build should still fail");
+    assert!(
+        stderr.contains("1 package(s) failed to build:"),
+        "stderr: {stderr}"
+    );
+    assert!(
+        stderr.contains("needs-broken: skipped due to failed dependency 'broken'"),
+        "stderr: {stderr}"
+    );
+    assert!(
+        stdout.contains("needs-broken skipped due to failed dependency 'broken'"),
+        "stdout: {stdout}"
+    );
+    assert!(marker("zapp").exists(), "Independent package should build");
+    assert!(!marker("needs-broken").exists());
+}
+
+/// Test: Project hooks run around the build with placeholders substituted
+#[test]
+fn test_build_runs_project_hooks() {
+    let project = setup_project();
+    let manifest = "[project]\nname = \"test-project\"\nversion = \"1.2.0\"\n\n\
+                    [packages.app]\nversion = \"1.0.0\"\n\n\
+                    [hooks]\n\
+                    pre_build = [\"echo starting {project}\"]\n\
+                    post_build = [\"echo {version} {success} > post.txt\", \"exit 1\"]\n\
+                    on_failure = [\"echo {project} {success} > failed.txt\"]\n";
+    let package = |step: &str| {
+        format!(
+            "[package]\nname = \"app\"\nversion = \"1.0.0\"\ndescription = \"app\"\n\n\
+             [source]\nurl = \"https://example.com/app.tar.gz\"\n\
+             sha256 = \"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\"\n\n\
+             [build]\ntype = \"custom\"\n\n\
+             [[build.steps]]\nrun = \"{step}\"\n"
+        )
+    };
+    project.create_file("packages/app/package.toml", &package("true"));
+    project.create_file("zigroot.toml", manifest);
+
+    // A failing post hook is an error, but the build still succeeds
+    let output = run_build(&project, &["--no-sandbox"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Build should succeed: {stderr}");
+    assert!(
+        stdout.contains("[hook] starting test-project"),
+        "stdout: {stdout}"
+    );
+    assert_eq!(project.read_file("post.txt"), "1.2.0 true\n");
+    assert!(
+        stderr.contains("post_build hook 'exit 1' exited with"),
+        "stderr: {stderr}"
+    );
+    assert!(!project.file_exists("failed.txt"));
+
+    // on_failure hooks run only when the build fails
+    project.create_file("packages/app/package.toml", &package("exit 3"));
+    let output = run_build(&project, &["--no-sandbox", "--package", "app"]);
+    assert!(!output.status.success(), "Build should fail");
+    assert_eq!(project.read_file("failed.txt"), "test-project false\n");
+}
+
+/// Test: packages that do not support the board's libc fail resolution
+#[test]
+fn test_build_rejects_unsupported_libc() {
+    let project = setup_project();
+    project.create_file(
+        "packages/musl-only/package.toml",
+        "[package]\nname = \"musl-only\"\nversion = \"1.0.0\"\ndescription = \"musl only\"\n\
+         supported_libc = [\"musl\"]\n\n\
+         [source]\nurl = \"https://example.com/musl-only.tar.gz\"\n\
+         sha256 = \"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\"\n\n\
+         [build]\ntype = \"custom\"\n\n\
+         [[build.steps]]\nrun = \"true\"\n",
+    );
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n\n\
+         [board]\nlibc = \"glibc\"\n\n\
+         [packages.musl-only]\nversion = \"1.0.0\"\n",
+    );
+
+    let output = run_build(&project, &["--no-sandbox"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("Package 'musl-only' does not support glibc (supported: musl)"),
+        "stderr: {stderr}"
+    );
+}
+
+/// Test: Step output goes to the package log, failures quote its last lines
+#[test]
+fn test_build_writes_package_logs() {
+    let project = setup_project();
+    project.create_file(
+        "packages/noisy/package.toml",
+        "[package]\nname = \"noisy\"\nversion = \"1.0.0\"\ndescription = \"noisy\"\n\n\
+         [source]\nurl = \"https://example.com/noisy.tar.gz\"\n\
+         sha256 = \"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\"\n\n\
+         [build]\ntype = \"custom\"\n\n\
+         [[build.steps]]\nrun = \"for i in $(seq 1 30); do echo line$i; done; echo compile error >&2; exit 1\"\n",
+    );
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n\n\
+         [packages.noisy]\nversion = \"1.0.0\"\n",
+    );
+
+    let output = run_build(&project, &["--no-sandbox", "--log-dir", "logs/build"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("Full log:") && stderr.contains("logs/build/noisy.log"),
+        "stderr: {stderr}"
+    );
+    assert!(stderr.contains("line30") && stderr.contains("compile error"));
+    assert!(!stderr.contains("line5\n"), "stderr: {stderr}");
+
+    let log = project.read_file("logs/build/noisy.log");
+    assert!(log.starts_with("$ for i in"), "log: {log}");
+    assert!(log.contains("line1\n") && log.contains("compile error\n"));
+    assert!(!project.path().join("build/logs/noisy.log").exists());
+
+    // Verbose builds show the output as it is produced
+    let output = run_build(&project, &["--no-sandbox", "--verbose"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[noisy] line1\n"), "stdout: {stdout}");
+}
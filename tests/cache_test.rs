@@ -299,6 +299,267 @@ fn test_cache_import_loads_tarball() {
     );
 }
 
+/// Helper to run zigroot cache against shared storage in `dirs`
+fn run_cache_with_dirs(
+    project: &TestProject,
+    dirs: &std::path::Path,
+    args: &[&str],
+) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .current_dir(project.path())
+        .env("ZIGROOT_DATA_DIR", dirs.join("data"))
+        .env("ZIGROOT_CACHE_DIR", dirs.join("cache"))
+        .arg("cache")
+        .args(args)
+        .output()
+        .expect("Failed to execute zigroot cache")
+}
+
+/// Helper to write a file, creating its parent directories
+fn write_file(path: &std::path::Path, content: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+/// Test: Filtered exports round-trip into another cache
+/// **Validates: Requirements 24.2, 24.3**
+#[test]
+fn test_cache_export_import_round_trip() {
+    let project = setup_project();
+    let zlib_sha = format!("abcd1234{}", "0".repeat(56));
+    let boot_sha = format!("feed{}", "0".repeat(60));
+    project.create_file(
+        "zigroot.lock",
+        &format!(
+            r#"[metadata]
+zigroot_version = "0.1.0"
+zig_version = "0.13.0"
+generated = "2024-01-01T00:00:00Z"
+
+[[package]]
+name = "zlib"
+version = "1.3.1"
+checksum = "sha256:{zlib_sha}"
+
+[[external]]
+name = "bootloader"
+type = "bootloader"
+checksum = "sha256:{boot_sha}"
+url = "https://example.com/u-boot.bin"
+"#
+        ),
+    );
+
+    let source = tempfile::tempdir().unwrap();
+    let downloads = source.path().join("data/downloads");
+    write_file(
+        &downloads.join("zlib/1.3.1/abcd1234/zlib-1.3.1.tar.gz"),
+        "zlib source",
+    );
+    write_file(
+        &downloads.join("busybox/1.36.1/00001111/busybox-1.36.1.tar.gz"),
+        "busybox source",
+    );
+    write_file(
+        &downloads.join(format!("external/sha256/{boot_sha}/bootloader")),
+        "u-boot",
+    );
+    write_file(
+        &source
+            .path()
+            .join("cache/build-cache/12/1234abcd/lib/libz.a"),
+        "archive",
+    );
+
+    // --project keeps what the lock file refers to
+    let archive = project.path().join("project.tar.zst");
+    let output = run_cache_with_dirs(
+        &project,
+        source.path(),
+        &["export", archive.to_str().unwrap(), "--project"],
+    );
+    assert!(
+        output.status.success(),
+        "export --project failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("2 files"));
+
+    let dest = tempfile::tempdir().unwrap();
+    let dest_downloads = dest.path().join("data/downloads");
+    write_file(
+        &dest_downloads.join(format!("external/sha256/{boot_sha}/bootloader")),
+        "u-boot",
+    );
+    let output = run_cache_with_dirs(
+        &project,
+        dest.path(),
+        &["import", archive.to_str().unwrap()],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "import failed: {stdout}");
+    assert!(
+        stdout.contains("1 files imported") && stdout.contains("1 already present"),
+        "stdout: {stdout}"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dest_downloads.join("zlib/1.3.1/abcd1234/zlib-1.3.1.tar.gz"))
+            .unwrap(),
+        "zlib source"
+    );
+    assert!(!dest_downloads.join("busybox").exists());
+
+    // --packages and --downloads-only narrow a full export
+    let archive = project.path().join("busybox.tar.zst");
+    let output = run_cache_with_dirs(
+        &project,
+        source.path(),
+        &[
+            "export",
+            archive.to_str().unwrap(),
+            "--packages",
+            "busybox,zlib",
+            "--downloads-only",
+        ],
+    );
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("2 files"));
+    let output = run_cache_with_dirs(
+        &project,
+        dest.path(),
+        &["import", archive.to_str().unwrap()],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("1 files imported") && stdout.contains("1 already present"),
+        "stdout: {stdout}"
+    );
+    assert!(dest_downloads
+        .join("busybox/1.36.1/00001111/busybox-1.36.1.tar.gz")
+        .exists());
+    assert!(!dest.path().join("cache/build-cache").exists());
+
+    // --builds-only exports the build cache alone
+    let archive = project.path().join("builds.tar.zst");
+    let output = run_cache_with_dirs(
+        &project,
+        source.path(),
+        &["export", archive.to_str().unwrap(), "--builds-only"],
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("1 files"));
+    let output = run_cache_with_dirs(
+        &project,
+        dest.path(),
+        &["import", archive.to_str().unwrap()],
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        std::fs::read_to_string(dest.path().join("cache/build-cache/12/1234abcd/lib/libz.a"))
+            .unwrap(),
+        "archive"
+    );
+}
+
+/// Test: Archives from a newer cache layout are refused
+/// **Validates: Requirement 24.3**
+#[test]
+fn test_cache_import_refuses_newer_layout() {
+    let project = setup_project();
+    let archive = project.path().join("future.tar.zst");
+    let manifest = "layout_version = 99\nzigroot_version = \"9.0.0\"\n";
+    let encoder = zstd::Encoder::new(std::fs::File::create(&archive).unwrap(), 3).unwrap();
+    let mut builder = tar::Builder::new(encoder);
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    builder
+        .append_data(&mut header, "zigroot-cache.toml", manifest.as_bytes())
+        .unwrap();
+    builder.into_inner().unwrap().finish().unwrap();
+
+    let dest = tempfile::tempdir().unwrap();
+    let output = run_cache_with_dirs(
+        &project,
+        dest.path(),
+        &["import", archive.to_str().unwrap()],
+    );
+    let combined = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!output.status.success());
+    assert!(
+        combined.contains("cache layout version 99"),
+        "output: {combined}"
+    );
+}
+
+/// Test: Entries written through a symlink of the same archive are refused
+/// **Validates: Requirement 24.3**
+#[test]
+fn test_cache_import_refuses_entries_below_symlinks() {
+    use sha2::{Digest, Sha256};
+
+    let project = setup_project();
+    let outside = tempfile::tempdir().unwrap();
+    let archive = project.path().join("evil.tar.zst");
+    let manifest = "layout_version = 1\nzigroot_version = \"0.1.0\"\n";
+    let payload = "owned\n";
+    let encoder = zstd::Encoder::new(std::fs::File::create(&archive).unwrap(), 3).unwrap();
+    let mut builder = tar::Builder::new(encoder);
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    builder
+        .append_data(&mut header, "zigroot-cache.toml", manifest.as_bytes())
+        .unwrap();
+
+    let link_checksum = hex::encode(Sha256::digest(
+        outside.path().as_os_str().as_encoded_bytes(),
+    ));
+    builder
+        .append_pax_extensions([("ZIGROOT.sha256", link_checksum.as_bytes())])
+        .unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Symlink);
+    header.set_size(0);
+    header.set_mode(0o777);
+    builder
+        .append_link(&mut header, "downloads/git/a/b/link", outside.path())
+        .unwrap();
+
+    let payload_checksum = hex::encode(Sha256::digest(payload.as_bytes()));
+    builder
+        .append_pax_extensions([("ZIGROOT.sha256", payload_checksum.as_bytes())])
+        .unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_size(payload.len() as u64);
+    header.set_mode(0o644);
+    builder
+        .append_data(&mut header, "downloads/git/a/b/link/x", payload.as_bytes())
+        .unwrap();
+    builder.into_inner().unwrap().finish().unwrap();
+
+    let dest = tempfile::tempdir().unwrap();
+    let output = run_cache_with_dirs(
+        &project,
+        dest.path(),
+        &["import", archive.to_str().unwrap()],
+    );
+    let combined = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!output.status.success());
+    assert!(
+        combined.contains("is below the symlink"),
+        "output: {combined}"
+    );
+    assert!(!outside.path().join("x").exists());
+}
+
 // ============================================
 // Property-Based Tests
 // ============================================
@@ -545,7 +545,7 @@ version = "1.0.0"
 [external.bootloader]
 type = "bootloader"
 url = "https://example.com/uboot.bin"
-sha256 = "abc123def456"
+sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
 "#;
     project.create_file("zigroot.toml", manifest);
 
@@ -561,6 +561,205 @@ sha256 = "abc123def456"
     );
 }
 
+/// Test: Check reports every invalid external artifact together
+#[test]
+fn test_check_reports_invalid_external_artifacts() {
+    let project = setup_project();
+
+    let manifest = r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[external.bootloader]
+type = "bootloader"
+url = "https://example.com/uboot.bin"
+sha256 = "abc123def456"
+
+[external.blob]
+type = "binary"
+path = "firmware/blob.bin"
+format = "gpt"
+"#;
+    project.create_file("zigroot.toml", manifest);
+
+    let output = run_check(&project, &["--json"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "Check should fail: {stdout}");
+
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let errors: Vec<&str> = json["config_errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|e| e.as_str())
+        .collect();
+    assert_eq!(errors.len(), 4, "errors: {errors:?}");
+    assert!(errors[0].contains("'blob' has invalid type 'binary'"));
+    assert!(errors[1].contains("'firmware/blob.bin' does not exist"));
+    assert!(errors[2].contains("only allowed for type partition_table"));
+    assert!(errors[3].contains("malformed sha256 'abc123def456'"));
+}
+
+/// Test: Check reports violated option relationships with their origin
+#[test]
+fn test_check_reports_option_relationship_violations() {
+    let project = setup_project();
+    project.create_dir("packages/curl");
+    project.create_file(
+        "packages/curl/package.toml",
+        r#"[package]
+name = "curl"
+version = "8.5.0"
+description = "Command line tool for transferring data"
+
+[source]
+url = "https://example.com/curl-8.5.0.tar.gz"
+sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+
+[build]
+type = "custom"
+
+[options.tls]
+type = "bool"
+default = false
+description = "Enable TLS support"
+requires = ["libcrypto"]
+
+[options.libcrypto]
+type = "bool"
+default = false
+description = "Link against libcrypto"
+"#,
+    );
+
+    let manifest = r#"[project]
+name = "test-project"
+version = "1.0.0"
+
+[packages.curl]
+version = "8.5.0"
+
+[packages.curl.options]
+tls = true
+"#;
+    project.create_file("zigroot.toml", manifest);
+
+    let output = run_check(&project, &["--json"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "Check should fail: {stdout}");
+
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let errors: Vec<&str> = json["config_errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|e| e.as_str())
+        .collect();
+    assert_eq!(errors.len(), 1, "errors: {errors:?}");
+    assert!(errors[0].starts_with("Package 'curl': 'tls' = true"));
+    assert!(errors[0].contains("zigroot.toml line 9"));
+    assert!(errors[0].contains("requires 'libcrypto', but 'libcrypto' = false"));
+    assert!(errors[0].contains("set it under [packages.curl.options]"));
+}
+
+/// Test: Check fails when two conflicting packages are selected
+#[test]
+fn test_check_detects_conflicting_packages() {
+    let project = setup_project();
+
+    create_local_package(&project, "openssh", "9.6.0");
+    project.create_dir("packages/dropbear");
+    project.create_file(
+        "packages/dropbear/package.toml",
+        r#"[package]
+name = "dropbear"
+version = "2024.85"
+description = "Small SSH server"
+conflicts = [{ name = "openssh", reason = "both provide sshd" }]
+
+[source]
+url = "https://example.com/dropbear-2024.85.tar.bz2"
+sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+"#,
+    );
+
+    let manifest = r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[board]
+
+[build]
+
+[packages.dropbear]
+version = "2024.85"
+
+[packages.openssh]
+version = "9.6.0"
+"#;
+    project.create_file("zigroot.toml", manifest);
+
+    let output = run_check(&project, &[]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        !output.status.success(),
+        "Check should fail for conflicting packages"
+    );
+    assert!(
+        stdout.contains("'dropbear' conflicts with 'openssh': both provide sshd"),
+        "Output should name both packages and the reason: {stdout}"
+    );
+}
+
+/// Test: Check reports missing FIT inputs before building
+#[test]
+fn test_check_detects_missing_fit_inputs() {
+    let project = setup_project();
+
+    let manifest = r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[build]
+image_format = "initramfs"
+
+[external.kernel]
+type = "kernel"
+path = "output/kernel/Image"
+
+[fit]
+kernel = "kernel"
+load_address = "0x40080000"
+ramdisk = "rootfs"
+arch = "arm64"
+
+[[fit.configs]]
+name = "evb"
+dtb = "dts/evb.dtb"
+"#;
+    project.create_file("zigroot.toml", manifest);
+    project.create_file("output/kernel/Image", "kernel");
+
+    let output = run_check(&project, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "Check should fail: {stdout}");
+    assert!(
+        stdout.contains("device tree 'dts/evb.dtb' not found"),
+        "Output should name the missing dtb: {stdout}"
+    );
+
+    // The built initramfs is produced by the build and not required yet
+    project.create_file("dts/evb.dtb", "dtb");
+    let output = run_check(&project, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "Check should pass: {stdout}");
+}
+
 // ============================================
 // Property-Based Tests
 // ============================================
@@ -689,3 +888,145 @@ version = "1.0.0"
         );
     }
 }
+
+/// Test: --network reports unreachable artifact URLs
+#[test]
+fn test_check_network_reports_unreachable_urls() {
+    let project = setup_project();
+
+    let manifest = r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[external.bootloader]
+type = "bootloader"
+url = "http://127.0.0.1:1/uboot.bin"
+sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+"#;
+    project.create_file("zigroot.toml", manifest);
+
+    // URLs are only probed on request
+    let output = run_check(&project, &[]);
+    assert!(output.status.success(), "Check should pass offline");
+
+    let output = run_check(&project, &["--network"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "Check should fail: {stdout}");
+    assert!(
+        stdout.contains("http://127.0.0.1:1/uboot.bin is unreachable"),
+        "stdout: {stdout}"
+    );
+}
+
+/// Test: Check validates the placeholders of hook commands
+#[test]
+fn test_check_validates_hook_placeholders() {
+    let project = setup_project();
+
+    let manifest = r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[hooks]
+post_build = ["./upload.sh {image_path} {target}"]
+on_failure = ["echo ${HOME} {project} {success}"]
+"#;
+    project.create_file("zigroot.toml", manifest);
+
+    let output = run_check(&project, &["--json"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "Check should fail: {stdout}");
+
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let errors: Vec<&str> = json["config_errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|e| e.as_str())
+        .collect();
+    assert_eq!(errors.len(), 1, "errors: {errors:?}");
+    assert!(errors[0].contains("hooks.post_build: unknown placeholder '{target}'"));
+}
+
+/// Cache a package index listing `names` under the project's `cache/`
+fn cache_package_index(project: &TestProject, names: &[&str]) {
+    let cached_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let packages: Vec<String> = names
+        .iter()
+        .map(|name| {
+            format!(r#"{{"name": "{name}", "description": "", "versions": [], "latest": "1.0.0"}}"#)
+        })
+        .collect();
+    project.create_file(
+        "cache/zigroot/registry/packages-index.json",
+        &format!(
+            r#"{{"cached_at": {cached_at}, "data": {{"version": 1, "updated": "", "packages": [{}]}}}}"#,
+            packages.join(", ")
+        ),
+    );
+}
+
+/// Test: Packages missing from the registry index fail with suggestions
+#[test]
+fn test_check_reports_unknown_registry_packages() {
+    let project = setup_project();
+    cache_package_index(&project, &["busybox", "dropbear"]);
+    create_local_package(&project, "myapp", "1.0.0");
+    project.create_file(
+        "zigroot.toml",
+        r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[packages.busyboxx]
+version = "1.36.1"
+
+[packages.dropbear]
+version = "2024.85"
+
+[packages.myapp]
+version = "1.0.0"
+
+[packages.fork]
+git = "https://example.com/fork.git"
+"#,
+    );
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zigroot"))
+            .current_dir(project.path())
+            .env("XDG_CACHE_HOME", project.path().join("cache"))
+            .arg("check")
+            .args(args)
+            .output()
+            .expect("Failed to execute zigroot check")
+    };
+
+    let output = run(&[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "Check should fail: {stdout}");
+    assert!(
+        stdout.contains("Package 'busyboxx' not found in the registry; did you mean 'busybox'?"),
+        "stdout: {stdout}"
+    );
+    for exempt in ["dropbear", "myapp", "fork"] {
+        assert!(
+            !stdout.contains(&format!("Package '{exempt}' not found")),
+            "stdout: {stdout}"
+        );
+    }
+
+    // --offline skips the registry
+    let output = run(&["--offline"]);
+    assert!(
+        !String::from_utf8_lossy(&output.stdout).contains("not found in the registry"),
+        "stdout: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
@@ -348,3 +348,127 @@ fn test_clean_removes_only_output_when_no_build() {
         "output/ directory should be removed"
     );
 }
+
+/// Test: --package removes only that package's artifacts and forces a rebuild
+#[test]
+fn test_clean_package_keeps_other_packages() {
+    let project = setup_project();
+    create_build_artifacts(&project);
+    project.create_file("build/src/busybox/Makefile", "all:");
+    project.create_file("build/src/dropbear/Makefile", "all:");
+    project.create_file("build/stamps/dropbear.stamp", "1234567890");
+
+    let output = run_clean(&project, &["--package", "busybox"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        output.status.success(),
+        "zigroot clean --package should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("Cleaned package 'busybox'"), "{stdout}");
+    assert!(!project.file_exists("build/src/busybox"));
+    assert!(!project.file_exists("build/stamps/busybox.stamp"));
+    assert!(project.file_exists("build/src/dropbear/Makefile"));
+    assert!(project.file_exists("build/stamps/dropbear.stamp"));
+    assert!(output_dir_exists(&project), "output/ should be kept");
+}
+
+/// Test: --package fails for an unknown package with nothing to clean
+#[test]
+fn test_clean_unknown_package_fails() {
+    let project = setup_project();
+
+    let output = run_clean(&project, &["--package", "nonexistent"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not found in manifest"));
+}
+
+/// Test: --dry-run reports sizes without removing anything
+#[test]
+fn test_clean_dry_run_removes_nothing() {
+    let project = setup_project();
+    create_build_artifacts(&project);
+
+    let output = run_clean(&project, &["--dry-run"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success());
+    assert!(stdout.contains("Would clean build artifacts"), "{stdout}");
+    assert!(stdout.contains("Would reclaim"), "{stdout}");
+    assert!(build_dir_exists(&project));
+    assert!(output_dir_exists(&project));
+}
+
+/// Test: Plain clean keeps downloads, --downloads removes them
+#[test]
+fn test_clean_downloads_on_request() {
+    let project = setup_project();
+    project.create_file("downloads/busybox-1.36.1.tar.bz2", "archive");
+
+    let output = run_clean(&project, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("Kept the shared download store"),
+        "{stdout}"
+    );
+    assert!(project.file_exists("downloads/busybox-1.36.1.tar.bz2"));
+
+    let output = run_clean(&project, &["--downloads"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("downloads: downloads/"), "{stdout}");
+    assert!(!project.file_exists("downloads"));
+}
+
+/// Test that a command changing the project refuses to run while another
+/// zigroot process holds the project, unless it is told to wait
+#[test]
+fn test_clean_waits_for_project_lock() {
+    use zigroot::infra::flock::{lock_project, PROJECT_LOCK};
+
+    let project = setup_project();
+    create_build_artifacts(&project);
+    let lock = lock_project(&project.path(), false, |_| {})
+        .unwrap()
+        .unwrap();
+
+    let output = run_clean(&project, &[]);
+    assert!(!output.status.success(), "clean should fail while locked");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!(
+            "Another zigroot process (pid {}, started",
+            std::process::id()
+        )),
+        "Busy message should name the holder: {stderr}"
+    );
+    assert!(stderr.contains("--wait"), "Should suggest --wait: {stderr}");
+    assert!(build_dir_exists(&project), "Nothing should be cleaned");
+
+    // Read-only commands still run
+    let check = Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .current_dir(project.path())
+        .args(["tree"])
+        .output()
+        .unwrap();
+    assert!(check.status.success());
+
+    let waiting = Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .current_dir(project.path())
+        .args(["clean", "--wait"])
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    assert!(build_dir_exists(&project), "clean --wait should block");
+    drop(lock);
+
+    let output = waiting.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Waiting for another zigroot process"));
+    assert!(!build_dir_exists(&project));
+    assert!(project.path().join(PROJECT_LOCK).exists());
+}
@@ -0,0 +1,106 @@
+//! Integration tests for `zigroot completions`
+//!
+//! Tests for shell completion:
+//! - Scripts are generated for every supported shell
+//! - bash, zsh and fish scripts call `zigroot __complete`
+//! - `__complete` lists names from the project and the cached indexes only
+
+mod common;
+
+use common::TestProject;
+use std::process::Command;
+
+/// Helper to run zigroot in a project with its own cache directory
+fn run_zigroot(project: &TestProject, args: &[&str]) -> std::process::Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
+    cmd.current_dir(project.path())
+        .env("XDG_CACHE_HOME", project.path().join("cache"))
+        .args(args);
+    cmd.output().expect("Failed to execute zigroot")
+}
+
+/// Test: a completion script is printed for each shell
+#[test]
+fn test_completions_for_each_shell() {
+    let project = TestProject::new();
+    for shell in ["bash", "zsh", "fish", "powershell", "elvish"] {
+        let output = run_zigroot(&project, &["completions", shell]);
+        assert!(output.status.success(), "completions {shell} failed");
+        let script = String::from_utf8_lossy(&output.stdout);
+        assert!(script.contains("zigroot"), "{shell}: {script}");
+
+        let dynamic = matches!(shell, "bash" | "zsh" | "fish");
+        assert_eq!(script.contains("zigroot __complete"), dynamic, "{shell}");
+        // The hidden command is not offered as a subcommand
+        assert!(!script.contains("__complete'") && !script.contains("\"__complete\""));
+    }
+}
+
+/// Test: the bash script is valid bash
+#[test]
+fn test_bash_completion_script_parses() {
+    let project = TestProject::new();
+    let output = run_zigroot(&project, &["completions", "bash"]);
+    project.create_file("zigroot.bash", &String::from_utf8_lossy(&output.stdout));
+
+    let Ok(check) = Command::new("bash")
+        .arg("-n")
+        .arg(project.path().join("zigroot.bash"))
+        .output()
+    else {
+        return; // bash is not installed
+    };
+    assert!(
+        check.status.success(),
+        "{}",
+        String::from_utf8_lossy(&check.stderr)
+    );
+}
+
+/// Test: `__complete` lists project and cached registry names by prefix
+#[test]
+fn test_complete_lists_names_from_project_and_cache() {
+    let project = TestProject::new();
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"demo\"\n\n[packages.busybox]\nversion = \"1.36.1\"\n",
+    );
+    project.create_file(
+        "boards/my-board/board.toml",
+        "[board]\nname = \"my-board\"\n",
+    );
+    project.create_file(
+        "cache/zigroot/registry/packages-index.json",
+        r#"{"data": {"version": 1, "updated": "2024-01-01", "packages": [
+            {"name": "bash", "description": "Shell", "versions": [], "latest": "5.2"},
+            {"name": "dropbear", "description": "SSH", "versions": [], "latest": "2022.83"}
+        ]}, "cached_at": 0}"#,
+    );
+
+    let output = run_zigroot(&project, &["__complete", "packages", "b"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "bash\nbusybox\n");
+
+    let output = run_zigroot(&project, &["__complete", "project-packages"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "busybox\n");
+
+    // No board index is cached, so only local boards are offered
+    let output = run_zigroot(&project, &["__complete", "boards", ""]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "my-board\n");
+}
+
+/// Test: `__complete` prints nothing for a cold cache or an unknown kind
+#[test]
+fn test_complete_is_silent_without_candidates() {
+    let project = TestProject::new();
+
+    for args in [
+        ["__complete", "packages", ""],
+        ["__complete", "unknown", "x"],
+    ] {
+        let output = run_zigroot(&project, &args);
+        assert!(output.status.success());
+        assert!(output.stdout.is_empty());
+        assert!(output.stderr.is_empty());
+    }
+}
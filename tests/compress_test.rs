@@ -295,6 +295,23 @@ fn test_cli_no_compress_overrides_all() {
     );
 }
 
+/// Test: --compress together with --no-compress is rejected
+#[test]
+fn test_cli_compress_flags_conflict() {
+    let project = setup_project();
+    create_test_board(&project, "x86_64-linux-musl");
+    create_manifest_with_compression(&project, true);
+
+    let output = run_build(&project, &["--compress", "--no-compress"]);
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "conflicting flags must fail");
+    assert!(
+        stderr.contains("--no-compress") && stderr.contains("--compress"),
+        "Error should name both flags: {stderr}"
+    );
+}
+
 /// Test: Unsupported architectures skip compression
 /// **Validates: Requirement 6.5**
 #[test]
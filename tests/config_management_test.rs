@@ -127,6 +127,47 @@ hostname = "${ZIGROOT_HOSTNAME}"
     std::env::remove_var("ZIGROOT_HOSTNAME");
 }
 
+/// Test: Version 2 manifests reject undefined variables and honor defaults
+/// **Validates: Requirement 11.2**
+#[test]
+fn test_manifest_env_var_strict_in_version_2() {
+    std::env::remove_var("ZIGROOT_STRICT_UNSET");
+    std::env::remove_var("ZIGROOT_STRICT_HOSTNAME");
+
+    let project = TestProject::new();
+    project.create_file(
+        "zigroot.toml",
+        r#"manifest_version = 2
+
+[project]
+name = "strict"
+
+[build]
+hostname = "${ZIGROOT_STRICT_HOSTNAME:-fallback-host}"
+
+[external.kernel]
+type = "kernel"
+url = "https://example.com/zImage"
+sha256 = "${ZIGROOT_STRICT_UNSET}"
+"#,
+    );
+    let path = project.path().join("zigroot.toml");
+
+    let err = zigroot::core::manifest::Manifest::load_with_env_substitution(&path)
+        .expect_err("unset variable should be rejected");
+    let message = err.to_string();
+    assert!(message.contains("ZIGROOT_STRICT_UNSET"), "{message}");
+    assert!(message.contains("external.kernel.sha256"), "{message}");
+
+    std::env::set_var("ZIGROOT_STRICT_UNSET", "abc");
+    let manifest = zigroot::core::manifest::Manifest::load_with_env_substitution(&path)
+        .expect("manifest should load once the variable is set");
+    assert_eq!(manifest.build.hostname, "fallback-host");
+    assert_eq!(manifest.external["kernel"].sha256.as_deref(), Some("abc"));
+
+    std::env::remove_var("ZIGROOT_STRICT_UNSET");
+}
+
 // ============================================
 // Configuration Inheritance Tests
 // **Validates: Requirements 11.5**
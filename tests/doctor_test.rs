@@ -318,3 +318,573 @@ fn test_doctor_exit_code_reflects_status() {
     }
     // If no critical issues, success is expected
 }
+
+/// Test: Doctor reports the GCC cross-toolchain for the configured board
+#[test]
+fn test_doctor_checks_gcc_toolchain_for_board() {
+    let project = TestProject::new();
+    project.create_file(
+        "zigroot.toml",
+        r#"
+[project]
+name = "gcc-project"
+
+[board]
+name = "test-board"
+
+[packages.legacy]
+version = "1.0.0"
+"#,
+    );
+    project.create_file(
+        "boards/test-board/board.toml",
+        r#"
+[board]
+name = "test-board"
+description = "A test board"
+target = "zigroot-test-linux-gnu"
+cpu = "generic"
+
+[defaults]
+image_format = "ext4"
+rootfs_size = "256M"
+hostname = "test"
+"#,
+    );
+    project.create_file(
+        "packages/legacy/package.toml",
+        r#"
+[package]
+name = "legacy"
+version = "1.0.0"
+description = "Only builds with gcc"
+
+[source]
+url = "https://example.com/legacy.tar.gz"
+sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+
+[build]
+toolchain = "gcc"
+"#,
+    );
+
+    let output = run_doctor_in_dir(&project, &["--json"]);
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("doctor output is not JSON");
+    let check = json["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == "GCC cross-toolchain (zigroot-test-linux-gnu)")
+        .expect("missing GCC toolchain check");
+
+    // No such toolchain exists, and the gcc package makes it required
+    assert_eq!(check["passed"], false);
+    assert_eq!(check["required"], true);
+    assert!(check["error"]
+        .as_str()
+        .unwrap()
+        .contains("zigroot-test-linux-gnu-gcc"));
+    assert!(!output.status.success());
+}
+
+/// Test: Doctor checks the QEMU emulator when the board can be run in QEMU
+#[test]
+fn test_doctor_checks_qemu_for_board() {
+    let project = TestProject::new();
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"qemu-project\"\n\n[board]\nname = \"test-board\"\n",
+    );
+    project.create_file(
+        "boards/test-board/board.toml",
+        r#"
+[board]
+name = "test-board"
+description = "A test board"
+target = "aarch64-linux-musl"
+cpu = "cortex-a53"
+
+[defaults]
+image_format = "ext4"
+rootfs_size = "256M"
+hostname = "test"
+
+[qemu]
+machine = "virt"
+binary = "zigroot-test-qemu-system-aarch64"
+"#,
+    );
+
+    let output = run_doctor_in_dir(&project, &["--json"]);
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("doctor output is not JSON");
+    let check = json["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == "QEMU (zigroot-test-qemu-system-aarch64)")
+        .expect("missing QEMU check");
+
+    // QEMU is optional, so a missing emulator is only reported
+    assert_eq!(check["passed"], false);
+    assert_eq!(check["required"], false);
+    assert!(check["suggestion"]
+        .as_str()
+        .unwrap()
+        .contains("zigroot run"));
+}
+
+/// Test: Doctor checks the host tools of the board's flash methods
+#[test]
+fn test_doctor_checks_flash_host_tools() {
+    let project = TestProject::new();
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"flash-project\"\n\n[board]\nname = \"test-board\"\n",
+    );
+    project.create_file(
+        "boards/test-board/board.toml",
+        r#"
+[board]
+name = "test-board"
+description = "A test board"
+target = "aarch64-linux-musl"
+cpu = "cortex-a53"
+
+[defaults]
+image_format = "ext4"
+rootfs_size = "256M"
+hostname = "test"
+
+[[flash.methods]]
+name = "rockusb"
+description = "Write over USB"
+host_tools = ["zigroot-test-rkdeveloptool"]
+command = "zigroot-test-rkdeveloptool wl 0 {image}"
+"#,
+    );
+
+    let output = run_doctor_in_dir(&project, &["--json"]);
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("doctor output is not JSON");
+    let check = json["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == "Flash tool zigroot-test-rkdeveloptool")
+        .expect("missing flash tool check");
+
+    assert_eq!(check["passed"], false);
+    assert_eq!(check["required"], false);
+    assert!(check["suggestion"]
+        .as_str()
+        .unwrap()
+        .contains("zigroot flash rockusb"));
+}
+
+/// Test: Doctor reports bubblewrap for sandboxed builds with install hints
+#[test]
+fn test_doctor_checks_build_sandbox() {
+    let project = TestProject::new();
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"sandbox-project\"\n\n[build]\nsandbox = true\n",
+    );
+
+    let output = run_doctor_in_dir(&project, &["--json"]);
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("doctor output is not JSON");
+    let check = json["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == "Build sandbox (bubblewrap)")
+        .expect("missing build sandbox check");
+
+    if check["passed"] == false {
+        let suggestion = check["suggestion"].as_str().unwrap();
+        assert!(
+            suggestion.contains("bubblewrap") || suggestion.contains("--no-sandbox"),
+            "{suggestion}"
+        );
+    }
+}
+
+/// Test: doctor lists the host tools of local packages
+#[test]
+fn test_doctor_checks_package_host_tools() {
+    let project = TestProject::new();
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"host-tools-project\"\n\n[packages.app]\nversion = \"1.0.0\"\n",
+    );
+    project.create_file(
+        "packages/app/package.toml",
+        r#"[package]
+name = "app"
+version = "1.0.0"
+description = "Package needing host tools"
+host_depends = ["sh", "zigroot-missing-tool>=1.0"]
+
+[source]
+url = "https://example.com/app-1.0.0.tar.gz"
+sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+"#,
+    );
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
+    let output = cmd
+        .current_dir(project.path())
+        .env("ZIGROOT_DATA_DIR", project.path().join("data"))
+        .args(["doctor", "--json"])
+        .output()
+        .expect("Failed to execute zigroot doctor");
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("doctor output is not JSON");
+    let checks = json["checks"].as_array().unwrap();
+    let check = |name: &str| {
+        checks
+            .iter()
+            .find(|c| c["name"] == name)
+            .unwrap_or_else(|| panic!("missing check {name}"))
+    };
+
+    assert_eq!(check("Host tool sh (app)")["passed"], true);
+    let missing = check("Host tool zigroot-missing-tool>=1.0 (app)");
+    assert_eq!(missing["passed"], false);
+    assert_eq!(missing["required"], false);
+    assert!(missing["suggestion"]
+        .as_str()
+        .unwrap()
+        .contains("zigroot build"));
+}
+
+// ============================================
+// Exit codes and structured results
+// ============================================
+
+/// Helper to run zigroot doctor in `project` with only fake tools on PATH
+///
+/// Each tool is a script that succeeds and prints a recent version, so the
+/// set of tools decides which system checks pass. The registry is not
+/// contacted.
+fn run_doctor_with_tools(
+    project: &TestProject,
+    tools: &[&str],
+    args: &[&str],
+) -> std::process::Output {
+    doctor_command(project, tools)
+        .arg("--offline")
+        .args(args)
+        .output()
+        .expect("Failed to execute zigroot doctor")
+}
+
+/// A doctor command with only fake `tools` on PATH and a private config
+fn doctor_command(project: &TestProject, tools: &[&str]) -> Command {
+    use std::os::unix::fs::PermissionsExt;
+
+    project.create_dir("bin");
+    for tool in tools {
+        let path = project.path().join("bin").join(tool);
+        std::fs::write(&path, "#!/bin/sh\necho \"version 4.2.2\"\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
+    cmd.current_dir(project.path())
+        .env("PATH", project.path().join("bin"))
+        .env("ZIGROOT_DATA_DIR", project.path().join("data"))
+        .env("ZIGROOT_CONFIG_DIR", project.path().join("config"))
+        .env_remove("ZIGROOT_SANDBOX_TOOL");
+    for var in [
+        "HTTPS_PROXY",
+        "https_proxy",
+        "HTTP_PROXY",
+        "http_proxy",
+        "ALL_PROXY",
+        "all_proxy",
+    ] {
+        cmd.env_remove(var);
+    }
+    cmd.arg("doctor");
+    cmd
+}
+
+/// Tools that make every system check pass
+const ALL_TOOLS: &[&str] = &["zig", "git", "upx", "mkimage", "docker", "bwrap"];
+
+/// Test: doctor exits successfully when every check passes
+#[test]
+fn test_doctor_exit_code_all_passed() {
+    let project = TestProject::new();
+
+    let output = run_doctor_with_tools(&project, ALL_TOOLS, &["--json"]);
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("doctor output is not JSON");
+    assert_eq!(json["status"], "success", "{json}");
+    assert!(output.status.success());
+
+    let output = run_doctor_with_tools(&project, ALL_TOOLS, &["--warnings-as-errors"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+/// Test: warnings only fail doctor with --warnings-as-errors
+#[test]
+fn test_doctor_exit_code_warnings() {
+    let project = TestProject::new();
+
+    let output = run_doctor_with_tools(&project, &["zig", "git"], &["--json"]);
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("doctor output is not JSON");
+    assert_eq!(json["status"], "warning");
+    assert_eq!(json["failed_count"], 0);
+    assert!(json["warning_count"].as_u64().unwrap() > 0);
+    assert!(output.status.success());
+
+    let output = run_doctor_with_tools(
+        &project,
+        &["zig", "git"],
+        &["--json", "--warnings-as-errors"],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--warnings-as-errors"));
+}
+
+/// Test: a failed required check fails doctor with or without the flag
+#[test]
+fn test_doctor_exit_code_failures() {
+    let project = TestProject::new();
+    let tools = &["git", "upx", "mkimage", "docker", "bwrap"];
+
+    let output = run_doctor_with_tools(&project, tools, &["--json"]);
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("doctor output is not JSON");
+    assert_eq!(json["status"], "error");
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = run_doctor_with_tools(&project, tools, &["--warnings-as-errors"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+/// Test: JSON checks carry ids, categories, statuses and remediation
+#[test]
+fn test_doctor_json_structured_project_checks() {
+    let project = TestProject::new();
+    project.create_file(
+        "zigroot.toml",
+        r#"[project]
+name = "structured"
+version = "1.2.0"
+
+[board]
+name = "missing-board"
+
+[build]
+image_format = "initramfs"
+initramfs_compression = "zstd"
+"#,
+    );
+
+    let output = run_doctor_with_tools(&project, ALL_TOOLS, &["--json"]);
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("doctor output is not JSON");
+    let checks = json["checks"].as_array().unwrap();
+    let check = |id: &str| {
+        checks
+            .iter()
+            .find(|c| c["id"] == id)
+            .unwrap_or_else(|| panic!("missing check {id}: {json}"))
+    };
+
+    assert_eq!(check("zig")["category"], "toolchain");
+    assert_eq!(check("zig")["status"], "pass");
+    assert_eq!(check("zig")["detected"], "4.2.2");
+    assert_eq!(check("proxy")["category"], "network");
+
+    let manifest = check("manifest");
+    assert_eq!(manifest["category"], "project");
+    assert_eq!(manifest["status"], "pass");
+    assert_eq!(manifest["detected"], "1.2.0");
+
+    let board = check("board");
+    assert_eq!(board["status"], "fail");
+    assert!(board["remediation"]
+        .as_str()
+        .unwrap()
+        .contains("zigroot board set"));
+
+    assert_eq!(check("lock-file")["status"], "warn");
+
+    let compressor = check("image-tool:zstd");
+    assert_eq!(compressor["category"], "host-tools");
+    assert_eq!(compressor["status"], "fail");
+
+    assert_eq!(json["status"], "error");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+/// Test: an unparsable manifest is a failed project check
+#[test]
+fn test_doctor_reports_invalid_manifest() {
+    let project = TestProject::new();
+    project.create_file("zigroot.toml", "[project\nname = ");
+
+    let output = run_doctor_with_tools(&project, ALL_TOOLS, &["--json"]);
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("doctor output is not JSON");
+    let manifest = json["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["id"] == "manifest")
+        .expect("missing manifest check");
+
+    assert_eq!(manifest["status"], "fail");
+    assert!(manifest["remediation"]
+        .as_str()
+        .unwrap()
+        .contains("zigroot check"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+/// Test: doctor reports lock drift and missing local package directories
+#[test]
+fn test_doctor_checks_lock_consistency_and_local_packages() {
+    let project = TestProject::new();
+    project.create_file(
+        "zigroot.toml",
+        r#"[project]
+name = "drift"
+
+[packages]
+app = { version = "1.0.0" }
+busybox = { version = "^1.36.0" }
+"#,
+    );
+    project.create_file(
+        "zigroot.lock",
+        r#"[metadata]
+zigroot_version = "0.1.0"
+zig_version = "0.11.0"
+generated = "2024-01-01T00:00:00Z"
+
+[[package]]
+name = "app"
+version = "1.0.0"
+checksum = "local"
+source = "path:packages/app"
+
+[[package]]
+name = "busybox"
+version = "1.35.0"
+checksum = "sha256:abc"
+"#,
+    );
+
+    let output = run_doctor_with_tools(&project, ALL_TOOLS, &["--json"]);
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("doctor output is not JSON");
+    assert!(json.get("config_issues").is_none(), "{json}");
+    let checks = json["checks"].as_array().unwrap();
+    let check = |id: &str| {
+        checks
+            .iter()
+            .find(|c| c["id"] == id)
+            .unwrap_or_else(|| panic!("missing check {id}: {json}"))
+    };
+
+    let lock = check("lock-consistency");
+    assert_eq!(lock["category"], "project");
+    assert_eq!(lock["status"], "warn");
+    assert!(lock["error"]
+        .as_str()
+        .unwrap()
+        .contains("busybox is locked at 1.35.0"));
+
+    let local = check("local-packages");
+    assert_eq!(local["status"], "fail");
+    assert!(local["error"].as_str().unwrap().contains("app"));
+    assert_eq!(output.status.code(), Some(1));
+
+    project.create_file("packages/app/package.toml", "");
+    let output = run_doctor_with_tools(&project, ALL_TOOLS, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Checking project..."), "{stdout}");
+    assert!(stdout.contains("Local packages"), "{stdout}");
+}
+
+/// Test: the registry check goes through the HTTP client and names the
+/// failure class
+#[tokio::test]
+async fn test_doctor_checks_registry() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/ok/index.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("{}"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/auth/index.json"))
+        .respond_with(ResponseTemplate::new(407))
+        .mount(&server)
+        .await;
+
+    let registry_check = |project: TestProject, config: String| async move {
+        project.create_file("config/config.toml", &config);
+        let mut cmd = doctor_command(&project, ALL_TOOLS);
+        let output = tokio::task::spawn_blocking(move || cmd.arg("--json").output().unwrap())
+            .await
+            .unwrap();
+        let json: serde_json::Value =
+            serde_json::from_slice(&output.stdout).expect("doctor output is not JSON");
+        json["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["id"] == "registry")
+            .cloned()
+            .unwrap_or_else(|| panic!("missing registry check: {json}"))
+    };
+
+    let check = registry_check(
+        TestProject::new(),
+        format!("[registry]\npackages_url = \"{}/ok\"\n", server.uri()),
+    )
+    .await;
+    assert_eq!(check["category"], "network");
+    assert_eq!(check["status"], "pass", "{check}");
+
+    let check = registry_check(
+        TestProject::new(),
+        format!("[registry]\npackages_url = \"{}/auth\"\n", server.uri()),
+    )
+    .await;
+    assert_eq!(check["status"], "warn");
+    assert!(check["error"]
+        .as_str()
+        .unwrap()
+        .contains("proxy authentication required"));
+
+    let check = registry_check(
+        TestProject::new(),
+        format!(
+            "[registry]\npackages_url = \"{}/ok\"\n\n[network]\nca_bundle = \"/nonexistent/ca.pem\"\n",
+            server.uri()
+        ),
+    )
+    .await;
+    assert_eq!(check["status"], "warn");
+    assert!(check["error"]
+        .as_str()
+        .unwrap()
+        .contains("Cannot load CA bundle"));
+}
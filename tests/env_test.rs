@@ -0,0 +1,129 @@
+//! Integration tests for `zigroot env`
+
+mod common;
+
+use common::TestProject;
+use std::process::Command;
+
+/// Helper to run zigroot env command
+fn run_env(project: &TestProject, args: &[&str]) -> std::process::Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
+    cmd.current_dir(project.path());
+    cmd.arg("env");
+    for arg in args {
+        cmd.arg(arg);
+    }
+    cmd.output().expect("Failed to execute zigroot env")
+}
+
+/// Helper to set up a project with a local `hello` package
+fn setup_project() -> TestProject {
+    let project = TestProject::new();
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"env-project\"\nversion = \"1.0.0\"\n\n\
+         [board]\n\n\
+         [packages.hello]\nversion = \"1.0.0\"\n",
+    );
+    project.create_file(
+        "packages/hello/package.toml",
+        "[package]\nname = \"hello\"\nversion = \"1.0.0\"\ndescription = \"hello\"\n\n\
+         [source]\nurl = \"https://example.com/hello.tar.gz\"\n\
+         sha256 = \"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\"\n\n\
+         [build]\ntype = \"custom\"\n\n\
+         [[build.steps]]\nrun = \"true\"\n",
+    );
+    project
+}
+
+/// Test: The package environment is printed as sorted export lines
+#[test]
+fn test_env_prints_exports() {
+    let project = setup_project();
+
+    let output = run_env(&project, &["hello"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("export CC='zig cc -target "), "{stdout}");
+    assert!(stdout.contains("export RANLIB='zig ranlib'"), "{stdout}");
+    let srcdir = project.path().join("build/src/hello");
+    assert!(
+        stdout.contains(&format!("export SRCDIR='{}'", srcdir.display())),
+        "{stdout}"
+    );
+}
+
+/// Test: --shell fish prints fish syntax
+#[test]
+fn test_env_fish_syntax() {
+    let project = setup_project();
+
+    let output = run_env(&project, &["hello", "--shell", "fish"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("set -gx CC 'zig cc -target "), "{stdout}");
+    assert!(!stdout.contains("export "), "{stdout}");
+}
+
+/// Test: --json prints the environment as an object
+#[test]
+fn test_env_json() {
+    let project = setup_project();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
+    let output = cmd
+        .current_dir(project.path())
+        .args(["--json", "env", "hello"])
+        .output()
+        .expect("Failed to execute zigroot env");
+    assert!(output.status.success());
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("env output should be JSON");
+    assert_eq!(json["package"], "hello");
+    assert_eq!(json["env"]["TARGET"], json["target"]);
+    assert!(json["sysroot"].is_array());
+}
+
+/// Test: --run executes the command in the build directory
+#[test]
+fn test_env_run_in_build_directory() {
+    let project = setup_project();
+
+    let output = run_env(&project, &["hello", "--run", "--", "sh", "-c", "pwd"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("does not exist"));
+
+    project.create_dir("build/src/hello");
+    let output = run_env(
+        &project,
+        &["hello", "--run", "--", "sh", "-c", "pwd; echo \"$TARGET\""],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("build/src/hello"), "{stdout}");
+    assert!(stdout.contains("-linux-"), "{stdout}");
+
+    let output = run_env(&project, &["hello", "--run", "--", "sh", "-c", "exit 3"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("sh exited with"));
+}
+
+/// Test: Unknown packages are rejected
+#[test]
+fn test_env_unknown_package() {
+    let project = setup_project();
+
+    let output = run_env(&project, &["nope"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Package 'nope' is not in zigroot.toml")
+    );
+}
@@ -12,6 +12,9 @@ mod common;
 use common::TestProject;
 use std::process::Command;
 
+/// Checksum passed with `--url`
+const SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
 /// Helper to run zigroot init command
 fn run_init(project: &TestProject, args: &[&str]) -> std::process::Output {
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
@@ -177,6 +180,48 @@ fn test_external_list_shows_artifact_status() {
     );
 }
 
+/// Test: list reports sizes and checksums, and structured records under --json
+#[test]
+fn test_external_list_reports_fetch_status() {
+    let project = setup_project();
+    create_manifest_with_externals(&project);
+    project.create_file("external/kernel.img", "kernel image content");
+    // Empty file matching the bootloader's checksum, as if downloaded
+    project.create_file("external/uboot.bin", "");
+
+    let output = run_external(&project, &["list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("bootloader [bootloader] - downloaded, 0 B, verified"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("kernel [kernel] - local"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("1 artifact(s) missing or failing verification: dtb"),
+        "stdout: {stdout}"
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .current_dir(project.path())
+        .args(["--json", "external", "list"])
+        .output()
+        .expect("Failed to execute zigroot external list");
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let artifacts = json["artifacts"].as_array().unwrap();
+    assert_eq!(artifacts.len(), 3);
+    assert_eq!(artifacts[0]["name"], "bootloader");
+    assert_eq!(artifacts[0]["checksum"], "verified");
+    assert_eq!(artifacts[1]["status"], "missing");
+    assert_eq!(artifacts[1]["checksum"], "pending");
+    assert_eq!(artifacts[2]["size_bytes"], 20);
+    assert_eq!(artifacts[2]["checksum"], "unchecked");
+}
+
 /// Test: list shows empty message when no artifacts configured
 /// **Validates: Requirement 8.9**
 #[test]
@@ -256,6 +301,8 @@ fn test_external_add_url_adds_remote_artifact() {
             "bootloader",
             "--url",
             "https://example.com/bootloader.bin",
+            "--sha256",
+            SHA256,
         ],
     );
 
@@ -311,6 +358,8 @@ fn test_external_add_url_supports_artifact_types() {
                 artifact_type,
                 "--url",
                 &url,
+                "--sha256",
+                SHA256,
             ],
         );
 
@@ -442,6 +491,8 @@ fn test_external_add_fails_without_manifest() {
             "bootloader",
             "--url",
             "https://example.com/test.bin",
+            "--sha256",
+            SHA256,
         ],
     );
 
@@ -498,7 +549,7 @@ fn test_external_add_requires_url_or_path() {
     );
 }
 
-/// Test: add with both --url and --path (downloads to specified path)
+/// Test: add rejects both --url and --path
 /// **Validates: Requirement 8.5**
 #[test]
 fn test_external_add_url_and_path() {
@@ -514,26 +565,88 @@ fn test_external_add_url_and_path() {
             "bootloader",
             "--url",
             "https://example.com/bootloader.bin",
+            "--sha256",
+            SHA256,
             "--path",
             "external/bootloader.bin",
         ],
     );
 
     let stderr = String::from_utf8_lossy(&output.stderr);
-    let stdout = String::from_utf8_lossy(&output.stdout);
-
-    // Command should succeed (both url and path is valid per requirement 8.5)
+    assert!(!output.status.success(), "Both sources should be rejected");
+    assert!(stderr.contains("exactly one"), "stderr: {stderr}");
     assert!(
-        output.status.success(),
-        "zigroot external add with both --url and --path should succeed: stdout={stdout}, stderr={stderr}"
+        !project
+            .read_file("zigroot.toml")
+            .contains("combined-artifact"),
+        "Manifest should be unchanged"
     );
+}
 
-    // Verify the manifest was updated with both url and path
-    let manifest_content = project.read_file("zigroot.toml");
-    assert!(
-        manifest_content.contains("combined-artifact"),
-        "Manifest should contain the new artifact: {manifest_content}"
+/// Test: add rejects unknown types, unknown formats and unchecked URLs
+#[test]
+fn test_external_add_validates_definition() {
+    let project = setup_project();
+    create_manifest_without_externals(&project);
+
+    let cases: [&[&str]; 3] = [
+        &[
+            "add",
+            "boot",
+            "--artifact-type",
+            "bootlader",
+            "--path",
+            "boot.bin",
+        ],
+        &[
+            "add",
+            "table",
+            "--artifact-type",
+            "partition_table",
+            "--path",
+            "table.bin",
+            "--format",
+            "gtp",
+        ],
+        &[
+            "add",
+            "boot",
+            "--artifact-type",
+            "bootloader",
+            "--url",
+            "https://example.com/boot.bin",
+        ],
+    ];
+    let expected = [
+        "bootloader, kernel, partition_table, dtb, firmware, other",
+        "gpt, mbr, rockchip",
+        "no sha256",
+    ];
+    for (args, expected) in cases.iter().zip(expected) {
+        let output = run_external(&project, args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success(), "{args:?} should fail");
+        assert!(stderr.contains(expected), "stderr: {stderr}");
+    }
+    assert!(!project.read_file("zigroot.toml").contains("[external"));
+
+    let output = run_external(
+        &project,
+        &[
+            "add",
+            "table",
+            "--artifact-type",
+            "partition_table",
+            "--path",
+            "table.bin",
+            "--format",
+            "rockchip",
+        ],
     );
+    assert!(output.status.success(), "Valid format should be accepted");
+    assert!(project
+        .read_file("zigroot.toml")
+        .contains("format = \"rockchip\""));
 }
 
 /// Test: list shows artifact types correctly
@@ -617,3 +730,147 @@ format = "gpt"
         "Should show partition table artifact: stdout={stdout}, stderr={stderr}"
     );
 }
+
+// ============================================
+// Unit Tests for zigroot external remove/update/fetch
+// ============================================
+
+/// Test: update rewrites an artifact's source and remove drops it
+#[test]
+fn test_external_update_and_remove() {
+    let project = setup_project();
+    create_manifest_with_externals(&project);
+
+    let blake3 = "b".repeat(64);
+    let output = run_external(
+        &project,
+        &[
+            "update",
+            "bootloader",
+            "--url",
+            "https://example.com/v2/uboot.bin",
+            "--blake3",
+            &blake3,
+        ],
+    );
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let manifest = project.read_file("zigroot.toml");
+    assert!(
+        manifest.contains("https://example.com/v2/uboot.bin"),
+        "{manifest}"
+    );
+    assert!(
+        manifest.contains(&blake3) && !manifest.contains(SHA256),
+        "{manifest}"
+    );
+
+    let output = run_external(&project, &["update", "bootloader", "--sha256", "abc"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("malformed sha256"));
+
+    let output = run_external(&project, &["remove", "kernel"]);
+    assert!(output.status.success());
+    assert!(!project
+        .read_file("zigroot.toml")
+        .contains("[external.kernel]"));
+    let output = run_external(&project, &["remove", "kernel"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not found"));
+}
+
+/// Test: fetch downloads into shared storage and the build places artifacts
+#[tokio::test]
+async fn test_external_fetch_and_build_place_artifacts() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/u-boot.bin"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(b"u-boot".as_slice()))
+        .mount(&server)
+        .await;
+
+    let project = setup_project();
+    let digest = blake3::hash(b"u-boot").to_hex().to_string();
+    let manifest = project.read_file("zigroot.toml");
+    project.create_file(
+        "zigroot.toml",
+        &format!(
+            "{manifest}\n[external.boot]\ntype = \"bootloader\"\nurl = \"{}/u-boot.bin\"\nblake3 = \"{digest}\"\n",
+            server.uri()
+        ),
+    );
+
+    let dir = project.path().clone();
+    let zigroot = move |args: &[&str]| {
+        let dir = dir.clone();
+        let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+        tokio::task::spawn_blocking(move || {
+            Command::new(env!("CARGO_BIN_EXE_zigroot"))
+                .current_dir(&dir)
+                .env("ZIGROOT_DATA_DIR", dir.join("data"))
+                .env("ZIGROOT_CACHE_DIR", dir.join("cache"))
+                .args(args)
+                .output()
+                .expect("Failed to execute zigroot")
+        })
+    };
+
+    let output = zigroot(&["--json", "external", "fetch"]).await.unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["artifacts"][0]["downloaded"], true, "{json}");
+    let shared = project
+        .path()
+        .join("data/downloads/external/blake3")
+        .join(&digest)
+        .join("u-boot.bin");
+    assert_eq!(std::fs::read(&shared).unwrap(), b"u-boot");
+    assert_eq!(
+        std::fs::read(project.path().join("external/u-boot.bin")).unwrap(),
+        b"u-boot"
+    );
+
+    // The build reuses the shared copy, places it and pins its checksum
+    std::fs::remove_file(project.path().join("external/u-boot.bin")).unwrap();
+    let requests = server.received_requests().await.unwrap().len();
+    let output = zigroot(&["build"]).await.unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(server.received_requests().await.unwrap().len(), requests);
+    assert_eq!(
+        std::fs::read(
+            project
+                .path()
+                .join("output/default/ext4/bootloader/u-boot.bin")
+        )
+        .unwrap(),
+        b"u-boot"
+    );
+    let lock = project.read_file("zigroot.lock");
+    assert!(lock.contains(&format!("blake3:{digest}")), "{lock}");
+
+    // --locked rejects a checksum that differs from the pinned one
+    let manifest = project.read_file("zigroot.toml");
+    let other = blake3::hash(b"barebox").to_hex().to_string();
+    project.create_file("zigroot.toml", &manifest.replace(&digest, &other));
+    let output = zigroot(&["build", "--locked"]).await.unwrap();
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("differs from lock file"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -2,10 +2,11 @@
 //!
 //! Tests for Requirements 3.1-3.8, 8.3-8.7:
 //! - Downloads source archives for all packages
-//! - Verifies SHA256 checksums
+//! - Verifies SHA256, SHA512 and BLAKE3 checksums
 //! - Skips already downloaded valid files
 //! - --parallel downloads concurrently
 //! - --force re-downloads all
+//! - --verify-only checks fetched files without downloading
 //! - Downloads external artifacts
 //!
 //! **Validates: Requirements 3.1-3.8, 8.3-8.7**
@@ -353,6 +354,200 @@ path = "external/local-bootloader.bin"
     );
 }
 
+/// Test: --verify-only checks BLAKE3 checksums of fetched files without downloading
+#[test]
+fn test_fetch_verify_only_checks_blake3_checksums() {
+    let project = setup_project();
+    project.create_file("external/boot.bin", "fake bootloader content");
+    let digest = blake3::hash(b"fake bootloader content").to_hex();
+    project.create_file(
+        "zigroot.toml",
+        &format!(
+            r#"
+[project]
+name = "test-project"
+
+[external.boot]
+type = "bootloader"
+path = "external/boot.bin"
+blake3 = "{digest}"
+"#
+        ),
+    );
+
+    let output = run_fetch(&project, &["--verify-only"]);
+    assert!(
+        output.status.success(),
+        "verify-only should pass for a matching file: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    project.create_file("external/boot.bin", "tampered");
+    let output = run_fetch(&project, &["--verify-only"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("blake3 checksum verification failed for 'boot'"),
+        "{stdout}"
+    );
+
+    project.create_file(
+        "zigroot.toml",
+        &format!(
+            r#"
+[project]
+name = "test-project"
+
+[external.kernel]
+type = "kernel"
+url = "https://example.com/Image"
+sha512 = "{}"
+"#,
+            "a".repeat(128)
+        ),
+    );
+    let output = run_fetch(&project, &["--verify-only"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("has not been fetched"));
+}
+
+/// Run fetch with a private global config without blocking the mock servers
+async fn run_fetch_async(project: &TestProject, args: &[&str]) -> std::process::Output {
+    let dir = project.path().clone();
+    let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+    tokio::task::spawn_blocking(move || {
+        Command::new(env!("CARGO_BIN_EXE_zigroot"))
+            .current_dir(&dir)
+            .env("ZIGROOT_CONFIG_DIR", dir.join("config"))
+            .arg("fetch")
+            .args(args)
+            .output()
+            .expect("Failed to execute zigroot fetch")
+    })
+    .await
+    .unwrap()
+}
+
+/// Test: A failing primary URL falls back to the artifact's mirrors, and
+/// the serving URL is recorded in the fetch log
+#[tokio::test]
+async fn test_fetch_falls_back_to_mirror() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let primary = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/boot.bin"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&primary)
+        .await;
+    let mirror = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/boot.bin"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(b"bootloader".as_slice()))
+        .mount(&mirror)
+        .await;
+
+    let project = setup_project();
+    let mirror_url = format!("{}/boot.bin", mirror.uri());
+    project.create_file(
+        "zigroot.toml",
+        &format!(
+            r#"
+[project]
+name = "test-project"
+
+[external.boot]
+type = "bootloader"
+url = "{}/boot.bin"
+mirrors = ["{mirror_url}"]
+blake3 = "{}"
+"#,
+            primary.uri(),
+            blake3::hash(b"bootloader").to_hex()
+        ),
+    );
+
+    let output = run_fetch_async(&project, &[]).await;
+    assert!(
+        output.status.success(),
+        "fetch should fall back to the mirror: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        std::fs::read(project.path().join("external/boot.bin")).unwrap(),
+        b"bootloader"
+    );
+    let log = std::fs::read_to_string(project.path().join("downloads/fetch.log")).unwrap();
+    assert_eq!(log, format!("boot {mirror_url}\n"));
+
+    let primary_requests = primary.received_requests().await.unwrap().len();
+    assert!(primary_requests > 0);
+    let output = run_fetch_async(&project, &["--force", "--prefer-mirror"]).await;
+    assert!(output.status.success());
+    assert_eq!(
+        primary.received_requests().await.unwrap().len(),
+        primary_requests,
+        "--prefer-mirror should not contact the primary when the mirror works"
+    );
+}
+
+/// Test: A rate-limited fetch still downloads the artifact
+#[tokio::test]
+async fn test_fetch_with_limit_rate() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/boot.bin"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(b"bootloader".as_slice()))
+        .mount(&server)
+        .await;
+
+    let project = setup_project();
+    project.create_file(
+        "zigroot.toml",
+        &format!(
+            r#"
+[project]
+name = "test-project"
+
+[external.boot]
+type = "bootloader"
+url = "{}/boot.bin"
+blake3 = "{}"
+"#,
+            server.uri(),
+            blake3::hash(b"bootloader").to_hex()
+        ),
+    );
+
+    let output = run_fetch_async(&project, &["--limit-rate", "1M"]).await;
+    assert!(
+        output.status.success(),
+        "rate-limited fetch should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        std::fs::read(project.path().join("external/boot.bin")).unwrap(),
+        b"bootloader"
+    );
+}
+
+/// Test: An unparseable --limit-rate is rejected before downloading
+#[test]
+fn test_fetch_rejects_invalid_limit_rate() {
+    let project = setup_project();
+    let output = run_fetch(&project, &["--limit-rate", "fast"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Invalid --limit-rate"),
+        "unexpected error: {stderr}"
+    );
+}
+
 /// Test: Fetch with no packages succeeds
 /// **Validates: Requirement 3.1 (edge case)**
 #[test]
@@ -372,6 +567,184 @@ fn test_fetch_no_packages() {
     );
 }
 
+/// Run git in `dir`, returning its trimmed output
+fn git(dir: &std::path::Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args([
+            "-c",
+            "user.name=zigroot",
+            "-c",
+            "user.email=zigroot@example.com",
+        ])
+        .args(["-c", "protocol.file.allow=always"])
+        .args(args)
+        .output()
+        .expect("Failed to execute git");
+    assert!(output.status.success(), "git {args:?} failed");
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Test: Git packages are cloned into shared storage once per commit, with
+/// submodules when their source declares it
+#[test]
+fn test_fetch_clones_git_packages_into_shared_storage() {
+    let repos = tempfile::TempDir::new().unwrap();
+    let (library, app) = (repos.path().join("library"), repos.path().join("app"));
+    std::fs::create_dir_all(&library).unwrap();
+    std::fs::create_dir_all(&app).unwrap();
+    git(&library, &["init", "-q", "-b", "main"]);
+    std::fs::write(library.join("lib.c"), "int lib;\n").unwrap();
+    git(&library, &["add", "lib.c"]);
+    git(&library, &["commit", "-q", "-m", "library"]);
+    git(&app, &["init", "-q", "-b", "main"]);
+    git(&app, &["submodule", "add", "-q", "../library", "library"]);
+    git(&app, &["commit", "-q", "-m", "app"]);
+    git(&app, &["tag", "-a", "v1.0.0", "-m", "release"]);
+    let tagged = git(&app, &["rev-parse", "HEAD"]);
+    git(&app, &["commit", "-q", "--allow-empty", "-m", "next"]);
+    let url = format!("file://{}", app.display());
+
+    let project = setup_project();
+    let data = tempfile::TempDir::new().unwrap();
+    let fetch = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_zigroot"))
+            .current_dir(project.path())
+            .env("ZIGROOT_DATA_DIR", data.path())
+            .arg("fetch")
+            .args(args)
+            .output()
+            .expect("Failed to execute zigroot fetch");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+    let checkouts = |commit: &str| -> Vec<std::path::PathBuf> {
+        let git_dir = data.path().join("downloads/git");
+        std::fs::read_dir(&git_dir)
+            .map(|repos| {
+                repos
+                    .filter_map(|repo| Some(repo.ok()?.path().join(commit)))
+                    .filter(|checkout| checkout.is_dir())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let output = run_add(&project, &["app", "--git", &url, "--ref", "v1.0.0"]);
+    assert!(output.status.success());
+    let stdout = fetch(&[]);
+    assert!(stdout.contains("Downloaded 1 package"), "{stdout}");
+    let cloned = checkouts(&tagged);
+    assert_eq!(cloned.len(), 1, "Tag should be cloned at its commit");
+    assert!(cloned[0].join(".gitmodules").exists());
+    assert!(
+        !cloned[0].join("library/lib.c").exists(),
+        "Submodules are only cloned on request"
+    );
+
+    // A second fetch reuses the clone
+    std::fs::write(cloned[0].join("marker"), "kept").unwrap();
+    let stdout = fetch(&[]);
+    assert!(stdout.contains("Skipped 1 package"), "{stdout}");
+    assert!(cloned[0].join("marker").exists());
+
+    // A local definition can ask for submodules
+    project.create_file(
+        "packages/app/package.toml",
+        &format!(
+            r#"[package]
+name = "app"
+version = "1.0.0"
+description = "App with vendored library"
+
+[source]
+git = "{url}"
+tag = "v1.0.0"
+git_submodules = true
+"#
+        ),
+    );
+    fetch(&["--force"]);
+    assert_eq!(
+        std::fs::read_to_string(cloned[0].join("library/lib.c")).unwrap(),
+        "int lib;\n"
+    );
+    assert!(!cloned[0].join("marker").exists());
+}
+
+/// Test: Git checkouts at another commit than the locked one are cloned
+/// again, and --locked fails if the locked commit cannot be fetched
+#[test]
+fn test_fetch_verifies_git_checkouts_against_lock() {
+    let repos = tempfile::TempDir::new().unwrap();
+    let app = repos.path().join("app");
+    std::fs::create_dir_all(&app).unwrap();
+    git(&app, &["init", "-q", "-b", "main"]);
+    std::fs::write(app.join("app.c"), "int main;\n").unwrap();
+    git(&app, &["add", "app.c"]);
+    git(&app, &["commit", "-q", "-m", "app"]);
+    let locked = git(&app, &["rev-parse", "HEAD"]);
+    let url = format!("file://{}", app.display());
+
+    let project = setup_project();
+    let data = tempfile::TempDir::new().unwrap();
+    let zigroot = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zigroot"))
+            .current_dir(project.path())
+            .env("ZIGROOT_DATA_DIR", data.path())
+            .args(args)
+            .output()
+            .expect("Failed to execute zigroot")
+    };
+    let output = run_add(&project, &["app", "--git", &url, "--ref", "main"]);
+    assert!(output.status.success());
+    assert!(zigroot(&["fetch"]).status.success());
+    let checkout = std::fs::read_dir(data.path().join("downloads/git"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path()
+        .join(&locked);
+    assert_eq!(git(&checkout, &["rev-parse", "HEAD"]), locked);
+
+    // A checkout moved to another commit is cloned again by fetch and build
+    for command in ["fetch", "build"] {
+        git(
+            &checkout,
+            &["commit", "-q", "--allow-empty", "-m", "tampered"],
+        );
+        let output = zigroot(&[command]);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(git(&checkout, &["rev-parse", "HEAD"]), locked, "{command}");
+    }
+
+    // A locked commit the remote does not have
+    let lock = project.read_file("zigroot.lock");
+    project.create_file("zigroot.lock", &lock.replace(&locked, &"0".repeat(40)));
+    let output = zigroot(&["fetch", "--locked"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !output.status.success(),
+        "fetch --locked should fail: {stdout}"
+    );
+    assert!(stdout.contains("at its locked commit"), "stdout: {stdout}");
+    let output = zigroot(&["fetch"]);
+    assert!(
+        output.status.success(),
+        "fetch should resolve the ref again: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
 /// Test: Fetch creates downloads directory structure
 /// **Validates: Requirement 3.1**
 #[test]
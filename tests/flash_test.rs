@@ -571,6 +571,340 @@ fn test_flash_invalid_method_name() {
     );
 }
 
+// ============================================
+// SSH Flashing
+// ============================================
+
+/// Helper to set up a project flashing over SSH with a fake `ssh`
+///
+/// The fake `ssh` runs the remote command locally, so the "device" is the
+/// project's `device/` directory. `ssh_exit` makes it fail like a refused
+/// connection instead.
+fn setup_ssh_project(ssh_exit: Option<u8>) -> TestProject {
+    use std::os::unix::fs::PermissionsExt;
+
+    let project = TestProject::new();
+    let device = project.path().join("device");
+    project.create_file(
+        "zigroot.toml",
+        &format!(
+            r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[flash.ssh]
+host = "device.local"
+user = "root"
+target_path = "{}/rootfs.img"
+pre_commands = ["mkdir -p {}"]
+post_commands = ["touch {}/rebooted"]
+"#,
+            device.display(),
+            device.display(),
+            device.display()
+        ),
+    );
+    project.create_file("output/rootfs.img", "ssh image content");
+
+    let script = match ssh_exit {
+        Some(code) => {
+            format!("#!/bin/sh\necho 'root@device.local: Permission denied (publickey).' >&2\nexit {code}\n")
+        }
+        None => "#!/bin/sh\nwhile [ $# -gt 1 ]; do\n  case \"$1\" in -o|-p) shift 2 ;; *) shift ;; esac\ndone\nexec sh -c \"$1\"\n".to_string(),
+    };
+    project.create_file("bin/ssh", &script);
+    std::fs::set_permissions(
+        project.path().join("bin/ssh"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+    project
+}
+
+/// Helper to run zigroot flash with the project's `bin/` first in PATH
+fn run_flash_with_bin(project: &TestProject, args: &[&str]) -> std::process::Output {
+    let path = format!(
+        "{}:{}",
+        project.path().join("bin").display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
+    cmd.current_dir(project.path());
+    cmd.env("PATH", path);
+    cmd.arg("flash");
+    cmd.args(args);
+    cmd.output().expect("Failed to execute zigroot flash")
+}
+
+/// Test: SSH flashing streams the image, verifies it and runs the commands
+#[test]
+fn test_flash_ssh_copies_and_verifies_image() {
+    let project = setup_ssh_project(None);
+
+    let output = run_flash_with_bin(&project, &["--method", "ssh", "--yes"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        output.status.success(),
+        "SSH flash should succeed: stdout={stdout}, stderr={stderr}"
+    );
+
+    assert_eq!(project.read_file("device/rootfs.img"), "ssh image content");
+    assert!(project.file_exists("device/rebooted"));
+    assert!(stdout.contains("on root@device.local"), "stdout={stdout}");
+    assert!(stdout.contains("Verified sha256"), "stdout={stdout}");
+}
+
+/// Test: --device overrides the remote target path
+#[test]
+fn test_flash_ssh_device_overrides_target() {
+    let project = setup_ssh_project(None);
+    let target = project.path().join("device/override.img");
+
+    let output = run_flash_with_bin(
+        &project,
+        &["ssh", "--device", &target.to_string_lossy(), "--yes"],
+    );
+    assert!(
+        output.status.success(),
+        "stderr={}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(project.file_exists("device/override.img"));
+    assert!(!project.file_exists("device/rootfs.img"));
+}
+
+/// Test: The confirmation shows the host and target
+#[test]
+fn test_flash_ssh_confirmation_shows_host() {
+    let project = setup_ssh_project(None);
+
+    let output = run_flash_with_bin(&project, &["ssh"]);
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("rootfs.img on root@device.local"),
+        "stderr={stderr}"
+    );
+    assert!(
+        !project.file_exists("device"),
+        "Nothing should run unconfirmed"
+    );
+}
+
+/// Test: Connection failures name the host and hint at SSH keys
+#[test]
+fn test_flash_ssh_connection_error_mentions_host() {
+    let project = setup_ssh_project(Some(255));
+
+    let output = run_flash_with_bin(&project, &["ssh", "--yes"]);
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Could not connect to root@device.local"),
+        "stderr={stderr}"
+    );
+    assert!(stderr.contains("SSH key"), "stderr={stderr}");
+}
+
+// ============================================
+// Board Flash Methods
+// ============================================
+
+/// Helper to set up a board with `[[flash.methods]]` command templates
+///
+/// `fake-flasher` is installed in the project's `bin/` and prints its
+/// arguments.
+fn setup_command_project() -> TestProject {
+    use std::os::unix::fs::PermissionsExt;
+
+    let project = TestProject::new();
+    project.create_file(
+        "boards/vendor-board/board.toml",
+        r#"
+[board]
+name = "vendor-board"
+description = "A board with a vendor flashing tool"
+target = "aarch64-linux-musl"
+cpu = "cortex-a53"
+
+[defaults]
+image_format = "ext4"
+rootfs_size = "64M"
+hostname = "vendor"
+
+[[flash.methods]]
+name = "vendor-usb"
+description = "Write over USB with the vendor tool"
+host_tools = ["fake-flasher"]
+command = "fake-flasher write {image} to {device}"
+
+[[flash.methods]]
+name = "vendor-boot"
+description = "Write the boot partition"
+host_tools = ["fake-flasher"]
+command = "fake-flasher boot {partition:boot}"
+
+[[flash.methods]]
+name = "vendor-missing"
+description = "Needs tools that are not installed"
+host_tools = ["fake-flasher", "zigroot-missing-flash-tool"]
+command = "zigroot-missing-flash-tool {image}"
+"#,
+    );
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"test-project\"\n\n[board]\nname = \"vendor-board\"\n",
+    );
+    project.create_file("output/rootfs.img", "vendor image content");
+    project.create_file("bin/fake-flasher", "#!/bin/sh\necho \"fake-flasher: $*\"\n");
+    std::fs::set_permissions(
+        project.path().join("bin/fake-flasher"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+    project
+}
+
+/// Test: --list shows board-provided methods with their commands
+#[test]
+fn test_flash_list_shows_board_methods() {
+    let project = setup_command_project();
+
+    let output = run_flash_with_bin(&project, &["--list"]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("vendor-usb - Write over USB with the vendor tool"));
+    assert!(stdout.contains("Command: fake-flasher write {image} to {device}"));
+    assert!(stdout.contains("Host tools: fake-flasher"));
+}
+
+/// Test: A board method runs its command with placeholders substituted
+#[test]
+fn test_flash_board_method_runs_command() {
+    let project = setup_command_project();
+
+    let output = run_flash_with_bin(&project, &["vendor-usb", "--device", "/dev/fake", "--yes"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout={stdout}, stderr={}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let image = project.path().join("output/rootfs.img");
+    assert!(
+        stdout.contains(&format!(
+            "fake-flasher: write {} to /dev/fake",
+            image.display()
+        )),
+        "stdout={stdout}"
+    );
+}
+
+/// Test: Unresolvable placeholders fail with their name before running
+#[test]
+fn test_flash_board_method_unresolved_placeholder() {
+    let project = setup_command_project();
+
+    let output = run_flash_with_bin(&project, &["vendor-usb", "--yes"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("{device}"), "stderr={stderr}");
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("fake-flasher:"));
+
+    let output = run_flash_with_bin(&project, &["vendor-boot", "--yes"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("{partition:boot}"), "stderr={stderr}");
+}
+
+/// Test: Missing host tools are all reported before anything runs
+#[test]
+fn test_flash_board_method_missing_host_tools() {
+    let project = setup_command_project();
+
+    let output = run_flash_with_bin(&project, &["vendor-missing", "--yes"]);
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("✗ zigroot-missing-flash-tool: not found in PATH"),
+        "stderr={stderr}"
+    );
+    assert!(stderr.contains("✓ fake-flasher"), "stderr={stderr}");
+}
+
+// ============================================
+// Flashing an Image File
+// ============================================
+
+/// Test: --image flashes the given file instead of the build output
+#[test]
+fn test_flash_image_replaces_build_output() {
+    let project = setup_command_project();
+    project.create_file("release/sdcard.squashfs", "hsqs release image");
+
+    let output = run_flash_with_bin(
+        &project,
+        &[
+            "vendor-usb",
+            "--device",
+            "/dev/fake",
+            "--yes",
+            "--image",
+            "release/sdcard.squashfs",
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout={stdout}, stderr={}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let image = project.path().join("release/sdcard.squashfs");
+    assert!(
+        stdout.contains(&format!(
+            "fake-flasher: write {} to /dev/fake",
+            image.display()
+        )),
+        "stdout={stdout}"
+    );
+}
+
+/// Test: --image needs no project, but the file must look like an image
+#[test]
+fn test_flash_image_outside_project() {
+    let project = TestProject::new();
+    project.create_file("sdcard.squashfs", "hsqs release image");
+    project.create_file("notes.txt", "not an image");
+
+    let output = run_flash(&project, &["--image", "sdcard.squashfs", "--list"]);
+    assert!(
+        output.status.success(),
+        "stderr={}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = run_flash(&project, &["--image", "notes.txt", "--list"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("does not look like a disk image"),
+        "stderr={stderr}"
+    );
+
+    let output = run_flash(&project, &["--image", "missing.img", "--list"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Image not found"), "stderr={stderr}");
+}
+
 // ============================================
 // Property-Based Tests
 // ============================================
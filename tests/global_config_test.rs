@@ -244,7 +244,8 @@ fn test_global_config_path() {
 #[test]
 fn test_global_config_save_and_load_roundtrip() {
     use zigroot::core::global_config::{
-        BuildConfig, CacheConfig, GlobalConfig, OutputConfig, RegistryConfig, UpdateConfig,
+        BuildConfig, CacheConfig, DownloadConfig, FetchConfig, GlobalConfig, NetworkConfig,
+        OutputConfig, RegistryConfig, UpdateConfig,
     };
 
     let temp_dir = TempDir::new().expect("Failed to create temp dir");
@@ -254,12 +255,29 @@ fn test_global_config_save_and_load_roundtrip() {
         registry: RegistryConfig {
             packages_url: Some("https://test.com/packages".to_string()),
             boards_url: Some("https://test.com/boards".to_string()),
+            public_key: None,
+        },
+        cache: CacheConfig {
+            dir: None,
+            ttl: Some(7200),
+            build_variants: None,
+        },
+        download: DownloadConfig {
+            concurrency: None,
+            source_mirror_prefix: None,
+        },
+        fetch: FetchConfig { limit_rate: None },
+        network: NetworkConfig {
+            proxy: None,
+            ca_bundle: None,
+            timeout_secs: None,
         },
-        cache: CacheConfig { ttl: Some(7200) },
         build: BuildConfig {
             compress: Some(true),
             jobs: Some(8),
             sandbox: Some(false),
+            stats: Some(false),
+            signing_key: None,
         },
         output: OutputConfig {
             color: Some(true),
@@ -283,6 +301,7 @@ fn test_global_config_save_and_load_roundtrip() {
     assert_eq!(loaded.build.compress, config.build.compress);
     assert_eq!(loaded.build.jobs, config.build.jobs);
     assert_eq!(loaded.build.sandbox, config.build.sandbox);
+    assert_eq!(loaded.build.stats, config.build.stats);
     assert_eq!(loaded.output.color, config.output.color);
     assert_eq!(loaded.output.quiet, config.output.quiet);
     assert_eq!(loaded.output.json, config.output.json);
@@ -309,3 +328,127 @@ fn test_global_config_effective_values() {
     // Should return default jobs
     assert!(config.build_jobs() > 0);
 }
+
+/// Helper to run zigroot config with an isolated config directory
+fn run_config(config_dir: &std::path::Path, args: &[&str]) -> std::process::Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .env("ZIGROOT_CONFIG_DIR", config_dir)
+        .arg("config")
+        .args(args)
+        .output()
+        .expect("Failed to execute zigroot config")
+}
+
+/// Test: config set persists a validated value that config get reads back
+#[test]
+fn test_config_set_and_get_roundtrip() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+
+    let output = run_config(temp_dir.path(), &["set", "download.concurrency", "8"]);
+    assert!(
+        output.status.success(),
+        "config set should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = std::fs::read_to_string(temp_dir.path().join("config.toml"))
+        .expect("config.toml should be written");
+    assert!(content.contains("concurrency = 8"), "content={content}");
+
+    let output = run_config(temp_dir.path(), &["get", "download.concurrency"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "8");
+}
+
+/// Test: config set rejects unknown keys and mistyped values
+#[test]
+fn test_config_set_rejects_invalid_input() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+
+    let output = run_config(temp_dir.path(), &["set", "build.compress", "maybe"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("true or false"));
+
+    let output = run_config(temp_dir.path(), &["set", "no.such_key", "1"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown config key"));
+
+    assert!(!temp_dir.path().join("config.toml").exists());
+}
+
+/// Test: config list respects --json
+#[test]
+fn test_config_list_json() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    run_config(
+        temp_dir.path(),
+        &[
+            "set",
+            "registry.packages_url",
+            "https://mirror.example.com/packages",
+        ],
+    );
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .env("ZIGROOT_CONFIG_DIR", temp_dir.path())
+        .args(["--json", "config", "list"])
+        .output()
+        .expect("Failed to execute zigroot config list");
+    assert!(output.status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("config list should output JSON");
+    assert_eq!(
+        json["settings"]["registry.packages_url"],
+        "https://mirror.example.com/packages"
+    );
+    assert!(json["settings"]["cache.dir"].is_null());
+}
+
+/// Test: cache.dir moves the cache used by commands
+#[test]
+fn test_config_cache_dir_is_used() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config_dir = temp_dir.path().join("config");
+    let cache_dir = temp_dir.path().join("cache");
+    let project_dir = temp_dir.path().join("project");
+    std::fs::create_dir_all(&project_dir).unwrap();
+    let output = run_config(
+        &config_dir,
+        &["set", "cache.dir", cache_dir.to_str().unwrap()],
+    );
+    assert!(output.status.success());
+
+    let run = |args: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_zigroot"))
+            .current_dir(&project_dir)
+            .env("ZIGROOT_CONFIG_DIR", &config_dir)
+            .env_remove("ZIGROOT_CACHE_DIR")
+            .args(args)
+            .output()
+            .expect("Failed to execute zigroot")
+    };
+    assert!(run(&["init"]).status.success());
+    std::fs::create_dir_all(project_dir.join("packages/hello")).unwrap();
+    std::fs::write(
+        project_dir.join("packages/hello/package.toml"),
+        "[package]\nname = \"hello\"\nversion = \"1.0.0\"\ndescription = \"hello\"\n\n\
+         [source]\nurl = \"https://example.com/hello.tar.gz\"\n\
+         sha256 = \"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\"\n\n\
+         [build]\ntype = \"custom\"\n\n[[build.steps]]\nrun = \"true\"\n",
+    )
+    .unwrap();
+    let manifest = std::fs::read_to_string(project_dir.join("zigroot.toml")).unwrap();
+    std::fs::write(
+        project_dir.join("zigroot.toml"),
+        format!("{manifest}\n[packages.hello]\nversion = \"1.0.0\"\n"),
+    )
+    .unwrap();
+    let output = run(&["build", "--no-sandbox"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(cache_dir.join("build-history.json").is_file());
+}
@@ -243,6 +243,26 @@ fn test_init_with_board_flag() {
     );
 }
 
+/// Test: --board <board>:<variant> stores the variant in the board options
+#[test]
+fn test_init_with_board_variant() {
+    let project = TestProject::new();
+
+    let output = run_init(&project, &["--board", "rpi4:2gb"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let manifest: toml::Value = toml::from_str(&project.read_file("zigroot.toml")).unwrap();
+    assert_eq!(manifest["board"]["name"].as_str(), Some("rpi4"));
+    assert_eq!(
+        manifest["board"]["options"]["variant"].as_str(),
+        Some("2gb")
+    );
+}
+
 /// Test: Appending to existing .gitignore preserves existing content
 /// **Validates: Requirement 1.7**
 #[test]
@@ -335,6 +355,123 @@ fn test_init_manifest_has_commented_examples() {
     );
 }
 
+/// Helper to run any zigroot command in the project
+fn run_zigroot(project: &TestProject, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .current_dir(project.path())
+        .args(args)
+        .output()
+        .expect("Failed to execute zigroot")
+}
+
+/// Test: projects generated from every bundled template pass `zigroot check`
+#[test]
+fn test_init_templates_pass_check() {
+    for template in ["minimal", "networking", "kiosk"] {
+        let project = TestProject::new();
+
+        let output = run_init(&project, &["--template", template]);
+        assert!(
+            output.status.success(),
+            "init --template {template} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert!(has_valid_manifest(&project));
+
+        let output = run_zigroot(&project, &["check"]);
+        assert!(
+            output.status.success(),
+            "check failed for {template}: {}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
+
+/// Test: template packages and files are added to the project
+#[test]
+fn test_init_template_adds_packages_and_files() {
+    let project = TestProject::new();
+
+    let output = run_init(&project, &["-t", "networking"]);
+    assert!(output.status.success());
+
+    let content = project.read_file("zigroot.toml");
+    assert!(content.contains("[packages.dropbear]"));
+    assert!(content.contains("hostname = \"zigroot-net\""));
+    assert!(project.file_exists("user/files/etc/network/interfaces"));
+    assert!(project.file_exists("user/files/etc/init.d/S40network"));
+}
+
+/// Test: --list-templates lists the bundled templates
+#[test]
+fn test_init_list_templates() {
+    let project = TestProject::new();
+
+    let output = run_init(&project, &["--list-templates"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for template in ["minimal", "networking", "kiosk"] {
+        assert!(stdout.contains(template), "{stdout}");
+    }
+    assert!(!project.file_exists("zigroot.toml"));
+
+    let output = run_zigroot(&project, &["--json", "init", "--list-templates"]);
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let names: Vec<_> = json["templates"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(names.len(), 3, "{names:?}");
+}
+
+/// Test: non-interactive flags are written to the manifest
+#[test]
+fn test_init_settings_flags() {
+    let project = TestProject::new();
+
+    let output = run_init(
+        &project,
+        &[
+            "--name",
+            "gateway",
+            "--hostname",
+            "gw-01",
+            "--image-format",
+            "squashfs",
+            "--rootfs-size",
+            "1G",
+        ],
+    );
+    assert!(output.status.success());
+
+    let content = project.read_file("zigroot.toml");
+    let manifest: toml::Value = toml::from_str(&content).unwrap();
+    assert_eq!(manifest["project"]["name"].as_str(), Some("gateway"));
+    assert_eq!(manifest["build"]["hostname"].as_str(), Some("gw-01"));
+    assert_eq!(manifest["build"]["image_format"].as_str(), Some("squashfs"));
+    assert_eq!(manifest["build"]["rootfs_size"].as_str(), Some("1G"));
+}
+
+/// Test: unknown templates and invalid settings fail without writing files
+#[test]
+fn test_init_rejects_unknown_template_and_invalid_settings() {
+    for args in [
+        &["--template", "desktop"][..],
+        &["--image-format", "zip"][..],
+        &["--hostname", "bad_host"][..],
+    ] {
+        let project = TestProject::new();
+
+        let output = run_init(&project, args);
+        assert!(!output.status.success(), "{args:?} should fail");
+        assert!(!project.file_exists("zigroot.toml"), "{args:?}");
+    }
+}
+
 // ============================================
 // Property-Based Tests
 // ============================================
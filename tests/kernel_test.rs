@@ -697,3 +697,205 @@ version = "1.36.1"
         "--kernel-only should skip non-kernel packages: stdout={stdout}, stderr={stderr}"
     );
 }
+
+// ============================================
+// Integration Tests for zigroot kernel build
+// ============================================
+
+/// Manifest with the kernel package selected
+const KERNEL_MANIFEST: &str = r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[build]
+image_format = "ext4"
+rootfs_size = "64M"
+hostname = "test"
+
+[packages.linux-kernel]
+version = "6.6.0"
+"#;
+
+/// Test: zigroot kernel build fails without a kernel package
+#[test]
+fn test_kernel_build_requires_kernel_package() {
+    let project = TestProject::new();
+    project.create_file("zigroot.toml", KERNEL_MANIFEST);
+
+    let output = run_kernel(&project, &["build"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("No kernel package found"),
+        "Should report missing kernel package: {stderr}"
+    );
+}
+
+/// Test: zigroot kernel build fails and asks to fetch when the source is missing
+#[test]
+fn test_kernel_build_without_source_prompts_fetch() {
+    let project = TestProject::new();
+    project.create_file("zigroot.toml", KERNEL_MANIFEST);
+    create_kernel_package(&project, Some("multi_v7_defconfig"), None);
+
+    let output = run_kernel(&project, &["build"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("zigroot fetch"),
+        "Should suggest fetching kernel source: {stderr}"
+    );
+}
+
+/// Test: zigroot kernel build collects artifacts and registers them as externals
+#[cfg(unix)]
+#[test]
+fn test_kernel_build_registers_external_artifacts() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let project = TestProject::new();
+    project.create_file("zigroot.toml", KERNEL_MANIFEST);
+    create_kernel_package(&project, Some("multi_v7_defconfig"), None);
+    project.create_dir("build/src/linux-kernel");
+
+    // Fake `make` that produces a kernel image and a DTB
+    let fake_make = r#"#!/bin/sh
+for arg in "$@"; do
+  case "$arg" in
+    zImage) mkdir -p arch/arm/boot && touch arch/arm/boot/zImage ;;
+    dtbs) mkdir -p arch/arm/boot/dts && touch arch/arm/boot/dts/test-board.dtb ;;
+  esac
+done
+"#;
+    project.create_file("bin/make", fake_make);
+    let make_path = project.path().join("bin/make");
+    std::fs::set_permissions(&make_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path_env = format!(
+        "{}:{}",
+        project.path().join("bin").display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let output = Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .current_dir(project.path())
+        .env("PATH", path_env)
+        .args(["kernel", "build"])
+        .output()
+        .expect("Failed to execute zigroot kernel build");
+
+    assert!(
+        output.status.success(),
+        "kernel build should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(project.file_exists("output/kernel/zImage"));
+    assert!(project.file_exists("output/kernel/test-board.dtb"));
+
+    let manifest = zigroot::core::manifest::Manifest::from_toml(&project.read_file("zigroot.toml"))
+        .expect("Manifest should remain valid");
+    let kernel = manifest.external.get("kernel").expect("kernel registered");
+    assert_eq!(kernel.artifact_type, "kernel");
+    assert_eq!(kernel.path.as_deref(), Some("output/kernel/zImage"));
+    let dtb = manifest
+        .external
+        .get("dtb-test-board")
+        .expect("dtb registered");
+    assert_eq!(dtb.artifact_type, "dtb");
+}
+
+/// Test: zigroot kernel savedefconfig fails and asks to fetch when the source
+/// is missing
+#[test]
+fn test_kernel_savedefconfig_without_source_prompts_fetch() {
+    let project = TestProject::new();
+    project.create_file("zigroot.toml", KERNEL_MANIFEST);
+    create_kernel_package(&project, Some("multi_v7_defconfig"), None);
+
+    let output = run_kernel(&project, &["savedefconfig"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("zigroot fetch") && stderr.contains("kernel savedefconfig"),
+        "Should suggest fetching kernel source: {stderr}"
+    );
+    assert!(!project.file_exists("kernel/defconfig"));
+}
+
+/// Test: savedefconfig stores the minimal config and build merges it back
+#[cfg(unix)]
+#[test]
+fn test_kernel_savedefconfig_roundtrip_through_build() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let project = TestProject::new();
+    project.create_file("zigroot.toml", KERNEL_MANIFEST);
+    create_kernel_package(&project, Some("multi_v7_defconfig"), None);
+    project.create_dir("build/src/linux-kernel/scripts/kconfig");
+
+    // Fake `make` and merge_config.sh that record their invocations
+    let fake_make = r#"#!/bin/sh
+echo "make $*" >> commands.log
+for arg in "$@"; do
+  case "$arg" in
+    multi_v7_defconfig) echo "CONFIG_FULL=y" > .config ;;
+    savedefconfig) echo "CONFIG_USB=y" > defconfig ;;
+    zImage) mkdir -p arch/arm/boot && touch arch/arm/boot/zImage ;;
+  esac
+done
+"#;
+    let fake_merge = "#!/bin/sh\necho \"merge $*\" >> commands.log\n";
+    project.create_file("bin/make", fake_make);
+    project.create_file(
+        "build/src/linux-kernel/scripts/kconfig/merge_config.sh",
+        fake_merge,
+    );
+    for script in [
+        "bin/make",
+        "build/src/linux-kernel/scripts/kconfig/merge_config.sh",
+    ] {
+        std::fs::set_permissions(
+            project.path().join(script),
+            std::fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+    }
+
+    let path_env = format!(
+        "{}:{}",
+        project.path().join("bin").display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let run = |subcommand: &str| {
+        Command::new(env!("CARGO_BIN_EXE_zigroot"))
+            .current_dir(project.path())
+            .env("PATH", &path_env)
+            .args(["kernel", subcommand])
+            .output()
+            .expect("Failed to execute zigroot kernel")
+    };
+
+    let output = run("savedefconfig");
+    assert!(
+        output.status.success(),
+        "savedefconfig should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(project.read_file("kernel/defconfig"), "CONFIG_USB=y\n");
+
+    let output = run("build");
+    assert!(
+        output.status.success(),
+        "kernel build should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let log = project.read_file("build/src/linux-kernel/commands.log");
+    assert!(
+        log.lines()
+            .any(|l| l.starts_with("merge -m .config") && l.ends_with("kernel/defconfig")),
+        "Build should merge the saved minimal config: {log}"
+    );
+}
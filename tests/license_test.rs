@@ -387,6 +387,91 @@ fn test_license_with_no_packages() {
     );
 }
 
+/// Test: License expressions are normalized, checked against the deny list
+/// and license files are collected from the package sources
+#[test]
+fn test_license_expressions_deny_list_and_collect() {
+    let project = setup_project();
+    create_local_package_with_license(&project, "dual", "1.0.0", "gpl-3.0-only or mit");
+    create_local_package_with_license(&project, "odd", "1.0.0", "Frobnicate-1.0");
+    project.create_file(
+        "packages/odd/package.toml",
+        &project
+            .read_file("packages/odd/package.toml")
+            .replace("[source]", "license_files = [\"LICENSES/*\"]\n\n[source]"),
+    );
+    project.create_file("build/src/dual/COPYING", "GPL text");
+    project.create_file("build/src/dual/main.c", "int main;");
+    project.create_file("build/src/odd/LICENSES/vendor.txt", "vendor text");
+
+    let manifest = r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[board]
+
+[build]
+
+[packages.dual]
+version = "1.0.0"
+
+[packages.odd]
+version = "1.0.0"
+
+[license]
+deny = ["GPL-3.0-only"]
+"#;
+    project.create_file("zigroot.toml", manifest);
+
+    let output = run_license(&project, &["--collect"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        output.status.success(),
+        "An OR with an acceptable choice should pass: stdout={stdout}, stderr={stderr}"
+    );
+
+    let licenses_dir = walkdir::WalkDir::new(project.path().join("output"))
+        .into_iter()
+        .filter_map(Result::ok)
+        .find(|entry| entry.file_name() == "licenses")
+        .expect("licenses directory should be created")
+        .into_path();
+    assert!(licenses_dir.join("dual/COPYING").exists());
+    assert!(!licenses_dir.join("dual/main.c").exists());
+    assert!(licenses_dir.join("odd/LICENSES/vendor.txt").exists());
+
+    let output = run_license(&project, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("GPL-3.0-only OR MIT"),
+        "License should be normalized: {stdout}"
+    );
+    assert!(
+        stdout.contains("'Frobnicate-1.0'"),
+        "Invalid expression should be flagged with the raw string: {stdout}"
+    );
+
+    create_local_package_with_license(&project, "strict", "1.0.0", "GPL-3.0-only");
+    project.create_file(
+        "zigroot.toml",
+        &manifest.replace(
+            "[license]",
+            "[packages.strict]\nversion = \"1.0.0\"\n\n[license]",
+        ),
+    );
+    let output = run_license(&project, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Denied license should fail");
+    assert!(stdout.contains("strict") && !stdout.contains("❌ dual"));
+    assert!(
+        stderr.contains("denied"),
+        "Error should mention denied licenses: {stderr}"
+    );
+}
+
 // ============================================
 // Property-Based Tests
 // ============================================
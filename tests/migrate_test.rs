@@ -0,0 +1,103 @@
+//! Integration tests for `zigroot migrate` command
+//!
+//! Tests for manifest schema versioning:
+//! - Version 1 manifests are rewritten in the current schema
+//! - Comments are preserved
+//! - Manifests from a newer zigroot are rejected
+
+mod common;
+
+use common::TestProject;
+use std::process::Command;
+
+/// Helper to run zigroot migrate command
+fn run_migrate(project: &TestProject, args: &[&str]) -> std::process::Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
+    cmd.current_dir(project.path());
+    cmd.args(args);
+    cmd.arg("migrate");
+    cmd.output().expect("Failed to execute zigroot migrate")
+}
+
+const V1_MANIFEST: &str = r#"# Legacy project
+[project]
+name = "legacy"
+
+[packages.dropbear]
+# Pinned release
+git = "https://github.com/example/dropbear"
+ref_ = "v2024.85"
+"#;
+
+#[test]
+fn test_migrate_rewrites_v1_manifest() {
+    let project = TestProject::new();
+    project.create_file("zigroot.toml", V1_MANIFEST);
+
+    let output = run_migrate(&project, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "migrate failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("packages.dropbear.ref_ -> packages.dropbear.ref"));
+
+    let content = project.read_file("zigroot.toml");
+    assert!(content.contains("manifest_version = 2"));
+    assert!(content.contains("ref = \"v2024.85\""));
+    assert!(!content.contains("ref_"));
+    assert!(content.contains("# Legacy project"));
+    assert!(content.contains("# Pinned release"));
+
+    // A second run has nothing to do
+    let output = run_migrate(&project, &[]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("already uses manifest_version 2"));
+    assert_eq!(project.read_file("zigroot.toml"), content);
+}
+
+#[test]
+fn test_migrate_json_output() {
+    let project = TestProject::new();
+    project.create_file("zigroot.toml", V1_MANIFEST);
+
+    let output = run_migrate(&project, &["--json"]);
+    assert!(output.status.success());
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("output is not JSON");
+    assert_eq!(json["from_version"], 1);
+    assert_eq!(json["to_version"], 2);
+    assert_eq!(json["migrated"], true);
+    assert_eq!(json["renamed"][0]["to"], "packages.dropbear.ref");
+}
+
+#[test]
+fn test_newer_manifest_version_asks_for_update() {
+    let project = TestProject::new();
+    let content = "manifest_version = 99\n\n[project]\nname = \"future\"\n";
+    project.create_file("zigroot.toml", content);
+
+    let output = run_migrate(&project, &[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Please update zigroot"), "stderr: {stderr}");
+    assert_eq!(project.read_file("zigroot.toml"), content);
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
+    let output = cmd
+        .current_dir(project.path())
+        .arg("check")
+        .output()
+        .expect("Failed to execute zigroot check");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Please update zigroot"));
+}
+
+#[test]
+fn test_migrate_requires_manifest() {
+    let project = TestProject::new();
+    let output = run_migrate(&project, &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No zigroot.toml found"));
+}
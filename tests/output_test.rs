@@ -156,6 +156,49 @@ fn test_warning_uses_yellow_indicator() {
     );
 }
 
+/// Test: warnings are summarized at the end of a command, as a JSON array
+/// with --json and not at all with --quiet
+#[test]
+fn test_warnings_summarized_at_end() {
+    let project = TestProject::new();
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"warned\"\n\n[packages.broken]\nversion = \"1.0.0\"\n",
+    );
+    project.create_file("packages/broken/package.toml", "[package\n");
+
+    let output = run_check(&project, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let summary = stdout
+        .rfind(" warnings:")
+        .or_else(|| stdout.rfind(" warning:"))
+        .unwrap_or_else(|| panic!("missing warning summary: {stdout}"));
+    assert!(
+        stdout[summary..].contains("Failed to parse package definition for 'broken'"),
+        "stdout: {stdout}"
+    );
+
+    let output = run_check(&project, &["--json"]);
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("check output is not JSON");
+    assert!(
+        json["warnings"][0]
+            .as_str()
+            .is_some_and(|w| w.contains("'broken'")),
+        "{json}"
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stdout)
+            .matches("\"warnings\"")
+            .count()
+            == 1,
+        "warnings should only appear in the check result"
+    );
+
+    let output = run_check(&project, &["--quiet"]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("warning"));
+}
+
 /// Test: --quiet suppresses all output except errors
 /// **Validates: Requirement 15.8**
 #[test]
@@ -360,6 +403,94 @@ fn test_non_interactive_fallback() {
     );
 }
 
+/// Test: progress bars are hidden under --json, quiet and without a terminal
+#[test]
+fn test_progress_hidden_for_machine_output() {
+    use zigroot::cli::output::progress_visible;
+
+    assert!(progress_visible(false, false, true));
+    assert!(!progress_visible(false, true, true));
+    assert!(!progress_visible(true, false, true));
+    assert!(!progress_visible(false, false, false));
+
+    let project = TestProject::new();
+    assert!(run_init(&project, &[]).status.success());
+    let output = run_zigroot(&project, &["--json", "build"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let values: Result<Vec<serde_json::Value>, _> =
+        serde_json::Deserializer::from_slice(&output.stdout)
+            .into_iter()
+            .collect();
+    let values = values.expect("--json build output is not JSON");
+    assert!(!values.is_empty());
+    for stream in [&output.stdout, &output.stderr] {
+        let text = String::from_utf8_lossy(stream);
+        assert!(
+            !text.contains('\r') && !text.contains('\x1b'),
+            "progress artifacts in output: {text:?}"
+        );
+        assert!(!text.contains('⠋'), "spinner frame in output: {text:?}");
+    }
+}
+
+/// Test: piped output has no escape codes unless --color always
+#[test]
+fn test_piped_output_has_no_color_codes() {
+    let run = |args: &[&str], no_color: Option<&str>| {
+        let project = TestProject::new();
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
+        cmd.current_dir(project.path()).args(args).arg("init");
+        match no_color {
+            Some(value) => cmd.env("NO_COLOR", value),
+            None => cmd.env_remove("NO_COLOR"),
+        };
+        let output = cmd.output().expect("Failed to execute zigroot init");
+        assert!(output.status.success());
+        format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    };
+
+    for (args, no_color) in [
+        (&[][..], None),
+        (&["--color", "auto"][..], None),
+        (&["--color", "never"][..], None),
+        (&[][..], Some("1")),
+    ] {
+        let output = run(args, no_color);
+        assert!(
+            !output.contains('\x1b'),
+            "{args:?} with NO_COLOR={no_color:?} printed escape codes: {output:?}"
+        );
+    }
+
+    let output = run(&["--color", "always"], Some("1"));
+    assert!(
+        output.contains("\x1b[32m✓\x1b[0m"),
+        "--color always should color: {output:?}"
+    );
+}
+
+/// Test: --color auto colors only terminals without NO_COLOR
+#[test]
+fn test_color_choice_resolution() {
+    use zigroot::cli::output::ColorChoice;
+
+    assert!(ColorChoice::Auto.resolve(None, true));
+    assert!(ColorChoice::Auto.resolve(Some(""), true));
+    assert!(!ColorChoice::Auto.resolve(Some("1"), true));
+    assert!(!ColorChoice::Auto.resolve(None, false));
+    assert!(ColorChoice::Always.resolve(Some("1"), false));
+    assert!(!ColorChoice::Never.resolve(None, true));
+}
+
 /// Test: Multi-progress bar support for parallel operations
 /// **Validates: Requirement 15.5**
 #[test]
@@ -484,6 +615,43 @@ fn test_build_summary_failed() {
     assert!(!summary.success);
 }
 
+/// Test: `BuildSummary` with estimated time
+#[test]
+fn test_build_summary_with_estimated_time() {
+    use std::time::{Duration, Instant};
+    use zigroot::cli::output::BuildSummary;
+
+    let summary =
+        BuildSummary::new(Instant::now(), 2, 2).with_estimated_time(Duration::from_secs(90));
+    assert_eq!(summary.estimated_time, Some(Duration::from_secs(90)));
+}
+
+/// Test: Overall progress estimates the remaining time from the weights
+#[test]
+fn test_overall_progress_eta() {
+    use std::time::Duration;
+    use zigroot::cli::output::OverallProgress;
+
+    let mut progress = OverallProgress::new(100.0);
+    assert_eq!(progress.eta(), Some(Duration::from_secs(100)));
+
+    progress.start_package("busybox");
+    progress.finish_package(100.0);
+    assert_eq!(progress.eta(), None);
+    progress.finish();
+}
+
+/// Test: `format_duration` helper function
+#[test]
+fn test_format_duration() {
+    use std::time::Duration;
+    use zigroot::cli::output::format_duration;
+
+    assert_eq!(format_duration(Duration::from_millis(44_600)), "45s");
+    assert_eq!(format_duration(Duration::from_secs(185)), "3m 05s");
+    assert_eq!(format_duration(Duration::from_secs(3720)), "1h 02m");
+}
+
 /// Test: format_size helper function
 /// **Validates: Requirement 15.7**
 #[test]
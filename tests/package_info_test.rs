@@ -320,6 +320,7 @@ fn test_package_info_git_package() {
             "custom-pkg",
             "--git",
             "https://github.com/example/repo#v1.0.0",
+            "--offline",
         ],
     );
     assert!(
@@ -421,3 +422,83 @@ fn test_package_info_shows_homepage() {
         "Package info should display package information: stdout={stdout}"
     );
 }
+
+/// Test: Package info shows each option's effective value and its source
+#[test]
+fn test_package_info_shows_option_sources() {
+    let project = TestProject::new();
+    project.create_file(
+        "packages/busybox/package.toml",
+        r#"[package]
+name = "busybox"
+version = "1.36.1"
+description = "Swiss army knife of embedded Linux"
+
+[source]
+url = "https://busybox.net/downloads/busybox-1.36.1.tar.bz2"
+sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+
+[options.static]
+type = "bool"
+default = false
+description = "Link statically"
+
+[options.shell]
+type = "choice"
+default = "ash"
+choices = ["ash", "hush"]
+description = "Default shell"
+
+[options.applets]
+type = "string"
+default = "all"
+description = "Applets to enable"
+"#,
+    );
+    project.create_file(
+        "boards/tiny/board.toml",
+        r#"[board]
+name = "tiny"
+description = "A tiny board"
+target = "arm-linux-musleabihf"
+cpu = "cortex-m7"
+
+[defaults]
+image_format = "initramfs"
+rootfs_size = "8M"
+hostname = "tiny"
+
+[package_options.busybox]
+shell = "hush"
+static = true
+"#,
+    );
+    project.create_file(
+        "zigroot.toml",
+        r#"[project]
+name = "test-project"
+version = "1.0.0"
+
+[board]
+name = "tiny"
+
+[packages.busybox]
+version = "1.36.1"
+
+[packages.busybox.options]
+static = false
+"#,
+    );
+
+    let output = run_package_info(&project, "busybox");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "zigroot package info should succeed: stdout={stdout}, stderr={}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    assert!(stdout.contains("applets: \"all\" (default)"), "{stdout}");
+    assert!(stdout.contains("shell: \"hush\" (board)"), "{stdout}");
+    assert!(stdout.contains("static: false (package)"), "{stdout}");
+}
@@ -279,6 +279,7 @@ fn test_package_list_git_package() {
             "custom-pkg",
             "--git",
             "https://github.com/example/repo#v1.0.0",
+            "--offline",
         ],
     );
     assert!(
@@ -150,3 +150,120 @@ fn test_package_test_provides_output() {
         );
     }
 }
+
+/// Create a local package whose build fails for 32-bit ARM targets
+fn create_target_sensitive_package(project: &TestProject) {
+    project.create_file(
+        "packages/app/package.toml",
+        r#"[package]
+name = "app"
+version = "1.2.0"
+description = "Target-sensitive package"
+license = "MIT"
+
+[source]
+url = "https://example.com/app-1.2.0.tar.gz"
+sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+
+[build]
+type = "custom"
+
+[[build.steps]]
+run = "test \"${TARGET%%-*}\" != arm"
+
+[[build.steps]]
+run = "echo \"$TARGET\" > \"$DESTDIR/target\""
+"#,
+    );
+}
+
+/// Test: each target of the matrix is built in its own scratch project and
+/// reported in the summary, with a failure failing the command
+#[test]
+fn test_package_test_target_matrix() {
+    let project = TestProject::new();
+    let data = tempfile::TempDir::new().unwrap();
+    create_target_sensitive_package(&project);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .current_dir(project.path())
+        .env("ZIGROOT_DATA_DIR", data.path())
+        .args([
+            "package",
+            "test",
+            "packages/app",
+            "--targets",
+            "aarch64-linux-musl,arm-linux-musleabihf",
+            "--keep",
+        ])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "stdout={stdout}");
+    assert!(
+        stderr.contains("Package 'app' failed on 1 of 2 target(s)"),
+        "stderr={stderr}"
+    );
+    let row = |target: &str| {
+        stdout
+            .lines()
+            .find(|line| line.trim_start().starts_with(&format!("target {target} ")))
+            .unwrap_or_default()
+            .to_string()
+    };
+    assert!(row("aarch64-linux-musl").contains("✓ pass"), "{stdout}");
+    assert!(row("arm-linux-musleabihf").contains("✗ fail"), "{stdout}");
+
+    let scratch = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("Scratch projects kept in "))
+        .expect("scratch directory should be printed");
+    let scratch = std::path::Path::new(scratch);
+    let built = scratch.join("target-aarch64-linux-musl/build/destdir/app/target");
+    let kept = std::fs::read_to_string(&built);
+    std::fs::remove_dir_all(scratch).unwrap();
+    assert_eq!(kept.unwrap(), "aarch64-linux-musl\n");
+    // The package under test is left alone
+    assert!(!project.path().join("packages/app/build").exists());
+}
+
+/// Test: registry packages are merged for the build, boards come from the
+/// current project, and a passing test is remembered by `publish`
+#[test]
+fn test_package_test_board_and_publish_hint() {
+    let project = TestProject::new();
+    let data = tempfile::TempDir::new().unwrap();
+    create_valid_package(&project, "app");
+    project.create_file(
+        "boards/devkit/board.toml",
+        "[board]\nname = \"devkit\"\ndescription = \"Dev kit\"\n\
+         target = \"riscv64-linux-musl\"\ncpu = \"generic_rv64\"\n\n\
+         [defaults]\nimage_format = \"ext4\"\nrootfs_size = \"auto\"\nhostname = \"devkit\"\n",
+    );
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zigroot"))
+            .current_dir(project.path())
+            .env("ZIGROOT_DATA_DIR", data.path())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let untested = "has not passed 'zigroot package test packages/app'";
+
+    let output = run(&["publish", "packages/app", "--dry-run"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(untested), "stdout={stdout}");
+
+    let output = run(&["package", "test", "packages/app", "--board", "devkit"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stdout={stdout}, stderr={stderr}");
+    assert!(stdout.contains("board devkit"), "{stdout}");
+    assert!(!stdout.contains("Scratch projects kept"));
+
+    let output = run(&["publish", "packages/app", "--dry-run"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Checking package 'app'"), "stdout={stdout}");
+    assert!(!stdout.contains(untested), "stdout={stdout}");
+}
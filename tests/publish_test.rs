@@ -6,6 +6,7 @@
 //! - Requires GitHub authentication
 //! - Checks for name conflicts
 //! - Detects package vs board
+//! - --dry-run runs the pre-flight checks without publishing
 //!
 //! **Validates: Requirements 28.7-28.11, 29.5-29.8**
 
@@ -257,3 +258,230 @@ fn test_publish_provides_helpful_output() {
         );
     }
 }
+
+// ============================================
+// Pre-flight Tests
+// ============================================
+
+/// SHA256 of the mock source archive "source"
+const SOURCE_SHA256: &str = "41cf6794ba4200b839c53531555f0f3998df4cbb01a4d5cb0b94e3ca5e23947d";
+
+/// Mock registry listing `published` versions of `preflight-pkg`, also
+/// serving the package source
+async fn mock_registry(published: &[&str]) -> wiremock::MockServer {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let versions: Vec<_> = published
+        .iter()
+        .map(|v| serde_json::json!({ "version": v }))
+        .collect();
+    let index = serde_json::json!({
+        "version": 1,
+        "updated": "2024-01-01",
+        "packages": [{
+            "name": "preflight-pkg",
+            "description": "A test package",
+            "versions": versions,
+            "latest": published.last().copied().unwrap_or_default(),
+        }],
+    });
+    let server = wiremock::MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/index.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(index))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/src.tar.gz"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(b"source".as_slice()))
+        .mount(&server)
+        .await;
+    server
+}
+
+/// Create `preflight-pkg` at `version` with its source on `server`
+fn create_preflight_package(project: &TestProject, server: &str, version: &str, sha256: &str) {
+    create_valid_package(project, "preflight-pkg");
+    std::fs::remove_file(project.path().join("packages/preflight-pkg/1.0.0.toml")).unwrap();
+    project.create_file(
+        &format!("packages/preflight-pkg/{version}.toml"),
+        &format!(
+            "[release]\nversion = \"{version}\"\n\n[source]\nurl = \"{server}/src.tar.gz\"\nsha256 = \"{sha256}\"\n"
+        ),
+    );
+    project.create_file(
+        "config/config.toml",
+        &format!("[registry]\npackages_url = \"{server}\"\nboards_url = \"{server}\"\n"),
+    );
+}
+
+/// Run publish against the project's registry configuration
+async fn run_publish_with(project: &TestProject, args: &[&str]) -> std::process::Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
+    cmd.current_dir(project.path())
+        .env("ZIGROOT_CONFIG_DIR", project.path().join("config"))
+        .env("XDG_CACHE_HOME", project.path().join("cache"))
+        .args(args);
+    tokio::task::spawn_blocking(move || cmd.output().expect("Failed to execute zigroot publish"))
+        .await
+        .unwrap()
+}
+
+/// Test: --dry-run checks everything and reports what would be published
+#[tokio::test]
+async fn test_publish_dry_run_reports_new_version() {
+    let server = mock_registry(&["1.0.0"]).await;
+    let project = TestProject::new();
+    create_preflight_package(&project, &server.uri(), "1.1.0", SOURCE_SHA256);
+
+    let output = run_publish_with(
+        &project,
+        &["--json", "publish", "packages/preflight-pkg", "--dry-run"],
+    )
+    .await;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["dry_run"], true);
+    assert_eq!(json["versions"], serde_json::json!(["1.1.0"]));
+    assert_eq!(json["license"], "MIT");
+    assert_eq!(json["exists"], true);
+
+    let output = run_publish_with(
+        &project,
+        &["publish", "packages/preflight-pkg", "--dry-run"],
+    )
+    .await;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Would publish package 'preflight-pkg' version 1.1.0"),
+        "{stdout}"
+    );
+}
+
+/// Test: a version that is already in the registry fails the pre-flight
+#[tokio::test]
+async fn test_publish_dry_run_rejects_published_version() {
+    let server = mock_registry(&["1.0.0", "1.1.0"]).await;
+    let project = TestProject::new();
+    create_preflight_package(&project, &server.uri(), "1.1.0", SOURCE_SHA256);
+
+    let output = run_publish_with(
+        &project,
+        &["publish", "packages/preflight-pkg", "--dry-run"],
+    )
+    .await;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("already published"), "{stderr}");
+}
+
+/// Test: publishing refuses when a pre-flight check fails
+#[tokio::test]
+async fn test_publish_refuses_failed_preflight() {
+    let server = mock_registry(&["1.0.0"]).await;
+    let project = TestProject::new();
+    let wrong = "0".repeat(64);
+    create_preflight_package(&project, &server.uri(), "1.1.0", &wrong);
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
+    cmd.current_dir(project.path())
+        .env("ZIGROOT_CONFIG_DIR", project.path().join("config"))
+        .env("XDG_CACHE_HOME", project.path().join("cache"))
+        .env("GITHUB_TOKEN", "ghp_test_token")
+        .args(["publish", "packages/preflight-pkg"]);
+    let output = tokio::task::spawn_blocking(move || cmd.output().unwrap())
+        .await
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("checksum verification"), "{stderr}");
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Publishing to"));
+}
+
+/// Publish `preflight-pkg` for real, signed with `key` if given
+async fn publish_preflight_package(
+    project: &TestProject,
+    key: Option<&str>,
+) -> std::process::Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
+    cmd.current_dir(project.path())
+        .env("ZIGROOT_CONFIG_DIR", project.path().join("config"))
+        .env("XDG_CACHE_HOME", project.path().join("cache"))
+        .env("GITHUB_TOKEN", "ghp_test_token")
+        .env_remove("ZIGROOT_SIGNING_KEY")
+        .args(["publish", "packages/preflight-pkg"]);
+    if let Some(key) = key {
+        cmd.env("ZIGROOT_SIGNING_KEY", key);
+    }
+    tokio::task::spawn_blocking(move || cmd.output().unwrap())
+        .await
+        .unwrap()
+}
+
+/// Test: publishing signs the definition, and verify checks the signature
+#[tokio::test]
+async fn test_publish_signs_definition() {
+    let server = mock_registry(&["1.0.0"]).await;
+    let project = TestProject::new();
+    create_preflight_package(&project, &server.uri(), "1.1.0", SOURCE_SHA256);
+    // Base64 of an Ed25519 seed
+    project.create_file(
+        "keys/signing.key",
+        "BwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwc=\n",
+    );
+
+    let output = publish_preflight_package(&project, None).await;
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("unsigned definition"), "{stdout}");
+    assert!(!project.file_exists("packages/preflight-pkg/definition.sig"));
+
+    let output = publish_preflight_package(&project, Some("keys/signing.key")).await;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(project.file_exists("packages/preflight-pkg/definition.sig"));
+    let public_key = stdout
+        .split("public key ")
+        .nth(1)
+        .and_then(|rest| rest.split(')').next())
+        .expect("public key in output");
+    project.create_file("keys/signing.pub", public_key);
+
+    let output = run_publish_with(
+        &project,
+        &[
+            "verify",
+            "packages/preflight-pkg",
+            "--public-key",
+            "keys/signing.pub",
+        ],
+    )
+    .await;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("Signature matches"), "{stdout}");
+
+    // The configured key catches a definition changed after signing
+    project.create_file(
+        "config/config.toml",
+        &format!(
+            "[registry]\npackages_url = \"{0}\"\nboards_url = \"{0}\"\npublic_key = \"{1}\"\n",
+            server.uri(),
+            project.path().join("keys/signing.pub").display()
+        ),
+    );
+    let metadata = project.read_file("packages/preflight-pkg/metadata.toml");
+    project.create_file(
+        "packages/preflight-pkg/metadata.toml",
+        &metadata.replace("A test package", "A tampered package"),
+    );
+    let output = run_publish_with(&project, &["verify", "packages/preflight-pkg"]).await;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("does not match"), "{stderr}");
+}
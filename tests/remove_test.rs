@@ -12,6 +12,7 @@ mod common;
 
 use common::TestProject;
 use proptest::prelude::*;
+use std::fmt::Write;
 use std::process::Command;
 
 /// Helper to run zigroot init command
@@ -329,6 +330,84 @@ fn test_remove_without_arguments() {
     assert!(is_valid_manifest(&project), "Manifest should remain valid");
 }
 
+/// Helper to write a manifest of local packages with their dependencies
+fn setup_local_packages(project: &TestProject, packages: &[(&str, &str)]) {
+    let mut manifest = "[project]\nname = \"test-project\"\nversion = \"1.0.0\"\n".to_string();
+    for (name, depends) in packages {
+        let _ = write!(manifest, "\n[packages.{name}]\nversion = \"1.0.0\"\n");
+        project.create_file(
+            &format!("packages/{name}/package.toml"),
+            &format!(
+                "[package]\nname = \"{name}\"\nversion = \"1.0.0\"\ndescription = \"{name}\"\n\
+                 depends = [{depends}]\n\n[source]\nurl = \"https://example.com/{name}.tar.gz\"\n\
+                 sha256 = \"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\"\n"
+            ),
+        );
+    }
+    project.create_file("zigroot.toml", &manifest);
+}
+
+/// Test: Packages other packages depend on are only removed with --force
+#[test]
+fn test_remove_blocked_by_dependents() {
+    let project = setup_project();
+    setup_local_packages(
+        &project,
+        &[("curl", "\"zlib\""), ("openssl", "\"zlib\""), ("zlib", "")],
+    );
+
+    let output = run_remove(&project, &["zlib"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Removal should be refused");
+    assert!(
+        stderr.contains("Package 'zlib' is required by: curl, openssl"),
+        "stderr: {stderr}"
+    );
+    assert!(stderr.contains("--force"), "stderr: {stderr}");
+    assert!(manifest_has_package(&project, "zlib"));
+
+    let output = run_remove(&project, &["zlib", "--force"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "Forced removal should succeed");
+    assert!(stdout.contains("Removed zlib v1.0.0"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("zlib is still required by: curl, openssl"),
+        "stdout: {stdout}"
+    );
+    assert!(!manifest_has_package(&project, "zlib"));
+    assert!(manifest_has_package(&project, "curl"));
+}
+
+/// Test: --cascade removes dependencies nothing else needs
+#[test]
+fn test_remove_cascade_removes_orphans() {
+    let project = setup_project();
+    setup_local_packages(
+        &project,
+        &[
+            ("app", "\"libfoo\""),
+            ("libfoo", "\"zlib\""),
+            ("curl", "\"zlib\""),
+            ("zlib", ""),
+        ],
+    );
+
+    let output = run_remove(&project, &["app", "--cascade"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Removal should succeed: {stderr}");
+    assert!(stdout.contains("Removed app v1.0.0"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Removed libfoo v1.0.0 (no longer needed)"),
+        "stdout: {stdout}"
+    );
+    assert!(!stdout.contains("Removed zlib"), "stdout: {stdout}");
+    assert!(!manifest_has_package(&project, "app"));
+    assert!(!manifest_has_package(&project, "libfoo"));
+    assert!(manifest_has_package(&project, "zlib"));
+    assert!(manifest_has_package(&project, "curl"));
+}
+
 // ============================================
 // Property-Based Tests
 // ============================================
@@ -0,0 +1,135 @@
+//! Integration tests for `zigroot run`
+//!
+//! QEMU is replaced by a script that prints the arguments it receives.
+
+mod common;
+
+use common::TestProject;
+use std::os::unix::fs::PermissionsExt;
+use std::process::Command;
+
+/// Helper to run zigroot run command
+fn run_run(project: &TestProject, args: &[&str]) -> std::process::Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
+    cmd.current_dir(project.path());
+    cmd.arg("run");
+    for arg in args {
+        cmd.arg(arg);
+    }
+    cmd.output().expect("Failed to execute zigroot run")
+}
+
+/// Helper to set up a built project for a QEMU x86 board
+///
+/// With a `qemu` section, the board runs a fake QEMU that echoes its
+/// arguments.
+fn setup_project(packages: &str, qemu: Option<&str>) -> TestProject {
+    let project = TestProject::new();
+    project.create_file(
+        "zigroot.toml",
+        &format!("[project]\nname = \"run-project\"\n\n[board]\nname = \"qemu-x86\"\n{packages}"),
+    );
+
+    let qemu = qemu.map_or_else(String::new, |section| {
+        project.create_file("bin/qemu", "#!/bin/sh\necho \"qemu-args: $*\"\n");
+        let binary = project.path().join("bin/qemu");
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        format!(
+            "\n[qemu]\n{section}binary = \"{}\"\nkernel = \"output/kernel/bzImage\"\n",
+            binary.display()
+        )
+    });
+    project.create_file(
+        "boards/qemu-x86/board.toml",
+        &format!(
+            r#"
+[board]
+name = "qemu-x86"
+description = "QEMU x86_64"
+target = "x86_64-linux-musl"
+cpu = "x86_64"
+
+[defaults]
+image_format = "ext4"
+rootfs_size = "256M"
+hostname = "qemu"
+{qemu}"#
+        ),
+    );
+    project.create_file("output/rootfs.img", "image");
+    project.create_file("output/kernel/bzImage", "kernel");
+    project
+}
+
+/// Arguments the fake QEMU was started with
+fn qemu_args(stdout: &str) -> &str {
+    stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("qemu-args: "))
+        .unwrap_or_else(|| panic!("QEMU was not run: {stdout}"))
+}
+
+/// Test: a board without a [qemu] section gets an example
+#[test]
+fn test_run_suggests_qemu_section() {
+    let project = setup_project("", None);
+
+    let output = run_run(&project, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Run should fail");
+    assert!(
+        stderr.contains("Board 'qemu-x86' has no [qemu] section"),
+        "stderr: {stderr}"
+    );
+    assert!(stderr.contains("machine = \"q35\""), "stderr: {stderr}");
+}
+
+/// Test: QEMU is launched with the board settings and the built artifacts
+#[test]
+fn test_run_launches_qemu_with_artifacts() {
+    let project = setup_project(
+        "",
+        Some("machine = \"q35\"\nmemory = \"512M\"\nconsole = \"ttyS0\"\n"),
+    );
+
+    let output = run_run(&project, &["--ssh-port", "2222", "--", "-snapshot"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Run should succeed: {stderr}");
+
+    let args = qemu_args(&stdout);
+    assert!(args.starts_with("-M q35 -m 512M -kernel "), "{args}");
+    assert!(args.contains("output/kernel/bzImage"), "{args}");
+    assert!(
+        args.contains("-append console=ttyS0 root=/dev/vda rw"),
+        "{args}"
+    );
+    assert!(
+        args.contains("output/rootfs.img,format=raw,if=virtio"),
+        "{args}"
+    );
+    assert!(args.contains("-nographic"), "{args}");
+    assert!(args.ends_with("-snapshot"), "{args}");
+
+    // Without an SSH server in the project, no port is forwarded
+    assert!(!args.contains("hostfwd"), "{args}");
+    assert!(stdout.contains("Ignoring --ssh-port"), "stdout: {stdout}");
+}
+
+/// Test: --ssh-port forwards to the guest when an SSH server is installed
+#[test]
+fn test_run_forwards_ssh_port() {
+    let project = setup_project(
+        "\n[packages.dropbear]\nversion = \"2024.85\"\n",
+        Some("machine = \"q35\"\n"),
+    );
+
+    let output = run_run(&project, &["--ssh-port", "2222", "--graphic"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "Run should succeed");
+
+    let args = qemu_args(&stdout);
+    assert!(args.contains("hostfwd=tcp::2222-:22"), "{args}");
+    assert!(args.contains("-serial mon:stdio"), "{args}");
+    assert!(!args.contains("-nographic"), "{args}");
+}
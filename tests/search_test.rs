@@ -393,6 +393,104 @@ fn test_search_case_insensitive() {
     );
 }
 
+// ============================================
+// Installed Packages
+// ============================================
+
+/// Helper to set up a project with busybox and a cached registry index
+///
+/// The index is cached under the project's `cache/`, used as
+/// `XDG_CACHE_HOME` by [`run_cached_search`], so no network is needed.
+fn setup_installed_project() -> TestProject {
+    let project = TestProject::new();
+    project.create_file(
+        "zigroot.toml",
+        "[project]\nname = \"search-project\"\n\n[board]\nname = \"luckfox-pico\"\n\n[packages.busybox]\nversion = \"1.36.1\"\n",
+    );
+    let cached_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    project.create_file(
+        "cache/zigroot/registry/packages-index.json",
+        &format!(
+            r#"{{"cached_at": {cached_at}, "data": {{"version": 1, "updated": "", "packages": [
+                {{"name": "busybox", "description": "Tiny utilities", "versions": [], "latest": "1.37.0"}},
+                {{"name": "busybox-extras", "description": "More utilities", "versions": [], "latest": "1.0.0"}}
+            ]}}}}"#
+        ),
+    );
+    project.create_file(
+        "cache/zigroot/registry/boards-index.json",
+        &format!(
+            r#"{{"cached_at": {cached_at}, "data": {{"version": 1, "updated": "", "boards": []}}}}"#
+        ),
+    );
+    project
+}
+
+/// Helper to run zigroot search against the cached index
+fn run_cached_search(project: &TestProject, args: &[&str]) -> std::process::Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
+    cmd.current_dir(project.path());
+    cmd.env("XDG_CACHE_HOME", project.path().join("cache"));
+    cmd.arg("search");
+    cmd.args(args);
+    cmd.output().expect("Failed to execute zigroot search")
+}
+
+/// Test: Results in the project are annotated with the pinned version
+#[test]
+fn test_search_marks_installed_packages() {
+    let project = setup_installed_project();
+
+    let output = run_cached_search(&project, &["busybox"]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let busybox = stdout
+        .lines()
+        .find(|line| line.contains("Tiny utilities"))
+        .expect("busybox should be listed");
+    assert!(
+        busybox.ends_with("(installed, pinned 1.36.1)"),
+        "stdout={stdout}"
+    );
+    let extras = stdout
+        .lines()
+        .find(|line| line.contains("More utilities"))
+        .expect("busybox-extras should be listed");
+    assert!(!extras.contains("installed"), "stdout={stdout}");
+}
+
+/// Test: --installed shows only packages in the project
+#[test]
+fn test_search_installed_filters_results() {
+    let project = setup_installed_project();
+
+    let output = run_cached_search(&project, &["busybox", "--installed"]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Packages (1 found)"), "stdout={stdout}");
+    assert!(!stdout.contains("More utilities"), "stdout={stdout}");
+
+    let output = run_cached_search(&project, &["extras", "--installed"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("No packages or boards in this project match 'extras'"));
+}
+
+/// Test: --installed outside a project fails
+#[test]
+fn test_search_installed_requires_project() {
+    let project = TestProject::new();
+
+    let output = run_cached_search(&project, &["busybox", "--installed"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("zigroot.toml"));
+}
+
 // ============================================
 // Property-Based Tests
 // ============================================
@@ -0,0 +1,93 @@
+//! Integration tests for `zigroot stats`
+//!
+//! Builds record statistics in the data directory, which is redirected to
+//! the test project with `ZIGROOT_DATA_DIR`.
+
+mod common;
+
+use common::TestProject;
+use std::process::Command;
+
+/// Run zigroot in the project with private data and config directories
+fn zigroot(project: &TestProject, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .current_dir(project.path())
+        .env("ZIGROOT_DATA_DIR", project.path().join("data"))
+        .env("ZIGROOT_CONFIG_DIR", project.path().join("config"))
+        .args(args)
+        .output()
+        .expect("Failed to execute zigroot")
+}
+
+fn stats_json(project: &TestProject) -> serde_json::Value {
+    let output = zigroot(project, &["--json", "stats"]);
+    assert!(
+        output.status.success(),
+        "stats failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("stats --json prints JSON")
+}
+
+fn setup_project() -> TestProject {
+    let project = TestProject::new();
+    let output = zigroot(&project, &["init"]);
+    assert!(
+        output.status.success(),
+        "Failed to initialize project: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    project
+}
+
+#[test]
+fn test_build_records_statistics() {
+    let project = setup_project();
+    let output = zigroot(&project, &["build"]);
+    assert!(
+        output.status.success(),
+        "build failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stats = stats_json(&project);
+    let records = stats["records"].as_array().unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["success"], true);
+    assert!(records[0]["image_size"].as_u64().unwrap() > 0);
+    assert_eq!(stats["summary"]["builds"], 1);
+
+    let output = zigroot(&project, &["stats"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Average build time"));
+}
+
+#[test]
+fn test_stats_skips_corrupt_lines_and_clears() {
+    let project = setup_project();
+    project.create_file("data/stats.jsonl", "{not json\n");
+
+    let stats = stats_json(&project);
+    assert!(stats["records"].as_array().unwrap().is_empty());
+    assert!(stats["warnings"][0]
+        .as_str()
+        .unwrap()
+        .contains("corrupt line 1"));
+
+    let output = zigroot(&project, &["stats", "--clear"]);
+    assert!(output.status.success());
+    assert!(!project.path().join("data/stats.jsonl").exists());
+}
+
+#[test]
+fn test_build_statistics_can_be_disabled() {
+    let project = setup_project();
+    let output = zigroot(&project, &["config", "set", "build.stats", "false"]);
+    assert!(
+        output.status.success(),
+        "config set failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    zigroot(&project, &["build"]);
+
+    assert!(!project.path().join("data/stats.jsonl").exists());
+}
@@ -480,6 +480,126 @@ fn test_tree_shows_hierarchical_structure() {
     );
 }
 
+/// Test: Build-only dependencies are shown only with --build-deps
+#[test]
+fn test_tree_build_deps_flag_shows_build_only_edges() {
+    let project = setup_project();
+    project.create_file(
+        "zigroot.toml",
+        r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[board]
+
+[build]
+
+[packages.app]
+version = "1.0.0"
+"#,
+    );
+    project.create_dir("packages/app");
+    project.create_file(
+        "packages/app/package.toml",
+        r#"[package]
+name = "app"
+version = "1.0.0"
+description = "App"
+dependencies = ["libfoo"]
+build_depends = ["zlib-static"]
+
+[source]
+url = "https://example.com/app-1.0.0.tar.gz"
+sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+"#,
+    );
+
+    let output = run_tree(&project, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("libfoo [runtime]"), "stdout={stdout}");
+    assert!(!stdout.contains("zlib-static"), "stdout={stdout}");
+
+    let output = run_tree(&project, &["--build-deps"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("libfoo [runtime]"), "stdout={stdout}");
+    assert!(stdout.contains("zlib-static [build]"), "stdout={stdout}");
+}
+
+/// Test: --why lists every path to a package, with edge types
+#[test]
+fn test_tree_why_explains_package() {
+    let project = setup_project();
+    project.create_file(
+        "zigroot.toml",
+        r#"
+[project]
+name = "test-project"
+version = "1.0.0"
+
+[board]
+
+[build]
+
+[packages.nginx]
+version = "1.25.0"
+
+[packages.dropbear]
+version = "2022.83.0"
+"#,
+    );
+    let source = r#"
+[source]
+url = "https://example.com/source.tar.gz"
+sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+"#;
+    for (name, deps) in [
+        ("nginx", "depends = [\"openssl\"]"),
+        ("openssl", "depends = [\"zlib\"]"),
+        ("dropbear", "build_depends = [\"zlib>=1.2.11\"]"),
+    ] {
+        project.create_file(
+            &format!("packages/{name}/package.toml"),
+            &format!(
+                "[package]\nname = \"{name}\"\nversion = \"1.0.0\"\ndescription = \"{name}\"\n{deps}\n{source}"
+            ),
+        );
+    }
+
+    let output = run_tree(&project, &["--why", "zlib"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stderr={}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("nginx → openssl [runtime] → zlib [runtime]"),
+        "stdout={stdout}"
+    );
+    assert!(
+        stdout.contains("dropbear → zlib [build, >=1.2.11]"),
+        "stdout={stdout}"
+    );
+
+    let output = run_tree(&project, &["--why", "openssl", "--graph"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("\"openssl\" [style=filled, fillcolor=gold];"),
+        "stdout={stdout}"
+    );
+    assert!(!stdout.contains("dropbear"), "stdout={stdout}");
+
+    let output = run_tree(&project, &["--why", "curl"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("not in the build"), "stderr={stderr}");
+    assert!(stderr.contains("zigroot search curl"), "stderr={stderr}");
+}
+
 // ============================================
 // Property-Based Tests
 // ============================================
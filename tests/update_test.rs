@@ -501,3 +501,60 @@ fn test_update_outside_project() {
         "Error should mention missing manifest or suggest init"
     );
 }
+
+/// Helper to run `zigroot update --self` with an isolated cache directory
+fn run_self_update(project: &TestProject, args: &[&str]) -> std::process::Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zigroot"));
+    cmd.current_dir(project.path());
+    cmd.env("ZIGROOT_CACHE_DIR", project.path().join("cache"));
+    cmd.args(["update", "--self"]);
+    cmd.args(args);
+    cmd.output()
+        .expect("Failed to execute zigroot update --self")
+}
+
+/// Test: `update --self --offline` reports a failed check without fetching
+#[test]
+fn test_self_update_offline_skips_check() {
+    let project = TestProject::new();
+
+    let output = run_self_update(&project, &["--offline"]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Could not check for updates: Offline mode"),
+        "Should report the skipped check, got: {stdout}"
+    );
+    assert!(
+        !project.file_exists("cache/update_check.json"),
+        "Offline checks should not be cached"
+    );
+}
+
+/// Test: `update --self` reuses a recent cached result
+#[test]
+fn test_self_update_uses_cached_result() {
+    let project = TestProject::new();
+    let checked_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    project.create_file(
+        "cache/update_check.json",
+        &format!(
+            r#"{{"checked_at": {checked_at}, "result": {{"UpdateAvailable": {{"current": "{}", "latest": "99.0.0", "release_url": "https://example.com/v99.0.0"}}}}}}"#,
+            env!("CARGO_PKG_VERSION")
+        ),
+    );
+
+    let output = run_self_update(&project, &[]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Latest version:  99.0.0"),
+        "Should print the cached update, got: {stdout}"
+    );
+    assert!(stdout.contains("Release notes: https://example.com/v99.0.0"));
+}
@@ -5,6 +5,7 @@
 //! - Validates board structure
 //! - Checks required fields
 //! - --fetch downloads and verifies checksums
+//! - --fetch checks every mirror of a source
 //!
 //! **Validates: Requirements 28.2-28.5, 29.2-29.4**
 
@@ -47,7 +48,7 @@ version = "1.0.0"
 
 [source]
 url = "https://example.com/test-1.0.0.tar.gz"
-sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+sha256 = "41cf6794ba4200b839c53531555f0f3998df4cbb01a4d5cb0b94e3ca5e23947d"
 "#;
     project.create_file(&format!("{}/1.0.0.toml", pkg_dir), version);
 }
@@ -228,6 +229,163 @@ fn test_verify_with_fetch_flag() {
     );
 }
 
+/// Serve `body` at `/{name}` on a mock mirror
+async fn mock_mirror(name: &str, body: &'static [u8]) -> wiremock::MockServer {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let server = wiremock::MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/{name}")))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(body))
+        .mount(&server)
+        .await;
+    server
+}
+
+/// Create a package whose source is served by `mirrors`
+fn create_mirrored_package(project: &TestProject, mirrors: &[String]) {
+    create_valid_package(project, "mirrored");
+    let (url, rest) = mirrors.split_first().unwrap();
+    let version = format!(
+        "[release]\nversion = \"1.0.0\"\n\n[source]\nurl = \"{url}\"\nmirrors = {rest:?}\nsha256 = \"{}\"\n",
+        // SHA256 of "source"
+        "41cf6794ba4200b839c53531555f0f3998df4cbb01a4d5cb0b94e3ca5e23947d"
+    );
+    project.create_file("packages/mirrored/1.0.0.toml", &version);
+}
+
+/// Run verify with `--fetch` under `--json` without blocking the mock servers
+async fn run_verify_json(project: &TestProject) -> std::process::Output {
+    let dir = project.path().clone();
+    tokio::task::spawn_blocking(move || {
+        Command::new(env!("CARGO_BIN_EXE_zigroot"))
+            .current_dir(dir)
+            .args(["--json", "verify", "packages/mirrored", "--fetch"])
+            .output()
+            .expect("Failed to execute zigroot verify")
+    })
+    .await
+    .unwrap()
+}
+
+/// Test: --fetch checks every mirror and reports a drifted one
+#[tokio::test]
+async fn test_verify_fetch_reports_each_mirror() {
+    let good = mock_mirror("src.tar.gz", b"source").await;
+    let drifted = mock_mirror("src.tar.gz", b"drifted").await;
+    let project = TestProject::new();
+    create_mirrored_package(
+        &project,
+        &[
+            format!("{}/src.tar.gz", good.uri()),
+            format!("{}/src.tar.gz", drifted.uri()),
+        ],
+    );
+
+    let output = run_verify_json(&project).await;
+    assert!(!output.status.success(), "a drifted mirror must fail");
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["valid"], false);
+    let mirrors = json["sources"][0]["mirrors"].as_array().unwrap();
+    assert_eq!(mirrors.len(), 2);
+    assert_eq!(mirrors[0]["status"], "match");
+    assert_eq!(mirrors[1]["status"], "mismatch");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 of 2"));
+}
+
+/// Test: --fetch passes when every mirror serves the declared bytes
+#[tokio::test]
+async fn test_verify_fetch_passes_when_all_mirrors_match() {
+    let first = mock_mirror("src.tar.gz", b"source").await;
+    let second = mock_mirror("src.tar.gz", b"source").await;
+    let project = TestProject::new();
+    create_mirrored_package(
+        &project,
+        &[
+            format!("{}/src.tar.gz", first.uri()),
+            format!("{}/src.tar.gz", second.uri()),
+        ],
+    );
+
+    let output = run_verify_json(&project).await;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["valid"], true);
+    assert_eq!(json["sources"][0]["mirrors"][1]["status"], "match");
+}
+
+/// Test: Lints report rule ids grouped by severity
+#[test]
+fn test_verify_lints_package() {
+    let project = TestProject::new();
+    create_valid_package(&project, "linted");
+    let version = r#"[release]
+version = "1.0.0"
+
+[source]
+url = "http://example.com/linted-0.9.tar.gz"
+sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+"#;
+    project.create_file("packages/linted/1.0.0.toml", version);
+
+    let output = run_verify(&project, "packages/linted", false);
+    assert!(
+        !output.status.success(),
+        "an empty-file checksum is an error"
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let errors = stdout.find("Errors:").expect("errors group");
+    let warnings = stdout.find("Warnings:").expect("warnings group");
+    assert!(errors < warnings, "{stdout}");
+    for rule in [
+        "empty-checksum",
+        "insecure-url",
+        "version-mismatch",
+        "bare-make",
+    ] {
+        assert!(stdout.contains(&format!("[{rule}]")), "{rule}: {stdout}");
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .current_dir(project.path())
+        .args(["--json", "verify", "packages/linted"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["valid"], false);
+    assert_eq!(json["lints"][0]["rule"], "empty-checksum");
+    assert_eq!(json["lints"][0]["severity"], "error");
+}
+
+/// Test: Warnings only fail verification with --deny-warnings
+#[test]
+fn test_verify_deny_warnings() {
+    let project = TestProject::new();
+    create_valid_package(&project, "warned");
+
+    let output = run_verify(&project, "packages/warned", false);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("[bare-make]"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zigroot"))
+        .current_dir(project.path())
+        .args(["verify", "packages/warned", "--deny-warnings"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("warnings denied"));
+}
+
 // ============================================
 // Board Validation Tests
 // ============================================
@@ -15,8 +15,10 @@ mod common;
 
 use proptest::prelude::*;
 use zigroot::core::version::{
+    background_update_check, background_update_check_from, check_for_updates_from,
     check_version_constraint, check_zigroot_version, compare_versions, is_newer, parse_constraint,
-    parse_version, VersionError, CURRENT_VERSION,
+    parse_version, satisfies_requirement, split_requirement, update_check_enabled, VersionError,
+    CURRENT_VERSION, NO_UPDATE_CHECK_ENV,
 };
 
 // ============================================
@@ -327,6 +329,24 @@ fn test_constraint_range() {
     assert!(check_version_constraint("0.9.9", ">=1.0.0, <2.0.0", "test").is_err());
 }
 
+#[test]
+fn test_manifest_requirement_bare_version_is_exact_pin() {
+    assert!(satisfies_requirement("1.36.1", "1.36.1"));
+    assert!(!satisfies_requirement("1.36.2", "1.36.1"));
+    assert!(satisfies_requirement("1.36.2", "^1.36.1"));
+    assert!(!satisfies_requirement("2.0.0", "^1.36.1"));
+    assert!(satisfies_requirement("latest", "latest"));
+    assert!(!satisfies_requirement("1.36.1", "latest"));
+}
+
+#[test]
+fn test_split_requirement() {
+    assert_eq!(split_requirement("^1.36.1"), ("^", "1.36.1"));
+    assert_eq!(split_requirement("~1.2.3"), ("~", "1.2.3"));
+    assert_eq!(split_requirement("=1.2.3"), ("=", "1.2.3"));
+    assert_eq!(split_requirement("1.2.3"), ("", "1.2.3"));
+}
+
 // ============================================
 // Property-Based Tests
 // ============================================
@@ -857,3 +877,153 @@ fn test_cache_timestamp() {
 
     assert_eq!(cached.checked_at, restored.checked_at);
 }
+
+// ============================================
+// Unit Tests - GitHub Release Check
+// ============================================
+
+/// Serve `body` with `status` as the latest GitHub release
+async fn mock_releases(status: u16, body: &str) -> wiremock::MockServer {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let server = wiremock::MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/releases/latest"))
+        .respond_with(ResponseTemplate::new(status).set_body_string(body))
+        .mount(&server)
+        .await;
+    server
+}
+
+/// Test: A newer release tag is reported as an update
+#[tokio::test]
+async fn test_check_for_updates_detects_newer_release() {
+    let server = mock_releases(
+        200,
+        r#"{"tag_name": "v99.0.0", "html_url": "https://example.com/v99.0.0"}"#,
+    )
+    .await;
+    let url = format!("{}/releases/latest", server.uri());
+
+    let result = check_for_updates_from(&reqwest::Client::new(), &url).await;
+    assert_eq!(
+        result,
+        UpdateCheckResult::UpdateAvailable {
+            current: CURRENT_VERSION.to_string(),
+            latest: "99.0.0".to_string(),
+            release_url: "https://example.com/v99.0.0".to_string(),
+        }
+    );
+}
+
+/// Test: The current release is reported as up to date
+#[tokio::test]
+async fn test_check_for_updates_current_release_is_up_to_date() {
+    let body =
+        format!(r#"{{"tag_name": "v{CURRENT_VERSION}", "html_url": "https://example.com"}}"#);
+    let server = mock_releases(200, &body).await;
+    let url = format!("{}/releases/latest", server.uri());
+
+    let result = check_for_updates_from(&reqwest::Client::new(), &url).await;
+    assert_eq!(
+        result,
+        UpdateCheckResult::UpToDate {
+            current: CURRENT_VERSION.to_string()
+        }
+    );
+}
+
+/// Test: An error response fails the check
+#[tokio::test]
+async fn test_check_for_updates_reports_http_errors() {
+    let server = mock_releases(404, "").await;
+    let url = format!("{}/releases/latest", server.uri());
+
+    let result = check_for_updates_from(&reqwest::Client::new(), &url).await;
+    assert!(
+        matches!(result, UpdateCheckResult::CheckFailed { .. }),
+        "Expected CheckFailed, got {result:?}"
+    );
+}
+
+// ============================================
+// Unit Tests - Background Update Check
+// ============================================
+
+/// Test: Background checks are skipped in quiet and JSON modes and via env
+#[test]
+fn test_update_check_enabled() {
+    std::env::remove_var(NO_UPDATE_CHECK_ENV);
+    assert!(update_check_enabled(false, false));
+    assert!(!update_check_enabled(true, false));
+    assert!(!update_check_enabled(false, true));
+
+    std::env::set_var(NO_UPDATE_CHECK_ENV, "1");
+    assert!(!update_check_enabled(false, false));
+    std::env::set_var(NO_UPDATE_CHECK_ENV, "0");
+    assert!(update_check_enabled(false, false));
+    std::env::remove_var(NO_UPDATE_CHECK_ENV);
+}
+
+/// Test: A recent cached update produces a notification without fetching
+#[tokio::test]
+async fn test_background_update_check_uses_recent_cache() {
+    let temp = tempfile::TempDir::new().unwrap();
+    std::env::set_var("ZIGROOT_CACHE_DIR", temp.path());
+    let checked_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    std::fs::write(
+        temp.path().join("update_check.json"),
+        format!(
+            r#"{{"checked_at": {checked_at}, "result": {{"UpdateAvailable": {{"current": "{CURRENT_VERSION}", "latest": "99.0.0", "release_url": ""}}}}}}"#
+        ),
+    )
+    .unwrap();
+
+    let notification = background_update_check().await;
+    std::env::remove_var("ZIGROOT_CACHE_DIR");
+
+    let notification = notification.expect("Should notify about the cached update");
+    assert!(notification.contains(&format!("{CURRENT_VERSION} → 99.0.0")));
+    assert!(notification.contains("zigroot update --self"));
+}
+
+/// Test: A second check within a day makes no request, even when the first
+/// one did not finish
+#[tokio::test]
+async fn test_background_update_check_runs_once_per_day() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let server = wiremock::MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/releases/latest"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(r#"{"tag_name": "v99.0.0", "html_url": ""}"#)
+                .set_delay(std::time::Duration::from_secs(2)),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let url = format!("{}/releases/latest", server.uri());
+    let temp = tempfile::TempDir::new().unwrap();
+    let cache_path = temp.path().join("update_check.json");
+
+    // The command ends while the request is in flight
+    let check = tokio::spawn({
+        let url = url.clone();
+        let cache_path = cache_path.clone();
+        async move { background_update_check_from(&url, &cache_path).await }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    check.abort();
+    let _ = check.await;
+    assert!(cache_path.is_file(), "The attempt should be recorded");
+
+    assert_eq!(background_update_check_from(&url, &cache_path).await, None);
+    server.verify().await;
+}